@@ -0,0 +1,119 @@
+//! Error types
+
+use num_derive::FromPrimitive;
+use solana_program::{
+    decode_error::DecodeError,
+    msg,
+    program_error::{PrintProgramError, ProgramError},
+};
+use thiserror::Error;
+
+/// Errors that may be returned by the NftVoterAddin program
+#[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
+pub enum NftVoterAddinError {
+    /// Invalid Realm for Registrar
+    #[error("Invalid Realm for Registrar")]
+    InvalidRealmForRegistrar = 1300,
+
+    /// Realm authority must sign
+    #[error("Realm authority must sign")]
+    RealmAuthorityMustSign, // 1301
+
+    /// Invalid collection size
+    #[error("Invalid collection size")]
+    InvalidCollectionSize,
+
+    /// Max number of collections reached
+    #[error("Max number of collections reached")]
+    MaxCollectionsReached,
+
+    /// Invalid MaxVoterWeightRecord for Registrar
+    #[error("Invalid MaxVoterWeightRecord for Registrar")]
+    InvalidMaxVoterWeightRecordForRegistrar,
+
+    /// Invalid VoterWeightRecord for Registrar
+    #[error("Invalid VoterWeightRecord for Registrar")]
+    InvalidVoterWeightRecordForRegistrar,
+
+    /// Invalid governing token owner for VoterWeightRecord
+    #[error("Invalid governing token owner for VoterWeightRecord")]
+    InvalidTokenOwnerForVoterWeightRecord,
+
+    /// Governing token owner must sign
+    #[error("Governing token owner must sign")]
+    GoverningTokenOwnerMustSign,
+
+    /// NFT is not owned by the governing token owner
+    #[error("NFT is not owned by the governing token owner")]
+    InvalidNftOwner,
+
+    /// NFT token account must hold exactly one token
+    #[error("NFT token account must hold exactly one token")]
+    InvalidNftAmount,
+
+    /// The same NFT was provided more than once
+    #[error("The same NFT was provided more than once")]
+    DuplicatedNftDetected,
+
+    /// Invalid NFT metadata account
+    #[error("Invalid NFT metadata account")]
+    InvalidNftMetadataAccount,
+
+    /// NFT collection must be verified
+    #[error("NFT collection must be verified")]
+    CollectionMustBeVerified,
+
+    /// NFT collection is not configured for Registrar
+    #[error("NFT collection is not configured for Registrar")]
+    CollectionNotFound,
+
+    /// CastVote action must use CastNftVote instruction
+    #[error("CastVote action must use CastNftVote instruction")]
+    CastVoteIsNotAllowed,
+
+    /// NFT has already voted on the Proposal
+    #[error("NFT has already voted on the Proposal")]
+    NftAlreadyVoted,
+
+    /// Invalid NftVoteRecord for Proposal
+    #[error("Invalid NftVoteRecord for Proposal")]
+    InvalidNftVoteRecordForProposal,
+
+    /// Invalid governing token owner for NftVoteRecord
+    #[error("Invalid governing token owner for NftVoteRecord")]
+    InvalidTokenOwnerForNftVoteRecord,
+
+    /// Invalid Proposal for Registrar
+    #[error("Invalid Proposal for Registrar")]
+    InvalidProposalForRegistrar,
+
+    /// Invalid governance VoteRecord for Proposal
+    #[error("Invalid governance VoteRecord for Proposal")]
+    InvalidVoteRecordForProposal,
+
+    /// Vote must be relinquished in governance before NftVoteRecord can be disposed
+    #[error("Vote must be relinquished in governance before NftVoteRecord can be disposed")]
+    VoteRecordMustBeRelinquished,
+
+    /// Voter weight overflow
+    #[error("Voter weight overflow")]
+    VoterWeightOverflow,
+}
+
+impl PrintProgramError for NftVoterAddinError {
+    fn print<E>(&self) {
+        msg!("GOVERNANCE-ADDIN-NFT-VOTER-ERROR: {}", &self.to_string());
+    }
+}
+
+impl From<NftVoterAddinError> for ProgramError {
+    fn from(e: NftVoterAddinError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+impl<T> DecodeError<T> for NftVoterAddinError {
+    fn type_of() -> &'static str {
+        "Governance Addin NFT Voter Error"
+    }
+}
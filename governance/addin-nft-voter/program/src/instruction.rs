@@ -0,0 +1,369 @@
+//! Program instructions
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+use spl_governance::state::{
+    token_owner_record::get_token_owner_record_address, vote_record::get_vote_record_address,
+};
+use spl_governance_addin_api::voter_weight::VoterWeightAction;
+
+use crate::{
+    state::{
+        nft_vote_record::get_nft_vote_record_address,
+        registrar::{
+            get_max_voter_weight_record_address, get_registrar_address,
+            get_voter_weight_record_address,
+        },
+    },
+    tools::nft::get_nft_metadata_address,
+};
+
+/// Instructions supported by the NftVoter addin program
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub enum NftVoterAddinInstruction {
+    /// Creates Registrar for the Realm governing token mint together with its MaxVoterWeightRecord
+    ///
+    ///   0. `[writable]` Registrar account. PDA seeds: ['registrar', realm, governing_token_mint]
+    ///   1. `[writable]` MaxVoterWeightRecord account. PDA seeds: ['max-voter-weight-record', realm, governing_token_mint]
+    ///   2. `[]` Governance program id
+    ///   3. `[]` Realm account
+    ///   4. `[]` Governing Token Mint
+    ///   5. `[signer]` Realm authority
+    ///   6. `[signer]` Payer
+    ///   7. `[]` System
+    CreateRegistrar {},
+
+    /// Adds a new or updates an existing NFT collection config and recalculates the max voter weight
+    ///
+    ///   0. `[writable]` Registrar account
+    ///   1. `[]` Realm account
+    ///   2. `[signer]` Realm authority
+    ///   3. `[]` Collection account (the collection NFT mint)
+    ///   4. `[writable]` MaxVoterWeightRecord account
+    ConfigureCollection {
+        /// Vote weight of each NFT in the collection
+        #[allow(dead_code)]
+        weight: u64,
+
+        /// The number of NFTs in the collection
+        #[allow(dead_code)]
+        size: u32,
+    },
+
+    /// Creates VoterWeightRecord for the given governing token owner
+    ///
+    ///   0. `[]` Registrar account
+    ///   1. `[writable]` VoterWeightRecord account. PDA seeds: ['voter-weight-record', realm, governing_token_mint, governing_token_owner]
+    ///   2. `[signer]` Payer
+    ///   3. `[]` System
+    CreateVoterWeightRecord {
+        /// The governing token owner the VoterWeightRecord is created for
+        #[allow(dead_code)]
+        governing_token_owner: Pubkey,
+    },
+
+    /// Updates VoterWeightRecord with the weight of the owned NFTs for the given action
+    /// The CastVote action is not supported and CastNftVote must be used instead
+    ///
+    ///   0. `[]` Registrar account
+    ///   1. `[writable]` VoterWeightRecord account
+    ///   2. `[]` NFT token account owned by the governing token owner
+    ///   3. `[]` NFT metadata account. PDA seeds: ['metadata', metadata program id, nft_mint]
+    ///   ... Repeated NFT token account and metadata pairs
+    UpdateVoterWeightRecord {
+        /// The governance action the voter weight is evaluated for
+        #[allow(dead_code)]
+        voter_weight_action: VoterWeightAction,
+    },
+
+    /// Casts vote with the owned NFTs and updates VoterWeightRecord with their weight for the Proposal
+    /// NftVoteRecord receipt is created for each NFT to prevent the NFT from voting twice on the Proposal
+    /// The weight is accumulated when the instruction is executed several times for the same Proposal in the same slot
+    ///
+    ///   0. `[]` Registrar account
+    ///   1. `[writable]` VoterWeightRecord account
+    ///   2. `[signer]` Governing token owner
+    ///   3. `[signer]` Payer
+    ///   4. `[]` System
+    ///   5. `[]` NFT token account owned by the governing token owner
+    ///   6. `[]` NFT metadata account. PDA seeds: ['metadata', metadata program id, nft_mint]
+    ///   7. `[writable]` NftVoteRecord account. PDA seeds: ['nft-vote-record', proposal, nft_mint]
+    ///   ... Repeated NFT token account, metadata and NftVoteRecord triples
+    CastNftVote {
+        /// The Proposal to cast the vote for
+        #[allow(dead_code)]
+        proposal: Pubkey,
+    },
+
+    /// Disposes NftVoteRecord receipts of the Proposal once the vote is no longer active
+    /// The vote is no longer active when the Proposal is not in Voting state
+    /// or when the vote has been relinquished in the governance program
+    ///
+    ///   0. `[]` Registrar account
+    ///   1. `[writable]` VoterWeightRecord account
+    ///   2. `[]` Proposal account
+    ///   3. `[]` Governance VoteRecord account of the governing token owner for the Proposal
+    ///   4. `[signer]` Governing token owner
+    ///   5. `[writable]` Beneficiary account which receives the lamports of the disposed receipts
+    ///   6. `[writable]` NftVoteRecord account
+    ///   ... Repeated NftVoteRecord accounts
+    RelinquishNftVote {},
+}
+
+/// Creates CreateRegistrar instruction
+pub fn create_registrar(
+    program_id: &Pubkey,
+    // Accounts
+    governance_program_id: &Pubkey,
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    realm_authority: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let max_voter_weight_record_address =
+        get_max_voter_weight_record_address(program_id, realm, governing_token_mint);
+
+    let accounts = vec![
+        AccountMeta::new(registrar_address, false),
+        AccountMeta::new(max_voter_weight_record_address, false),
+        AccountMeta::new_readonly(*governance_program_id, false),
+        AccountMeta::new_readonly(*realm, false),
+        AccountMeta::new_readonly(*governing_token_mint, false),
+        AccountMeta::new_readonly(*realm_authority, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let instruction = NftVoterAddinInstruction::CreateRegistrar {};
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates ConfigureCollection instruction
+pub fn configure_collection(
+    program_id: &Pubkey,
+    // Accounts
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    realm_authority: &Pubkey,
+    collection: &Pubkey,
+    // Args
+    weight: u64,
+    size: u32,
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let max_voter_weight_record_address =
+        get_max_voter_weight_record_address(program_id, realm, governing_token_mint);
+
+    let accounts = vec![
+        AccountMeta::new(registrar_address, false),
+        AccountMeta::new_readonly(*realm, false),
+        AccountMeta::new_readonly(*realm_authority, true),
+        AccountMeta::new_readonly(*collection, false),
+        AccountMeta::new(max_voter_weight_record_address, false),
+    ];
+
+    let instruction = NftVoterAddinInstruction::ConfigureCollection { weight, size };
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates CreateVoterWeightRecord instruction
+pub fn create_voter_weight_record(
+    program_id: &Pubkey,
+    // Accounts
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    payer: &Pubkey,
+    // Args
+    governing_token_owner: &Pubkey,
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let voter_weight_record_address = get_voter_weight_record_address(
+        program_id,
+        realm,
+        governing_token_mint,
+        governing_token_owner,
+    );
+
+    let accounts = vec![
+        AccountMeta::new_readonly(registrar_address, false),
+        AccountMeta::new(voter_weight_record_address, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let instruction = NftVoterAddinInstruction::CreateVoterWeightRecord {
+        governing_token_owner: *governing_token_owner,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates UpdateVoterWeightRecord instruction
+/// The nfts are (NFT token account, NFT mint) pairs
+pub fn update_voter_weight_record(
+    program_id: &Pubkey,
+    // Accounts
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    governing_token_owner: &Pubkey,
+    nfts: &[(Pubkey, Pubkey)],
+    // Args
+    voter_weight_action: VoterWeightAction,
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let voter_weight_record_address = get_voter_weight_record_address(
+        program_id,
+        realm,
+        governing_token_mint,
+        governing_token_owner,
+    );
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(registrar_address, false),
+        AccountMeta::new(voter_weight_record_address, false),
+    ];
+
+    for (nft_token_account, nft_mint) in nfts {
+        accounts.push(AccountMeta::new_readonly(*nft_token_account, false));
+        accounts.push(AccountMeta::new_readonly(
+            get_nft_metadata_address(nft_mint),
+            false,
+        ));
+    }
+
+    let instruction = NftVoterAddinInstruction::UpdateVoterWeightRecord {
+        voter_weight_action,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates CastNftVote instruction
+/// The nfts are (NFT token account, NFT mint) pairs
+pub fn cast_nft_vote(
+    program_id: &Pubkey,
+    // Accounts
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    governing_token_owner: &Pubkey,
+    payer: &Pubkey,
+    nfts: &[(Pubkey, Pubkey)],
+    // Args
+    proposal: &Pubkey,
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let voter_weight_record_address = get_voter_weight_record_address(
+        program_id,
+        realm,
+        governing_token_mint,
+        governing_token_owner,
+    );
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(registrar_address, false),
+        AccountMeta::new(voter_weight_record_address, false),
+        AccountMeta::new_readonly(*governing_token_owner, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    for (nft_token_account, nft_mint) in nfts {
+        accounts.push(AccountMeta::new_readonly(*nft_token_account, false));
+        accounts.push(AccountMeta::new_readonly(
+            get_nft_metadata_address(nft_mint),
+            false,
+        ));
+        accounts.push(AccountMeta::new(
+            get_nft_vote_record_address(program_id, proposal, nft_mint),
+            false,
+        ));
+    }
+
+    let instruction = NftVoterAddinInstruction::CastNftVote {
+        proposal: *proposal,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates RelinquishNftVote instruction
+#[allow(clippy::too_many_arguments)]
+pub fn relinquish_nft_vote(
+    program_id: &Pubkey,
+    // Accounts
+    governance_program_id: &Pubkey,
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    proposal: &Pubkey,
+    governing_token_owner: &Pubkey,
+    beneficiary: &Pubkey,
+    nft_mints: &[Pubkey],
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let voter_weight_record_address = get_voter_weight_record_address(
+        program_id,
+        realm,
+        governing_token_mint,
+        governing_token_owner,
+    );
+
+    let token_owner_record_address = get_token_owner_record_address(
+        governance_program_id,
+        realm,
+        governing_token_mint,
+        governing_token_owner,
+    );
+    let vote_record_address =
+        get_vote_record_address(governance_program_id, proposal, &token_owner_record_address);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(registrar_address, false),
+        AccountMeta::new(voter_weight_record_address, false),
+        AccountMeta::new_readonly(*proposal, false),
+        AccountMeta::new_readonly(vote_record_address, false),
+        AccountMeta::new_readonly(*governing_token_owner, true),
+        AccountMeta::new(*beneficiary, false),
+    ];
+
+    for nft_mint in nft_mints {
+        accounts.push(AccountMeta::new(
+            get_nft_vote_record_address(program_id, proposal, nft_mint),
+            false,
+        ));
+    }
+
+    let instruction = NftVoterAddinInstruction::RelinquishNftVote {};
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
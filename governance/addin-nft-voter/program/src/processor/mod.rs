@@ -0,0 +1,58 @@
+//! Program processor
+
+mod process_cast_nft_vote;
+mod process_configure_collection;
+mod process_create_registrar;
+mod process_create_voter_weight_record;
+mod process_relinquish_nft_vote;
+mod process_update_voter_weight_record;
+
+use crate::instruction::NftVoterAddinInstruction;
+
+use process_cast_nft_vote::*;
+use process_configure_collection::*;
+use process_create_registrar::*;
+use process_create_voter_weight_record::*;
+use process_relinquish_nft_vote::*;
+use process_update_voter_weight_record::*;
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Processes an instruction
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    msg!("VERSION:{:?}", env!("CARGO_PKG_VERSION"));
+
+    let instruction = NftVoterAddinInstruction::try_from_slice(input)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    msg!("GOVERNANCE-ADDIN-NFT-VOTER-INSTRUCTION: {:?}", instruction);
+
+    match instruction {
+        NftVoterAddinInstruction::CreateRegistrar {} => {
+            process_create_registrar(program_id, accounts)
+        }
+        NftVoterAddinInstruction::ConfigureCollection { weight, size } => {
+            process_configure_collection(program_id, accounts, weight, size)
+        }
+        NftVoterAddinInstruction::CreateVoterWeightRecord {
+            governing_token_owner,
+        } => process_create_voter_weight_record(program_id, accounts, governing_token_owner),
+        NftVoterAddinInstruction::UpdateVoterWeightRecord {
+            voter_weight_action,
+        } => process_update_voter_weight_record(program_id, accounts, voter_weight_action),
+        NftVoterAddinInstruction::CastNftVote { proposal } => {
+            process_cast_nft_vote(program_id, accounts, proposal)
+        }
+        NftVoterAddinInstruction::RelinquishNftVote {} => {
+            process_relinquish_nft_vote(program_id, accounts)
+        }
+    }
+}
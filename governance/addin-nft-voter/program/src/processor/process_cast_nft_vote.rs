@@ -0,0 +1,121 @@
+//! Program state processor
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+use spl_governance_addin_api::voter_weight::VoterWeightAction;
+use spl_governance_tools::account::create_and_serialize_account_signed;
+
+use crate::{
+    error::NftVoterAddinError,
+    state::{
+        enums::NftVoterAccountType,
+        nft_vote_record::{get_nft_vote_record_address_seeds, NftVoteRecord},
+        registrar::{get_registrar_data, get_voter_weight_record_data_for_registrar},
+    },
+};
+
+/// Processes CastNftVote instruction
+pub fn process_cast_nft_vote(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proposal: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let registrar_info = next_account_info(account_info_iter)?; // 0
+    let voter_weight_record_info = next_account_info(account_info_iter)?; // 1
+    let governing_token_owner_info = next_account_info(account_info_iter)?; // 2
+    let payer_info = next_account_info(account_info_iter)?; // 3
+    let system_info = next_account_info(account_info_iter)?; // 4
+    let rent = &Rent::get()?;
+
+    let registrar_data = get_registrar_data(program_id, registrar_info)?;
+    let mut voter_weight_record_data = get_voter_weight_record_data_for_registrar(
+        program_id,
+        voter_weight_record_info,
+        &registrar_data,
+    )?;
+
+    if voter_weight_record_data.governing_token_owner != *governing_token_owner_info.key {
+        return Err(NftVoterAddinError::InvalidTokenOwnerForVoterWeightRecord.into());
+    }
+
+    if !governing_token_owner_info.is_signer {
+        return Err(NftVoterAddinError::GoverningTokenOwnerMustSign.into());
+    }
+
+    let nft_accounts = account_info_iter.as_slice().chunks_exact(3);
+
+    if !nft_accounts.remainder().is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let clock = Clock::get()?;
+
+    // Accumulate the weight when the vote is cast in several instructions for the same Proposal and slot
+    let mut voter_weight = if voter_weight_record_data.weight_action
+        == Some(VoterWeightAction::CastVote)
+        && voter_weight_record_data.weight_action_target == Some(proposal)
+        && voter_weight_record_data.voter_weight_expiry == Some(clock.slot)
+    {
+        voter_weight_record_data.voter_weight
+    } else {
+        0
+    };
+
+    let mut unique_nft_mints = vec![];
+
+    for nft_accounts in nft_accounts {
+        let nft_vote_record_info = &nft_accounts[2];
+
+        let (nft_vote_weight, nft_mint) = registrar_data.resolve_nft_vote_weight_and_mint(
+            governing_token_owner_info.key,
+            &nft_accounts[0],
+            &nft_accounts[1],
+            &mut unique_nft_mints,
+        )?;
+
+        voter_weight = voter_weight
+            .checked_add(nft_vote_weight)
+            .ok_or(NftVoterAddinError::VoterWeightOverflow)?;
+
+        // The vote receipt exists as long as the NFT's vote is active
+        if !nft_vote_record_info.data_is_empty() {
+            return Err(NftVoterAddinError::NftAlreadyVoted.into());
+        }
+
+        let nft_vote_record_data = NftVoteRecord {
+            account_type: NftVoterAccountType::NftVoteRecord,
+            proposal,
+            nft_mint,
+            governing_token_owner: *governing_token_owner_info.key,
+        };
+
+        create_and_serialize_account_signed(
+            payer_info,
+            nft_vote_record_info,
+            &nft_vote_record_data,
+            &get_nft_vote_record_address_seeds(&proposal, &nft_mint),
+            program_id,
+            system_info,
+            rent,
+        )?;
+    }
+
+    voter_weight_record_data.voter_weight = voter_weight;
+    voter_weight_record_data.voter_weight_expiry = Some(clock.slot);
+    voter_weight_record_data.weight_action = Some(VoterWeightAction::CastVote);
+    voter_weight_record_data.weight_action_target = Some(proposal);
+
+    voter_weight_record_data.serialize(&mut *voter_weight_record_info.data.borrow_mut())?;
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Program state processor
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+};
+use spl_governance_addin_api::max_voter_weight::MaxVoterWeightRecord;
+use spl_governance_tools::account::get_account_data;
+
+use crate::{
+    error::NftVoterAddinError,
+    state::registrar::{get_max_voter_weight_record_address, get_registrar_data, CollectionConfig},
+};
+
+/// Processes ConfigureCollection instruction
+pub fn process_configure_collection(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    weight: u64,
+    size: u32,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let registrar_info = next_account_info(account_info_iter)?; // 0
+    let realm_info = next_account_info(account_info_iter)?; // 1
+    let realm_authority_info = next_account_info(account_info_iter)?; // 2
+    let collection_info = next_account_info(account_info_iter)?; // 3
+    let max_voter_weight_record_info = next_account_info(account_info_iter)?; // 4
+
+    let mut registrar_data = get_registrar_data(program_id, registrar_info)?;
+    registrar_data.assert_realm_authority_is_signer(realm_info, realm_authority_info)?;
+
+    let max_voter_weight_record_address = get_max_voter_weight_record_address(
+        program_id,
+        &registrar_data.realm,
+        &registrar_data.governing_token_mint,
+    );
+
+    if max_voter_weight_record_address != *max_voter_weight_record_info.key {
+        return Err(NftVoterAddinError::InvalidMaxVoterWeightRecordForRegistrar.into());
+    }
+
+    registrar_data.set_collection_config(CollectionConfig {
+        collection: *collection_info.key,
+        size,
+        weight,
+    })?;
+
+    let mut max_voter_weight_record_data =
+        get_account_data::<MaxVoterWeightRecord>(program_id, max_voter_weight_record_info)?;
+
+    max_voter_weight_record_data.max_voter_weight = registrar_data.get_max_voter_weight()?;
+
+    registrar_data.serialize(&mut *registrar_info.data.borrow_mut())?;
+    max_voter_weight_record_data.serialize(&mut *max_voter_weight_record_info.data.borrow_mut())?;
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Program state processor
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+use spl_governance::state::realm::get_realm_data_for_authority;
+use spl_governance_addin_api::max_voter_weight::MaxVoterWeightRecord;
+use spl_governance_tools::account::create_and_serialize_account_signed;
+
+use crate::{
+    error::NftVoterAddinError,
+    state::{
+        enums::NftVoterAccountType,
+        registrar::{
+            get_max_voter_weight_record_address_seeds, get_registrar_address_seeds, Registrar,
+        },
+    },
+};
+
+/// Processes CreateRegistrar instruction
+pub fn process_create_registrar(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let registrar_info = next_account_info(account_info_iter)?; // 0
+    let max_voter_weight_record_info = next_account_info(account_info_iter)?; // 1
+    let governance_program_info = next_account_info(account_info_iter)?; // 2
+    let realm_info = next_account_info(account_info_iter)?; // 3
+    let governing_token_mint_info = next_account_info(account_info_iter)?; // 4
+    let realm_authority_info = next_account_info(account_info_iter)?; // 5
+    let payer_info = next_account_info(account_info_iter)?; // 6
+    let system_info = next_account_info(account_info_iter)?; // 7
+    let rent = &Rent::get()?;
+
+    let realm_data = get_realm_data_for_authority(
+        governance_program_info.key,
+        realm_info,
+        realm_authority_info.key,
+    )?;
+
+    if !realm_authority_info.is_signer {
+        return Err(NftVoterAddinError::RealmAuthorityMustSign.into());
+    }
+
+    realm_data.assert_is_valid_governing_token_mint(governing_token_mint_info.key)?;
+
+    let registrar_data = Registrar {
+        account_type: NftVoterAccountType::Registrar,
+        governance_program_id: *governance_program_info.key,
+        realm: *realm_info.key,
+        governing_token_mint: *governing_token_mint_info.key,
+        collection_configs: vec![],
+        reserved: [0; 64],
+    };
+
+    create_and_serialize_account_signed(
+        payer_info,
+        registrar_info,
+        &registrar_data,
+        &get_registrar_address_seeds(realm_info.key, governing_token_mint_info.key),
+        program_id,
+        system_info,
+        rent,
+    )?;
+
+    // The max voter weight only changes when collections are configured and it doesn't expire
+    let max_voter_weight_record_data = MaxVoterWeightRecord {
+        account_discriminator: MaxVoterWeightRecord::ACCOUNT_DISCRIMINATOR,
+        realm: *realm_info.key,
+        governing_token_mint: *governing_token_mint_info.key,
+        max_voter_weight: 0,
+        max_voter_weight_expiry: None,
+        reserved: [0; 8],
+    };
+
+    create_and_serialize_account_signed(
+        payer_info,
+        max_voter_weight_record_info,
+        &max_voter_weight_record_data,
+        &get_max_voter_weight_record_address_seeds(realm_info.key, governing_token_mint_info.key),
+        program_id,
+        system_info,
+        rent,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+//! Program state processor
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+};
+use spl_governance::state::{
+    enums::ProposalState,
+    proposal::get_proposal_data,
+    token_owner_record::get_token_owner_record_address,
+    vote_record::{get_vote_record_address, get_vote_record_data},
+};
+use spl_governance_tools::account::dispose_account;
+
+use crate::{
+    error::NftVoterAddinError,
+    state::{
+        nft_vote_record::get_nft_vote_record_data_for_proposal_and_token_owner,
+        registrar::{get_registrar_data, get_voter_weight_record_data_for_registrar},
+    },
+};
+
+/// Processes RelinquishNftVote instruction
+pub fn process_relinquish_nft_vote(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let registrar_info = next_account_info(account_info_iter)?; // 0
+    let voter_weight_record_info = next_account_info(account_info_iter)?; // 1
+    let proposal_info = next_account_info(account_info_iter)?; // 2
+    let vote_record_info = next_account_info(account_info_iter)?; // 3
+    let governing_token_owner_info = next_account_info(account_info_iter)?; // 4
+    let beneficiary_info = next_account_info(account_info_iter)?; // 5
+
+    let registrar_data = get_registrar_data(program_id, registrar_info)?;
+    let mut voter_weight_record_data = get_voter_weight_record_data_for_registrar(
+        program_id,
+        voter_weight_record_info,
+        &registrar_data,
+    )?;
+
+    if voter_weight_record_data.governing_token_owner != *governing_token_owner_info.key {
+        return Err(NftVoterAddinError::InvalidTokenOwnerForVoterWeightRecord.into());
+    }
+
+    if !governing_token_owner_info.is_signer {
+        return Err(NftVoterAddinError::GoverningTokenOwnerMustSign.into());
+    }
+
+    let proposal_data = get_proposal_data(&registrar_data.governance_program_id, proposal_info)?;
+
+    if proposal_data.governing_token_mint != registrar_data.governing_token_mint {
+        return Err(NftVoterAddinError::InvalidProposalForRegistrar.into());
+    }
+
+    // While the Proposal is being voted on the vote must be relinquished in governance first
+    // Otherwise the NFTs could be transferred and vote again on the same Proposal
+    if proposal_data.state == ProposalState::Voting {
+        let token_owner_record_address = get_token_owner_record_address(
+            &registrar_data.governance_program_id,
+            &registrar_data.realm,
+            &registrar_data.governing_token_mint,
+            governing_token_owner_info.key,
+        );
+
+        let vote_record_address = get_vote_record_address(
+            &registrar_data.governance_program_id,
+            proposal_info.key,
+            &token_owner_record_address,
+        );
+
+        if vote_record_address != *vote_record_info.key {
+            return Err(NftVoterAddinError::InvalidVoteRecordForProposal.into());
+        }
+
+        // The VoteRecord is disposed when the vote is withdrawn during voting
+        if !vote_record_info.data_is_empty()
+            && !get_vote_record_data(&registrar_data.governance_program_id, vote_record_info)?
+                .is_relinquished
+        {
+            return Err(NftVoterAddinError::VoteRecordMustBeRelinquished.into());
+        }
+    }
+
+    for nft_vote_record_info in account_info_iter {
+        get_nft_vote_record_data_for_proposal_and_token_owner(
+            program_id,
+            nft_vote_record_info,
+            proposal_info.key,
+            governing_token_owner_info.key,
+        )?;
+
+        dispose_account(nft_vote_record_info, beneficiary_info);
+    }
+
+    // Reset the weight cast for the Proposal to prevent it from being used again
+    if voter_weight_record_data.weight_action_target == Some(*proposal_info.key) {
+        voter_weight_record_data.voter_weight = 0;
+        voter_weight_record_data.voter_weight_expiry = Some(0);
+
+        voter_weight_record_data.serialize(&mut *voter_weight_record_info.data.borrow_mut())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! Program state processor
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_governance_addin_api::voter_weight::VoterWeightAction;
+
+use crate::{
+    error::NftVoterAddinError,
+    state::registrar::{get_registrar_data, get_voter_weight_record_data_for_registrar},
+};
+
+/// Processes UpdateVoterWeightRecord instruction
+pub fn process_update_voter_weight_record(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    voter_weight_action: VoterWeightAction,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let registrar_info = next_account_info(account_info_iter)?; // 0
+    let voter_weight_record_info = next_account_info(account_info_iter)?; // 1
+
+    // CastVote must go through CastNftVote which creates the NFT vote receipts
+    if voter_weight_action == VoterWeightAction::CastVote {
+        return Err(NftVoterAddinError::CastVoteIsNotAllowed.into());
+    }
+
+    let registrar_data = get_registrar_data(program_id, registrar_info)?;
+    let mut voter_weight_record_data = get_voter_weight_record_data_for_registrar(
+        program_id,
+        voter_weight_record_info,
+        &registrar_data,
+    )?;
+
+    let nft_accounts = account_info_iter.as_slice().chunks_exact(2);
+
+    if !nft_accounts.remainder().is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let mut voter_weight = 0_u64;
+    let mut unique_nft_mints = vec![];
+
+    for nft_accounts in nft_accounts {
+        let (nft_vote_weight, _) = registrar_data.resolve_nft_vote_weight_and_mint(
+            &voter_weight_record_data.governing_token_owner,
+            &nft_accounts[0],
+            &nft_accounts[1],
+            &mut unique_nft_mints,
+        )?;
+
+        voter_weight = voter_weight
+            .checked_add(nft_vote_weight)
+            .ok_or(NftVoterAddinError::VoterWeightOverflow)?;
+    }
+
+    voter_weight_record_data.voter_weight = voter_weight;
+
+    // The NFTs can be transferred at any time and the weight is only valid for the current slot
+    voter_weight_record_data.voter_weight_expiry = Some(Clock::get()?.slot);
+    voter_weight_record_data.weight_action = Some(voter_weight_action);
+    voter_weight_record_data.weight_action_target = None;
+
+    voter_weight_record_data.serialize(&mut *voter_weight_record_info.data.borrow_mut())?;
+
+    Ok(())
+}
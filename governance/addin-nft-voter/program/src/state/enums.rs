@@ -0,0 +1,16 @@
+//! State enumerations
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+
+/// Defines all NftVoter accounts types
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub enum NftVoterAccountType {
+    /// Default uninitialized account state
+    Uninitialized,
+
+    /// Registrar which configures the addin for a Realm and governing token mint
+    Registrar,
+
+    /// Vote receipt of a single NFT for a Proposal
+    NftVoteRecord,
+}
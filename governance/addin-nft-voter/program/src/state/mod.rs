@@ -0,0 +1,5 @@
+//! Program accounts
+
+pub mod enums;
+pub mod nft_vote_record;
+pub mod registrar;
@@ -0,0 +1,85 @@
+//! NftVoteRecord Account
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, program_pack::IsInitialized,
+    pubkey::Pubkey,
+};
+use spl_governance_tools::account::{get_account_data, AccountMaxSize};
+
+use crate::{error::NftVoterAddinError, state::enums::NftVoterAccountType};
+
+/// NftVoteRecord account
+/// It's a vote receipt which prevents the same NFT from voting more than once on a Proposal
+/// The record exists as long as the NFT's vote is active and it's disposed with RelinquishNftVote
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct NftVoteRecord {
+    /// Account type
+    pub account_type: NftVoterAccountType,
+
+    /// The Proposal the NFT voted on
+    pub proposal: Pubkey,
+
+    /// The mint of the NFT which voted
+    pub nft_mint: Pubkey,
+
+    /// The governing token owner who voted with the NFT
+    pub governing_token_owner: Pubkey,
+}
+
+impl AccountMaxSize for NftVoteRecord {}
+
+impl IsInitialized for NftVoteRecord {
+    fn is_initialized(&self) -> bool {
+        self.account_type == NftVoterAccountType::NftVoteRecord
+    }
+}
+
+/// Deserializes NftVoteRecord account and checks owner program
+pub fn get_nft_vote_record_data(
+    program_id: &Pubkey,
+    nft_vote_record_info: &AccountInfo,
+) -> Result<NftVoteRecord, ProgramError> {
+    get_account_data::<NftVoteRecord>(program_id, nft_vote_record_info)
+}
+
+/// Deserializes NftVoteRecord account and asserts it belongs to the given Proposal and governing token owner
+pub fn get_nft_vote_record_data_for_proposal_and_token_owner(
+    program_id: &Pubkey,
+    nft_vote_record_info: &AccountInfo,
+    proposal: &Pubkey,
+    governing_token_owner: &Pubkey,
+) -> Result<NftVoteRecord, ProgramError> {
+    let nft_vote_record_data = get_nft_vote_record_data(program_id, nft_vote_record_info)?;
+
+    if nft_vote_record_data.proposal != *proposal {
+        return Err(NftVoterAddinError::InvalidNftVoteRecordForProposal.into());
+    }
+
+    if nft_vote_record_data.governing_token_owner != *governing_token_owner {
+        return Err(NftVoterAddinError::InvalidTokenOwnerForNftVoteRecord.into());
+    }
+
+    Ok(nft_vote_record_data)
+}
+
+/// Returns NftVoteRecord PDA seeds
+pub fn get_nft_vote_record_address_seeds<'a>(
+    proposal: &'a Pubkey,
+    nft_mint: &'a Pubkey,
+) -> [&'a [u8]; 3] {
+    [b"nft-vote-record", proposal.as_ref(), nft_mint.as_ref()]
+}
+
+/// Returns NftVoteRecord PDA address
+pub fn get_nft_vote_record_address(
+    program_id: &Pubkey,
+    proposal: &Pubkey,
+    nft_mint: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &get_nft_vote_record_address_seeds(proposal, nft_mint),
+        program_id,
+    )
+    .0
+}
@@ -0,0 +1,444 @@
+//! Registrar Account
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+};
+use spl_governance::{
+    state::realm::get_realm_data_for_authority,
+    tools::spl_token::{
+        assert_is_valid_spl_token_account, get_spl_token_mint, get_spl_token_owner,
+    },
+};
+use spl_governance_addin_api::voter_weight::VoterWeightRecord;
+use spl_governance_tools::account::{get_account_data, AccountMaxSize};
+
+use crate::{
+    error::NftVoterAddinError, state::enums::NftVoterAccountType,
+    tools::nft::get_verified_nft_collection,
+};
+
+/// Configuration of an NFT collection whose NFTs can be used to vote
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct CollectionConfig {
+    /// The verified collection key (the collection NFT mint)
+    pub collection: Pubkey,
+
+    /// The number of NFTs in the collection
+    /// It's used to calculate the max voter weight
+    pub size: u32,
+
+    /// Vote weight of each NFT in the collection
+    pub weight: u64,
+}
+
+impl CollectionConfig {
+    /// Returns the max vote weight of all the NFTs in the collection
+    pub fn get_max_vote_weight(&self) -> Result<u64, ProgramError> {
+        (self.size as u64)
+            .checked_mul(self.weight)
+            .ok_or_else(|| NftVoterAddinError::VoterWeightOverflow.into())
+    }
+}
+
+/// Registrar account
+/// It configures the addin for the given Realm and governing token mint and holds the collections eligible to vote
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct Registrar {
+    /// Account type
+    pub account_type: NftVoterAccountType,
+
+    /// The governance program the Realm belongs to
+    pub governance_program_id: Pubkey,
+
+    /// The Realm the Registrar belongs to
+    pub realm: Pubkey,
+
+    /// Governing token mint the Registrar provides voter weight for
+    pub governing_token_mint: Pubkey,
+
+    /// NFT collections eligible to vote
+    pub collection_configs: Vec<CollectionConfig>,
+
+    /// Reserved space for future versions
+    pub reserved: [u8; 64],
+}
+
+impl AccountMaxSize for Registrar {
+    fn get_max_size(&self) -> Option<usize> {
+        Some(165 + Registrar::MAX_COLLECTIONS * 44)
+    }
+}
+
+impl IsInitialized for Registrar {
+    fn is_initialized(&self) -> bool {
+        self.account_type == NftVoterAccountType::Registrar
+    }
+}
+
+impl Registrar {
+    /// Max number of collections a Registrar can be configured with
+    pub const MAX_COLLECTIONS: usize = 10;
+
+    /// Adds a new collection config or replaces the existing config for the same collection
+    pub fn set_collection_config(
+        &mut self,
+        collection_config: CollectionConfig,
+    ) -> Result<(), ProgramError> {
+        if collection_config.size == 0 {
+            return Err(NftVoterAddinError::InvalidCollectionSize.into());
+        }
+
+        if let Some(existing_config) = self
+            .collection_configs
+            .iter_mut()
+            .find(|cc| cc.collection == collection_config.collection)
+        {
+            *existing_config = collection_config;
+        } else {
+            if self.collection_configs.len() >= Registrar::MAX_COLLECTIONS {
+                return Err(NftVoterAddinError::MaxCollectionsReached.into());
+            }
+
+            self.collection_configs.push(collection_config);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the max voter weight of all the NFTs in all the configured collections
+    pub fn get_max_voter_weight(&self) -> Result<u64, ProgramError> {
+        self.collection_configs
+            .iter()
+            .try_fold(0_u64, |max_voter_weight, collection_config| {
+                max_voter_weight
+                    .checked_add(collection_config.get_max_vote_weight()?)
+                    .ok_or_else(|| NftVoterAddinError::VoterWeightOverflow.into())
+            })
+    }
+
+    /// Returns the config of the given collection
+    pub fn get_collection_config(
+        &self,
+        collection: &Pubkey,
+    ) -> Result<&CollectionConfig, ProgramError> {
+        self.collection_configs
+            .iter()
+            .find(|cc| cc.collection == *collection)
+            .ok_or_else(|| NftVoterAddinError::CollectionNotFound.into())
+    }
+
+    /// Resolves the vote weight of the given NFT owned by the governing token owner
+    /// The NFT's mint is recorded in unique_nft_mints to detect the same NFT being used more than once
+    /// Returns the NFT vote weight and its mint
+    pub fn resolve_nft_vote_weight_and_mint(
+        &self,
+        governing_token_owner: &Pubkey,
+        nft_info: &AccountInfo,
+        nft_metadata_info: &AccountInfo,
+        unique_nft_mints: &mut Vec<Pubkey>,
+    ) -> Result<(u64, Pubkey), ProgramError> {
+        if get_spl_token_owner(nft_info)? != *governing_token_owner {
+            return Err(NftVoterAddinError::InvalidNftOwner.into());
+        }
+
+        if get_spl_token_amount(nft_info)? != 1 {
+            return Err(NftVoterAddinError::InvalidNftAmount.into());
+        }
+
+        let nft_mint = get_spl_token_mint(nft_info)?;
+
+        if unique_nft_mints.contains(&nft_mint) {
+            return Err(NftVoterAddinError::DuplicatedNftDetected.into());
+        }
+
+        unique_nft_mints.push(nft_mint);
+
+        let collection = get_verified_nft_collection(nft_metadata_info, &nft_mint)?;
+        let collection_config = self.get_collection_config(&collection)?;
+
+        Ok((collection_config.weight, nft_mint))
+    }
+
+    /// Asserts the given Realm is the Registrar's Realm and its authority signed the transaction
+    pub fn assert_realm_authority_is_signer(
+        &self,
+        realm_info: &AccountInfo,
+        realm_authority_info: &AccountInfo,
+    ) -> Result<(), ProgramError> {
+        if self.realm != *realm_info.key {
+            return Err(NftVoterAddinError::InvalidRealmForRegistrar.into());
+        }
+
+        get_realm_data_for_authority(
+            &self.governance_program_id,
+            realm_info,
+            realm_authority_info.key,
+        )?;
+
+        if !realm_authority_info.is_signer {
+            return Err(NftVoterAddinError::RealmAuthorityMustSign.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the amount of tokens held by the given spl-token account
+fn get_spl_token_amount(token_account_info: &AccountInfo) -> Result<u64, ProgramError> {
+    assert_is_valid_spl_token_account(token_account_info)?;
+
+    let token_account = spl_token::state::Account::unpack(&token_account_info.data.borrow())?;
+
+    Ok(token_account.amount)
+}
+
+/// Deserializes Registrar account and checks owner program
+pub fn get_registrar_data(
+    program_id: &Pubkey,
+    registrar_info: &AccountInfo,
+) -> Result<Registrar, ProgramError> {
+    get_account_data::<Registrar>(program_id, registrar_info)
+}
+
+/// Deserializes VoterWeightRecord account and asserts it belongs to the given Registrar
+pub fn get_voter_weight_record_data_for_registrar(
+    program_id: &Pubkey,
+    voter_weight_record_info: &AccountInfo,
+    registrar: &Registrar,
+) -> Result<VoterWeightRecord, ProgramError> {
+    let voter_weight_record_data =
+        get_account_data::<VoterWeightRecord>(program_id, voter_weight_record_info)?;
+
+    if voter_weight_record_data.realm != registrar.realm
+        || voter_weight_record_data.governing_token_mint != registrar.governing_token_mint
+    {
+        return Err(NftVoterAddinError::InvalidVoterWeightRecordForRegistrar.into());
+    }
+
+    Ok(voter_weight_record_data)
+}
+
+/// Returns Registrar PDA seeds
+pub fn get_registrar_address_seeds<'a>(
+    realm: &'a Pubkey,
+    governing_token_mint: &'a Pubkey,
+) -> [&'a [u8]; 3] {
+    [b"registrar", realm.as_ref(), governing_token_mint.as_ref()]
+}
+
+/// Returns Registrar PDA address
+pub fn get_registrar_address(
+    program_id: &Pubkey,
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &get_registrar_address_seeds(realm, governing_token_mint),
+        program_id,
+    )
+    .0
+}
+
+/// Returns MaxVoterWeightRecord PDA seeds
+pub fn get_max_voter_weight_record_address_seeds<'a>(
+    realm: &'a Pubkey,
+    governing_token_mint: &'a Pubkey,
+) -> [&'a [u8]; 3] {
+    [
+        b"max-voter-weight-record",
+        realm.as_ref(),
+        governing_token_mint.as_ref(),
+    ]
+}
+
+/// Returns MaxVoterWeightRecord PDA address
+pub fn get_max_voter_weight_record_address(
+    program_id: &Pubkey,
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &get_max_voter_weight_record_address_seeds(realm, governing_token_mint),
+        program_id,
+    )
+    .0
+}
+
+/// Returns VoterWeightRecord PDA seeds
+pub fn get_voter_weight_record_address_seeds<'a>(
+    realm: &'a Pubkey,
+    governing_token_mint: &'a Pubkey,
+    governing_token_owner: &'a Pubkey,
+) -> [&'a [u8]; 4] {
+    [
+        b"voter-weight-record",
+        realm.as_ref(),
+        governing_token_mint.as_ref(),
+        governing_token_owner.as_ref(),
+    ]
+}
+
+/// Returns VoterWeightRecord PDA address
+pub fn get_voter_weight_record_address(
+    program_id: &Pubkey,
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    governing_token_owner: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &get_voter_weight_record_address_seeds(realm, governing_token_mint, governing_token_owner),
+        program_id,
+    )
+    .0
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn create_test_registrar() -> Registrar {
+        Registrar {
+            account_type: NftVoterAccountType::Registrar,
+            governance_program_id: Pubkey::new_unique(),
+            realm: Pubkey::new_unique(),
+            governing_token_mint: Pubkey::new_unique(),
+            collection_configs: vec![],
+            reserved: [0; 64],
+        }
+    }
+
+    fn create_test_collection_config() -> CollectionConfig {
+        CollectionConfig {
+            collection: Pubkey::new_unique(),
+            size: 100,
+            weight: 10,
+        }
+    }
+
+    #[test]
+    fn test_max_size() {
+        // Arrange
+        let mut registrar = create_test_registrar();
+
+        for _ in 0..Registrar::MAX_COLLECTIONS {
+            registrar
+                .set_collection_config(create_test_collection_config())
+                .unwrap();
+        }
+
+        // Act
+        let size = registrar.try_to_vec().unwrap().len();
+
+        // Assert
+        assert_eq!(registrar.get_max_size(), Some(size));
+    }
+
+    #[test]
+    fn test_set_collection_config_replaces_existing_config() {
+        // Arrange
+        let mut registrar = create_test_registrar();
+
+        let mut collection_config = create_test_collection_config();
+        registrar
+            .set_collection_config(collection_config.clone())
+            .unwrap();
+
+        collection_config.weight = 20;
+
+        // Act
+        registrar
+            .set_collection_config(collection_config.clone())
+            .unwrap();
+
+        // Assert
+        assert_eq!(registrar.collection_configs, vec![collection_config]);
+    }
+
+    #[test]
+    fn test_set_collection_config_with_max_collections_reached_error() {
+        // Arrange
+        let mut registrar = create_test_registrar();
+
+        for _ in 0..Registrar::MAX_COLLECTIONS {
+            registrar
+                .set_collection_config(create_test_collection_config())
+                .unwrap();
+        }
+
+        // Act
+        let err = registrar
+            .set_collection_config(create_test_collection_config())
+            .err()
+            .unwrap();
+
+        // Assert
+        assert_eq!(err, NftVoterAddinError::MaxCollectionsReached.into());
+    }
+
+    #[test]
+    fn test_set_collection_config_with_zero_size_error() {
+        // Arrange
+        let mut registrar = create_test_registrar();
+
+        let mut collection_config = create_test_collection_config();
+        collection_config.size = 0;
+
+        // Act
+        let err = registrar
+            .set_collection_config(collection_config)
+            .err()
+            .unwrap();
+
+        // Assert
+        assert_eq!(err, NftVoterAddinError::InvalidCollectionSize.into());
+    }
+
+    #[test]
+    fn test_get_max_voter_weight() {
+        // Arrange
+        let mut registrar = create_test_registrar();
+
+        registrar
+            .set_collection_config(create_test_collection_config())
+            .unwrap();
+
+        registrar
+            .set_collection_config(CollectionConfig {
+                collection: Pubkey::new_unique(),
+                size: 5,
+                weight: 3,
+            })
+            .unwrap();
+
+        // Act
+        let max_voter_weight = registrar.get_max_voter_weight().unwrap();
+
+        // Assert
+        assert_eq!(100 * 10 + 5 * 3, max_voter_weight);
+    }
+
+    #[test]
+    fn test_get_max_voter_weight_with_overflow_error() {
+        // Arrange
+        let mut registrar = create_test_registrar();
+
+        registrar
+            .set_collection_config(CollectionConfig {
+                collection: Pubkey::new_unique(),
+                size: 2,
+                weight: u64::MAX,
+            })
+            .unwrap();
+
+        // Act
+        let err = registrar.get_max_voter_weight().err().unwrap();
+
+        // Assert
+        assert_eq!(err, NftVoterAddinError::VoterWeightOverflow.into());
+    }
+}
@@ -0,0 +1,3 @@
+//! Utility functions
+
+pub mod nft;
@@ -0,0 +1,259 @@
+//! NFT metadata utility functions
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use metaplex_token_metadata::state::{Key, Metadata, PREFIX};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::error::NftVoterAddinError;
+
+/// The collection an NFT belongs to
+/// It mirrors the `collection` field stored in Metaplex token metadata accounts after the `token_standard` field
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct NftCollection {
+    /// Indicates whether the collection authority verified the NFT as a member of the collection
+    pub verified: bool,
+
+    /// The collection key (the collection NFT mint)
+    pub key: Pubkey,
+}
+
+/// Returns Metaplex token metadata PDA address for the given NFT mint
+pub fn get_nft_metadata_address(nft_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            metaplex_token_metadata::id().as_ref(),
+            nft_mint.as_ref(),
+        ],
+        &metaplex_token_metadata::id(),
+    )
+    .0
+}
+
+/// Deserializes the collection from the Metaplex token metadata account of the given NFT mint
+pub fn get_nft_collection(
+    nft_metadata_info: &AccountInfo,
+    nft_mint: &Pubkey,
+) -> Result<Option<NftCollection>, ProgramError> {
+    if nft_metadata_info.owner != &metaplex_token_metadata::id()
+        || get_nft_metadata_address(nft_mint) != *nft_metadata_info.key
+    {
+        return Err(NftVoterAddinError::InvalidNftMetadataAccount.into());
+    }
+
+    let data = nft_metadata_info.data.borrow();
+    let mut data: &[u8] = &data;
+
+    let metadata = Metadata::deserialize(&mut data)
+        .map_err(|_| NftVoterAddinError::InvalidNftMetadataAccount)?;
+
+    if metadata.key != Key::MetadataV1 || metadata.mint != *nft_mint {
+        return Err(NftVoterAddinError::InvalidNftMetadataAccount.into());
+    }
+
+    // Skip token_standard which precedes the collection
+    let (_token_standard, collection) =
+        <(Option<u8>, Option<NftCollection>)>::deserialize(&mut data)
+            .map_err(|_| NftVoterAddinError::InvalidNftMetadataAccount)?;
+
+    Ok(collection)
+}
+
+/// Returns the verified collection of the given NFT mint
+pub fn get_verified_nft_collection(
+    nft_metadata_info: &AccountInfo,
+    nft_mint: &Pubkey,
+) -> Result<Pubkey, ProgramError> {
+    match get_nft_collection(nft_metadata_info, nft_mint)? {
+        Some(collection) if collection.verified => Ok(collection.key),
+        _ => Err(NftVoterAddinError::CollectionMustBeVerified.into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use metaplex_token_metadata::state::Data;
+    use solana_program::clock::Epoch;
+
+    use super::*;
+
+    fn create_test_metadata_data(nft_mint: &Pubkey, collection: Option<NftCollection>) -> Vec<u8> {
+        let metadata = Metadata {
+            key: Key::MetadataV1,
+            update_authority: Pubkey::new_unique(),
+            mint: *nft_mint,
+            data: Data {
+                name: "NFT".to_string(),
+                symbol: "NFT".to_string(),
+                uri: "https://nft".to_string(),
+                seller_fee_basis_points: 0,
+                creators: None,
+            },
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+        };
+
+        let mut data = metadata.try_to_vec().unwrap();
+        data.extend((Some(0_u8), collection).try_to_vec().unwrap());
+
+        data
+    }
+
+    fn get_collection(
+        metadata_address: &Pubkey,
+        metadata_owner: &Pubkey,
+        nft_mint: &Pubkey,
+        data: &mut [u8],
+    ) -> Result<Pubkey, ProgramError> {
+        let mut lamports = 0;
+
+        let metadata_info = AccountInfo::new(
+            metadata_address,
+            false,
+            false,
+            &mut lamports,
+            data,
+            metadata_owner,
+            false,
+            Epoch::default(),
+        );
+
+        get_verified_nft_collection(&metadata_info, nft_mint)
+    }
+
+    #[test]
+    fn test_get_verified_nft_collection() {
+        // Arrange
+        let nft_mint = Pubkey::new_unique();
+        let collection = Pubkey::new_unique();
+
+        let mut data = create_test_metadata_data(
+            &nft_mint,
+            Some(NftCollection {
+                verified: true,
+                key: collection,
+            }),
+        );
+
+        // Act
+        let result = get_collection(
+            &get_nft_metadata_address(&nft_mint),
+            &metaplex_token_metadata::id(),
+            &nft_mint,
+            &mut data,
+        );
+
+        // Assert
+        assert_eq!(result, Ok(collection));
+    }
+
+    #[test]
+    fn test_get_verified_nft_collection_with_unverified_collection_error() {
+        // Arrange
+        let nft_mint = Pubkey::new_unique();
+
+        let mut data = create_test_metadata_data(
+            &nft_mint,
+            Some(NftCollection {
+                verified: false,
+                key: Pubkey::new_unique(),
+            }),
+        );
+
+        // Act
+        let result = get_collection(
+            &get_nft_metadata_address(&nft_mint),
+            &metaplex_token_metadata::id(),
+            &nft_mint,
+            &mut data,
+        );
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(NftVoterAddinError::CollectionMustBeVerified.into())
+        );
+    }
+
+    #[test]
+    fn test_get_verified_nft_collection_without_collection_error() {
+        // Arrange
+        let nft_mint = Pubkey::new_unique();
+
+        let mut data = create_test_metadata_data(&nft_mint, None);
+
+        // Act
+        let result = get_collection(
+            &get_nft_metadata_address(&nft_mint),
+            &metaplex_token_metadata::id(),
+            &nft_mint,
+            &mut data,
+        );
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(NftVoterAddinError::CollectionMustBeVerified.into())
+        );
+    }
+
+    #[test]
+    fn test_get_verified_nft_collection_with_invalid_metadata_owner_error() {
+        // Arrange
+        let nft_mint = Pubkey::new_unique();
+
+        let mut data = create_test_metadata_data(
+            &nft_mint,
+            Some(NftCollection {
+                verified: true,
+                key: Pubkey::new_unique(),
+            }),
+        );
+
+        // Act
+        let result = get_collection(
+            &get_nft_metadata_address(&nft_mint),
+            &Pubkey::new_unique(),
+            &nft_mint,
+            &mut data,
+        );
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(NftVoterAddinError::InvalidNftMetadataAccount.into())
+        );
+    }
+
+    #[test]
+    fn test_get_verified_nft_collection_for_other_mint_error() {
+        // Arrange
+        let nft_mint = Pubkey::new_unique();
+        let other_nft_mint = Pubkey::new_unique();
+
+        // Metadata of another NFT
+        let mut data = create_test_metadata_data(
+            &other_nft_mint,
+            Some(NftCollection {
+                verified: true,
+                key: Pubkey::new_unique(),
+            }),
+        );
+
+        // Act
+        let result = get_collection(
+            &get_nft_metadata_address(&nft_mint),
+            &metaplex_token_metadata::id(),
+            &nft_mint,
+            &mut data,
+        );
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(NftVoterAddinError::InvalidNftMetadataAccount.into())
+        );
+    }
+}
@@ -0,0 +1,178 @@
+#![cfg(feature = "test-bpf")]
+
+use solana_program::pubkey::Pubkey;
+use solana_program_test::*;
+use solana_sdk::signer::Signer;
+
+mod program_test;
+
+use program_test::*;
+use spl_governance::state::{enums::ProposalState, proposal::ProposalV2};
+use spl_governance_addin_api::voter_weight::VoterWeightAction;
+use spl_governance_addin_nft_voter::{
+    error::NftVoterAddinError,
+    state::{
+        enums::NftVoterAccountType,
+        nft_vote_record::{get_nft_vote_record_address, NftVoteRecord},
+    },
+};
+
+#[tokio::test]
+async fn test_cast_nft_vote() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    let collection = nft_voter_test
+        .with_collection(&realm_cookie, 10, 100)
+        .await
+        .unwrap();
+
+    let voter_cookie = nft_voter_test.with_voter(&realm_cookie).await;
+
+    let nft_cookie1 = nft_voter_test
+        .with_collection_nft(&voter_cookie, &collection)
+        .await;
+    let nft_cookie2 = nft_voter_test
+        .with_collection_nft(&voter_cookie, &collection)
+        .await;
+
+    let proposal = Pubkey::new_unique();
+
+    // Act
+    nft_voter_test
+        .cast_nft_vote(
+            &realm_cookie,
+            &voter_cookie,
+            &[&nft_cookie1, &nft_cookie2],
+            &proposal,
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let clock = nft_voter_test.bench.get_clock().await;
+
+    let voter_weight_record = nft_voter_test
+        .get_voter_weight_record(&voter_cookie.voter_weight_record)
+        .await;
+
+    assert_eq!(20, voter_weight_record.voter_weight);
+    assert_eq!(Some(clock.slot), voter_weight_record.voter_weight_expiry);
+    assert_eq!(
+        Some(VoterWeightAction::CastVote),
+        voter_weight_record.weight_action
+    );
+    assert_eq!(Some(proposal), voter_weight_record.weight_action_target);
+
+    let nft_vote_record = nft_voter_test
+        .get_nft_vote_record_account(&get_nft_vote_record_address(
+            &nft_voter_test.program_id,
+            &proposal,
+            &nft_cookie1.mint,
+        ))
+        .await;
+
+    assert_eq!(
+        NftVoteRecord {
+            account_type: NftVoterAccountType::NftVoteRecord,
+            proposal,
+            nft_mint: nft_cookie1.mint,
+            governing_token_owner: voter_cookie.governing_token_owner.pubkey(),
+        },
+        nft_vote_record
+    );
+}
+
+#[tokio::test]
+async fn test_cast_nft_vote_with_nft_already_voted_error() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    let collection = nft_voter_test
+        .with_collection(&realm_cookie, 10, 100)
+        .await
+        .unwrap();
+
+    let voter_cookie = nft_voter_test.with_voter(&realm_cookie).await;
+
+    let nft_cookie1 = nft_voter_test
+        .with_collection_nft(&voter_cookie, &collection)
+        .await;
+    let nft_cookie2 = nft_voter_test
+        .with_collection_nft(&voter_cookie, &collection)
+        .await;
+
+    let proposal = Pubkey::new_unique();
+
+    nft_voter_test
+        .cast_nft_vote(&realm_cookie, &voter_cookie, &[&nft_cookie1], &proposal)
+        .await
+        .unwrap();
+
+    // Act
+    let err = nft_voter_test
+        .cast_nft_vote(
+            &realm_cookie,
+            &voter_cookie,
+            &[&nft_cookie2, &nft_cookie1],
+            &proposal,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, NftVoterAddinError::NftAlreadyVoted.into());
+}
+
+#[tokio::test]
+async fn test_cast_vote_with_nft_voter_weight() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    let registrar_cookie = nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    let collection = nft_voter_test
+        .with_collection(&realm_cookie, 10, 100)
+        .await
+        .unwrap();
+
+    let voter_cookie = nft_voter_test.with_voter(&realm_cookie).await;
+
+    let nft_cookie = nft_voter_test
+        .with_collection_nft(&voter_cookie, &collection)
+        .await;
+
+    let proposal_cookie = nft_voter_test
+        .with_proposal(&realm_cookie, &voter_cookie, &[&nft_cookie])
+        .await
+        .unwrap();
+
+    // Act
+    nft_voter_test
+        .cast_vote(
+            &realm_cookie,
+            &registrar_cookie,
+            &voter_cookie,
+            &[&nft_cookie],
+            &proposal_cookie,
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let proposal_account = nft_voter_test
+        .bench
+        .get_borsh_account::<ProposalV2>(&proposal_cookie.address)
+        .await;
+
+    assert_eq!(ProposalState::Voting, proposal_account.state);
+    assert_eq!(10, proposal_account.options[0].vote_weight);
+}
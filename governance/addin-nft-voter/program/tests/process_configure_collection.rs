@@ -0,0 +1,138 @@
+#![cfg(feature = "test-bpf")]
+
+use solana_program::pubkey::Pubkey;
+use solana_program_test::*;
+use solana_sdk::signature::Keypair;
+
+mod program_test;
+
+use program_test::*;
+use spl_governance::error::GovernanceError;
+use spl_governance_addin_nft_voter::{
+    error::NftVoterAddinError, state::registrar::CollectionConfig,
+};
+
+#[tokio::test]
+async fn test_configure_collection() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    let registrar_cookie = nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    // Act
+    let collection = nft_voter_test
+        .with_collection(&realm_cookie, 10, 100)
+        .await
+        .unwrap();
+
+    // Assert
+    let registrar_account = nft_voter_test
+        .get_registrar_account(&registrar_cookie.address)
+        .await;
+
+    assert_eq!(
+        registrar_account.collection_configs,
+        vec![CollectionConfig {
+            collection,
+            size: 100,
+            weight: 10,
+        }]
+    );
+
+    let max_voter_weight_record = nft_voter_test
+        .get_max_voter_weight_record(&registrar_cookie.max_voter_weight_record)
+        .await;
+
+    assert_eq!(1000, max_voter_weight_record.max_voter_weight);
+}
+
+#[tokio::test]
+async fn test_configure_collection_with_existing_collection() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    let registrar_cookie = nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    let collection = nft_voter_test
+        .with_collection(&realm_cookie, 10, 100)
+        .await
+        .unwrap();
+
+    nft_voter_test
+        .with_collection(&realm_cookie, 1, 5)
+        .await
+        .unwrap();
+
+    // Act
+    nft_voter_test
+        .configure_collection(
+            &realm_cookie,
+            &realm_cookie.realm_authority,
+            &collection,
+            2,
+            200,
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let registrar_account = nft_voter_test
+        .get_registrar_account(&registrar_cookie.address)
+        .await;
+
+    assert_eq!(2, registrar_account.collection_configs.len());
+
+    let max_voter_weight_record = nft_voter_test
+        .get_max_voter_weight_record(&registrar_cookie.max_voter_weight_record)
+        .await;
+
+    assert_eq!(2 * 200 + 5, max_voter_weight_record.max_voter_weight);
+}
+
+#[tokio::test]
+async fn test_configure_collection_with_invalid_realm_authority_error() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    let realm_authority = Keypair::new();
+
+    // Act
+    let err = nft_voter_test
+        .configure_collection(
+            &realm_cookie,
+            &realm_authority,
+            &Pubkey::new_unique(),
+            10,
+            100,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, GovernanceError::InvalidAuthorityForRealm.into());
+}
+
+#[tokio::test]
+async fn test_configure_collection_with_zero_size_error() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    // Act
+    let err = nft_voter_test
+        .with_collection(&realm_cookie, 10, 0)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, NftVoterAddinError::InvalidCollectionSize.into());
+}
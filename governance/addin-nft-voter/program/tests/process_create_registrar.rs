@@ -0,0 +1,82 @@
+#![cfg(feature = "test-bpf")]
+
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+mod program_test;
+
+use program_test::*;
+use spl_governance::error::GovernanceError;
+
+#[tokio::test]
+async fn test_create_registrar() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+
+    // Act
+    let registrar_cookie = nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    // Assert
+    let registrar_account = nft_voter_test
+        .get_registrar_account(&registrar_cookie.address)
+        .await;
+
+    assert_eq!(registrar_cookie.account, registrar_account);
+
+    let max_voter_weight_record = nft_voter_test
+        .get_max_voter_weight_record(&registrar_cookie.max_voter_weight_record)
+        .await;
+
+    assert_eq!(realm_cookie.address, max_voter_weight_record.realm);
+    assert_eq!(0, max_voter_weight_record.max_voter_weight);
+    assert_eq!(None, max_voter_weight_record.max_voter_weight_expiry);
+}
+
+#[tokio::test]
+async fn test_create_registrar_with_invalid_realm_authority_error() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+
+    let realm_authority = Keypair::new();
+
+    // Act
+    let err = nft_voter_test
+        .with_registrar_using_instruction(
+            &realm_cookie,
+            |i| i.accounts[5] = AccountMeta::new_readonly(realm_authority.pubkey(), true), // realm_authority
+            Some(&[&realm_authority]),
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, GovernanceError::InvalidAuthorityForRealm.into());
+}
+
+#[tokio::test]
+async fn test_create_registrar_with_invalid_governing_token_mint_error() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+
+    // Act
+    let err = nft_voter_test
+        .with_registrar_using_instruction(
+            &realm_cookie,
+            |i| i.accounts[4].pubkey = Pubkey::new_unique(), // governing_token_mint
+            None,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, GovernanceError::InvalidGoverningTokenMint.into());
+}
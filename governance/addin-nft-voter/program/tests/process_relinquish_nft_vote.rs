@@ -0,0 +1,132 @@
+#![cfg(feature = "test-bpf")]
+
+use solana_program_test::*;
+
+mod program_test;
+
+use program_test::*;
+use spl_governance_addin_nft_voter::{
+    error::NftVoterAddinError, state::nft_vote_record::get_nft_vote_record_address,
+};
+
+#[tokio::test]
+async fn test_relinquish_nft_vote() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    let registrar_cookie = nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    let collection = nft_voter_test
+        .with_collection(&realm_cookie, 10, 100)
+        .await
+        .unwrap();
+
+    let voter_cookie = nft_voter_test.with_voter(&realm_cookie).await;
+
+    let nft_cookie = nft_voter_test
+        .with_collection_nft(&voter_cookie, &collection)
+        .await;
+
+    let proposal_cookie = nft_voter_test
+        .with_proposal(&realm_cookie, &voter_cookie, &[&nft_cookie])
+        .await
+        .unwrap();
+
+    nft_voter_test
+        .cast_vote(
+            &realm_cookie,
+            &registrar_cookie,
+            &voter_cookie,
+            &[&nft_cookie],
+            &proposal_cookie,
+        )
+        .await
+        .unwrap();
+
+    nft_voter_test
+        .relinquish_vote(&realm_cookie, &voter_cookie, &proposal_cookie)
+        .await
+        .unwrap();
+
+    // Act
+    nft_voter_test
+        .relinquish_nft_vote(
+            &realm_cookie,
+            &voter_cookie,
+            &[&nft_cookie],
+            &proposal_cookie.address,
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let nft_vote_record = nft_voter_test
+        .bench
+        .get_account(&get_nft_vote_record_address(
+            &nft_voter_test.program_id,
+            &proposal_cookie.address,
+            &nft_cookie.mint,
+        ))
+        .await;
+
+    assert_eq!(None, nft_vote_record);
+
+    let voter_weight_record = nft_voter_test
+        .get_voter_weight_record(&voter_cookie.voter_weight_record)
+        .await;
+
+    assert_eq!(0, voter_weight_record.voter_weight);
+    assert_eq!(Some(0), voter_weight_record.voter_weight_expiry);
+}
+
+#[tokio::test]
+async fn test_relinquish_nft_vote_with_vote_record_not_relinquished_error() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    let registrar_cookie = nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    let collection = nft_voter_test
+        .with_collection(&realm_cookie, 10, 100)
+        .await
+        .unwrap();
+
+    let voter_cookie = nft_voter_test.with_voter(&realm_cookie).await;
+
+    let nft_cookie = nft_voter_test
+        .with_collection_nft(&voter_cookie, &collection)
+        .await;
+
+    let proposal_cookie = nft_voter_test
+        .with_proposal(&realm_cookie, &voter_cookie, &[&nft_cookie])
+        .await
+        .unwrap();
+
+    nft_voter_test
+        .cast_vote(
+            &realm_cookie,
+            &registrar_cookie,
+            &voter_cookie,
+            &[&nft_cookie],
+            &proposal_cookie,
+        )
+        .await
+        .unwrap();
+
+    // Act
+    let err = nft_voter_test
+        .relinquish_nft_vote(
+            &realm_cookie,
+            &voter_cookie,
+            &[&nft_cookie],
+            &proposal_cookie.address,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, NftVoterAddinError::VoteRecordMustBeRelinquished.into());
+}
@@ -0,0 +1,246 @@
+#![cfg(feature = "test-bpf")]
+
+use solana_program::pubkey::Pubkey;
+use solana_program_test::*;
+
+mod program_test;
+
+use program_test::*;
+use spl_governance_addin_api::voter_weight::VoterWeightAction;
+use spl_governance_addin_nft_voter::{error::NftVoterAddinError, tools::nft::NftCollection};
+
+#[tokio::test]
+async fn test_update_voter_weight_record() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    let collection1 = nft_voter_test
+        .with_collection(&realm_cookie, 10, 100)
+        .await
+        .unwrap();
+    let collection2 = nft_voter_test
+        .with_collection(&realm_cookie, 3, 100)
+        .await
+        .unwrap();
+
+    let voter_cookie = nft_voter_test.with_voter(&realm_cookie).await;
+
+    let nft_cookie1 = nft_voter_test
+        .with_collection_nft(&voter_cookie, &collection1)
+        .await;
+    let nft_cookie2 = nft_voter_test
+        .with_collection_nft(&voter_cookie, &collection2)
+        .await;
+
+    // Act
+    nft_voter_test
+        .update_voter_weight_record(
+            &realm_cookie,
+            &voter_cookie,
+            &[&nft_cookie1, &nft_cookie2],
+            VoterWeightAction::CreateProposal,
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let clock = nft_voter_test.bench.get_clock().await;
+
+    let voter_weight_record = nft_voter_test
+        .get_voter_weight_record(&voter_cookie.voter_weight_record)
+        .await;
+
+    assert_eq!(10 + 3, voter_weight_record.voter_weight);
+    assert_eq!(Some(clock.slot), voter_weight_record.voter_weight_expiry);
+    assert_eq!(
+        Some(VoterWeightAction::CreateProposal),
+        voter_weight_record.weight_action
+    );
+    assert_eq!(None, voter_weight_record.weight_action_target);
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_with_cast_vote_not_allowed_error() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    let collection = nft_voter_test
+        .with_collection(&realm_cookie, 10, 100)
+        .await
+        .unwrap();
+
+    let voter_cookie = nft_voter_test.with_voter(&realm_cookie).await;
+
+    let nft_cookie = nft_voter_test
+        .with_collection_nft(&voter_cookie, &collection)
+        .await;
+
+    // Act
+    let err = nft_voter_test
+        .update_voter_weight_record(
+            &realm_cookie,
+            &voter_cookie,
+            &[&nft_cookie],
+            VoterWeightAction::CastVote,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, NftVoterAddinError::CastVoteIsNotAllowed.into());
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_with_unverified_collection_error() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    let collection = nft_voter_test
+        .with_collection(&realm_cookie, 10, 100)
+        .await
+        .unwrap();
+
+    let voter_cookie = nft_voter_test.with_voter(&realm_cookie).await;
+
+    let nft_cookie = nft_voter_test
+        .with_nft(
+            &voter_cookie,
+            Some(NftCollection {
+                verified: false,
+                key: collection,
+            }),
+        )
+        .await;
+
+    // Act
+    let err = nft_voter_test
+        .update_voter_weight_record(
+            &realm_cookie,
+            &voter_cookie,
+            &[&nft_cookie],
+            VoterWeightAction::CreateProposal,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, NftVoterAddinError::CollectionMustBeVerified.into());
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_with_collection_not_found_error() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    nft_voter_test
+        .with_collection(&realm_cookie, 10, 100)
+        .await
+        .unwrap();
+
+    let voter_cookie = nft_voter_test.with_voter(&realm_cookie).await;
+
+    // NFT from a collection which is not configured
+    let nft_cookie = nft_voter_test
+        .with_collection_nft(&voter_cookie, &Pubkey::new_unique())
+        .await;
+
+    // Act
+    let err = nft_voter_test
+        .update_voter_weight_record(
+            &realm_cookie,
+            &voter_cookie,
+            &[&nft_cookie],
+            VoterWeightAction::CreateProposal,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, NftVoterAddinError::CollectionNotFound.into());
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_with_duplicated_nft_error() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    let collection = nft_voter_test
+        .with_collection(&realm_cookie, 10, 100)
+        .await
+        .unwrap();
+
+    let voter_cookie = nft_voter_test.with_voter(&realm_cookie).await;
+
+    let nft_cookie = nft_voter_test
+        .with_collection_nft(&voter_cookie, &collection)
+        .await;
+
+    // Act
+    let err = nft_voter_test
+        .update_voter_weight_record(
+            &realm_cookie,
+            &voter_cookie,
+            &[&nft_cookie, &nft_cookie],
+            VoterWeightAction::CreateProposal,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, NftVoterAddinError::DuplicatedNftDetected.into());
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_with_nft_owned_by_other_voter_error() {
+    // Arrange
+    let mut nft_voter_test = NftVoterProgramTest::start_new().await;
+
+    let realm_cookie = nft_voter_test.with_realm().await;
+    nft_voter_test.with_registrar(&realm_cookie).await.unwrap();
+
+    let collection = nft_voter_test
+        .with_collection(&realm_cookie, 10, 100)
+        .await
+        .unwrap();
+
+    let voter_cookie = nft_voter_test.with_voter(&realm_cookie).await;
+    let voter_cookie2 = nft_voter_test.with_voter(&realm_cookie).await;
+
+    let nft_cookie = nft_voter_test
+        .with_collection_nft(&voter_cookie2, &collection)
+        .await;
+
+    // Act
+    let err = nft_voter_test
+        .update_voter_weight_record(
+            &realm_cookie,
+            &voter_cookie,
+            &[&nft_cookie],
+            VoterWeightAction::CreateProposal,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, NftVoterAddinError::InvalidNftOwner.into());
+}
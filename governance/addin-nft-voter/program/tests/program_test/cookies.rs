@@ -0,0 +1,37 @@
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use spl_governance_addin_nft_voter::state::registrar::Registrar;
+
+#[derive(Debug)]
+pub struct RealmCookie {
+    pub address: Pubkey,
+    pub community_mint: Pubkey,
+    pub realm_authority: Keypair,
+}
+
+#[derive(Debug)]
+pub struct RegistrarCookie {
+    pub address: Pubkey,
+    pub account: Registrar,
+    pub max_voter_weight_record: Pubkey,
+}
+
+#[derive(Debug)]
+pub struct VoterCookie {
+    pub governing_token_owner: Keypair,
+    pub voter_weight_record: Pubkey,
+    pub token_owner_record: Pubkey,
+}
+
+#[derive(Debug)]
+pub struct NftCookie {
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub metadata: Pubkey,
+}
+
+#[derive(Debug)]
+pub struct ProposalCookie {
+    pub governance: Pubkey,
+    pub address: Pubkey,
+}
@@ -0,0 +1,653 @@
+use std::str::FromStr;
+
+use borsh::BorshSerialize;
+use metaplex_token_metadata::state::{Data, Key, Metadata};
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::{processor, ProgramTest};
+
+use solana_sdk::{account::Account, signature::Keypair, signer::Signer};
+use spl_governance::{
+    instruction::{
+        cast_vote, create_governance, create_proposal, create_realm, create_token_owner_record,
+        relinquish_vote, sign_off_proposal,
+    },
+    state::{
+        enums::{MintMaxVoteWeightSource, VoteThreshold, VoteTipping},
+        governance::{get_governance_address, GovernanceConfig},
+        proposal::{get_proposal_address, VoteType},
+        realm::get_realm_address,
+        token_owner_record::get_token_owner_record_address,
+        vote_record::{Vote, VoteChoice},
+    },
+};
+use spl_governance_addin_api::{
+    max_voter_weight::MaxVoterWeightRecord,
+    voter_weight::{VoterWeightAction, VoterWeightRecord},
+};
+use spl_governance_addin_nft_voter::{
+    instruction::{
+        cast_nft_vote, configure_collection, create_registrar, create_voter_weight_record,
+        relinquish_nft_vote, update_voter_weight_record,
+    },
+    processor::process_instruction,
+    state::{
+        enums::NftVoterAccountType,
+        nft_vote_record::NftVoteRecord,
+        registrar::{
+            get_max_voter_weight_record_address, get_registrar_address,
+            get_voter_weight_record_address, Registrar,
+        },
+    },
+    tools::nft::{get_nft_metadata_address, NftCollection},
+};
+use spl_governance_test_sdk::{tools::NopOverride, ProgramTestBench};
+
+use self::cookies::{NftCookie, ProposalCookie, RealmCookie, RegistrarCookie, VoterCookie};
+
+pub mod cookies;
+
+pub struct NftVoterProgramTest {
+    pub bench: ProgramTestBench,
+    pub program_id: Pubkey,
+    pub governance_program_id: Pubkey,
+}
+
+impl NftVoterProgramTest {
+    #[allow(dead_code)]
+    pub async fn start_new() -> Self {
+        let mut program_test = ProgramTest::default();
+
+        let program_id = Pubkey::from_str("NftVoterAddin111111111111111111111111111111").unwrap();
+        program_test.add_program(
+            "spl_governance_addin_nft_voter",
+            program_id,
+            processor!(process_instruction),
+        );
+
+        let governance_program_id =
+            Pubkey::from_str("Governance111111111111111111111111111111111").unwrap();
+        program_test.add_program(
+            "spl_governance",
+            governance_program_id,
+            processor!(spl_governance::processor::process_instruction),
+        );
+
+        let bench = ProgramTestBench::start_new(program_test).await;
+
+        Self {
+            bench,
+            program_id,
+            governance_program_id,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_realm(&mut self) -> RealmCookie {
+        let name = self.bench.get_unique_name("realm");
+
+        let realm_address = get_realm_address(&self.governance_program_id, &name);
+
+        let community_mint_keypair = Keypair::new();
+
+        self.bench
+            .create_mint(&community_mint_keypair, &self.bench.payer.pubkey(), None)
+            .await;
+
+        let realm_authority = Keypair::new();
+
+        let create_realm_ix = create_realm(
+            &self.governance_program_id,
+            &realm_authority.pubkey(),
+            &community_mint_keypair.pubkey(),
+            &self.bench.payer.pubkey(),
+            None,
+            Some(self.program_id),
+            Some(self.program_id),
+            name.clone(),
+            1,
+            MintMaxVoteWeightSource::FULL_SUPPLY_FRACTION,
+        );
+
+        self.bench
+            .process_transaction(&[create_realm_ix], None)
+            .await
+            .unwrap();
+
+        RealmCookie {
+            address: realm_address,
+            community_mint: community_mint_keypair.pubkey(),
+            realm_authority,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_registrar(
+        &mut self,
+        realm_cookie: &RealmCookie,
+    ) -> Result<RegistrarCookie, ProgramError> {
+        self.with_registrar_using_instruction(realm_cookie, NopOverride, None)
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_registrar_using_instruction<F: Fn(&mut Instruction)>(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        instruction_override: F,
+        signers_override: Option<&[&Keypair]>,
+    ) -> Result<RegistrarCookie, ProgramError> {
+        let mut create_registrar_ix = create_registrar(
+            &self.program_id,
+            &self.governance_program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &realm_cookie.realm_authority.pubkey(),
+            &self.bench.payer.pubkey(),
+        );
+
+        instruction_override(&mut create_registrar_ix);
+
+        let default_signers = &[&realm_cookie.realm_authority];
+        let signers = signers_override.unwrap_or(default_signers);
+
+        self.bench
+            .process_transaction(&[create_registrar_ix], Some(signers))
+            .await?;
+
+        let account = Registrar {
+            account_type: NftVoterAccountType::Registrar,
+            governance_program_id: self.governance_program_id,
+            realm: realm_cookie.address,
+            governing_token_mint: realm_cookie.community_mint,
+            collection_configs: vec![],
+            reserved: [0; 64],
+        };
+
+        Ok(RegistrarCookie {
+            address: get_registrar_address(
+                &self.program_id,
+                &realm_cookie.address,
+                &realm_cookie.community_mint,
+            ),
+            account,
+            max_voter_weight_record: get_max_voter_weight_record_address(
+                &self.program_id,
+                &realm_cookie.address,
+                &realm_cookie.community_mint,
+            ),
+        })
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_collection(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        weight: u64,
+        size: u32,
+    ) -> Result<Pubkey, ProgramError> {
+        let collection = Pubkey::new_unique();
+
+        self.configure_collection(
+            realm_cookie,
+            &realm_cookie.realm_authority,
+            &collection,
+            weight,
+            size,
+        )
+        .await?;
+
+        Ok(collection)
+    }
+
+    #[allow(dead_code)]
+    pub async fn configure_collection(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        realm_authority: &Keypair,
+        collection: &Pubkey,
+        weight: u64,
+        size: u32,
+    ) -> Result<(), ProgramError> {
+        let configure_collection_ix = configure_collection(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &realm_authority.pubkey(),
+            collection,
+            weight,
+            size,
+        );
+
+        self.bench
+            .process_transaction(&[configure_collection_ix], Some(&[realm_authority]))
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_voter(&mut self, realm_cookie: &RealmCookie) -> VoterCookie {
+        let governing_token_owner = Keypair::new();
+
+        let create_voter_weight_record_ix = create_voter_weight_record(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &self.bench.payer.pubkey(),
+            &governing_token_owner.pubkey(),
+        );
+
+        let create_token_owner_record_ix = create_token_owner_record(
+            &self.governance_program_id,
+            &realm_cookie.address,
+            &governing_token_owner.pubkey(),
+            &realm_cookie.community_mint,
+            &self.bench.payer.pubkey(),
+        );
+
+        self.bench
+            .process_transaction(
+                &[create_voter_weight_record_ix, create_token_owner_record_ix],
+                None,
+            )
+            .await
+            .unwrap();
+
+        VoterCookie {
+            voter_weight_record: get_voter_weight_record_address(
+                &self.program_id,
+                &realm_cookie.address,
+                &realm_cookie.community_mint,
+                &governing_token_owner.pubkey(),
+            ),
+            token_owner_record: get_token_owner_record_address(
+                &self.governance_program_id,
+                &realm_cookie.address,
+                &realm_cookie.community_mint,
+                &governing_token_owner.pubkey(),
+            ),
+            governing_token_owner,
+        }
+    }
+
+    /// Creates NFT owned by the voter together with its metadata account
+    /// The metadata account is set directly because the metadata program isn't deployed for the tests
+    #[allow(dead_code)]
+    pub async fn with_nft(
+        &mut self,
+        voter_cookie: &VoterCookie,
+        collection: Option<NftCollection>,
+    ) -> NftCookie {
+        let mint_keypair = Keypair::new();
+        let mint_authority = Keypair::new();
+
+        self.bench
+            .create_mint(&mint_keypair, &mint_authority.pubkey(), None)
+            .await;
+
+        let token_account_cookie = self
+            .bench
+            .with_token_account(
+                &mint_keypair.pubkey(),
+                &voter_cookie.governing_token_owner.pubkey(),
+                &mint_authority,
+                1,
+            )
+            .await;
+
+        let metadata = Metadata {
+            key: Key::MetadataV1,
+            update_authority: mint_authority.pubkey(),
+            mint: mint_keypair.pubkey(),
+            data: Data {
+                name: "NFT".to_string(),
+                symbol: "NFT".to_string(),
+                uri: "https://nft".to_string(),
+                seller_fee_basis_points: 0,
+                creators: None,
+            },
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+        };
+
+        // token_standard and collection follow the legacy metadata fields
+        let mut data = metadata.try_to_vec().unwrap();
+        data.extend((Some(0_u8), collection).try_to_vec().unwrap());
+
+        let metadata_address = get_nft_metadata_address(&mint_keypair.pubkey());
+
+        let metadata_account = Account {
+            lamports: self.bench.rent.minimum_balance(data.len()),
+            data,
+            owner: metaplex_token_metadata::id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+
+        self.bench
+            .context
+            .set_account(&metadata_address, &metadata_account.into());
+
+        NftCookie {
+            mint: mint_keypair.pubkey(),
+            token_account: token_account_cookie.address,
+            metadata: metadata_address,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_collection_nft(
+        &mut self,
+        voter_cookie: &VoterCookie,
+        collection: &Pubkey,
+    ) -> NftCookie {
+        self.with_nft(
+            voter_cookie,
+            Some(NftCollection {
+                verified: true,
+                key: *collection,
+            }),
+        )
+        .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn update_voter_weight_record(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        voter_cookie: &VoterCookie,
+        nft_cookies: &[&NftCookie],
+        voter_weight_action: VoterWeightAction,
+    ) -> Result<(), ProgramError> {
+        let update_voter_weight_record_ix = update_voter_weight_record(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &voter_cookie.governing_token_owner.pubkey(),
+            &get_nfts(nft_cookies),
+            voter_weight_action,
+        );
+
+        self.bench
+            .process_transaction(&[update_voter_weight_record_ix], None)
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn cast_nft_vote(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        voter_cookie: &VoterCookie,
+        nft_cookies: &[&NftCookie],
+        proposal: &Pubkey,
+    ) -> Result<(), ProgramError> {
+        let cast_nft_vote_ix = cast_nft_vote(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &voter_cookie.governing_token_owner.pubkey(),
+            &self.bench.payer.pubkey(),
+            &get_nfts(nft_cookies),
+            proposal,
+        );
+
+        self.bench
+            .process_transaction(
+                &[cast_nft_vote_ix],
+                Some(&[&voter_cookie.governing_token_owner]),
+            )
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn relinquish_nft_vote(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        voter_cookie: &VoterCookie,
+        nft_cookies: &[&NftCookie],
+        proposal: &Pubkey,
+    ) -> Result<(), ProgramError> {
+        let nft_mints: Vec<Pubkey> = nft_cookies.iter().map(|nft| nft.mint).collect();
+
+        let relinquish_nft_vote_ix = relinquish_nft_vote(
+            &self.program_id,
+            &self.governance_program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            proposal,
+            &voter_cookie.governing_token_owner.pubkey(),
+            &self.bench.payer.pubkey(),
+            &nft_mints,
+        );
+
+        self.bench
+            .process_transaction(
+                &[relinquish_nft_vote_ix],
+                Some(&[&voter_cookie.governing_token_owner]),
+            )
+            .await
+    }
+
+    /// Creates Governance and a Proposal in Voting state using the voter's NFTs weight
+    /// The VoterWeightRecord is updated in the same transaction because it expires at the current slot
+    #[allow(dead_code)]
+    pub async fn with_proposal(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        voter_cookie: &VoterCookie,
+        nft_cookies: &[&NftCookie],
+    ) -> Result<ProposalCookie, ProgramError> {
+        let governing_token_owner = voter_cookie.governing_token_owner.pubkey();
+        let nfts = get_nfts(nft_cookies);
+
+        let governance_config = GovernanceConfig {
+            min_community_weight_to_create_proposal: 1,
+            min_council_weight_to_create_proposal: 1,
+            community_veto_vote_threshold: VoteThreshold::Disabled,
+            min_transaction_hold_up_time: 10,
+            max_voting_time: 1000,
+            community_vote_threshold: VoteThreshold::YesVotePercentage(60),
+            vote_tipping: VoteTipping::Strict,
+            council_vote_threshold: VoteThreshold::YesVotePercentage(60),
+            council_veto_vote_threshold: VoteThreshold::Disabled,
+        };
+
+        let governed_account = Pubkey::new_unique();
+
+        let create_governance_ix = create_governance(
+            &self.governance_program_id,
+            &realm_cookie.address,
+            Some(&governed_account),
+            &voter_cookie.token_owner_record,
+            &self.bench.payer.pubkey(),
+            &governing_token_owner,
+            Some(voter_cookie.voter_weight_record),
+            governance_config,
+        );
+
+        self.bench
+            .process_transaction(
+                &[
+                    update_voter_weight_record(
+                        &self.program_id,
+                        &realm_cookie.address,
+                        &realm_cookie.community_mint,
+                        &governing_token_owner,
+                        &nfts,
+                        VoterWeightAction::CreateGovernance,
+                    ),
+                    create_governance_ix,
+                ],
+                Some(&[&voter_cookie.governing_token_owner]),
+            )
+            .await?;
+
+        let governance_address = get_governance_address(
+            &self.governance_program_id,
+            &realm_cookie.address,
+            &governed_account,
+        );
+
+        let create_proposal_ix = create_proposal(
+            &self.governance_program_id,
+            &governance_address,
+            &voter_cookie.token_owner_record,
+            &governing_token_owner,
+            &self.bench.payer.pubkey(),
+            Some(voter_cookie.voter_weight_record),
+            &realm_cookie.address,
+            "Proposal".to_string(),
+            "https://proposal".to_string(),
+            &realm_cookie.community_mint,
+            VoteType::SingleChoice,
+            vec!["Yes".to_string()],
+            true,
+            0,
+        );
+
+        let proposal_address = get_proposal_address(
+            &self.governance_program_id,
+            &governance_address,
+            &realm_cookie.community_mint,
+            &0_u32.to_le_bytes(),
+        );
+
+        let sign_off_proposal_ix = sign_off_proposal(
+            &self.governance_program_id,
+            &realm_cookie.address,
+            &governance_address,
+            &proposal_address,
+            &governing_token_owner,
+            Some(&voter_cookie.token_owner_record),
+        );
+
+        self.bench
+            .process_transaction(
+                &[
+                    update_voter_weight_record(
+                        &self.program_id,
+                        &realm_cookie.address,
+                        &realm_cookie.community_mint,
+                        &governing_token_owner,
+                        &nfts,
+                        VoterWeightAction::CreateProposal,
+                    ),
+                    create_proposal_ix,
+                    sign_off_proposal_ix,
+                ],
+                Some(&[&voter_cookie.governing_token_owner]),
+            )
+            .await?;
+
+        Ok(ProposalCookie {
+            governance: governance_address,
+            address: proposal_address,
+        })
+    }
+
+    /// Casts vote in governance using the voter's NFTs weight
+    #[allow(dead_code)]
+    pub async fn cast_vote(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        registrar_cookie: &RegistrarCookie,
+        voter_cookie: &VoterCookie,
+        nft_cookies: &[&NftCookie],
+        proposal_cookie: &ProposalCookie,
+    ) -> Result<(), ProgramError> {
+        let governing_token_owner = voter_cookie.governing_token_owner.pubkey();
+
+        let cast_nft_vote_ix = cast_nft_vote(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &governing_token_owner,
+            &self.bench.payer.pubkey(),
+            &get_nfts(nft_cookies),
+            &proposal_cookie.address,
+        );
+
+        let cast_vote_ix = cast_vote(
+            &self.governance_program_id,
+            &realm_cookie.address,
+            &proposal_cookie.governance,
+            &proposal_cookie.address,
+            &voter_cookie.token_owner_record,
+            &voter_cookie.token_owner_record,
+            &governing_token_owner,
+            &realm_cookie.community_mint,
+            &self.bench.payer.pubkey(),
+            Some(voter_cookie.voter_weight_record),
+            Some(registrar_cookie.max_voter_weight_record),
+            Vote::Approve(vec![VoteChoice {
+                rank: 0,
+                weight_percentage: 100,
+            }]),
+        );
+
+        self.bench
+            .process_transaction(
+                &[cast_nft_vote_ix, cast_vote_ix],
+                Some(&[&voter_cookie.governing_token_owner]),
+            )
+            .await
+    }
+
+    /// Withdraws the vote in governance
+    #[allow(dead_code)]
+    pub async fn relinquish_vote(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        voter_cookie: &VoterCookie,
+        proposal_cookie: &ProposalCookie,
+    ) -> Result<(), ProgramError> {
+        let relinquish_vote_ix = relinquish_vote(
+            &self.governance_program_id,
+            &realm_cookie.address,
+            &proposal_cookie.governance,
+            &proposal_cookie.address,
+            &voter_cookie.token_owner_record,
+            &realm_cookie.community_mint,
+            Some(voter_cookie.governing_token_owner.pubkey()),
+            Some(self.bench.payer.pubkey()),
+        );
+
+        self.bench
+            .process_transaction(
+                &[relinquish_vote_ix],
+                Some(&[&voter_cookie.governing_token_owner]),
+            )
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_registrar_account(&mut self, address: &Pubkey) -> Registrar {
+        self.bench.get_borsh_account::<Registrar>(address).await
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_nft_vote_record_account(&mut self, address: &Pubkey) -> NftVoteRecord {
+        self.bench.get_borsh_account::<NftVoteRecord>(address).await
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_voter_weight_record(&mut self, address: &Pubkey) -> VoterWeightRecord {
+        self.bench
+            .get_borsh_account::<VoterWeightRecord>(address)
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_max_voter_weight_record(&mut self, address: &Pubkey) -> MaxVoterWeightRecord {
+        self.bench
+            .get_borsh_account::<MaxVoterWeightRecord>(address)
+            .await
+    }
+}
+
+/// Returns (NFT token account, NFT mint) pairs for the given NFTs
+fn get_nfts(nft_cookies: &[&NftCookie]) -> Vec<(Pubkey, Pubkey)> {
+    nft_cookies
+        .iter()
+        .map(|nft| (nft.token_account, nft.mint))
+        .collect()
+}
@@ -0,0 +1,111 @@
+//! Error types
+
+use num_derive::FromPrimitive;
+use solana_program::{
+    decode_error::DecodeError,
+    msg,
+    program_error::{PrintProgramError, ProgramError},
+};
+use thiserror::Error;
+
+/// Errors that may be returned by the TokenLockupAddin program
+#[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
+pub enum TokenLockupAddinError {
+    /// Invalid Realm for Registrar
+    #[error("Invalid Realm for Registrar")]
+    InvalidRealmForRegistrar = 1200,
+
+    /// Realm authority must sign
+    #[error("Realm authority must sign")]
+    RealmAuthorityMustSign, // 1201
+
+    /// Only the Realm community mint is supported
+    #[error("Only the Realm community mint is supported")]
+    InvalidGoverningTokenMint,
+
+    /// Invalid lockup saturation period
+    #[error("Invalid lockup saturation period")]
+    InvalidLockupSaturationPeriod,
+
+    /// Invalid Registrar for Voter
+    #[error("Invalid Registrar for Voter")]
+    InvalidRegistrarForVoter,
+
+    /// Invalid Voter authority
+    #[error("Invalid Voter authority")]
+    InvalidVoterAuthority,
+
+    /// Voter authority must sign
+    #[error("Voter authority must sign")]
+    VoterAuthorityMustSign,
+
+    /// Invalid deposit amount
+    #[error("Invalid deposit amount")]
+    InvalidDepositAmount,
+
+    /// Invalid lockup period
+    #[error("Invalid lockup period")]
+    InvalidLockupPeriod,
+
+    /// Max number of deposits reached
+    #[error("Max number of deposits reached")]
+    MaxDepositsReached,
+
+    /// Invalid deposit index
+    #[error("Invalid deposit index")]
+    InvalidDepositIndex,
+
+    /// Withdraw amount exceeds unlocked amount
+    #[error("Withdraw amount exceeds unlocked amount")]
+    InsufficientUnlockedTokens,
+
+    /// Cannot withdraw tokens while Voter has unrelinquished votes
+    #[error("Cannot withdraw tokens while Voter has unrelinquished votes")]
+    CannotWithdrawWithActiveVotes,
+
+    /// Invalid TokenOwnerRecord for Voter
+    #[error("Invalid TokenOwnerRecord for Voter")]
+    InvalidTokenOwnerRecordForVoter,
+
+    /// Clawback not allowed for deposit
+    #[error("Clawback not allowed for deposit")]
+    ClawbackNotAllowed,
+
+    /// Grant must be locked
+    #[error("Grant must be locked")]
+    GrantMustBeLocked,
+
+    /// Invalid vault for Registrar
+    #[error("Invalid vault for Registrar")]
+    InvalidVaultForRegistrar,
+
+    /// Invalid VoterWeightRecord for Voter
+    #[error("Invalid VoterWeightRecord for Voter")]
+    InvalidVoterWeightRecordForVoter,
+
+    /// Invalid MaxVoterWeightRecord for Registrar
+    #[error("Invalid MaxVoterWeightRecord for Registrar")]
+    InvalidMaxVoterWeightRecordForRegistrar,
+
+    /// Voter weight overflow
+    #[error("Voter weight overflow")]
+    VoterWeightOverflow,
+}
+
+impl PrintProgramError for TokenLockupAddinError {
+    fn print<E>(&self) {
+        msg!("GOVERNANCE-ADDIN-TOKEN-LOCKUP-ERROR: {}", &self.to_string());
+    }
+}
+
+impl From<TokenLockupAddinError> for ProgramError {
+    fn from(e: TokenLockupAddinError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+impl<T> DecodeError<T> for TokenLockupAddinError {
+    fn type_of() -> &'static str {
+        "Governance Addin Token Lockup Error"
+    }
+}
@@ -0,0 +1,447 @@
+//! Program instructions
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use spl_governance::state::token_owner_record::get_token_owner_record_address;
+
+use crate::state::{
+    enums::LockupKind,
+    registrar::{
+        get_max_voter_weight_record_address, get_registrar_address, get_vault_address,
+        RegistrarConfig,
+    },
+    voter::{get_voter_address, get_voter_weight_record_address},
+};
+
+/// Instructions supported by the TokenLockup addin program
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub enum TokenLockupAddinInstruction {
+    /// Creates Registrar for the Realm community mint together with its vault and MaxVoterWeightRecord
+    ///
+    ///   0. `[writable]` Registrar account. PDA seeds: ['registrar', realm, governing_token_mint]
+    ///   1. `[writable]` Vault token account. PDA seeds: ['vault', registrar]
+    ///   2. `[writable]` MaxVoterWeightRecord account. PDA seeds: ['max-voter-weight-record', realm, governing_token_mint]
+    ///   3. `[]` Governance program id
+    ///   4. `[]` Realm account
+    ///   5. `[]` Governing Token Mint (Realm community mint)
+    ///   6. `[signer]` Realm authority
+    ///   7. `[signer]` Payer
+    ///   8. `[]` System
+    ///   9. `[]` SPL Token program
+    ///   10. `[]` Sysvar Rent
+    CreateRegistrar {
+        /// Registrar config
+        #[allow(dead_code)]
+        config: RegistrarConfig,
+    },
+
+    /// Creates Voter account and its VoterWeightRecord
+    ///
+    ///   0. `[]` Registrar account
+    ///   1. `[writable]` Voter account. PDA seeds: ['voter', registrar, voter_authority]
+    ///   2. `[writable]` VoterWeightRecord account. PDA seeds: ['voter-weight-record', realm, governing_token_mint, voter_authority]
+    ///   3. `[signer]` Voter authority
+    ///   4. `[signer]` Payer
+    ///   5. `[]` System
+    CreateVoter {},
+
+    /// Deposits governing tokens with the given lockup
+    /// Tokens deposited without lockup are added to the existing deposit without lockup if there is one
+    ///
+    ///   0. `[]` Registrar account
+    ///   1. `[writable]` Voter account
+    ///   2. `[signer]` Voter authority
+    ///   3. `[writable]` Vault token account
+    ///   4. `[writable]` Source token account
+    ///   5. `[signer]` Source token account authority
+    ///   6. `[]` SPL Token program
+    Deposit {
+        /// The amount to deposit
+        #[allow(dead_code)]
+        amount: u64,
+
+        /// The kind of the lockup
+        #[allow(dead_code)]
+        lockup_kind: LockupKind,
+
+        /// The lockup period in seconds, it must be 0 for LockupKind::None
+        #[allow(dead_code)]
+        lockup_period_secs: u64,
+    },
+
+    /// Withdraws unlocked tokens from the given deposit
+    /// Tokens can't be withdrawn while the Voter has unrelinquished votes in the Realm
+    ///
+    ///   0. `[]` Registrar account
+    ///   1. `[writable]` Voter account
+    ///   2. `[signer]` Voter authority
+    ///   3. `[]` TokenOwnerRecord of the Voter authority for the Realm and governing token mint
+    ///   4. `[writable]` Vault token account
+    ///   5. `[writable]` Destination token account
+    ///   6. `[]` SPL Token program
+    Withdraw {
+        /// The index of the deposit to withdraw from
+        #[allow(dead_code)]
+        deposit_index: u8,
+
+        /// The amount to withdraw
+        #[allow(dead_code)]
+        amount: u64,
+    },
+
+    /// Grants locked tokens to the Voter
+    /// The Realm authority can claw back the granted tokens as long as they are locked
+    ///
+    ///   0. `[]` Registrar account
+    ///   1. `[]` Realm account
+    ///   2. `[signer]` Realm authority
+    ///   3. `[writable]` Voter account
+    ///   4. `[writable]` Vault token account
+    ///   5. `[writable]` Source token account
+    ///   6. `[signer]` Source token account authority
+    ///   7. `[]` SPL Token program
+    Grant {
+        /// The amount to grant
+        #[allow(dead_code)]
+        amount: u64,
+
+        /// The kind of the lockup, it must be either Cliff or Linear
+        #[allow(dead_code)]
+        lockup_kind: LockupKind,
+
+        /// The lockup period in seconds
+        #[allow(dead_code)]
+        lockup_period_secs: u64,
+    },
+
+    /// Claws back the tokens which are still locked in the granted deposit
+    /// The tokens which have already been unlocked stay with the Voter and the deposit's lockup ends
+    ///
+    ///   0. `[]` Registrar account
+    ///   1. `[]` Realm account
+    ///   2. `[signer]` Realm authority
+    ///   3. `[writable]` Voter account
+    ///   4. `[writable]` Vault token account
+    ///   5. `[writable]` Destination token account
+    ///   6. `[]` SPL Token program
+    Clawback {
+        /// The index of the deposit to claw back
+        #[allow(dead_code)]
+        deposit_index: u8,
+    },
+
+    /// Updates VoterWeightRecord with the current Voter's weight
+    /// The weight decays with time and the instruction must be executed before the governance instruction within the same transaction
+    ///
+    ///   0. `[]` Registrar account
+    ///   1. `[]` Voter account
+    ///   2. `[writable]` VoterWeightRecord account
+    UpdateVoterWeightRecord {},
+
+    /// Updates MaxVoterWeightRecord using the current governing token mint supply
+    /// The instruction must be executed before the governance instruction within the same transaction
+    ///
+    ///   0. `[]` Registrar account
+    ///   1. `[writable]` MaxVoterWeightRecord account
+    ///   2. `[]` Governing Token Mint
+    UpdateMaxVoterWeightRecord {},
+}
+
+/// Creates CreateRegistrar instruction
+#[allow(clippy::too_many_arguments)]
+pub fn create_registrar(
+    program_id: &Pubkey,
+    // Accounts
+    governance_program_id: &Pubkey,
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    realm_authority: &Pubkey,
+    payer: &Pubkey,
+    // Args
+    config: RegistrarConfig,
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let vault_address = get_vault_address(program_id, &registrar_address);
+    let max_voter_weight_record_address =
+        get_max_voter_weight_record_address(program_id, realm, governing_token_mint);
+
+    let accounts = vec![
+        AccountMeta::new(registrar_address, false),
+        AccountMeta::new(vault_address, false),
+        AccountMeta::new(max_voter_weight_record_address, false),
+        AccountMeta::new_readonly(*governance_program_id, false),
+        AccountMeta::new_readonly(*realm, false),
+        AccountMeta::new_readonly(*governing_token_mint, false),
+        AccountMeta::new_readonly(*realm_authority, true),
+        AccountMeta::new_readonly(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    ];
+
+    let instruction = TokenLockupAddinInstruction::CreateRegistrar { config };
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates CreateVoter instruction
+pub fn create_voter(
+    program_id: &Pubkey,
+    // Accounts
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    voter_authority: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let voter_address = get_voter_address(program_id, &registrar_address, voter_authority);
+    let voter_weight_record_address =
+        get_voter_weight_record_address(program_id, realm, governing_token_mint, voter_authority);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(registrar_address, false),
+        AccountMeta::new(voter_address, false),
+        AccountMeta::new(voter_weight_record_address, false),
+        AccountMeta::new_readonly(*voter_authority, true),
+        AccountMeta::new_readonly(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let instruction = TokenLockupAddinInstruction::CreateVoter {};
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates Deposit instruction
+#[allow(clippy::too_many_arguments)]
+pub fn deposit(
+    program_id: &Pubkey,
+    // Accounts
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    voter_authority: &Pubkey,
+    source_token_account: &Pubkey,
+    source_token_authority: &Pubkey,
+    // Args
+    amount: u64,
+    lockup_kind: LockupKind,
+    lockup_period_secs: u64,
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let voter_address = get_voter_address(program_id, &registrar_address, voter_authority);
+    let vault_address = get_vault_address(program_id, &registrar_address);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(registrar_address, false),
+        AccountMeta::new(voter_address, false),
+        AccountMeta::new_readonly(*voter_authority, true),
+        AccountMeta::new(vault_address, false),
+        AccountMeta::new(*source_token_account, false),
+        AccountMeta::new_readonly(*source_token_authority, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    let instruction = TokenLockupAddinInstruction::Deposit {
+        amount,
+        lockup_kind,
+        lockup_period_secs,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates Withdraw instruction
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw(
+    program_id: &Pubkey,
+    // Accounts
+    governance_program_id: &Pubkey,
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    voter_authority: &Pubkey,
+    destination_token_account: &Pubkey,
+    // Args
+    deposit_index: u8,
+    amount: u64,
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let voter_address = get_voter_address(program_id, &registrar_address, voter_authority);
+    let vault_address = get_vault_address(program_id, &registrar_address);
+    let token_owner_record_address = get_token_owner_record_address(
+        governance_program_id,
+        realm,
+        governing_token_mint,
+        voter_authority,
+    );
+
+    let accounts = vec![
+        AccountMeta::new_readonly(registrar_address, false),
+        AccountMeta::new(voter_address, false),
+        AccountMeta::new_readonly(*voter_authority, true),
+        AccountMeta::new_readonly(token_owner_record_address, false),
+        AccountMeta::new(vault_address, false),
+        AccountMeta::new(*destination_token_account, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    let instruction = TokenLockupAddinInstruction::Withdraw {
+        deposit_index,
+        amount,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates Grant instruction
+#[allow(clippy::too_many_arguments)]
+pub fn grant(
+    program_id: &Pubkey,
+    // Accounts
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    realm_authority: &Pubkey,
+    voter_authority: &Pubkey,
+    source_token_account: &Pubkey,
+    source_token_authority: &Pubkey,
+    // Args
+    amount: u64,
+    lockup_kind: LockupKind,
+    lockup_period_secs: u64,
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let voter_address = get_voter_address(program_id, &registrar_address, voter_authority);
+    let vault_address = get_vault_address(program_id, &registrar_address);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(registrar_address, false),
+        AccountMeta::new_readonly(*realm, false),
+        AccountMeta::new_readonly(*realm_authority, true),
+        AccountMeta::new(voter_address, false),
+        AccountMeta::new(vault_address, false),
+        AccountMeta::new(*source_token_account, false),
+        AccountMeta::new_readonly(*source_token_authority, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    let instruction = TokenLockupAddinInstruction::Grant {
+        amount,
+        lockup_kind,
+        lockup_period_secs,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates Clawback instruction
+#[allow(clippy::too_many_arguments)]
+pub fn clawback(
+    program_id: &Pubkey,
+    // Accounts
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    realm_authority: &Pubkey,
+    voter_authority: &Pubkey,
+    destination_token_account: &Pubkey,
+    // Args
+    deposit_index: u8,
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let voter_address = get_voter_address(program_id, &registrar_address, voter_authority);
+    let vault_address = get_vault_address(program_id, &registrar_address);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(registrar_address, false),
+        AccountMeta::new_readonly(*realm, false),
+        AccountMeta::new_readonly(*realm_authority, true),
+        AccountMeta::new(voter_address, false),
+        AccountMeta::new(vault_address, false),
+        AccountMeta::new(*destination_token_account, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    let instruction = TokenLockupAddinInstruction::Clawback { deposit_index };
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates UpdateVoterWeightRecord instruction
+pub fn update_voter_weight_record(
+    program_id: &Pubkey,
+    // Accounts
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    voter_authority: &Pubkey,
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let voter_address = get_voter_address(program_id, &registrar_address, voter_authority);
+    let voter_weight_record_address =
+        get_voter_weight_record_address(program_id, realm, governing_token_mint, voter_authority);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(registrar_address, false),
+        AccountMeta::new_readonly(voter_address, false),
+        AccountMeta::new(voter_weight_record_address, false),
+    ];
+
+    let instruction = TokenLockupAddinInstruction::UpdateVoterWeightRecord {};
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates UpdateMaxVoterWeightRecord instruction
+pub fn update_max_voter_weight_record(
+    program_id: &Pubkey,
+    // Accounts
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+) -> Instruction {
+    let registrar_address = get_registrar_address(program_id, realm, governing_token_mint);
+    let max_voter_weight_record_address =
+        get_max_voter_weight_record_address(program_id, realm, governing_token_mint);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(registrar_address, false),
+        AccountMeta::new(max_voter_weight_record_address, false),
+        AccountMeta::new_readonly(*governing_token_mint, false),
+    ];
+
+    let instruction = TokenLockupAddinInstruction::UpdateMaxVoterWeightRecord {};
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
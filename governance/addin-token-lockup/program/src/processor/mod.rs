@@ -0,0 +1,86 @@
+//! Program processor
+
+mod process_clawback;
+mod process_create_registrar;
+mod process_create_voter;
+mod process_deposit;
+mod process_grant;
+mod process_update_max_voter_weight_record;
+mod process_update_voter_weight_record;
+mod process_withdraw;
+
+use crate::instruction::TokenLockupAddinInstruction;
+
+use process_clawback::*;
+use process_create_registrar::*;
+use process_create_voter::*;
+use process_deposit::*;
+use process_grant::*;
+use process_update_max_voter_weight_record::*;
+use process_update_voter_weight_record::*;
+use process_withdraw::*;
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Processes an instruction
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    msg!("VERSION:{:?}", env!("CARGO_PKG_VERSION"));
+
+    let instruction = TokenLockupAddinInstruction::try_from_slice(input)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    msg!(
+        "GOVERNANCE-ADDIN-TOKEN-LOCKUP-INSTRUCTION: {:?}",
+        instruction
+    );
+
+    match instruction {
+        TokenLockupAddinInstruction::CreateRegistrar { config } => {
+            process_create_registrar(program_id, accounts, config)
+        }
+        TokenLockupAddinInstruction::CreateVoter {} => process_create_voter(program_id, accounts),
+        TokenLockupAddinInstruction::Deposit {
+            amount,
+            lockup_kind,
+            lockup_period_secs,
+        } => process_deposit(
+            program_id,
+            accounts,
+            amount,
+            lockup_kind,
+            lockup_period_secs,
+        ),
+        TokenLockupAddinInstruction::Withdraw {
+            deposit_index,
+            amount,
+        } => process_withdraw(program_id, accounts, deposit_index, amount),
+        TokenLockupAddinInstruction::Grant {
+            amount,
+            lockup_kind,
+            lockup_period_secs,
+        } => process_grant(
+            program_id,
+            accounts,
+            amount,
+            lockup_kind,
+            lockup_period_secs,
+        ),
+        TokenLockupAddinInstruction::Clawback { deposit_index } => {
+            process_clawback(program_id, accounts, deposit_index)
+        }
+        TokenLockupAddinInstruction::UpdateVoterWeightRecord {} => {
+            process_update_voter_weight_record(program_id, accounts)
+        }
+        TokenLockupAddinInstruction::UpdateMaxVoterWeightRecord {} => {
+            process_update_max_voter_weight_record(program_id, accounts)
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Program state processor
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_governance::tools::spl_token::transfer_spl_tokens_signed;
+
+use crate::{
+    error::TokenLockupAddinError,
+    state::{
+        deposit_entry::Lockup,
+        enums::LockupKind,
+        registrar::{get_registrar_address_seeds, get_registrar_data, Registrar},
+        voter::get_voter_data_for_registrar,
+    },
+};
+
+/// Processes Clawback instruction
+pub fn process_clawback(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_index: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let registrar_info = next_account_info(account_info_iter)?; // 0
+    let realm_info = next_account_info(account_info_iter)?; // 1
+    let realm_authority_info = next_account_info(account_info_iter)?; // 2
+    let voter_info = next_account_info(account_info_iter)?; // 3
+    let vault_info = next_account_info(account_info_iter)?; // 4
+    let destination_token_info = next_account_info(account_info_iter)?; // 5
+    let spl_token_info = next_account_info(account_info_iter)?; // 6
+
+    let registrar_data = get_registrar_data(program_id, registrar_info)?;
+    registrar_data.assert_realm_authority_is_signer(realm_info, realm_authority_info)?;
+
+    let mut voter_data = get_voter_data_for_registrar(program_id, voter_info, registrar_info.key)?;
+
+    Registrar::assert_is_valid_vault(program_id, registrar_info.key, vault_info)?;
+
+    let current_ts = Clock::get()?.unix_timestamp;
+    let deposit = voter_data.get_deposit_mut(deposit_index)?;
+
+    if !deposit.allow_clawback {
+        return Err(TokenLockupAddinError::ClawbackNotAllowed.into());
+    }
+
+    let amount_locked = deposit.get_amount_locked(current_ts);
+
+    // The vested tokens stay with the Voter without any lockup
+    deposit.amount_deposited -= amount_locked;
+    deposit.amount_initially_locked = 0;
+    deposit.lockup = Lockup::new(LockupKind::None, current_ts, 0)?;
+    deposit.allow_clawback = false;
+
+    voter_data.remove_deposit_if_empty(deposit_index);
+
+    transfer_spl_tokens_signed(
+        vault_info,
+        destination_token_info,
+        registrar_info,
+        &get_registrar_address_seeds(&registrar_data.realm, &registrar_data.governing_token_mint),
+        program_id,
+        amount_locked,
+        spl_token_info,
+    )?;
+
+    voter_data.serialize(&mut *voter_info.data.borrow_mut())?;
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! Program state processor
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+use spl_governance::{
+    state::realm::get_realm_data_for_authority,
+    tools::spl_token::{create_spl_token_account_signed, get_spl_token_mint_supply},
+};
+use spl_governance_addin_api::max_voter_weight::MaxVoterWeightRecord;
+use spl_governance_tools::account::create_and_serialize_account_signed;
+
+use crate::{
+    error::TokenLockupAddinError,
+    state::{
+        enums::TokenLockupAccountType,
+        registrar::{
+            assert_is_valid_registrar_config, get_max_voter_weight_record_address_seeds,
+            get_registrar_address_seeds, get_vault_address_seeds, Registrar, RegistrarConfig,
+        },
+    },
+};
+
+/// Processes CreateRegistrar instruction
+pub fn process_create_registrar(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: RegistrarConfig,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let registrar_info = next_account_info(account_info_iter)?; // 0
+    let vault_info = next_account_info(account_info_iter)?; // 1
+    let max_voter_weight_record_info = next_account_info(account_info_iter)?; // 2
+    let governance_program_info = next_account_info(account_info_iter)?; // 3
+    let realm_info = next_account_info(account_info_iter)?; // 4
+    let governing_token_mint_info = next_account_info(account_info_iter)?; // 5
+    let realm_authority_info = next_account_info(account_info_iter)?; // 6
+    let payer_info = next_account_info(account_info_iter)?; // 7
+    let system_info = next_account_info(account_info_iter)?; // 8
+    let spl_token_info = next_account_info(account_info_iter)?; // 9
+    let rent_sysvar_info = next_account_info(account_info_iter)?; // 10
+    let rent = &Rent::from_account_info(rent_sysvar_info)?;
+
+    assert_is_valid_registrar_config(&config)?;
+
+    let realm_data = get_realm_data_for_authority(
+        governance_program_info.key,
+        realm_info,
+        realm_authority_info.key,
+    )?;
+
+    if !realm_authority_info.is_signer {
+        return Err(TokenLockupAddinError::RealmAuthorityMustSign.into());
+    }
+
+    // The addin can only be configured as the community voter weight addin for the Realm
+    if realm_data.community_mint != *governing_token_mint_info.key {
+        return Err(TokenLockupAddinError::InvalidGoverningTokenMint.into());
+    }
+
+    let registrar_data = Registrar {
+        account_type: TokenLockupAccountType::Registrar,
+        governance_program_id: *governance_program_info.key,
+        realm: *realm_info.key,
+        governing_token_mint: *governing_token_mint_info.key,
+        config,
+        reserved: [0; 64],
+    };
+
+    create_and_serialize_account_signed(
+        payer_info,
+        registrar_info,
+        &registrar_data,
+        &get_registrar_address_seeds(realm_info.key, governing_token_mint_info.key),
+        program_id,
+        system_info,
+        rent,
+    )?;
+
+    create_spl_token_account_signed(
+        payer_info,
+        vault_info,
+        &get_vault_address_seeds(registrar_info.key),
+        governing_token_mint_info,
+        registrar_info,
+        program_id,
+        system_info,
+        spl_token_info,
+        rent_sysvar_info,
+        rent,
+    )?;
+
+    let max_voter_weight_record_data = MaxVoterWeightRecord {
+        account_discriminator: MaxVoterWeightRecord::ACCOUNT_DISCRIMINATOR,
+        realm: *realm_info.key,
+        governing_token_mint: *governing_token_mint_info.key,
+        max_voter_weight: registrar_data
+            .get_max_voter_weight(get_spl_token_mint_supply(governing_token_mint_info)?)?,
+        max_voter_weight_expiry: Some(Clock::get()?.slot),
+        reserved: [0; 8],
+    };
+
+    create_and_serialize_account_signed(
+        payer_info,
+        max_voter_weight_record_info,
+        &max_voter_weight_record_data,
+        &get_max_voter_weight_record_address_seeds(realm_info.key, governing_token_mint_info.key),
+        program_id,
+        system_info,
+        rent,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Program state processor
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+use spl_governance_addin_api::voter_weight::VoterWeightRecord;
+use spl_governance_tools::account::create_and_serialize_account_signed;
+
+use crate::{
+    error::TokenLockupAddinError,
+    state::{
+        enums::TokenLockupAccountType,
+        registrar::get_registrar_data,
+        voter::{get_voter_address_seeds, get_voter_weight_record_address_seeds, Voter},
+    },
+};
+
+/// Processes CreateVoter instruction
+pub fn process_create_voter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let registrar_info = next_account_info(account_info_iter)?; // 0
+    let voter_info = next_account_info(account_info_iter)?; // 1
+    let voter_weight_record_info = next_account_info(account_info_iter)?; // 2
+    let voter_authority_info = next_account_info(account_info_iter)?; // 3
+    let payer_info = next_account_info(account_info_iter)?; // 4
+    let system_info = next_account_info(account_info_iter)?; // 5
+    let rent = &Rent::get()?;
+
+    let registrar_data = get_registrar_data(program_id, registrar_info)?;
+
+    if !voter_authority_info.is_signer {
+        return Err(TokenLockupAddinError::VoterAuthorityMustSign.into());
+    }
+
+    let voter_data = Voter {
+        account_type: TokenLockupAccountType::Voter,
+        registrar: *registrar_info.key,
+        voter_authority: *voter_authority_info.key,
+        deposits: vec![],
+    };
+
+    create_and_serialize_account_signed(
+        payer_info,
+        voter_info,
+        &voter_data,
+        &get_voter_address_seeds(registrar_info.key, voter_authority_info.key),
+        program_id,
+        system_info,
+        rent,
+    )?;
+
+    let voter_weight_record_data = VoterWeightRecord {
+        account_discriminator: VoterWeightRecord::ACCOUNT_DISCRIMINATOR,
+        realm: registrar_data.realm,
+        governing_token_mint: registrar_data.governing_token_mint,
+        governing_token_owner: *voter_authority_info.key,
+        voter_weight: 0,
+        voter_weight_expiry: Some(Clock::get()?.slot),
+        weight_action: None,
+        weight_action_target: None,
+        reserved: [0; 8],
+    };
+
+    create_and_serialize_account_signed(
+        payer_info,
+        voter_weight_record_info,
+        &voter_weight_record_data,
+        &get_voter_weight_record_address_seeds(
+            &registrar_data.realm,
+            &registrar_data.governing_token_mint,
+            voter_authority_info.key,
+        ),
+        program_id,
+        system_info,
+        rent,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//! Program state processor
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_governance::tools::spl_token::transfer_spl_tokens;
+
+use crate::{
+    error::TokenLockupAddinError,
+    state::{
+        deposit_entry::{DepositEntry, Lockup},
+        enums::LockupKind,
+        registrar::Registrar,
+        voter::get_voter_data_for_registrar,
+    },
+};
+
+/// Processes Deposit instruction
+pub fn process_deposit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    lockup_kind: LockupKind,
+    lockup_period_secs: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let registrar_info = next_account_info(account_info_iter)?; // 0
+    let voter_info = next_account_info(account_info_iter)?; // 1
+    let voter_authority_info = next_account_info(account_info_iter)?; // 2
+    let vault_info = next_account_info(account_info_iter)?; // 3
+    let source_token_info = next_account_info(account_info_iter)?; // 4
+    let source_token_authority_info = next_account_info(account_info_iter)?; // 5
+    let spl_token_info = next_account_info(account_info_iter)?; // 6
+
+    let mut voter_data = get_voter_data_for_registrar(program_id, voter_info, registrar_info.key)?;
+    voter_data.assert_voter_authority_is_signer(voter_authority_info)?;
+
+    Registrar::assert_is_valid_vault(program_id, registrar_info.key, vault_info)?;
+
+    if amount == 0 {
+        return Err(TokenLockupAddinError::InvalidDepositAmount.into());
+    }
+
+    let lockup = Lockup::new(
+        lockup_kind,
+        Clock::get()?.unix_timestamp,
+        lockup_period_secs,
+    )?;
+
+    // Top up the existing deposit without lockup to not use up the deposit slots
+    if let Some(unlocked_deposit) = voter_data
+        .deposits
+        .iter_mut()
+        .find(|deposit| lockup.kind == LockupKind::None && deposit.lockup.kind == LockupKind::None)
+    {
+        unlocked_deposit.amount_deposited = unlocked_deposit
+            .amount_deposited
+            .checked_add(amount)
+            .unwrap();
+    } else {
+        let amount_initially_locked = if lockup.kind == LockupKind::None {
+            0
+        } else {
+            amount
+        };
+
+        voter_data.add_deposit(DepositEntry {
+            lockup,
+            amount_deposited: amount,
+            amount_initially_locked,
+            allow_clawback: false,
+        })?;
+    }
+
+    transfer_spl_tokens(
+        source_token_info,
+        vault_info,
+        source_token_authority_info,
+        amount,
+        spl_token_info,
+    )?;
+
+    voter_data.serialize(&mut *voter_info.data.borrow_mut())?;
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! Program state processor
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_governance::tools::spl_token::transfer_spl_tokens;
+
+use crate::{
+    error::TokenLockupAddinError,
+    state::{
+        deposit_entry::{DepositEntry, Lockup},
+        enums::LockupKind,
+        registrar::{get_registrar_data, Registrar},
+        voter::get_voter_data_for_registrar,
+    },
+};
+
+/// Processes Grant instruction
+pub fn process_grant(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    lockup_kind: LockupKind,
+    lockup_period_secs: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let registrar_info = next_account_info(account_info_iter)?; // 0
+    let realm_info = next_account_info(account_info_iter)?; // 1
+    let realm_authority_info = next_account_info(account_info_iter)?; // 2
+    let voter_info = next_account_info(account_info_iter)?; // 3
+    let vault_info = next_account_info(account_info_iter)?; // 4
+    let source_token_info = next_account_info(account_info_iter)?; // 5
+    let source_token_authority_info = next_account_info(account_info_iter)?; // 6
+    let spl_token_info = next_account_info(account_info_iter)?; // 7
+
+    let registrar_data = get_registrar_data(program_id, registrar_info)?;
+    registrar_data.assert_realm_authority_is_signer(realm_info, realm_authority_info)?;
+
+    let mut voter_data = get_voter_data_for_registrar(program_id, voter_info, registrar_info.key)?;
+
+    Registrar::assert_is_valid_vault(program_id, registrar_info.key, vault_info)?;
+
+    if amount == 0 {
+        return Err(TokenLockupAddinError::InvalidDepositAmount.into());
+    }
+
+    // Grants without lockup can't be clawed back and should be plain token transfers instead
+    if lockup_kind == LockupKind::None {
+        return Err(TokenLockupAddinError::GrantMustBeLocked.into());
+    }
+
+    let lockup = Lockup::new(
+        lockup_kind,
+        Clock::get()?.unix_timestamp,
+        lockup_period_secs,
+    )?;
+
+    voter_data.add_deposit(DepositEntry {
+        lockup,
+        amount_deposited: amount,
+        amount_initially_locked: amount,
+        allow_clawback: true,
+    })?;
+
+    transfer_spl_tokens(
+        source_token_info,
+        vault_info,
+        source_token_authority_info,
+        amount,
+        spl_token_info,
+    )?;
+
+    voter_data.serialize(&mut *voter_info.data.borrow_mut())?;
+
+    Ok(())
+}
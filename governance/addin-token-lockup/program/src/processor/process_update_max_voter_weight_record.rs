@@ -0,0 +1,61 @@
+//! Program state processor
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_governance::tools::spl_token::get_spl_token_mint_supply;
+use spl_governance_addin_api::max_voter_weight::MaxVoterWeightRecord;
+use spl_governance_tools::account::get_account_data;
+
+use crate::{
+    error::TokenLockupAddinError,
+    state::registrar::{get_max_voter_weight_record_address, get_registrar_data},
+};
+
+/// Processes UpdateMaxVoterWeightRecord instruction
+pub fn process_update_max_voter_weight_record(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let registrar_info = next_account_info(account_info_iter)?; // 0
+    let max_voter_weight_record_info = next_account_info(account_info_iter)?; // 1
+    let governing_token_mint_info = next_account_info(account_info_iter)?; // 2
+
+    let registrar_data = get_registrar_data(program_id, registrar_info)?;
+
+    if registrar_data.governing_token_mint != *governing_token_mint_info.key {
+        return Err(TokenLockupAddinError::InvalidGoverningTokenMint.into());
+    }
+
+    let max_voter_weight_record_address = get_max_voter_weight_record_address(
+        program_id,
+        &registrar_data.realm,
+        &registrar_data.governing_token_mint,
+    );
+
+    if max_voter_weight_record_address != *max_voter_weight_record_info.key {
+        return Err(TokenLockupAddinError::InvalidMaxVoterWeightRecordForRegistrar.into());
+    }
+
+    let mut max_voter_weight_record_data =
+        get_account_data::<MaxVoterWeightRecord>(program_id, max_voter_weight_record_info)?;
+
+    let clock = Clock::get()?;
+
+    max_voter_weight_record_data.max_voter_weight = registrar_data
+        .get_max_voter_weight(get_spl_token_mint_supply(governing_token_mint_info)?)?;
+
+    // The mint supply can change and the max weight is only valid for the current slot
+    max_voter_weight_record_data.max_voter_weight_expiry = Some(clock.slot);
+
+    max_voter_weight_record_data.serialize(&mut *max_voter_weight_record_info.data.borrow_mut())?;
+
+    Ok(())
+}
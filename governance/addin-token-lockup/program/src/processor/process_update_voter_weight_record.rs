@@ -0,0 +1,61 @@
+//! Program state processor
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_governance_addin_api::voter_weight::VoterWeightRecord;
+use spl_governance_tools::account::get_account_data;
+
+use crate::{
+    error::TokenLockupAddinError,
+    state::{
+        registrar::get_registrar_data,
+        voter::{get_voter_data_for_registrar, get_voter_weight_record_address},
+    },
+};
+
+/// Processes UpdateVoterWeightRecord instruction
+pub fn process_update_voter_weight_record(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let registrar_info = next_account_info(account_info_iter)?; // 0
+    let voter_info = next_account_info(account_info_iter)?; // 1
+    let voter_weight_record_info = next_account_info(account_info_iter)?; // 2
+
+    let registrar_data = get_registrar_data(program_id, registrar_info)?;
+    let voter_data = get_voter_data_for_registrar(program_id, voter_info, registrar_info.key)?;
+
+    let voter_weight_record_address = get_voter_weight_record_address(
+        program_id,
+        &registrar_data.realm,
+        &registrar_data.governing_token_mint,
+        &voter_data.voter_authority,
+    );
+
+    if voter_weight_record_address != *voter_weight_record_info.key {
+        return Err(TokenLockupAddinError::InvalidVoterWeightRecordForVoter.into());
+    }
+
+    let mut voter_weight_record_data =
+        get_account_data::<VoterWeightRecord>(program_id, voter_weight_record_info)?;
+
+    let clock = Clock::get()?;
+
+    voter_weight_record_data.voter_weight =
+        voter_data.get_voter_weight(&registrar_data.config, clock.unix_timestamp)?;
+
+    // The weight decays with time and is only valid for the current slot
+    voter_weight_record_data.voter_weight_expiry = Some(clock.slot);
+
+    voter_weight_record_data.serialize(&mut *voter_weight_record_info.data.borrow_mut())?;
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+//! Program state processor
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_governance::{
+    state::token_owner_record::get_token_owner_record_data_for_realm_and_governing_mint,
+    tools::spl_token::transfer_spl_tokens_signed,
+};
+
+use crate::{
+    error::TokenLockupAddinError,
+    state::{
+        registrar::{get_registrar_address_seeds, get_registrar_data, Registrar},
+        voter::get_voter_data_for_registrar,
+    },
+};
+
+/// Processes Withdraw instruction
+pub fn process_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    deposit_index: u8,
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let registrar_info = next_account_info(account_info_iter)?; // 0
+    let voter_info = next_account_info(account_info_iter)?; // 1
+    let voter_authority_info = next_account_info(account_info_iter)?; // 2
+    let token_owner_record_info = next_account_info(account_info_iter)?; // 3
+    let vault_info = next_account_info(account_info_iter)?; // 4
+    let destination_token_info = next_account_info(account_info_iter)?; // 5
+    let spl_token_info = next_account_info(account_info_iter)?; // 6
+
+    let registrar_data = get_registrar_data(program_id, registrar_info)?;
+
+    let mut voter_data = get_voter_data_for_registrar(program_id, voter_info, registrar_info.key)?;
+    voter_data.assert_voter_authority_is_signer(voter_authority_info)?;
+
+    let token_owner_record_data = get_token_owner_record_data_for_realm_and_governing_mint(
+        &registrar_data.governance_program_id,
+        token_owner_record_info,
+        &registrar_data.realm,
+        &registrar_data.governing_token_mint,
+    )?;
+
+    if token_owner_record_data.governing_token_owner != voter_data.voter_authority {
+        return Err(TokenLockupAddinError::InvalidTokenOwnerRecordForVoter.into());
+    }
+
+    // The tokens can't be withdrawn while they are used to vote on active proposals
+    // Otherwise they could be deposited again by another Voter and used to vote twice
+    if token_owner_record_data.unrelinquished_votes_count > 0 {
+        return Err(TokenLockupAddinError::CannotWithdrawWithActiveVotes.into());
+    }
+
+    Registrar::assert_is_valid_vault(program_id, registrar_info.key, vault_info)?;
+
+    let current_ts = Clock::get()?.unix_timestamp;
+    let deposit = voter_data.get_deposit_mut(deposit_index)?;
+
+    if amount > deposit.get_amount_unlocked(current_ts) {
+        return Err(TokenLockupAddinError::InsufficientUnlockedTokens.into());
+    }
+
+    deposit.amount_deposited -= amount;
+    voter_data.remove_deposit_if_empty(deposit_index);
+
+    transfer_spl_tokens_signed(
+        vault_info,
+        destination_token_info,
+        registrar_info,
+        &get_registrar_address_seeds(&registrar_data.realm, &registrar_data.governing_token_mint),
+        program_id,
+        amount,
+        spl_token_info,
+    )?;
+
+    voter_data.serialize(&mut *voter_info.data.borrow_mut())?;
+
+    Ok(())
+}
@@ -0,0 +1,368 @@
+//! Deposit entry
+
+use std::convert::TryFrom;
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use solana_program::{clock::UnixTimestamp, program_error::ProgramError};
+
+use crate::{
+    error::TokenLockupAddinError,
+    state::{enums::LockupKind, registrar::RegistrarConfig},
+};
+
+/// Lockup of deposited tokens
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct Lockup {
+    /// The kind of the lockup
+    pub kind: LockupKind,
+
+    /// The time when the lockup started
+    pub start_ts: UnixTimestamp,
+
+    /// The time when all tokens are unlocked
+    pub end_ts: UnixTimestamp,
+}
+
+impl Lockup {
+    /// Creates a new lockup starting at start_ts and lasting lockup_period_secs
+    pub fn new(
+        kind: LockupKind,
+        start_ts: UnixTimestamp,
+        lockup_period_secs: u64,
+    ) -> Result<Self, ProgramError> {
+        // Tokens without lockup can't have lockup period and locked tokens must have one
+        if (kind == LockupKind::None) != (lockup_period_secs == 0) {
+            return Err(TokenLockupAddinError::InvalidLockupPeriod.into());
+        }
+
+        let end_ts = i64::try_from(lockup_period_secs)
+            .ok()
+            .and_then(|period| start_ts.checked_add(period))
+            .ok_or(TokenLockupAddinError::InvalidLockupPeriod)?;
+
+        Ok(Self {
+            kind,
+            start_ts,
+            end_ts,
+        })
+    }
+
+    /// Returns the number of seconds left until the lockup ends
+    pub fn get_seconds_left(&self, current_ts: UnixTimestamp) -> u64 {
+        if self.kind == LockupKind::None {
+            return 0;
+        }
+
+        self.end_ts.saturating_sub(current_ts).max(0) as u64
+    }
+}
+
+/// Deposit of governing tokens made by a Voter
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct DepositEntry {
+    /// The lockup of the deposited tokens
+    pub lockup: Lockup,
+
+    /// The amount of deposited tokens which haven't been withdrawn yet, including the locked ones
+    pub amount_deposited: u64,
+
+    /// The amount of tokens locked at the lockup start
+    pub amount_initially_locked: u64,
+
+    /// Indicates whether the Realm authority can claw back the tokens which are still locked
+    /// It's set for grants and allows to recover tokens which haven't vested yet
+    pub allow_clawback: bool,
+}
+
+impl DepositEntry {
+    /// Serialized size of DepositEntry
+    pub const SIZE: usize = 34;
+
+    /// Returns the amount of tokens which are still locked at the given time
+    pub fn get_amount_locked(&self, current_ts: UnixTimestamp) -> u64 {
+        let seconds_left = self.lockup.get_seconds_left(current_ts);
+
+        if seconds_left == 0 {
+            return 0;
+        }
+
+        let amount_locked = match self.lockup.kind {
+            LockupKind::None => 0,
+            LockupKind::Cliff => self.amount_initially_locked,
+            LockupKind::Linear => {
+                let lockup_period = self.lockup.end_ts.saturating_sub(self.lockup.start_ts) as u64;
+
+                if seconds_left >= lockup_period {
+                    self.amount_initially_locked
+                } else {
+                    // Round up the locked amount to never release more tokens than vested
+                    let lockup_period = lockup_period as u128;
+                    ((self.amount_initially_locked as u128 * seconds_left as u128 + lockup_period
+                        - 1)
+                        / lockup_period) as u64
+                }
+            }
+        };
+
+        amount_locked.min(self.amount_deposited)
+    }
+
+    /// Returns the amount of tokens which can be withdrawn at the given time
+    pub fn get_amount_unlocked(&self, current_ts: UnixTimestamp) -> u64 {
+        self.amount_deposited - self.get_amount_locked(current_ts)
+    }
+
+    /// Returns the voter weight of the deposit at the given time
+    /// All deposited tokens give the baseline weight of 1 and the locked ones get extra weight for the remaining lockup
+    pub fn get_voter_weight(
+        &self,
+        config: &RegistrarConfig,
+        current_ts: UnixTimestamp,
+    ) -> Result<u64, ProgramError> {
+        let extra_lockup_vote_weight = self.get_extra_lockup_vote_weight(config, current_ts)?;
+
+        self.amount_deposited
+            .checked_add(extra_lockup_vote_weight)
+            .ok_or_else(|| TokenLockupAddinError::VoterWeightOverflow.into())
+    }
+
+    /// Returns the extra vote weight of the locked tokens
+    ///
+    /// For Cliff lockup all locked tokens get the extra weight proportional to the remaining lockup
+    /// and the max extra weight is reached once the remaining lockup is equal or longer than the saturation period
+    ///
+    /// For Linear lockup every locked token is weighted by its own remaining lockup,
+    /// i.e. the tokens which vest sooner get less extra weight than the ones vesting later
+    fn get_extra_lockup_vote_weight(
+        &self,
+        config: &RegistrarConfig,
+        current_ts: UnixTimestamp,
+    ) -> Result<u64, ProgramError> {
+        let amount_locked = self.get_amount_locked(current_ts) as u128;
+
+        if amount_locked == 0 || config.max_extra_lockup_vote_weight_factor == 0 {
+            return Ok(0);
+        }
+
+        let seconds_left = self.lockup.get_seconds_left(current_ts) as u128;
+        let saturation_secs = config.lockup_saturation_secs as u128;
+        let factor_base = RegistrarConfig::VOTE_WEIGHT_FACTOR_BASE as u128;
+
+        let max_extra_lockup_vote_weight = amount_locked
+            .checked_mul(config.max_extra_lockup_vote_weight_factor as u128)
+            .ok_or(TokenLockupAddinError::VoterWeightOverflow)?;
+
+        let (numerator, denominator) = match self.lockup.kind {
+            LockupKind::None => return Ok(0),
+            LockupKind::Cliff => (
+                seconds_left.min(saturation_secs),
+                factor_base * saturation_secs,
+            ),
+            LockupKind::Linear => {
+                // The locked tokens vest uniformly over the remaining lockup and hence the extra weight is the average
+                // of the saturated weights of all the remaining vesting times
+                if seconds_left <= saturation_secs {
+                    (seconds_left, 2 * factor_base * saturation_secs)
+                } else {
+                    (
+                        2 * seconds_left - saturation_secs,
+                        2 * factor_base * seconds_left,
+                    )
+                }
+            }
+        };
+
+        let extra_lockup_vote_weight = max_extra_lockup_vote_weight
+            .checked_mul(numerator)
+            .ok_or(TokenLockupAddinError::VoterWeightOverflow)?
+            / denominator;
+
+        u64::try_from(extra_lockup_vote_weight)
+            .map_err(|_| TokenLockupAddinError::VoterWeightOverflow.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn create_test_config() -> RegistrarConfig {
+        RegistrarConfig {
+            // Locked tokens can get up to 2x weight
+            max_extra_lockup_vote_weight_factor: RegistrarConfig::VOTE_WEIGHT_FACTOR_BASE,
+            lockup_saturation_secs: 100,
+        }
+    }
+
+    fn create_test_deposit(kind: LockupKind, lockup_period_secs: u64) -> DepositEntry {
+        DepositEntry {
+            lockup: Lockup::new(kind, 1000, lockup_period_secs).unwrap(),
+            amount_deposited: 1000,
+            amount_initially_locked: 1000,
+            allow_clawback: false,
+        }
+    }
+
+    #[test]
+    fn test_size() {
+        let deposit = create_test_deposit(LockupKind::Cliff, 10);
+
+        let size = deposit.try_to_vec().unwrap().len();
+
+        assert_eq!(DepositEntry::SIZE, size);
+    }
+
+    #[test]
+    fn test_lockup_with_invalid_period_error() {
+        // Act
+        let none_err = Lockup::new(LockupKind::None, 1000, 10).err().unwrap();
+        let cliff_err = Lockup::new(LockupKind::Cliff, 1000, 0).err().unwrap();
+        let linear_err = Lockup::new(LockupKind::Linear, 1000, 0).err().unwrap();
+        let overflow_err = Lockup::new(LockupKind::Cliff, 1000, u64::MAX)
+            .err()
+            .unwrap();
+
+        // Assert
+        let expected_err: ProgramError = TokenLockupAddinError::InvalidLockupPeriod.into();
+
+        assert_eq!(none_err, expected_err);
+        assert_eq!(cliff_err, expected_err);
+        assert_eq!(linear_err, expected_err);
+        assert_eq!(overflow_err, expected_err);
+    }
+
+    #[test]
+    fn test_get_amount_locked_for_none_lockup() {
+        // Arrange
+        let deposit = create_test_deposit(LockupKind::None, 0);
+
+        // Act
+        let amount_locked = deposit.get_amount_locked(1000);
+
+        // Assert
+        assert_eq!(0, amount_locked);
+        assert_eq!(1000, deposit.get_amount_unlocked(1000));
+    }
+
+    #[test]
+    fn test_get_amount_locked_for_cliff_lockup() {
+        // Arrange
+        let deposit = create_test_deposit(LockupKind::Cliff, 100);
+
+        // Act + Assert
+        assert_eq!(1000, deposit.get_amount_locked(1000));
+        assert_eq!(1000, deposit.get_amount_locked(1099));
+        assert_eq!(0, deposit.get_amount_locked(1100));
+    }
+
+    #[test]
+    fn test_get_amount_locked_for_linear_lockup() {
+        // Arrange
+        let deposit = create_test_deposit(LockupKind::Linear, 100);
+
+        // Act + Assert
+        assert_eq!(1000, deposit.get_amount_locked(1000));
+        assert_eq!(750, deposit.get_amount_locked(1025));
+        assert_eq!(10, deposit.get_amount_locked(1099));
+        assert_eq!(0, deposit.get_amount_locked(1100));
+    }
+
+    #[test]
+    fn test_get_amount_locked_for_linear_lockup_rounds_up() {
+        // Arrange
+        let mut deposit = create_test_deposit(LockupKind::Linear, 3);
+        deposit.amount_deposited = 10;
+        deposit.amount_initially_locked = 10;
+
+        // Act
+        let amount_locked = deposit.get_amount_locked(1001);
+
+        // Assert
+        assert_eq!(7, amount_locked);
+    }
+
+    #[test]
+    fn test_get_voter_weight_for_none_lockup() {
+        // Arrange
+        let deposit = create_test_deposit(LockupKind::None, 0);
+
+        // Act
+        let voter_weight = deposit
+            .get_voter_weight(&create_test_config(), 1000)
+            .unwrap();
+
+        // Assert
+        assert_eq!(1000, voter_weight);
+    }
+
+    #[test]
+    fn test_get_voter_weight_for_cliff_lockup() {
+        // Arrange
+        let deposit = create_test_deposit(LockupKind::Cliff, 200);
+        let config = create_test_config();
+
+        // Act + Assert
+
+        // Saturated lockup
+        assert_eq!(2000, deposit.get_voter_weight(&config, 1000).unwrap());
+        assert_eq!(2000, deposit.get_voter_weight(&config, 1100).unwrap());
+
+        // Half of the saturation period left
+        assert_eq!(1500, deposit.get_voter_weight(&config, 1150).unwrap());
+
+        // Lockup expired
+        assert_eq!(1000, deposit.get_voter_weight(&config, 1200).unwrap());
+    }
+
+    #[test]
+    fn test_get_voter_weight_for_linear_lockup() {
+        // Arrange
+        let deposit = create_test_deposit(LockupKind::Linear, 100);
+        let config = create_test_config();
+
+        // Act + Assert
+
+        // Tokens vest uniformly within the saturation period and get half of the max extra weight on average
+        assert_eq!(1500, deposit.get_voter_weight(&config, 1000).unwrap());
+
+        // 500 tokens locked for 50 seconds on average
+        assert_eq!(1125, deposit.get_voter_weight(&config, 1050).unwrap());
+
+        // Lockup expired
+        assert_eq!(1000, deposit.get_voter_weight(&config, 1100).unwrap());
+    }
+
+    #[test]
+    fn test_get_voter_weight_for_linear_lockup_longer_than_saturation() {
+        // Arrange
+        let deposit = create_test_deposit(LockupKind::Linear, 400);
+        let config = create_test_config();
+
+        // Act
+        let voter_weight = deposit.get_voter_weight(&config, 1000).unwrap();
+
+        // Assert
+
+        // 1/4 of the tokens vest within the saturation period and get half of the max extra weight on average
+        // and the remaining 3/4 get the max extra weight
+        assert_eq!(1000 + 125 + 750, voter_weight);
+    }
+
+    #[test]
+    fn test_get_voter_weight_with_overflow_error() {
+        // Arrange
+        let mut deposit = create_test_deposit(LockupKind::Cliff, 200);
+        deposit.amount_deposited = u64::MAX;
+        deposit.amount_initially_locked = u64::MAX;
+
+        // Act
+        let err = deposit
+            .get_voter_weight(&create_test_config(), 1000)
+            .err()
+            .unwrap();
+
+        // Assert
+        assert_eq!(err, TokenLockupAddinError::VoterWeightOverflow.into());
+    }
+}
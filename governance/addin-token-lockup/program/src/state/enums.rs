@@ -0,0 +1,30 @@
+//! State enumerations
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+
+/// Defines all TokenLockup accounts types
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub enum TokenLockupAccountType {
+    /// Default uninitialized account state
+    Uninitialized,
+
+    /// Registrar which configures the addin for a Realm and governing token mint
+    Registrar,
+
+    /// Voter account which holds the voter's deposits
+    Voter,
+}
+
+/// The kind of lockup applied to deposited tokens
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub enum LockupKind {
+    /// Tokens are not locked and can be withdrawn at any time
+    /// They give the baseline vote weight only
+    None,
+
+    /// All tokens are locked until the lockup end and unlocked at once
+    Cliff,
+
+    /// Tokens vest linearly between the lockup start and end
+    Linear,
+}
@@ -0,0 +1,6 @@
+//! Program accounts
+
+pub mod deposit_entry;
+pub mod enums;
+pub mod registrar;
+pub mod voter;
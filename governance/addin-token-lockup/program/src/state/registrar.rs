@@ -0,0 +1,251 @@
+//! Registrar Account
+
+use std::convert::TryFrom;
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, program_pack::IsInitialized,
+    pubkey::Pubkey,
+};
+use spl_governance::state::realm::get_realm_data_for_authority;
+use spl_governance_tools::account::{get_account_data, AccountMaxSize};
+
+use crate::{error::TokenLockupAddinError, state::enums::TokenLockupAccountType};
+
+/// Registrar config which defines how locked tokens are weighted
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct RegistrarConfig {
+    /// Extra vote weight factor (10^9 precision) given to tokens locked for lockup_saturation_secs or longer
+    /// For example VOTE_WEIGHT_FACTOR_BASE doubles the vote weight of tokens locked for the saturation period
+    pub max_extra_lockup_vote_weight_factor: u64,
+
+    /// Lockup period in seconds at which locked tokens reach the max extra vote weight
+    pub lockup_saturation_secs: u64,
+}
+
+impl RegistrarConfig {
+    /// Base for vote weight factor calculation
+    pub const VOTE_WEIGHT_FACTOR_BASE: u64 = 1_000_000_000;
+}
+
+/// Registrar account
+/// It configures the addin for the given Realm and its community mint and owns the vault with deposited tokens
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct Registrar {
+    /// Account type
+    pub account_type: TokenLockupAccountType,
+
+    /// The governance program the Realm belongs to
+    pub governance_program_id: Pubkey,
+
+    /// The Realm the Registrar belongs to
+    pub realm: Pubkey,
+
+    /// Governing token mint the Registrar takes deposits of
+    pub governing_token_mint: Pubkey,
+
+    /// Registrar config
+    pub config: RegistrarConfig,
+
+    /// Reserved space for future versions
+    pub reserved: [u8; 64],
+}
+
+impl AccountMaxSize for Registrar {}
+
+impl IsInitialized for Registrar {
+    fn is_initialized(&self) -> bool {
+        self.account_type == TokenLockupAccountType::Registrar
+    }
+}
+
+impl Registrar {
+    /// Returns the max voter weight for the given governing token mint supply
+    /// The max voter weight is the weight of the whole supply locked for the saturation period
+    pub fn get_max_voter_weight(
+        &self,
+        governing_token_mint_supply: u64,
+    ) -> Result<u64, ProgramError> {
+        let max_voter_weight = (governing_token_mint_supply as u128)
+            .checked_mul(
+                RegistrarConfig::VOTE_WEIGHT_FACTOR_BASE as u128
+                    + self.config.max_extra_lockup_vote_weight_factor as u128,
+            )
+            .ok_or(TokenLockupAddinError::VoterWeightOverflow)?
+            / RegistrarConfig::VOTE_WEIGHT_FACTOR_BASE as u128;
+
+        u64::try_from(max_voter_weight)
+            .map_err(|_| TokenLockupAddinError::VoterWeightOverflow.into())
+    }
+
+    /// Asserts the given Realm is the Registrar's Realm and its authority signed the transaction
+    pub fn assert_realm_authority_is_signer(
+        &self,
+        realm_info: &AccountInfo,
+        realm_authority_info: &AccountInfo,
+    ) -> Result<(), ProgramError> {
+        if self.realm != *realm_info.key {
+            return Err(TokenLockupAddinError::InvalidRealmForRegistrar.into());
+        }
+
+        get_realm_data_for_authority(
+            &self.governance_program_id,
+            realm_info,
+            realm_authority_info.key,
+        )?;
+
+        if !realm_authority_info.is_signer {
+            return Err(TokenLockupAddinError::RealmAuthorityMustSign.into());
+        }
+
+        Ok(())
+    }
+
+    /// Asserts the given vault is the token account owned by the Registrar
+    pub fn assert_is_valid_vault(
+        program_id: &Pubkey,
+        registrar: &Pubkey,
+        vault_info: &AccountInfo,
+    ) -> Result<(), ProgramError> {
+        if get_vault_address(program_id, registrar) != *vault_info.key {
+            return Err(TokenLockupAddinError::InvalidVaultForRegistrar.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Asserts the given registrar config is valid
+pub fn assert_is_valid_registrar_config(config: &RegistrarConfig) -> Result<(), ProgramError> {
+    if config.lockup_saturation_secs == 0 {
+        return Err(TokenLockupAddinError::InvalidLockupSaturationPeriod.into());
+    }
+
+    Ok(())
+}
+
+/// Deserializes Registrar account and checks owner program
+pub fn get_registrar_data(
+    program_id: &Pubkey,
+    registrar_info: &AccountInfo,
+) -> Result<Registrar, ProgramError> {
+    get_account_data::<Registrar>(program_id, registrar_info)
+}
+
+/// Returns Registrar PDA seeds
+pub fn get_registrar_address_seeds<'a>(
+    realm: &'a Pubkey,
+    governing_token_mint: &'a Pubkey,
+) -> [&'a [u8]; 3] {
+    [b"registrar", realm.as_ref(), governing_token_mint.as_ref()]
+}
+
+/// Returns Registrar PDA address
+pub fn get_registrar_address(
+    program_id: &Pubkey,
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &get_registrar_address_seeds(realm, governing_token_mint),
+        program_id,
+    )
+    .0
+}
+
+/// Returns Registrar vault PDA seeds
+pub fn get_vault_address_seeds(registrar: &Pubkey) -> [&[u8]; 2] {
+    [b"vault", registrar.as_ref()]
+}
+
+/// Returns Registrar vault PDA address
+pub fn get_vault_address(program_id: &Pubkey, registrar: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&get_vault_address_seeds(registrar), program_id).0
+}
+
+/// Returns MaxVoterWeightRecord PDA seeds
+pub fn get_max_voter_weight_record_address_seeds<'a>(
+    realm: &'a Pubkey,
+    governing_token_mint: &'a Pubkey,
+) -> [&'a [u8]; 3] {
+    [
+        b"max-voter-weight-record",
+        realm.as_ref(),
+        governing_token_mint.as_ref(),
+    ]
+}
+
+/// Returns MaxVoterWeightRecord PDA address
+pub fn get_max_voter_weight_record_address(
+    program_id: &Pubkey,
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &get_max_voter_weight_record_address_seeds(realm, governing_token_mint),
+        program_id,
+    )
+    .0
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn create_test_registrar() -> Registrar {
+        Registrar {
+            account_type: TokenLockupAccountType::Registrar,
+            governance_program_id: Pubkey::new_unique(),
+            realm: Pubkey::new_unique(),
+            governing_token_mint: Pubkey::new_unique(),
+            config: RegistrarConfig {
+                max_extra_lockup_vote_weight_factor: RegistrarConfig::VOTE_WEIGHT_FACTOR_BASE / 2,
+                lockup_saturation_secs: 100,
+            },
+            reserved: [0; 64],
+        }
+    }
+
+    #[test]
+    fn test_get_max_voter_weight() {
+        // Arrange
+        let registrar = create_test_registrar();
+
+        // Act
+        let max_voter_weight = registrar.get_max_voter_weight(1000).unwrap();
+
+        // Assert
+        assert_eq!(1500, max_voter_weight);
+    }
+
+    #[test]
+    fn test_get_max_voter_weight_with_overflow_error() {
+        // Arrange
+        let registrar = create_test_registrar();
+
+        // Act
+        let err = registrar.get_max_voter_weight(u64::MAX).err().unwrap();
+
+        // Assert
+        assert_eq!(err, TokenLockupAddinError::VoterWeightOverflow.into());
+    }
+
+    #[test]
+    fn test_assert_registrar_config_with_zero_saturation_error() {
+        // Arrange
+        let mut registrar = create_test_registrar();
+        registrar.config.lockup_saturation_secs = 0;
+
+        // Act
+        let err = assert_is_valid_registrar_config(&registrar.config)
+            .err()
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            err,
+            TokenLockupAddinError::InvalidLockupSaturationPeriod.into()
+        );
+    }
+}
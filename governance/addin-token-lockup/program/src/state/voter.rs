@@ -0,0 +1,286 @@
+//! Voter Account
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, clock::UnixTimestamp, program_error::ProgramError,
+    program_pack::IsInitialized, pubkey::Pubkey,
+};
+use spl_governance_tools::account::{get_account_data, AccountMaxSize};
+
+use crate::{
+    error::TokenLockupAddinError,
+    state::{
+        deposit_entry::DepositEntry, enums::TokenLockupAccountType, registrar::RegistrarConfig,
+    },
+};
+
+/// Voter account
+/// It holds all deposits made by the voter to the Registrar
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct Voter {
+    /// Account type
+    pub account_type: TokenLockupAccountType,
+
+    /// The Registrar the Voter belongs to
+    pub registrar: Pubkey,
+
+    /// The authority of the Voter which can deposit and withdraw tokens
+    /// It's the governing token owner the Voter's weight is provided for
+    pub voter_authority: Pubkey,
+
+    /// The deposits made by the Voter
+    pub deposits: Vec<DepositEntry>,
+}
+
+impl AccountMaxSize for Voter {
+    fn get_max_size(&self) -> Option<usize> {
+        Some(69 + Voter::MAX_DEPOSITS * DepositEntry::SIZE)
+    }
+}
+
+impl IsInitialized for Voter {
+    fn is_initialized(&self) -> bool {
+        self.account_type == TokenLockupAccountType::Voter
+    }
+}
+
+impl Voter {
+    /// Max number of deposits a Voter can hold
+    pub const MAX_DEPOSITS: usize = 16;
+
+    /// Returns the voter weight of all the deposits at the given time
+    pub fn get_voter_weight(
+        &self,
+        config: &RegistrarConfig,
+        current_ts: UnixTimestamp,
+    ) -> Result<u64, ProgramError> {
+        self.deposits
+            .iter()
+            .try_fold(0_u64, |voter_weight, deposit| {
+                voter_weight
+                    .checked_add(deposit.get_voter_weight(config, current_ts)?)
+                    .ok_or_else(|| TokenLockupAddinError::VoterWeightOverflow.into())
+            })
+    }
+
+    /// Adds a new deposit
+    pub fn add_deposit(&mut self, deposit: DepositEntry) -> Result<(), ProgramError> {
+        if self.deposits.len() >= Voter::MAX_DEPOSITS {
+            return Err(TokenLockupAddinError::MaxDepositsReached.into());
+        }
+
+        self.deposits.push(deposit);
+
+        Ok(())
+    }
+
+    /// Returns the deposit at the given index
+    pub fn get_deposit_mut(
+        &mut self,
+        deposit_index: u8,
+    ) -> Result<&mut DepositEntry, ProgramError> {
+        self.deposits
+            .get_mut(deposit_index as usize)
+            .ok_or_else(|| TokenLockupAddinError::InvalidDepositIndex.into())
+    }
+
+    /// Removes the deposit at the given index if all its tokens have been withdrawn
+    /// Note: The order of the remaining deposits is preserved
+    pub fn remove_deposit_if_empty(&mut self, deposit_index: u8) {
+        if self.deposits[deposit_index as usize].amount_deposited == 0 {
+            self.deposits.remove(deposit_index as usize);
+        }
+    }
+
+    /// Asserts the given account is the Voter's authority and it signed the transaction
+    pub fn assert_voter_authority_is_signer(
+        &self,
+        voter_authority_info: &AccountInfo,
+    ) -> Result<(), ProgramError> {
+        if self.voter_authority != *voter_authority_info.key {
+            return Err(TokenLockupAddinError::InvalidVoterAuthority.into());
+        }
+
+        if !voter_authority_info.is_signer {
+            return Err(TokenLockupAddinError::VoterAuthorityMustSign.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Deserializes Voter account and checks owner program
+pub fn get_voter_data(
+    program_id: &Pubkey,
+    voter_info: &AccountInfo,
+) -> Result<Voter, ProgramError> {
+    get_account_data::<Voter>(program_id, voter_info)
+}
+
+/// Deserializes Voter account and asserts it belongs to the given Registrar
+pub fn get_voter_data_for_registrar(
+    program_id: &Pubkey,
+    voter_info: &AccountInfo,
+    registrar: &Pubkey,
+) -> Result<Voter, ProgramError> {
+    let voter_data = get_voter_data(program_id, voter_info)?;
+
+    if voter_data.registrar != *registrar {
+        return Err(TokenLockupAddinError::InvalidRegistrarForVoter.into());
+    }
+
+    Ok(voter_data)
+}
+
+/// Returns Voter PDA seeds
+pub fn get_voter_address_seeds<'a>(
+    registrar: &'a Pubkey,
+    voter_authority: &'a Pubkey,
+) -> [&'a [u8]; 3] {
+    [b"voter", registrar.as_ref(), voter_authority.as_ref()]
+}
+
+/// Returns Voter PDA address
+pub fn get_voter_address(
+    program_id: &Pubkey,
+    registrar: &Pubkey,
+    voter_authority: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &get_voter_address_seeds(registrar, voter_authority),
+        program_id,
+    )
+    .0
+}
+
+/// Returns VoterWeightRecord PDA seeds
+pub fn get_voter_weight_record_address_seeds<'a>(
+    realm: &'a Pubkey,
+    governing_token_mint: &'a Pubkey,
+    voter_authority: &'a Pubkey,
+) -> [&'a [u8]; 4] {
+    [
+        b"voter-weight-record",
+        realm.as_ref(),
+        governing_token_mint.as_ref(),
+        voter_authority.as_ref(),
+    ]
+}
+
+/// Returns VoterWeightRecord PDA address
+pub fn get_voter_weight_record_address(
+    program_id: &Pubkey,
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    voter_authority: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &get_voter_weight_record_address_seeds(realm, governing_token_mint, voter_authority),
+        program_id,
+    )
+    .0
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::state::{deposit_entry::Lockup, enums::LockupKind};
+
+    use super::*;
+
+    fn create_test_deposit() -> DepositEntry {
+        DepositEntry {
+            lockup: Lockup::new(LockupKind::Cliff, 1000, 100).unwrap(),
+            amount_deposited: 1000,
+            amount_initially_locked: 1000,
+            allow_clawback: false,
+        }
+    }
+
+    fn create_test_voter() -> Voter {
+        Voter {
+            account_type: TokenLockupAccountType::Voter,
+            registrar: Pubkey::new_unique(),
+            voter_authority: Pubkey::new_unique(),
+            deposits: vec![],
+        }
+    }
+
+    #[test]
+    fn test_max_size() {
+        // Arrange
+        let mut voter = create_test_voter();
+
+        for _ in 0..Voter::MAX_DEPOSITS {
+            voter.add_deposit(create_test_deposit()).unwrap();
+        }
+
+        // Act
+        let size = voter.try_to_vec().unwrap().len();
+
+        // Assert
+        assert_eq!(voter.get_max_size(), Some(size));
+    }
+
+    #[test]
+    fn test_add_deposit_with_max_deposits_reached_error() {
+        // Arrange
+        let mut voter = create_test_voter();
+
+        for _ in 0..Voter::MAX_DEPOSITS {
+            voter.add_deposit(create_test_deposit()).unwrap();
+        }
+
+        // Act
+        let err = voter.add_deposit(create_test_deposit()).err().unwrap();
+
+        // Assert
+        assert_eq!(err, TokenLockupAddinError::MaxDepositsReached.into());
+    }
+
+    #[test]
+    fn test_get_voter_weight() {
+        // Arrange
+        let mut voter = create_test_voter();
+
+        let config = RegistrarConfig {
+            max_extra_lockup_vote_weight_factor: RegistrarConfig::VOTE_WEIGHT_FACTOR_BASE,
+            lockup_saturation_secs: 100,
+        };
+
+        voter.add_deposit(create_test_deposit()).unwrap();
+
+        voter
+            .add_deposit(DepositEntry {
+                lockup: Lockup::new(LockupKind::None, 1000, 0).unwrap(),
+                amount_deposited: 500,
+                amount_initially_locked: 0,
+                allow_clawback: false,
+            })
+            .unwrap();
+
+        // Act
+        let voter_weight = voter.get_voter_weight(&config, 1050).unwrap();
+
+        // Assert
+        assert_eq!(1500 + 500, voter_weight);
+    }
+
+    #[test]
+    fn test_remove_deposit_if_empty() {
+        // Arrange
+        let mut voter = create_test_voter();
+
+        voter.add_deposit(create_test_deposit()).unwrap();
+        voter.add_deposit(create_test_deposit()).unwrap();
+
+        voter.deposits[0].amount_deposited = 0;
+
+        // Act
+        voter.remove_deposit_if_empty(0);
+        voter.remove_deposit_if_empty(0);
+
+        // Assert
+        assert_eq!(voter.deposits, vec![create_test_deposit()]);
+    }
+}
@@ -0,0 +1,119 @@
+#![cfg(feature = "test-bpf")]
+
+use solana_program_test::*;
+use solana_sdk::signer::Signer;
+
+mod program_test;
+
+use program_test::*;
+use spl_governance_addin_token_lockup::{error::TokenLockupAddinError, state::enums::LockupKind};
+
+#[tokio::test]
+async fn test_clawback() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    let registrar_cookie = lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    lockup_test
+        .with_grant(&realm_cookie, &voter_cookie, 100, LockupKind::Cliff, 500)
+        .await
+        .unwrap();
+
+    let destination_cookie = lockup_test
+        .with_community_token_account(&realm_cookie, &realm_cookie.realm_authority.pubkey(), 0)
+        .await;
+
+    // Act
+    lockup_test
+        .clawback(&realm_cookie, &voter_cookie, &destination_cookie.address, 0)
+        .await
+        .unwrap();
+
+    // Assert
+    let voter_account = lockup_test.get_voter_account(&voter_cookie.address).await;
+    assert!(voter_account.deposits.is_empty());
+
+    let destination_amount = lockup_test
+        .get_token_account_amount(&destination_cookie.address)
+        .await;
+    assert_eq!(100, destination_amount);
+
+    let vault_amount = lockup_test
+        .get_token_account_amount(&registrar_cookie.vault)
+        .await;
+    assert_eq!(0, vault_amount);
+}
+
+#[tokio::test]
+async fn test_clawback_after_lockup_expired() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    lockup_test
+        .with_grant(&realm_cookie, &voter_cookie, 100, LockupKind::Cliff, 10)
+        .await
+        .unwrap();
+
+    let destination_cookie = lockup_test
+        .with_community_token_account(&realm_cookie, &realm_cookie.realm_authority.pubkey(), 0)
+        .await;
+
+    lockup_test.advance_clock_by_min_timespan(10).await;
+
+    // Act
+    lockup_test
+        .clawback(&realm_cookie, &voter_cookie, &destination_cookie.address, 0)
+        .await
+        .unwrap();
+
+    // Assert
+
+    // The vested tokens stay with the Voter and are unlocked
+    let voter_account = lockup_test.get_voter_account(&voter_cookie.address).await;
+
+    assert_eq!(1, voter_account.deposits.len());
+    assert_eq!(LockupKind::None, voter_account.deposits[0].lockup.kind);
+    assert_eq!(100, voter_account.deposits[0].amount_deposited);
+    assert!(!voter_account.deposits[0].allow_clawback);
+
+    let destination_amount = lockup_test
+        .get_token_account_amount(&destination_cookie.address)
+        .await;
+    assert_eq!(0, destination_amount);
+}
+
+#[tokio::test]
+async fn test_clawback_with_clawback_not_allowed_error() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 100, LockupKind::Cliff, 500)
+        .await
+        .unwrap();
+
+    let destination_cookie = lockup_test
+        .with_community_token_account(&realm_cookie, &realm_cookie.realm_authority.pubkey(), 0)
+        .await;
+
+    // Act
+    let err = lockup_test
+        .clawback(&realm_cookie, &voter_cookie, &destination_cookie.address, 0)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, TokenLockupAddinError::ClawbackNotAllowed.into());
+}
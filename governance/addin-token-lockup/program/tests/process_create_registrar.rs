@@ -0,0 +1,91 @@
+#![cfg(feature = "test-bpf")]
+
+use solana_program_test::*;
+use solana_sdk::signature::Keypair;
+
+mod program_test;
+
+use program_test::*;
+use spl_governance::error::GovernanceError;
+use spl_governance_addin_token_lockup::error::TokenLockupAddinError;
+
+#[tokio::test]
+async fn test_create_registrar() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+
+    // Act
+    let registrar_cookie = lockup_test.with_registrar(&realm_cookie).await.unwrap();
+
+    // Assert
+    let registrar_account = lockup_test
+        .get_registrar_account(&registrar_cookie.address)
+        .await;
+
+    assert_eq!(registrar_cookie.account, registrar_account);
+
+    let max_voter_weight_record = lockup_test
+        .get_max_voter_weight_record(&registrar_cookie.max_voter_weight_record)
+        .await;
+
+    assert_eq!(realm_cookie.address, max_voter_weight_record.realm);
+    assert_eq!(
+        realm_cookie.community_mint,
+        max_voter_weight_record.governing_token_mint
+    );
+    assert_eq!(0, max_voter_weight_record.max_voter_weight);
+
+    let vault_amount = lockup_test
+        .get_token_account_amount(&registrar_cookie.vault)
+        .await;
+
+    assert_eq!(0, vault_amount);
+}
+
+#[tokio::test]
+async fn test_create_registrar_with_invalid_realm_authority_error() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    let config = lockup_test.get_default_registrar_config();
+
+    // Try to use a different authority
+    let realm_authority = Keypair::new();
+
+    // Act
+    let err = lockup_test
+        .with_registrar_using_config(&realm_cookie, &realm_authority, config)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, GovernanceError::InvalidAuthorityForRealm.into());
+}
+
+#[tokio::test]
+async fn test_create_registrar_with_zero_lockup_saturation_error() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+
+    let mut config = lockup_test.get_default_registrar_config();
+    config.lockup_saturation_secs = 0;
+
+    // Act
+    let err = lockup_test
+        .with_registrar_using_config(&realm_cookie, &realm_cookie.realm_authority, config)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        err,
+        TokenLockupAddinError::InvalidLockupSaturationPeriod.into()
+    );
+}
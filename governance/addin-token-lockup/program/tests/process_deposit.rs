@@ -0,0 +1,110 @@
+#![cfg(feature = "test-bpf")]
+
+use solana_program_test::*;
+
+mod program_test;
+
+use program_test::*;
+use spl_governance_addin_token_lockup::{
+    error::TokenLockupAddinError,
+    state::{
+        deposit_entry::{DepositEntry, Lockup},
+        enums::LockupKind,
+    },
+};
+
+#[tokio::test]
+async fn test_deposit_with_cliff_lockup() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    let registrar_cookie = lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    // Act
+    lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 100, LockupKind::Cliff, 500)
+        .await
+        .unwrap();
+
+    // Assert
+    let voter_account = lockup_test.get_voter_account(&voter_cookie.address).await;
+
+    assert_eq!(1, voter_account.deposits.len());
+
+    let deposit = &voter_account.deposits[0];
+
+    assert_eq!(
+        *deposit,
+        DepositEntry {
+            lockup: Lockup {
+                kind: LockupKind::Cliff,
+                start_ts: deposit.lockup.start_ts,
+                end_ts: deposit.lockup.start_ts + 500,
+            },
+            amount_deposited: 100,
+            amount_initially_locked: 100,
+            allow_clawback: false,
+        }
+    );
+
+    let vault_amount = lockup_test
+        .get_token_account_amount(&registrar_cookie.vault)
+        .await;
+
+    assert_eq!(100, vault_amount);
+}
+
+#[tokio::test]
+async fn test_deposit_without_lockup_tops_up_existing_deposit() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 100, LockupKind::None, 0)
+        .await
+        .unwrap();
+
+    lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 50, LockupKind::Cliff, 500)
+        .await
+        .unwrap();
+
+    // Act
+    lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 20, LockupKind::None, 0)
+        .await
+        .unwrap();
+
+    // Assert
+    let voter_account = lockup_test.get_voter_account(&voter_cookie.address).await;
+
+    assert_eq!(2, voter_account.deposits.len());
+    assert_eq!(120, voter_account.deposits[0].amount_deposited);
+    assert_eq!(50, voter_account.deposits[1].amount_deposited);
+}
+
+#[tokio::test]
+async fn test_deposit_with_invalid_lockup_period_error() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    // Act
+    let err = lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 100, LockupKind::Cliff, 0)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, TokenLockupAddinError::InvalidLockupPeriod.into());
+}
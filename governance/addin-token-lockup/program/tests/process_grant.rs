@@ -0,0 +1,62 @@
+#![cfg(feature = "test-bpf")]
+
+use solana_program_test::*;
+
+mod program_test;
+
+use program_test::*;
+use spl_governance_addin_token_lockup::{error::TokenLockupAddinError, state::enums::LockupKind};
+
+#[tokio::test]
+async fn test_grant() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    let registrar_cookie = lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    // Act
+    lockup_test
+        .with_grant(&realm_cookie, &voter_cookie, 100, LockupKind::Linear, 500)
+        .await
+        .unwrap();
+
+    // Assert
+    let voter_account = lockup_test.get_voter_account(&voter_cookie.address).await;
+
+    assert_eq!(1, voter_account.deposits.len());
+
+    let deposit = &voter_account.deposits[0];
+
+    assert_eq!(LockupKind::Linear, deposit.lockup.kind);
+    assert_eq!(100, deposit.amount_deposited);
+    assert_eq!(100, deposit.amount_initially_locked);
+    assert!(deposit.allow_clawback);
+
+    let vault_amount = lockup_test
+        .get_token_account_amount(&registrar_cookie.vault)
+        .await;
+
+    assert_eq!(100, vault_amount);
+}
+
+#[tokio::test]
+async fn test_grant_without_lockup_error() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    // Act
+    let err = lockup_test
+        .with_grant(&realm_cookie, &voter_cookie, 100, LockupKind::None, 0)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, TokenLockupAddinError::GrantMustBeLocked.into());
+}
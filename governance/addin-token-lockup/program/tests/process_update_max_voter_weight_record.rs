@@ -0,0 +1,41 @@
+#![cfg(feature = "test-bpf")]
+
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+mod program_test;
+
+use program_test::*;
+
+#[tokio::test]
+async fn test_update_max_voter_weight_record() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    let registrar_cookie = lockup_test.with_registrar(&realm_cookie).await.unwrap();
+
+    lockup_test
+        .with_community_token_account(&realm_cookie, &Keypair::new().pubkey(), 1000)
+        .await;
+
+    // Act
+    lockup_test
+        .update_max_voter_weight_record(&realm_cookie)
+        .await
+        .unwrap();
+
+    // Assert
+    let clock = lockup_test.bench.get_clock().await;
+
+    let max_voter_weight_record = lockup_test
+        .get_max_voter_weight_record(&registrar_cookie.max_voter_weight_record)
+        .await;
+
+    // The whole supply locked for the saturation period with the default config doubles the weight
+    assert_eq!(2000, max_voter_weight_record.max_voter_weight);
+    assert_eq!(
+        Some(clock.slot),
+        max_voter_weight_record.max_voter_weight_expiry
+    );
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "test-bpf")]
+
+use solana_program_test::*;
+
+mod program_test;
+
+use program_test::*;
+use spl_governance::error::GovernanceError;
+use spl_governance_addin_token_lockup::state::enums::LockupKind;
+
+#[tokio::test]
+async fn test_update_voter_weight_record() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    // Tokens locked for the saturation period get the max extra weight which doubles them
+    lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 100, LockupKind::Cliff, 1000)
+        .await
+        .unwrap();
+
+    lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 50, LockupKind::None, 0)
+        .await
+        .unwrap();
+
+    // Act
+    lockup_test
+        .update_voter_weight_record(&realm_cookie, &voter_cookie)
+        .await
+        .unwrap();
+
+    // Assert
+    let clock = lockup_test.bench.get_clock().await;
+
+    let voter_weight_record = lockup_test
+        .get_voter_weight_record(&voter_cookie.voter_weight_record)
+        .await;
+
+    assert_eq!(200 + 50, voter_weight_record.voter_weight);
+    assert_eq!(Some(clock.slot), voter_weight_record.voter_weight_expiry);
+    assert_eq!(realm_cookie.address, voter_weight_record.realm);
+    assert_eq!(
+        realm_cookie.community_mint,
+        voter_weight_record.governing_token_mint
+    );
+}
+
+#[tokio::test]
+async fn test_create_governance_with_locked_tokens_voter_weight() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 100, LockupKind::Cliff, 1000)
+        .await
+        .unwrap();
+
+    // Act
+    lockup_test
+        .with_governance(&realm_cookie, &voter_cookie, 150)
+        .await
+        .unwrap();
+
+    // Assert
+    let voter_weight_record = lockup_test
+        .get_voter_weight_record(&voter_cookie.voter_weight_record)
+        .await;
+
+    assert_eq!(200, voter_weight_record.voter_weight);
+}
+
+#[tokio::test]
+async fn test_create_governance_with_unlocked_tokens_voter_weight_error() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    // Unlocked tokens don't get any extra weight
+    lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 100, LockupKind::None, 0)
+        .await
+        .unwrap();
+
+    // Act
+    let err = lockup_test
+        .with_governance(&realm_cookie, &voter_cookie, 150)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        err,
+        GovernanceError::NotEnoughTokensToCreateGovernance.into()
+    );
+}
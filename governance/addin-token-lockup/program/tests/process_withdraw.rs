@@ -0,0 +1,166 @@
+#![cfg(feature = "test-bpf")]
+
+use solana_program_test::*;
+use solana_sdk::signer::Signer;
+
+mod program_test;
+
+use program_test::*;
+use spl_governance_addin_token_lockup::{error::TokenLockupAddinError, state::enums::LockupKind};
+
+#[tokio::test]
+async fn test_withdraw_unlocked_tokens() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    let registrar_cookie = lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 100, LockupKind::None, 0)
+        .await
+        .unwrap();
+
+    let destination_cookie = lockup_test
+        .with_community_token_account(&realm_cookie, &voter_cookie.voter_authority.pubkey(), 0)
+        .await;
+
+    // Act
+    lockup_test
+        .withdraw(
+            &realm_cookie,
+            &voter_cookie,
+            &destination_cookie.address,
+            0,
+            40,
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let voter_account = lockup_test.get_voter_account(&voter_cookie.address).await;
+    assert_eq!(60, voter_account.deposits[0].amount_deposited);
+
+    let destination_amount = lockup_test
+        .get_token_account_amount(&destination_cookie.address)
+        .await;
+    assert_eq!(40, destination_amount);
+
+    let vault_amount = lockup_test
+        .get_token_account_amount(&registrar_cookie.vault)
+        .await;
+    assert_eq!(60, vault_amount);
+}
+
+#[tokio::test]
+async fn test_withdraw_all_tokens_removes_deposit() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 100, LockupKind::None, 0)
+        .await
+        .unwrap();
+
+    let destination_cookie = lockup_test
+        .with_community_token_account(&realm_cookie, &voter_cookie.voter_authority.pubkey(), 0)
+        .await;
+
+    // Act
+    lockup_test
+        .withdraw(
+            &realm_cookie,
+            &voter_cookie,
+            &destination_cookie.address,
+            0,
+            100,
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let voter_account = lockup_test.get_voter_account(&voter_cookie.address).await;
+    assert!(voter_account.deposits.is_empty());
+}
+
+#[tokio::test]
+async fn test_withdraw_locked_tokens_error() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 100, LockupKind::Cliff, 500)
+        .await
+        .unwrap();
+
+    let destination_cookie = lockup_test
+        .with_community_token_account(&realm_cookie, &voter_cookie.voter_authority.pubkey(), 0)
+        .await;
+
+    // Act
+    let err = lockup_test
+        .withdraw(
+            &realm_cookie,
+            &voter_cookie,
+            &destination_cookie.address,
+            0,
+            1,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        err,
+        TokenLockupAddinError::InsufficientUnlockedTokens.into()
+    );
+}
+
+#[tokio::test]
+async fn test_withdraw_after_cliff_lockup_expired() {
+    // Arrange
+    let mut lockup_test = TokenLockupProgramTest::start_new().await;
+
+    let realm_cookie = lockup_test.with_realm().await;
+    lockup_test.with_registrar(&realm_cookie).await.unwrap();
+    let voter_cookie = lockup_test.with_voter(&realm_cookie).await;
+
+    lockup_test
+        .with_deposit(&realm_cookie, &voter_cookie, 100, LockupKind::Cliff, 10)
+        .await
+        .unwrap();
+
+    let destination_cookie = lockup_test
+        .with_community_token_account(&realm_cookie, &voter_cookie.voter_authority.pubkey(), 0)
+        .await;
+
+    lockup_test.advance_clock_by_min_timespan(10).await;
+
+    // Act
+    lockup_test
+        .withdraw(
+            &realm_cookie,
+            &voter_cookie,
+            &destination_cookie.address,
+            0,
+            100,
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let destination_amount = lockup_test
+        .get_token_account_amount(&destination_cookie.address)
+        .await;
+    assert_eq!(100, destination_amount);
+}
@@ -0,0 +1,27 @@
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use spl_governance_addin_token_lockup::state::registrar::Registrar;
+
+#[derive(Debug)]
+pub struct RealmCookie {
+    pub address: Pubkey,
+    pub community_mint: Pubkey,
+    pub community_mint_authority: Keypair,
+    pub realm_authority: Keypair,
+}
+
+#[derive(Debug)]
+pub struct RegistrarCookie {
+    pub address: Pubkey,
+    pub account: Registrar,
+    pub vault: Pubkey,
+    pub max_voter_weight_record: Pubkey,
+}
+
+#[derive(Debug)]
+pub struct VoterCookie {
+    pub address: Pubkey,
+    pub voter_authority: Keypair,
+    pub voter_weight_record: Pubkey,
+    pub token_owner_record: Pubkey,
+}
@@ -0,0 +1,517 @@
+use std::str::FromStr;
+
+use solana_program::{
+    clock::UnixTimestamp, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+};
+use solana_program_test::{processor, ProgramTest};
+
+use solana_sdk::{signature::Keypair, signer::Signer};
+use spl_governance::{
+    instruction::{create_governance, create_realm, create_token_owner_record},
+    state::{
+        enums::{MintMaxVoteWeightSource, VoteThreshold, VoteTipping},
+        governance::GovernanceConfig,
+        realm::get_realm_address,
+        token_owner_record::get_token_owner_record_address,
+    },
+};
+use spl_governance_addin_api::{
+    max_voter_weight::MaxVoterWeightRecord, voter_weight::VoterWeightRecord,
+};
+use spl_governance_addin_token_lockup::{
+    instruction::{
+        clawback, create_registrar, create_voter, deposit, grant, update_max_voter_weight_record,
+        update_voter_weight_record, withdraw,
+    },
+    processor::process_instruction,
+    state::{
+        enums::{LockupKind, TokenLockupAccountType},
+        registrar::{
+            get_max_voter_weight_record_address, get_registrar_address, get_vault_address,
+            Registrar, RegistrarConfig,
+        },
+        voter::{get_voter_address, get_voter_weight_record_address, Voter},
+    },
+};
+use spl_governance_test_sdk::{cookies::TokenAccountCookie, ProgramTestBench};
+
+use self::cookies::{RealmCookie, RegistrarCookie, VoterCookie};
+
+pub mod cookies;
+
+pub struct TokenLockupProgramTest {
+    pub bench: ProgramTestBench,
+    pub program_id: Pubkey,
+    pub governance_program_id: Pubkey,
+}
+
+impl TokenLockupProgramTest {
+    #[allow(dead_code)]
+    pub async fn start_new() -> Self {
+        let mut program_test = ProgramTest::default();
+
+        let program_id = Pubkey::from_str("TokenLockupAddin111111111111111111111111111").unwrap();
+        program_test.add_program(
+            "spl_governance_addin_token_lockup",
+            program_id,
+            processor!(process_instruction),
+        );
+
+        let governance_program_id =
+            Pubkey::from_str("Governance111111111111111111111111111111111").unwrap();
+        program_test.add_program(
+            "spl_governance",
+            governance_program_id,
+            processor!(spl_governance::processor::process_instruction),
+        );
+
+        let bench = ProgramTestBench::start_new(program_test).await;
+
+        Self {
+            bench,
+            program_id,
+            governance_program_id,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn get_default_registrar_config(&self) -> RegistrarConfig {
+        RegistrarConfig {
+            // Locked tokens can get up to 2x weight
+            max_extra_lockup_vote_weight_factor: RegistrarConfig::VOTE_WEIGHT_FACTOR_BASE,
+            lockup_saturation_secs: 1000,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_realm(&mut self) -> RealmCookie {
+        let name = self.bench.get_unique_name("realm");
+
+        let realm_address = get_realm_address(&self.governance_program_id, &name);
+
+        let community_mint_keypair = Keypair::new();
+        let community_mint_authority = Keypair::new();
+
+        self.bench
+            .create_mint(
+                &community_mint_keypair,
+                &community_mint_authority.pubkey(),
+                None,
+            )
+            .await;
+
+        let realm_authority = Keypair::new();
+
+        let create_realm_ix = create_realm(
+            &self.governance_program_id,
+            &realm_authority.pubkey(),
+            &community_mint_keypair.pubkey(),
+            &self.bench.payer.pubkey(),
+            None,
+            Some(self.program_id),
+            Some(self.program_id),
+            name.clone(),
+            1,
+            MintMaxVoteWeightSource::FULL_SUPPLY_FRACTION,
+        );
+
+        self.bench
+            .process_transaction(&[create_realm_ix], None)
+            .await
+            .unwrap();
+
+        RealmCookie {
+            address: realm_address,
+            community_mint: community_mint_keypair.pubkey(),
+            community_mint_authority,
+            realm_authority,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_registrar(
+        &mut self,
+        realm_cookie: &RealmCookie,
+    ) -> Result<RegistrarCookie, ProgramError> {
+        let config = self.get_default_registrar_config();
+
+        self.with_registrar_using_config(realm_cookie, &realm_cookie.realm_authority, config)
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_registrar_using_config(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        realm_authority: &Keypair,
+        config: RegistrarConfig,
+    ) -> Result<RegistrarCookie, ProgramError> {
+        let create_registrar_ix = create_registrar(
+            &self.program_id,
+            &self.governance_program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &realm_authority.pubkey(),
+            &self.bench.payer.pubkey(),
+            config.clone(),
+        );
+
+        self.bench
+            .process_transaction(&[create_registrar_ix], Some(&[realm_authority]))
+            .await?;
+
+        let registrar_address = get_registrar_address(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+        );
+
+        let account = Registrar {
+            account_type: TokenLockupAccountType::Registrar,
+            governance_program_id: self.governance_program_id,
+            realm: realm_cookie.address,
+            governing_token_mint: realm_cookie.community_mint,
+            config,
+            reserved: [0; 64],
+        };
+
+        Ok(RegistrarCookie {
+            address: registrar_address,
+            account,
+            vault: get_vault_address(&self.program_id, &registrar_address),
+            max_voter_weight_record: get_max_voter_weight_record_address(
+                &self.program_id,
+                &realm_cookie.address,
+                &realm_cookie.community_mint,
+            ),
+        })
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_voter(&mut self, realm_cookie: &RealmCookie) -> VoterCookie {
+        let voter_authority = Keypair::new();
+
+        let create_voter_ix = create_voter(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &voter_authority.pubkey(),
+            &self.bench.payer.pubkey(),
+        );
+
+        let create_token_owner_record_ix = create_token_owner_record(
+            &self.governance_program_id,
+            &realm_cookie.address,
+            &voter_authority.pubkey(),
+            &realm_cookie.community_mint,
+            &self.bench.payer.pubkey(),
+        );
+
+        self.bench
+            .process_transaction(
+                &[create_voter_ix, create_token_owner_record_ix],
+                Some(&[&voter_authority]),
+            )
+            .await
+            .unwrap();
+
+        let registrar_address = get_registrar_address(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+        );
+
+        VoterCookie {
+            address: get_voter_address(
+                &self.program_id,
+                &registrar_address,
+                &voter_authority.pubkey(),
+            ),
+            voter_weight_record: get_voter_weight_record_address(
+                &self.program_id,
+                &realm_cookie.address,
+                &realm_cookie.community_mint,
+                &voter_authority.pubkey(),
+            ),
+            token_owner_record: get_token_owner_record_address(
+                &self.governance_program_id,
+                &realm_cookie.address,
+                &realm_cookie.community_mint,
+                &voter_authority.pubkey(),
+            ),
+            voter_authority,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_community_token_account(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        owner: &Pubkey,
+        amount: u64,
+    ) -> TokenAccountCookie {
+        self.bench
+            .with_token_account(
+                &realm_cookie.community_mint,
+                owner,
+                &realm_cookie.community_mint_authority,
+                amount,
+            )
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_deposit(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        voter_cookie: &VoterCookie,
+        amount: u64,
+        lockup_kind: LockupKind,
+        lockup_period_secs: u64,
+    ) -> Result<(), ProgramError> {
+        let token_account_cookie = self
+            .with_community_token_account(
+                realm_cookie,
+                &voter_cookie.voter_authority.pubkey(),
+                amount,
+            )
+            .await;
+
+        let deposit_ix = deposit(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &voter_cookie.voter_authority.pubkey(),
+            &token_account_cookie.address,
+            &voter_cookie.voter_authority.pubkey(),
+            amount,
+            lockup_kind,
+            lockup_period_secs,
+        );
+
+        self.bench
+            .process_transaction(&[deposit_ix], Some(&[&voter_cookie.voter_authority]))
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn withdraw(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        voter_cookie: &VoterCookie,
+        destination_token_account: &Pubkey,
+        deposit_index: u8,
+        amount: u64,
+    ) -> Result<(), ProgramError> {
+        let withdraw_ix = withdraw(
+            &self.program_id,
+            &self.governance_program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &voter_cookie.voter_authority.pubkey(),
+            destination_token_account,
+            deposit_index,
+            amount,
+        );
+
+        self.bench
+            .process_transaction(&[withdraw_ix], Some(&[&voter_cookie.voter_authority]))
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_grant(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        voter_cookie: &VoterCookie,
+        amount: u64,
+        lockup_kind: LockupKind,
+        lockup_period_secs: u64,
+    ) -> Result<(), ProgramError> {
+        let grantor = Keypair::new();
+
+        let token_account_cookie = self
+            .with_community_token_account(realm_cookie, &grantor.pubkey(), amount)
+            .await;
+
+        let grant_ix = grant(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &realm_cookie.realm_authority.pubkey(),
+            &voter_cookie.voter_authority.pubkey(),
+            &token_account_cookie.address,
+            &grantor.pubkey(),
+            amount,
+            lockup_kind,
+            lockup_period_secs,
+        );
+
+        self.bench
+            .process_transaction(
+                &[grant_ix],
+                Some(&[&realm_cookie.realm_authority, &grantor]),
+            )
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn clawback(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        voter_cookie: &VoterCookie,
+        destination_token_account: &Pubkey,
+        deposit_index: u8,
+    ) -> Result<(), ProgramError> {
+        let clawback_ix = clawback(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &realm_cookie.realm_authority.pubkey(),
+            &voter_cookie.voter_authority.pubkey(),
+            destination_token_account,
+            deposit_index,
+        );
+
+        self.bench
+            .process_transaction(&[clawback_ix], Some(&[&realm_cookie.realm_authority]))
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn update_voter_weight_record(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        voter_cookie: &VoterCookie,
+    ) -> Result<(), ProgramError> {
+        let update_voter_weight_record_ix = update_voter_weight_record(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &voter_cookie.voter_authority.pubkey(),
+        );
+
+        self.bench
+            .process_transaction(&[update_voter_weight_record_ix], None)
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn update_max_voter_weight_record(
+        &mut self,
+        realm_cookie: &RealmCookie,
+    ) -> Result<(), ProgramError> {
+        let update_max_voter_weight_record_ix = update_max_voter_weight_record(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+        );
+
+        self.bench
+            .process_transaction(&[update_max_voter_weight_record_ix], None)
+            .await
+    }
+
+    /// Creates Governance using the Voter's weight
+    /// The VoterWeightRecord is updated in the same transaction because it expires at the current slot
+    #[allow(dead_code)]
+    pub async fn with_governance(
+        &mut self,
+        realm_cookie: &RealmCookie,
+        voter_cookie: &VoterCookie,
+        min_community_weight_to_create_proposal: u64,
+    ) -> Result<(), ProgramError> {
+        let update_voter_weight_record_ix = update_voter_weight_record(
+            &self.program_id,
+            &realm_cookie.address,
+            &realm_cookie.community_mint,
+            &voter_cookie.voter_authority.pubkey(),
+        );
+
+        let governance_config = GovernanceConfig {
+            min_community_weight_to_create_proposal,
+            min_council_weight_to_create_proposal: 1,
+            community_veto_vote_threshold: VoteThreshold::Disabled,
+            min_transaction_hold_up_time: 10,
+            max_voting_time: 10,
+            community_vote_threshold: VoteThreshold::YesVotePercentage(60),
+            vote_tipping: VoteTipping::Strict,
+            council_vote_threshold: VoteThreshold::YesVotePercentage(60),
+            council_veto_vote_threshold: VoteThreshold::Disabled,
+        };
+
+        let create_governance_ix = create_governance(
+            &self.governance_program_id,
+            &realm_cookie.address,
+            None,
+            &voter_cookie.token_owner_record,
+            &self.bench.payer.pubkey(),
+            &voter_cookie.voter_authority.pubkey(),
+            Some(voter_cookie.voter_weight_record),
+            governance_config,
+        );
+
+        self.bench
+            .process_transaction(
+                &[update_voter_weight_record_ix, create_governance_ix],
+                Some(&[&voter_cookie.voter_authority]),
+            )
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn advance_clock_by_min_timespan(&mut self, time_span: u64) {
+        let clock = self.bench.get_clock().await;
+        self.advance_clock_past_timestamp(clock.unix_timestamp + (time_span as i64))
+            .await;
+    }
+
+    #[allow(dead_code)]
+    pub async fn advance_clock_past_timestamp(&mut self, unix_timestamp: UnixTimestamp) {
+        let mut clock = self.bench.get_clock().await;
+        let mut n = 1;
+
+        while clock.unix_timestamp <= unix_timestamp {
+            // Since the exact time is not deterministic keep wrapping by arbitrary 400 slots until we pass the requested timestamp
+            self.bench
+                .context
+                .warp_to_slot(clock.slot + n * 400)
+                .unwrap();
+
+            n += 1;
+            clock = self.bench.get_clock().await;
+        }
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_registrar_account(&mut self, address: &Pubkey) -> Registrar {
+        self.bench.get_borsh_account::<Registrar>(address).await
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_voter_account(&mut self, address: &Pubkey) -> Voter {
+        self.bench.get_borsh_account::<Voter>(address).await
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_voter_weight_record(&mut self, address: &Pubkey) -> VoterWeightRecord {
+        self.bench
+            .get_borsh_account::<VoterWeightRecord>(address)
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_max_voter_weight_record(&mut self, address: &Pubkey) -> MaxVoterWeightRecord {
+        self.bench
+            .get_borsh_account::<MaxVoterWeightRecord>(address)
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_token_account_amount(&mut self, address: &Pubkey) -> u64 {
+        let account = self.bench.get_account(address).await.unwrap();
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+}
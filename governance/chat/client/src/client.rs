@@ -0,0 +1,77 @@
+//! RPC helpers to fetch GovernanceChat accounts
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+};
+use solana_program::{
+    account_info::IntoAccountInfo, borsh::try_from_slice_unchecked, pubkey::Pubkey,
+};
+use spl_governance_chat::state::{
+    get_chat_message_data, is_chat_message_account_type, ChatMessage, GovernanceChatAccountType,
+};
+
+use crate::{
+    error::GovernanceChatClientError,
+    thread::{thread_chat_messages, ChatMessageThread},
+};
+
+/// Returns all messages posted for the given Proposal ordered by their post time
+/// Note: Deleted messages are not returned and hidden messages are returned with is_hidden set
+pub fn get_proposal_chat_messages(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    proposal: &Pubkey,
+) -> Result<Vec<(Pubkey, ChatMessage)>, GovernanceChatClientError> {
+    let accounts = rpc_client.get_program_accounts_with_config(
+        program_id,
+        RpcProgramAccountsConfig {
+            // ChatMessage.proposal follows the account type
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp {
+                offset: 1,
+                bytes: MemcmpEncodedBytes::Base58(proposal.to_string()),
+                encoding: None,
+            })]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(rpc_client.commitment()),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    let mut chat_messages = vec![];
+
+    for (address, mut account) in accounts {
+        // Skip accounts of other types which happen to match the filter
+        match try_from_slice_unchecked::<GovernanceChatAccountType>(&account.data) {
+            Ok(account_type) if is_chat_message_account_type(&account_type) => {}
+            _ => continue,
+        }
+
+        let account_info = (&address, &mut account).into_account_info();
+
+        let chat_message = get_chat_message_data(program_id, &account_info)
+            .map_err(|err| GovernanceChatClientError::InvalidAccount(address, err))?;
+
+        chat_messages.push((address, chat_message));
+    }
+
+    chat_messages.sort_by_key(|(address, chat_message)| (chat_message.posted_at, *address));
+
+    Ok(chat_messages)
+}
+
+/// Returns all messages posted for the given Proposal arranged into threads
+pub fn get_proposal_chat_threads(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    proposal: &Pubkey,
+) -> Result<Vec<ChatMessageThread>, GovernanceChatClientError> {
+    let chat_messages = get_proposal_chat_messages(rpc_client, program_id, proposal)?;
+
+    Ok(thread_chat_messages(chat_messages))
+}
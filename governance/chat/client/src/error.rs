@@ -0,0 +1,17 @@
+//! Error types
+
+use solana_client::client_error::ClientError;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use thiserror::Error;
+
+/// Errors that may be returned by the GovernanceChat client
+#[derive(Debug, Error)]
+pub enum GovernanceChatClientError {
+    /// RPC client error
+    #[error("RPC client error: {0}")]
+    Client(#[from] ClientError),
+
+    /// Account couldn't be deserialized
+    #[error("Invalid account {0}: {1}")]
+    InvalidAccount(Pubkey, ProgramError),
+}
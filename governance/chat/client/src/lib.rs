@@ -0,0 +1,8 @@
+#![deny(missing_docs)]
+//! Governance Chat client
+
+pub mod client;
+pub mod error;
+pub mod thread;
+
+pub use spl_governance_chat;
@@ -0,0 +1,195 @@
+//! Threading of chat messages into replies and reactions
+
+use std::collections::{HashMap, HashSet};
+
+use solana_program::pubkey::Pubkey;
+use spl_governance_chat::state::{ChatMessage, MessageBody};
+
+/// Chat message with its replies and reactions
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChatMessageThread {
+    /// ChatMessage account address
+    pub address: Pubkey,
+
+    /// The message
+    pub message: ChatMessage,
+
+    /// Replies to the message ordered by their post time
+    pub replies: Vec<ChatMessageThread>,
+
+    /// Reactions to the message ordered by their post time
+    pub reactions: Vec<(Pubkey, ChatMessage)>,
+}
+
+/// Arranges the given messages into threads ordered by their post time
+/// Messages which are not replies and replies to messages which were deleted become top level threads
+/// Reactions to messages which were deleted are dropped
+pub fn thread_chat_messages(
+    mut chat_messages: Vec<(Pubkey, ChatMessage)>,
+) -> Vec<ChatMessageThread> {
+    chat_messages.sort_by_key(|(address, chat_message)| (chat_message.posted_at, *address));
+
+    let addresses: HashSet<Pubkey> = chat_messages.iter().map(|(address, _)| *address).collect();
+
+    let mut roots = vec![];
+    let mut children: HashMap<Pubkey, Vec<(Pubkey, ChatMessage)>> = HashMap::new();
+
+    for (address, chat_message) in chat_messages {
+        match chat_message.reply_to {
+            Some(reply_to) if addresses.contains(&reply_to) => {
+                children
+                    .entry(reply_to)
+                    .or_default()
+                    .push((address, chat_message));
+            }
+            _ => {
+                if let MessageBody::Text(_) = chat_message.body {
+                    roots.push((address, chat_message));
+                }
+            }
+        }
+    }
+
+    // Note: Messages forming a reply cycle (possible only when a deleted message address is reused)
+    // are not reachable from any root and are dropped
+    roots
+        .into_iter()
+        .map(|(address, message)| build_thread(address, message, &mut children))
+        .collect()
+}
+
+fn build_thread(
+    address: Pubkey,
+    message: ChatMessage,
+    children: &mut HashMap<Pubkey, Vec<(Pubkey, ChatMessage)>>,
+) -> ChatMessageThread {
+    let mut replies = vec![];
+    let mut reactions = vec![];
+
+    for (child_address, child_message) in children.remove(&address).unwrap_or_default() {
+        match child_message.body {
+            MessageBody::Text(_) => {
+                replies.push(build_thread(child_address, child_message, children))
+            }
+            MessageBody::Reaction(_) => reactions.push((child_address, child_message)),
+        }
+    }
+
+    ChatMessageThread {
+        address,
+        message,
+        replies,
+        reactions,
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use spl_governance_chat::state::GovernanceChatAccountType;
+
+    use super::*;
+
+    fn create_test_message(
+        posted_at: i64,
+        reply_to: Option<Pubkey>,
+        body: MessageBody,
+    ) -> (Pubkey, ChatMessage) {
+        (
+            Pubkey::new_unique(),
+            ChatMessage {
+                account_type: GovernanceChatAccountType::ChatMessageV2,
+                proposal: Pubkey::default(),
+                author: Pubkey::default(),
+                posted_at,
+                reply_to,
+                body,
+                edited_at: None,
+                is_hidden: false,
+            },
+        )
+    }
+
+    fn text(text: &str) -> MessageBody {
+        MessageBody::Text(text.to_string())
+    }
+
+    #[test]
+    fn test_thread_chat_messages() {
+        // Arrange
+        let message1 = create_test_message(10, None, text("1"));
+        let message2 = create_test_message(20, None, text("2"));
+        let reply1 = create_test_message(30, Some(message1.0), text("1.1"));
+        let reply2 = create_test_message(40, Some(reply1.0), text("1.1.1"));
+        let reaction1 = create_test_message(
+            50,
+            Some(message1.0),
+            MessageBody::Reaction("👍".to_string()),
+        );
+
+        // Act
+        let threads = thread_chat_messages(vec![
+            reaction1.clone(),
+            reply2.clone(),
+            message2.clone(),
+            reply1.clone(),
+            message1.clone(),
+        ]);
+
+        // Assert
+        assert_eq!(
+            threads,
+            vec![
+                ChatMessageThread {
+                    address: message1.0,
+                    message: message1.1,
+                    replies: vec![ChatMessageThread {
+                        address: reply1.0,
+                        message: reply1.1,
+                        replies: vec![ChatMessageThread {
+                            address: reply2.0,
+                            message: reply2.1,
+                            replies: vec![],
+                            reactions: vec![],
+                        }],
+                        reactions: vec![],
+                    }],
+                    reactions: vec![reaction1],
+                },
+                ChatMessageThread {
+                    address: message2.0,
+                    message: message2.1,
+                    replies: vec![],
+                    reactions: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_thread_chat_messages_with_deleted_parent() {
+        // Arrange
+        let deleted_message_address = Pubkey::new_unique();
+
+        let reply = create_test_message(10, Some(deleted_message_address), text("1.1"));
+        let reaction = create_test_message(
+            20,
+            Some(deleted_message_address),
+            MessageBody::Reaction("👍".to_string()),
+        );
+
+        // Act
+        let threads = thread_chat_messages(vec![reply.clone(), reaction]);
+
+        // Assert
+        assert_eq!(
+            threads,
+            vec![ChatMessageThread {
+                address: reply.0,
+                message: reply.1,
+                replies: vec![],
+                reactions: vec![],
+            }]
+        );
+    }
+}
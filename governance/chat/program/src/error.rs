@@ -18,6 +18,34 @@ pub enum GovernanceChatError {
     /// Account already initialized
     #[error("Account already initialized")]
     AccountAlreadyInitialized,
+
+    /// Invalid author for ChatMessage
+    #[error("Invalid author for ChatMessage")]
+    InvalidChatMessageAuthor,
+
+    /// Invalid Proposal for ChatMessage
+    #[error("Invalid Proposal for ChatMessage")]
+    InvalidProposalForChatMessage,
+
+    /// Hidden ChatMessage can't be edited
+    #[error("Hidden ChatMessage can't be edited")]
+    CannotEditHiddenChatMessage,
+
+    /// Invalid ChatConfig for Realm
+    #[error("Invalid ChatConfig for Realm")]
+    InvalidChatConfigForRealm,
+
+    /// Realm authority must sign
+    #[error("Realm authority must sign")]
+    RealmAuthorityMustSign,
+
+    /// Invalid moderator
+    #[error("Invalid moderator")]
+    InvalidModerator,
+
+    /// Moderator must sign
+    #[error("Moderator must sign")]
+    ModeratorMustSign,
 }
 
 impl PrintProgramError for GovernanceChatError {
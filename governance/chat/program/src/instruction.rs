@@ -8,7 +8,7 @@ use solana_program::{
 };
 use spl_governance::instruction::with_realm_config_accounts;
 
-use crate::state::MessageBody;
+use crate::state::{get_chat_config_address, MessageBody};
 
 /// Instructions supported by the GovernanceChat program
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
@@ -18,15 +18,19 @@ pub enum GovernanceChatInstruction {
     ///
     ///   0. `[]` Governance program id
     ///   1. `[]` Realm account of the Proposal
-    ///   2. `[]` Governance account the Proposal is for    
-    ///   3. `[]` Proposal account   
+    ///   2. `[]` Governance account the Proposal is for
+    ///   3. `[]` Proposal account
     ///   4. `[]` TokenOwnerRecord account for the message author
     ///   5. `[signer]` Governance Authority (TokenOwner or Governance Delegate)
     ///   6. `[writable, signer]` ChatMessage account
-    ///   7. `[signer]` Payer    
-    ///   8. `[]` System program    
-    ///   9. `[]` ReplyTo Message account (optional)  
-    ///    10. `[]` Optional Voter Weight Record
+    ///   7. `[signer]` Payer
+    ///   8. `[]` System program
+    ///   9. `[]` ReplyTo Message account (optional)
+    ///   10. `[]` RealmConfig account. PDA seeds: ['realm-config', realm]
+    ///   11. `[]` Optional Voter Weight Record
+    ///   12. `[]` Optional ChatConfig account of the Realm. PDA seeds: ['chat-config', realm]
+    ///           The account doesn't have to exist and the default config is used in such case
+    ///           Clients which omit it post with the default config
     PostMessage {
         #[allow(dead_code)]
         /// Message body (text or reaction)
@@ -37,6 +41,71 @@ pub enum GovernanceChatInstruction {
         /// If yes then ReplyTo Message account has to be provided
         is_reply: bool,
     },
+
+    /// Edits the body of a message
+    /// Only the message author (TokenOwner or Governance Delegate) can edit the message
+    ///
+    ///   0. `[]` Governance program id
+    ///   1. `[]` Governance account the Proposal is for
+    ///   2. `[]` Proposal account
+    ///   3. `[]` TokenOwnerRecord account of the message author
+    ///   4. `[signer]` Governance Authority (TokenOwner or Governance Delegate)
+    ///   5. `[writable]` ChatMessage account
+    ///   6. `[signer]` Payer
+    ///   7. `[]` System program
+    EditMessage {
+        #[allow(dead_code)]
+        /// New message body
+        body: MessageBody,
+    },
+
+    /// Deletes a message and disposes its account
+    /// Only the message author (TokenOwner or Governance Delegate) can delete the message
+    ///
+    ///   0. `[]` Governance program id
+    ///   1. `[]` Governance account the Proposal is for
+    ///   2. `[]` Proposal account
+    ///   3. `[]` TokenOwnerRecord account of the message author
+    ///   4. `[signer]` Governance Authority (TokenOwner or Governance Delegate)
+    ///   5. `[writable]` ChatMessage account
+    ///   6. `[writable]` Beneficiary account which would receive lamports from the disposed ChatMessage account
+    DeleteMessage {},
+
+    /// Hides or unhides a message
+    /// The moderator must be either the Realm authority or the moderator governance configured in ChatConfig
+    ///
+    ///   0. `[]` Governance program id
+    ///   1. `[]` Realm account of the Proposal
+    ///   2. `[]` Governance account the Proposal is for
+    ///   3. `[]` Proposal account
+    ///   4. `[]` ChatConfig account of the Realm. PDA seeds: ['chat-config', realm]
+    ///   5. `[signer]` Moderator (Realm authority or moderator Governance)
+    ///   6. `[writable]` ChatMessage account
+    ///   7. `[signer]` Payer
+    ///   8. `[]` System program
+    HideMessage {
+        #[allow(dead_code)]
+        /// Indicates whether the message should be hidden or unhidden
+        is_hidden: bool,
+    },
+
+    /// Creates or updates the chat configuration of a Realm
+    ///
+    ///   0. `[]` Governance program id
+    ///   1. `[]` Realm account
+    ///   2. `[signer]` Realm authority
+    ///   3. `[writable]` ChatConfig account. PDA seeds: ['chat-config', realm]
+    ///   4. `[signer]` Payer
+    ///   5. `[]` System program
+    SetChatConfig {
+        #[allow(dead_code)]
+        /// Minimum voter weight a governing token owner must have to post messages
+        min_weight_to_post: u64,
+
+        #[allow(dead_code)]
+        /// Governance which can hide messages in addition to the Realm authority
+        moderator_governance: Option<Pubkey>,
+    },
 }
 
 /// Creates PostMessage instruction
@@ -57,6 +126,8 @@ pub fn post_message(
     // Args
     body: MessageBody,
 ) -> Instruction {
+    let chat_config_address = get_chat_config_address(program_id, realm);
+
     let mut accounts = vec![
         AccountMeta::new_readonly(*governance_program_id, false),
         AccountMeta::new_readonly(*realm, false),
@@ -84,6 +155,8 @@ pub fn post_message(
         None,
     );
 
+    accounts.push(AccountMeta::new_readonly(chat_config_address, false));
+
     let instruction = GovernanceChatInstruction::PostMessage { body, is_reply };
 
     Instruction {
@@ -92,3 +165,143 @@ pub fn post_message(
         data: instruction.try_to_vec().unwrap(),
     }
 }
+
+/// Creates EditMessage instruction
+#[allow(clippy::too_many_arguments)]
+pub fn edit_message(
+    program_id: &Pubkey,
+    // Accounts
+    governance_program_id: &Pubkey,
+    governance: &Pubkey,
+    proposal: &Pubkey,
+    token_owner_record: &Pubkey,
+    governance_authority: &Pubkey,
+    chat_message: &Pubkey,
+    payer: &Pubkey,
+    // Args
+    body: MessageBody,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*governance_program_id, false),
+        AccountMeta::new_readonly(*governance, false),
+        AccountMeta::new_readonly(*proposal, false),
+        AccountMeta::new_readonly(*token_owner_record, false),
+        AccountMeta::new_readonly(*governance_authority, true),
+        AccountMeta::new(*chat_message, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let instruction = GovernanceChatInstruction::EditMessage { body };
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates DeleteMessage instruction
+#[allow(clippy::too_many_arguments)]
+pub fn delete_message(
+    program_id: &Pubkey,
+    // Accounts
+    governance_program_id: &Pubkey,
+    governance: &Pubkey,
+    proposal: &Pubkey,
+    token_owner_record: &Pubkey,
+    governance_authority: &Pubkey,
+    chat_message: &Pubkey,
+    beneficiary: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*governance_program_id, false),
+        AccountMeta::new_readonly(*governance, false),
+        AccountMeta::new_readonly(*proposal, false),
+        AccountMeta::new_readonly(*token_owner_record, false),
+        AccountMeta::new_readonly(*governance_authority, true),
+        AccountMeta::new(*chat_message, false),
+        AccountMeta::new(*beneficiary, false),
+    ];
+
+    let instruction = GovernanceChatInstruction::DeleteMessage {};
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates HideMessage instruction
+#[allow(clippy::too_many_arguments)]
+pub fn hide_message(
+    program_id: &Pubkey,
+    // Accounts
+    governance_program_id: &Pubkey,
+    realm: &Pubkey,
+    governance: &Pubkey,
+    proposal: &Pubkey,
+    moderator: &Pubkey,
+    chat_message: &Pubkey,
+    payer: &Pubkey,
+    // Args
+    is_hidden: bool,
+) -> Instruction {
+    let chat_config_address = get_chat_config_address(program_id, realm);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*governance_program_id, false),
+        AccountMeta::new_readonly(*realm, false),
+        AccountMeta::new_readonly(*governance, false),
+        AccountMeta::new_readonly(*proposal, false),
+        AccountMeta::new_readonly(chat_config_address, false),
+        AccountMeta::new_readonly(*moderator, true),
+        AccountMeta::new(*chat_message, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let instruction = GovernanceChatInstruction::HideMessage { is_hidden };
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
+/// Creates SetChatConfig instruction
+pub fn set_chat_config(
+    program_id: &Pubkey,
+    // Accounts
+    governance_program_id: &Pubkey,
+    realm: &Pubkey,
+    realm_authority: &Pubkey,
+    payer: &Pubkey,
+    // Args
+    min_weight_to_post: u64,
+    moderator_governance: Option<Pubkey>,
+) -> Instruction {
+    let chat_config_address = get_chat_config_address(program_id, realm);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*governance_program_id, false),
+        AccountMeta::new_readonly(*realm, false),
+        AccountMeta::new_readonly(*realm_authority, true),
+        AccountMeta::new(chat_config_address, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let instruction = GovernanceChatInstruction::SetChatConfig {
+        min_weight_to_post,
+        moderator_governance,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
@@ -3,25 +3,37 @@
 use crate::{
     error::GovernanceChatError,
     instruction::GovernanceChatInstruction,
-    state::{assert_is_valid_chat_message, ChatMessage, GovernanceChatAccountType, MessageBody},
+    state::{
+        assert_is_valid_chat_message, get_chat_config_address_seeds,
+        get_chat_config_data_for_realm, get_chat_message_data_for_proposal, ChatConfig,
+        ChatMessage, GovernanceChatAccountType, MessageBody,
+    },
 };
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
     entrypoint::ProgramResult,
     msg,
+    program::invoke,
     program_error::ProgramError,
     pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
     sysvar::Sysvar,
 };
 use spl_governance::state::{
-    governance::get_governance_data_for_realm, proposal::get_proposal_data_for_governance,
-    realm::get_realm_data, token_owner_record::get_token_owner_record_data_for_realm,
+    governance::{get_governance_data, get_governance_data_for_realm},
+    proposal::get_proposal_data_for_governance,
+    realm::{get_realm_data, get_realm_data_for_authority},
+    token_owner_record::get_token_owner_record_data_for_realm,
 };
 use spl_governance_addin_api::voter_weight::VoterWeightAction;
-use spl_governance_tools::account::create_and_serialize_account;
+use spl_governance_tools::account::{
+    create_and_serialize_account, create_and_serialize_account_signed, dispose_account,
+    AccountMaxSize,
+};
 
 /// Processes an instruction
 pub fn process_instruction(
@@ -39,6 +51,30 @@ pub fn process_instruction(
             msg!("GOVERNANCE-CHAT-INSTRUCTION: PostMessage");
             process_post_message(program_id, accounts, body, is_reply)
         }
+        GovernanceChatInstruction::EditMessage { body } => {
+            msg!("GOVERNANCE-CHAT-INSTRUCTION: EditMessage");
+            process_edit_message(program_id, accounts, body)
+        }
+        GovernanceChatInstruction::DeleteMessage {} => {
+            msg!("GOVERNANCE-CHAT-INSTRUCTION: DeleteMessage");
+            process_delete_message(program_id, accounts)
+        }
+        GovernanceChatInstruction::HideMessage { is_hidden } => {
+            msg!("GOVERNANCE-CHAT-INSTRUCTION: HideMessage");
+            process_hide_message(program_id, accounts, is_hidden)
+        }
+        GovernanceChatInstruction::SetChatConfig {
+            min_weight_to_post,
+            moderator_governance,
+        } => {
+            msg!("GOVERNANCE-CHAT-INSTRUCTION: SetChatConfig");
+            process_set_chat_config(
+                program_id,
+                accounts,
+                min_weight_to_post,
+                moderator_governance,
+            )
+        }
     }
 }
 
@@ -104,22 +140,30 @@ pub fn process_post_message(
         proposal_info.key,
     )?;
 
-    // The owner needs to have at least voter weight of 1 to comment on proposals
+    // ChatConfig comes last and is optional to keep the accounts of older clients valid
+    let chat_config_data = if let Some(chat_config_info) = account_info_iter.next() {
+        get_chat_config_data_for_realm(program_id, chat_config_info, realm_info.key)?
+    } else {
+        ChatConfig::new_default(realm_info.key)
+    };
+
+    // The owner needs to have at least the voter weight configured for the realm to comment on proposals
     // Note: It can be either community or council token and is irrelevant to the proposal's governing token
-    // Note: 1 is currently hardcoded but if different level is required then it should be added to realm config
-    if voter_weight < 1 {
+    if voter_weight < chat_config_data.min_weight_to_post {
         return Err(GovernanceChatError::NotEnoughTokensToCommentProposal.into());
     }
 
     let clock = Clock::get()?;
 
     let chat_message_data = ChatMessage {
-        account_type: GovernanceChatAccountType::ChatMessage,
+        account_type: GovernanceChatAccountType::ChatMessageV2,
         proposal: *proposal_info.key,
         author: token_owner_record_data.governing_token_owner,
         posted_at: clock.unix_timestamp,
         reply_to: reply_to_address,
         body,
+        edited_at: None,
+        is_hidden: false,
     };
 
     create_and_serialize_account(
@@ -132,3 +176,259 @@ pub fn process_post_message(
 
     Ok(())
 }
+
+/// Processes EditMessage instruction
+pub fn process_edit_message(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    body: MessageBody,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let governance_program_info = next_account_info(account_info_iter)?; // 0
+    let governance_info = next_account_info(account_info_iter)?; // 1
+    let proposal_info = next_account_info(account_info_iter)?; // 2
+    let token_owner_record_info = next_account_info(account_info_iter)?; // 3
+    let governance_authority_info = next_account_info(account_info_iter)?; // 4
+
+    let chat_message_info = next_account_info(account_info_iter)?; // 5
+
+    let payer_info = next_account_info(account_info_iter)?; // 6
+    let system_info = next_account_info(account_info_iter)?; // 7
+
+    let mut chat_message_data = get_chat_message_data_for_author(
+        program_id,
+        governance_program_info.key,
+        governance_info,
+        proposal_info,
+        token_owner_record_info,
+        governance_authority_info,
+        chat_message_info,
+    )?;
+
+    if chat_message_data.is_hidden {
+        return Err(GovernanceChatError::CannotEditHiddenChatMessage.into());
+    }
+
+    let clock = Clock::get()?;
+
+    chat_message_data.body = body;
+    chat_message_data.edited_at = Some(clock.unix_timestamp);
+
+    serialize_chat_message(
+        &mut chat_message_data,
+        chat_message_info,
+        payer_info,
+        system_info,
+    )?;
+
+    Ok(())
+}
+
+/// Processes DeleteMessage instruction
+pub fn process_delete_message(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let governance_program_info = next_account_info(account_info_iter)?; // 0
+    let governance_info = next_account_info(account_info_iter)?; // 1
+    let proposal_info = next_account_info(account_info_iter)?; // 2
+    let token_owner_record_info = next_account_info(account_info_iter)?; // 3
+    let governance_authority_info = next_account_info(account_info_iter)?; // 4
+
+    let chat_message_info = next_account_info(account_info_iter)?; // 5
+    let beneficiary_info = next_account_info(account_info_iter)?; // 6
+
+    get_chat_message_data_for_author(
+        program_id,
+        governance_program_info.key,
+        governance_info,
+        proposal_info,
+        token_owner_record_info,
+        governance_authority_info,
+        chat_message_info,
+    )?;
+
+    dispose_account(chat_message_info, beneficiary_info);
+
+    Ok(())
+}
+
+/// Processes HideMessage instruction
+pub fn process_hide_message(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    is_hidden: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let governance_program_info = next_account_info(account_info_iter)?; // 0
+    let realm_info = next_account_info(account_info_iter)?; // 1
+    let governance_info = next_account_info(account_info_iter)?; // 2
+    let proposal_info = next_account_info(account_info_iter)?; // 3
+    let chat_config_info = next_account_info(account_info_iter)?; // 4
+    let moderator_info = next_account_info(account_info_iter)?; // 5
+
+    let chat_message_info = next_account_info(account_info_iter)?; // 6
+
+    let payer_info = next_account_info(account_info_iter)?; // 7
+    let system_info = next_account_info(account_info_iter)?; // 8
+
+    let governance_program_id = governance_program_info.key;
+    let realm_data = get_realm_data(governance_program_id, realm_info)?;
+
+    // deserialize governance and proposal to assert the proposal belongs to the given realm
+    get_governance_data_for_realm(governance_program_id, governance_info, realm_info.key)?;
+    get_proposal_data_for_governance(governance_program_id, proposal_info, governance_info.key)?;
+
+    let chat_config_data =
+        get_chat_config_data_for_realm(program_id, chat_config_info, realm_info.key)?;
+
+    // The realm authority and the moderator governance configured for the realm can moderate messages
+    if realm_data.authority != Some(*moderator_info.key)
+        && chat_config_data.moderator_governance != Some(*moderator_info.key)
+    {
+        return Err(GovernanceChatError::InvalidModerator.into());
+    }
+
+    if !moderator_info.is_signer {
+        return Err(GovernanceChatError::ModeratorMustSign.into());
+    }
+
+    let mut chat_message_data =
+        get_chat_message_data_for_proposal(program_id, chat_message_info, proposal_info.key)?;
+
+    chat_message_data.is_hidden = is_hidden;
+
+    serialize_chat_message(
+        &mut chat_message_data,
+        chat_message_info,
+        payer_info,
+        system_info,
+    )?;
+
+    Ok(())
+}
+
+/// Processes SetChatConfig instruction
+pub fn process_set_chat_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    min_weight_to_post: u64,
+    moderator_governance: Option<Pubkey>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let governance_program_info = next_account_info(account_info_iter)?; // 0
+    let realm_info = next_account_info(account_info_iter)?; // 1
+    let realm_authority_info = next_account_info(account_info_iter)?; // 2
+    let chat_config_info = next_account_info(account_info_iter)?; // 3
+
+    let payer_info = next_account_info(account_info_iter)?; // 4
+    let system_info = next_account_info(account_info_iter)?; // 5
+
+    get_realm_data_for_authority(
+        governance_program_info.key,
+        realm_info,
+        realm_authority_info.key,
+    )?;
+
+    if !realm_authority_info.is_signer {
+        return Err(GovernanceChatError::RealmAuthorityMustSign.into());
+    }
+
+    let mut chat_config_data =
+        get_chat_config_data_for_realm(program_id, chat_config_info, realm_info.key)?;
+
+    chat_config_data.min_weight_to_post = min_weight_to_post;
+    chat_config_data.moderator_governance = moderator_governance;
+
+    if chat_config_info.data_is_empty() {
+        let rent = Rent::get()?;
+
+        create_and_serialize_account_signed::<ChatConfig>(
+            payer_info,
+            chat_config_info,
+            &chat_config_data,
+            &get_chat_config_address_seeds(realm_info.key),
+            program_id,
+            system_info,
+            &rent,
+        )?;
+    } else {
+        chat_config_data.serialize(&mut *chat_config_info.data.borrow_mut())?;
+    }
+
+    Ok(())
+}
+
+/// Deserializes ChatMessage and asserts the given governance authority is the message author or its delegate
+/// The TokenOwnerRecord of the author must belong to the realm of the message Proposal
+fn get_chat_message_data_for_author(
+    program_id: &Pubkey,
+    governance_program_id: &Pubkey,
+    governance_info: &AccountInfo,
+    proposal_info: &AccountInfo,
+    token_owner_record_info: &AccountInfo,
+    governance_authority_info: &AccountInfo,
+    chat_message_info: &AccountInfo,
+) -> Result<ChatMessage, ProgramError> {
+    let governance_data = get_governance_data(governance_program_id, governance_info)?;
+
+    get_proposal_data_for_governance(governance_program_id, proposal_info, governance_info.key)?;
+
+    let token_owner_record_data = get_token_owner_record_data_for_realm(
+        governance_program_id,
+        token_owner_record_info,
+        &governance_data.realm,
+    )?;
+
+    token_owner_record_data.assert_token_owner_or_delegate_is_signer(governance_authority_info)?;
+
+    let chat_message_data =
+        get_chat_message_data_for_proposal(program_id, chat_message_info, proposal_info.key)?;
+
+    if chat_message_data.author != token_owner_record_data.governing_token_owner {
+        return Err(GovernanceChatError::InvalidChatMessageAuthor.into());
+    }
+
+    Ok(chat_message_data)
+}
+
+/// Serializes ChatMessage into its account as the current account version
+/// If the account is too small for the message (V1 account or longer body) then it's resized
+/// and the payer tops it up to stay rent exempt
+fn serialize_chat_message<'a>(
+    chat_message_data: &mut ChatMessage,
+    chat_message_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    chat_message_data.account_type = GovernanceChatAccountType::ChatMessageV2;
+
+    let account_size = chat_message_data.get_max_size().unwrap();
+
+    if account_size > chat_message_info.data_len() {
+        let rent = Rent::get()?;
+
+        let lamports = rent
+            .minimum_balance(account_size)
+            .saturating_sub(chat_message_info.lamports());
+
+        if lamports > 0 {
+            invoke(
+                &system_instruction::transfer(payer_info.key, chat_message_info.key, lamports),
+                &[
+                    payer_info.clone(),
+                    chat_message_info.clone(),
+                    system_info.clone(),
+                ],
+            )?;
+        }
+
+        chat_message_info.realloc(account_size, false)?;
+    }
+
+    chat_message_data.serialize(&mut *chat_message_info.data.borrow_mut())?;
+
+    Ok(())
+}
@@ -2,10 +2,16 @@
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use solana_program::{
-    account_info::AccountInfo, clock::UnixTimestamp, program_error::ProgramError, pubkey::Pubkey,
+    account_info::AccountInfo, borsh::try_from_slice_unchecked, clock::UnixTimestamp,
+    program_error::ProgramError, program_pack::IsInitialized, pubkey::Pubkey,
 };
 
-use spl_governance_tools::account::{assert_is_valid_account_of_type, AccountMaxSize};
+use spl_governance_tools::{
+    account::{assert_is_valid_account_of_types, get_account_data, AccountMaxSize},
+    error::GovernanceToolsError,
+};
+
+use crate::error::GovernanceChatError;
 
 /// Defines all GovernanceChat accounts types
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
@@ -15,6 +21,13 @@ pub enum GovernanceChatAccountType {
 
     /// Chat message
     ChatMessage,
+
+    /// Chat message
+    /// V2 adds edited_at and is_hidden
+    ChatMessageV2,
+
+    /// Chat configuration of a Realm
+    ChatConfig,
 }
 
 /// Chat message body
@@ -28,6 +41,15 @@ pub enum MessageBody {
     Reaction(String),
 }
 
+impl MessageBody {
+    fn get_size(&self) -> usize {
+        match self {
+            MessageBody::Text(body) => body.len(),
+            MessageBody::Reaction(body) => body.len(),
+        }
+    }
+}
+
 /// Chat message
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
 pub struct ChatMessage {
@@ -48,16 +70,59 @@ pub struct ChatMessage {
 
     /// Body of the message
     pub body: MessageBody,
+
+    /// The time the message was last edited by its author
+    pub edited_at: Option<UnixTimestamp>,
+
+    /// Indicates whether the message was hidden by a moderator
+    pub is_hidden: bool,
 }
 
 impl AccountMaxSize for ChatMessage {
     fn get_max_size(&self) -> Option<usize> {
-        let body_size = match &self.body {
-            MessageBody::Text(body) => body.len(),
-            MessageBody::Reaction(body) => body.len(),
-        };
+        Some(self.body.get_size() + 121)
+    }
+}
 
-        Some(body_size + 111)
+impl IsInitialized for ChatMessage {
+    fn is_initialized(&self) -> bool {
+        self.account_type == GovernanceChatAccountType::ChatMessageV2
+    }
+}
+
+/// Chat message account layout before edited_at and is_hidden were introduced
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct ChatMessageV1 {
+    /// Account type
+    pub account_type: GovernanceChatAccountType,
+
+    /// The proposal the message is for
+    pub proposal: Pubkey,
+
+    /// Author of the message
+    pub author: Pubkey,
+
+    /// Message timestamp
+    pub posted_at: UnixTimestamp,
+
+    /// Parent message
+    pub reply_to: Option<Pubkey>,
+
+    /// Body of the message
+    pub body: MessageBody,
+}
+
+impl IsInitialized for ChatMessageV1 {
+    fn is_initialized(&self) -> bool {
+        self.account_type == GovernanceChatAccountType::ChatMessage
+    }
+}
+
+/// Checks whether the given account type is one of the chat message account types
+pub fn is_chat_message_account_type(account_type: &GovernanceChatAccountType) -> bool {
+    match account_type {
+        GovernanceChatAccountType::ChatMessage | GovernanceChatAccountType::ChatMessageV2 => true,
+        GovernanceChatAccountType::Uninitialized | GovernanceChatAccountType::ChatConfig => false,
     }
 }
 
@@ -66,11 +131,130 @@ pub fn assert_is_valid_chat_message(
     program_id: &Pubkey,
     chat_message_info: &AccountInfo,
 ) -> Result<(), ProgramError> {
-    assert_is_valid_account_of_type(
-        program_id,
-        chat_message_info,
-        GovernanceChatAccountType::ChatMessage,
-    )
+    assert_is_valid_account_of_types(program_id, chat_message_info, is_chat_message_account_type)
+}
+
+/// Deserializes chat message account and checks owner program
+/// V1 accounts are translated to the current version
+pub fn get_chat_message_data(
+    program_id: &Pubkey,
+    chat_message_info: &AccountInfo,
+) -> Result<ChatMessage, ProgramError> {
+    if chat_message_info.data_is_empty() {
+        return Err(GovernanceToolsError::AccountDoesNotExist.into());
+    }
+
+    let account_type: GovernanceChatAccountType =
+        try_from_slice_unchecked(&chat_message_info.data.borrow())?;
+
+    if account_type == GovernanceChatAccountType::ChatMessage {
+        let chat_message_data_v1 =
+            get_account_data::<ChatMessageV1>(program_id, chat_message_info)?;
+
+        return Ok(ChatMessage {
+            account_type,
+            proposal: chat_message_data_v1.proposal,
+            author: chat_message_data_v1.author,
+            posted_at: chat_message_data_v1.posted_at,
+            reply_to: chat_message_data_v1.reply_to,
+            body: chat_message_data_v1.body,
+            edited_at: None,
+            is_hidden: false,
+        });
+    }
+
+    get_account_data::<ChatMessage>(program_id, chat_message_info)
+}
+
+/// Deserializes chat message account and asserts it belongs to the given Proposal
+pub fn get_chat_message_data_for_proposal(
+    program_id: &Pubkey,
+    chat_message_info: &AccountInfo,
+    proposal: &Pubkey,
+) -> Result<ChatMessage, ProgramError> {
+    let chat_message_data = get_chat_message_data(program_id, chat_message_info)?;
+
+    if chat_message_data.proposal != *proposal {
+        return Err(GovernanceChatError::InvalidProposalForChatMessage.into());
+    }
+
+    Ok(chat_message_data)
+}
+
+/// Chat configuration of a Realm
+#[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct ChatConfig {
+    /// Account type
+    pub account_type: GovernanceChatAccountType,
+
+    /// The Realm the config belongs to
+    pub realm: Pubkey,
+
+    /// Minimum voter weight a governing token owner must have to post messages
+    /// The voter weight is resolved using the Realm voter weight addin if configured
+    pub min_weight_to_post: u64,
+
+    /// Governance which can hide messages in addition to the Realm authority
+    pub moderator_governance: Option<Pubkey>,
+
+    /// Reserved space for future versions
+    pub reserved: [u8; 64],
+}
+
+impl AccountMaxSize for ChatConfig {
+    fn get_max_size(&self) -> Option<usize> {
+        Some(138)
+    }
+}
+
+impl IsInitialized for ChatConfig {
+    fn is_initialized(&self) -> bool {
+        self.account_type == GovernanceChatAccountType::ChatConfig
+    }
+}
+
+impl ChatConfig {
+    /// Default min voter weight required to post messages when the Realm has no ChatConfig
+    pub const DEFAULT_MIN_WEIGHT_TO_POST: u64 = 1;
+
+    /// Returns the default config used for Realms without ChatConfig account
+    pub fn new_default(realm: &Pubkey) -> Self {
+        ChatConfig {
+            account_type: GovernanceChatAccountType::ChatConfig,
+            realm: *realm,
+            min_weight_to_post: ChatConfig::DEFAULT_MIN_WEIGHT_TO_POST,
+            moderator_governance: None,
+            reserved: [0; 64],
+        }
+    }
+}
+
+/// Deserializes ChatConfig account and asserts it's the ChatConfig PDA of the given Realm
+/// If the account doesn't exist then the default config is returned
+pub fn get_chat_config_data_for_realm(
+    program_id: &Pubkey,
+    chat_config_info: &AccountInfo,
+    realm: &Pubkey,
+) -> Result<ChatConfig, ProgramError> {
+    if *chat_config_info.key != get_chat_config_address(program_id, realm) {
+        return Err(GovernanceChatError::InvalidChatConfigForRealm.into());
+    }
+
+    if chat_config_info.data_is_empty() {
+        return Ok(ChatConfig::new_default(realm));
+    }
+
+    get_account_data::<ChatConfig>(program_id, chat_config_info)
+}
+
+/// Returns ChatConfig PDA seeds
+pub fn get_chat_config_address_seeds(realm: &Pubkey) -> [&[u8]; 2] {
+    [b"chat-config", realm.as_ref()]
+}
+
+/// Returns ChatConfig PDA address
+pub fn get_chat_config_address(program_id: &Pubkey, realm: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&get_chat_config_address_seeds(realm), program_id).0
 }
 
 #[cfg(test)]
@@ -81,15 +265,76 @@ mod test {
     #[test]
     fn test_max_size() {
         let message = ChatMessage {
-            account_type: GovernanceChatAccountType::ChatMessage,
+            account_type: GovernanceChatAccountType::ChatMessageV2,
             proposal: Pubkey::new_unique(),
             author: Pubkey::new_unique(),
             posted_at: 10,
             reply_to: Some(Pubkey::new_unique()),
             body: MessageBody::Text("message".to_string()),
+            edited_at: Some(20),
+            is_hidden: false,
         };
         let size = message.try_to_vec().unwrap().len();
 
         assert_eq!(message.get_max_size(), Some(size));
     }
+
+    #[test]
+    fn test_chat_config_max_size() {
+        let chat_config = ChatConfig {
+            moderator_governance: Some(Pubkey::new_unique()),
+            ..ChatConfig::new_default(&Pubkey::new_unique())
+        };
+        let size = chat_config.try_to_vec().unwrap().len();
+
+        assert_eq!(chat_config.get_max_size(), Some(size));
+    }
+
+    #[test]
+    fn test_get_chat_message_data_with_v1_account() {
+        // Arrange
+        let program_id = Pubkey::new_unique();
+        let chat_message_address = Pubkey::new_unique();
+
+        let message_v1 = ChatMessageV1 {
+            account_type: GovernanceChatAccountType::ChatMessage,
+            proposal: Pubkey::new_unique(),
+            author: Pubkey::new_unique(),
+            posted_at: 10,
+            reply_to: None,
+            body: MessageBody::Reaction("👍".to_string()),
+        };
+
+        let mut lamports = 0;
+        let mut data = message_v1.try_to_vec().unwrap();
+
+        let chat_message_info = AccountInfo::new(
+            &chat_message_address,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &program_id,
+            false,
+            0,
+        );
+
+        // Act
+        let message = get_chat_message_data(&program_id, &chat_message_info).unwrap();
+
+        // Assert
+        assert_eq!(
+            message,
+            ChatMessage {
+                account_type: GovernanceChatAccountType::ChatMessage,
+                proposal: message_v1.proposal,
+                author: message_v1.author,
+                posted_at: message_v1.posted_at,
+                reply_to: None,
+                body: message_v1.body,
+                edited_at: None,
+                is_hidden: false,
+            }
+        );
+    }
 }
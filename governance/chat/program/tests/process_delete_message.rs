@@ -0,0 +1,95 @@
+#![cfg(feature = "test-bpf")]
+
+use program_test::GovernanceChatProgramTest;
+use solana_program_test::tokio;
+use solana_sdk::signature::Keypair;
+use spl_governance::error::GovernanceError;
+use spl_governance_chat::error::GovernanceChatError;
+
+mod program_test;
+
+#[tokio::test]
+async fn test_delete_message() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let proposal_cookie = governance_chat_test.with_proposal().await;
+
+    let chat_message_cookie = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .unwrap();
+
+    // Act
+    governance_chat_test
+        .delete_message(&proposal_cookie, &chat_message_cookie)
+        .await
+        .unwrap();
+
+    // Assert
+    let chat_message_account = governance_chat_test
+        .bench
+        .get_account(&chat_message_cookie.address)
+        .await;
+
+    assert_eq!(None, chat_message_account);
+}
+
+#[tokio::test]
+async fn test_delete_message_with_owner_or_delegate_must_sign_error() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let mut proposal_cookie = governance_chat_test.with_proposal().await;
+
+    let chat_message_cookie = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .unwrap();
+
+    proposal_cookie.token_owner = Keypair::new();
+
+    // Act
+    let err = governance_chat_test
+        .delete_message(&proposal_cookie, &chat_message_cookie)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        err,
+        GovernanceError::GoverningTokenOwnerOrDelegateMustSign.into()
+    );
+}
+
+#[tokio::test]
+async fn test_delete_message_with_invalid_author_error() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let mut proposal_cookie = governance_chat_test.with_proposal().await;
+
+    let chat_message_cookie = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .unwrap();
+
+    // Try to delete the message using another token owner of the same realm
+    let token_owner_record_cookie = governance_chat_test
+        .with_token_owner_deposit(&proposal_cookie, 100)
+        .await;
+
+    proposal_cookie.token_owner_record_address = token_owner_record_cookie.address;
+    proposal_cookie.token_owner = token_owner_record_cookie.token_owner;
+
+    // Act
+    let err = governance_chat_test
+        .delete_message(&proposal_cookie, &chat_message_cookie)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, GovernanceChatError::InvalidChatMessageAuthor.into());
+}
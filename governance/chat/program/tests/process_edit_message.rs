@@ -0,0 +1,175 @@
+#![cfg(feature = "test-bpf")]
+
+use program_test::GovernanceChatProgramTest;
+use solana_program_test::tokio;
+use solana_sdk::signature::Keypair;
+use spl_governance::error::GovernanceError;
+use spl_governance_chat::{error::GovernanceChatError, state::MessageBody};
+
+mod program_test;
+
+#[tokio::test]
+async fn test_edit_message() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let proposal_cookie = governance_chat_test.with_proposal().await;
+
+    let mut chat_message_cookie = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .unwrap();
+
+    // Act
+    governance_chat_test
+        .edit_message(
+            &proposal_cookie,
+            &mut chat_message_cookie,
+            MessageBody::Text("My edited and longer comment".to_string()),
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let chat_message_data = governance_chat_test
+        .get_message_account(&chat_message_cookie.address)
+        .await;
+
+    assert_eq!(chat_message_data, chat_message_cookie.account);
+    assert!(chat_message_data.edited_at.is_some());
+}
+
+#[tokio::test]
+async fn test_edit_message_with_owner_or_delegate_must_sign_error() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let mut proposal_cookie = governance_chat_test.with_proposal().await;
+
+    let mut chat_message_cookie = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .unwrap();
+
+    proposal_cookie.token_owner = Keypair::new();
+
+    // Act
+    let err = governance_chat_test
+        .edit_message(
+            &proposal_cookie,
+            &mut chat_message_cookie,
+            MessageBody::Text("My edited comment".to_string()),
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        err,
+        GovernanceError::GoverningTokenOwnerOrDelegateMustSign.into()
+    );
+}
+
+#[tokio::test]
+async fn test_edit_message_with_invalid_author_error() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let mut proposal_cookie = governance_chat_test.with_proposal().await;
+
+    let mut chat_message_cookie = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .unwrap();
+
+    // Try to edit the message using another token owner of the same realm
+    let token_owner_record_cookie = governance_chat_test
+        .with_token_owner_deposit(&proposal_cookie, 100)
+        .await;
+
+    proposal_cookie.token_owner_record_address = token_owner_record_cookie.address;
+    proposal_cookie.token_owner = token_owner_record_cookie.token_owner;
+
+    // Act
+    let err = governance_chat_test
+        .edit_message(
+            &proposal_cookie,
+            &mut chat_message_cookie,
+            MessageBody::Text("My edited comment".to_string()),
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, GovernanceChatError::InvalidChatMessageAuthor.into());
+}
+
+#[tokio::test]
+async fn test_edit_message_with_invalid_proposal_error() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let proposal_cookie1 = governance_chat_test.with_proposal().await;
+    let proposal_cookie2 = governance_chat_test.with_proposal().await;
+
+    let mut chat_message_cookie = governance_chat_test
+        .with_chat_message(&proposal_cookie1, None)
+        .await
+        .unwrap();
+
+    // Act
+    let err = governance_chat_test
+        .edit_message(
+            &proposal_cookie2,
+            &mut chat_message_cookie,
+            MessageBody::Text("My edited comment".to_string()),
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        err,
+        GovernanceChatError::InvalidProposalForChatMessage.into()
+    );
+}
+
+#[tokio::test]
+async fn test_edit_hidden_message_error() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let proposal_cookie = governance_chat_test.with_proposal().await;
+
+    let mut chat_message_cookie = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .unwrap();
+
+    governance_chat_test
+        .hide_message(
+            &proposal_cookie,
+            &mut chat_message_cookie,
+            &proposal_cookie.realm_authority,
+            true,
+        )
+        .await
+        .unwrap();
+
+    // Act
+    let err = governance_chat_test
+        .edit_message(
+            &proposal_cookie,
+            &mut chat_message_cookie,
+            MessageBody::Text("My edited comment".to_string()),
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, GovernanceChatError::CannotEditHiddenChatMessage.into());
+}
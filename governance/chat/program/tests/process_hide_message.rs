@@ -0,0 +1,146 @@
+#![cfg(feature = "test-bpf")]
+
+use program_test::GovernanceChatProgramTest;
+use solana_program_test::tokio;
+use solana_sdk::{signature::Keypair, signer::Signer};
+use spl_governance_chat::error::GovernanceChatError;
+
+mod program_test;
+
+#[tokio::test]
+async fn test_hide_message_by_realm_authority() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let proposal_cookie = governance_chat_test.with_proposal().await;
+
+    let mut chat_message_cookie = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .unwrap();
+
+    // Act
+    governance_chat_test
+        .hide_message(
+            &proposal_cookie,
+            &mut chat_message_cookie,
+            &proposal_cookie.realm_authority,
+            true,
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let chat_message_data = governance_chat_test
+        .get_message_account(&chat_message_cookie.address)
+        .await;
+
+    assert_eq!(chat_message_data, chat_message_cookie.account);
+    assert!(chat_message_data.is_hidden);
+}
+
+#[tokio::test]
+async fn test_unhide_message() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let proposal_cookie = governance_chat_test.with_proposal().await;
+
+    let mut chat_message_cookie = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .unwrap();
+
+    governance_chat_test
+        .hide_message(
+            &proposal_cookie,
+            &mut chat_message_cookie,
+            &proposal_cookie.realm_authority,
+            true,
+        )
+        .await
+        .unwrap();
+
+    // Act
+    governance_chat_test
+        .hide_message(
+            &proposal_cookie,
+            &mut chat_message_cookie,
+            &proposal_cookie.realm_authority,
+            false,
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let chat_message_data = governance_chat_test
+        .get_message_account(&chat_message_cookie.address)
+        .await;
+
+    assert_eq!(chat_message_data, chat_message_cookie.account);
+    assert!(!chat_message_data.is_hidden);
+}
+
+#[tokio::test]
+async fn test_hide_message_by_moderator_governance() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let proposal_cookie = governance_chat_test.with_proposal().await;
+
+    // The moderator governance would sign the instruction when executing a proposal transaction
+    // and for the purpose of the test any signer configured as the moderator can be used
+    let moderator = Keypair::new();
+
+    governance_chat_test
+        .with_chat_config(&proposal_cookie, 1, Some(moderator.pubkey()))
+        .await
+        .unwrap();
+
+    let mut chat_message_cookie = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .unwrap();
+
+    // Act
+    governance_chat_test
+        .hide_message(&proposal_cookie, &mut chat_message_cookie, &moderator, true)
+        .await
+        .unwrap();
+
+    // Assert
+    let chat_message_data = governance_chat_test
+        .get_message_account(&chat_message_cookie.address)
+        .await;
+
+    assert_eq!(chat_message_data, chat_message_cookie.account);
+}
+
+#[tokio::test]
+async fn test_hide_message_with_invalid_moderator_error() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let proposal_cookie = governance_chat_test.with_proposal().await;
+
+    let mut chat_message_cookie = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .unwrap();
+
+    // Try to hide the message by its author
+    // Act
+    let err = governance_chat_test
+        .hide_message(
+            &proposal_cookie,
+            &mut chat_message_cookie,
+            &proposal_cookie.token_owner,
+            true,
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, GovernanceChatError::InvalidModerator.into());
+}
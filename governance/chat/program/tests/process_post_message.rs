@@ -149,3 +149,108 @@ async fn test_post_message_with_voter_weight_addin() {
 
     assert_eq!(chat_message_data, chat_message_cookie.account);
 }
+
+#[tokio::test]
+async fn test_post_message_with_min_weight_to_post() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let proposal_cookie = governance_chat_test.with_proposal().await;
+
+    // The token owner deposited 100 tokens
+    governance_chat_test
+        .with_chat_config(&proposal_cookie, 100, None)
+        .await
+        .unwrap();
+
+    // Act
+    let chat_message_cookie = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .unwrap();
+
+    // Assert
+    let chat_message_data = governance_chat_test
+        .get_message_account(&chat_message_cookie.address)
+        .await;
+
+    assert_eq!(chat_message_data, chat_message_cookie.account);
+}
+
+#[tokio::test]
+async fn test_post_message_with_min_weight_to_post_not_reached_error() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let proposal_cookie = governance_chat_test.with_proposal().await;
+
+    // The token owner deposited 100 tokens
+    governance_chat_test
+        .with_chat_config(&proposal_cookie, 101, None)
+        .await
+        .unwrap();
+
+    // Act
+    let err = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        err,
+        GovernanceChatError::NotEnoughTokensToCommentProposal.into()
+    );
+}
+
+#[tokio::test]
+async fn test_post_message_with_voter_weight_addin_and_min_weight_to_post_not_reached_error() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_with_voter_weight_addin().await;
+
+    let proposal_cookie = governance_chat_test.with_proposal().await;
+
+    // The voter weight addin provides voter weight of 100
+    governance_chat_test
+        .with_chat_config(&proposal_cookie, 101, None)
+        .await
+        .unwrap();
+
+    // Act
+    let err = governance_chat_test
+        .with_chat_message(&proposal_cookie, None)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        err,
+        GovernanceChatError::NotEnoughTokensToCommentProposal.into()
+    );
+}
+
+#[tokio::test]
+async fn test_post_message_without_chat_config_account() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let proposal_cookie = governance_chat_test.with_proposal().await;
+
+    // Act
+    let chat_message_cookie = governance_chat_test
+        .with_chat_message_using_ix(&proposal_cookie, None, |i| {
+            // Clients built before ChatConfig was introduced don't pass the account
+            i.accounts.pop();
+        })
+        .await
+        .unwrap();
+
+    // Assert
+    let chat_message_data = governance_chat_test
+        .get_message_account(&chat_message_cookie.address)
+        .await;
+
+    assert_eq!(chat_message_data, chat_message_cookie.account);
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "test-bpf")]
+
+use program_test::GovernanceChatProgramTest;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_sdk::signature::Keypair;
+use spl_governance::error::GovernanceError;
+
+mod program_test;
+
+#[tokio::test]
+async fn test_set_chat_config() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let proposal_cookie = governance_chat_test.with_proposal().await;
+
+    // Act
+    let chat_config_cookie = governance_chat_test
+        .with_chat_config(&proposal_cookie, 10, Some(Pubkey::new_unique()))
+        .await
+        .unwrap();
+
+    // Assert
+    let chat_config_data = governance_chat_test
+        .get_chat_config_account(&chat_config_cookie.address)
+        .await;
+
+    assert_eq!(chat_config_data, chat_config_cookie.account);
+}
+
+#[tokio::test]
+async fn test_update_chat_config() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let proposal_cookie = governance_chat_test.with_proposal().await;
+
+    governance_chat_test
+        .with_chat_config(&proposal_cookie, 10, Some(Pubkey::new_unique()))
+        .await
+        .unwrap();
+
+    // Act
+    let chat_config_cookie = governance_chat_test
+        .with_chat_config(&proposal_cookie, 20, None)
+        .await
+        .unwrap();
+
+    // Assert
+    let chat_config_data = governance_chat_test
+        .get_chat_config_account(&chat_config_cookie.address)
+        .await;
+
+    assert_eq!(chat_config_data, chat_config_cookie.account);
+}
+
+#[tokio::test]
+async fn test_set_chat_config_with_invalid_realm_authority_error() {
+    // Arrange
+    let mut governance_chat_test = GovernanceChatProgramTest::start_new().await;
+
+    let mut proposal_cookie = governance_chat_test.with_proposal().await;
+
+    proposal_cookie.realm_authority = Keypair::new();
+
+    // Act
+    let err = governance_chat_test
+        .with_chat_config(&proposal_cookie, 10, None)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, GovernanceError::InvalidAuthorityForRealm.into());
+}
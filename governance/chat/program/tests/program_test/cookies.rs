@@ -1,6 +1,6 @@
 use solana_program::pubkey::Pubkey;
 use solana_sdk::signature::Keypair;
-use spl_governance_chat::state::ChatMessage;
+use spl_governance_chat::state::{ChatConfig, ChatMessage};
 
 #[derive(Debug)]
 pub struct ChatMessageCookie {
@@ -8,6 +8,12 @@ pub struct ChatMessageCookie {
     pub account: ChatMessage,
 }
 
+#[derive(Debug)]
+pub struct ChatConfigCookie {
+    pub address: Pubkey,
+    pub account: ChatConfig,
+}
+
 #[derive(Debug)]
 pub struct ProposalCookie {
     pub address: Pubkey,
@@ -20,6 +26,8 @@ pub struct ProposalCookie {
     pub governing_token_mint_authority: Keypair,
 
     pub voter_weight_record: Option<Pubkey>,
+
+    pub realm_authority: Keypair,
 }
 
 #[derive(Debug)]
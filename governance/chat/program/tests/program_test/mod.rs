@@ -1,6 +1,6 @@
 use std::str::FromStr;
 
-use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
 use solana_program_test::{processor, ProgramTest};
 
 use solana_sdk::{signature::Keypair, signer::Signer};
@@ -19,13 +19,17 @@ use spl_governance::{
 };
 use spl_governance_addin_mock::instruction::setup_voter_weight_record;
 use spl_governance_chat::{
-    instruction::post_message,
+    instruction::{delete_message, edit_message, hide_message, post_message, set_chat_config},
     processor::process_instruction,
-    state::{ChatMessage, GovernanceChatAccountType, MessageBody},
+    state::{
+        get_chat_config_address, ChatConfig, ChatMessage, GovernanceChatAccountType, MessageBody,
+    },
+};
+use spl_governance_test_sdk::{
+    addins::ensure_addin_mock_is_built, tools::NopOverride, ProgramTestBench,
 };
-use spl_governance_test_sdk::{addins::ensure_addin_mock_is_built, ProgramTestBench};
 
-use crate::program_test::cookies::{ChatMessageCookie, ProposalCookie};
+use crate::program_test::cookies::{ChatConfigCookie, ChatMessageCookie, ProposalCookie};
 
 use self::cookies::TokenOwnerRecordCookie;
 
@@ -181,6 +185,7 @@ impl GovernanceChatProgramTest {
         let governance_config = GovernanceConfig {
             min_community_weight_to_create_proposal: 5,
             min_council_weight_to_create_proposal: 2,
+            community_veto_vote_threshold: VoteThreshold::Disabled,
             min_transaction_hold_up_time: 10,
             max_voting_time: 10,
             community_vote_threshold: VoteThreshold::YesVotePercentage(60),
@@ -287,8 +292,9 @@ impl GovernanceChatProgramTest {
             token_owner_record_address,
             token_owner,
             governing_token_mint: governing_token_mint_keypair.pubkey(),
-            governing_token_mint_authority: governing_token_mint_authority,
+            governing_token_mint_authority,
             voter_weight_record,
+            realm_authority,
         }
     }
 
@@ -347,11 +353,22 @@ impl GovernanceChatProgramTest {
         &mut self,
         proposal_cookie: &ProposalCookie,
         reply_to: Option<Pubkey>,
+    ) -> Result<ChatMessageCookie, ProgramError> {
+        self.with_chat_message_using_ix(proposal_cookie, reply_to, NopOverride)
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn with_chat_message_using_ix<F: Fn(&mut Instruction)>(
+        &mut self,
+        proposal_cookie: &ProposalCookie,
+        reply_to: Option<Pubkey>,
+        instruction_override: F,
     ) -> Result<ChatMessageCookie, ProgramError> {
         let message_account = Keypair::new();
         let message_body = MessageBody::Text("My comment".to_string());
 
-        let post_message_ix = post_message(
+        let mut post_message_ix = post_message(
             &self.program_id,
             &self.governance_program_id,
             &proposal_cookie.realm_address,
@@ -366,15 +383,19 @@ impl GovernanceChatProgramTest {
             message_body.clone(),
         );
 
+        instruction_override(&mut post_message_ix);
+
         let clock = self.bench.get_clock().await;
 
         let message = ChatMessage {
-            account_type: GovernanceChatAccountType::ChatMessage,
+            account_type: GovernanceChatAccountType::ChatMessageV2,
             proposal: proposal_cookie.address,
             author: proposal_cookie.token_owner.pubkey(),
             posted_at: clock.unix_timestamp,
             reply_to,
             body: message_body,
+            edited_at: None,
+            is_hidden: false,
         };
 
         self.bench
@@ -390,6 +411,131 @@ impl GovernanceChatProgramTest {
         })
     }
 
+    #[allow(dead_code)]
+    pub async fn with_chat_config(
+        &mut self,
+        proposal_cookie: &ProposalCookie,
+        min_weight_to_post: u64,
+        moderator_governance: Option<Pubkey>,
+    ) -> Result<ChatConfigCookie, ProgramError> {
+        let set_chat_config_ix = set_chat_config(
+            &self.program_id,
+            &self.governance_program_id,
+            &proposal_cookie.realm_address,
+            &proposal_cookie.realm_authority.pubkey(),
+            &self.bench.payer.pubkey(),
+            min_weight_to_post,
+            moderator_governance,
+        );
+
+        self.bench
+            .process_transaction(
+                &[set_chat_config_ix],
+                Some(&[&proposal_cookie.realm_authority]),
+            )
+            .await?;
+
+        let chat_config = ChatConfig {
+            min_weight_to_post,
+            moderator_governance,
+            ..ChatConfig::new_default(&proposal_cookie.realm_address)
+        };
+
+        Ok(ChatConfigCookie {
+            address: get_chat_config_address(&self.program_id, &proposal_cookie.realm_address),
+            account: chat_config,
+        })
+    }
+
+    #[allow(dead_code)]
+    pub async fn edit_message(
+        &mut self,
+        proposal_cookie: &ProposalCookie,
+        chat_message_cookie: &mut ChatMessageCookie,
+        body: MessageBody,
+    ) -> Result<(), ProgramError> {
+        let edit_message_ix = edit_message(
+            &self.program_id,
+            &self.governance_program_id,
+            &proposal_cookie.governance_address,
+            &proposal_cookie.address,
+            &proposal_cookie.token_owner_record_address,
+            &proposal_cookie.token_owner.pubkey(),
+            &chat_message_cookie.address,
+            &self.bench.payer.pubkey(),
+            body.clone(),
+        );
+
+        self.bench
+            .process_transaction(&[edit_message_ix], Some(&[&proposal_cookie.token_owner]))
+            .await?;
+
+        let clock = self.bench.get_clock().await;
+
+        chat_message_cookie.account.body = body;
+        chat_message_cookie.account.edited_at = Some(clock.unix_timestamp);
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn delete_message(
+        &mut self,
+        proposal_cookie: &ProposalCookie,
+        chat_message_cookie: &ChatMessageCookie,
+    ) -> Result<(), ProgramError> {
+        let delete_message_ix = delete_message(
+            &self.program_id,
+            &self.governance_program_id,
+            &proposal_cookie.governance_address,
+            &proposal_cookie.address,
+            &proposal_cookie.token_owner_record_address,
+            &proposal_cookie.token_owner.pubkey(),
+            &chat_message_cookie.address,
+            &proposal_cookie.token_owner.pubkey(),
+        );
+
+        self.bench
+            .process_transaction(&[delete_message_ix], Some(&[&proposal_cookie.token_owner]))
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn hide_message(
+        &mut self,
+        proposal_cookie: &ProposalCookie,
+        chat_message_cookie: &mut ChatMessageCookie,
+        moderator: &Keypair,
+        is_hidden: bool,
+    ) -> Result<(), ProgramError> {
+        let hide_message_ix = hide_message(
+            &self.program_id,
+            &self.governance_program_id,
+            &proposal_cookie.realm_address,
+            &proposal_cookie.governance_address,
+            &proposal_cookie.address,
+            &moderator.pubkey(),
+            &chat_message_cookie.address,
+            &self.bench.payer.pubkey(),
+            is_hidden,
+        );
+
+        self.bench
+            .process_transaction(&[hide_message_ix], Some(&[moderator]))
+            .await?;
+
+        chat_message_cookie.account.is_hidden = is_hidden;
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_chat_config_account(&mut self, chat_config_address: &Pubkey) -> ChatConfig {
+        self.bench
+            .get_borsh_account::<ChatConfig>(chat_config_address)
+            .await
+    }
+
     #[allow(dead_code)]
     pub async fn get_message_account(&mut self, message_address: &Pubkey) -> ChatMessage {
         self.bench
@@ -0,0 +1,170 @@
+use {
+    solana_account_decoder::UiAccountEncoding,
+    solana_client::{
+        rpc_client::RpcClient,
+        rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+        rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+    },
+    solana_program::{
+        account_info::{AccountInfo, IntoAccountInfo},
+        borsh::try_from_slice_unchecked,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+    },
+    solana_sdk::account::Account,
+    spl_governance::state::{
+        enums::GovernanceAccountType,
+        governance::{get_governance_data, GovernanceV2},
+        proposal::{get_proposal_data, ProposalV2},
+        proposal_transaction::{
+            get_proposal_transaction_address, get_proposal_transaction_data, ProposalTransactionV2,
+        },
+        realm::{get_realm_data, RealmV2},
+    },
+};
+
+type Error = Box<dyn std::error::Error>;
+
+/// Deserializes the given account using the program's own account getter
+/// which checks the owner and translates legacy account versions
+fn deserialize_account<T>(
+    program_id: &Pubkey,
+    address: &Pubkey,
+    account: &mut Account,
+    get_data: fn(&Pubkey, &AccountInfo) -> Result<T, ProgramError>,
+) -> Result<T, Error> {
+    let account_info = (address, account).into_account_info();
+    get_data(program_id, &account_info)
+        .map_err(|err| format!("Invalid account {}: {}", address, err).into())
+}
+
+fn get_account_data<T>(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    address: &Pubkey,
+    get_data: fn(&Pubkey, &AccountInfo) -> Result<T, ProgramError>,
+) -> Result<T, Error> {
+    let mut account = rpc_client
+        .get_account(address)
+        .map_err(|err| format!("Account {} not found: {}", address, err))?;
+    deserialize_account(program_id, address, &mut account, get_data)
+}
+
+pub(crate) fn get_realm(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    realm_address: &Pubkey,
+) -> Result<RealmV2, Error> {
+    get_account_data(rpc_client, program_id, realm_address, get_realm_data)
+}
+
+pub(crate) fn get_governance(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    governance_address: &Pubkey,
+) -> Result<GovernanceV2, Error> {
+    get_account_data(
+        rpc_client,
+        program_id,
+        governance_address,
+        get_governance_data,
+    )
+}
+
+pub(crate) fn get_proposal(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    proposal_address: &Pubkey,
+) -> Result<ProposalV2, Error> {
+    get_account_data(rpc_client, program_id, proposal_address, get_proposal_data)
+}
+
+/// Returns the existing ProposalTransactions of the given Proposal option ordered by their index
+/// Note: Removed transactions leave gaps in the indexes and are skipped
+pub(crate) fn get_proposal_transactions(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    proposal_address: &Pubkey,
+    option_index: u8,
+    transactions_next_index: u16,
+) -> Result<Vec<(Pubkey, ProposalTransactionV2)>, Error> {
+    let addresses: Vec<Pubkey> = (0..transactions_next_index)
+        .map(|index| {
+            get_proposal_transaction_address(
+                program_id,
+                proposal_address,
+                &option_index.to_le_bytes(),
+                &index.to_le_bytes(),
+            )
+        })
+        .collect();
+
+    let mut proposal_transactions = vec![];
+
+    // getMultipleAccounts is limited to 100 accounts per request
+    for chunk in addresses.chunks(100) {
+        let accounts = rpc_client.get_multiple_accounts(chunk)?;
+
+        for (address, account) in chunk.iter().zip(accounts) {
+            if let Some(mut account) = account {
+                proposal_transactions.push((
+                    *address,
+                    deserialize_account(
+                        program_id,
+                        address,
+                        &mut account,
+                        get_proposal_transaction_data,
+                    )?,
+                ));
+            }
+        }
+    }
+
+    Ok(proposal_transactions)
+}
+
+/// Returns all Proposals of the given Governance ordered by their draft time
+pub(crate) fn get_proposals(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    governance_address: &Pubkey,
+) -> Result<Vec<(Pubkey, ProposalV2)>, Error> {
+    let accounts = rpc_client
+        .get_program_accounts_with_config(
+            program_id,
+            RpcProgramAccountsConfig {
+                // Proposal.governance follows the account type and no other account
+                // of the program stores a Governance address at that offset
+                filters: Some(vec![RpcFilterType::Memcmp(Memcmp {
+                    offset: 1,
+                    bytes: MemcmpEncodedBytes::Base58(governance_address.to_string()),
+                    encoding: None,
+                })]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(rpc_client.commitment()),
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            },
+        )
+        .map_err(|err| format!("Failed to fetch proposals: {}", err))?;
+
+    let mut proposals = vec![];
+
+    for (address, mut account) in accounts {
+        match try_from_slice_unchecked::<GovernanceAccountType>(&account.data) {
+            Ok(GovernanceAccountType::ProposalV1) | Ok(GovernanceAccountType::ProposalV2) => {
+                proposals.push((
+                    address,
+                    deserialize_account(program_id, &address, &mut account, get_proposal_data)?,
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    proposals.sort_by_key(|(_, proposal)| proposal.draft_at);
+
+    Ok(proposals)
+}
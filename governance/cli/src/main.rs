@@ -0,0 +1,1566 @@
+mod client;
+mod simulation;
+
+use {
+    crate::{
+        client::*,
+        simulation::{simulate_proposal_transactions, SimulationResult},
+    },
+    clap::{
+        crate_description, crate_name, crate_version, value_t, value_t_or_exit, values_t_or_exit,
+        App, AppSettings, Arg, ArgGroup, ArgMatches, SubCommand,
+    },
+    solana_clap_utils::{
+        input_parsers::pubkey_of,
+        input_validators::{is_parsable, is_url, is_valid_pubkey, is_valid_signer},
+        keypair::{signer_from_path_with_config, SignerFromPathConfig},
+    },
+    solana_client::rpc_client::RpcClient,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        message::Message,
+        pubkey::Pubkey,
+    },
+    solana_remote_wallet::remote_wallet::RemoteWalletManager,
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    },
+    spl_associated_token_account::get_associated_token_address,
+    spl_governance::{
+        instruction::*,
+        state::{
+            enums::{
+                InstructionExecutionFlags, MintMaxVoteWeightSource, ProposalState, VoteThreshold,
+                VoteTipping,
+            },
+            governance::{get_governance_address, GovernanceConfig},
+            proposal::{get_proposal_address, OptionVoteResult, ProposalV2, VoteType},
+            proposal_transaction::{AccountMetaData, InstructionData},
+            realm::{get_realm_address, RealmV2},
+            token_owner_record::get_token_owner_record_address,
+            vote_record::{Vote, VoteChoice},
+        },
+    },
+    std::{fs, process::exit, sync::Arc},
+};
+
+pub(crate) struct Config {
+    rpc_client: RpcClient,
+    verbose: bool,
+    program_id: Pubkey,
+    authority: Box<dyn Signer>,
+    fee_payer: Box<dyn Signer>,
+    dry_run: bool,
+}
+
+type Error = Box<dyn std::error::Error>;
+type CommandResult = Result<(), Error>;
+
+/// Vote as given on the command line where approved options are identified by their indexes
+enum CliVote {
+    Approve(Vec<u8>),
+    Deny,
+    Abstain,
+    Veto,
+}
+
+/// The default spl-governance program instance
+const DEFAULT_PROGRAM_ID: &str = "GovER5Lthms3bLBqWub97yVrMmEogzX7xNjdXpPPCVZw";
+
+fn get_signer(
+    matches: &ArgMatches<'_>,
+    keypair_name: &str,
+    keypair_path: &str,
+    wallet_manager: &mut Option<Arc<RemoteWalletManager>>,
+) -> Box<dyn Signer> {
+    signer_from_path_with_config(
+        matches,
+        matches.value_of(keypair_name).unwrap_or(keypair_path),
+        keypair_name,
+        wallet_manager,
+        &SignerFromPathConfig {
+            allow_null_signer: false,
+        },
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        exit(1);
+    })
+}
+
+fn send_transaction(config: &Config, instructions: &[Instruction]) -> CommandResult {
+    let message = Message::new(instructions, Some(&config.fee_payer.pubkey()));
+
+    // The authority only signs the transaction when any of the instructions requires it
+    let mut signers = vec![config.fee_payer.as_ref()];
+    if config.authority.pubkey() != config.fee_payer.pubkey()
+        && message.signer_keys().contains(&&config.authority.pubkey())
+    {
+        signers.push(config.authority.as_ref());
+    }
+
+    let mut transaction = Transaction::new_unsigned(message);
+    let recent_blockhash = config.rpc_client.get_latest_blockhash()?;
+    transaction.try_sign(&signers, recent_blockhash)?;
+
+    if config.dry_run {
+        let result = config.rpc_client.simulate_transaction(&transaction)?;
+        println!("Simulate result: {:?}", result);
+    } else {
+        let signature = config
+            .rpc_client
+            .send_and_confirm_transaction_with_spinner(&transaction)?;
+        println!("Signature: {}", signature);
+    }
+    Ok(())
+}
+
+/// Parses vote threshold given either as a yes vote percentage, as a quorum percentage prefixed with 'quorum:' or as 'disabled'
+fn parse_vote_threshold(value: &str) -> Result<VoteThreshold, String> {
+    if value == "disabled" {
+        return Ok(VoteThreshold::Disabled);
+    }
+
+    let (percentage, is_quorum) = match value.strip_prefix("quorum:") {
+        Some(percentage) => (percentage, true),
+        None => (value, false),
+    };
+
+    match percentage.parse::<u8>() {
+        Ok(percentage) if percentage > 0 && percentage <= 100 => Ok(if is_quorum {
+            VoteThreshold::QuorumPercentage(percentage)
+        } else {
+            VoteThreshold::YesVotePercentage(percentage)
+        }),
+        _ => Err(format!(
+            "Unable to parse vote threshold, provided: {}. Expected a percentage between 1 and 100, 'quorum:' followed by a percentage or 'disabled'",
+            value
+        )),
+    }
+}
+
+/// Parses veto vote threshold which can't be a quorum percentage
+fn parse_veto_vote_threshold(value: &str) -> Result<VoteThreshold, String> {
+    match parse_vote_threshold(value)? {
+        VoteThreshold::QuorumPercentage(_) => Err(format!(
+            "Unable to parse veto vote threshold, provided: {}. Quorum thresholds are not supported for veto votes",
+            value
+        )),
+        vote_threshold => Ok(vote_threshold),
+    }
+}
+
+fn is_vote_threshold(value: String) -> Result<(), String> {
+    parse_vote_threshold(&value).map(|_| ())
+}
+
+fn is_veto_vote_threshold(value: String) -> Result<(), String> {
+    parse_veto_vote_threshold(&value).map(|_| ())
+}
+
+fn vote_threshold_of(matches: &ArgMatches<'_>, name: &str) -> Option<VoteThreshold> {
+    matches
+        .value_of(name)
+        .map(|value| parse_vote_threshold(value).unwrap())
+}
+
+/// Decodes a base64 encoded transaction message into instructions which can be inserted into a Proposal
+fn parse_transaction_message(encoded_message: &str) -> Result<Vec<InstructionData>, Error> {
+    let message_data = base64::decode(encoded_message.trim())
+        .map_err(|err| format!("Invalid base64 transaction message: {}", err))?;
+    let message: Message = bincode::deserialize(&message_data)
+        .map_err(|err| format!("Invalid transaction message: {}", err))?;
+
+    let instructions = message
+        .instructions
+        .iter()
+        .map(|instruction| InstructionData {
+            program_id: message.account_keys[instruction.program_id_index as usize],
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|index| AccountMetaData {
+                    pubkey: message.account_keys[*index as usize],
+                    is_signer: message.is_signer(*index as usize),
+                    is_writable: message.is_writable(*index as usize),
+                })
+                .collect(),
+            data: instruction.data.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    if instructions.is_empty() {
+        return Err("Transaction message has no instructions".into());
+    }
+
+    Ok(instructions)
+}
+
+/// Returns the accounts the given Proposal instructions must be executed with
+/// Note: The instructions are signed by the Governance PDA and none of the accounts is a signer of the transaction
+fn get_execution_accounts(instructions: &[InstructionData]) -> Vec<AccountMeta> {
+    let mut accounts = vec![];
+
+    for instruction in instructions {
+        accounts.push(AccountMeta::new_readonly(instruction.program_id, false));
+        accounts.extend(instruction.accounts.iter().map(|account| {
+            if account.is_writable {
+                AccountMeta::new(account.pubkey, false)
+            } else {
+                AccountMeta::new_readonly(account.pubkey, false)
+            }
+        }));
+    }
+
+    accounts
+}
+
+fn command_create_realm(
+    config: &Config,
+    name: String,
+    community_mint: &Pubkey,
+    council_mint: Option<Pubkey>,
+    min_community_weight_to_create_governance: u64,
+    community_mint_max_vote_weight_source: MintMaxVoteWeightSource,
+) -> CommandResult {
+    let realm_address = get_realm_address(&config.program_id, &name);
+    println!("Creating realm {}", realm_address);
+
+    send_transaction(
+        config,
+        &[create_realm(
+            &config.program_id,
+            &config.authority.pubkey(),
+            community_mint,
+            &config.fee_payer.pubkey(),
+            council_mint,
+            None,
+            None,
+            name,
+            min_community_weight_to_create_governance,
+            community_mint_max_vote_weight_source,
+        )],
+    )
+}
+
+fn command_create_governance(
+    config: &Config,
+    realm_address: &Pubkey,
+    governed_account: Option<Pubkey>,
+    governing_token_mint: Option<Pubkey>,
+    governance_config: GovernanceConfig,
+) -> CommandResult {
+    let realm = get_realm(&config.rpc_client, &config.program_id, realm_address)?;
+
+    // If the governed account is not provided then a random address is used as the Governance seed
+    let governed_account = governed_account.unwrap_or_else(|| Keypair::new().pubkey());
+    let governing_token_mint = governing_token_mint.unwrap_or(realm.community_mint);
+
+    let governance_address =
+        get_governance_address(&config.program_id, realm_address, &governed_account);
+    println!("Creating governance {}", governance_address);
+
+    let token_owner_record_address = get_token_owner_record_address(
+        &config.program_id,
+        realm_address,
+        &governing_token_mint,
+        &config.authority.pubkey(),
+    );
+
+    send_transaction(
+        config,
+        &[create_governance(
+            &config.program_id,
+            realm_address,
+            Some(&governed_account),
+            &token_owner_record_address,
+            &config.fee_payer.pubkey(),
+            &config.authority.pubkey(),
+            None,
+            governance_config,
+        )],
+    )
+}
+
+fn command_deposit(
+    config: &Config,
+    realm_address: &Pubkey,
+    governing_token_mint: &Pubkey,
+    governing_token_source: Option<Pubkey>,
+    amount: u64,
+) -> CommandResult {
+    let governing_token_source = governing_token_source.unwrap_or_else(|| {
+        get_associated_token_address(&config.authority.pubkey(), governing_token_mint)
+    });
+
+    let token_owner_record_address = get_token_owner_record_address(
+        &config.program_id,
+        realm_address,
+        governing_token_mint,
+        &config.authority.pubkey(),
+    );
+    println!(
+        "Depositing {} tokens from {} into token owner record {}",
+        amount, governing_token_source, token_owner_record_address
+    );
+
+    send_transaction(
+        config,
+        &[deposit_governing_tokens(
+            &config.program_id,
+            realm_address,
+            &governing_token_source,
+            &config.authority.pubkey(),
+            &config.authority.pubkey(),
+            &config.fee_payer.pubkey(),
+            amount,
+            governing_token_mint,
+        )],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn command_create_proposal(
+    config: &Config,
+    governance_address: &Pubkey,
+    name: String,
+    description_link: String,
+    governing_token_mint: Option<Pubkey>,
+    options: Vec<String>,
+    multi_choice: bool,
+    use_deny_option: bool,
+    execution_flags: Option<InstructionExecutionFlags>,
+) -> CommandResult {
+    let governance = get_governance(&config.rpc_client, &config.program_id, governance_address)?;
+    let realm = get_realm(&config.rpc_client, &config.program_id, &governance.realm)?;
+
+    let governing_token_mint = governing_token_mint.unwrap_or(realm.community_mint);
+
+    let vote_type = if multi_choice {
+        VoteType::MultiChoice {
+            max_voter_options: options.len() as u8,
+            max_winning_options: options.len() as u8,
+        }
+    } else {
+        VoteType::SingleChoice
+    };
+
+    let proposal_address = get_proposal_address(
+        &config.program_id,
+        governance_address,
+        &governing_token_mint,
+        &governance.proposals_count.to_le_bytes(),
+    );
+    println!("Creating proposal {}", proposal_address);
+
+    let token_owner_record_address = get_token_owner_record_address(
+        &config.program_id,
+        &governance.realm,
+        &governing_token_mint,
+        &config.authority.pubkey(),
+    );
+
+    let mut instructions = vec![create_proposal(
+        &config.program_id,
+        governance_address,
+        &token_owner_record_address,
+        &config.authority.pubkey(),
+        &config.fee_payer.pubkey(),
+        None,
+        &governance.realm,
+        name,
+        description_link,
+        &governing_token_mint,
+        vote_type,
+        options,
+        use_deny_option,
+        governance.proposals_count,
+    )];
+
+    if let Some(execution_flags) = execution_flags {
+        instructions.push(set_proposal_execution_flags(
+            &config.program_id,
+            &proposal_address,
+            &token_owner_record_address,
+            &config.authority.pubkey(),
+            execution_flags,
+        ));
+    }
+
+    send_transaction(config, &instructions)
+}
+
+fn command_insert_transaction(
+    config: &Config,
+    proposal_address: &Pubkey,
+    option_index: u8,
+    hold_up_time: Option<u32>,
+    transaction_file: &str,
+) -> CommandResult {
+    let proposal = get_proposal(&config.rpc_client, &config.program_id, proposal_address)?;
+    let governance = get_governance(&config.rpc_client, &config.program_id, &proposal.governance)?;
+
+    let option = proposal
+        .options
+        .get(option_index as usize)
+        .ok_or_else(|| format!("Proposal has no option with index {}", option_index))?;
+
+    let instructions = parse_transaction_message(&fs::read_to_string(transaction_file)?)?;
+
+    if config.verbose {
+        for instruction in &instructions {
+            println!("{:?}", instruction);
+        }
+    }
+
+    println!(
+        "Inserting transaction {} with {} instruction(s) into option {} ({})",
+        option.transactions_next_index,
+        instructions.len(),
+        option_index,
+        option.label
+    );
+
+    send_transaction(
+        config,
+        &[insert_transaction(
+            &config.program_id,
+            &proposal.governance,
+            proposal_address,
+            &proposal.token_owner_record,
+            &config.authority.pubkey(),
+            &config.fee_payer.pubkey(),
+            option_index,
+            option.transactions_next_index,
+            hold_up_time.unwrap_or(governance.config.min_transaction_hold_up_time),
+            instructions,
+        )],
+    )
+}
+
+fn command_sign_off(config: &Config, proposal_address: &Pubkey) -> CommandResult {
+    let proposal = get_proposal(&config.rpc_client, &config.program_id, proposal_address)?;
+    let governance = get_governance(&config.rpc_client, &config.program_id, &proposal.governance)?;
+
+    // Without signatories the Proposal owner signs off the Proposal using its TokenOwnerRecord
+    let proposal_owner_record = if proposal.signatories_count == 0 {
+        Some(&proposal.token_owner_record)
+    } else {
+        None
+    };
+
+    send_transaction(
+        config,
+        &[sign_off_proposal(
+            &config.program_id,
+            &governance.realm,
+            &proposal.governance,
+            proposal_address,
+            &config.authority.pubkey(),
+            proposal_owner_record,
+        )],
+    )
+}
+
+/// Returns the governing token mint used to cast the given vote
+/// Veto votes are cast by the opposite voting population to the Proposal's one
+fn get_vote_governing_token_mint(
+    realm: &RealmV2,
+    proposal: &ProposalV2,
+    vote: &Vote,
+) -> Result<Pubkey, Error> {
+    if *vote != Vote::Veto {
+        return Ok(proposal.governing_token_mint);
+    }
+
+    if proposal.governing_token_mint == realm.community_mint {
+        realm
+            .config
+            .council_mint
+            .ok_or_else(|| "Realm has no council mint to cast a veto vote".into())
+    } else {
+        Ok(realm.community_mint)
+    }
+}
+
+fn command_vote(config: &Config, proposal_address: &Pubkey, vote: CliVote) -> CommandResult {
+    let proposal = get_proposal(&config.rpc_client, &config.program_id, proposal_address)?;
+    let governance = get_governance(&config.rpc_client, &config.program_id, &proposal.governance)?;
+    let realm = get_realm(&config.rpc_client, &config.program_id, &governance.realm)?;
+
+    let vote = match vote {
+        CliVote::Approve(option_indexes) => {
+            if let Some(option_index) = option_indexes
+                .iter()
+                .find(|option_index| **option_index as usize >= proposal.options.len())
+            {
+                return Err(format!("Proposal has no option with index {}", option_index).into());
+            }
+
+            Vote::Approve(
+                (0..proposal.options.len())
+                    .map(|option_index| VoteChoice {
+                        rank: 0,
+                        weight_percentage: if option_indexes.contains(&(option_index as u8)) {
+                            100
+                        } else {
+                            0
+                        },
+                    })
+                    .collect(),
+            )
+        }
+        CliVote::Deny => Vote::Deny,
+        CliVote::Abstain => Vote::Abstain,
+        CliVote::Veto => Vote::Veto,
+    };
+
+    let vote_governing_token_mint = get_vote_governing_token_mint(&realm, &proposal, &vote)?;
+    let voter_token_owner_record_address = get_token_owner_record_address(
+        &config.program_id,
+        &governance.realm,
+        &vote_governing_token_mint,
+        &config.authority.pubkey(),
+    );
+
+    send_transaction(
+        config,
+        &[cast_vote(
+            &config.program_id,
+            &governance.realm,
+            &proposal.governance,
+            proposal_address,
+            &proposal.token_owner_record,
+            &voter_token_owner_record_address,
+            &config.authority.pubkey(),
+            &vote_governing_token_mint,
+            &config.fee_payer.pubkey(),
+            None,
+            None,
+            vote,
+        )],
+    )
+}
+
+fn command_finalize(config: &Config, proposal_address: &Pubkey) -> CommandResult {
+    let proposal = get_proposal(&config.rpc_client, &config.program_id, proposal_address)?;
+    let governance = get_governance(&config.rpc_client, &config.program_id, &proposal.governance)?;
+
+    send_transaction(
+        config,
+        &[finalize_vote(
+            &config.program_id,
+            &governance.realm,
+            &proposal.governance,
+            proposal_address,
+            &proposal.token_owner_record,
+            &proposal.governing_token_mint,
+            None,
+        )],
+    )
+}
+
+fn command_execute(config: &Config, proposal_address: &Pubkey) -> CommandResult {
+    let proposal = get_proposal(&config.rpc_client, &config.program_id, proposal_address)?;
+
+    for (option_index, option) in proposal.options.iter().enumerate() {
+        if option.vote_result != OptionVoteResult::Succeeded {
+            continue;
+        }
+
+        let proposal_transactions = get_proposal_transactions(
+            &config.rpc_client,
+            &config.program_id,
+            proposal_address,
+            option_index as u8,
+            option.transactions_next_index,
+        )?
+        .into_iter()
+        .filter(|(_, proposal_transaction)| proposal_transaction.executed_at.is_none())
+        .collect::<Vec<_>>();
+
+        if proposal_transactions.is_empty() {
+            continue;
+        }
+
+        if proposal.execution_flags == InstructionExecutionFlags::UseTransaction {
+            // All the option's transactions are executed atomically within a single instruction
+            println!(
+                "Executing {} transaction(s) of option {} ({})",
+                proposal_transactions.len(),
+                option_index,
+                option.label
+            );
+
+            let mut instruction_accounts = vec![];
+            for (_, proposal_transaction) in &proposal_transactions {
+                instruction_accounts
+                    .extend(get_execution_accounts(&proposal_transaction.instructions));
+            }
+
+            send_transaction(
+                config,
+                &[execute_transactions(
+                    &config.program_id,
+                    &proposal.governance,
+                    proposal_address,
+                    &proposal_transactions
+                        .iter()
+                        .map(|(address, _)| *address)
+                        .collect::<Vec<_>>(),
+                    &instruction_accounts,
+                )],
+            )?;
+        } else {
+            for (address, proposal_transaction) in &proposal_transactions {
+                println!(
+                    "Executing transaction {} of option {} ({})",
+                    proposal_transaction.transaction_index, option_index, option.label
+                );
+
+                let instruction_accounts =
+                    get_execution_accounts(&proposal_transaction.instructions);
+
+                send_transaction(
+                    config,
+                    &[execute_transaction(
+                        &config.program_id,
+                        &proposal.governance,
+                        proposal_address,
+                        address,
+                        &instruction_accounts[0].pubkey,
+                        &instruction_accounts[1..],
+                    )],
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_simulation_result(result: &SimulationResult) {
+    match &result.err {
+        None => println!("  Result: Success"),
+        Some(err) => println!("  Result: Failed: {}", err),
+    }
+    if let Some(units_consumed) = result.units_consumed {
+        println!("  Compute Units: {}", units_consumed);
+    }
+
+    if !result.balance_changes.is_empty() {
+        println!("  Balance Changes:");
+        for balance_change in &result.balance_changes {
+            if balance_change.lamports != 0 {
+                println!(
+                    "    {}: {:+} lamports",
+                    balance_change.address, balance_change.lamports
+                );
+            }
+            if let Some((mint, amount)) = balance_change.token {
+                println!(
+                    "    {}: {:+} tokens of mint {}",
+                    balance_change.address, amount, mint
+                );
+            }
+        }
+    }
+
+    println!("  Logs:");
+    for log in &result.logs {
+        println!("    {}", log);
+    }
+}
+
+fn command_simulate(
+    config: &Config,
+    proposal_address: &Pubkey,
+    option_index: Option<u8>,
+) -> CommandResult {
+    let proposal = get_proposal(&config.rpc_client, &config.program_id, proposal_address)?;
+
+    if let Some(option_index) = option_index {
+        if option_index as usize >= proposal.options.len() {
+            return Err(format!("Proposal has no option with index {}", option_index).into());
+        }
+    }
+
+    for (index, option) in proposal.options.iter().enumerate() {
+        if option_index.map_or(false, |option_index| option_index as usize != index) {
+            continue;
+        }
+
+        let proposal_transactions = get_proposal_transactions(
+            &config.rpc_client,
+            &config.program_id,
+            proposal_address,
+            index as u8,
+            option.transactions_next_index,
+        )?
+        .into_iter()
+        .filter(|(_, proposal_transaction)| proposal_transaction.executed_at.is_none())
+        .map(|(_, proposal_transaction)| proposal_transaction)
+        .collect::<Vec<_>>();
+
+        if proposal_transactions.is_empty() {
+            println!(
+                "Option {} ({}): No transactions to execute",
+                index, option.label
+            );
+            println!();
+            continue;
+        }
+
+        // Transactions are simulated the way they are executed, atomically for UseTransaction Proposals
+        // and otherwise one by one where each transaction is simulated against the current state
+        let batches = if proposal.execution_flags == InstructionExecutionFlags::UseTransaction {
+            vec![proposal_transactions.iter().collect::<Vec<_>>()]
+        } else {
+            proposal_transactions
+                .iter()
+                .map(|proposal_transaction| vec![proposal_transaction])
+                .collect()
+        };
+
+        for batch in batches {
+            let transaction_indexes = batch
+                .iter()
+                .map(|proposal_transaction| proposal_transaction.transaction_index.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            println!(
+                "Option {} ({}): Transaction {}",
+                index, option.label, transaction_indexes
+            );
+
+            if config.verbose {
+                for proposal_transaction in &batch {
+                    for instruction in &proposal_transaction.instructions {
+                        println!("  {:?}", instruction);
+                    }
+                }
+            }
+
+            let result = simulate_proposal_transactions(
+                &config.rpc_client,
+                &config.fee_payer.pubkey(),
+                &batch,
+            )?;
+            print_simulation_result(&result);
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
+fn command_list_proposals(config: &Config, governance_address: &Pubkey) -> CommandResult {
+    let proposals = get_proposals(&config.rpc_client, &config.program_id, governance_address)?;
+
+    if proposals.is_empty() {
+        println!("No proposals found for governance {}", governance_address);
+        return Ok(());
+    }
+
+    for (address, proposal) in proposals {
+        println!("Proposal: {}", address);
+        println!("  Name: {}", proposal.name);
+        if config.verbose {
+            println!("  Description: {}", proposal.description_link);
+            println!("  Governing Token Mint: {}", proposal.governing_token_mint);
+            println!("  Execution Flags: {:?}", proposal.execution_flags);
+        }
+        println!("  State: {:?}", proposal.state);
+
+        for (option_index, option) in proposal.options.iter().enumerate() {
+            let vote_result = if option.vote_result == OptionVoteResult::None {
+                String::default()
+            } else {
+                format!(" ({:?})", option.vote_result)
+            };
+            println!(
+                "  Option {}: {}: {}{}",
+                option_index, option.label, option.vote_weight, vote_result
+            );
+        }
+
+        if let Some(deny_vote_weight) = proposal.deny_vote_weight {
+            println!("  Deny: {}", deny_vote_weight);
+        }
+        if let Some(abstain_vote_weight) = proposal.abstain_vote_weight {
+            println!("  Abstain: {}", abstain_vote_weight);
+        }
+        if proposal.veto_vote_weight > 0 {
+            println!("  Veto: {}", proposal.veto_vote_weight);
+        }
+        if let Some(max_vote_weight) = proposal.max_vote_weight {
+            println!("  Max Vote Weight: {}", max_vote_weight);
+        }
+        if proposal.state == ProposalState::Draft || proposal.state == ProposalState::SigningOff {
+            println!(
+                "  Signatories: {}/{}",
+                proposal.signatories_signed_off_count, proposal.signatories_count
+            );
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+fn main() {
+    solana_logger::setup_with_default("solana=info");
+
+    let proposal_arg = Arg::with_name("proposal")
+        .index(1)
+        .validator(is_valid_pubkey)
+        .value_name("PROPOSAL_ADDRESS")
+        .takes_value(true)
+        .required(true)
+        .help("Proposal address");
+
+    let governing_token_mint_arg = Arg::with_name("governing_token_mint")
+        .long("governing-token-mint")
+        .validator(is_valid_pubkey)
+        .value_name("MINT_ADDRESS")
+        .takes_value(true)
+        .help("Governing token mint [default: Realm community mint]");
+
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg({
+            let arg = Arg::with_name("config_file")
+                .short("C")
+                .long("config")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Configuration file to use");
+            if let Some(ref config_file) = *solana_cli_config::CONFIG_FILE {
+                arg.default_value(config_file)
+            } else {
+                arg
+            }
+        })
+        .arg(
+            Arg::with_name("verbose")
+                .long("verbose")
+                .short("v")
+                .takes_value(false)
+                .global(true)
+                .help("Show additional information"),
+        )
+        .arg(
+            Arg::with_name("dry_run")
+                .long("dry-run")
+                .takes_value(false)
+                .global(true)
+                .help("Simulate transaction instead of executing"),
+        )
+        .arg(
+            Arg::with_name("json_rpc_url")
+                .long("url")
+                .value_name("URL")
+                .takes_value(true)
+                .global(true)
+                .validator(is_url)
+                .help("JSON RPC URL for the cluster.  Default from the configuration file."),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("PROGRAM_ID")
+                .takes_value(true)
+                .global(true)
+                .validator(is_valid_pubkey)
+                .default_value(DEFAULT_PROGRAM_ID)
+                .help("spl-governance program instance"),
+        )
+        .arg(
+            Arg::with_name("authority")
+                .long("authority")
+                .value_name("KEYPAIR")
+                .validator(is_valid_signer)
+                .takes_value(true)
+                .global(true)
+                .help(
+                    "Governing token owner or Realm authority signing the transaction. \
+                     [default: cli config keypair]",
+                ),
+        )
+        .arg(
+            Arg::with_name("fee_payer")
+                .long("fee-payer")
+                .value_name("KEYPAIR")
+                .validator(is_valid_signer)
+                .takes_value(true)
+                .global(true)
+                .help("Transaction fee payer account [default: cli config keypair]"),
+        )
+        .subcommand(
+            SubCommand::with_name("create-realm")
+                .about("Create a new realm with the authority as the realm authority")
+                .arg(
+                    Arg::with_name("name")
+                        .index(1)
+                        .value_name("NAME")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Realm name, the realm address is derived from it"),
+                )
+                .arg(
+                    Arg::with_name("community_mint")
+                        .index(2)
+                        .validator(is_valid_pubkey)
+                        .value_name("MINT_ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Community token mint"),
+                )
+                .arg(
+                    Arg::with_name("council_mint")
+                        .long("council-mint")
+                        .validator(is_valid_pubkey)
+                        .value_name("MINT_ADDRESS")
+                        .takes_value(true)
+                        .help("Optional council token mint"),
+                )
+                .arg(
+                    Arg::with_name("min_community_weight_to_create_governance")
+                        .long("min-community-weight-to-create-governance")
+                        .validator(is_parsable::<u64>)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Min community token amount required to create a governance"),
+                )
+                .arg(
+                    Arg::with_name("community_max_vote_weight")
+                        .long("community-max-vote-weight")
+                        .validator(is_parsable::<u64>)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help(
+                            "Absolute max vote weight of the community. \
+                             [default: the community mint supply]",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("create-governance")
+                .about("Create a new governance for a realm")
+                .arg(
+                    Arg::with_name("realm")
+                        .index(1)
+                        .validator(is_valid_pubkey)
+                        .value_name("REALM_ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Realm address"),
+                )
+                .arg(
+                    Arg::with_name("governed_account")
+                        .long("governed-account")
+                        .validator(is_valid_pubkey)
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .help("Account governed by the governance [default: random address]"),
+                )
+                .arg(governing_token_mint_arg.clone().help(
+                    "Governing token mint of the authority's token owner record \
+                     [default: Realm community mint]",
+                ))
+                .arg(
+                    Arg::with_name("community_vote_threshold")
+                        .long("community-vote-threshold")
+                        .validator(is_vote_threshold)
+                        .value_name("PERCENTAGE")
+                        .takes_value(true)
+                        .default_value("60")
+                        .help(
+                            "Yes vote percentage required for community proposals to pass, \
+                             'quorum:PERCENTAGE' or 'disabled'",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("council_vote_threshold")
+                        .long("council-vote-threshold")
+                        .validator(is_vote_threshold)
+                        .value_name("PERCENTAGE")
+                        .takes_value(true)
+                        .help(
+                            "Yes vote percentage required for council proposals to pass, \
+                             'quorum:PERCENTAGE' or 'disabled' [default: community vote threshold]",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("community_veto_vote_threshold")
+                        .long("community-veto-vote-threshold")
+                        .validator(is_veto_vote_threshold)
+                        .value_name("PERCENTAGE")
+                        .takes_value(true)
+                        .default_value("disabled")
+                        .help("Community veto vote percentage required to veto council proposals or 'disabled'"),
+                )
+                .arg(
+                    Arg::with_name("council_veto_vote_threshold")
+                        .long("council-veto-vote-threshold")
+                        .validator(is_veto_vote_threshold)
+                        .value_name("PERCENTAGE")
+                        .takes_value(true)
+                        .help(
+                            "Council veto vote percentage required to veto community proposals or 'disabled' \
+                             [default: council vote threshold percentage]",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("min_community_weight_to_create_proposal")
+                        .long("min-community-weight-to-create-proposal")
+                        .validator(is_parsable::<u64>)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Min community token amount required to create a proposal"),
+                )
+                .arg(
+                    Arg::with_name("min_council_weight_to_create_proposal")
+                        .long("min-council-weight-to-create-proposal")
+                        .validator(is_parsable::<u64>)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Min council token amount required to create a proposal"),
+                )
+                .arg(
+                    Arg::with_name("min_transaction_hold_up_time")
+                        .long("min-transaction-hold-up-time")
+                        .validator(is_parsable::<u32>)
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Min time between a proposal succeeding and its transactions executing"),
+                )
+                .arg(
+                    Arg::with_name("max_voting_time")
+                        .long("max-voting-time")
+                        .validator(is_parsable::<u32>)
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .default_value("259200")
+                        .help("Time limit for proposals to be open for voting"),
+                )
+                .arg(
+                    Arg::with_name("vote_tipping")
+                        .long("vote-tipping")
+                        .value_name("TIPPING")
+                        .takes_value(true)
+                        .possible_values(&["strict", "early", "disabled"])
+                        .default_value("strict")
+                        .help("Conditions under which a vote completes before the voting time ends"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("deposit")
+                .about("Deposit governing tokens into the authority's token owner record")
+                .arg(
+                    Arg::with_name("realm")
+                        .index(1)
+                        .validator(is_valid_pubkey)
+                        .value_name("REALM_ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Realm address"),
+                )
+                .arg(
+                    Arg::with_name("governing_token_mint")
+                        .index(2)
+                        .validator(is_valid_pubkey)
+                        .value_name("MINT_ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Community or council token mint of the realm"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .index(3)
+                        .validator(is_parsable::<u64>)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Amount of tokens to deposit in the mint's base units"),
+                )
+                .arg(
+                    Arg::with_name("source")
+                        .long("source")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .help("Token account to deposit from [default: authority's associated token account]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("create-proposal")
+                .about("Create a new proposal for a governance")
+                .arg(
+                    Arg::with_name("governance")
+                        .index(1)
+                        .validator(is_valid_pubkey)
+                        .value_name("GOVERNANCE_ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Governance address"),
+                )
+                .arg(
+                    Arg::with_name("name")
+                        .index(2)
+                        .value_name("NAME")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Proposal name"),
+                )
+                .arg(
+                    Arg::with_name("description_link")
+                        .long("description-link")
+                        .value_name("LINK")
+                        .takes_value(true)
+                        .default_value("")
+                        .help("Link to the proposal description"),
+                )
+                .arg(governing_token_mint_arg.clone())
+                .arg(
+                    Arg::with_name("option")
+                        .long("option")
+                        .value_name("LABEL")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .default_value("Approve")
+                        .help("Proposal option, can be specified multiple times"),
+                )
+                .arg(
+                    Arg::with_name("multi_choice")
+                        .long("multi-choice")
+                        .takes_value(false)
+                        .help("Allow voters to approve multiple options and multiple options to succeed"),
+                )
+                .arg(
+                    Arg::with_name("no_deny_option")
+                        .long("no-deny-option")
+                        .takes_value(false)
+                        .help("Create a non executable survey proposal without the deny option"),
+                )
+                .arg(
+                    Arg::with_name("execution_flags")
+                        .long("execution-flags")
+                        .value_name("FLAGS")
+                        .takes_value(true)
+                        .possible_values(&["none", "ordered", "transaction"])
+                        .help(
+                            "Execute the transactions of an option in any order, \
+                             in the order of their index or atomically in a single transaction \
+                             [default: none]",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("insert-transaction")
+                .about("Insert a transaction into a draft proposal")
+                .arg(proposal_arg.clone())
+                .arg(
+                    Arg::with_name("transaction_file")
+                        .index(2)
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .required(true)
+                        .help(
+                            "File with the base64 encoded transaction message to execute, \
+                             as produced by --dump-transaction-message",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("option_index")
+                        .long("option-index")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INDEX")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Index of the proposal option the transaction is executed for"),
+                )
+                .arg(
+                    Arg::with_name("hold_up_time")
+                        .long("hold-up-time")
+                        .validator(is_parsable::<u32>)
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .help(
+                            "Time between the proposal succeeding and the transaction executing \
+                             [default: governance min transaction hold up time]",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("sign-off")
+                .about("Sign off a proposal as its owner or signatory")
+                .arg(proposal_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("vote")
+                .about("Cast a vote on a proposal")
+                .arg(proposal_arg.clone())
+                .arg(
+                    Arg::with_name("approve")
+                        .long("approve")
+                        .validator(is_parsable::<u8>)
+                        .value_name("OPTION_INDEX")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Approve the option with the given index, can be specified multiple times"),
+                )
+                .arg(
+                    Arg::with_name("deny")
+                        .long("deny")
+                        .takes_value(false)
+                        .help("Vote against the proposal"),
+                )
+                .arg(
+                    Arg::with_name("abstain")
+                        .long("abstain")
+                        .takes_value(false)
+                        .help("Abstain from the vote"),
+                )
+                .arg(
+                    Arg::with_name("veto")
+                        .long("veto")
+                        .takes_value(false)
+                        .help("Veto the proposal using the opposite governing token"),
+                )
+                .group(
+                    ArgGroup::with_name("vote")
+                        .args(&["approve", "deny", "abstain", "veto"])
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("finalize")
+                .about("Finalize the vote of a proposal once the voting time ended")
+                .arg(proposal_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("execute")
+                .about("Execute the pending transactions of the succeeded options of a proposal")
+                .arg(proposal_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("simulate")
+                .about(
+                    "Simulate the pending transactions of a proposal against the current state \
+                     and show their logs and balance changes",
+                )
+                .arg(proposal_arg)
+                .arg(
+                    Arg::with_name("option_index")
+                        .long("option-index")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INDEX")
+                        .takes_value(true)
+                        .help("Only simulate the transactions of the option with the given index"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("proposals")
+                .about("List the proposals of a governance with their state and votes")
+                .arg(
+                    Arg::with_name("governance")
+                        .index(1)
+                        .validator(is_valid_pubkey)
+                        .value_name("GOVERNANCE_ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Governance address"),
+                ),
+        )
+        .get_matches();
+
+    let mut wallet_manager = None;
+    let config = {
+        let cli_config = if let Some(config_file) = matches.value_of("config_file") {
+            solana_cli_config::Config::load(config_file).unwrap_or_default()
+        } else {
+            solana_cli_config::Config::default()
+        };
+        let json_rpc_url = value_t!(matches, "json_rpc_url", String)
+            .unwrap_or_else(|_| cli_config.json_rpc_url.clone());
+
+        let authority = get_signer(
+            &matches,
+            "authority",
+            &cli_config.keypair_path,
+            &mut wallet_manager,
+        );
+        let fee_payer = get_signer(
+            &matches,
+            "fee_payer",
+            &cli_config.keypair_path,
+            &mut wallet_manager,
+        );
+
+        Config {
+            rpc_client: RpcClient::new_with_commitment(json_rpc_url, CommitmentConfig::confirmed()),
+            verbose: matches.is_present("verbose"),
+            program_id: pubkey_of(&matches, "program_id").unwrap(),
+            authority,
+            fee_payer,
+            dry_run: matches.is_present("dry_run"),
+        }
+    };
+
+    let _ = match matches.subcommand() {
+        ("create-realm", Some(arg_matches)) => {
+            let name = value_t_or_exit!(arg_matches, "name", String);
+            let community_mint = pubkey_of(arg_matches, "community_mint").unwrap();
+            let council_mint = pubkey_of(arg_matches, "council_mint");
+            let min_community_weight_to_create_governance = value_t_or_exit!(
+                arg_matches,
+                "min_community_weight_to_create_governance",
+                u64
+            );
+            let community_mint_max_vote_weight_source =
+                value_t!(arg_matches, "community_max_vote_weight", u64)
+                    .map(MintMaxVoteWeightSource::Absolute)
+                    .unwrap_or(MintMaxVoteWeightSource::FULL_SUPPLY_FRACTION);
+            command_create_realm(
+                &config,
+                name,
+                &community_mint,
+                council_mint,
+                min_community_weight_to_create_governance,
+                community_mint_max_vote_weight_source,
+            )
+        }
+        ("create-governance", Some(arg_matches)) => {
+            let realm = pubkey_of(arg_matches, "realm").unwrap();
+            let governed_account = pubkey_of(arg_matches, "governed_account");
+            let governing_token_mint = pubkey_of(arg_matches, "governing_token_mint");
+            let community_vote_threshold =
+                vote_threshold_of(arg_matches, "community_vote_threshold").unwrap();
+            let council_vote_threshold = vote_threshold_of(arg_matches, "council_vote_threshold")
+                .unwrap_or_else(|| community_vote_threshold.clone());
+            let community_veto_vote_threshold =
+                vote_threshold_of(arg_matches, "community_veto_vote_threshold").unwrap();
+            // Veto votes don't support quorum thresholds, so a council quorum is used as a Yes vote percentage
+            let council_veto_vote_threshold =
+                vote_threshold_of(arg_matches, "council_veto_vote_threshold").unwrap_or_else(
+                    || match council_vote_threshold {
+                        VoteThreshold::QuorumPercentage(percentage) => {
+                            VoteThreshold::YesVotePercentage(percentage)
+                        }
+                        ref vote_threshold => vote_threshold.clone(),
+                    },
+                );
+            let vote_tipping = match arg_matches.value_of("vote_tipping").unwrap() {
+                "strict" => VoteTipping::Strict,
+                "early" => VoteTipping::Early,
+                "disabled" => VoteTipping::Disabled,
+                _ => unreachable!(),
+            };
+            let governance_config = GovernanceConfig {
+                community_vote_threshold,
+                min_community_weight_to_create_proposal: value_t_or_exit!(
+                    arg_matches,
+                    "min_community_weight_to_create_proposal",
+                    u64
+                ),
+                min_transaction_hold_up_time: value_t_or_exit!(
+                    arg_matches,
+                    "min_transaction_hold_up_time",
+                    u32
+                ),
+                max_voting_time: value_t_or_exit!(arg_matches, "max_voting_time", u32),
+                vote_tipping,
+                council_vote_threshold,
+                council_veto_vote_threshold,
+                min_council_weight_to_create_proposal: value_t_or_exit!(
+                    arg_matches,
+                    "min_council_weight_to_create_proposal",
+                    u64
+                ),
+                community_veto_vote_threshold,
+            };
+            command_create_governance(
+                &config,
+                &realm,
+                governed_account,
+                governing_token_mint,
+                governance_config,
+            )
+        }
+        ("deposit", Some(arg_matches)) => {
+            let realm = pubkey_of(arg_matches, "realm").unwrap();
+            let governing_token_mint = pubkey_of(arg_matches, "governing_token_mint").unwrap();
+            let source = pubkey_of(arg_matches, "source");
+            let amount = value_t_or_exit!(arg_matches, "amount", u64);
+            command_deposit(&config, &realm, &governing_token_mint, source, amount)
+        }
+        ("create-proposal", Some(arg_matches)) => {
+            let governance = pubkey_of(arg_matches, "governance").unwrap();
+            let name = value_t_or_exit!(arg_matches, "name", String);
+            let description_link = value_t_or_exit!(arg_matches, "description_link", String);
+            let governing_token_mint = pubkey_of(arg_matches, "governing_token_mint");
+            let options = values_t_or_exit!(arg_matches, "option", String);
+            let execution_flags = arg_matches
+                .value_of("execution_flags")
+                .map(|execution_flags| match execution_flags {
+                    "none" => InstructionExecutionFlags::None,
+                    "ordered" => InstructionExecutionFlags::Ordered,
+                    "transaction" => InstructionExecutionFlags::UseTransaction,
+                    _ => unreachable!(),
+                });
+            command_create_proposal(
+                &config,
+                &governance,
+                name,
+                description_link,
+                governing_token_mint,
+                options,
+                arg_matches.is_present("multi_choice"),
+                !arg_matches.is_present("no_deny_option"),
+                execution_flags,
+            )
+        }
+        ("insert-transaction", Some(arg_matches)) => {
+            let proposal = pubkey_of(arg_matches, "proposal").unwrap();
+            let transaction_file = arg_matches.value_of("transaction_file").unwrap();
+            let option_index = value_t_or_exit!(arg_matches, "option_index", u8);
+            let hold_up_time = value_t!(arg_matches, "hold_up_time", u32).ok();
+            command_insert_transaction(
+                &config,
+                &proposal,
+                option_index,
+                hold_up_time,
+                transaction_file,
+            )
+        }
+        ("sign-off", Some(arg_matches)) => {
+            let proposal = pubkey_of(arg_matches, "proposal").unwrap();
+            command_sign_off(&config, &proposal)
+        }
+        ("vote", Some(arg_matches)) => {
+            let proposal = pubkey_of(arg_matches, "proposal").unwrap();
+            let vote = if arg_matches.is_present("approve") {
+                CliVote::Approve(values_t_or_exit!(arg_matches, "approve", u8))
+            } else if arg_matches.is_present("deny") {
+                CliVote::Deny
+            } else if arg_matches.is_present("abstain") {
+                CliVote::Abstain
+            } else {
+                CliVote::Veto
+            };
+            command_vote(&config, &proposal, vote)
+        }
+        ("finalize", Some(arg_matches)) => {
+            let proposal = pubkey_of(arg_matches, "proposal").unwrap();
+            command_finalize(&config, &proposal)
+        }
+        ("execute", Some(arg_matches)) => {
+            let proposal = pubkey_of(arg_matches, "proposal").unwrap();
+            command_execute(&config, &proposal)
+        }
+        ("simulate", Some(arg_matches)) => {
+            let proposal = pubkey_of(arg_matches, "proposal").unwrap();
+            let option_index = value_t!(arg_matches, "option_index", u8).ok();
+            command_simulate(&config, &proposal, option_index)
+        }
+        ("proposals", Some(arg_matches)) => {
+            let governance = pubkey_of(arg_matches, "governance").unwrap();
+            command_list_proposals(&config, &governance)
+        }
+        _ => unreachable!(),
+    }
+    .map_err(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, solana_program::system_instruction};
+
+    #[test]
+    fn test_parse_vote_threshold() {
+        assert_eq!(
+            parse_vote_threshold("60"),
+            Ok(VoteThreshold::YesVotePercentage(60))
+        );
+        assert_eq!(
+            parse_vote_threshold("disabled"),
+            Ok(VoteThreshold::Disabled)
+        );
+        assert_eq!(
+            parse_vote_threshold("quorum:30"),
+            Ok(VoteThreshold::QuorumPercentage(30))
+        );
+        assert!(parse_vote_threshold("0").is_err());
+        assert!(parse_vote_threshold("101").is_err());
+        assert!(parse_vote_threshold("yes").is_err());
+        assert!(parse_vote_threshold("quorum:0").is_err());
+        assert!(parse_vote_threshold("quorum:101").is_err());
+        assert!(parse_vote_threshold("quorum").is_err());
+    }
+
+    #[test]
+    fn test_parse_veto_vote_threshold() {
+        assert_eq!(
+            parse_veto_vote_threshold("60"),
+            Ok(VoteThreshold::YesVotePercentage(60))
+        );
+        assert_eq!(
+            parse_veto_vote_threshold("disabled"),
+            Ok(VoteThreshold::Disabled)
+        );
+        assert!(parse_veto_vote_threshold("quorum:30").is_err());
+    }
+
+    #[test]
+    fn test_parse_transaction_message() {
+        let governance = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let instruction = system_instruction::transfer(&governance, &recipient, 100);
+
+        let message = Message::new(&[instruction.clone()], Some(&governance));
+        let encoded_message = base64::encode(bincode::serialize(&message).unwrap());
+
+        let instructions = parse_transaction_message(&encoded_message).unwrap();
+
+        assert_eq!(instructions, vec![InstructionData::from(instruction)]);
+    }
+
+    #[test]
+    fn test_parse_transaction_message_with_invalid_message_error() {
+        assert!(parse_transaction_message("not base64").is_err());
+        assert!(parse_transaction_message(&base64::encode([1, 2, 3])).is_err());
+    }
+
+    #[test]
+    fn test_get_execution_accounts() {
+        let governance = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let instruction = system_instruction::transfer(&governance, &recipient, 100);
+
+        let accounts = get_execution_accounts(&[InstructionData::from(instruction.clone())]);
+
+        assert_eq!(
+            accounts,
+            vec![
+                AccountMeta::new_readonly(instruction.program_id, false),
+                AccountMeta::new(governance, false),
+                AccountMeta::new(recipient, false),
+            ]
+        );
+    }
+}
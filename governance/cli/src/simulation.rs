@@ -0,0 +1,266 @@
+use {
+    solana_account_decoder::UiAccountEncoding,
+    solana_client::{
+        rpc_client::RpcClient,
+        rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig},
+    },
+    solana_program::{
+        instruction::Instruction, message::Message, program_pack::Pack, pubkey::Pubkey,
+    },
+    solana_sdk::{account::Account, transaction::Transaction, transaction::TransactionError},
+    spl_governance::state::proposal_transaction::ProposalTransactionV2,
+};
+
+type Error = Box<dyn std::error::Error>;
+
+/// Balance change of an account caused by a simulated transaction
+#[derive(Debug, PartialEq)]
+pub(crate) struct BalanceChange {
+    /// The changed account
+    pub address: Pubkey,
+
+    /// Lamports balance change
+    pub lamports: i128,
+
+    /// Token mint and token balance change for SPL Token accounts
+    pub token: Option<(Pubkey, i128)>,
+}
+
+/// Outcome of a simulated Proposal transaction
+pub(crate) struct SimulationResult {
+    /// Transaction error if the simulation failed
+    pub err: Option<TransactionError>,
+
+    /// Program logs
+    pub logs: Vec<String>,
+
+    /// Compute units consumed by the transaction
+    pub units_consumed: Option<u64>,
+
+    /// Balance changes of the writable accounts, only available if the simulation succeeded
+    pub balance_changes: Vec<BalanceChange>,
+}
+
+/// Returns the token mint and amount of the given account if it's an SPL Token account
+fn get_token_balance(account: &Account) -> Option<(Pubkey, u64)> {
+    if account.owner != spl_token::id() {
+        return None;
+    }
+
+    spl_token::state::Account::unpack(&account.data)
+        .ok()
+        .map(|token_account| (token_account.mint, token_account.amount))
+}
+
+/// Returns the balance changes between the pre and post states of the given accounts
+/// Accounts which don't exist are treated as accounts with zero balances
+pub(crate) fn get_balance_changes(
+    addresses: &[Pubkey],
+    pre_accounts: &[Option<Account>],
+    post_accounts: &[Option<Account>],
+) -> Vec<BalanceChange> {
+    addresses
+        .iter()
+        .zip(pre_accounts.iter().zip(post_accounts))
+        .filter_map(|(address, (pre_account, post_account))| {
+            let pre_lamports = pre_account.as_ref().map_or(0, |a| a.lamports) as i128;
+            let post_lamports = post_account.as_ref().map_or(0, |a| a.lamports) as i128;
+
+            let pre_token_balance = pre_account.as_ref().and_then(get_token_balance);
+            let post_token_balance = post_account.as_ref().and_then(get_token_balance);
+
+            let token = match (pre_token_balance, post_token_balance) {
+                (Some((mint, pre_amount)), Some((_, post_amount))) => {
+                    Some((mint, post_amount as i128 - pre_amount as i128))
+                }
+                (None, Some((mint, post_amount))) => Some((mint, post_amount as i128)),
+                (Some((mint, pre_amount)), None) => Some((mint, -(pre_amount as i128))),
+                (None, None) => None,
+            }
+            .filter(|(_, amount)| *amount != 0);
+
+            if post_lamports == pre_lamports && token.is_none() {
+                None
+            } else {
+                Some(BalanceChange {
+                    address: *address,
+                    lamports: post_lamports - pre_lamports,
+                    token,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Simulates the instructions of the given ProposalTransactions within a single transaction against the current state
+///
+/// The instructions are simulated as they would be executed with the Governance PDA and other PDA signers
+/// Signatures are not verified and the fee_payer only pays the simulated transaction fee
+pub(crate) fn simulate_proposal_transactions(
+    rpc_client: &RpcClient,
+    fee_payer: &Pubkey,
+    proposal_transactions: &[&ProposalTransactionV2],
+) -> Result<SimulationResult, Error> {
+    let instructions: Vec<Instruction> = proposal_transactions
+        .iter()
+        .flat_map(|proposal_transaction| proposal_transaction.instructions.iter())
+        .map(Instruction::from)
+        .collect();
+
+    let message = Message::new(&instructions, Some(fee_payer));
+
+    // The fee payer is excluded from the reported accounts because its balance changes by the fee
+    let addresses: Vec<Pubkey> = message
+        .account_keys
+        .iter()
+        .enumerate()
+        .filter(|(index, address)| message.is_writable(*index) && *address != fee_payer)
+        .map(|(_, address)| *address)
+        .collect();
+
+    let pre_accounts = rpc_client.get_multiple_accounts(&addresses)?;
+
+    let result = rpc_client
+        .simulate_transaction_with_config(
+            &Transaction::new_unsigned(message),
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(rpc_client.commitment()),
+                accounts: Some(RpcSimulateTransactionAccountsConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    addresses: addresses.iter().map(|a| a.to_string()).collect(),
+                }),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )?
+        .value;
+
+    let balance_changes = if result.err.is_none() {
+        let post_accounts: Vec<Option<Account>> = result
+            .accounts
+            .unwrap_or_default()
+            .iter()
+            .map(|account| account.as_ref().and_then(|account| account.decode()))
+            .collect();
+
+        get_balance_changes(&addresses, &pre_accounts, &post_accounts)
+    } else {
+        vec![]
+    };
+
+    Ok(SimulationResult {
+        err: result.err,
+        logs: result.logs.unwrap_or_default(),
+        units_consumed: result.units_consumed,
+        balance_changes,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, solana_program::program_option::COption};
+
+    fn create_token_account(mint: &Pubkey, amount: u64) -> Account {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint: *mint,
+            owner: Pubkey::new_unique(),
+            amount,
+            delegate: COption::None,
+            state: spl_token::state::AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+
+        Account {
+            lamports: 10,
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn create_system_account(lamports: u64) -> Account {
+        Account {
+            lamports,
+            ..Account::default()
+        }
+    }
+
+    #[test]
+    fn test_get_balance_changes() {
+        // Arrange
+        let mint = Pubkey::new_unique();
+        let addresses = vec![
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+
+        let pre_accounts = vec![
+            Some(create_system_account(100)),
+            Some(create_system_account(100)),
+            Some(create_token_account(&mint, 50)),
+            None,
+        ];
+        let post_accounts = vec![
+            Some(create_system_account(40)),
+            Some(create_system_account(100)),
+            Some(create_token_account(&mint, 20)),
+            Some(create_system_account(60)),
+        ];
+
+        // Act
+        let balance_changes = get_balance_changes(&addresses, &pre_accounts, &post_accounts);
+
+        // Assert
+        assert_eq!(
+            balance_changes,
+            vec![
+                BalanceChange {
+                    address: addresses[0],
+                    lamports: -60,
+                    token: None
+                },
+                BalanceChange {
+                    address: addresses[2],
+                    lamports: 0,
+                    token: Some((mint, -30))
+                },
+                BalanceChange {
+                    address: addresses[3],
+                    lamports: 60,
+                    token: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_balance_changes_with_closed_token_account() {
+        // Arrange
+        let mint = Pubkey::new_unique();
+        let addresses = vec![Pubkey::new_unique()];
+
+        let pre_accounts = vec![Some(create_token_account(&mint, 50))];
+        let post_accounts = vec![None];
+
+        // Act
+        let balance_changes = get_balance_changes(&addresses, &pre_accounts, &post_accounts);
+
+        // Assert
+        assert_eq!(
+            balance_changes,
+            vec![BalanceChange {
+                address: addresses[0],
+                lamports: -10,
+                token: Some((mint, -50))
+            }]
+        );
+    }
+}
@@ -406,6 +406,22 @@ pub enum GovernanceError {
     /// Cannot Relinquish in Finalizing state
     #[error("Cannot Relinquish in Finalizing state")]
     CannotRelinquishInFinalizingState,
+
+    /// Transactions must be executed in the order of their index
+    #[error("Transactions must be executed in the order of their index")]
+    InvalidTransactionExecutionOrder,
+
+    /// Transaction index gaps are not allowed for Ordered execution
+    #[error("Transaction index gaps are not allowed for Ordered execution")]
+    TransactionIndexGapNotAllowed,
+
+    /// All transactions of the option must be provided in index order for atomic execution
+    #[error("All transactions of the option must be provided in index order for atomic execution")]
+    InvalidTransactionsForAtomicExecution,
+
+    /// Invalid max vote weight absolute value
+    #[error("Invalid max vote weight absolute value")]
+    InvalidMaxVoteWeightAbsoluteValue,
 }
 
 impl PrintProgramError for GovernanceError {
@@ -2,7 +2,7 @@
 
 use crate::{
     state::{
-        enums::MintMaxVoteWeightSource,
+        enums::{InstructionExecutionFlags, MintMaxVoteWeightSource},
         governance::{
             get_governance_address, get_mint_governance_address, get_program_governance_address,
             get_token_governance_address, GovernanceConfig,
@@ -292,7 +292,6 @@ pub enum GovernanceInstruction {
     ///           The voting token mint is the governing_token_mint of the Proposal for Approve, Deny and Abstain votes
     ///           For Veto vote the voting token mint is the mint of the opposite voting population
     ///           Council mint to veto Community proposals and Community mint to veto Council proposals
-    ///           Note: In the current version only Council veto is supported
     ///   8. `[signer]` Payer
     ///   9. `[]` System program
     ///   10. `[]` Realm Config
@@ -337,9 +336,14 @@ pub enum GovernanceInstruction {
     /// The actual transaction being executed will be signed by Governance PDA the Proposal belongs to
     /// For example to execute Program upgrade the ProgramGovernance PDA would be used as the singer
     ///
+    /// For Proposals with Ordered execution_flags the transactions of an option must be executed in the order of their index
+    /// For Proposals with UseTransaction execution_flags all transactions of an option are executed atomically
+    /// and all the option's ProposalTransaction accounts must be provided in the order of their index
+    ///
     ///   0. `[]` Governance account
     ///   1. `[writable]` Proposal account
     ///   2. `[writable]` ProposalTransaction account you wish to execute
+    ///      For UseTransaction execution all ProposalTransaction accounts of the option in the order of their index
     ///   3+ Any extra accounts that are part of the transaction, in order
     ExecuteTransaction,
 
@@ -475,6 +479,18 @@ pub enum GovernanceInstruction {
     ///  2. `[signer]` Payer
     ///  3. `[]` System
     CreateNativeTreasury,
+
+    /// Sets the execution flags defining how the Proposal transactions are executed
+    /// The flags can only be changed while the Proposal is in Draft state
+    ///
+    ///   0. `[writable]` Proposal account
+    ///   1. `[]` TokenOwnerRecord account of the Proposal owner
+    ///   2. `[signer]` Governance Authority (Token Owner or Governance Delegate)
+    SetProposalExecutionFlags {
+        #[allow(dead_code)]
+        /// Instruction execution flags for the Proposal
+        execution_flags: InstructionExecutionFlags,
+    },
 }
 
 /// Creates CreateRealm instruction
@@ -1268,6 +1284,38 @@ pub fn execute_transaction(
     }
 }
 
+/// Creates ExecuteTransaction instruction which executes all transactions of an option atomically
+/// Note: The Proposal must use InstructionExecutionFlags::UseTransaction
+///       and proposal_transactions must be all the option's transactions in the order of their index
+pub fn execute_transactions(
+    program_id: &Pubkey,
+    // Accounts
+    governance: &Pubkey,
+    proposal: &Pubkey,
+    proposal_transactions: &[Pubkey],
+    instruction_accounts: &[AccountMeta],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*governance, false),
+        AccountMeta::new(*proposal, false),
+    ];
+
+    accounts.extend(
+        proposal_transactions
+            .iter()
+            .map(|proposal_transaction| AccountMeta::new(*proposal_transaction, false)),
+    );
+    accounts.extend_from_slice(instruction_accounts);
+
+    let instruction = GovernanceInstruction::ExecuteTransaction {};
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
+
 /// Creates SetGovernanceConfig instruction
 pub fn set_governance_config(
     program_id: &Pubkey,
@@ -1536,3 +1584,28 @@ pub fn create_native_treasury(
         data: instruction.try_to_vec().unwrap(),
     }
 }
+
+/// Creates SetProposalExecutionFlags instruction
+pub fn set_proposal_execution_flags(
+    program_id: &Pubkey,
+    // Accounts
+    proposal: &Pubkey,
+    token_owner_record: &Pubkey,
+    governance_authority: &Pubkey,
+    // Args
+    execution_flags: InstructionExecutionFlags,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*proposal, false),
+        AccountMeta::new_readonly(*token_owner_record, false),
+        AccountMeta::new_readonly(*governance_authority, true),
+    ];
+
+    let instruction = GovernanceInstruction::SetProposalExecutionFlags { execution_flags };
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction.try_to_vec().unwrap(),
+    }
+}
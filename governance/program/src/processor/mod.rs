@@ -21,6 +21,7 @@ mod process_remove_signatory;
 mod process_remove_transaction;
 mod process_set_governance_config;
 mod process_set_governance_delegate;
+mod process_set_proposal_execution_flags;
 mod process_set_realm_authority;
 mod process_set_realm_config;
 mod process_sign_off_proposal;
@@ -50,6 +51,7 @@ use process_remove_signatory::*;
 use process_remove_transaction::*;
 use process_set_governance_config::*;
 use process_set_governance_delegate::*;
+use process_set_proposal_execution_flags::*;
 use process_set_realm_authority::*;
 use process_set_realm_config::*;
 use process_sign_off_proposal::*;
@@ -213,5 +215,8 @@ pub fn process_instruction(
         GovernanceInstruction::CreateNativeTreasury {} => {
             process_create_native_treasury(program_id, accounts)
         }
+        GovernanceInstruction::SetProposalExecutionFlags { execution_flags } => {
+            process_set_proposal_execution_flags(program_id, accounts, execution_flags)
+        }
     }
 }
@@ -147,7 +147,13 @@ pub fn process_cast_vote(
                 .unwrap();
         }
         Vote::Abstain => {
-            return Err(GovernanceError::NotSupportedVoteType.into());
+            proposal_data.abstain_vote_weight = Some(
+                proposal_data
+                    .abstain_vote_weight
+                    .unwrap()
+                    .checked_add(voter_weight)
+                    .unwrap(),
+            )
         }
     }
 
@@ -69,7 +69,7 @@ pub fn process_create_mint_governance(
         governed_account: *governed_mint_info.key,
         config,
         proposals_count: 0,
-        reserved: [0; 6],
+        reserved: [0; 4],
         voting_proposal_count: 0,
         reserved_v2: [0; 128],
     };
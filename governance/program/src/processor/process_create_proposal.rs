@@ -151,7 +151,7 @@ pub fn process_create_proposal(
         deny_vote_weight,
 
         veto_vote_weight: 0,
-        abstain_vote_weight: None,
+        abstain_vote_weight: Some(0),
 
         max_vote_weight: None,
         max_voting_time: None,
@@ -10,12 +10,15 @@ use solana_program::{
     sysvar::Sysvar,
 };
 
-use crate::state::{
-    enums::{ProposalState, TransactionExecutionStatus},
-    governance::get_governance_data,
-    native_treasury::get_native_treasury_address_seeds,
-    proposal::{get_proposal_data_for_governance, OptionVoteResult},
-    proposal_transaction::get_proposal_transaction_data_for_proposal,
+use crate::{
+    error::GovernanceError,
+    state::{
+        enums::{InstructionExecutionFlags, ProposalState, TransactionExecutionStatus},
+        governance::get_governance_data,
+        native_treasury::get_native_treasury_address_seeds,
+        proposal::{get_proposal_data_for_governance, OptionVoteResult},
+        proposal_transaction::get_proposal_transaction_data_for_proposal,
+    },
 };
 
 /// Processes ExecuteTransaction instruction
@@ -33,19 +36,52 @@ pub fn process_execute_transaction(program_id: &Pubkey, accounts: &[AccountInfo]
     let mut proposal_data =
         get_proposal_data_for_governance(program_id, proposal_info, governance_info.key)?;
 
-    let mut proposal_transaction_data = get_proposal_transaction_data_for_proposal(
+    let proposal_transaction_data = get_proposal_transaction_data_for_proposal(
         program_id,
         proposal_transaction_info,
         proposal_info.key,
     )?;
 
-    proposal_data
-        .assert_can_execute_transaction(&proposal_transaction_data, clock.unix_timestamp)?;
+    let option_index = proposal_transaction_data.option_index as usize;
+    let mut proposal_transactions = vec![(proposal_transaction_info, proposal_transaction_data)];
+
+    // For atomic execution all transactions of the option are executed within this instruction
+    // and the remaining ProposalTransaction accounts of the option must follow in the order of their index
+    if proposal_data.execution_flags == InstructionExecutionFlags::UseTransaction {
+        for _ in 1..proposal_data.options[option_index].transactions_count {
+            let next_proposal_transaction_info = next_account_info(account_info_iter)?;
+
+            let next_proposal_transaction_data = get_proposal_transaction_data_for_proposal(
+                program_id,
+                next_proposal_transaction_info,
+                proposal_info.key,
+            )?;
+
+            let (_, previous_proposal_transaction_data) = proposal_transactions.last().unwrap();
+
+            if next_proposal_transaction_data.option_index as usize != option_index
+                || next_proposal_transaction_data.transaction_index
+                    <= previous_proposal_transaction_data.transaction_index
+            {
+                return Err(GovernanceError::InvalidTransactionsForAtomicExecution.into());
+            }
+
+            proposal_transactions.push((
+                next_proposal_transaction_info,
+                next_proposal_transaction_data,
+            ));
+        }
+    }
+
+    for (_, proposal_transaction_data) in proposal_transactions.iter() {
+        proposal_data
+            .assert_can_execute_transaction(proposal_transaction_data, clock.unix_timestamp)?;
+    }
 
     // Execute instruction with Governance PDA as signer
-    let instructions = proposal_transaction_data
-        .instructions
+    let instructions = proposal_transactions
         .iter()
+        .flat_map(|(_, proposal_transaction_data)| proposal_transaction_data.instructions.iter())
         .map(Instruction::from);
 
     // In the current implementation accounts for all instructions are passed to each instruction invocation
@@ -88,8 +124,11 @@ pub fn process_execute_transaction(program_id: &Pubkey, accounts: &[AccountInfo]
         proposal_data.state = ProposalState::Executing;
     }
 
-    let mut option = &mut proposal_data.options[proposal_transaction_data.option_index as usize];
-    option.transactions_executed_count = option.transactions_executed_count.checked_add(1).unwrap();
+    let mut option = &mut proposal_data.options[option_index];
+    option.transactions_executed_count = option
+        .transactions_executed_count
+        .checked_add(proposal_transactions.len() as u16)
+        .unwrap();
 
     // Checking for Executing and ExecutingWithErrors states because instruction can still be executed after being flagged with error
     // The check for instructions_executed_count ensures Proposal can't be transitioned to Completed state from ExecutingWithErrors
@@ -107,9 +146,11 @@ pub fn process_execute_transaction(program_id: &Pubkey, accounts: &[AccountInfo]
 
     proposal_data.serialize(&mut *proposal_info.data.borrow_mut())?;
 
-    proposal_transaction_data.executed_at = Some(clock.unix_timestamp);
-    proposal_transaction_data.execution_status = TransactionExecutionStatus::Success;
-    proposal_transaction_data.serialize(&mut *proposal_transaction_info.data.borrow_mut())?;
+    for (proposal_transaction_info, mut proposal_transaction_data) in proposal_transactions {
+        proposal_transaction_data.executed_at = Some(clock.unix_timestamp);
+        proposal_transaction_data.execution_status = TransactionExecutionStatus::Success;
+        proposal_transaction_data.serialize(&mut *proposal_transaction_info.data.borrow_mut())?;
+    }
 
     Ok(())
 }
@@ -103,7 +103,13 @@ pub fn process_relinquish_vote(program_id: &Pubkey, accounts: &[AccountInfo]) ->
                     .unwrap();
             }
             Vote::Abstain => {
-                return Err(GovernanceError::NotSupportedVoteType.into());
+                proposal_data.abstain_vote_weight = Some(
+                    proposal_data
+                        .abstain_vote_weight
+                        .unwrap()
+                        .checked_sub(vote_record_data.voter_weight)
+                        .unwrap(),
+                )
             }
         }
 
@@ -0,0 +1,41 @@
+//! Program state processor
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+};
+
+use crate::state::{
+    enums::InstructionExecutionFlags, proposal::get_proposal_data,
+    token_owner_record::get_token_owner_record_data_for_proposal_owner,
+};
+
+/// Processes SetProposalExecutionFlags instruction
+pub fn process_set_proposal_execution_flags(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    execution_flags: InstructionExecutionFlags,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let proposal_info = next_account_info(account_info_iter)?; // 0
+    let token_owner_record_info = next_account_info(account_info_iter)?; // 1
+    let governance_authority_info = next_account_info(account_info_iter)?; // 2
+
+    let mut proposal_data = get_proposal_data(program_id, proposal_info)?;
+    proposal_data.assert_can_edit_instructions()?;
+
+    let token_owner_record_data = get_token_owner_record_data_for_proposal_owner(
+        program_id,
+        token_owner_record_info,
+        &proposal_data.token_owner_record,
+    )?;
+
+    token_owner_record_data.assert_token_owner_or_delegate_is_signer(governance_authority_info)?;
+
+    proposal_data.execution_flags = execution_flags;
+    proposal_data.serialize(&mut *proposal_info.data.borrow_mut())?;
+
+    Ok(())
+}
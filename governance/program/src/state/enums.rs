@@ -139,8 +139,6 @@ impl Default for ProposalState {
 }
 
 /// The type of the vote threshold used to resolve a vote on a Proposal
-///
-/// Note: In the current version only YesVotePercentage and Disabled thresholds are supported
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
 pub enum VoteThreshold {
     /// Voting threshold of Yes votes in % required to tip the vote (Approval Quorum)
@@ -152,7 +150,8 @@ pub enum VoteThreshold {
     /// The minimum number of votes in % out of the entire pool of governance tokens eligible to vote
     /// which must be cast for the vote to be valid
     /// Once the quorum is achieved a simple majority (50%+1) of Yes votes is required for the vote to succeed
-    /// Note: Quorum is not implemented in the current version
+    /// In other words the Yes votes must outweigh the No votes
+    /// Note: It's not supported for veto vote thresholds which have no No votes to outweigh
     QuorumPercentage(u8),
 
     /// Disabled vote threshold indicates the given voting population (community or council) is not allowed to vote
@@ -211,14 +210,13 @@ pub enum InstructionExecutionFlags {
     /// Instructions can be executed individually, in any order, as soon as they hold_up time expires
     None,
 
-    /// Instructions are executed in a specific order
-    /// Note: Ordered execution is not supported in the current version
-    /// The implementation requires another account type to track deleted instructions
+    /// Instructions are executed in the order of their transaction index within each option
+    /// The next executable index of an option is tracked by its transactions_executed_count
+    /// and hence the transaction indexes can't have gaps when the Proposal is signed off
     Ordered,
 
-    /// Multiple instructions can be executed as a single transaction
-    /// Note: Transactions are not supported in the current version
-    /// The implementation requires another account type to group instructions within a transaction
+    /// All transactions of an option are executed atomically within a single ExecuteTransaction instruction
+    /// If any of the instructions fails then none of the transactions is executed
     UseTransaction,
 }
 
@@ -231,7 +229,8 @@ pub enum MintMaxVoteWeightSource {
     SupplyFraction(u64),
 
     /// Absolute value, irrelevant of the actual mint supply, is used as max vote weight
-    /// Note: this option is not implemented in the current version
+    /// It can be used when a large part of the supply is not eligible to vote, for example unvested or treasury holdings
+    /// Note: If more votes are cast than the absolute value then the max vote weight is adjusted to the total cast votes
     Absolute(u64),
 }
 
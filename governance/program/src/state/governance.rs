@@ -24,7 +24,6 @@ use spl_governance_tools::{
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
 pub struct GovernanceConfig {
     /// The type of the vote threshold used for community vote
-    /// Note: In the current version only YesVotePercentage and Disabled thresholds are supported
     pub community_vote_threshold: VoteThreshold,
 
     /// Minimum community weight a governance token owner must possess to be able to create a proposal
@@ -40,7 +39,6 @@ pub struct GovernanceConfig {
     pub vote_tipping: VoteTipping,
 
     /// The type of the vote threshold used for council vote
-    /// Note: In the current version only YesVotePercentage and Disabled thresholds are supported
     pub council_vote_threshold: VoteThreshold,
 
     /// The threshold for Council Veto votes
@@ -48,11 +46,10 @@ pub struct GovernanceConfig {
 
     /// Minimum council weight a governance token owner must possess to be able to create a proposal
     pub min_council_weight_to_create_proposal: u64,
-    //
-    // The threshold for Community Veto votes
-    // Note: Community Veto vote is not supported in the current version
-    // In order to use this threshold the space from GovernanceV2.reserved must be taken to expand GovernanceConfig size
-    // pub community_veto_vote_threshold: VoteThreshold,
+
+    /// The threshold for Community Veto votes
+    /// Note: The threshold took the space from GovernanceV2.reserved
+    pub community_veto_vote_threshold: VoteThreshold,
 }
 
 /// Governance Account
@@ -82,7 +79,7 @@ pub struct GovernanceV2 {
     pub config: GovernanceConfig,
 
     /// Reserved space for future versions
-    pub reserved: [u8; 6],
+    pub reserved: [u8; 4],
 
     /// The number of proposals in voting state in the Governance
     pub voting_proposal_count: u16,
@@ -225,10 +222,7 @@ impl GovernanceV2 {
         let vote_threshold = if realm_data.community_mint == *vote_governing_token_mint {
             match vote_kind {
                 VoteKind::Electorate => &self.config.community_vote_threshold,
-                VoteKind::Veto => {
-                    // Community Veto vote is not supported in current version
-                    return Err(GovernanceError::GoverningTokenMintNotAllowedToVote.into());
-                }
+                VoteKind::Veto => &self.config.community_veto_vote_threshold,
             }
         } else if realm_data.config.council_mint == Some(*vote_governing_token_mint) {
             match vote_kind {
@@ -296,6 +290,16 @@ pub fn get_governance_data(
             governance_data.config.council_vote_threshold.clone();
     }
 
+    // community_veto_vote_threshold took the space from reserved which is always 0 for accounts created before it was introduced
+    //
+    // If we read such an account then community_veto_vote_threshold == VoteThreshold::YesVotePercentage(0)
+    // and we coerce it to Disabled to preserve the behaviour of the existing Governances
+    //
+    // Note: assert_is_valid_governance_config() prevents setting community_veto_vote_threshold to VoteThreshold::YesVotePercentage(0)
+    if governance_data.config.community_veto_vote_threshold == VoteThreshold::YesVotePercentage(0) {
+        governance_data.config.community_veto_vote_threshold = VoteThreshold::Disabled;
+    }
+
     Ok(governance_data)
 }
 
@@ -449,7 +453,8 @@ pub fn assert_is_valid_governance_config(
 ) -> Result<(), ProgramError> {
     assert_is_valid_vote_threshold(&governance_config.community_vote_threshold)?;
     assert_is_valid_vote_threshold(&governance_config.council_vote_threshold)?;
-    assert_is_valid_vote_threshold(&governance_config.council_veto_vote_threshold)?;
+    assert_is_valid_veto_vote_threshold(&governance_config.council_veto_vote_threshold)?;
+    assert_is_valid_veto_vote_threshold(&governance_config.community_veto_vote_threshold)?;
 
     // Setting both thresholds to Disabled is not allowed, however we might reconsider it as
     // a way to disable Governance permanently
@@ -470,8 +475,10 @@ pub fn assert_is_valid_vote_threshold(vote_threshold: &VoteThreshold) -> Result<
                 return Err(GovernanceError::InvalidVoteThresholdPercentage.into());
             }
         }
-        VoteThreshold::QuorumPercentage(_) => {
-            return Err(GovernanceError::VoteThresholdTypeNotSupported.into());
+        VoteThreshold::QuorumPercentage(quorum_percentage) => {
+            if !(1..=100).contains(&quorum_percentage) {
+                return Err(GovernanceError::InvalidVoteThresholdPercentage.into());
+            }
         }
         VoteThreshold::Disabled => {}
     }
@@ -479,6 +486,18 @@ pub fn assert_is_valid_vote_threshold(vote_threshold: &VoteThreshold) -> Result<
     Ok(())
 }
 
+/// Asserts the provided veto vote_threshold is valid
+/// A veto has no opposing side to outweigh once a quorum is reached, so only YesVotePercentage or Disabled are supported
+pub fn assert_is_valid_veto_vote_threshold(
+    vote_threshold: &VoteThreshold,
+) -> Result<(), ProgramError> {
+    if let VoteThreshold::QuorumPercentage(_) = vote_threshold {
+        return Err(GovernanceError::VoteThresholdTypeNotSupported.into());
+    }
+
+    assert_is_valid_vote_threshold(vote_threshold)
+}
+
 #[cfg(test)]
 mod test {
     use solana_program::clock::Epoch;
@@ -535,6 +554,11 @@ mod test {
             governance.config.council_vote_threshold,
             governance.config.council_veto_vote_threshold
         );
+
+        assert_eq!(
+            governance.config.community_veto_vote_threshold,
+            VoteThreshold::Disabled
+        );
     }
 
     #[test]
@@ -549,6 +573,7 @@ mod test {
             council_vote_threshold: VoteThreshold::YesVotePercentage(0),
             council_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
             min_council_weight_to_create_proposal: 1,
+            community_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
         };
 
         // Act
@@ -572,6 +597,7 @@ mod test {
             council_vote_threshold: VoteThreshold::YesVotePercentage(1),
             council_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
             min_council_weight_to_create_proposal: 1,
+            community_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
         };
 
         // Act
@@ -595,6 +621,7 @@ mod test {
             council_vote_threshold: VoteThreshold::Disabled,
             council_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
             min_council_weight_to_create_proposal: 1,
+            community_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
         };
 
         // Act
@@ -618,6 +645,77 @@ mod test {
             council_vote_threshold: VoteThreshold::YesVotePercentage(1),
             council_veto_vote_threshold: VoteThreshold::YesVotePercentage(0),
             min_council_weight_to_create_proposal: 1,
+            community_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
+        };
+
+        // Act
+        let err = assert_is_valid_governance_config(&governance_config)
+            .err()
+            .unwrap();
+
+        // Assert
+        assert_eq!(err, GovernanceError::InvalidVoteThresholdPercentage.into());
+    }
+
+    #[test]
+    fn test_assert_config_invalid_with_community_zero_yes_veto_vote_threshold() {
+        // Arrange
+        let governance_config = GovernanceConfig {
+            community_vote_threshold: VoteThreshold::YesVotePercentage(1),
+            min_community_weight_to_create_proposal: 1,
+            min_transaction_hold_up_time: 1,
+            max_voting_time: 1,
+            vote_tipping: VoteTipping::Strict,
+            council_vote_threshold: VoteThreshold::YesVotePercentage(1),
+            council_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
+            min_council_weight_to_create_proposal: 1,
+            community_veto_vote_threshold: VoteThreshold::YesVotePercentage(0),
+        };
+
+        // Act
+        let err = assert_is_valid_governance_config(&governance_config)
+            .err()
+            .unwrap();
+
+        // Assert
+        assert_eq!(err, GovernanceError::InvalidVoteThresholdPercentage.into());
+    }
+
+    #[test]
+    fn test_assert_config_valid_with_quorum_vote_threshold() {
+        // Arrange
+        let governance_config = GovernanceConfig {
+            community_vote_threshold: VoteThreshold::QuorumPercentage(30),
+            min_community_weight_to_create_proposal: 1,
+            min_transaction_hold_up_time: 1,
+            max_voting_time: 1,
+            vote_tipping: VoteTipping::Strict,
+            council_vote_threshold: VoteThreshold::QuorumPercentage(100),
+            council_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
+            min_council_weight_to_create_proposal: 1,
+            community_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
+        };
+
+        // Act
+        let result = assert_is_valid_governance_config(&governance_config);
+
+        // Assert
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_assert_config_invalid_with_zero_quorum_vote_threshold() {
+        // Arrange
+        let governance_config = GovernanceConfig {
+            community_vote_threshold: VoteThreshold::QuorumPercentage(0),
+            min_community_weight_to_create_proposal: 1,
+            min_transaction_hold_up_time: 1,
+            max_voting_time: 1,
+            vote_tipping: VoteTipping::Strict,
+            council_vote_threshold: VoteThreshold::YesVotePercentage(1),
+            council_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
+            min_council_weight_to_create_proposal: 1,
+            community_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
         };
 
         // Act
@@ -628,4 +726,52 @@ mod test {
         // Assert
         assert_eq!(err, GovernanceError::InvalidVoteThresholdPercentage.into());
     }
+
+    #[test]
+    fn test_assert_config_invalid_with_council_quorum_veto_vote_threshold() {
+        // Arrange
+        let governance_config = GovernanceConfig {
+            community_vote_threshold: VoteThreshold::YesVotePercentage(1),
+            min_community_weight_to_create_proposal: 1,
+            min_transaction_hold_up_time: 1,
+            max_voting_time: 1,
+            vote_tipping: VoteTipping::Strict,
+            council_vote_threshold: VoteThreshold::YesVotePercentage(1),
+            council_veto_vote_threshold: VoteThreshold::QuorumPercentage(30),
+            min_council_weight_to_create_proposal: 1,
+            community_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
+        };
+
+        // Act
+        let err = assert_is_valid_governance_config(&governance_config)
+            .err()
+            .unwrap();
+
+        // Assert
+        assert_eq!(err, GovernanceError::VoteThresholdTypeNotSupported.into());
+    }
+
+    #[test]
+    fn test_assert_config_invalid_with_community_quorum_veto_vote_threshold() {
+        // Arrange
+        let governance_config = GovernanceConfig {
+            community_vote_threshold: VoteThreshold::YesVotePercentage(1),
+            min_community_weight_to_create_proposal: 1,
+            min_transaction_hold_up_time: 1,
+            max_voting_time: 1,
+            vote_tipping: VoteTipping::Strict,
+            council_vote_threshold: VoteThreshold::YesVotePercentage(1),
+            council_veto_vote_threshold: VoteThreshold::YesVotePercentage(1),
+            min_council_weight_to_create_proposal: 1,
+            community_veto_vote_threshold: VoteThreshold::QuorumPercentage(30),
+        };
+
+        // Act
+        let err = assert_is_valid_governance_config(&governance_config)
+            .err()
+            .unwrap();
+
+        // Assert
+        assert_eq!(err, GovernanceError::VoteThresholdTypeNotSupported.into());
+    }
 }
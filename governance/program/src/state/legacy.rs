@@ -127,7 +127,7 @@ pub struct GovernanceV1 {
     pub config: GovernanceConfig,
 
     /// Reserved space for future versions
-    pub reserved: [u8; 6],
+    pub reserved: [u8; 4],
 
     /// The number of proposals in voting state in the Governance
     /// Note: This is field introduced in V2 but it took space from reserved
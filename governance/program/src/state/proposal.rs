@@ -144,8 +144,9 @@ pub struct ProposalV2 {
     /// This field is a leftover from unused veto_vote_weight: Option<u64>
     pub reserved1: u8,
 
-    /// The total weight of  votes
-    /// Note: Abstain is not supported in the current version
+    /// The total weight of Abstain votes
+    /// Abstain votes count towards the quorum but don't count towards the approval of any option
+    /// Note: The field is None for proposals created before Abstain votes were supported
     pub abstain_vote_weight: Option<u64>,
 
     /// Optional start time if the Proposal should not enter voting state immediately after being signed off
@@ -175,7 +176,6 @@ pub struct ProposalV2 {
     pub closed_at: Option<UnixTimestamp>,
 
     /// Instruction execution flag for ordered and transactional instructions
-    /// Note: This field is not used in the current version
     pub execution_flags: InstructionExecutionFlags,
 
     /// The max vote weight for the Governing Token mint at the time Proposal was decided
@@ -229,7 +229,7 @@ impl ProposalV2 {
     /// Checks if Proposal can be singed off
     pub fn assert_can_sign_off(&self) -> Result<(), ProgramError> {
         match self.state {
-            ProposalState::Draft | ProposalState::SigningOff => Ok(()),
+            ProposalState::Draft | ProposalState::SigningOff => {}
             ProposalState::Executing
             | ProposalState::ExecutingWithErrors
             | ProposalState::Completed
@@ -237,8 +237,23 @@ impl ProposalV2 {
             | ProposalState::Voting
             | ProposalState::Succeeded
             | ProposalState::Defeated
-            | ProposalState::Vetoed => Err(GovernanceError::InvalidStateCannotSignOff.into()),
+            | ProposalState::Vetoed => {
+                return Err(GovernanceError::InvalidStateCannotSignOff.into())
+            }
+        }
+
+        // For Ordered execution transactions_executed_count is used as the index of the next transaction to execute
+        // and it requires the transactions of each option to have consecutive indexes without gaps left by removed transactions
+        if self.execution_flags == InstructionExecutionFlags::Ordered
+            && self
+                .options
+                .iter()
+                .any(|o| o.transactions_count != o.transactions_next_index)
+        {
+            return Err(GovernanceError::TransactionIndexGapNotAllowed.into());
         }
+
+        Ok(())
     }
 
     /// Checks the Proposal is in Voting state
@@ -346,16 +361,22 @@ impl ProposalV2 {
         // If the proposal has a reject option then any other option must beat it regardless of the configured min_vote_threshold_weight
         let deny_vote_weight = self.deny_vote_weight.unwrap_or(0);
 
+        // For quorum threshold the min_vote_threshold_weight applies to the total weight of cast votes
+        let is_quorum_reached = self.get_total_cast_vote_weight() >= min_vote_threshold_weight;
+
         let mut best_succeeded_option_weight = 0;
         let mut best_succeeded_option_count = 0u16;
 
         for option in self.options.iter_mut() {
-            // Any positive vote (Yes) must be equal or above the required min_vote_threshold_weight and higher than the reject option vote (No)
+            let is_vote_threshold_reached = match vote_threshold {
+                VoteThreshold::QuorumPercentage(_) => is_quorum_reached,
+                _ => option.vote_weight >= min_vote_threshold_weight,
+            };
+
+            // Any positive vote (Yes) must reach the vote threshold and be higher than the reject option vote (No)
             // The same number of positive (Yes) and rejecting (No) votes is a tie and resolved as Defeated
             // In other words  +1 vote as a tie breaker is required to succeed for the positive option vote
-            if option.vote_weight >= min_vote_threshold_weight
-                && option.vote_weight > deny_vote_weight
-            {
+            if is_vote_threshold_reached && option.vote_weight > deny_vote_weight {
                 option.vote_result = OptionVoteResult::Succeeded;
 
                 match option.vote_weight.cmp(&best_succeeded_option_weight) {
@@ -418,6 +439,30 @@ impl ProposalV2 {
         Ok(final_state)
     }
 
+    /// Returns the total weight of Electorate votes cast on the Proposal including Abstain votes
+    /// Note: For MultiChoice votes a voter's weight is counted for each approved option
+    ///       and hence only the weight of the most voted option is taken as the lower bound
+    fn get_total_cast_vote_weight(&self) -> u64 {
+        let deny_vote_weight = self.deny_vote_weight.unwrap_or(0);
+        let abstain_vote_weight = self.abstain_vote_weight.unwrap_or(0);
+
+        let options_vote_weight = match self.vote_type {
+            VoteType::SingleChoice => self
+                .options
+                .iter()
+                .fold(0u64, |total, o| total.checked_add(o.vote_weight).unwrap()),
+            VoteType::MultiChoice { .. } => {
+                self.options.iter().map(|o| o.vote_weight).max().unwrap()
+            }
+        };
+
+        options_vote_weight
+            .checked_add(deny_vote_weight)
+            .unwrap()
+            .checked_add(abstain_vote_weight)
+            .unwrap()
+    }
+
     /// Calculates max voter weight for given mint supply and realm config
     fn get_max_voter_weight_from_mint_supply(
         &mut self,
@@ -426,7 +471,7 @@ impl ProposalV2 {
         governing_token_mint_supply: u64,
         vote_kind: &VoteKind,
     ) -> Result<u64, ProgramError> {
-        // max vote weight fraction is only used for community mint
+        // max vote weight source is only used for community mint
         if Some(*governing_token_mint) == realm_data.config.council_mint {
             return Ok(governing_token_mint_supply);
         }
@@ -447,8 +492,10 @@ impl ProposalV2 {
                 // and we have to adjust it in case more votes have been cast
                 Ok(self.coerce_max_voter_weight(max_voter_weight, vote_kind))
             }
-            MintMaxVoteWeightSource::Absolute(_) => {
-                Err(GovernanceError::VoteWeightSourceNotSupported.into())
+            MintMaxVoteWeightSource::Absolute(max_voter_weight) => {
+                // The absolute value is independent of the mint supply and it's possible that more votes
+                // have been cast in which case we have to adjust it
+                Ok(self.coerce_max_voter_weight(max_voter_weight, vote_kind))
             }
         }
     }
@@ -458,6 +505,7 @@ impl ProposalV2 {
         let total_vote_weight = match vote_kind {
             VoteKind::Electorate => {
                 let deny_vote_weight = self.deny_vote_weight.unwrap_or(0);
+                let abstain_vote_weight = self.abstain_vote_weight.unwrap_or(0);
 
                 let max_option_vote_weight =
                     self.options.iter().map(|o| o.vote_weight).max().unwrap();
@@ -465,6 +513,8 @@ impl ProposalV2 {
                 max_option_vote_weight
                     .checked_add(deny_vote_weight)
                     .unwrap()
+                    .checked_add(abstain_vote_weight)
+                    .unwrap()
             }
             VoteKind::Veto => self.veto_vote_weight,
         };
@@ -570,6 +620,7 @@ impl ProposalV2 {
             VoteKind::Electorate => self.try_get_tipped_electorate_vote_state(
                 max_voter_weight,
                 vote_tipping,
+                vote_threshold,
                 min_vote_threshold_weight,
             ),
             VoteKind::Veto => self.try_get_tipped_veto_vote_state(min_vote_threshold_weight),
@@ -582,6 +633,7 @@ impl ProposalV2 {
         &mut self,
         max_voter_weight: u64,
         vote_tipping: &VoteTipping,
+        vote_threshold: &VoteThreshold,
         min_vote_threshold_weight: u64,
     ) -> Option<ProposalState> {
         // Vote tipping is currently supported for SingleChoice votes with single Yes and No (rejection) options only
@@ -599,6 +651,10 @@ impl ProposalV2 {
 
         let yes_vote_weight = yes_option.vote_weight;
         let deny_vote_weight = self.deny_vote_weight.unwrap();
+        let abstain_vote_weight = self.abstain_vote_weight.unwrap_or(0);
+
+        // Abstain votes can't be changed into Yes or No votes and hence reduce the weight which is still to be cast
+        let max_undecided_vote_weight = max_voter_weight.saturating_sub(abstain_vote_weight);
 
         if yes_vote_weight == max_voter_weight {
             yes_option.vote_result = OptionVoteResult::Succeeded;
@@ -610,10 +666,29 @@ impl ProposalV2 {
             return Some(ProposalState::Defeated);
         }
 
+        // For quorum threshold the min_vote_threshold_weight applies to the total weight of cast votes
+        // and once the quorum is reached the Yes votes only have to outweigh the No votes
+        let (is_vote_threshold_reached, can_reach_vote_threshold) = match vote_threshold {
+            VoteThreshold::QuorumPercentage(_) => (
+                yes_vote_weight
+                    .checked_add(deny_vote_weight)
+                    .unwrap()
+                    .checked_add(abstain_vote_weight)
+                    .unwrap()
+                    >= min_vote_threshold_weight,
+                true,
+            ),
+            _ => (
+                yes_vote_weight >= min_vote_threshold_weight,
+                deny_vote_weight
+                    <= (max_undecided_vote_weight.saturating_sub(min_vote_threshold_weight)),
+            ),
+        };
+
         match vote_tipping {
             VoteTipping::Disabled => {}
             VoteTipping::Strict => {
-                if yes_vote_weight >= min_vote_threshold_weight
+                if is_vote_threshold_reached
                     && yes_vote_weight > (max_voter_weight.saturating_sub(yes_vote_weight))
                 {
                     yes_option.vote_result = OptionVoteResult::Succeeded;
@@ -621,9 +696,7 @@ impl ProposalV2 {
                 }
             }
             VoteTipping::Early => {
-                if yes_vote_weight >= min_vote_threshold_weight
-                    && yes_vote_weight > deny_vote_weight
-                {
+                if is_vote_threshold_reached && yes_vote_weight > deny_vote_weight {
                     yes_option.vote_result = OptionVoteResult::Succeeded;
                     return Some(ProposalState::Succeeded);
                 }
@@ -635,8 +708,8 @@ impl ProposalV2 {
         // min_vote_threshold_weight for another option. This tipping is always
         // strict, there's no equivalent to "early" tipping for deny votes.
         if *vote_tipping != VoteTipping::Disabled
-            && (deny_vote_weight > (max_voter_weight.saturating_sub(min_vote_threshold_weight))
-                || deny_vote_weight >= (max_voter_weight.saturating_sub(deny_vote_weight)))
+            && (!can_reach_vote_threshold
+                || deny_vote_weight >= (max_undecided_vote_weight.saturating_sub(deny_vote_weight)))
         {
             yes_option.vote_result = OptionVoteResult::Defeated;
             return Some(ProposalState::Defeated);
@@ -725,12 +798,18 @@ impl ProposalV2 {
             }
         }
 
-        if self.options[proposal_transaction_data.option_index as usize].vote_result
-            != OptionVoteResult::Succeeded
-        {
+        let option = &self.options[proposal_transaction_data.option_index as usize];
+
+        if option.vote_result != OptionVoteResult::Succeeded {
             return Err(GovernanceError::CannotExecuteDefeatedOption.into());
         }
 
+        if self.execution_flags == InstructionExecutionFlags::Ordered
+            && proposal_transaction_data.transaction_index != option.transactions_executed_count
+        {
+            return Err(GovernanceError::InvalidTransactionExecutionOrder.into());
+        }
+
         if self
             .voting_completed_at
             .unwrap()
@@ -808,7 +887,9 @@ impl ProposalV2 {
                 }
             }
             Vote::Abstain => {
-                return Err(GovernanceError::NotSupportedVoteType.into());
+                if self.abstain_vote_weight.is_none() {
+                    return Err(GovernanceError::InvalidVote.into());
+                }
             }
             Vote::Veto => {}
         }
@@ -879,20 +960,22 @@ impl ProposalV2 {
 
 /// Converts given vote threshold (ex. in percentages) to absolute vote weight
 /// and returns the min weight required for a proposal option to pass
+/// For quorum threshold it's the min weight of all cast votes required for the vote to be valid
 fn get_min_vote_threshold_weight(
     vote_threshold: &VoteThreshold,
     max_voter_weight: u64,
 ) -> Result<u64, ProgramError> {
-    let yes_vote_threshold_percentage = match vote_threshold {
+    let vote_threshold_percentage = match vote_threshold {
         VoteThreshold::YesVotePercentage(yes_vote_threshold_percentage) => {
             *yes_vote_threshold_percentage
         }
-        _ => {
+        VoteThreshold::QuorumPercentage(quorum_percentage) => *quorum_percentage,
+        VoteThreshold::Disabled => {
             return Err(GovernanceError::VoteThresholdTypeNotSupported.into());
         }
     };
 
-    let numerator = (yes_vote_threshold_percentage as u128)
+    let numerator = (vote_threshold_percentage as u128)
         .checked_mul(max_voter_weight as u128)
         .unwrap();
 
@@ -1182,6 +1265,7 @@ mod test {
         GovernanceConfig {
             min_community_weight_to_create_proposal: 5,
             min_council_weight_to_create_proposal: 1,
+            community_veto_vote_threshold: VoteThreshold::Disabled,
             min_transaction_hold_up_time: 10,
             max_voting_time: 5,
             community_vote_threshold: VoteThreshold::YesVotePercentage(60),
@@ -1790,6 +1874,259 @@ mod test {
         }
     }
 
+    proptest! {
+        #[test]
+        fn test_try_tip_vote_with_quorum_full_vote_results(
+            (yes_votes_count, no_votes_count, governing_token_supply, quorum_percentage) in full_vote_results(),
+
+        ) {
+            // Arrange
+            let mut proposal = create_test_proposal();
+
+            proposal.options[0].vote_weight = yes_votes_count;
+            proposal.deny_vote_weight = Some(no_votes_count.min(governing_token_supply-yes_votes_count));
+
+            proposal.state = ProposalState::Voting;
+
+            let current_timestamp = 15_i64;
+
+            let realm = create_test_realm();
+            let governing_token_mint = proposal.governing_token_mint;
+            let vote_kind = VoteKind::Electorate;
+            let vote_tipping = VoteTipping::Strict;
+
+            let max_voter_weight = proposal.get_max_voter_weight_from_mint_supply(&realm,&governing_token_mint,governing_token_supply,&vote_kind).unwrap();
+            let vote_threshold = VoteThreshold::QuorumPercentage(quorum_percentage);
+
+            // Act
+            proposal.try_tip_vote(max_voter_weight, &vote_tipping, current_timestamp,&vote_threshold,&vote_kind).unwrap();
+
+            // Assert
+            let quorum_count = get_min_vote_threshold_weight(&vote_threshold,governing_token_supply).unwrap();
+
+            let no_vote_weight = proposal.deny_vote_weight.unwrap();
+
+            if yes_votes_count + no_vote_weight >= quorum_count && yes_votes_count > (governing_token_supply - yes_votes_count)
+            {
+                assert_eq!(proposal.state,ProposalState::Succeeded);
+            } else if no_vote_weight >= (governing_token_supply - no_vote_weight ) {
+                assert_eq!(proposal.state,ProposalState::Defeated);
+            } else {
+                assert_eq!(proposal.state,ProposalState::Voting);
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_finalize_vote_with_quorum_full_vote_results(
+            (yes_votes_count, no_votes_count, governing_token_supply, quorum_percentage) in full_vote_results(),
+
+        ) {
+            // Arrange
+            let mut proposal = create_test_proposal();
+
+            proposal.options[0].vote_weight = yes_votes_count;
+            proposal.deny_vote_weight = Some(no_votes_count.min(governing_token_supply-yes_votes_count));
+
+            proposal.state = ProposalState::Voting;
+
+            let governance_config = create_test_governance_config();
+
+            let current_timestamp = 16_i64;
+
+            let realm = create_test_realm();
+            let governing_token_mint = proposal.governing_token_mint;
+            let vote_kind = VoteKind::Electorate;
+
+            let max_voter_weight = proposal.get_max_voter_weight_from_mint_supply(&realm,&governing_token_mint,governing_token_supply,&vote_kind).unwrap();
+            let vote_threshold = VoteThreshold::QuorumPercentage(quorum_percentage);
+
+            // Act
+            proposal.finalize_vote(max_voter_weight, &governance_config,current_timestamp,&vote_threshold).unwrap();
+
+            // Assert
+            let no_vote_weight = proposal.deny_vote_weight.unwrap();
+
+            let quorum_count = get_min_vote_threshold_weight(&vote_threshold,governing_token_supply).unwrap();
+
+            if yes_votes_count + no_vote_weight >= quorum_count && yes_votes_count > no_vote_weight
+            {
+                assert_eq!(proposal.state,ProposalState::Succeeded);
+            } else {
+                assert_eq!(proposal.state,ProposalState::Defeated);
+            }
+        }
+    }
+
+    #[test]
+    fn test_finalize_vote_with_quorum_not_reached() {
+        // Arrange
+        let mut proposal = create_test_proposal();
+
+        // Yes outweighs No but only 30% of the supply voted
+        proposal.options[0].vote_weight = 20;
+        proposal.deny_vote_weight = Some(10);
+
+        proposal.state = ProposalState::Voting;
+
+        let governance_config = create_test_governance_config();
+        let current_timestamp = 16_i64;
+        let vote_threshold = VoteThreshold::QuorumPercentage(40);
+
+        // Act
+        proposal
+            .finalize_vote(100, &governance_config, current_timestamp, &vote_threshold)
+            .unwrap();
+
+        // Assert
+        assert_eq!(proposal.state, ProposalState::Defeated);
+        assert_eq!(proposal.options[0].vote_result, OptionVoteResult::Defeated);
+    }
+
+    #[test]
+    fn test_try_tip_vote_with_quorum_and_early_tipping() {
+        // Arrange
+        let mut proposal = create_test_proposal();
+
+        // The quorum of 40 is reached and Yes outweighs No
+        proposal.options[0].vote_weight = 25;
+        proposal.deny_vote_weight = Some(15);
+
+        proposal.state = ProposalState::Voting;
+
+        let current_timestamp = 15_i64;
+        let vote_threshold = VoteThreshold::QuorumPercentage(40);
+
+        // Act
+        proposal
+            .try_tip_vote(
+                100,
+                &VoteTipping::Early,
+                current_timestamp,
+                &vote_threshold,
+                &VoteKind::Electorate,
+            )
+            .unwrap();
+
+        // Assert
+        assert_eq!(proposal.state, ProposalState::Succeeded);
+        assert_eq!(proposal.vote_threshold, Some(vote_threshold));
+    }
+
+    #[test]
+    fn test_finalize_vote_with_quorum_reached_with_abstain_votes() {
+        // Arrange
+        let mut proposal = create_test_proposal();
+
+        // Yes outweighs No and the quorum of 40 is only reached with the Abstain votes
+        proposal.options[0].vote_weight = 20;
+        proposal.deny_vote_weight = Some(10);
+        proposal.abstain_vote_weight = Some(10);
+
+        proposal.state = ProposalState::Voting;
+
+        let governance_config = create_test_governance_config();
+        let current_timestamp = 16_i64;
+        let vote_threshold = VoteThreshold::QuorumPercentage(40);
+
+        // Act
+        proposal
+            .finalize_vote(100, &governance_config, current_timestamp, &vote_threshold)
+            .unwrap();
+
+        // Assert
+        assert_eq!(proposal.state, ProposalState::Succeeded);
+        assert_eq!(proposal.options[0].vote_result, OptionVoteResult::Succeeded);
+    }
+
+    #[test]
+    fn test_finalize_vote_with_abstain_votes_not_counted_towards_approval() {
+        // Arrange
+        let mut proposal = create_test_proposal();
+
+        // Yes and Abstain votes together would reach the 60% threshold but Abstain doesn't count towards approval
+        proposal.options[0].vote_weight = 50;
+        proposal.deny_vote_weight = Some(10);
+        proposal.abstain_vote_weight = Some(40);
+
+        proposal.state = ProposalState::Voting;
+
+        let governance_config = create_test_governance_config();
+        let current_timestamp = 16_i64;
+        let vote_threshold = VoteThreshold::YesVotePercentage(60);
+
+        // Act
+        proposal
+            .finalize_vote(100, &governance_config, current_timestamp, &vote_threshold)
+            .unwrap();
+
+        // Assert
+        assert_eq!(proposal.state, ProposalState::Defeated);
+        assert_eq!(proposal.options[0].vote_result, OptionVoteResult::Defeated);
+    }
+
+    #[test]
+    fn test_try_tip_vote_to_defeated_with_abstain_votes() {
+        // Arrange
+        let mut proposal = create_test_proposal();
+
+        // With 30 Abstain votes the remaining 40 votes can't reach the 60% threshold any longer
+        proposal.options[0].vote_weight = 20;
+        proposal.deny_vote_weight = Some(11);
+        proposal.abstain_vote_weight = Some(30);
+
+        proposal.state = ProposalState::Voting;
+
+        let current_timestamp = 15_i64;
+        let vote_threshold = VoteThreshold::YesVotePercentage(60);
+
+        // Act
+        proposal
+            .try_tip_vote(
+                100,
+                &VoteTipping::Strict,
+                current_timestamp,
+                &vote_threshold,
+                &VoteKind::Electorate,
+            )
+            .unwrap();
+
+        // Assert
+        assert_eq!(proposal.state, ProposalState::Defeated);
+        assert_eq!(proposal.options[0].vote_result, OptionVoteResult::Defeated);
+    }
+
+    #[test]
+    fn test_try_tip_vote_with_quorum_reached_with_abstain_votes() {
+        // Arrange
+        let mut proposal = create_test_proposal();
+
+        // The quorum of 40 is reached only with the Abstain votes and Yes outweighs No
+        proposal.options[0].vote_weight = 20;
+        proposal.deny_vote_weight = Some(5);
+        proposal.abstain_vote_weight = Some(15);
+
+        proposal.state = ProposalState::Voting;
+
+        let current_timestamp = 15_i64;
+        let vote_threshold = VoteThreshold::QuorumPercentage(40);
+
+        // Act
+        proposal
+            .try_tip_vote(
+                100,
+                &VoteTipping::Early,
+                current_timestamp,
+                &vote_threshold,
+                &VoteKind::Electorate,
+            )
+            .unwrap();
+
+        // Assert
+        assert_eq!(proposal.state, ProposalState::Succeeded);
+    }
+
     #[test]
     fn test_try_tip_vote_with_reduced_community_mint_max_vote_weight() {
         // Arrange
@@ -2001,6 +2338,201 @@ mod test {
         assert_eq!(proposal.max_vote_weight, Some(100));
     }
 
+    #[test]
+    fn test_try_tip_vote_with_absolute_community_mint_max_vote_weight() {
+        // Arrange
+        let mut proposal = create_test_proposal();
+
+        proposal.options[0].vote_weight = 60;
+        proposal.deny_vote_weight = Some(10);
+
+        proposal.state = ProposalState::Voting;
+
+        let current_timestamp = 15_i64;
+
+        let community_token_supply = 1000;
+
+        let mut realm = create_test_realm();
+        let governing_token_mint = proposal.governing_token_mint;
+        let vote_kind = VoteKind::Electorate;
+        let vote_tipping = VoteTipping::Strict;
+
+        // set max vote weight to 100 irrespective of the mint supply
+        realm.config.community_mint_max_vote_weight_source = MintMaxVoteWeightSource::Absolute(100);
+
+        let max_voter_weight = proposal
+            .get_max_voter_weight_from_mint_supply(
+                &realm,
+                &governing_token_mint,
+                community_token_supply,
+                &vote_kind,
+            )
+            .unwrap();
+
+        let vote_threshold = VoteThreshold::YesVotePercentage(60);
+
+        // Act
+        proposal
+            .try_tip_vote(
+                max_voter_weight,
+                &vote_tipping,
+                current_timestamp,
+                &vote_threshold,
+                &vote_kind,
+            )
+            .unwrap();
+
+        // Assert
+        assert_eq!(proposal.state, ProposalState::Succeeded);
+        assert_eq!(proposal.max_vote_weight, Some(100));
+    }
+
+    #[test]
+    fn test_try_tip_vote_with_absolute_community_mint_max_vote_weight_and_vote_overflow() {
+        // Arrange
+        let mut proposal = create_test_proposal();
+
+        proposal.deny_vote_weight = Some(10);
+
+        proposal.state = ProposalState::Voting;
+
+        let current_timestamp = 15_i64;
+
+        let community_token_supply = 1000;
+
+        let mut realm = create_test_realm();
+        let governing_token_mint = proposal.governing_token_mint;
+        let vote_kind = VoteKind::Electorate;
+        let vote_tipping = VoteTipping::Strict;
+
+        realm.config.community_mint_max_vote_weight_source = MintMaxVoteWeightSource::Absolute(100);
+
+        // vote above the absolute max vote weight
+        // Yes vote weight
+        proposal.options[0].vote_weight = 120;
+
+        let max_voter_weight = proposal
+            .get_max_voter_weight_from_mint_supply(
+                &realm,
+                &governing_token_mint,
+                community_token_supply,
+                &vote_kind,
+            )
+            .unwrap();
+
+        let vote_threshold = VoteThreshold::YesVotePercentage(60);
+
+        // Act
+        proposal
+            .try_tip_vote(
+                max_voter_weight,
+                &vote_tipping,
+                current_timestamp,
+                &vote_threshold,
+                &vote_kind,
+            )
+            .unwrap();
+
+        // Assert
+        assert_eq!(proposal.state, ProposalState::Succeeded);
+        assert_eq!(proposal.max_vote_weight, Some(130));
+    }
+
+    #[test]
+    fn test_try_tip_vote_for_council_vote_with_absolute_community_mint_max_vote_weight() {
+        // Arrange
+        let mut proposal = create_test_proposal();
+
+        proposal.options[0].vote_weight = 60;
+        proposal.deny_vote_weight = Some(10);
+
+        proposal.state = ProposalState::Voting;
+
+        let current_timestamp = 15_i64;
+
+        let council_token_supply = 200;
+
+        let mut realm = create_test_realm();
+        let governing_token_mint = proposal.governing_token_mint;
+        let vote_kind = VoteKind::Electorate;
+        let vote_tipping = VoteTipping::Strict;
+
+        realm.config.community_mint_max_vote_weight_source = MintMaxVoteWeightSource::Absolute(100);
+        realm.config.council_mint = Some(governing_token_mint);
+
+        let max_voter_weight = proposal
+            .get_max_voter_weight_from_mint_supply(
+                &realm,
+                &governing_token_mint,
+                council_token_supply,
+                &vote_kind,
+            )
+            .unwrap();
+
+        let vote_threshold = VoteThreshold::YesVotePercentage(60);
+
+        // Act
+        proposal
+            .try_tip_vote(
+                max_voter_weight,
+                &vote_tipping,
+                current_timestamp,
+                &vote_threshold,
+                &vote_kind,
+            )
+            .unwrap();
+
+        // Assert
+        assert_eq!(proposal.state, ProposalState::Voting);
+    }
+
+    #[test]
+    fn test_finalize_vote_with_absolute_community_mint_max_vote_weight() {
+        // Arrange
+        let mut proposal = create_test_proposal();
+
+        proposal.options[0].vote_weight = 60;
+        proposal.deny_vote_weight = Some(10);
+
+        proposal.state = ProposalState::Voting;
+
+        let governance_config = create_test_governance_config();
+
+        let current_timestamp = 16_i64;
+        let community_token_supply = 1000;
+
+        let mut realm = create_test_realm();
+        let governing_token_mint = proposal.governing_token_mint;
+        let vote_kind = VoteKind::Electorate;
+
+        realm.config.community_mint_max_vote_weight_source = MintMaxVoteWeightSource::Absolute(100);
+
+        let max_voter_weight = proposal
+            .get_max_voter_weight_from_mint_supply(
+                &realm,
+                &governing_token_mint,
+                community_token_supply,
+                &vote_kind,
+            )
+            .unwrap();
+
+        let vote_threshold = VoteThreshold::YesVotePercentage(60);
+
+        // Act
+        proposal
+            .finalize_vote(
+                max_voter_weight,
+                &governance_config,
+                current_timestamp,
+                &vote_threshold,
+            )
+            .unwrap();
+
+        // Assert
+        assert_eq!(proposal.state, ProposalState::Succeeded);
+        assert_eq!(proposal.max_vote_weight, Some(100));
+    }
+
     #[test]
     fn test_finalize_vote_with_reduced_community_mint_max_vote_weight_and_vote_overflow() {
         // Arrange
@@ -2159,6 +2691,39 @@ mod test {
         assert_eq!(result, Ok(()));
     }
 
+    #[test]
+    fn test_assert_can_sign_off_with_transaction_index_gap_for_ordered_execution_error() {
+        // Arrange
+        let mut proposal = create_test_proposal();
+        proposal.state = ProposalState::Draft;
+        proposal.execution_flags = InstructionExecutionFlags::Ordered;
+
+        // A transaction was removed and left a gap in the transaction indexes
+        proposal.options[0].transactions_count = 9;
+
+        // Act
+        let err = proposal.assert_can_sign_off().err().unwrap();
+
+        // Assert
+        assert_eq!(err, GovernanceError::TransactionIndexGapNotAllowed.into());
+    }
+
+    #[test]
+    fn test_assert_can_sign_off_with_transaction_index_gap_for_unordered_execution() {
+        // Arrange
+        let mut proposal = create_test_proposal();
+        proposal.state = ProposalState::Draft;
+        proposal.execution_flags = InstructionExecutionFlags::None;
+
+        proposal.options[0].transactions_count = 9;
+
+        // Act
+        let result = proposal.assert_can_sign_off();
+
+        // Assert
+        assert_eq!(result, Ok(()));
+    }
+
     #[test]
     pub fn test_assert_valid_vote_with_deny_vote_for_survey_only_proposal_error() {
         // Arrange
@@ -2175,6 +2740,22 @@ mod test {
         assert_eq!(result, Err(GovernanceError::InvalidVote.into()));
     }
 
+    #[test]
+    pub fn test_assert_valid_vote_with_abstain_vote_for_legacy_proposal_error() {
+        // Arrange
+        let mut proposal = create_test_proposal();
+        proposal.abstain_vote_weight = None;
+
+        // Proposals created before Abstain votes were supported don't track Abstain votes
+        let vote = Vote::Abstain;
+
+        // Act
+        let result = proposal.assert_valid_vote(&vote);
+
+        // Assert
+        assert_eq!(result, Err(GovernanceError::InvalidVote.into()));
+    }
+
     #[test]
     pub fn test_assert_valid_vote_with_too_many_options_error() {
         // Arrange
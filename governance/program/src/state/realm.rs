@@ -191,9 +191,10 @@ impl RealmV2 {
             VoteKind::Veto => {
                 // When Community veto Council proposal then return council_token_mint as the Proposal governing_token_mint
                 if self.community_mint == *vote_governing_token_mint {
-                    // Community Veto is not supported in the current version
-                    return Err(GovernanceError::GoverningTokenMintNotAllowedToVote.into());
-                    //return Ok(self.config.council_mint.unwrap());
+                    return self
+                        .config
+                        .council_mint
+                        .ok_or_else(|| GovernanceError::GoverningTokenMintNotAllowedToVote.into());
                 }
 
                 // When Council veto Community proposal then return community_token_mint as the Proposal governing_token_mint
@@ -406,8 +407,10 @@ pub fn assert_valid_realm_config_args(config_args: &RealmConfigArgs) -> Result<(
                 return Err(GovernanceError::InvalidMaxVoteWeightSupplyFraction.into());
             }
         }
-        MintMaxVoteWeightSource::Absolute(_) => {
-            return Err(GovernanceError::MintMaxVoteWeightSourceNotSupported.into())
+        MintMaxVoteWeightSource::Absolute(value) => {
+            if value == 0 {
+                return Err(GovernanceError::InvalidMaxVoteWeightAbsoluteValue.into());
+            }
         }
     }
 
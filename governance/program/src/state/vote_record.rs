@@ -56,7 +56,7 @@ pub enum Vote {
     Deny,
 
     /// Declare indifference to proposal
-    /// Note: Not supported in the current version
+    /// Abstain votes count towards the quorum but don't count towards the approval
     Abstain,
 
     /// Veto proposal
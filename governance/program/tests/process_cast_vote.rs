@@ -8,7 +8,7 @@ use solana_program_test::tokio;
 use program_test::*;
 use spl_governance::{
     error::GovernanceError,
-    state::enums::{ProposalState, VoteThreshold, VoteTipping},
+    state::enums::{MintMaxVoteWeightSource, ProposalState, VoteThreshold, VoteTipping},
 };
 
 #[tokio::test]
@@ -96,6 +96,134 @@ async fn test_cast_vote() {
     assert_eq!(0, governance_account.voting_proposal_count);
 }
 
+#[tokio::test]
+async fn test_cast_abstain_vote() {
+    // Arrange
+    let mut governance_test = GovernanceProgramTest::start_new().await;
+
+    let realm_cookie = governance_test.with_realm().await;
+    let governed_account_cookie = governance_test.with_governed_account().await;
+
+    let token_owner_record_cookie = governance_test
+        .with_community_token_deposit(&realm_cookie)
+        .await
+        .unwrap();
+
+    let mut governance_cookie = governance_test
+        .with_governance(
+            &realm_cookie,
+            &governed_account_cookie,
+            &token_owner_record_cookie,
+        )
+        .await
+        .unwrap();
+
+    // Total 300 tokens
+    governance_test
+        .mint_community_tokens(&realm_cookie, 200)
+        .await;
+
+    let proposal_cookie = governance_test
+        .with_signed_off_proposal(&token_owner_record_cookie, &mut governance_cookie)
+        .await
+        .unwrap();
+
+    // Act
+    let vote_record_cookie = governance_test
+        .with_cast_yes_no_vote(
+            &proposal_cookie,
+            &token_owner_record_cookie,
+            YesNoVote::Abstain,
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let vote_record_account = governance_test
+        .get_vote_record_account(&vote_record_cookie.address)
+        .await;
+
+    assert_eq!(vote_record_cookie.account, vote_record_account);
+
+    let proposal_account = governance_test
+        .get_proposal_account(&proposal_cookie.address)
+        .await;
+
+    assert_eq!(
+        Some(
+            token_owner_record_cookie
+                .account
+                .governing_token_deposit_amount
+        ),
+        proposal_account.abstain_vote_weight
+    );
+    assert_eq!(0, proposal_account.options[0].vote_weight);
+    assert_eq!(Some(0), proposal_account.deny_vote_weight);
+
+    assert_eq!(proposal_account.state, ProposalState::Voting);
+
+    let token_owner_record = governance_test
+        .get_token_owner_record_account(&token_owner_record_cookie.address)
+        .await;
+
+    assert_eq!(1, token_owner_record.unrelinquished_votes_count);
+    assert_eq!(1, token_owner_record.total_votes_count);
+}
+
+#[tokio::test]
+async fn test_cast_vote_with_absolute_max_vote_weight() {
+    // Arrange
+    let mut governance_test = GovernanceProgramTest::start_new().await;
+
+    let mut set_realm_config_args = governance_test.get_default_set_realm_config_args();
+    set_realm_config_args
+        .realm_config_args
+        .community_mint_max_vote_weight_source = MintMaxVoteWeightSource::Absolute(150);
+
+    let realm_cookie = governance_test
+        .with_realm_using_config_args(&set_realm_config_args)
+        .await;
+    let governed_account_cookie = governance_test.with_governed_account().await;
+
+    let token_owner_record_cookie = governance_test
+        .with_community_token_deposit(&realm_cookie)
+        .await
+        .unwrap();
+
+    let mut governance_cookie = governance_test
+        .with_governance(
+            &realm_cookie,
+            &governed_account_cookie,
+            &token_owner_record_cookie,
+        )
+        .await
+        .unwrap();
+
+    // Total 300 tokens but only 150 of them can be used for voting
+    governance_test
+        .mint_community_tokens(&realm_cookie, 200)
+        .await;
+
+    let proposal_cookie = governance_test
+        .with_signed_off_proposal(&token_owner_record_cookie, &mut governance_cookie)
+        .await
+        .unwrap();
+
+    // Act
+    governance_test
+        .with_cast_yes_no_vote(&proposal_cookie, &token_owner_record_cookie, YesNoVote::Yes)
+        .await
+        .unwrap();
+
+    // Assert
+    let proposal_account = governance_test
+        .get_proposal_account(&proposal_cookie.address)
+        .await;
+
+    assert_eq!(proposal_account.state, ProposalState::Succeeded);
+    assert_eq!(Some(150), proposal_account.max_vote_weight);
+}
+
 #[tokio::test]
 async fn test_cast_vote_with_invalid_governance_error() {
     // Arrange
@@ -60,6 +60,38 @@ async fn test_create_realm_with_non_default_config() {
     assert_eq!(realm_cookie.account, realm_account);
 }
 
+#[tokio::test]
+async fn test_create_realm_with_absolute_max_vote_weight() {
+    // Arrange
+    let mut governance_test = GovernanceProgramTest::start_new().await;
+
+    let realm_config_args = RealmConfigArgs {
+        use_council_mint: true,
+        community_mint_max_vote_weight_source: MintMaxVoteWeightSource::Absolute(1000),
+        min_community_weight_to_create_governance: 10,
+        use_community_voter_weight_addin: false,
+        use_max_community_voter_weight_addin: false,
+    };
+
+    let set_realm_config_args = SetRealmConfigArgs {
+        realm_config_args,
+        community_voter_weight_addin: None,
+        max_community_voter_weight_addin: None,
+    };
+
+    // Act
+    let realm_cookie = governance_test
+        .with_realm_using_config_args(&set_realm_config_args)
+        .await;
+
+    // Assert
+    let realm_account = governance_test
+        .get_realm_account(&realm_cookie.address)
+        .await;
+
+    assert_eq!(realm_cookie.account, realm_account);
+}
+
 #[tokio::test]
 async fn test_create_realm_for_existing_pda() {
     // Arrange
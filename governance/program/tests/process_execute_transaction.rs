@@ -12,7 +12,7 @@ use solana_program_test::tokio;
 use program_test::*;
 use spl_governance::{
     error::GovernanceError,
-    state::enums::{ProposalState, TransactionExecutionStatus},
+    state::enums::{InstructionExecutionFlags, ProposalState, TransactionExecutionStatus},
 };
 
 #[tokio::test]
@@ -747,3 +747,508 @@ async fn test_execute_transaction_with_create_proposal_and_execute_in_single_slo
         GovernanceError::CannotExecuteTransactionWithinHoldUpTime.into()
     );
 }
+
+#[tokio::test]
+async fn test_execute_ordered_transactions() {
+    // Arrange
+    let mut governance_test = GovernanceProgramTest::start_new().await;
+
+    let realm_cookie = governance_test.with_realm().await;
+    let governed_mint_cookie = governance_test.with_governed_mint().await;
+
+    let token_owner_record_cookie = governance_test
+        .with_community_token_deposit(&realm_cookie)
+        .await
+        .unwrap();
+
+    let mut mint_governance_cookie = governance_test
+        .with_mint_governance(
+            &realm_cookie,
+            &governed_mint_cookie,
+            &token_owner_record_cookie,
+        )
+        .await
+        .unwrap();
+
+    let mut proposal_cookie = governance_test
+        .with_proposal(&token_owner_record_cookie, &mut mint_governance_cookie)
+        .await
+        .unwrap();
+
+    let signatory_record_cookie = governance_test
+        .with_signatory(&proposal_cookie, &token_owner_record_cookie)
+        .await
+        .unwrap();
+
+    governance_test
+        .set_proposal_execution_flags(
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            InstructionExecutionFlags::Ordered,
+        )
+        .await
+        .unwrap();
+
+    let proposal_transaction_cookie1 = governance_test
+        .with_mint_tokens_transaction(
+            &governed_mint_cookie,
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            0,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    let proposal_transaction_cookie2 = governance_test
+        .with_mint_tokens_transaction(
+            &governed_mint_cookie,
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            0,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    governance_test
+        .sign_off_proposal(&proposal_cookie, &signatory_record_cookie)
+        .await
+        .unwrap();
+
+    governance_test
+        .with_cast_yes_no_vote(&proposal_cookie, &token_owner_record_cookie, YesNoVote::Yes)
+        .await
+        .unwrap();
+
+    // Advance clock past hold_up_time
+    governance_test
+        .advance_clock_by_min_timespan(proposal_transaction_cookie1.account.hold_up_time as u64)
+        .await;
+
+    // Act
+    governance_test
+        .execute_proposal_transaction(&proposal_cookie, &proposal_transaction_cookie1)
+        .await
+        .unwrap();
+
+    governance_test
+        .execute_proposal_transaction(&proposal_cookie, &proposal_transaction_cookie2)
+        .await
+        .unwrap();
+
+    // Assert
+    let proposal_account = governance_test
+        .get_proposal_account(&proposal_cookie.address)
+        .await;
+
+    assert_eq!(
+        InstructionExecutionFlags::Ordered,
+        proposal_account.execution_flags
+    );
+    assert_eq!(2, proposal_account.options[0].transactions_executed_count);
+    assert_eq!(ProposalState::Completed, proposal_account.state);
+}
+
+#[tokio::test]
+async fn test_execute_ordered_transactions_out_of_order_error() {
+    // Arrange
+    let mut governance_test = GovernanceProgramTest::start_new().await;
+
+    let realm_cookie = governance_test.with_realm().await;
+    let governed_mint_cookie = governance_test.with_governed_mint().await;
+
+    let token_owner_record_cookie = governance_test
+        .with_community_token_deposit(&realm_cookie)
+        .await
+        .unwrap();
+
+    let mut mint_governance_cookie = governance_test
+        .with_mint_governance(
+            &realm_cookie,
+            &governed_mint_cookie,
+            &token_owner_record_cookie,
+        )
+        .await
+        .unwrap();
+
+    let mut proposal_cookie = governance_test
+        .with_proposal(&token_owner_record_cookie, &mut mint_governance_cookie)
+        .await
+        .unwrap();
+
+    let signatory_record_cookie = governance_test
+        .with_signatory(&proposal_cookie, &token_owner_record_cookie)
+        .await
+        .unwrap();
+
+    governance_test
+        .set_proposal_execution_flags(
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            InstructionExecutionFlags::Ordered,
+        )
+        .await
+        .unwrap();
+
+    let proposal_transaction_cookie1 = governance_test
+        .with_mint_tokens_transaction(
+            &governed_mint_cookie,
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            0,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    let proposal_transaction_cookie2 = governance_test
+        .with_mint_tokens_transaction(
+            &governed_mint_cookie,
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            0,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    governance_test
+        .sign_off_proposal(&proposal_cookie, &signatory_record_cookie)
+        .await
+        .unwrap();
+
+    governance_test
+        .with_cast_yes_no_vote(&proposal_cookie, &token_owner_record_cookie, YesNoVote::Yes)
+        .await
+        .unwrap();
+
+    // Advance clock past hold_up_time
+    governance_test
+        .advance_clock_by_min_timespan(proposal_transaction_cookie1.account.hold_up_time as u64)
+        .await;
+
+    // Act
+    let err = governance_test
+        .execute_proposal_transaction(&proposal_cookie, &proposal_transaction_cookie2)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        err,
+        GovernanceError::InvalidTransactionExecutionOrder.into()
+    );
+}
+
+#[tokio::test]
+async fn test_sign_off_ordered_proposal_with_transaction_index_gap_error() {
+    // Arrange
+    let mut governance_test = GovernanceProgramTest::start_new().await;
+
+    let realm_cookie = governance_test.with_realm().await;
+    let governed_mint_cookie = governance_test.with_governed_mint().await;
+
+    let token_owner_record_cookie = governance_test
+        .with_community_token_deposit(&realm_cookie)
+        .await
+        .unwrap();
+
+    let mut mint_governance_cookie = governance_test
+        .with_mint_governance(
+            &realm_cookie,
+            &governed_mint_cookie,
+            &token_owner_record_cookie,
+        )
+        .await
+        .unwrap();
+
+    let mut proposal_cookie = governance_test
+        .with_proposal(&token_owner_record_cookie, &mut mint_governance_cookie)
+        .await
+        .unwrap();
+
+    let signatory_record_cookie = governance_test
+        .with_signatory(&proposal_cookie, &token_owner_record_cookie)
+        .await
+        .unwrap();
+
+    governance_test
+        .set_proposal_execution_flags(
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            InstructionExecutionFlags::Ordered,
+        )
+        .await
+        .unwrap();
+
+    let proposal_transaction_cookie1 = governance_test
+        .with_mint_tokens_transaction(
+            &governed_mint_cookie,
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            0,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    governance_test
+        .with_mint_tokens_transaction(
+            &governed_mint_cookie,
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            0,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    governance_test
+        .remove_transaction(
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            &proposal_transaction_cookie1,
+        )
+        .await
+        .unwrap();
+
+    // Act
+    let err = governance_test
+        .sign_off_proposal(&proposal_cookie, &signatory_record_cookie)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(err, GovernanceError::TransactionIndexGapNotAllowed.into());
+}
+
+#[tokio::test]
+async fn test_execute_transactions_atomically() {
+    // Arrange
+    let mut governance_test = GovernanceProgramTest::start_new().await;
+
+    let realm_cookie = governance_test.with_realm().await;
+    let governed_mint_cookie = governance_test.with_governed_mint().await;
+
+    let token_owner_record_cookie = governance_test
+        .with_community_token_deposit(&realm_cookie)
+        .await
+        .unwrap();
+
+    let mut mint_governance_cookie = governance_test
+        .with_mint_governance(
+            &realm_cookie,
+            &governed_mint_cookie,
+            &token_owner_record_cookie,
+        )
+        .await
+        .unwrap();
+
+    let mut proposal_cookie = governance_test
+        .with_proposal(&token_owner_record_cookie, &mut mint_governance_cookie)
+        .await
+        .unwrap();
+
+    let signatory_record_cookie = governance_test
+        .with_signatory(&proposal_cookie, &token_owner_record_cookie)
+        .await
+        .unwrap();
+
+    governance_test
+        .set_proposal_execution_flags(
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            InstructionExecutionFlags::UseTransaction,
+        )
+        .await
+        .unwrap();
+
+    let proposal_transaction_cookie1 = governance_test
+        .with_mint_tokens_transaction(
+            &governed_mint_cookie,
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            0,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    let proposal_transaction_cookie2 = governance_test
+        .with_mint_tokens_transaction(
+            &governed_mint_cookie,
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            0,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    governance_test
+        .sign_off_proposal(&proposal_cookie, &signatory_record_cookie)
+        .await
+        .unwrap();
+
+    governance_test
+        .with_cast_yes_no_vote(&proposal_cookie, &token_owner_record_cookie, YesNoVote::Yes)
+        .await
+        .unwrap();
+
+    // Advance clock past hold_up_time
+    governance_test
+        .advance_clock_by_min_timespan(proposal_transaction_cookie1.account.hold_up_time as u64)
+        .await;
+
+    let clock = governance_test.bench.get_clock().await;
+
+    // Act
+    governance_test
+        .execute_proposal_transactions(
+            &proposal_cookie,
+            &[&proposal_transaction_cookie1, &proposal_transaction_cookie2],
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let proposal_account = governance_test
+        .get_proposal_account(&proposal_cookie.address)
+        .await;
+
+    assert_eq!(2, proposal_account.options[0].transactions_executed_count);
+    assert_eq!(ProposalState::Completed, proposal_account.state);
+
+    for proposal_transaction_cookie in
+        [&proposal_transaction_cookie1, &proposal_transaction_cookie2]
+    {
+        let proposal_transaction_account = governance_test
+            .get_proposal_transaction_account(&proposal_transaction_cookie.address)
+            .await;
+
+        assert_eq!(
+            Some(clock.unix_timestamp),
+            proposal_transaction_account.executed_at
+        );
+        assert_eq!(
+            TransactionExecutionStatus::Success,
+            proposal_transaction_account.execution_status
+        );
+
+        let instruction_token_account = governance_test
+            .get_token_account(
+                &proposal_transaction_cookie.account.instructions[0].accounts[1].pubkey,
+            )
+            .await;
+
+        assert_eq!(10, instruction_token_account.amount);
+    }
+}
+
+#[tokio::test]
+async fn test_execute_transactions_atomically_with_invalid_order_error() {
+    // Arrange
+    let mut governance_test = GovernanceProgramTest::start_new().await;
+
+    let realm_cookie = governance_test.with_realm().await;
+    let governed_mint_cookie = governance_test.with_governed_mint().await;
+
+    let token_owner_record_cookie = governance_test
+        .with_community_token_deposit(&realm_cookie)
+        .await
+        .unwrap();
+
+    let mut mint_governance_cookie = governance_test
+        .with_mint_governance(
+            &realm_cookie,
+            &governed_mint_cookie,
+            &token_owner_record_cookie,
+        )
+        .await
+        .unwrap();
+
+    let mut proposal_cookie = governance_test
+        .with_proposal(&token_owner_record_cookie, &mut mint_governance_cookie)
+        .await
+        .unwrap();
+
+    let signatory_record_cookie = governance_test
+        .with_signatory(&proposal_cookie, &token_owner_record_cookie)
+        .await
+        .unwrap();
+
+    governance_test
+        .set_proposal_execution_flags(
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            InstructionExecutionFlags::UseTransaction,
+        )
+        .await
+        .unwrap();
+
+    let proposal_transaction_cookie1 = governance_test
+        .with_mint_tokens_transaction(
+            &governed_mint_cookie,
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            0,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    let proposal_transaction_cookie2 = governance_test
+        .with_mint_tokens_transaction(
+            &governed_mint_cookie,
+            &mut proposal_cookie,
+            &token_owner_record_cookie,
+            0,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    governance_test
+        .sign_off_proposal(&proposal_cookie, &signatory_record_cookie)
+        .await
+        .unwrap();
+
+    governance_test
+        .with_cast_yes_no_vote(&proposal_cookie, &token_owner_record_cookie, YesNoVote::Yes)
+        .await
+        .unwrap();
+
+    // Advance clock past hold_up_time
+    governance_test
+        .advance_clock_by_min_timespan(proposal_transaction_cookie1.account.hold_up_time as u64)
+        .await;
+
+    // Act
+    let err = governance_test
+        .execute_proposal_transactions(
+            &proposal_cookie,
+            &[&proposal_transaction_cookie2, &proposal_transaction_cookie1],
+        )
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        err,
+        GovernanceError::InvalidTransactionsForAtomicExecution.into()
+    );
+}
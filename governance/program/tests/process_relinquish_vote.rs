@@ -206,6 +206,77 @@ async fn test_relinquish_active_no_vote() {
     assert_eq!(None, vote_record_account);
 }
 
+#[tokio::test]
+async fn test_relinquish_active_abstain_vote() {
+    // Arrange
+    let mut governance_test = GovernanceProgramTest::start_new().await;
+
+    let realm_cookie = governance_test.with_realm().await;
+    let governed_account_cookie = governance_test.with_governed_account().await;
+
+    let token_owner_record_cookie = governance_test
+        .with_community_token_deposit(&realm_cookie)
+        .await
+        .unwrap();
+
+    let mut governance_cookie = governance_test
+        .with_governance(
+            &realm_cookie,
+            &governed_account_cookie,
+            &token_owner_record_cookie,
+        )
+        .await
+        .unwrap();
+
+    // Total 300 tokens
+    governance_test
+        .mint_community_tokens(&realm_cookie, 200)
+        .await;
+
+    let proposal_cookie = governance_test
+        .with_signed_off_proposal(&token_owner_record_cookie, &mut governance_cookie)
+        .await
+        .unwrap();
+
+    let vote_record_cookie = governance_test
+        .with_cast_yes_no_vote(
+            &proposal_cookie,
+            &token_owner_record_cookie,
+            YesNoVote::Abstain,
+        )
+        .await
+        .unwrap();
+
+    // Act
+    governance_test
+        .relinquish_vote(&proposal_cookie, &token_owner_record_cookie)
+        .await
+        .unwrap();
+
+    // Assert
+
+    let proposal_account = governance_test
+        .get_proposal_account(&proposal_cookie.address)
+        .await;
+
+    assert_eq!(Some(0), proposal_account.abstain_vote_weight);
+    assert_eq!(ProposalState::Voting, proposal_account.state);
+
+    let token_owner_record = governance_test
+        .get_token_owner_record_account(&token_owner_record_cookie.address)
+        .await;
+
+    assert_eq!(0, token_owner_record.unrelinquished_votes_count);
+    assert_eq!(0, token_owner_record.total_votes_count);
+
+    let vote_record_account = governance_test
+        .bench
+        .get_account(&vote_record_cookie.address)
+        .await;
+
+    assert_eq!(None, vote_record_account);
+}
+
 #[tokio::test]
 async fn test_relinquish_vote_with_invalid_mint_error() {
     // Arrange
@@ -292,3 +292,79 @@ async fn test_set_realm_config_with_council_restore_error() {
         GovernanceError::RealmCouncilMintChangeIsNotSupported.into()
     );
 }
+
+#[tokio::test]
+async fn test_set_realm_config_with_absolute_max_vote_weight() {
+    // Arrange
+    let mut governance_test = GovernanceProgramTest::start_new().await;
+
+    let mut realm_cookie = governance_test.with_realm().await;
+
+    let realm_config_args = RealmConfigArgs {
+        use_council_mint: true,
+
+        community_mint_max_vote_weight_source: MintMaxVoteWeightSource::Absolute(1000),
+        min_community_weight_to_create_governance: 10,
+        use_community_voter_weight_addin: false,
+        use_max_community_voter_weight_addin: false,
+    };
+
+    let set_realm_config_args = SetRealmConfigArgs {
+        realm_config_args,
+        community_voter_weight_addin: None,
+        max_community_voter_weight_addin: None,
+    };
+
+    // Act
+    governance_test
+        .set_realm_config(&mut realm_cookie, &set_realm_config_args)
+        .await
+        .unwrap();
+
+    // Assert
+    let realm_account = governance_test
+        .get_realm_account(&realm_cookie.address)
+        .await;
+
+    assert_eq!(realm_cookie.account, realm_account);
+    assert_eq!(
+        MintMaxVoteWeightSource::Absolute(1000),
+        realm_account.config.community_mint_max_vote_weight_source
+    );
+}
+
+#[tokio::test]
+async fn test_set_realm_config_with_zero_absolute_max_vote_weight_error() {
+    // Arrange
+    let mut governance_test = GovernanceProgramTest::start_new().await;
+
+    let mut realm_cookie = governance_test.with_realm().await;
+
+    let realm_config_args = RealmConfigArgs {
+        use_council_mint: true,
+
+        community_mint_max_vote_weight_source: MintMaxVoteWeightSource::Absolute(0),
+        min_community_weight_to_create_governance: 10,
+        use_community_voter_weight_addin: false,
+        use_max_community_voter_weight_addin: false,
+    };
+
+    let set_realm_config_args = SetRealmConfigArgs {
+        realm_config_args,
+        community_voter_weight_addin: None,
+        max_community_voter_weight_addin: None,
+    };
+
+    // Act
+    let err = governance_test
+        .set_realm_config(&mut realm_cookie, &set_realm_config_args)
+        .await
+        .err()
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        err,
+        GovernanceError::InvalidMaxVoteWeightAbsoluteValue.into()
+    );
+}
@@ -19,10 +19,11 @@ use spl_governance::{
         add_signatory, cancel_proposal, cast_vote, create_governance, create_mint_governance,
         create_native_treasury, create_program_governance, create_proposal, create_realm,
         create_token_governance, create_token_owner_record, deposit_governing_tokens,
-        execute_transaction, finalize_vote, flag_transaction_error, insert_transaction,
-        relinquish_vote, remove_signatory, remove_transaction, set_governance_config,
-        set_governance_delegate, set_realm_authority, set_realm_config, sign_off_proposal,
-        upgrade_program_metadata, withdraw_governing_tokens,
+        execute_transaction, execute_transactions, finalize_vote, flag_transaction_error,
+        insert_transaction, relinquish_vote, remove_signatory, remove_transaction,
+        set_governance_config, set_governance_delegate, set_proposal_execution_flags,
+        set_realm_authority, set_realm_config, sign_off_proposal, upgrade_program_metadata,
+        withdraw_governing_tokens,
     },
     processor::process_instruction,
     state::{
@@ -91,6 +92,9 @@ pub enum YesNoVote {
     /// No vote
     #[allow(dead_code)]
     No,
+    /// Abstain vote
+    #[allow(dead_code)]
+    Abstain,
 }
 
 pub struct GovernanceProgramTest {
@@ -1211,6 +1215,7 @@ impl GovernanceProgramTest {
         GovernanceConfig {
             min_community_weight_to_create_proposal: 5,
             min_council_weight_to_create_proposal: 2,
+            community_veto_vote_threshold: VoteThreshold::Disabled,
             min_transaction_hold_up_time: 10,
             max_voting_time: 10,
             community_vote_threshold: VoteThreshold::YesVotePercentage(60),
@@ -1292,7 +1297,7 @@ impl GovernanceProgramTest {
             governed_account: governed_account_cookie.address,
             config: governance_config.clone(),
             proposals_count: 0,
-            reserved: [0; 6],
+            reserved: [0; 4],
             voting_proposal_count: 0,
             reserved_v2: [0; 128],
         };
@@ -1461,7 +1466,7 @@ impl GovernanceProgramTest {
             governed_account: governed_program_cookie.address,
             config,
             proposals_count: 0,
-            reserved: [0; 6],
+            reserved: [0; 4],
             voting_proposal_count: 0,
             reserved_v2: [0; 128],
         };
@@ -1585,7 +1590,7 @@ impl GovernanceProgramTest {
             governed_account: governed_mint_cookie.address,
             config: governance_config.clone(),
             proposals_count: 0,
-            reserved: [0; 6],
+            reserved: [0; 4],
             voting_proposal_count: 0,
             reserved_v2: [0; 128],
         };
@@ -1669,7 +1674,7 @@ impl GovernanceProgramTest {
             governed_account: governed_token_cookie.address,
             config,
             proposals_count: 0,
-            reserved: [0; 6],
+            reserved: [0; 4],
             voting_proposal_count: 0,
             reserved_v2: [0; 128],
         };
@@ -1856,7 +1861,7 @@ impl GovernanceProgramTest {
             deny_vote_weight,
 
             veto_vote_weight: 0,
-            abstain_vote_weight: None,
+            abstain_vote_weight: Some(0),
 
             execution_flags: InstructionExecutionFlags::None,
             max_vote_weight: None,
@@ -2150,6 +2155,7 @@ impl GovernanceProgramTest {
                 weight_percentage: 100,
             }]),
             YesNoVote::No => Vote::Deny,
+            YesNoVote::Abstain => Vote::Abstain,
         };
 
         self.with_cast_vote(proposal_cookie, token_owner_record_cookie, vote)
@@ -2575,6 +2581,70 @@ impl GovernanceProgramTest {
             .await
     }
 
+    #[allow(dead_code)]
+    pub async fn execute_proposal_transactions(
+        &mut self,
+        proposal_cookie: &ProposalCookie,
+        proposal_transaction_cookies: &[&ProposalTransactionCookie],
+    ) -> Result<(), ProgramError> {
+        let proposal_transactions: Vec<Pubkey> = proposal_transaction_cookies
+            .iter()
+            .map(|proposal_transaction_cookie| proposal_transaction_cookie.address)
+            .collect();
+
+        let mut instruction_accounts = vec![];
+
+        for proposal_transaction_cookie in proposal_transaction_cookies {
+            instruction_accounts.push(AccountMeta::new_readonly(
+                proposal_transaction_cookie.instruction.program_id,
+                false,
+            ));
+            instruction_accounts
+                .extend_from_slice(&proposal_transaction_cookie.instruction.accounts);
+        }
+
+        let execute_proposal_transactions_ix = execute_transactions(
+            &self.program_id,
+            &proposal_cookie.account.governance,
+            &proposal_cookie.address,
+            &proposal_transactions,
+            &instruction_accounts,
+        );
+
+        self.bench
+            .process_transaction(&[execute_proposal_transactions_ix], None)
+            .await
+    }
+
+    #[allow(dead_code)]
+    pub async fn set_proposal_execution_flags(
+        &mut self,
+        proposal_cookie: &mut ProposalCookie,
+        token_owner_record_cookie: &TokenOwnerRecordCookie,
+        execution_flags: InstructionExecutionFlags,
+    ) -> Result<(), ProgramError> {
+        let governance_authority = token_owner_record_cookie.get_governance_authority();
+
+        let set_proposal_execution_flags_ix = set_proposal_execution_flags(
+            &self.program_id,
+            &proposal_cookie.address,
+            &proposal_cookie.account.token_owner_record,
+            &governance_authority.pubkey(),
+            execution_flags.clone(),
+        );
+
+        self.bench
+            .process_transaction(
+                &[set_proposal_execution_flags_ix],
+                Some(&[&governance_authority]),
+            )
+            .await?;
+
+        proposal_cookie.account.execution_flags = execution_flags;
+
+        Ok(())
+    }
+
     #[allow(dead_code)]
     pub async fn flag_transaction_error(
         &mut self,
@@ -7,10 +7,18 @@ use {
         rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
         rpc_filter::{Memcmp, MemcmpEncodedBytes, MemcmpEncoding, RpcFilterType},
     },
-    solana_program::{borsh::try_from_slice_unchecked, program_pack::Pack, pubkey::Pubkey, stake},
+    solana_program::{
+        borsh::{get_packed_len, try_from_slice_unchecked},
+        pubkey::Pubkey,
+        stake,
+    },
     spl_stake_pool::{
         find_withdraw_authority_program_address,
-        state::{StakePool, ValidatorList},
+        state::{StakePool, ValidatorHistoryList, ValidatorList},
+    },
+    spl_token_2022::{
+        extension::StateWithExtensions,
+        state::{Account, Mint},
     },
     std::collections::HashSet,
 };
@@ -21,7 +29,11 @@ pub fn get_stake_pool(
     rpc_client: &RpcClient,
     stake_pool_address: &Pubkey,
 ) -> Result<StakePool, Error> {
-    let account_data = rpc_client.get_account_data(stake_pool_address)?;
+    let mut account_data = rpc_client.get_account_data(stake_pool_address)?;
+    // read older pools as if grown by `ReallocStakePool`
+    if account_data.len() < get_packed_len::<StakePool>() {
+        account_data.resize(get_packed_len::<StakePool>(), 0);
+    }
     let stake_pool = try_from_slice_unchecked::<StakePool>(account_data.as_slice())
         .map_err(|err| format!("Invalid stake pool {}: {}", stake_pool_address, err))?;
     Ok(stake_pool)
@@ -37,14 +49,32 @@ pub fn get_validator_list(
     Ok(validator_list)
 }
 
+pub fn get_validator_history(
+    rpc_client: &RpcClient,
+    validator_history_address: &Pubkey,
+) -> Result<ValidatorHistoryList, Error> {
+    let account_data = rpc_client.get_account_data(validator_history_address)?;
+    let validator_history = try_from_slice_unchecked::<ValidatorHistoryList>(
+        account_data.as_slice(),
+    )
+    .map_err(|err| {
+        format!(
+            "Invalid validator history {}: {}",
+            validator_history_address, err
+        )
+    })?;
+    Ok(validator_history)
+}
+
 pub fn get_token_account(
     rpc_client: &RpcClient,
     token_account_address: &Pubkey,
     expected_token_mint: &Pubkey,
-) -> Result<spl_token::state::Account, Error> {
+) -> Result<Account, Error> {
     let account_data = rpc_client.get_account_data(token_account_address)?;
-    let token_account = spl_token::state::Account::unpack_from_slice(account_data.as_slice())
-        .map_err(|err| format!("Invalid token account {}: {}", token_account_address, err))?;
+    let token_account = StateWithExtensions::<Account>::unpack(account_data.as_slice())
+        .map_err(|err| format!("Invalid token account {}: {}", token_account_address, err))?
+        .base;
 
     if token_account.mint != *expected_token_mint {
         Err(format!(
@@ -57,13 +87,11 @@ pub fn get_token_account(
     }
 }
 
-pub fn get_token_mint(
-    rpc_client: &RpcClient,
-    token_mint_address: &Pubkey,
-) -> Result<spl_token::state::Mint, Error> {
+pub fn get_token_mint(rpc_client: &RpcClient, token_mint_address: &Pubkey) -> Result<Mint, Error> {
     let account_data = rpc_client.get_account_data(token_mint_address)?;
-    let token_mint = spl_token::state::Mint::unpack_from_slice(account_data.as_slice())
-        .map_err(|err| format!("Invalid token mint {}: {}", token_mint_address, err))?;
+    let token_mint = StateWithExtensions::<Mint>::unpack(account_data.as_slice())
+        .map_err(|err| format!("Invalid token mint {}: {}", token_mint_address, err))?
+        .base;
 
     Ok(token_mint)
 }
@@ -1,10 +1,12 @@
 mod client;
 mod output;
+mod rebalance;
 
 use {
     crate::{
         client::*,
         output::{CliStakePool, CliStakePoolDetails, CliStakePoolStakeAccountInfo, CliStakePools},
+        rebalance::{RebalanceOperation, RebalanceStrategy, RebalanceValidator},
     },
     clap::{
         crate_description, crate_name, crate_version, value_t, value_t_or_exit, App, AppSettings,
@@ -22,6 +24,7 @@ use {
     solana_client::rpc_client::RpcClient,
     solana_program::{
         borsh::{get_instance_packed_len, get_packed_len},
+        clock::{DEFAULT_S_PER_SLOT, SECONDS_PER_DAY},
         instruction::Instruction,
         program_pack::Pack,
         pubkey::Pubkey,
@@ -38,21 +41,25 @@ use {
         system_instruction,
         transaction::Transaction,
     },
-    spl_associated_token_account::get_associated_token_address,
+    spl_associated_token_account::{
+        get_associated_token_address_with_program_id, instruction::create_associated_token_account,
+    },
     spl_stake_pool::state::ValidatorStakeInfo,
     spl_stake_pool::{
         self, find_stake_program_address, find_transient_stake_program_address,
         find_withdraw_authority_program_address,
+        inline_mpl_token_metadata::state::{MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH, MAX_URI_LENGTH},
         instruction::{FundingType, PreferredValidatorType},
-        state::{Fee, FeeType, StakePool, ValidatorList},
+        state::{
+            Fee, FeeType, InstantUnstake, StakePool, StakeStatus, ValidatorHistory,
+            ValidatorHistoryList, ValidatorList,
+        },
         MINIMUM_ACTIVE_STAKE, MINIMUM_RESERVE_LAMPORTS,
     },
+    spl_token_2022::extension::ExtensionType,
     std::cmp::Ordering,
-    std::{process::exit, sync::Arc},
+    std::{collections::HashMap, fs, process::exit, sync::Arc},
 };
-// use instruction::create_associated_token_account once ATA 1.0.5 is released
-#[allow(deprecated)]
-use spl_associated_token_account::create_associated_token_account;
 
 pub(crate) struct Config {
     rpc_client: RpcClient,
@@ -232,11 +239,20 @@ fn command_create_pool(
     validator_list_keypair: Option<Keypair>,
     mint_keypair: Option<Keypair>,
     reserve_keypair: Option<Keypair>,
+    token_program_id: Pubkey,
     unsafe_fees: bool,
 ) -> CommandResult {
     if !unsafe_fees {
         check_stake_pool_fees(&epoch_fee, &withdrawal_fee, &deposit_fee)?;
     }
+    spl_token_2022::check_spl_token_program_account(&token_program_id).map_err(|_| {
+        format!(
+            "Token program {} is not supported, expected {} or {}",
+            token_program_id,
+            spl_token::id(),
+            spl_token_2022::id()
+        )
+    })?;
     let reserve_keypair = reserve_keypair.unwrap_or_else(Keypair::new);
     println!("Creating reserve stake {}", reserve_keypair.pubkey());
 
@@ -254,9 +270,6 @@ fn command_create_pool(
     let mint_account_balance = config
         .rpc_client
         .get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)?;
-    let pool_fee_account_balance = config
-        .rpc_client
-        .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)?;
     let stake_pool_account_lamports = config
         .rpc_client
         .get_minimum_balance_for_rent_exemption(get_packed_len::<StakePool>())?;
@@ -267,7 +280,6 @@ fn command_create_pool(
         .get_minimum_balance_for_rent_exemption(validator_list_size)?;
     let mut total_rent_free_balances = reserve_stake_balance
         + mint_account_balance
-        + pool_fee_account_balance
         + stake_pool_account_lamports
         + validator_list_balance;
 
@@ -306,11 +318,11 @@ fn command_create_pool(
             &mint_keypair.pubkey(),
             mint_account_balance,
             spl_token::state::Mint::LEN as u64,
-            &spl_token::id(),
+            &token_program_id,
         ),
         // Initialize pool token mint account
-        spl_token::instruction::initialize_mint(
-            &spl_token::id(),
+        spl_token_2022::instruction::initialize_mint(
+            &token_program_id,
             &mint_keypair.pubkey(),
             &withdraw_authority,
             None,
@@ -322,6 +334,7 @@ fn command_create_pool(
         config,
         &mint_keypair.pubkey(),
         &config.manager.pubkey(),
+        &token_program_id,
         &mut instructions,
         &mut total_rent_free_balances,
     );
@@ -362,7 +375,7 @@ fn command_create_pool(
                 &reserve_keypair.pubkey(),
                 &mint_keypair.pubkey(),
                 &pool_fee_account,
-                &spl_token::id(),
+                &token_program_id,
                 deposit_authority.as_ref().map(|x| x.pubkey()),
                 epoch_fee,
                 withdrawal_fee,
@@ -596,6 +609,124 @@ fn command_decrease_validator_stake(
     Ok(())
 }
 
+fn command_rebalance(
+    config: &Config,
+    stake_pool_address: &Pubkey,
+    policy_file: &str,
+    print_only: bool,
+) -> CommandResult {
+    let policy = rebalance::read_policy(policy_file)?;
+    if !config.no_update {
+        command_update(config, stake_pool_address, false, false)?;
+    }
+
+    let stake_pool = get_stake_pool(&config.rpc_client, stake_pool_address)?;
+    let validator_list = get_validator_list(&config.rpc_client, &stake_pool.validator_list)?;
+    let reserve_lamports = config.rpc_client.get_balance(&stake_pool.reserve_stake)?;
+    let stake_rent = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(STAKE_STATE_LEN)?;
+
+    let weights: HashMap<Pubkey, u128> = match policy.strategy {
+        RebalanceStrategy::Equal => validator_list
+            .validators
+            .iter()
+            .map(|validator| (validator.vote_account_address, 1))
+            .collect(),
+        RebalanceStrategy::Commission => {
+            // delinquent validators are left out, so they get no stake
+            let vote_accounts = config.rpc_client.get_vote_accounts()?;
+            vote_accounts
+                .current
+                .iter()
+                .filter_map(|vote_account| {
+                    let vote_account_address = vote_account.vote_pubkey.parse().ok()?;
+                    let weight = 100u8.saturating_sub(vote_account.commission);
+                    Some((vote_account_address, weight as u128))
+                })
+                .collect()
+        }
+        RebalanceStrategy::Score => {
+            let scores_file = policy.scores_file.as_ref().unwrap();
+            let data = fs::read_to_string(scores_file)
+                .map_err(|err| format!("Unable to read scores file {}: {}", scores_file, err))?;
+            rebalance::parse_scores(&data)?
+        }
+    };
+
+    let mut validators = vec![];
+    for validator in &validator_list.validators {
+        if validator.status != StakeStatus::Active || validator.transient_stake_lamports > 0 {
+            if config.verbose {
+                println!(
+                    "Skipping validator {}, its stake is still moving or being removed",
+                    validator.vote_account_address
+                );
+            }
+            continue;
+        }
+        validators.push(RebalanceValidator {
+            vote_account_address: validator.vote_account_address,
+            active_stake_lamports: validator.active_stake_lamports,
+            weight: weights
+                .get(&validator.vote_account_address)
+                .copied()
+                .unwrap_or_default(),
+        });
+    }
+
+    let moves = rebalance::plan_rebalance(
+        &validators,
+        reserve_lamports,
+        native_token::sol_to_lamports(policy.reserve_sol),
+        stake_rent,
+        native_token::sol_to_lamports(policy.min_move_sol),
+    )?;
+    if moves.is_empty() {
+        println!("Stake pool is balanced, nothing to do");
+        return Ok(());
+    }
+    for stake_move in &moves {
+        println!("{}", stake_move);
+    }
+    if print_only {
+        return Ok(());
+    }
+
+    let mut signers = vec![config.fee_payer.as_ref(), config.staker.as_ref()];
+    unique_signers!(signers);
+    for stake_move in moves {
+        let validator_stake_info = validator_list
+            .find(&stake_move.vote_account_address)
+            .ok_or("Vote account not found in validator list")?;
+        let instruction = match stake_move.operation {
+            RebalanceOperation::Decrease => {
+                spl_stake_pool::instruction::decrease_validator_stake_with_vote(
+                    &spl_stake_pool::id(),
+                    &stake_pool,
+                    stake_pool_address,
+                    &stake_move.vote_account_address,
+                    stake_move.lamports,
+                    validator_stake_info.transient_seed_suffix_start,
+                )
+            }
+            RebalanceOperation::Increase => {
+                spl_stake_pool::instruction::increase_validator_stake_with_vote(
+                    &spl_stake_pool::id(),
+                    &stake_pool,
+                    stake_pool_address,
+                    &stake_move.vote_account_address,
+                    stake_move.lamports,
+                    validator_stake_info.transient_seed_suffix_start,
+                )
+            }
+        };
+        let transaction = checked_transaction_with_signers(config, &[instruction], &signers)?;
+        send_transaction(config, transaction)?;
+    }
+    Ok(())
+}
+
 fn command_set_preferred_validator(
     config: &Config,
     stake_pool_address: &Pubkey,
@@ -625,24 +756,33 @@ fn add_associated_token_account(
     config: &Config,
     mint: &Pubkey,
     owner: &Pubkey,
+    token_program_id: &Pubkey,
     instructions: &mut Vec<Instruction>,
     rent_free_balances: &mut u64,
 ) -> Pubkey {
     // Account for tokens not specified, creating one
-    let account = get_associated_token_address(owner, mint);
+    let account = get_associated_token_address_with_program_id(owner, mint, token_program_id);
     if get_token_account(&config.rpc_client, &account, mint).is_err() {
         println!("Creating associated token account {} to receive stake pool tokens of mint {}, owned by {}", account, mint, owner);
 
+        // token-2022 associated token accounts are created with the immutable owner extension
+        let account_len = if *token_program_id == spl_token_2022::id() {
+            ExtensionType::get_account_len::<spl_token_2022::state::Account>(&[
+                ExtensionType::ImmutableOwner,
+            ])
+        } else {
+            spl_token::state::Account::LEN
+        };
         let min_account_balance = config
             .rpc_client
-            .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+            .get_minimum_balance_for_rent_exemption(account_len)
             .unwrap();
 
-        #[allow(deprecated)]
         instructions.push(create_associated_token_account(
             &config.fee_payer.pubkey(),
             owner,
             mint,
+            token_program_id,
         ));
 
         *rent_free_balances += min_account_balance;
@@ -706,6 +846,7 @@ fn command_deposit_stake(
             config,
             &stake_pool.pool_mint,
             &config.token_owner.pubkey(),
+            &stake_pool.token_program_id,
             &mut instructions,
             &mut total_rent_free_balances,
         ));
@@ -741,7 +882,7 @@ fn command_deposit_stake(
                 &stake_pool.manager_fee_account,
                 &referrer_token_account,
                 &stake_pool.pool_mint,
-                &spl_token::id(),
+                &stake_pool.token_program_id,
             )
         } else {
             spl_stake_pool::instruction::deposit_stake(
@@ -757,7 +898,7 @@ fn command_deposit_stake(
                 &stake_pool.manager_fee_account,
                 &referrer_token_account,
                 &stake_pool.pool_mint,
-                &spl_token::id(),
+                &stake_pool.token_program_id,
             )
         };
 
@@ -802,6 +943,7 @@ fn command_deposit_all_stake(
             config,
             &stake_pool.pool_mint,
             &config.token_owner.pubkey(),
+            &stake_pool.token_program_id,
             &mut create_token_account_instructions,
             &mut total_rent_free_balances,
         ));
@@ -884,7 +1026,7 @@ fn command_deposit_all_stake(
                 &stake_pool.manager_fee_account,
                 &referrer_token_account,
                 &stake_pool.pool_mint,
-                &spl_token::id(),
+                &stake_pool.token_program_id,
             )
         } else {
             spl_stake_pool::instruction::deposit_stake(
@@ -900,7 +1042,7 @@ fn command_deposit_all_stake(
                 &stake_pool.manager_fee_account,
                 &referrer_token_account,
                 &stake_pool.pool_mint,
-                &spl_token::id(),
+                &stake_pool.token_program_id,
             )
         };
 
@@ -971,6 +1113,7 @@ fn command_deposit_sol(
             config,
             &stake_pool.pool_mint,
             &config.token_owner.pubkey(),
+            &stake_pool.token_program_id,
             &mut instructions,
             &mut total_rent_free_balances,
         ));
@@ -1005,7 +1148,7 @@ fn command_deposit_sol(
             &stake_pool.manager_fee_account,
             &referrer_token_account,
             &stake_pool.pool_mint,
-            &spl_token::id(),
+            &stake_pool.token_program_id,
             amount,
         )
     } else {
@@ -1019,7 +1162,7 @@ fn command_deposit_sol(
             &stake_pool.manager_fee_account,
             &referrer_token_account,
             &stake_pool.pool_mint,
-            &spl_token::id(),
+            &stake_pool.token_program_id,
             amount,
         )
     };
@@ -1059,6 +1202,12 @@ fn command_list(config: &Config, stake_pool_address: &Pubkey) -> CommandResult {
         .rpc_client
         .get_minimum_balance_for_rent_exemption(STAKE_STATE_LEN)?
         + MINIMUM_RESERVE_LAMPORTS;
+    let validator_history = stake_pool
+        .validator_history
+        .map(|address| get_validator_history(&config.rpc_client, &address))
+        .transpose()?;
+    let epochs_per_year = SECONDS_PER_DAY as f64 * 365.25
+        / (config.rpc_client.get_epoch_schedule()?.slots_per_epoch as f64 * DEFAULT_S_PER_SLOT);
     let cli_stake_pool_stake_account_infos = validator_list
         .validators
         .iter()
@@ -1075,6 +1224,10 @@ fn command_list(config: &Config, stake_pool_address: &Pubkey) -> CommandResult {
                 validator.transient_seed_suffix_start,
             );
             let update_required = validator.last_update_epoch != epoch_info.epoch;
+            let apy = validator_history
+                .as_ref()
+                .and_then(|history| history.find(&validator.vote_account_address))
+                .and_then(|history| validator_apy(history, epoch_info.epoch, epochs_per_year));
             CliStakePoolStakeAccountInfo {
                 vote_account_address: validator.vote_account_address.to_string(),
                 stake_account_address: stake_account_address.to_string(),
@@ -1085,6 +1238,7 @@ fn command_list(config: &Config, stake_pool_address: &Pubkey) -> CommandResult {
                     .to_string(),
                 validator_transient_stake_lamports: validator.transient_stake_lamports,
                 update_required,
+                apy,
             }
         })
         .collect();
@@ -1113,6 +1267,82 @@ fn command_list(config: &Config, stake_pool_address: &Pubkey) -> CommandResult {
     Ok(())
 }
 
+/// Yearly yield of a validator, compounding its average rate per epoch over
+/// the recorded history
+fn validator_apy(history: &ValidatorHistory, epoch: u64, epochs_per_year: f64) -> Option<f64> {
+    let (stake_lamports, reward_lamports) = history.totals(epoch);
+    if stake_lamports == 0 {
+        return None;
+    }
+    let epoch_rate = reward_lamports as f64 / stake_lamports as f64;
+    Some((1.0 + epoch_rate).powf(epochs_per_year) - 1.0)
+}
+
+fn command_create_validator_history(
+    config: &Config,
+    stake_pool_address: &Pubkey,
+    validator_history_keypair: Option<Keypair>,
+) -> CommandResult {
+    let stake_pool = get_stake_pool(&config.rpc_client, stake_pool_address)?;
+    if let Some(validator_history) = stake_pool.validator_history {
+        return Err(format!(
+            "Stake pool {} already records its validator history in {}",
+            stake_pool_address, validator_history
+        )
+        .into());
+    }
+    let validator_list = get_validator_list(&config.rpc_client, &stake_pool.validator_list)?;
+    let validator_history_keypair = validator_history_keypair.unwrap_or_else(Keypair::new);
+
+    let validator_history_size = get_instance_packed_len(&ValidatorHistoryList::new(
+        validator_list.header.max_validators,
+    ))?;
+    let validator_history_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(validator_history_size)?;
+
+    let mut instructions = vec![];
+    // older pools have no room for the validator history address yet
+    let stake_pool_account = config.rpc_client.get_account(stake_pool_address)?;
+    if stake_pool_account.data.len() < get_packed_len::<StakePool>() {
+        instructions.push(spl_stake_pool::instruction::realloc_stake_pool(
+            &spl_stake_pool::id(),
+            stake_pool_address,
+            &config.fee_payer.pubkey(),
+        ));
+    }
+    instructions.extend([
+        system_instruction::create_account(
+            &config.fee_payer.pubkey(),
+            &validator_history_keypair.pubkey(),
+            validator_history_balance,
+            validator_history_size as u64,
+            &spl_stake_pool::id(),
+        ),
+        spl_stake_pool::instruction::initialize_validator_history(
+            &spl_stake_pool::id(),
+            stake_pool_address,
+            &config.manager.pubkey(),
+            &stake_pool.validator_list,
+            &validator_history_keypair.pubkey(),
+        ),
+    ]);
+
+    let mut signers = vec![
+        config.fee_payer.as_ref(),
+        &validator_history_keypair,
+        config.manager.as_ref(),
+    ];
+    unique_signers!(signers);
+    let transaction = checked_transaction_with_signers(config, &instructions, &signers)?;
+    println!(
+        "Creating validator history {}",
+        validator_history_keypair.pubkey()
+    );
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
 fn command_update(
     config: &Config,
     stake_pool_address: &Pubkey,
@@ -1347,10 +1577,12 @@ fn command_withdraw_stake(
     let pool_withdraw_authority =
         find_withdraw_authority_program_address(&spl_stake_pool::id(), stake_pool_address).0;
 
-    let pool_token_account = pool_token_account.unwrap_or(get_associated_token_address(
-        &config.token_owner.pubkey(),
-        &stake_pool.pool_mint,
-    ));
+    let pool_token_account =
+        pool_token_account.unwrap_or(get_associated_token_address_with_program_id(
+            &config.token_owner.pubkey(),
+            &stake_pool.pool_mint,
+            &stake_pool.token_program_id,
+        ));
     let token_account = get_token_account(
         &config.rpc_client,
         &pool_token_account,
@@ -1428,8 +1660,8 @@ fn command_withdraw_stake(
 
     instructions.push(
         // Approve spending token
-        spl_token::instruction::approve(
-            &spl_token::id(),
+        spl_token_2022::instruction::approve(
+            &stake_pool.token_program_id,
             &pool_token_account,
             &user_transfer_authority.pubkey(),
             &config.token_owner.pubkey(),
@@ -1489,7 +1721,7 @@ fn command_withdraw_stake(
             &pool_token_account,
             &stake_pool.manager_fee_account,
             &stake_pool.pool_mint,
-            &spl_token::id(),
+            &stake_pool.token_program_id,
             withdraw_account.pool_amount,
         ));
     }
@@ -1528,10 +1760,12 @@ fn command_withdraw_sol(
     let pool_mint = get_token_mint(&config.rpc_client, &stake_pool.pool_mint)?;
     let pool_amount = spl_token::ui_amount_to_amount(pool_amount, pool_mint.decimals);
 
-    let pool_token_account = pool_token_account.unwrap_or(get_associated_token_address(
-        &config.token_owner.pubkey(),
-        &stake_pool.pool_mint,
-    ));
+    let pool_token_account =
+        pool_token_account.unwrap_or(get_associated_token_address_with_program_id(
+            &config.token_owner.pubkey(),
+            &stake_pool.pool_mint,
+            &stake_pool.token_program_id,
+        ));
     let token_account = get_token_account(
         &config.rpc_client,
         &pool_token_account,
@@ -1558,8 +1792,8 @@ fn command_withdraw_sol(
 
     let mut instructions = vec![
         // Approve spending token
-        spl_token::instruction::approve(
-            &spl_token::id(),
+        spl_token_2022::instruction::approve(
+            &stake_pool.token_program_id,
             &pool_token_account,
             &user_transfer_authority.pubkey(),
             &config.token_owner.pubkey(),
@@ -1597,7 +1831,7 @@ fn command_withdraw_sol(
             sol_receiver,
             &stake_pool.manager_fee_account,
             &stake_pool.pool_mint,
-            &spl_token::id(),
+            &stake_pool.token_program_id,
             pool_amount,
         )
     } else {
@@ -1611,7 +1845,7 @@ fn command_withdraw_sol(
             sol_receiver,
             &stake_pool.manager_fee_account,
             &stake_pool.pool_mint,
-            &spl_token::id(),
+            &stake_pool.token_program_id,
             pool_amount,
         )
     };
@@ -1706,6 +1940,102 @@ fn command_set_staker(
     Ok(())
 }
 
+fn command_realloc_pool(config: &Config, stake_pool_address: &Pubkey) -> CommandResult {
+    let stake_pool_account = config.rpc_client.get_account(stake_pool_address)?;
+    if stake_pool_account.data.len() >= get_packed_len::<StakePool>() {
+        println!(
+            "Stake pool {} already has the current size",
+            stake_pool_address
+        );
+        return Ok(());
+    }
+    let mut signers = vec![config.fee_payer.as_ref()];
+    unique_signers!(signers);
+    let transaction = checked_transaction_with_signers(
+        config,
+        &[spl_stake_pool::instruction::realloc_stake_pool(
+            &spl_stake_pool::id(),
+            stake_pool_address,
+            &config.fee_payer.pubkey(),
+        )],
+        &signers,
+    )?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+fn check_token_metadata(name: &str, symbol: &str, uri: &str) -> Result<(), Error> {
+    if name.len() > MAX_NAME_LENGTH {
+        return Err(format!("Token name longer than {} bytes", MAX_NAME_LENGTH).into());
+    }
+    if symbol.len() > MAX_SYMBOL_LENGTH {
+        return Err(format!("Token symbol longer than {} bytes", MAX_SYMBOL_LENGTH).into());
+    }
+    if uri.len() > MAX_URI_LENGTH {
+        return Err(format!("Token uri longer than {} bytes", MAX_URI_LENGTH).into());
+    }
+    Ok(())
+}
+
+fn command_create_token_metadata(
+    config: &Config,
+    stake_pool_address: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> CommandResult {
+    check_token_metadata(&name, &symbol, &uri)?;
+    let stake_pool = get_stake_pool(&config.rpc_client, stake_pool_address)?;
+
+    let mut signers = vec![config.fee_payer.as_ref(), config.manager.as_ref()];
+    unique_signers!(signers);
+    let transaction = checked_transaction_with_signers(
+        config,
+        &[spl_stake_pool::instruction::create_token_metadata(
+            &spl_stake_pool::id(),
+            stake_pool_address,
+            &config.manager.pubkey(),
+            &stake_pool.pool_mint,
+            &config.fee_payer.pubkey(),
+            name,
+            symbol,
+            uri,
+        )],
+        &signers,
+    )?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+fn command_update_token_metadata(
+    config: &Config,
+    stake_pool_address: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> CommandResult {
+    check_token_metadata(&name, &symbol, &uri)?;
+    let stake_pool = get_stake_pool(&config.rpc_client, stake_pool_address)?;
+
+    let mut signers = vec![config.fee_payer.as_ref(), config.manager.as_ref()];
+    unique_signers!(signers);
+    let transaction = checked_transaction_with_signers(
+        config,
+        &[spl_stake_pool::instruction::update_token_metadata(
+            &spl_stake_pool::id(),
+            stake_pool_address,
+            &config.manager.pubkey(),
+            &stake_pool.pool_mint,
+            name,
+            symbol,
+            uri,
+        )],
+        &signers,
+    )?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
 fn command_set_funding_authority(
     config: &Config,
     stake_pool_address: &Pubkey,
@@ -1986,6 +2316,14 @@ fn main() {
                     .takes_value(true)
                     .help("Stake pool reserve keypair [default: new keypair]"),
             )
+            .arg(
+                Arg::with_name("token_program_id")
+                    .long("token-program-id")
+                    .validator(is_pubkey)
+                    .value_name("ADDRESS")
+                    .takes_value(true)
+                    .help("Token program of the stake pool mint, either SPL Token or Token-2022 [default: SPL Token program]"),
+            )
             .arg(
                 Arg::with_name("unsafe_fees")
                     .long("unsafe-fees")
@@ -2110,6 +2448,34 @@ fn main() {
                     .help("Amount in SOL to remove from the validator stake account. Must be at least the rent-exempt amount for a stake."),
             )
         )
+        .subcommand(SubCommand::with_name("rebalance")
+            .about("Increase and decrease stake on the validators of the pool to follow a target weighting policy. Must be signed by the pool staker.")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address"),
+            )
+            .arg(
+                Arg::with_name("policy")
+                    .index(2)
+                    .value_name("POLICY_FILE")
+                    .takes_value(true)
+                    .required(true)
+                    .help("JSON policy file, with a \"strategy\" of \"equal\", \"commission\", or \"score\", \
+                          a \"scores-file\" CSV of \"vote_account,score\" lines for the score strategy, \
+                          and optional \"reserve-sol\" to keep in the reserve and \"min-move-sol\" to skip smaller moves."),
+            )
+            .arg(
+                Arg::with_name("print_only")
+                    .long("print-only")
+                    .takes_value(false)
+                    .help("Only print the stake moves, without sending any transaction"),
+            )
+        )
         .subcommand(SubCommand::with_name("set-preferred-validator")
             .about("Set the preferred validator for deposits or withdrawals. Must be signed by the pool staker.")
             .arg(
@@ -2302,6 +2668,26 @@ fn main() {
                     .help("Stake pool address."),
             )
         )
+        .subcommand(SubCommand::with_name("create-validator-history")
+            .about("Create the account recording the rewards of the validators on each update, used to estimate their APY. Must be signed by the manager.")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address."),
+            )
+            .arg(
+                Arg::with_name("validator_history_keypair")
+                    .long("validator-history-keypair")
+                    .validator(is_keypair_or_ask_keyword)
+                    .value_name("PATH")
+                    .takes_value(true)
+                    .help("Validator history keypair [default: new keypair]"),
+            )
+        )
         .subcommand(SubCommand::with_name("update")
             .about("Updates all balances in the pool after validator stake accounts receive rewards.")
             .arg(
@@ -2474,6 +2860,90 @@ fn main() {
                     .help("Public key for the new stake pool staker."),
             )
         )
+        .subcommand(SubCommand::with_name("realloc-pool")
+            .about("Grow a stake pool created by an older version of the program to the current size. The fee payer funds the additional rent.")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address."),
+            )
+        )
+        .subcommand(SubCommand::with_name("create-token-metadata")
+            .about("Create the token metadata of the pool token, so wallets display its name and symbol. Must be signed by the manager.")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address."),
+            )
+            .arg(
+                Arg::with_name("name")
+                    .index(2)
+                    .value_name("TOKEN_NAME")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Name of the pool token, at most 32 bytes."),
+            )
+            .arg(
+                Arg::with_name("symbol")
+                    .index(3)
+                    .value_name("TOKEN_SYMBOL")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Symbol of the pool token, at most 10 bytes."),
+            )
+            .arg(
+                Arg::with_name("uri")
+                    .index(4)
+                    .value_name("TOKEN_URI")
+                    .takes_value(true)
+                    .required(true)
+                    .help("URI of the JSON metadata of the pool token, at most 200 bytes."),
+            )
+        )
+        .subcommand(SubCommand::with_name("update-token-metadata")
+            .about("Update the token metadata of the pool token. Must be signed by the manager.")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address."),
+            )
+            .arg(
+                Arg::with_name("name")
+                    .index(2)
+                    .value_name("TOKEN_NAME")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Name of the pool token, at most 32 bytes."),
+            )
+            .arg(
+                Arg::with_name("symbol")
+                    .index(3)
+                    .value_name("TOKEN_SYMBOL")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Symbol of the pool token, at most 10 bytes."),
+            )
+            .arg(
+                Arg::with_name("uri")
+                    .index(4)
+                    .value_name("TOKEN_URI")
+                    .takes_value(true)
+                    .required(true)
+                    .help("URI of the JSON metadata of the pool token, at most 200 bytes."),
+            )
+        )
         .subcommand(SubCommand::with_name("set-funding-authority")
             .about("Change one of the funding authorities for the stake pool. Must be signed by the manager.")
             .arg(
@@ -2581,6 +3051,52 @@ fn main() {
                     .help("Fee percentage, maximum 100"),
             )
         )
+        .subcommand(SubCommand::with_name("set-instant-unstake")
+            .about("Configure the liquidity buffer kept in the reserve for instant unstakes, and the fee charged on SOL withdrawals as it drains. Takes effect on the next epoch, and fees can only rise by 1.5x each epoch. Must be signed by the manager.")
+            .arg(
+                Arg::with_name("pool")
+                    .index(1)
+                    .validator(is_pubkey)
+                    .value_name("POOL_ADDRESS")
+                    .takes_value(true)
+                    .required(true)
+                    .help("Stake pool address."),
+            )
+            .arg(
+                Arg::with_name("target_buffer")
+                    .long("target-buffer")
+                    .validator(is_parsable::<u64>)
+                    .value_name("BASIS_POINTS")
+                    .takes_value(true)
+                    .required_unless("disable")
+                    .help("Target liquidity buffer in the reserve, in basis points of the total pool lamports."),
+            )
+            .arg(
+                Arg::with_name("min_fee")
+                    .long("min-fee")
+                    .validator(is_parsable::<u64>)
+                    .value_name("BASIS_POINTS")
+                    .takes_value(true)
+                    .required_unless("disable")
+                    .help("Fee charged while the buffer is full, in basis points."),
+            )
+            .arg(
+                Arg::with_name("max_fee")
+                    .long("max-fee")
+                    .validator(is_parsable::<u64>)
+                    .value_name("BASIS_POINTS")
+                    .takes_value(true)
+                    .required_unless("disable")
+                    .help("Fee charged once the buffer is empty, in basis points."),
+            )
+            .arg(
+                Arg::with_name("disable")
+                    .long("disable")
+                    .takes_value(false)
+                    .conflicts_with_all(&["target_buffer", "min_fee", "max_fee"])
+                    .help("Disable instant unstakes."),
+            )
+        )
         .subcommand(SubCommand::with_name("list-all")
             .about("List information about all stake pools")
         )
@@ -2691,6 +3207,8 @@ fn main() {
             let validator_list_keypair = keypair_of(arg_matches, "validator_list_keypair");
             let mint_keypair = keypair_of(arg_matches, "mint_keypair");
             let reserve_keypair = keypair_of(arg_matches, "reserve_keypair");
+            let token_program_id =
+                pubkey_of(arg_matches, "token_program_id").unwrap_or_else(spl_token::id);
             let unsafe_fees = arg_matches.is_present("unsafe_fees");
             command_create_pool(
                 &config,
@@ -2713,6 +3231,7 @@ fn main() {
                 validator_list_keypair,
                 mint_keypair,
                 reserve_keypair,
+                token_program_id,
                 unsafe_fees,
             )
         }
@@ -2746,6 +3265,12 @@ fn main() {
             let amount = value_t_or_exit!(arg_matches, "amount", f64);
             command_decrease_validator_stake(&config, &stake_pool_address, &vote_account, amount)
         }
+        ("rebalance", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let policy_file = arg_matches.value_of("policy").unwrap();
+            let print_only = arg_matches.is_present("print_only");
+            command_rebalance(&config, &stake_pool_address, policy_file, print_only)
+        }
         ("set-preferred-validator", Some(arg_matches)) => {
             let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
             let preferred_type = match arg_matches.value_of("preferred_type").unwrap() {
@@ -2806,6 +3331,15 @@ fn main() {
             let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
             command_list(&config, &stake_pool_address)
         }
+        ("create-validator-history", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let validator_history_keypair = keypair_of(arg_matches, "validator_history_keypair");
+            command_create_validator_history(
+                &config,
+                &stake_pool_address,
+                validator_history_keypair,
+            )
+        }
         ("update", Some(arg_matches)) => {
             let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
             let no_merge = arg_matches.is_present("no_merge");
@@ -2867,6 +3401,24 @@ fn main() {
             let new_staker = pubkey_of(arg_matches, "new_staker").unwrap();
             command_set_staker(&config, &stake_pool_address, &new_staker)
         }
+        ("realloc-pool", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            command_realloc_pool(&config, &stake_pool_address)
+        }
+        ("create-token-metadata", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let name = value_t_or_exit!(arg_matches, "name", String);
+            let symbol = value_t_or_exit!(arg_matches, "symbol", String);
+            let uri = value_t_or_exit!(arg_matches, "uri", String);
+            command_create_token_metadata(&config, &stake_pool_address, name, symbol, uri)
+        }
+        ("update-token-metadata", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let name = value_t_or_exit!(arg_matches, "name", String);
+            let symbol = value_t_or_exit!(arg_matches, "symbol", String);
+            let uri = value_t_or_exit!(arg_matches, "uri", String);
+            command_update_token_metadata(&config, &stake_pool_address, name, symbol, uri)
+        }
         ("set-funding-authority", Some(arg_matches)) => {
             let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
             let new_authority = pubkey_of(arg_matches, "new_authority");
@@ -2923,6 +3475,27 @@ fn main() {
             };
             command_set_fee(&config, &stake_pool_address, fee_type)
         }
+        ("set-instant-unstake", Some(arg_matches)) => {
+            let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let instant_unstake = if arg_matches.is_present("disable") {
+                None
+            } else {
+                let basis_points = |name| Fee {
+                    denominator: 10_000,
+                    numerator: value_t_or_exit!(arg_matches, name, u64),
+                };
+                Some(InstantUnstake {
+                    target_buffer: basis_points("target_buffer"),
+                    min_fee: basis_points("min_fee"),
+                    max_fee: basis_points("max_fee"),
+                })
+            };
+            command_set_fee(
+                &config,
+                &stake_pool_address,
+                FeeType::InstantUnstake(instant_unstake),
+            )
+        }
         ("list-all", _) => command_list_all_pools(&config),
         ("deposit-all-stake", Some(arg_matches)) => {
             let stake_pool_address = pubkey_of(arg_matches, "pool").unwrap();
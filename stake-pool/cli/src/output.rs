@@ -3,7 +3,9 @@ use {
     solana_cli_output::{QuietDisplay, VerboseDisplay},
     solana_sdk::native_token::Sol,
     solana_sdk::{pubkey::Pubkey, stake::state::Lockup},
-    spl_stake_pool::state::{Fee, StakePool, StakeStatus, ValidatorList, ValidatorStakeInfo},
+    spl_stake_pool::state::{
+        Fee, InstantUnstake, StakePool, StakeStatus, ValidatorList, ValidatorStakeInfo,
+    },
     std::fmt::{Display, Formatter, Result, Write},
 };
 
@@ -70,6 +72,9 @@ pub(crate) struct CliStakePool {
     pub next_sol_withdrawal_fee: Option<CliStakePoolFee>,
     pub last_epoch_pool_token_supply: u64,
     pub last_epoch_total_lamports: u64,
+    pub instant_unstake: Option<CliStakePoolInstantUnstake>,
+    pub next_instant_unstake: Option<CliStakePoolInstantUnstake>,
+    pub scheduled_decrease_lamports: u64,
     pub details: Option<CliStakePoolDetails>,
 }
 
@@ -166,6 +171,35 @@ impl VerboseDisplay for CliStakePool {
             "SOL Deposit Referral Fee: {}% of SOL Deposit Fee",
             &self.sol_referral_fee
         )?;
+        if let Some(instant_unstake) = &self.instant_unstake {
+            writeln!(
+                w,
+                "Instant Unstake Buffer: {} of total lamports",
+                instant_unstake.target_buffer
+            )?;
+            writeln!(
+                w,
+                "Instant Unstake Fee: {} to {} of withdrawal amount, as the buffer drains",
+                instant_unstake.min_fee, instant_unstake.max_fee
+            )?;
+            writeln!(
+                w,
+                "Scheduled Decrease: {}",
+                Sol(self.scheduled_decrease_lamports)
+            )?;
+        }
+        if let Some(next_instant_unstake) = &self.next_instant_unstake {
+            writeln!(
+                w,
+                "Next Instant Unstake Buffer: {} of total lamports",
+                next_instant_unstake.target_buffer
+            )?;
+            writeln!(
+                w,
+                "Next Instant Unstake Fee: {} to {} of withdrawal amount, as the buffer drains",
+                next_instant_unstake.min_fee, next_instant_unstake.max_fee
+            )?;
+        }
         writeln!(w)?;
 
         match &self.details {
@@ -230,6 +264,35 @@ impl Display for CliStakePool {
             "SOL Deposit Referral Fee: {}% of SOL Deposit Fee",
             &self.sol_referral_fee
         )?;
+        if let Some(instant_unstake) = &self.instant_unstake {
+            writeln!(
+                f,
+                "Instant Unstake Buffer: {} of total lamports",
+                instant_unstake.target_buffer
+            )?;
+            writeln!(
+                f,
+                "Instant Unstake Fee: {} to {} of withdrawal amount, as the buffer drains",
+                instant_unstake.min_fee, instant_unstake.max_fee
+            )?;
+            writeln!(
+                f,
+                "Scheduled Decrease: {}",
+                Sol(self.scheduled_decrease_lamports)
+            )?;
+        }
+        if let Some(next_instant_unstake) = &self.next_instant_unstake {
+            writeln!(
+                f,
+                "Next Instant Unstake Buffer: {} of total lamports",
+                next_instant_unstake.target_buffer
+            )?;
+            writeln!(
+                f,
+                "Next Instant Unstake Fee: {} to {} of withdrawal amount, as the buffer drains",
+                next_instant_unstake.min_fee, next_instant_unstake.max_fee
+            )?;
+        }
         Ok(())
     }
 }
@@ -248,6 +311,11 @@ pub(crate) struct CliStakePoolDetails {
     pub update_required: bool,
 }
 
+fn format_apy(apy: Option<f64>) -> String {
+    apy.map(|apy| format!("\tAPY: {:.2}%", apy * 100.0))
+        .unwrap_or_default()
+}
+
 impl Display for CliStakePoolDetails {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         writeln!(
@@ -259,10 +327,11 @@ impl Display for CliStakePoolDetails {
         for stake_account in &self.stake_accounts {
             writeln!(
                 f,
-                "Vote Account: {}\tBalance: {}\tLast Update Epoch: {}",
+                "Vote Account: {}\tBalance: {}\tLast Update Epoch: {}{}",
                 stake_account.vote_account_address,
                 Sol(stake_account.validator_lamports),
                 stake_account.validator_last_update_epoch,
+                format_apy(stake_account.apy),
             )?;
         }
         writeln!(
@@ -304,13 +373,14 @@ impl VerboseDisplay for CliStakePoolDetails {
         for stake_account in &self.stake_accounts {
             writeln!(
                 w,
-                "Vote Account: {}\tStake Account: {}\tActive Balance: {}\tTransient Stake Account: {}\tTransient Balance: {}\tLast Update Epoch: {}{}",
+                "Vote Account: {}\tStake Account: {}\tActive Balance: {}\tTransient Stake Account: {}\tTransient Balance: {}\tLast Update Epoch: {}{}{}",
                 stake_account.vote_account_address,
                 stake_account.stake_account_address,
                 Sol(stake_account.validator_active_stake_lamports),
                 stake_account.validator_transient_stake_account_address,
                 Sol(stake_account.validator_transient_stake_lamports),
                 stake_account.validator_last_update_epoch,
+                format_apy(stake_account.apy),
                 if stake_account.update_required {
                     " [UPDATE REQUIRED]"
                 } else {
@@ -354,6 +424,7 @@ pub(crate) struct CliStakePoolStakeAccountInfo {
     pub validator_transient_stake_account_address: String,
     pub validator_transient_stake_lamports: u64,
     pub update_required: bool,
+    pub apy: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -441,6 +512,24 @@ impl From<Fee> for CliStakePoolFee {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliStakePoolInstantUnstake {
+    pub target_buffer: CliStakePoolFee,
+    pub min_fee: CliStakePoolFee,
+    pub max_fee: CliStakePoolFee,
+}
+
+impl From<InstantUnstake> for CliStakePoolInstantUnstake {
+    fn from(i: InstantUnstake) -> Self {
+        Self {
+            target_buffer: CliStakePoolFee::from(i.target_buffer),
+            min_fee: CliStakePoolFee::from(i.min_fee),
+            max_fee: CliStakePoolFee::from(i.max_fee),
+        }
+    }
+}
+
 impl From<(Pubkey, StakePool, ValidatorList, Pubkey)> for CliStakePool {
     fn from(s: (Pubkey, StakePool, ValidatorList, Pubkey)) -> Self {
         let (address, stake_pool, validator_list, pool_withdraw_authority) = s;
@@ -490,6 +579,13 @@ impl From<(Pubkey, StakePool, ValidatorList, Pubkey)> for CliStakePool {
                 .map(CliStakePoolFee::from),
             last_epoch_pool_token_supply: stake_pool.last_epoch_pool_token_supply,
             last_epoch_total_lamports: stake_pool.last_epoch_total_lamports,
+            instant_unstake: stake_pool
+                .instant_unstake
+                .map(CliStakePoolInstantUnstake::from),
+            next_instant_unstake: stake_pool
+                .next_instant_unstake
+                .map(CliStakePoolInstantUnstake::from),
+            scheduled_decrease_lamports: stake_pool.scheduled_decrease_lamports,
             details: None,
         }
     }
@@ -0,0 +1,330 @@
+//! Rebalancing strategies for the stake pool, computing the validator stake
+//! increases and decreases that move the pool towards a target weighting
+
+use {
+    serde::Deserialize,
+    solana_program::pubkey::Pubkey,
+    spl_stake_pool::MINIMUM_ACTIVE_STAKE,
+    std::{cmp::Ordering, collections::HashMap, fmt, fs, str::FromStr},
+};
+
+type Error = Box<dyn std::error::Error>;
+
+/// Scores from the CSV file are scaled by this factor to get integer weights
+const SCORE_PRECISION: f64 = 1_000_000.0;
+
+/// How the stake is spread across the validators of the pool
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum RebalanceStrategy {
+    /// Same stake on every validator
+    Equal,
+    /// Stake proportional to `100 - commission`, delinquent validators get
+    /// nothing
+    Commission,
+    /// Stake proportional to the scores of a CSV file
+    Score,
+}
+
+/// Target weighting policy, read from a JSON file such as:
+///
+/// `{ "strategy": "score", "scores-file": "scores.csv", "reserve-sol": 10 }`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct RebalancePolicy {
+    pub strategy: RebalanceStrategy,
+    /// CSV file of `vote_account,score` lines, required for the score strategy
+    #[serde(default)]
+    pub scores_file: Option<String>,
+    /// Amount of SOL to keep in the reserve for withdrawals
+    #[serde(default)]
+    pub reserve_sol: f64,
+    /// Stake moves smaller than this amount of SOL are skipped
+    #[serde(default)]
+    pub min_move_sol: f64,
+}
+
+pub(crate) fn read_policy(policy_file: &str) -> Result<RebalancePolicy, Error> {
+    let data = fs::read_to_string(policy_file)
+        .map_err(|err| format!("Unable to read policy file {}: {}", policy_file, err))?;
+    let policy: RebalancePolicy = serde_json::from_str(&data)
+        .map_err(|err| format!("Invalid policy file {}: {}", policy_file, err))?;
+    if policy.strategy == RebalanceStrategy::Score && policy.scores_file.is_none() {
+        return Err("The score strategy requires a scores-file in the policy".into());
+    }
+    if policy.reserve_sol < 0.0 || policy.min_move_sol < 0.0 {
+        return Err("Policy amounts cannot be negative".into());
+    }
+    Ok(policy)
+}
+
+/// Parses `vote_account,score` lines into weights. Empty lines, lines starting
+/// with `#` and a header line are ignored, and scores must not be negative.
+pub(crate) fn parse_scores(data: &str) -> Result<HashMap<Pubkey, u128>, Error> {
+    let mut scores = HashMap::new();
+    for (index, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split(',').map(str::trim);
+        let address = fields.next().unwrap_or_default();
+        let score = fields.next().unwrap_or_default();
+        let vote_account_address = match Pubkey::from_str(address) {
+            Ok(vote_account_address) => vote_account_address,
+            Err(_) if index == 0 => continue,
+            Err(_) => return Err(format!("Invalid vote account on line {}", index + 1).into()),
+        };
+        let score = score
+            .parse::<f64>()
+            .ok()
+            .filter(|score| score.is_finite() && *score >= 0.0)
+            .ok_or_else(|| format!("Invalid score on line {}", index + 1))?;
+        scores.insert(vote_account_address, (score * SCORE_PRECISION) as u128);
+    }
+    Ok(scores)
+}
+
+/// Validator of the pool that can be rebalanced this epoch
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RebalanceValidator {
+    pub vote_account_address: Pubkey,
+    /// Stake that can be moved off the validator, which excludes the minimum
+    /// stake the validator stake account must keep
+    pub active_stake_lamports: u64,
+    pub weight: u128,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum RebalanceOperation {
+    Decrease,
+    Increase,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RebalanceMove {
+    pub operation: RebalanceOperation,
+    pub vote_account_address: Pubkey,
+    pub lamports: u64,
+}
+
+impl fmt::Display for RebalanceMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operation = match self.operation {
+            RebalanceOperation::Decrease => "Decrease",
+            RebalanceOperation::Increase => "Increase",
+        };
+        write!(
+            f,
+            "{} stake on {} by {}",
+            operation,
+            self.vote_account_address,
+            solana_sdk::native_token::Sol(self.lamports)
+        )
+    }
+}
+
+/// Computes the moves bringing each validator to its share of the pool
+/// stake, which is the stake of the validators plus the reserve above
+/// `reserve_floor`. Validator stake accounts can never go below the minimum
+/// stake of rent-exemption plus `MINIMUM_ACTIVE_STAKE`, so every target is
+/// at least that minimum, even for validators without weight.
+///
+/// Each validator gets at most one move, and moves smaller than `min_move`
+/// or than the program minimums are skipped. Decreased stake only reaches the
+/// reserve on the next epoch, so increases are funded by the current reserve
+/// alone, largest shortfall first, each one also taking the rent-exemption of
+/// its transient stake account. Running the rebalance again on later epochs
+/// finishes the job.
+pub(crate) fn plan_rebalance(
+    validators: &[RebalanceValidator],
+    reserve_lamports: u64,
+    reserve_floor: u64,
+    stake_rent: u64,
+    min_move: u64,
+) -> Result<Vec<RebalanceMove>, Error> {
+    let total_weight = validators.iter().map(|v| v.weight).sum::<u128>();
+    if total_weight == 0 {
+        return Err("No validator has a positive weight in the policy".into());
+    }
+    // splits from the reserve must leave it rent-exempt
+    let reserve_floor = reserve_floor.max(stake_rent.saturating_add(1));
+    let available_reserve = reserve_lamports.saturating_sub(reserve_floor);
+    let minimum_stake = stake_rent.saturating_add(MINIMUM_ACTIVE_STAKE);
+    let validator_stake =
+        |validator: &RebalanceValidator| validator.active_stake_lamports + minimum_stake;
+    let total_lamports = validators
+        .iter()
+        .map(|v| validator_stake(v) as u128)
+        .sum::<u128>()
+        + available_reserve as u128;
+
+    let mut decreases = vec![];
+    let mut shortfalls = vec![];
+    for validator in validators {
+        let stake = validator_stake(validator);
+        let target = ((total_lamports * validator.weight / total_weight) as u64).max(minimum_stake);
+        match stake.cmp(&target) {
+            Ordering::Greater => {
+                // transient stake from decreases must be more than rent-exempt
+                let lamports = stake - target;
+                if lamports >= min_move && lamports > stake_rent {
+                    decreases.push(RebalanceMove {
+                        operation: RebalanceOperation::Decrease,
+                        vote_account_address: validator.vote_account_address,
+                        lamports,
+                    });
+                }
+            }
+            Ordering::Less => shortfalls.push((validator.vote_account_address, target - stake)),
+            Ordering::Equal => {}
+        }
+    }
+
+    shortfalls.sort_by(|a, b| b.1.cmp(&a.1));
+    let mut budget = available_reserve;
+    let mut increases = vec![];
+    for (vote_account_address, shortfall) in shortfalls {
+        let lamports = shortfall.min(budget.saturating_sub(stake_rent));
+        if lamports >= min_move.max(MINIMUM_ACTIVE_STAKE) {
+            budget -= lamports + stake_rent;
+            increases.push(RebalanceMove {
+                operation: RebalanceOperation::Increase,
+                vote_account_address,
+                lamports,
+            });
+        }
+    }
+
+    decreases.extend(increases);
+    Ok(decreases)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::native_token::LAMPORTS_PER_SOL};
+
+    const STAKE_RENT: u64 = 2_282_880;
+
+    fn validator(active_sol: u64, weight: u128) -> RebalanceValidator {
+        RebalanceValidator {
+            vote_account_address: Pubkey::new_unique(),
+            active_stake_lamports: active_sol * LAMPORTS_PER_SOL,
+            weight,
+        }
+    }
+
+    #[test]
+    fn parse_scores_csv() {
+        let first = Pubkey::new_unique();
+        let second = Pubkey::new_unique();
+        let data = format!(
+            "vote_account,score\n{},1.5\n\n# comment\n{}, 3\n",
+            first, second
+        );
+        let scores = parse_scores(&data).unwrap();
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores[&first], 1_500_000);
+        assert_eq!(scores[&second], 3_000_000);
+
+        assert!(parse_scores(&format!("{},-1\n", first)).is_err());
+        assert!(parse_scores(&format!("{},1\nnot-a-pubkey,1\n", first)).is_err());
+    }
+
+    #[test]
+    fn equal_weights() {
+        let validators = vec![validator(100, 1), validator(20, 1), validator(30, 1)];
+        let moves = plan_rebalance(
+            &validators,
+            60 * LAMPORTS_PER_SOL,
+            LAMPORTS_PER_SOL,
+            STAKE_RENT,
+            0,
+        )
+        .unwrap();
+        // 209 movable SOL, so about 69.67 SOL each
+        assert_eq!(moves.len(), 3);
+        assert_eq!(moves[0].operation, RebalanceOperation::Decrease);
+        assert_eq!(
+            moves[0].vote_account_address,
+            validators[0].vote_account_address
+        );
+        assert_eq!(moves[0].lamports, 30_333_333_334);
+        assert_eq!(moves[1].operation, RebalanceOperation::Increase);
+        assert_eq!(
+            moves[1].vote_account_address,
+            validators[1].vote_account_address
+        );
+        assert_eq!(moves[1].lamports, 49_666_666_666);
+        // the reserve runs out, keeping the floor and rent for both transients
+        assert_eq!(
+            moves[2].vote_account_address,
+            validators[2].vote_account_address
+        );
+        assert_eq!(
+            moves[2].lamports,
+            59 * LAMPORTS_PER_SOL - 49_666_666_666 - 2 * STAKE_RENT
+        );
+    }
+
+    #[test]
+    fn weighted_with_thresholds() {
+        // already balanced, the reserve only holds its floor
+        let validators = vec![validator(20, 2), validator(20, 2)];
+        let moves = plan_rebalance(&validators, STAKE_RENT + 1, 0, STAKE_RENT, 0).unwrap();
+        assert!(moves.is_empty());
+
+        // zero weight removes all stake, small moves are skipped
+        let validators = vec![validator(30, 0), validator(10, 1), validator(10, 1)];
+        let moves = plan_rebalance(
+            &validators,
+            LAMPORTS_PER_SOL,
+            LAMPORTS_PER_SOL,
+            STAKE_RENT,
+            LAMPORTS_PER_SOL,
+        )
+        .unwrap();
+        assert_eq!(
+            moves,
+            vec![RebalanceMove {
+                operation: RebalanceOperation::Decrease,
+                vote_account_address: validators[0].vote_account_address,
+                lamports: 30 * LAMPORTS_PER_SOL,
+            }]
+        );
+
+        let validators = vec![validator(30, 0)];
+        assert!(plan_rebalance(&validators, 0, 0, STAKE_RENT, 0).is_err());
+    }
+
+    #[test]
+    fn zero_weight_keeps_minimum_stake() {
+        // a commission of 100% or a validator missing from the scores file
+        // gives a zero weight, which only drains the stake above the minimum,
+        // so a validator already at the minimum is left alone
+        let validators = vec![validator(0, 0), validator(30, 0), validator(10, 1)];
+        let moves = plan_rebalance(
+            &validators,
+            10 * LAMPORTS_PER_SOL,
+            LAMPORTS_PER_SOL,
+            STAKE_RENT,
+            0,
+        )
+        .unwrap();
+        assert_eq!(moves.len(), 2);
+        assert_eq!(
+            moves[0],
+            RebalanceMove {
+                operation: RebalanceOperation::Decrease,
+                vote_account_address: validators[1].vote_account_address,
+                lamports: 30 * LAMPORTS_PER_SOL,
+            }
+        );
+        assert_eq!(moves[1].operation, RebalanceOperation::Increase);
+        assert_eq!(
+            moves[1].vote_account_address,
+            validators[2].vote_account_address
+        );
+        assert_eq!(moves[1].lamports, 9 * LAMPORTS_PER_SOL - STAKE_RENT);
+    }
+}
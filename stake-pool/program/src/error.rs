@@ -132,6 +132,21 @@ pub enum StakePoolError {
     /// Too much SOL withdrawn from the stake pool's reserve account
     #[error("SolWithdrawalTooLarge")]
     SolWithdrawalTooLarge,
+    /// Provided metadata account does not match metadata account derived for pool mint
+    #[error("InvalidMetadataAccount")]
+    InvalidMetadataAccount,
+    /// Provided validator history account does not match the stake pool's
+    #[error("InvalidValidatorHistory")]
+    InvalidValidatorHistory,
+    /// Pool mint has an extension that the stake pool does not support
+    #[error("UnsupportedMintExtension")]
+    UnsupportedMintExtension,
+    /// Manager fee account has an extension that the stake pool does not support
+    #[error("UnsupportedFeeAccountExtension")]
+    UnsupportedFeeAccountExtension,
+    /// Scheduled decreases must come from the validator with the most active stake
+    #[error("InvalidScheduledDecreaseValidator")]
+    InvalidScheduledDecreaseValidator,
 }
 impl From<StakePoolError> for ProgramError {
     fn from(e: StakePoolError) -> Self {
@@ -0,0 +1,215 @@
+//! Inlined Metaplex token metadata types and instructions, to avoid a direct
+//! dependency on `mpl-token-metadata`, which pulls in a different Solana
+//! version
+
+solana_program::declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+pub(crate) mod instruction {
+    use {
+        super::state::DataV2,
+        borsh::{BorshDeserialize, BorshSerialize},
+        solana_program::{
+            instruction::{AccountMeta, Instruction},
+            pubkey::Pubkey,
+            system_program,
+        },
+    };
+
+    /// Discriminator of `CreateMetadataAccountV3` in the metadata program
+    const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+    /// Discriminator of `UpdateMetadataAccountV2` in the metadata program
+    const UPDATE_METADATA_ACCOUNT_V2: u8 = 15;
+
+    #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+    struct CreateMetadataAccountArgsV3 {
+        /// Note that unique metadatas are disabled for now.
+        pub data: DataV2,
+        /// Whether you want your metadata to be updateable in the future.
+        pub is_mutable: bool,
+        /// UNUSED If this is a collection parent NFT.
+        pub collection_details: Option<u8>,
+    }
+
+    #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+    struct UpdateMetadataAccountArgsV2 {
+        pub data: Option<DataV2>,
+        pub update_authority: Option<Pubkey>,
+        pub primary_sale_happened: Option<bool>,
+        pub is_mutable: Option<bool>,
+    }
+
+    /// Creates a `CreateMetadataAccountV3` instruction for a mutable
+    /// fungible token metadata, without creators, collection, or uses
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn create_metadata_accounts_v3(
+        program_id: Pubkey,
+        metadata_account: Pubkey,
+        mint: Pubkey,
+        mint_authority: Pubkey,
+        payer: Pubkey,
+        update_authority: Pubkey,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Instruction {
+        let mut data = vec![CREATE_METADATA_ACCOUNT_V3];
+        data.append(
+            &mut CreateMetadataAccountArgsV3 {
+                data: DataV2::new(name, symbol, uri),
+                is_mutable: true,
+                collection_details: None,
+            }
+            .try_to_vec()
+            .unwrap(),
+        );
+        Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(metadata_account, false),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new_readonly(mint_authority, true),
+                AccountMeta::new(payer, true),
+                AccountMeta::new_readonly(update_authority, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            data,
+        }
+    }
+
+    /// Creates an `UpdateMetadataAccountV2` instruction replacing the name,
+    /// symbol, and uri of the metadata, and keeping everything else
+    pub(crate) fn update_metadata_accounts_v2(
+        program_id: Pubkey,
+        metadata_account: Pubkey,
+        update_authority: Pubkey,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Instruction {
+        let mut data = vec![UPDATE_METADATA_ACCOUNT_V2];
+        data.append(
+            &mut UpdateMetadataAccountArgsV2 {
+                data: Some(DataV2::new(name, symbol, uri)),
+                update_authority: None,
+                primary_sale_happened: None,
+                is_mutable: None,
+            }
+            .try_to_vec()
+            .unwrap(),
+        );
+        Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(metadata_account, false),
+                AccountMeta::new_readonly(update_authority, true),
+            ],
+            data,
+        }
+    }
+}
+
+/// PDAs of the metadata program
+pub mod pda {
+    use {super::ID, solana_program::pubkey::Pubkey};
+
+    /// Helper to find a metadata account address
+    pub fn find_metadata_account(mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"metadata", ID.as_ref(), mint.as_ref()], &ID)
+    }
+}
+
+/// Accounts of the metadata program
+pub mod state {
+    use borsh::{BorshDeserialize, BorshSerialize};
+
+    /// Maximum length of the token name
+    pub const MAX_NAME_LENGTH: usize = 32;
+    /// Maximum length of the token symbol
+    pub const MAX_SYMBOL_LENGTH: usize = 10;
+    /// Maximum length of the token uri
+    pub const MAX_URI_LENGTH: usize = 200;
+
+    /// Metadata fields for the token. Creators, collection, and uses are
+    /// always `None`, which serializes the same as the full types, so they
+    /// are stood in for by `Option<u8>`.
+    #[repr(C)]
+    #[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+    pub(crate) struct DataV2 {
+        /// The name of the asset
+        pub name: String,
+        /// The symbol for the asset
+        pub symbol: String,
+        /// URI pointing to JSON representing the asset
+        pub uri: String,
+        /// Royalty basis points that goes to creators in secondary sales (0-10000)
+        pub seller_fee_basis_points: u16,
+        /// UNUSED Array of creators, optional
+        pub creators: Option<u8>,
+        /// UNUSED Collection
+        pub collection: Option<u8>,
+        /// UNUSED Uses
+        pub uses: Option<u8>,
+    }
+
+    impl DataV2 {
+        pub(crate) fn new(name: String, symbol: String, uri: String) -> Self {
+            Self {
+                name,
+                symbol,
+                uri,
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_program::pubkey::Pubkey};
+
+    #[test]
+    fn create_metadata_data_layout() {
+        let instruction = instruction::create_metadata_accounts_v3(
+            ID,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            "Pool".to_string(),
+            "POOL".to_string(),
+            "uri".to_string(),
+        );
+        let mut expected = vec![33];
+        for field in ["Pool", "POOL", "uri"] {
+            expected.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            expected.extend_from_slice(field.as_bytes());
+        }
+        // no royalties, creators, collection, or uses, mutable, no details
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 1, 0]);
+        assert_eq!(instruction.data, expected);
+    }
+
+    #[test]
+    fn update_metadata_data_layout() {
+        let instruction = instruction::update_metadata_accounts_v2(
+            ID,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            "Pool".to_string(),
+            "POOL".to_string(),
+            "uri".to_string(),
+        );
+        let mut expected = vec![15, 1];
+        for field in ["Pool", "POOL", "uri"] {
+            expected.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            expected.extend_from_slice(field.as_bytes());
+        }
+        // data fields, then no new update authority, sale flag, or mutability
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(instruction.data, expected);
+    }
+}
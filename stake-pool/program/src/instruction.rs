@@ -5,6 +5,7 @@ use {
     crate::{
         find_deposit_authority_program_address, find_stake_program_address,
         find_transient_stake_program_address, find_withdraw_authority_program_address,
+        inline_mpl_token_metadata::{self, pda::find_metadata_account},
         state::{Fee, FeeType, StakePool, ValidatorList},
         MAX_VALIDATORS_TO_UPDATE,
     },
@@ -121,6 +122,10 @@ pub enum StakePoolInstruction {
     /// Internally, this instruction splits a validator stake account into its
     /// corresponding transient stake account and deactivates it.
     ///
+    /// Anyone may also decrease up to the stake pool's
+    /// `scheduled_decrease_lamports`, which replenish the instant unstake
+    /// buffer, in which case the stake pool must be writable.
+    ///
     /// In order to rebalance the pool without taking custody, the staker needs
     /// a way of reducing the stake on a stake account. This instruction splits
     /// some amount of stake, up to the total activated stake, from the canonical
@@ -130,8 +135,8 @@ pub enum StakePoolInstruction {
     /// exist. The amount of lamports to move must be at least rent-exemption
     /// plus 1 lamport.
     ///
-    ///  0. `[]` Stake pool
-    ///  1. `[s]` Stake pool staker
+    ///  0. `[w]` Stake pool
+    ///  1. `[s]` Stake pool staker, or any account for scheduled decreases
     ///  2. `[]` Stake pool withdraw authority
     ///  3. `[w]` Validator list
     ///  4. `[w]` Canonical stake account to split from
@@ -227,6 +232,8 @@ pub enum StakePoolInstruction {
     ///  5. `[]` Sysvar stake history
     ///  6. `[]` Stake program
     ///  7. ..7+N ` [] N pairs of validator and transient stake accounts
+    ///  7+N. `[w]` Validator history account of the stake pool, required if
+    ///     the pool has one
     UpdateValidatorListBalance {
         /// Index to start updating on the validator list
         #[allow(dead_code)] // but it's not
@@ -240,6 +247,9 @@ pub enum StakePoolInstruction {
 
     ///   Updates total pool balance based on balances in the reserve and validator list
     ///
+    ///   If instant unstakes are enabled, also schedules the decreases needed
+    ///   to refill the liquidity buffer of the reserve.
+    ///
     ///   0. `[w]` Stake pool
     ///   1. `[]` Stake pool withdraw authority
     ///   2. `[w]` Validator stake list storage account
@@ -319,6 +329,12 @@ pub enum StakePoolInstruction {
 
     ///  (Manager only) Update fee
     ///
+    ///  `FeeType::InstantUnstake` configures the reserve liquidity buffer and
+    ///  the dynamic fee charged on SOL withdrawals as the buffer drains. Like
+    ///  withdrawal fees, a new configuration takes effect on the next epoch's
+    ///  `UpdateStakePoolBalance`, and its fees may only rise by a limited
+    ///  factor, while disabling instant unstakes takes effect immediately.
+    ///
     ///  0. `[w]` StakePool
     ///  1. `[s]` Manager
     SetFee {
@@ -358,7 +374,8 @@ pub enum StakePoolInstruction {
     SetFundingAuthority(FundingType),
 
     ///   Withdraw SOL directly from the pool's reserve account. Fails if the
-    ///   reserve does not have enough SOL.
+    ///   reserve does not have enough SOL. If instant unstakes are enabled, the
+    ///   instant unstake fee is charged on top of the SOL withdrawal fee.
     ///
     ///   0. `[w]` Stake pool
     ///   1. `[]` Stake pool withdraw authority
@@ -374,6 +391,142 @@ pub enum StakePoolInstruction {
     ///  11. `[]` Token program id
     ///  12. `[s]` (Optional) Stake pool sol withdraw authority
     WithdrawSol(u64),
+
+    /// (Staker only) Move stake from one validator in the pool to another
+    ///
+    /// Internally, this instruction splits `lamports` from the source validator
+    /// stake account into a source transient stake account and deactivates it,
+    /// and at the same time splits `lamports` from the reserve into a
+    /// destination transient stake account delegated to the destination
+    /// validator. The reserve fronts the new stake for one epoch, after which
+    /// `UpdateValidatorListBalance` merges the deactivated source transient
+    /// stake back into the reserve, and the destination transient stake into
+    /// the destination validator stake account.
+    ///
+    /// The stake earns rewards on the source validator during the current
+    /// epoch and on the destination validator from the next one, without
+    /// going through a full deactivate / reactivate cycle.
+    ///
+    /// This instruction only succeeds if neither transient stake account
+    /// exists, and the reserve can cover the destination stake while staying
+    /// rent-exempt. The amount of lamports to move must be more than
+    /// rent-exemption, and at least `crate::MINIMUM_ACTIVE_STAKE`.
+    ///
+    /// Since the stake program can't move active stake between validators,
+    /// the reserve is the only source of the new stake: a pool can't
+    /// redelegate more than its reserve holds, even if the source validator
+    /// stake is much larger. Until the next update, the redelegated lamports
+    /// are accounted in both transient stake accounts, and the reserve is
+    /// short by the same amount, so the total lamports of the pool don't
+    /// change.
+    ///
+    ///  0. `[]` Stake pool
+    ///  1. `[s]` Stake pool staker
+    ///  2. `[]` Stake pool withdraw authority
+    ///  3. `[w]` Validator list
+    ///  4. `[w]` Stake pool reserve stake
+    ///  5. `[w]` Source canonical stake account to split from
+    ///  6. `[w]` Source transient stake account to receive split
+    ///  7. `[w]` Destination transient stake account
+    ///  8. `[]` Destination validator stake account
+    ///  9. `[]` Destination validator vote account to delegate to
+    /// 10. `[]` Clock sysvar
+    /// 11. `[]` Rent sysvar
+    /// 12. `[]` Stake History sysvar
+    /// 13. `[]` Stake Config sysvar
+    /// 14. `[]` System program
+    /// 15. `[]` Stake program
+    ///  userdata: amount of lamports to move to the destination validator.
+    ///  The actual amount split from the reserve into the destination
+    ///  transient stake account is: `lamports + stake_rent_exemption`
+    ///  The rent-exemption of the stake account is withdrawn back to the reserve
+    ///  after it is merged.
+    Redelegate {
+        /// amount of lamports to move from the source to the destination
+        /// validator
+        #[allow(dead_code)] // but it's not
+        lamports: u64,
+        /// seed used to create the source transient stake account
+        #[allow(dead_code)] // but it's not
+        source_transient_stake_seed: u64,
+        /// seed used to create the destination transient stake account
+        #[allow(dead_code)] // but it's not
+        destination_transient_stake_seed: u64,
+    },
+
+    ///   (Manager only) Create token metadata for the stake pool token in the
+    ///   metadata program, signing as the pool's withdraw authority, which is
+    ///   the mint authority of the pool token
+    ///
+    ///   0. `[]` Stake pool
+    ///   1. `[s]` Manager
+    ///   2. `[]` Stake pool withdraw authority
+    ///   3. `[]` Pool token mint account
+    ///   4. `[s, w]` Payer for creation of token metadata account
+    ///   5. `[w]` Token metadata account
+    ///   6. `[]` Metadata program id
+    ///   7. `[]` System program id
+    CreateTokenMetadata {
+        /// Token name
+        #[allow(dead_code)] // but it's not
+        name: String,
+        /// Token symbol e.g. stkSOL
+        #[allow(dead_code)] // but it's not
+        symbol: String,
+        /// URI of the uploaded metadata of the spl-token
+        #[allow(dead_code)] // but it's not
+        uri: String,
+    },
+
+    ///   (Manager only) Update token metadata for the stake pool token in the
+    ///   metadata program
+    ///
+    ///   0. `[]` Stake pool
+    ///   1. `[s]` Manager
+    ///   2. `[]` Stake pool withdraw authority
+    ///   3. `[w]` Token metadata account
+    ///   4. `[]` Metadata program id
+    UpdateTokenMetadata {
+        /// Token name
+        #[allow(dead_code)] // but it's not
+        name: String,
+        /// Token symbol e.g. stkSOL
+        #[allow(dead_code)] // but it's not
+        symbol: String,
+        /// URI of the uploaded metadata of the spl-token
+        #[allow(dead_code)] // but it's not
+        uri: String,
+    },
+
+    ///   (Manager only) Initializes the validator history account, recording
+    ///   the rewards of each validator on `UpdateValidatorListBalance`
+    ///
+    ///   The account must be created beforehand, owned by the program and
+    ///   rent-exempt, with room for at least the maximum number of validators
+    ///   of the pool.
+    ///
+    ///   0. `[w]` Stake pool
+    ///   1. `[s]` Manager
+    ///   2. `[]` Validator list
+    ///   3. `[w]` Uninitialized validator history account
+    InitializeValidatorHistory,
+
+    ///   Grows a stake pool account created by an older version of the
+    ///   program to the current size, zeroing everything after the older
+    ///   fields, including bytes left stale by cleared optional fields, so
+    ///   that instant unstakes start disabled and no validator history is
+    ///   recorded.
+    ///
+    ///   Pools at the previous size can fail to deserialize, or to store the
+    ///   new fields, in the other instructions until they have been
+    ///   reallocated. Anyone can send this instruction, the payer funds the
+    ///   additional rent, and it is a no-op on pools that already have the
+    ///   current size.
+    ///
+    ///   0. `[w]` Stake pool
+    ///   1. `[s, w]` Payer for the additional rent
+    ///   2. `[]` System program
+    ReallocStakePool,
 }
 
 /// Creates an 'initialize' instruction.
@@ -506,7 +659,7 @@ pub fn decrease_validator_stake(
     transient_stake_seed: u64,
 ) -> Instruction {
     let accounts = vec![
-        AccountMeta::new_readonly(*stake_pool, false),
+        AccountMeta::new(*stake_pool, false),
         AccountMeta::new_readonly(*staker, true),
         AccountMeta::new_readonly(*stake_pool_withdraw_authority, false),
         AccountMeta::new(*validator_list, false),
@@ -725,6 +878,105 @@ pub fn decrease_validator_stake_with_vote(
     )
 }
 
+/// Creates `Redelegate` instruction (move stake from one validator to another)
+pub fn redelegate(
+    program_id: &Pubkey,
+    stake_pool: &Pubkey,
+    staker: &Pubkey,
+    stake_pool_withdraw_authority: &Pubkey,
+    validator_list: &Pubkey,
+    reserve_stake: &Pubkey,
+    source_validator_stake: &Pubkey,
+    source_transient_stake: &Pubkey,
+    destination_transient_stake: &Pubkey,
+    destination_validator_stake: &Pubkey,
+    destination_validator: &Pubkey,
+    lamports: u64,
+    source_transient_stake_seed: u64,
+    destination_transient_stake_seed: u64,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*stake_pool, false),
+        AccountMeta::new_readonly(*staker, true),
+        AccountMeta::new_readonly(*stake_pool_withdraw_authority, false),
+        AccountMeta::new(*validator_list, false),
+        AccountMeta::new(*reserve_stake, false),
+        AccountMeta::new(*source_validator_stake, false),
+        AccountMeta::new(*source_transient_stake, false),
+        AccountMeta::new(*destination_transient_stake, false),
+        AccountMeta::new_readonly(*destination_validator_stake, false),
+        AccountMeta::new_readonly(*destination_validator, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(sysvar::stake_history::id(), false),
+        AccountMeta::new_readonly(stake::config::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(stake::program::id(), false),
+    ];
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: StakePoolInstruction::Redelegate {
+            lamports,
+            source_transient_stake_seed,
+            destination_transient_stake_seed,
+        }
+        .try_to_vec()
+        .unwrap(),
+    }
+}
+
+/// Create a `Redelegate` instruction given an existing stake pool and the
+/// source and destination vote accounts
+pub fn redelegate_with_vote(
+    program_id: &Pubkey,
+    stake_pool: &StakePool,
+    stake_pool_address: &Pubkey,
+    source_vote_account_address: &Pubkey,
+    source_transient_stake_seed: u64,
+    destination_vote_account_address: &Pubkey,
+    destination_transient_stake_seed: u64,
+    lamports: u64,
+) -> Instruction {
+    let pool_withdraw_authority =
+        find_withdraw_authority_program_address(program_id, stake_pool_address).0;
+    let (source_validator_stake_address, _) =
+        find_stake_program_address(program_id, source_vote_account_address, stake_pool_address);
+    let (source_transient_stake_address, _) = find_transient_stake_program_address(
+        program_id,
+        source_vote_account_address,
+        stake_pool_address,
+        source_transient_stake_seed,
+    );
+    let (destination_validator_stake_address, _) = find_stake_program_address(
+        program_id,
+        destination_vote_account_address,
+        stake_pool_address,
+    );
+    let (destination_transient_stake_address, _) = find_transient_stake_program_address(
+        program_id,
+        destination_vote_account_address,
+        stake_pool_address,
+        destination_transient_stake_seed,
+    );
+    redelegate(
+        program_id,
+        stake_pool_address,
+        &stake_pool.staker,
+        &pool_withdraw_authority,
+        &stake_pool.validator_list,
+        &stake_pool.reserve_stake,
+        &source_validator_stake_address,
+        &source_transient_stake_address,
+        &destination_transient_stake_address,
+        &destination_validator_stake_address,
+        destination_vote_account_address,
+        lamports,
+        source_transient_stake_seed,
+        destination_transient_stake_seed,
+    )
+}
+
 /// Creates `UpdateValidatorListBalance` instruction (update validator stake account balances)
 pub fn update_validator_list_balance(
     program_id: &Pubkey,
@@ -851,7 +1103,7 @@ pub fn update_stake_pool(
     let mut update_list_instructions: Vec<Instruction> = vec![];
     let mut start_index = 0;
     for accounts_chunk in vote_accounts.chunks(MAX_VALIDATORS_TO_UPDATE) {
-        update_list_instructions.push(update_validator_list_balance(
+        let mut instruction = update_validator_list_balance(
             program_id,
             stake_pool_address,
             &withdraw_authority,
@@ -861,7 +1113,13 @@ pub fn update_stake_pool(
             accounts_chunk,
             start_index,
             no_merge,
-        ));
+        );
+        if let Some(validator_history) = stake_pool.validator_history {
+            instruction
+                .accounts
+                .push(AccountMeta::new(validator_history, false));
+        }
+        update_list_instructions.push(instruction);
         start_index += MAX_VALIDATORS_TO_UPDATE as u32;
     }
 
@@ -1276,3 +1534,109 @@ pub fn set_funding_authority(
             .unwrap(),
     }
 }
+
+/// Creates an instruction to create metadata using the metadata program for
+/// the pool token
+pub fn create_token_metadata(
+    program_id: &Pubkey,
+    stake_pool: &Pubkey,
+    manager: &Pubkey,
+    pool_mint: &Pubkey,
+    payer: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    let (stake_pool_withdraw_authority, _) =
+        find_withdraw_authority_program_address(program_id, stake_pool);
+    let (token_metadata, _) = find_metadata_account(pool_mint);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*stake_pool, false),
+        AccountMeta::new_readonly(*manager, true),
+        AccountMeta::new_readonly(stake_pool_withdraw_authority, false),
+        AccountMeta::new_readonly(*pool_mint, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(token_metadata, false),
+        AccountMeta::new_readonly(inline_mpl_token_metadata::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: StakePoolInstruction::CreateTokenMetadata { name, symbol, uri }
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Creates an instruction to update metadata in the metadata program account
+/// for the pool token
+pub fn update_token_metadata(
+    program_id: &Pubkey,
+    stake_pool: &Pubkey,
+    manager: &Pubkey,
+    pool_mint: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    let (stake_pool_withdraw_authority, _) =
+        find_withdraw_authority_program_address(program_id, stake_pool);
+    let (token_metadata, _) = find_metadata_account(pool_mint);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*stake_pool, false),
+        AccountMeta::new_readonly(*manager, true),
+        AccountMeta::new_readonly(stake_pool_withdraw_authority, false),
+        AccountMeta::new(token_metadata, false),
+        AccountMeta::new_readonly(inline_mpl_token_metadata::id(), false),
+    ];
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: StakePoolInstruction::UpdateTokenMetadata { name, symbol, uri }
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Creates an instruction to initialize the validator history of the pool
+pub fn initialize_validator_history(
+    program_id: &Pubkey,
+    stake_pool: &Pubkey,
+    manager: &Pubkey,
+    validator_list: &Pubkey,
+    validator_history: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*stake_pool, false),
+        AccountMeta::new_readonly(*manager, true),
+        AccountMeta::new_readonly(*validator_list, false),
+        AccountMeta::new(*validator_history, false),
+    ];
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: StakePoolInstruction::InitializeValidatorHistory
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// Creates an instruction to grow an older stake pool account to the current
+/// size
+pub fn realloc_stake_pool(program_id: &Pubkey, stake_pool: &Pubkey, payer: &Pubkey) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*stake_pool, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: StakePoolInstruction::ReallocStakePool.try_to_vec().unwrap(),
+    }
+}
@@ -2,13 +2,20 @@
 
 use {
     crate::{
+        big_vec::BigVec,
         error::StakePoolError,
         find_deposit_authority_program_address,
+        inline_mpl_token_metadata::{
+            self,
+            instruction::{create_metadata_accounts_v3, update_metadata_accounts_v2},
+            pda::find_metadata_account,
+        },
         instruction::{FundingType, PreferredValidatorType, StakePoolInstruction},
         minimum_reserve_lamports, minimum_stake_lamports,
         state::{
-            AccountType, Fee, FeeType, StakePool, StakeStatus, ValidatorList, ValidatorListHeader,
-            ValidatorStakeInfo,
+            check_fee_account_extensions, check_mint_extensions, AccountType, Fee, FeeType,
+            StakePool, StakeStatus, ValidatorHistory, ValidatorHistoryHeader, ValidatorHistoryList,
+            ValidatorList, ValidatorListHeader, ValidatorStakeInfo,
         },
         AUTHORITY_DEPOSIT, AUTHORITY_WITHDRAW, MINIMUM_ACTIVE_STAKE, TRANSIENT_STAKE_SEED_PREFIX,
     },
@@ -16,20 +23,23 @@ use {
     num_traits::FromPrimitive,
     solana_program::{
         account_info::{next_account_info, AccountInfo},
-        borsh::try_from_slice_unchecked,
+        borsh::{get_packed_len, try_from_slice_unchecked},
         clock::{Clock, Epoch},
         decode_error::DecodeError,
         entrypoint::ProgramResult,
         msg,
         program::{invoke, invoke_signed},
         program_error::{PrintProgramError, ProgramError},
-        program_pack::Pack,
         pubkey::Pubkey,
         rent::Rent,
         stake, system_instruction, system_program,
         sysvar::Sysvar,
     },
-    spl_token::state::Mint,
+    spl_token_2022::{
+        check_spl_token_program_account,
+        extension::StateWithExtensions,
+        state::{Account, Mint},
+    },
 };
 
 /// Deserialize the stake state from AccountInfo
@@ -117,6 +127,33 @@ fn check_stake_program(program_id: &Pubkey) -> Result<(), ProgramError> {
     }
 }
 
+/// Check metadata program address
+fn check_mpl_metadata_program(program_id: &Pubkey) -> Result<(), ProgramError> {
+    if *program_id != inline_mpl_token_metadata::id() {
+        msg!(
+            "Expected metadata program {}, received {}",
+            inline_mpl_token_metadata::id(),
+            program_id
+        );
+        Err(ProgramError::IncorrectProgramId)
+    } else {
+        Ok(())
+    }
+}
+
+/// Check metadata account address is the one derived for the pool mint
+fn check_mpl_metadata_account_address(
+    metadata_address: &Pubkey,
+    pool_mint: &Pubkey,
+) -> Result<(), ProgramError> {
+    let (metadata_account_pubkey, _) = find_metadata_account(pool_mint);
+    if metadata_account_pubkey != *metadata_address {
+        Err(StakePoolError::InvalidMetadataAccount.into())
+    } else {
+        Ok(())
+    }
+}
+
 /// Check account owner is the given program
 fn check_account_owner(
     account_info: &AccountInfo,
@@ -222,6 +259,37 @@ fn create_pda_account<'a>(
     }
 }
 
+/// Records the rewards of a validator in the history, taking a new entry if
+/// needed, or the least recently updated one once the history is full
+fn record_validator_rewards(
+    header: &ValidatorHistoryHeader,
+    big_vec: &mut BigVec,
+    vote_account_address: &Pubkey,
+    epoch: u64,
+    stake_lamports: u64,
+    reward_lamports: u64,
+) -> Result<(), ProgramError> {
+    if let Some(history) = big_vec.find_mut::<ValidatorHistory>(
+        vote_account_address.as_ref(),
+        ValidatorHistory::memcmp_pubkey,
+    ) {
+        history.record(epoch, stake_lamports, reward_lamports);
+        return Ok(());
+    }
+    let mut history = ValidatorHistory::new(*vote_account_address);
+    history.record(epoch, stake_lamports, reward_lamports);
+    if big_vec.len() < header.max_validators {
+        big_vec.push(history)
+    } else {
+        let oldest = big_vec
+            .iter_mut::<ValidatorHistory>()
+            .min_by_key(|history| history.last_update_epoch)
+            .ok_or(StakePoolError::InvalidValidatorHistory)?;
+        *oldest = history;
+        Ok(())
+    }
+}
+
 /// Program state handler.
 pub struct Processor {}
 impl Processor {
@@ -468,7 +536,7 @@ impl Processor {
         )
     }
 
-    /// Issue a spl_token `Burn` instruction.
+    /// Issue a spl_token or spl_token_2022 `Burn` instruction.
     #[allow(clippy::too_many_arguments)]
     fn token_burn<'a>(
         token_program: AccountInfo<'a>,
@@ -477,7 +545,7 @@ impl Processor {
         authority: AccountInfo<'a>,
         amount: u64,
     ) -> Result<(), ProgramError> {
-        let ix = spl_token::instruction::burn(
+        let ix = spl_token_2022::instruction::burn(
             token_program.key,
             burn_account.key,
             mint.key,
@@ -489,7 +557,7 @@ impl Processor {
         invoke(&ix, &[burn_account, mint, authority, token_program])
     }
 
-    /// Issue a spl_token `MintTo` instruction.
+    /// Issue a spl_token or spl_token_2022 `MintTo` instruction.
     #[allow(clippy::too_many_arguments)]
     fn token_mint_to<'a>(
         stake_pool: &Pubkey,
@@ -505,7 +573,7 @@ impl Processor {
         let authority_signature_seeds = [&me_bytes[..32], authority_type, &[bump_seed]];
         let signers = &[&authority_signature_seeds[..]];
 
-        let ix = spl_token::instruction::mint_to(
+        let ix = spl_token_2022::instruction::mint_to(
             token_program.key,
             mint.key,
             destination.key,
@@ -517,24 +585,30 @@ impl Processor {
         invoke_signed(&ix, &[mint, destination, authority, token_program], signers)
     }
 
-    /// Issue a spl_token `Transfer` instruction.
+    /// Issue a spl_token or spl_token_2022 `TransferChecked` instruction.
     #[allow(clippy::too_many_arguments)]
     fn token_transfer<'a>(
         token_program: AccountInfo<'a>,
         source: AccountInfo<'a>,
+        mint: AccountInfo<'a>,
         destination: AccountInfo<'a>,
         authority: AccountInfo<'a>,
         amount: u64,
     ) -> Result<(), ProgramError> {
-        let ix = spl_token::instruction::transfer(
+        let decimals = StateWithExtensions::<Mint>::unpack(&mint.data.borrow())?
+            .base
+            .decimals;
+        let ix = spl_token_2022::instruction::transfer_checked(
             token_program.key,
             source.key,
+            mint.key,
             destination.key,
             authority.key,
             &[],
             amount,
+            decimals,
         )?;
-        invoke(&ix, &[source, destination, authority, token_program])
+        invoke(&ix, &[source, mint, destination, authority, token_program])
     }
 
     fn sol_transfer<'a>(
@@ -632,10 +706,11 @@ impl Processor {
             return Err(StakePoolError::FeeTooHigh.into());
         }
 
-        if *token_program_info.key != spl_token::id() {
+        if check_spl_token_program_account(token_program_info.key).is_err() {
             msg!(
-                "Only the SPL token program is currently supported, expected {}, received {}",
+                "Only the SPL token and token-2022 programs are supported, expected {} or {}, received {}",
                 spl_token::id(),
+                spl_token_2022::id(),
                 *token_program_info.key
             );
             return Err(ProgramError::IncorrectProgramId);
@@ -649,10 +724,13 @@ impl Processor {
             return Err(ProgramError::IncorrectProgramId);
         }
 
-        if *pool_mint_info.key
-            != spl_token::state::Account::unpack_from_slice(&manager_fee_info.data.borrow())?.mint
         {
-            return Err(StakePoolError::WrongAccountMint.into());
+            let manager_fee_data = manager_fee_info.data.borrow();
+            let manager_fee_account = StateWithExtensions::<Account>::unpack(&manager_fee_data)?;
+            if *pool_mint_info.key != manager_fee_account.base.mint {
+                return Err(StakePoolError::WrongAccountMint.into());
+            }
+            check_fee_account_extensions(&manager_fee_account)?;
         }
 
         let (stake_deposit_authority, sol_deposit_authority) =
@@ -677,7 +755,12 @@ impl Processor {
             return Err(StakePoolError::InvalidProgramAddress.into());
         }
 
-        let pool_mint = Mint::unpack_from_slice(&pool_mint_info.data.borrow())?;
+        let pool_mint = {
+            let pool_mint_data = pool_mint_info.data.borrow();
+            let pool_mint = StateWithExtensions::<Mint>::unpack(&pool_mint_data)?;
+            check_mint_extensions(&pool_mint)?;
+            pool_mint.base
+        };
 
         if pool_mint.supply != 0 {
             return Err(StakePoolError::NonZeroPoolTokenSupply.into());
@@ -1067,7 +1150,7 @@ impl Processor {
         if stake_pool.preferred_withdraw_validator_vote_address == Some(vote_account_address) {
             stake_pool.preferred_withdraw_validator_vote_address = None;
         }
-        stake_pool.serialize(&mut *stake_pool_info.data.borrow_mut())?;
+        stake_pool.serialize_zeroed(&mut stake_pool_info.data.borrow_mut())?;
 
         Ok(())
     }
@@ -1098,7 +1181,7 @@ impl Processor {
         check_stake_program(stake_program_info.key)?;
         check_account_owner(stake_pool_info, program_id)?;
 
-        let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
+        let mut stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
         if !stake_pool.is_valid() {
             msg!("Expected valid stake pool");
             return Err(StakePoolError::InvalidState.into());
@@ -1109,7 +1192,19 @@ impl Processor {
             program_id,
             stake_pool_info.key,
         )?;
-        stake_pool.check_staker(staker_info)?;
+        // anyone can decrease stake to replenish the instant unstake buffer,
+        // but only from the validator with the most active stake
+        let is_scheduled_decrease = *staker_info.key != stake_pool.staker
+            && lamports <= stake_pool.scheduled_decrease_lamports;
+        if is_scheduled_decrease {
+            msg!(
+                "Decreasing {} of the {} lamports scheduled for the instant unstake buffer",
+                lamports,
+                stake_pool.scheduled_decrease_lamports
+            );
+        } else {
+            stake_pool.check_staker(staker_info)?;
+        }
 
         if stake_pool.last_update_epoch < clock.epoch {
             return Err(StakePoolError::StakeListAndPoolOutOfDate.into());
@@ -1133,6 +1228,16 @@ impl Processor {
             &vote_account_address,
         )?;
 
+        let max_active_stake_lamports = if is_scheduled_decrease {
+            validator_list
+                .iter::<ValidatorStakeInfo>()
+                .filter(|validator| validator.status == StakeStatus::Active)
+                .map(|validator| validator.active_stake_lamports)
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        };
         let maybe_validator_stake_info = validator_list.find_mut::<ValidatorStakeInfo>(
             vote_account_address.as_ref(),
             ValidatorStakeInfo::memcmp_pubkey,
@@ -1148,6 +1253,17 @@ impl Processor {
         if validator_stake_info.transient_stake_lamports > 0 {
             return Err(StakePoolError::TransientAccountInUse.into());
         }
+        if is_scheduled_decrease
+            && validator_stake_info.active_stake_lamports < max_active_stake_lamports
+        {
+            msg!(
+                "Validator {} has {} active lamports, scheduled decreases must come from one with {}",
+                vote_account_address,
+                validator_stake_info.active_stake_lamports,
+                max_active_stake_lamports
+            );
+            return Err(StakePoolError::InvalidScheduledDecreaseValidator.into());
+        }
 
         let transient_stake_bump_seed = check_transient_stake_address(
             program_id,
@@ -1222,6 +1338,14 @@ impl Processor {
         validator_stake_info.transient_stake_lamports = lamports;
         validator_stake_info.transient_seed_suffix_start = transient_stake_seed;
 
+        if is_scheduled_decrease {
+            stake_pool.scheduled_decrease_lamports = stake_pool
+                .scheduled_decrease_lamports
+                .checked_sub(lamports)
+                .ok_or(StakePoolError::CalculationFailure)?;
+            stake_pool.serialize_zeroed(&mut stake_pool_info.data.borrow_mut())?;
+        }
+
         Ok(())
     }
 
@@ -1412,6 +1536,303 @@ impl Processor {
         Ok(())
     }
 
+    /// Processes `Redelegate` instruction.
+    #[inline(never)] // needed due to stack size violation
+    fn process_redelegate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        lamports: u64,
+        source_transient_stake_seed: u64,
+        destination_transient_stake_seed: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let stake_pool_info = next_account_info(account_info_iter)?;
+        let staker_info = next_account_info(account_info_iter)?;
+        let withdraw_authority_info = next_account_info(account_info_iter)?;
+        let validator_list_info = next_account_info(account_info_iter)?;
+        let reserve_stake_account_info = next_account_info(account_info_iter)?;
+        let source_validator_stake_account_info = next_account_info(account_info_iter)?;
+        let source_transient_stake_account_info = next_account_info(account_info_iter)?;
+        let destination_transient_stake_account_info = next_account_info(account_info_iter)?;
+        let destination_validator_stake_account_info = next_account_info(account_info_iter)?;
+        let destination_vote_account_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::from_account_info(clock_info)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let rent = &Rent::from_account_info(rent_info)?;
+        let stake_history_info = next_account_info(account_info_iter)?;
+        let stake_config_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let stake_program_info = next_account_info(account_info_iter)?;
+
+        check_system_program(system_program_info.key)?;
+        check_stake_program(stake_program_info.key)?;
+        check_account_owner(stake_pool_info, program_id)?;
+
+        let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
+        if !stake_pool.is_valid() {
+            msg!("Expected valid stake pool");
+            return Err(StakePoolError::InvalidState.into());
+        }
+
+        stake_pool.check_authority_withdraw(
+            withdraw_authority_info.key,
+            program_id,
+            stake_pool_info.key,
+        )?;
+        stake_pool.check_staker(staker_info)?;
+
+        if stake_pool.last_update_epoch < clock.epoch {
+            return Err(StakePoolError::StakeListAndPoolOutOfDate.into());
+        }
+
+        stake_pool.check_validator_list(validator_list_info)?;
+        stake_pool.check_reserve_stake(reserve_stake_account_info)?;
+        check_account_owner(validator_list_info, program_id)?;
+
+        let mut validator_list_data = validator_list_info.data.borrow_mut();
+        let (header, mut validator_list) =
+            ValidatorListHeader::deserialize_vec(&mut validator_list_data)?;
+        if !header.is_valid() {
+            return Err(StakePoolError::InvalidState.into());
+        }
+
+        let (source_meta, source_stake) = get_stake_state(source_validator_stake_account_info)?;
+        let source_vote_account_address = source_stake.delegation.voter_pubkey;
+        check_validator_stake_address(
+            program_id,
+            stake_pool_info.key,
+            source_validator_stake_account_info.key,
+            &source_vote_account_address,
+        )?;
+        let destination_vote_account_address = destination_vote_account_info.key;
+        if source_vote_account_address == *destination_vote_account_address {
+            msg!(
+                "Source and destination validators are both {}",
+                source_vote_account_address
+            );
+            return Err(StakePoolError::InvalidStakeAccountAddress.into());
+        }
+
+        {
+            let maybe_source_stake_info = validator_list.find_mut::<ValidatorStakeInfo>(
+                source_vote_account_address.as_ref(),
+                ValidatorStakeInfo::memcmp_pubkey,
+            );
+            if maybe_source_stake_info.is_none() {
+                msg!(
+                    "Vote account {} not found in stake pool",
+                    source_vote_account_address
+                );
+                return Err(StakePoolError::ValidatorNotFound.into());
+            }
+            if maybe_source_stake_info.unwrap().transient_stake_lamports > 0 {
+                return Err(StakePoolError::TransientAccountInUse.into());
+            }
+        }
+        {
+            let maybe_destination_stake_info = validator_list.find_mut::<ValidatorStakeInfo>(
+                destination_vote_account_address.as_ref(),
+                ValidatorStakeInfo::memcmp_pubkey,
+            );
+            if maybe_destination_stake_info.is_none() {
+                msg!(
+                    "Vote account {} not found in stake pool",
+                    destination_vote_account_address
+                );
+                return Err(StakePoolError::ValidatorNotFound.into());
+            }
+            let destination_stake_info = maybe_destination_stake_info.unwrap();
+            if destination_stake_info.transient_stake_lamports > 0 {
+                return Err(StakePoolError::TransientAccountInUse.into());
+            }
+            if destination_stake_info.status != StakeStatus::Active {
+                msg!("Validator is marked for removal and no longer allows redelegations");
+                return Err(StakePoolError::ValidatorNotFound.into());
+            }
+        }
+
+        // Check that the destination validator stake account is actually
+        // delegated to the right validator, same as for increases.
+        {
+            check_account_owner(
+                destination_validator_stake_account_info,
+                stake_program_info.key,
+            )?;
+            check_validator_stake_address(
+                program_id,
+                stake_pool_info.key,
+                destination_validator_stake_account_info.key,
+                destination_vote_account_address,
+            )?;
+            let (meta, stake) = get_stake_state(destination_validator_stake_account_info)?;
+            if !stake_is_usable_by_pool(&meta, withdraw_authority_info.key, &stake_pool.lockup) {
+                msg!("Validator stake for {} not usable by pool, must be owned by withdraw authority", destination_vote_account_address);
+                return Err(StakePoolError::WrongStakeState.into());
+            }
+            if stake.delegation.voter_pubkey != *destination_vote_account_address {
+                msg!(
+                    "Validator stake {} not delegated to {}",
+                    destination_validator_stake_account_info.key,
+                    destination_vote_account_address
+                );
+                return Err(StakePoolError::WrongStakeState.into());
+            }
+        }
+
+        let source_transient_stake_bump_seed = check_transient_stake_address(
+            program_id,
+            stake_pool_info.key,
+            source_transient_stake_account_info.key,
+            &source_vote_account_address,
+            source_transient_stake_seed,
+        )?;
+        let source_transient_stake_account_signer_seeds: &[&[_]] = &[
+            TRANSIENT_STAKE_SEED_PREFIX,
+            &source_vote_account_address.to_bytes(),
+            &stake_pool_info.key.to_bytes(),
+            &source_transient_stake_seed.to_le_bytes(),
+            &[source_transient_stake_bump_seed],
+        ];
+        let destination_transient_stake_bump_seed = check_transient_stake_address(
+            program_id,
+            stake_pool_info.key,
+            destination_transient_stake_account_info.key,
+            destination_vote_account_address,
+            destination_transient_stake_seed,
+        )?;
+        let destination_transient_stake_account_signer_seeds: &[&[_]] = &[
+            TRANSIENT_STAKE_SEED_PREFIX,
+            &destination_vote_account_address.to_bytes(),
+            &stake_pool_info.key.to_bytes(),
+            &destination_transient_stake_seed.to_le_bytes(),
+            &[destination_transient_stake_bump_seed],
+        ];
+
+        let stake_rent = rent.minimum_balance(std::mem::size_of::<stake::state::StakeState>());
+        if lamports <= stake_rent || lamports < MINIMUM_ACTIVE_STAKE {
+            msg!(
+                "Need more than {} and at least {} lamports for transient stakes to be rent-exempt and mergeable, {} provided",
+                stake_rent,
+                MINIMUM_ACTIVE_STAKE,
+                lamports
+            );
+            return Err(ProgramError::AccountNotRentExempt);
+        }
+
+        let remaining_lamports = source_validator_stake_account_info
+            .lamports()
+            .checked_sub(lamports)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        let required_lamports = minimum_stake_lamports(&source_meta);
+        if remaining_lamports < required_lamports {
+            msg!("Need at least {} lamports in the source stake account after redelegation, {} requested, {} is the current possible maximum",
+                required_lamports,
+                lamports,
+                source_validator_stake_account_info.lamports().checked_sub(required_lamports).ok_or(StakePoolError::CalculationFailure)?
+            );
+            return Err(ProgramError::InsufficientFunds);
+        }
+
+        // the reserve fronts the destination stake until the source stake is
+        // deactivated and merged back into it, and the rent exemption of the
+        // destination transient stake account is withdrawn after its merge
+        let destination_lamports = lamports.saturating_add(stake_rent);
+        if reserve_stake_account_info
+            .lamports()
+            .saturating_sub(destination_lamports)
+            <= stake_rent
+        {
+            let max_split_amount = reserve_stake_account_info
+                .lamports()
+                .saturating_sub(2 * stake_rent);
+            msg!(
+                "Reserve stake does not have enough lamports for redelegation, must be less than {}, {} requested",
+                max_split_amount,
+                lamports
+            );
+            return Err(ProgramError::InsufficientFunds);
+        }
+
+        create_transient_stake_account(
+            source_transient_stake_account_info.clone(),
+            source_transient_stake_account_signer_seeds,
+            system_program_info.clone(),
+        )?;
+
+        // split source stake into its transient stake account and deactivate it
+        Self::stake_split(
+            stake_pool_info.key,
+            source_validator_stake_account_info.clone(),
+            withdraw_authority_info.clone(),
+            AUTHORITY_WITHDRAW,
+            stake_pool.stake_withdraw_bump_seed,
+            lamports,
+            source_transient_stake_account_info.clone(),
+        )?;
+        Self::stake_deactivate(
+            source_transient_stake_account_info.clone(),
+            clock_info.clone(),
+            withdraw_authority_info.clone(),
+            stake_pool_info.key,
+            AUTHORITY_WITHDRAW,
+            stake_pool.stake_withdraw_bump_seed,
+        )?;
+
+        create_transient_stake_account(
+            destination_transient_stake_account_info.clone(),
+            destination_transient_stake_account_signer_seeds,
+            system_program_info.clone(),
+        )?;
+
+        // split reserve stake into the destination transient stake account and
+        // activate it to the destination validator
+        Self::stake_split(
+            stake_pool_info.key,
+            reserve_stake_account_info.clone(),
+            withdraw_authority_info.clone(),
+            AUTHORITY_WITHDRAW,
+            stake_pool.stake_withdraw_bump_seed,
+            destination_lamports,
+            destination_transient_stake_account_info.clone(),
+        )?;
+        Self::stake_delegate(
+            destination_transient_stake_account_info.clone(),
+            destination_vote_account_info.clone(),
+            clock_info.clone(),
+            stake_history_info.clone(),
+            stake_config_info.clone(),
+            withdraw_authority_info.clone(),
+            stake_pool_info.key,
+            AUTHORITY_WITHDRAW,
+            stake_pool.stake_withdraw_bump_seed,
+        )?;
+
+        let source_stake_info = validator_list
+            .find_mut::<ValidatorStakeInfo>(
+                source_vote_account_address.as_ref(),
+                ValidatorStakeInfo::memcmp_pubkey,
+            )
+            .ok_or(StakePoolError::ValidatorNotFound)?;
+        source_stake_info.active_stake_lamports = source_stake_info
+            .active_stake_lamports
+            .checked_sub(lamports)
+            .ok_or(StakePoolError::CalculationFailure)?;
+        source_stake_info.transient_stake_lamports = lamports;
+        source_stake_info.transient_seed_suffix_start = source_transient_stake_seed;
+
+        let destination_stake_info = validator_list
+            .find_mut::<ValidatorStakeInfo>(
+                destination_vote_account_address.as_ref(),
+                ValidatorStakeInfo::memcmp_pubkey,
+            )
+            .ok_or(StakePoolError::ValidatorNotFound)?;
+        destination_stake_info.transient_stake_lamports = destination_lamports;
+        destination_stake_info.transient_seed_suffix_start = destination_transient_stake_seed;
+
+        Ok(())
+    }
+
     /// Process `SetPreferredValidator` instruction
     #[inline(never)] // needed due to stack size violation
     fn process_set_preferred_validator(
@@ -1471,7 +1892,7 @@ impl Processor {
                 stake_pool.preferred_withdraw_validator_vote_address = vote_account_address
             }
         };
-        stake_pool.serialize(&mut *stake_pool_info.data.borrow_mut())?;
+        stake_pool.serialize_zeroed(&mut stake_pool_info.data.borrow_mut())?;
         Ok(())
     }
 
@@ -1492,7 +1913,7 @@ impl Processor {
         let clock = &Clock::from_account_info(clock_info)?;
         let stake_history_info = next_account_info(account_info_iter)?;
         let stake_program_info = next_account_info(account_info_iter)?;
-        let validator_stake_accounts = account_info_iter.as_slice();
+        let mut validator_stake_accounts = account_info_iter.as_slice();
 
         check_account_owner(stake_pool_info, program_id)?;
         let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
@@ -1508,6 +1929,39 @@ impl Processor {
         stake_pool.check_reserve_stake(reserve_stake_info)?;
         check_stake_program(stake_program_info.key)?;
 
+        // the validator history comes after the pairs of stake accounts, and
+        // must be given if the pool has one, since anyone can send updates
+        let validator_history_info = match stake_pool.validator_history {
+            Some(validator_history) => match validator_stake_accounts.split_last() {
+                Some((last_info, stake_accounts)) if *last_info.key == validator_history => {
+                    validator_stake_accounts = stake_accounts;
+                    check_account_owner(last_info, program_id)?;
+                    Some(last_info)
+                }
+                _ => {
+                    msg!(
+                        "Validator history account {} of the stake pool is missing",
+                        validator_history
+                    );
+                    return Err(StakePoolError::InvalidValidatorHistory.into());
+                }
+            },
+            None => None,
+        };
+        let mut validator_history_data = validator_history_info
+            .map(|validator_history_info| validator_history_info.data.borrow_mut());
+        let mut validator_history = match validator_history_data.as_mut() {
+            Some(validator_history_data) => {
+                let (header, big_vec) =
+                    ValidatorHistoryHeader::deserialize_vec(validator_history_data)?;
+                if !header.is_valid() || header.stake_pool != *stake_pool_info.key {
+                    return Err(StakePoolError::InvalidValidatorHistory.into());
+                }
+                Some((header, big_vec))
+            }
+            None => None,
+        };
+
         if validator_stake_accounts
             .len()
             .checked_rem(2)
@@ -1577,6 +2031,11 @@ impl Processor {
             //  * deactivating -> nothing, just account its lamports
             //  * inactive -> merge into reserve stake
             //  * not a stake -> ignore
+            // A redelegation leaves one transient stake on each validator: the
+            // deactivating source stake goes back to the reserve once inactive,
+            // and the activating destination stake, fronted by the reserve,
+            // merges into the destination validator stake once active, with
+            // its rent-exemption withdrawn back to the reserve.
             match transient_stake_state {
                 Some(stake::state::StakeState::Initialized(meta)) => {
                     if stake_is_usable_by_pool(
@@ -1636,6 +2095,11 @@ impl Processor {
                                 // now this entry can be removed totally
                                 validator_stake_record.status = StakeStatus::ReadyForRemoval;
                             }
+                        } else if stake.delegation.deactivation_epoch != Epoch::MAX {
+                            // split from an active stake, so it keeps its old
+                            // activation epoch, but can't merge until inactive
+                            msg!("Transient stake deactivating, not ready to be merged into the reserve");
+                            transient_stake_lamports = account_stake;
                         } else if stake.delegation.activation_epoch < clock.epoch {
                             if let Some(stake::state::StakeState::Stake(_, validator_stake)) =
                                 validator_stake_state
@@ -1742,6 +2206,29 @@ impl Processor {
                 }
             }
 
+            // rewards are only told apart when no stake moved since the
+            // update of the previous epoch
+            if let Some((header, big_vec)) = validator_history.as_mut() {
+                if validator_stake_record.status == StakeStatus::Active
+                    && validator_stake_record.last_update_epoch.saturating_add(1) == clock.epoch
+                    && validator_stake_record.transient_stake_lamports == 0
+                    && transient_stake_lamports == 0
+                    && active_stake_lamports > 0
+                {
+                    record_validator_rewards(
+                        header,
+                        big_vec,
+                        &validator_stake_record.vote_account_address,
+                        clock.epoch,
+                        validator_stake_record
+                            .active_stake_lamports
+                            .saturating_add(MINIMUM_ACTIVE_STAKE),
+                        active_stake_lamports
+                            .saturating_sub(validator_stake_record.active_stake_lamports),
+                    )?;
+                }
+            }
+
             validator_stake_record.last_update_epoch = clock.epoch;
             validator_stake_record.active_stake_lamports = active_stake_lamports;
             validator_stake_record.transient_stake_lamports = transient_stake_lamports;
@@ -1798,16 +2285,18 @@ impl Processor {
         let reserve_stake = try_from_slice_unchecked::<stake::state::StakeState>(
             &reserve_stake_info.data.borrow(),
         )?;
-        let mut total_lamports = if let stake::state::StakeState::Initialized(meta) = reserve_stake
-        {
-            reserve_stake_info
-                .lamports()
-                .checked_sub(minimum_reserve_lamports(&meta))
-                .ok_or(StakePoolError::CalculationFailure)?
-        } else {
-            msg!("Reserve stake account in unknown state, aborting");
-            return Err(StakePoolError::WrongStakeState.into());
-        };
+        let available_reserve_lamports =
+            if let stake::state::StakeState::Initialized(meta) = reserve_stake {
+                reserve_stake_info
+                    .lamports()
+                    .checked_sub(minimum_reserve_lamports(&meta))
+                    .ok_or(StakePoolError::CalculationFailure)?
+            } else {
+                msg!("Reserve stake account in unknown state, aborting");
+                return Err(StakePoolError::WrongStakeState.into());
+            };
+        let mut total_lamports = available_reserve_lamports;
+        let mut transient_stake_lamports = 0u64;
         for validator_stake_record in validator_list.iter::<ValidatorStakeInfo>() {
             if validator_stake_record.last_update_epoch < clock.epoch {
                 return Err(StakePoolError::StakeListOutOfDate.into());
@@ -1815,6 +2304,9 @@ impl Processor {
             total_lamports = total_lamports
                 .checked_add(validator_stake_record.stake_lamports())
                 .ok_or(StakePoolError::CalculationFailure)?;
+            transient_stake_lamports = transient_stake_lamports
+                .checked_add(validator_stake_record.transient_stake_lamports)
+                .ok_or(StakePoolError::CalculationFailure)?;
         }
 
         let reward_lamports = total_lamports.saturating_sub(previous_lamports);
@@ -1854,16 +2346,23 @@ impl Processor {
                 stake_pool.sol_withdrawal_fee = fee;
                 stake_pool.next_sol_withdrawal_fee = None;
             }
+            if let Some(instant_unstake) = stake_pool.next_instant_unstake {
+                stake_pool.instant_unstake = Some(instant_unstake);
+                stake_pool.next_instant_unstake = None;
+            }
             stake_pool.last_update_epoch = clock.epoch;
             stake_pool.last_epoch_total_lamports = previous_lamports;
             stake_pool.last_epoch_pool_token_supply = previous_pool_token_supply;
         }
         stake_pool.total_lamports = total_lamports;
+        stake_pool.scheduled_decrease_lamports = stake_pool
+            .calc_instant_unstake_shortfall(available_reserve_lamports, transient_stake_lamports)
+            .ok_or(StakePoolError::CalculationFailure)?;
 
-        let pool_mint = Mint::unpack_from_slice(&pool_mint_info.data.borrow())?;
+        let pool_mint = StateWithExtensions::<Mint>::unpack(&pool_mint_info.data.borrow())?.base;
         stake_pool.pool_token_supply = pool_mint.supply;
 
-        stake_pool.serialize(&mut *stake_pool_info.data.borrow_mut())?;
+        stake_pool.serialize_zeroed(&mut stake_pool_info.data.borrow_mut())?;
 
         Ok(())
     }
@@ -2147,7 +2646,7 @@ impl Processor {
             .total_lamports
             .checked_add(total_deposit_lamports)
             .ok_or(StakePoolError::CalculationFailure)?;
-        stake_pool.serialize(&mut *stake_pool_info.data.borrow_mut())?;
+        stake_pool.serialize_zeroed(&mut stake_pool_info.data.borrow_mut())?;
 
         validator_stake_info.active_stake_lamports = post_validator_stake
             .delegation
@@ -2292,7 +2791,7 @@ impl Processor {
             .total_lamports
             .checked_add(deposit_lamports)
             .ok_or(StakePoolError::CalculationFailure)?;
-        stake_pool.serialize(&mut *stake_pool_info.data.borrow_mut())?;
+        stake_pool.serialize_zeroed(&mut stake_pool_info.data.borrow_mut())?;
 
         Ok(())
     }
@@ -2502,6 +3001,7 @@ impl Processor {
             Self::token_transfer(
                 token_program_info.clone(),
                 burn_from_pool_info.clone(),
+                pool_mint_info.clone(),
                 manager_fee_info.clone(),
                 user_transfer_authority_info.clone(),
                 pool_tokens_fee,
@@ -2516,7 +3016,7 @@ impl Processor {
             .total_lamports
             .checked_sub(withdraw_lamports)
             .ok_or(StakePoolError::CalculationFailure)?;
-        stake_pool.serialize(&mut *stake_pool_info.data.borrow_mut())?;
+        stake_pool.serialize_zeroed(&mut stake_pool_info.data.borrow_mut())?;
 
         if let Some((validator_list_item, withdrawing_from_transient_stake_account)) =
             validator_list_item_info
@@ -2589,6 +3089,20 @@ impl Processor {
             return Err(StakePoolError::StakeListAndPoolOutOfDate.into());
         }
 
+        let stake_state = try_from_slice_unchecked::<stake::state::StakeState>(
+            &reserve_stake_info.data.borrow(),
+        )?;
+        let minimum_reserve_lamports =
+            if let stake::state::StakeState::Initialized(meta) = stake_state {
+                minimum_reserve_lamports(&meta)
+            } else {
+                msg!("Reserve stake account not in intialized state");
+                return Err(StakePoolError::WrongStakeState.into());
+            };
+        let available_reserve_lamports = reserve_stake_info
+            .lamports()
+            .saturating_sub(minimum_reserve_lamports);
+
         // To prevent a faulty manager fee account from preventing withdrawals
         // if the token program does not own the account, or if the account is not initialized
         let pool_tokens_fee = if stake_pool.manager_fee_account == *burn_from_pool_info.key
@@ -2598,6 +3112,12 @@ impl Processor {
         } else {
             stake_pool
                 .calc_pool_tokens_sol_withdrawal_fee(pool_tokens)
+                .and_then(|fee| {
+                    fee.checked_add(stake_pool.calc_pool_tokens_instant_unstake_fee(
+                        pool_tokens,
+                        available_reserve_lamports,
+                    )?)
+                })
                 .ok_or(StakePoolError::CalculationFailure)?
         };
         let pool_tokens_burnt = pool_tokens
@@ -2612,25 +3132,14 @@ impl Processor {
             return Err(StakePoolError::WithdrawalTooSmall.into());
         }
 
-        let new_reserve_lamports = reserve_stake_info
-            .lamports()
-            .saturating_sub(withdraw_lamports);
-        let stake_state = try_from_slice_unchecked::<stake::state::StakeState>(
-            &reserve_stake_info.data.borrow(),
-        )?;
-        if let stake::state::StakeState::Initialized(meta) = stake_state {
-            let minimum_reserve_lamports = minimum_reserve_lamports(&meta);
-            if new_reserve_lamports < minimum_reserve_lamports {
-                msg!("Attempting to withdraw {} lamports, maximum possible SOL withdrawal is {} lamports",
-                    withdraw_lamports,
-                    reserve_stake_info.lamports().saturating_sub(minimum_reserve_lamports)
-                );
-                return Err(StakePoolError::SolWithdrawalTooLarge.into());
-            }
-        } else {
-            msg!("Reserve stake account not in intialized state");
-            return Err(StakePoolError::WrongStakeState.into());
-        };
+        if withdraw_lamports > available_reserve_lamports {
+            msg!(
+                "Attempting to withdraw {} lamports, maximum possible SOL withdrawal is {} lamports",
+                withdraw_lamports,
+                available_reserve_lamports
+            );
+            return Err(StakePoolError::SolWithdrawalTooLarge.into());
+        }
 
         Self::token_burn(
             token_program_info.clone(),
@@ -2644,6 +3153,7 @@ impl Processor {
             Self::token_transfer(
                 token_program_info.clone(),
                 burn_from_pool_info.clone(),
+                pool_mint_info.clone(),
                 manager_fee_info.clone(),
                 user_transfer_authority_info.clone(),
                 pool_tokens_fee,
@@ -2671,7 +3181,265 @@ impl Processor {
             .total_lamports
             .checked_sub(withdraw_lamports)
             .ok_or(StakePoolError::CalculationFailure)?;
-        stake_pool.serialize(&mut *stake_pool_info.data.borrow_mut())?;
+        stake_pool.serialize_zeroed(&mut stake_pool_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    /// Processes `InitializeValidatorHistory` instruction.
+    #[inline(never)] // needed to avoid stack size violation
+    fn process_initialize_validator_history(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let stake_pool_info = next_account_info(account_info_iter)?;
+        let manager_info = next_account_info(account_info_iter)?;
+        let validator_list_info = next_account_info(account_info_iter)?;
+        let validator_history_info = next_account_info(account_info_iter)?;
+        let rent = Rent::get()?;
+
+        check_account_owner(stake_pool_info, program_id)?;
+        let mut stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
+        if !stake_pool.is_valid() {
+            return Err(StakePoolError::InvalidState.into());
+        }
+        stake_pool.check_manager(manager_info)?;
+        stake_pool.check_validator_list(validator_list_info)?;
+
+        check_account_owner(validator_list_info, program_id)?;
+        let mut validator_list_data = validator_list_info.data.borrow_mut();
+        let (validator_list_header, _) =
+            ValidatorListHeader::deserialize_vec(&mut validator_list_data)?;
+        if !validator_list_header.is_valid() {
+            return Err(StakePoolError::InvalidState.into());
+        }
+
+        check_account_owner(validator_history_info, program_id)?;
+        let mut validator_history = try_from_slice_unchecked::<ValidatorHistoryList>(
+            &validator_history_info.data.borrow(),
+        )?;
+        if !validator_history.header.is_uninitialized() {
+            msg!("Provided validator history already in use");
+            return Err(StakePoolError::AlreadyInUse.into());
+        }
+
+        let max_validators =
+            ValidatorHistoryList::calculate_max_validators(validator_history_info.data_len());
+        if max_validators < validator_list_header.max_validators as usize {
+            msg!(
+                "Validator history has room for {} validators, the pool can have {}",
+                max_validators,
+                validator_list_header.max_validators
+            );
+            return Err(StakePoolError::UnexpectedValidatorListAccountSize.into());
+        }
+        if !rent.is_exempt(
+            validator_history_info.lamports(),
+            validator_history_info.data_len(),
+        ) {
+            msg!("Validator history not rent-exempt");
+            return Err(ProgramError::AccountNotRentExempt);
+        }
+
+        validator_history.header = ValidatorHistoryHeader {
+            account_type: AccountType::ValidatorHistory,
+            stake_pool: *stake_pool_info.key,
+            max_validators: max_validators as u32,
+        };
+        validator_history.validators.clear();
+        validator_history.serialize(&mut *validator_history_info.data.borrow_mut())?;
+
+        stake_pool.validator_history = Some(*validator_history_info.key);
+        stake_pool.serialize_zeroed(&mut stake_pool_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes `ReallocStakePool` instruction.
+    #[inline(never)] // needed to avoid stack size violation
+    fn process_realloc_stake_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let stake_pool_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        check_system_program(system_program_info.key)?;
+        check_account_owner(stake_pool_info, program_id)?;
+        // older pools cannot be deserialized yet, so only check the account type
+        if stake_pool_info.data.borrow().first() != Some(&(AccountType::StakePool as u8)) {
+            msg!("Expected valid stake pool");
+            return Err(StakePoolError::InvalidState.into());
+        }
+
+        let stake_pool_len = get_packed_len::<StakePool>();
+        if stake_pool_info.data_len() >= stake_pool_len {
+            return Ok(());
+        }
+        msg!(
+            "Stake pool needs realloc, +{} bytes",
+            stake_pool_len.saturating_sub(stake_pool_info.data_len())
+        );
+        // older programs left stale bytes after the fields they serialized, so
+        // zero everything past them, which deserializes to `None` and 0, the
+        // defaults of the new fields
+        let legacy_len = StakePool::legacy_serialized_len(&stake_pool_info.data.borrow())?;
+        stake_pool_info.realloc(stake_pool_len, true)?;
+        stake_pool_info.data.borrow_mut()[legacy_len..].fill(0);
+        let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
+        if !stake_pool.is_valid() {
+            return Err(StakePoolError::InvalidState.into());
+        }
+
+        let rent = Rent::get()?;
+        let lamports_diff = rent
+            .minimum_balance(stake_pool_len)
+            .saturating_sub(stake_pool_info.lamports());
+        if lamports_diff > 0 {
+            invoke(
+                &system_instruction::transfer(payer_info.key, stake_pool_info.key, lamports_diff),
+                &[
+                    payer_info.clone(),
+                    stake_pool_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Processes `CreateTokenMetadata` instruction.
+    #[inline(never)]
+    fn process_create_pool_token_metadata(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let stake_pool_info = next_account_info(account_info_iter)?;
+        let manager_info = next_account_info(account_info_iter)?;
+        let withdraw_authority_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let metadata_info = next_account_info(account_info_iter)?;
+        let mpl_token_metadata_program_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        if !payer_info.is_signer {
+            msg!("Payer did not sign metadata creation");
+            return Err(StakePoolError::SignatureMissing.into());
+        }
+
+        check_system_program(system_program_info.key)?;
+        check_account_owner(payer_info, &system_program::id())?;
+        check_account_owner(stake_pool_info, program_id)?;
+        check_mpl_metadata_program(mpl_token_metadata_program_info.key)?;
+
+        let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
+        if !stake_pool.is_valid() {
+            return Err(StakePoolError::InvalidState.into());
+        }
+
+        stake_pool.check_manager(manager_info)?;
+        stake_pool.check_authority_withdraw(
+            withdraw_authority_info.key,
+            program_id,
+            stake_pool_info.key,
+        )?;
+        stake_pool.check_mint(pool_mint_info)?;
+        check_mpl_metadata_account_address(metadata_info.key, &stake_pool.pool_mint)?;
+
+        // the mint authority and update authority of the pool token metadata
+        // are both the pool withdraw authority
+        let new_metadata_instruction = create_metadata_accounts_v3(
+            *mpl_token_metadata_program_info.key,
+            *metadata_info.key,
+            *pool_mint_info.key,
+            *withdraw_authority_info.key,
+            *payer_info.key,
+            *withdraw_authority_info.key,
+            name,
+            symbol,
+            uri,
+        );
+
+        let authority_signature_seeds = [
+            &stake_pool_info.key.to_bytes()[..32],
+            AUTHORITY_WITHDRAW,
+            &[stake_pool.stake_withdraw_bump_seed],
+        ];
+        let signers = &[&authority_signature_seeds[..]];
+
+        invoke_signed(
+            &new_metadata_instruction,
+            &[
+                metadata_info.clone(),
+                pool_mint_info.clone(),
+                withdraw_authority_info.clone(),
+                payer_info.clone(),
+                withdraw_authority_info.clone(),
+                system_program_info.clone(),
+            ],
+            signers,
+        )?;
+
+        Ok(())
+    }
+
+    /// Processes `UpdateTokenMetadata` instruction.
+    #[inline(never)]
+    fn process_update_pool_token_metadata(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let stake_pool_info = next_account_info(account_info_iter)?;
+        let manager_info = next_account_info(account_info_iter)?;
+        let withdraw_authority_info = next_account_info(account_info_iter)?;
+        let metadata_info = next_account_info(account_info_iter)?;
+        let mpl_token_metadata_program_info = next_account_info(account_info_iter)?;
+
+        check_account_owner(stake_pool_info, program_id)?;
+        check_mpl_metadata_program(mpl_token_metadata_program_info.key)?;
+
+        let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())?;
+        if !stake_pool.is_valid() {
+            return Err(StakePoolError::InvalidState.into());
+        }
+
+        stake_pool.check_manager(manager_info)?;
+        stake_pool.check_authority_withdraw(
+            withdraw_authority_info.key,
+            program_id,
+            stake_pool_info.key,
+        )?;
+        check_mpl_metadata_account_address(metadata_info.key, &stake_pool.pool_mint)?;
+
+        let update_metadata_accounts_instruction = update_metadata_accounts_v2(
+            *mpl_token_metadata_program_info.key,
+            *metadata_info.key,
+            *withdraw_authority_info.key,
+            name,
+            symbol,
+            uri,
+        );
+
+        let authority_signature_seeds = [
+            &stake_pool_info.key.to_bytes()[..32],
+            AUTHORITY_WITHDRAW,
+            &[stake_pool.stake_withdraw_bump_seed],
+        ];
+        let signers = &[&authority_signature_seeds[..]];
+
+        invoke_signed(
+            &update_metadata_accounts_instruction,
+            &[metadata_info.clone(), withdraw_authority_info.clone()],
+            signers,
+        )?;
 
         Ok(())
     }
@@ -2698,16 +3466,19 @@ impl Processor {
             return Err(StakePoolError::SignatureMissing.into());
         }
 
-        if stake_pool.pool_mint
-            != spl_token::state::Account::unpack_from_slice(&new_manager_fee_info.data.borrow())?
-                .mint
         {
-            return Err(StakePoolError::WrongAccountMint.into());
+            let new_manager_fee_data = new_manager_fee_info.data.borrow();
+            let new_manager_fee_account =
+                StateWithExtensions::<Account>::unpack(&new_manager_fee_data)?;
+            if stake_pool.pool_mint != new_manager_fee_account.base.mint {
+                return Err(StakePoolError::WrongAccountMint.into());
+            }
+            check_fee_account_extensions(&new_manager_fee_account)?;
         }
 
         stake_pool.manager = *new_manager_info.key;
         stake_pool.manager_fee_account = *new_manager_fee_info.key;
-        stake_pool.serialize(&mut *stake_pool_info.data.borrow_mut())?;
+        stake_pool.serialize_zeroed(&mut stake_pool_info.data.borrow_mut())?;
         Ok(())
     }
 
@@ -2736,7 +3507,7 @@ impl Processor {
 
         fee.check_too_high()?;
         stake_pool.update_fee(&fee)?;
-        stake_pool.serialize(&mut *stake_pool_info.data.borrow_mut())?;
+        stake_pool.serialize_zeroed(&mut stake_pool_info.data.borrow_mut())?;
         Ok(())
     }
 
@@ -2760,7 +3531,7 @@ impl Processor {
             return Err(StakePoolError::SignatureMissing.into());
         }
         stake_pool.staker = *new_staker_info.key;
-        stake_pool.serialize(&mut *stake_pool_info.data.borrow_mut())?;
+        stake_pool.serialize_zeroed(&mut stake_pool_info.data.borrow_mut())?;
         Ok(())
     }
 
@@ -2794,7 +3565,7 @@ impl Processor {
             FundingType::SolDeposit => stake_pool.sol_deposit_authority = new_authority,
             FundingType::SolWithdraw => stake_pool.sol_withdraw_authority = new_authority,
         }
-        stake_pool.serialize(&mut *stake_pool_info.data.borrow_mut())?;
+        stake_pool.serialize_zeroed(&mut stake_pool_info.data.borrow_mut())?;
         Ok(())
     }
 
@@ -2916,6 +3687,36 @@ impl Processor {
                 msg!("Instruction: WithdrawSol");
                 Self::process_withdraw_sol(program_id, accounts, pool_tokens)
             }
+            StakePoolInstruction::Redelegate {
+                lamports,
+                source_transient_stake_seed,
+                destination_transient_stake_seed,
+            } => {
+                msg!("Instruction: Redelegate");
+                Self::process_redelegate(
+                    program_id,
+                    accounts,
+                    lamports,
+                    source_transient_stake_seed,
+                    destination_transient_stake_seed,
+                )
+            }
+            StakePoolInstruction::CreateTokenMetadata { name, symbol, uri } => {
+                msg!("Instruction: CreateTokenMetadata");
+                Self::process_create_pool_token_metadata(program_id, accounts, name, symbol, uri)
+            }
+            StakePoolInstruction::UpdateTokenMetadata { name, symbol, uri } => {
+                msg!("Instruction: UpdateTokenMetadata");
+                Self::process_update_pool_token_metadata(program_id, accounts, name, symbol, uri)
+            }
+            StakePoolInstruction::InitializeValidatorHistory => {
+                msg!("Instruction: InitializeValidatorHistory");
+                Self::process_initialize_validator_history(program_id, accounts)
+            }
+            StakePoolInstruction::ReallocStakePool => {
+                msg!("Instruction: ReallocStakePool");
+                Self::process_realloc_stake_pool(program_id, accounts)
+            }
         }
     }
 }
@@ -2964,6 +3765,11 @@ impl PrintProgramError for StakePoolError {
             StakePoolError::TransientAccountInUse => msg!("Error: Provided validator stake account already has a transient stake account in use"),
             StakePoolError::InvalidSolWithdrawAuthority => msg!("Error: Provided sol withdraw authority does not match the program's"),
             StakePoolError::SolWithdrawalTooLarge => msg!("Error: Too much SOL withdrawn from the stake pool's reserve account"),
+            StakePoolError::InvalidMetadataAccount => msg!("Error: Metadata account derived from pool mint account does not match the one passed to program"),
+            StakePoolError::InvalidValidatorHistory => msg!("Error: Validator history account does not match the one of the stake pool"),
+            StakePoolError::UnsupportedMintExtension => msg!("Error: Pool mint has an extension that is not supported by the stake pool"),
+            StakePoolError::UnsupportedFeeAccountExtension => msg!("Error: Manager fee account has an extension that is not supported by the stake pool"),
+            StakePoolError::InvalidScheduledDecreaseValidator => msg!("Error: Scheduled decreases must come from the validator with the most active stake"),
         }
     }
 }
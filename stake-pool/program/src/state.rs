@@ -19,7 +19,10 @@ use {
         stake::state::Lockup,
     },
     spl_math::checked_ceil_div::CheckedCeilDiv,
-    spl_token::state::{Account, AccountState},
+    spl_token_2022::{
+        extension::{ExtensionType, StateWithExtensions},
+        state::{Account, AccountState, Mint},
+    },
     std::{convert::TryFrom, fmt, matches},
 };
 
@@ -32,6 +35,8 @@ pub enum AccountType {
     StakePool,
     /// Validator stake list
     ValidatorList,
+    /// Rolling history of validator rewards
+    ValidatorHistory,
 }
 
 impl Default for AccountType {
@@ -40,6 +45,10 @@ impl Default for AccountType {
     }
 }
 
+/// Size of stake pool accounts created before instant unstakes and validator
+/// histories were added, which must be grown with `ReallocStakePool` before use
+pub const LEGACY_STAKE_POOL_LEN: usize = 611;
+
 /// Initialized program details.
 #[repr(C)]
 #[derive(Clone, Debug, Default, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
@@ -153,6 +162,21 @@ pub struct StakePool {
 
     /// Last epoch's total lamports, used only for APR estimation
     pub last_epoch_total_lamports: u64,
+
+    /// Instant unstake configuration, charging a dynamic fee on SOL
+    /// withdrawals to keep a liquidity buffer in the reserve
+    pub instant_unstake: Option<InstantUnstake>,
+
+    /// Instant unstake configuration for the next epoch
+    pub next_instant_unstake: Option<InstantUnstake>,
+
+    /// Lamports that anyone may decrease from validators this epoch to
+    /// replenish the instant unstake buffer, set on `UpdateStakePoolBalance`
+    pub scheduled_decrease_lamports: u64,
+
+    /// Account recording the rewards of each validator over the last epochs,
+    /// updated on `UpdateValidatorListBalance` if set
+    pub validator_history: Option<Pubkey>,
 }
 impl StakePool {
     /// calculate the pool tokens that should be minted for a deposit of `stake_lamports`
@@ -197,6 +221,49 @@ impl StakePool {
         u64::try_from(self.sol_withdrawal_fee.apply(pool_tokens)?).ok()
     }
 
+    /// calculate pool tokens to be deducted as instant unstake fees, given the
+    /// lamports available in the reserve before the withdrawal
+    #[inline]
+    pub fn calc_pool_tokens_instant_unstake_fee(
+        &self,
+        pool_tokens: u64,
+        available_reserve_lamports: u64,
+    ) -> Option<u64> {
+        if let Some(instant_unstake) = &self.instant_unstake {
+            let withdraw_lamports = self.calc_lamports_withdraw_amount(pool_tokens)?;
+            let fee = instant_unstake.fee(
+                self.total_lamports,
+                available_reserve_lamports.saturating_sub(withdraw_lamports),
+            )?;
+            u64::try_from(fee.apply(pool_tokens)?).ok()
+        } else {
+            Some(0)
+        }
+    }
+
+    /// calculate the lamports missing from the reserve to fill the instant
+    /// unstake buffer, 0 if instant unstakes are disabled
+    ///
+    /// Lamports already in transient stake accounts are not counted as
+    /// missing, so that a decrease still deactivating is not scheduled twice.
+    #[inline]
+    pub fn calc_instant_unstake_shortfall(
+        &self,
+        available_reserve_lamports: u64,
+        transient_stake_lamports: u64,
+    ) -> Option<u64> {
+        if let Some(instant_unstake) = &self.instant_unstake {
+            Some(
+                instant_unstake
+                    .target_buffer_lamports(self.total_lamports)?
+                    .saturating_sub(available_reserve_lamports)
+                    .saturating_sub(transient_stake_lamports),
+            )
+        } else {
+            Some(0)
+        }
+    }
+
     /// calculate pool tokens to be deducted as stake deposit fees
     #[inline]
     pub fn calc_pool_tokens_stake_deposit_fee(&self, pool_tokens_minted: u64) -> Option<u64> {
@@ -289,15 +356,16 @@ impl StakePool {
         &self,
         manager_fee_info: &AccountInfo,
     ) -> Result<(), ProgramError> {
-        let token_account = Account::unpack(&manager_fee_info.data.borrow())?;
+        let account_data = manager_fee_info.data.borrow();
+        let token_account = StateWithExtensions::<Account>::unpack(&account_data)?;
         if manager_fee_info.owner != &self.token_program_id
-            || token_account.state != AccountState::Initialized
-            || token_account.mint != self.pool_mint
+            || token_account.base.state != AccountState::Initialized
+            || token_account.base.mint != self.pool_mint
         {
             msg!("Manager fee account is not owned by token program, is not initialized, or does not match stake pool's mint");
             return Err(StakePoolError::InvalidFeeAccount.into());
         }
-        Ok(())
+        check_fee_account_extensions(&token_account)
     }
 
     /// Checks that the withdraw authority is valid
@@ -458,6 +526,59 @@ impl StakePool {
         self.account_type == AccountType::Uninitialized
     }
 
+    /// Serialize the StakePool into account data, zeroing the bytes after it.
+    /// Cleared optional fields serialize shorter, and the stale bytes they
+    /// leave behind would otherwise be read by fields appended later.
+    pub fn serialize_zeroed(&self, data: &mut [u8]) -> Result<(), ProgramError> {
+        let mut remaining = data;
+        self.serialize(&mut remaining)?;
+        remaining.fill(0);
+        Ok(())
+    }
+
+    /// Length of the fields serialized in pools of `LEGACY_STAKE_POOL_LEN`.
+    /// Programs of that time did not zero the bytes after them, so anything
+    /// past this length is stale.
+    pub fn legacy_serialized_len(data: &[u8]) -> Result<usize, ProgramError> {
+        let mut remaining = data;
+        let buf = &mut remaining;
+        AccountType::deserialize(buf)?;
+        // manager, staker, stake_deposit_authority
+        for _ in 0..3 {
+            Pubkey::deserialize(buf)?;
+        }
+        u8::deserialize(buf)?;
+        // validator_list, reserve_stake, pool_mint, manager_fee_account,
+        // token_program_id
+        for _ in 0..5 {
+            Pubkey::deserialize(buf)?;
+        }
+        // total_lamports, pool_token_supply, last_update_epoch
+        for _ in 0..3 {
+            u64::deserialize(buf)?;
+        }
+        Lockup::deserialize(buf)?;
+        Fee::deserialize(buf)?;
+        Option::<Fee>::deserialize(buf)?;
+        Option::<Pubkey>::deserialize(buf)?;
+        Option::<Pubkey>::deserialize(buf)?;
+        Fee::deserialize(buf)?;
+        Fee::deserialize(buf)?;
+        Option::<Fee>::deserialize(buf)?;
+        u8::deserialize(buf)?;
+        Option::<Pubkey>::deserialize(buf)?;
+        Fee::deserialize(buf)?;
+        u8::deserialize(buf)?;
+        Option::<Pubkey>::deserialize(buf)?;
+        Fee::deserialize(buf)?;
+        Option::<Fee>::deserialize(buf)?;
+        // last_epoch_pool_token_supply, last_epoch_total_lamports
+        for _ in 0..2 {
+            u64::deserialize(buf)?;
+        }
+        Ok(data.len() - remaining.len())
+    }
+
     /// Updates one of the StakePool's fees.
     pub fn update_fee(&mut self, fee: &FeeType) -> Result<(), StakePoolError> {
         match fee {
@@ -474,11 +595,74 @@ impl StakePool {
             }
             FeeType::SolDeposit(new_fee) => self.sol_deposit_fee = *new_fee,
             FeeType::StakeDeposit(new_fee) => self.stake_deposit_fee = *new_fee,
+            FeeType::InstantUnstake(Some(new_instant_unstake)) => {
+                let old_instant_unstake = self.instant_unstake.unwrap_or_default();
+                new_instant_unstake
+                    .min_fee
+                    .check_withdrawal(&old_instant_unstake.min_fee)?;
+                new_instant_unstake
+                    .max_fee
+                    .check_withdrawal(&old_instant_unstake.max_fee)?;
+                self.next_instant_unstake = Some(*new_instant_unstake)
+            }
+            // disabling only lowers fees, so it takes effect immediately
+            FeeType::InstantUnstake(None) => {
+                self.instant_unstake = None;
+                self.next_instant_unstake = None;
+                self.scheduled_decrease_lamports = 0;
+            }
         };
         Ok(())
     }
 }
 
+/// Pool mint extensions that do not change how the stake pool mints, burns,
+/// or transfers pool tokens
+const SUPPORTED_MINT_EXTENSIONS: &[ExtensionType] = &[
+    ExtensionType::Uninitialized,
+    ExtensionType::ConfidentialTransferMint,
+    ExtensionType::DefaultAccountState,
+    ExtensionType::InterestBearingConfig,
+];
+
+/// Manager fee account extensions that do not prevent the stake pool from
+/// transferring fees to it
+const SUPPORTED_FEE_ACCOUNT_EXTENSIONS: &[ExtensionType] = &[
+    ExtensionType::Uninitialized,
+    ExtensionType::TransferFeeAmount,
+    ExtensionType::ConfidentialTransferAccount,
+    ExtensionType::ImmutableOwner,
+];
+
+/// Checks that the pool mint only has extensions supported by the stake pool,
+/// rejecting ones such as `TransferFeeConfig` or `NonTransferable`
+pub(crate) fn check_mint_extensions(mint: &StateWithExtensions<Mint>) -> Result<(), ProgramError> {
+    for extension_type in mint.get_extension_types()? {
+        if !SUPPORTED_MINT_EXTENSIONS.contains(&extension_type) {
+            msg!("Pool mint extension {:?} is not supported", extension_type);
+            return Err(StakePoolError::UnsupportedMintExtension.into());
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the manager fee account only has extensions supported by the
+/// stake pool
+pub(crate) fn check_fee_account_extensions(
+    account: &StateWithExtensions<Account>,
+) -> Result<(), ProgramError> {
+    for extension_type in account.get_extension_types()? {
+        if !SUPPORTED_FEE_ACCOUNT_EXTENSIONS.contains(&extension_type) {
+            msg!(
+                "Fee account extension {:?} is not supported",
+                extension_type
+            );
+            return Err(StakePoolError::UnsupportedFeeAccountExtension.into());
+        }
+    }
+    Ok(())
+}
+
 /// Storage list for all validator stake accounts in the pool.
 #[repr(C)]
 #[derive(Clone, Debug, Default, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
@@ -695,6 +879,177 @@ impl ValidatorListHeader {
     }
 }
 
+/// Number of epochs kept in the rolling history of each validator
+pub const VALIDATOR_HISTORY_EPOCHS: usize = 10;
+
+/// Rolling history of the rewards earned by the validators of a pool, which
+/// stakers can use to estimate the yield of each validator
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct ValidatorHistoryList {
+    /// Data outside of the history list, separated out for cheaper deserializations
+    pub header: ValidatorHistoryHeader,
+
+    /// History of each validator in the pool
+    pub validators: Vec<ValidatorHistory>,
+}
+
+/// Helper type to deserialize just the start of a ValidatorHistoryList
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct ValidatorHistoryHeader {
+    /// Account type, must be ValidatorHistory currently
+    pub account_type: AccountType,
+
+    /// Stake pool whose validators are recorded
+    pub stake_pool: Pubkey,
+
+    /// Maximum number of validators recorded
+    pub max_validators: u32,
+}
+
+/// Rewards earned by a validator stake account over an epoch
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct EpochRewards {
+    /// Epoch when the rewards were recorded, 0 if never recorded
+    pub epoch: u64,
+
+    /// Stake delegated at the start of the previous epoch
+    pub stake_lamports: u64,
+
+    /// Rewards earned by the stake over the previous epoch
+    pub reward_lamports: u64,
+}
+
+/// Rolling history of the rewards of a validator, indexed by epoch
+///
+/// Epochs with stake moving in or out of the transient stake account are not
+/// recorded, since the rewards cannot be told apart from the moved stake.
+///
+/// Like `ValidatorStakeInfo`, this structure is reinterpreted from the account
+/// bytes with an unsafe pointer cast, so it cannot have any alignment-padding.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
+pub struct ValidatorHistory {
+    /// Validator vote account address
+    pub vote_account_address: Pubkey,
+
+    /// Last epoch recorded
+    pub last_update_epoch: u64,
+
+    /// Rewards of the last epochs, at index `epoch % VALIDATOR_HISTORY_EPOCHS`
+    pub epochs: [EpochRewards; VALIDATOR_HISTORY_EPOCHS],
+}
+
+impl ValidatorHistory {
+    /// Creates an empty history for the validator
+    pub fn new(vote_account_address: Pubkey) -> Self {
+        Self {
+            vote_account_address,
+            ..Self::default()
+        }
+    }
+
+    /// Records the rewards of the epoch ending before `epoch`
+    pub fn record(&mut self, epoch: u64, stake_lamports: u64, reward_lamports: u64) {
+        self.epochs[epoch as usize % VALIDATOR_HISTORY_EPOCHS] = EpochRewards {
+            epoch,
+            stake_lamports,
+            reward_lamports,
+        };
+        self.last_update_epoch = epoch;
+    }
+
+    /// Total stake and rewards recorded over the history ending at
+    /// `current_epoch`, skipping older entries that were not overwritten
+    pub fn totals(&self, current_epoch: u64) -> (u128, u128) {
+        let oldest_epoch = current_epoch.saturating_sub(VALIDATOR_HISTORY_EPOCHS as u64);
+        self.epochs
+            .iter()
+            .filter(|rewards| rewards.epoch > oldest_epoch && rewards.epoch <= current_epoch)
+            .fold((0, 0), |(stake, rewards), entry| {
+                (
+                    stake + entry.stake_lamports as u128,
+                    rewards + entry.reward_lamports as u128,
+                )
+            })
+    }
+
+    /// Performs a very cheap comparison, for checking if this validator
+    /// history matches the vote account address
+    pub fn memcmp_pubkey(data: &[u8], vote_address_bytes: &[u8]) -> bool {
+        sol_memcmp(&data[0..PUBKEY_BYTES], vote_address_bytes, PUBKEY_BYTES) == 0
+    }
+}
+
+impl Sealed for ValidatorHistory {}
+
+impl Pack for ValidatorHistory {
+    const LEN: usize = 280;
+    fn pack_into_slice(&self, data: &mut [u8]) {
+        let mut data = data;
+        self.serialize(&mut data).unwrap();
+    }
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let unpacked = Self::try_from_slice(src)?;
+        Ok(unpacked)
+    }
+}
+
+impl ValidatorHistoryList {
+    /// Create an empty instance containing space for `max_validators`
+    pub fn new(max_validators: u32) -> Self {
+        Self {
+            header: ValidatorHistoryHeader {
+                account_type: AccountType::ValidatorHistory,
+                max_validators,
+                ..ValidatorHistoryHeader::default()
+            },
+            validators: vec![ValidatorHistory::default(); max_validators as usize],
+        }
+    }
+
+    /// Calculate the number of validator histories that fit in the provided length
+    pub fn calculate_max_validators(buffer_length: usize) -> usize {
+        let header_size = ValidatorHistoryHeader::LEN + 4;
+        buffer_length.saturating_sub(header_size) / ValidatorHistory::LEN
+    }
+
+    /// Get the history of a validator
+    pub fn find(&self, vote_account_address: &Pubkey) -> Option<&ValidatorHistory> {
+        self.validators
+            .iter()
+            .find(|x| x.vote_account_address == *vote_account_address)
+    }
+}
+
+impl ValidatorHistoryHeader {
+    const LEN: usize = 1 + 32 + 4;
+
+    /// Check if the account is actually initialized as a validator history
+    pub fn is_valid(&self) -> bool {
+        self.account_type == AccountType::ValidatorHistory
+    }
+
+    /// Check if the validator history is uninitialized
+    pub fn is_uninitialized(&self) -> bool {
+        self.account_type == AccountType::Uninitialized
+    }
+
+    /// Extracts the validator history into its header and internal BigVec
+    pub fn deserialize_vec(data: &mut [u8]) -> Result<(Self, BigVec), ProgramError> {
+        let mut data_mut = &data[..];
+        let header = ValidatorHistoryHeader::deserialize(&mut data_mut)?;
+        let length = get_instance_packed_len(&header)?;
+
+        let big_vec = BigVec {
+            data: &mut data[length..],
+        };
+        Ok((header, big_vec))
+    }
+}
+
 /// Fee rate as a ratio, minted on `UpdateStakePoolBalance` as a proportion of
 /// the rewards
 /// If either the numerator or the denominator is 0, the fee is considered to be 0
@@ -770,6 +1125,75 @@ impl fmt::Display for Fee {
     }
 }
 
+/// Instant unstake configuration: SOL withdrawals pay a fee on top of the SOL
+/// withdrawal fee, going from `min_fee` when the reserve holds the whole
+/// target buffer to `max_fee` when it is empty
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct InstantUnstake {
+    /// Target liquidity buffer in the reserve, as a proportion of the total
+    /// lamports of the pool
+    pub target_buffer: Fee,
+    /// Fee charged when the buffer is full
+    pub min_fee: Fee,
+    /// Fee charged when the buffer is empty
+    pub max_fee: Fee,
+}
+
+impl InstantUnstake {
+    /// Precision of the interpolated fee
+    const FEE_DENOMINATOR: u64 = 1_000_000_000;
+
+    /// Lamports of the target buffer for a pool with `total_lamports`
+    #[inline]
+    pub fn target_buffer_lamports(&self, total_lamports: u64) -> Option<u64> {
+        u64::try_from(self.target_buffer.apply(total_lamports)?).ok()
+    }
+
+    /// Fee for an instant unstake leaving `buffer_lamports` available in the
+    /// reserve, linear between the maximum fee for an empty buffer and the
+    /// minimum fee for a full one
+    pub fn fee(&self, total_lamports: u64, buffer_lamports: u64) -> Option<Fee> {
+        let target = self.target_buffer_lamports(total_lamports)? as u128;
+        let min_fee = self.min_fee.apply(Self::FEE_DENOMINATOR)?;
+        let max_fee = self.max_fee.apply(Self::FEE_DENOMINATOR)?;
+        let numerator = if target == 0 {
+            min_fee
+        } else {
+            let buffer = (buffer_lamports as u128).min(target);
+            max_fee
+                .checked_mul(target.checked_sub(buffer)?)?
+                .checked_add(min_fee.checked_mul(buffer)?)?
+                .checked_div(target)?
+        };
+        Some(Fee {
+            denominator: Self::FEE_DENOMINATOR,
+            numerator: u64::try_from(numerator).ok()?,
+        })
+    }
+
+    /// Checks that the buffer and fees are at most 100%, and that the minimum
+    /// fee does not exceed the maximum fee
+    fn is_valid(&self) -> bool {
+        let fee_too_high = |fee: &Fee| fee.numerator > fee.denominator;
+        !fee_too_high(&self.target_buffer)
+            && !fee_too_high(&self.min_fee)
+            && !fee_too_high(&self.max_fee)
+            && self.min_fee.apply(Self::FEE_DENOMINATOR)
+                <= self.max_fee.apply(Self::FEE_DENOMINATOR)
+    }
+}
+
+impl fmt::Display for InstantUnstake {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "buffer {}, fee {} to {}",
+            self.target_buffer, self.min_fee, self.max_fee
+        )
+    }
+}
+
 /// The type of fees that can be set on the stake pool
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
 pub enum FeeType {
@@ -787,6 +1211,8 @@ pub enum FeeType {
     StakeDeposit(Fee),
     /// SOL withdrawal fee
     SolWithdrawal(Fee),
+    /// Instant unstake buffer and fees, `None` disables instant unstakes
+    InstantUnstake(Option<InstantUnstake>),
 }
 
 impl FeeType {
@@ -800,6 +1226,9 @@ impl FeeType {
             Self::SolWithdrawal(fee) => fee.numerator > fee.denominator,
             Self::SolDeposit(fee) => fee.numerator > fee.denominator,
             Self::StakeDeposit(fee) => fee.numerator > fee.denominator,
+            Self::InstantUnstake(instant_unstake) => {
+                instant_unstake.map_or(false, |instant_unstake| !instant_unstake.is_valid())
+            }
         };
         if too_high {
             msg!("Fee greater than 100%: {:?}", self);
@@ -813,7 +1242,10 @@ impl FeeType {
     pub fn can_only_change_next_epoch(&self) -> bool {
         matches!(
             self,
-            Self::StakeWithdrawal(_) | Self::SolWithdrawal(_) | Self::Epoch(_)
+            Self::StakeWithdrawal(_)
+                | Self::SolWithdrawal(_)
+                | Self::Epoch(_)
+                | Self::InstantUnstake(_)
         )
     }
 }
@@ -912,6 +1344,101 @@ mod test {
         assert_eq!(stake_list_unpacked, stake_list);
     }
 
+    #[test]
+    fn legacy_stake_pool_packing() {
+        // all optional fields set, so the older layout is used entirely
+        let fee = Fee {
+            numerator: 1,
+            denominator: 100,
+        };
+        let stake_pool = StakePool {
+            account_type: AccountType::StakePool,
+            total_lamports: 100 * LAMPORTS_PER_SOL,
+            next_epoch_fee: Some(fee),
+            preferred_deposit_validator_vote_address: Some(Pubkey::new_unique()),
+            preferred_withdraw_validator_vote_address: Some(Pubkey::new_unique()),
+            next_stake_withdrawal_fee: Some(fee),
+            sol_deposit_authority: Some(Pubkey::new_unique()),
+            sol_withdraw_authority: Some(Pubkey::new_unique()),
+            next_sol_withdrawal_fee: Some(fee),
+            last_epoch_total_lamports: 99 * LAMPORTS_PER_SOL,
+            ..StakePool::default()
+        };
+        let mut byte_vec = vec![0u8; get_packed_len::<StakePool>()];
+        let mut bytes = byte_vec.as_mut_slice();
+        stake_pool.serialize(&mut bytes).unwrap();
+
+        // older pools are too short for the current layout
+        let mut legacy_byte_vec = byte_vec[..LEGACY_STAKE_POOL_LEN].to_vec();
+        assert!(try_from_slice_unchecked::<StakePool>(&legacy_byte_vec).is_err());
+
+        // zero-extending them gives the defaults of the new fields
+        legacy_byte_vec.resize(get_packed_len::<StakePool>(), 0);
+        let stake_pool_unpacked = try_from_slice_unchecked::<StakePool>(&legacy_byte_vec).unwrap();
+        assert_eq!(stake_pool_unpacked, stake_pool);
+        assert_eq!(stake_pool_unpacked.instant_unstake, None);
+        assert_eq!(stake_pool_unpacked.scheduled_decrease_lamports, 0);
+        assert_eq!(stake_pool_unpacked.validator_history, None);
+        assert_eq!(
+            StakePool::legacy_serialized_len(&byte_vec).unwrap(),
+            LEGACY_STAKE_POOL_LEN
+        );
+    }
+
+    #[test]
+    fn legacy_stake_pool_stale_tail() {
+        // older programs cleared `next_epoch_fee` without zeroing the bytes
+        // it no longer used
+        let stake_pool = StakePool {
+            account_type: AccountType::StakePool,
+            next_epoch_fee: Some(Fee {
+                numerator: 1,
+                denominator: 100,
+            }),
+            preferred_deposit_validator_vote_address: Some(Pubkey::new_unique()),
+            preferred_withdraw_validator_vote_address: Some(Pubkey::new_unique()),
+            sol_deposit_authority: Some(Pubkey::new_unique()),
+            sol_withdraw_authority: Some(Pubkey::new_unique()),
+            last_epoch_pool_token_supply: 98 * LAMPORTS_PER_SOL,
+            last_epoch_total_lamports: 99 * LAMPORTS_PER_SOL,
+            ..StakePool::default()
+        };
+        let mut byte_vec = vec![0u8; get_packed_len::<StakePool>()];
+        stake_pool.serialize(&mut byte_vec.as_mut_slice()).unwrap();
+        let stake_pool = StakePool {
+            next_epoch_fee: None,
+            ..stake_pool
+        };
+        stake_pool.serialize(&mut byte_vec.as_mut_slice()).unwrap();
+        byte_vec.truncate(LEGACY_STAKE_POOL_LEN);
+
+        // everything but the unset fields added after the older layout
+        let new_fields_len = get_instance_packed_len(&(
+            stake_pool.instant_unstake,
+            stake_pool.next_instant_unstake,
+            stake_pool.scheduled_decrease_lamports,
+            stake_pool.validator_history,
+        ))
+        .unwrap();
+        let legacy_len = StakePool::legacy_serialized_len(&byte_vec).unwrap();
+        assert_eq!(
+            legacy_len,
+            get_instance_packed_len(&stake_pool).unwrap() - new_fields_len
+        );
+        assert!(byte_vec[legacy_len..].iter().any(|byte| *byte != 0));
+
+        byte_vec.resize(get_packed_len::<StakePool>(), 0);
+        byte_vec[legacy_len..].fill(0);
+        let stake_pool_unpacked = try_from_slice_unchecked::<StakePool>(&byte_vec).unwrap();
+        assert_eq!(stake_pool_unpacked, stake_pool);
+
+        // the current program zeroes the bytes after the pool on every write
+        let mut byte_vec = vec![1u8; get_packed_len::<StakePool>()];
+        stake_pool.serialize_zeroed(&mut byte_vec).unwrap();
+        let len = get_instance_packed_len(&stake_pool).unwrap();
+        assert!(byte_vec[len..].iter().all(|byte| *byte == 0));
+    }
+
     #[test]
     fn validator_list_active_stake() {
         let max_validators = 10_000;
@@ -1065,6 +1592,204 @@ mod test {
         assert_eq!(fee, rewards);
     }
 
+    #[test]
+    fn instant_unstake_fee_calculation() {
+        let instant_unstake = InstantUnstake {
+            target_buffer: Fee {
+                numerator: 1,
+                denominator: 10,
+            },
+            min_fee: Fee {
+                numerator: 1,
+                denominator: 1_000,
+            },
+            max_fee: Fee {
+                numerator: 3,
+                denominator: 100,
+            },
+        };
+        let stake_pool = StakePool {
+            total_lamports: 1_000 * LAMPORTS_PER_SOL,
+            pool_token_supply: 1_000 * LAMPORTS_PER_SOL,
+            instant_unstake: Some(instant_unstake),
+            ..StakePool::default()
+        };
+
+        // full buffer after the withdrawal, minimum fee
+        let fee = stake_pool
+            .calc_pool_tokens_instant_unstake_fee(LAMPORTS_PER_SOL, 200 * LAMPORTS_PER_SOL)
+            .unwrap();
+        assert_eq!(fee, LAMPORTS_PER_SOL / 1_000);
+        // buffer halfway drained after the withdrawal
+        let fee = stake_pool
+            .calc_pool_tokens_instant_unstake_fee(LAMPORTS_PER_SOL, 51 * LAMPORTS_PER_SOL)
+            .unwrap();
+        assert_eq!(fee, LAMPORTS_PER_SOL * 155 / 10_000);
+        // empty buffer, maximum fee
+        let fee = stake_pool
+            .calc_pool_tokens_instant_unstake_fee(LAMPORTS_PER_SOL, LAMPORTS_PER_SOL)
+            .unwrap();
+        assert_eq!(fee, LAMPORTS_PER_SOL * 3 / 100);
+
+        assert_eq!(
+            stake_pool
+                .calc_instant_unstake_shortfall(40 * LAMPORTS_PER_SOL, 0)
+                .unwrap(),
+            60 * LAMPORTS_PER_SOL
+        );
+        assert_eq!(
+            stake_pool
+                .calc_instant_unstake_shortfall(40 * LAMPORTS_PER_SOL, 20 * LAMPORTS_PER_SOL)
+                .unwrap(),
+            40 * LAMPORTS_PER_SOL
+        );
+        assert_eq!(
+            stake_pool
+                .calc_instant_unstake_shortfall(150 * LAMPORTS_PER_SOL, 0)
+                .unwrap(),
+            0
+        );
+
+        let stake_pool = StakePool {
+            instant_unstake: None,
+            ..stake_pool
+        };
+        assert_eq!(
+            stake_pool
+                .calc_pool_tokens_instant_unstake_fee(LAMPORTS_PER_SOL, 0)
+                .unwrap(),
+            0
+        );
+        assert_eq!(stake_pool.calc_instant_unstake_shortfall(0, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn instant_unstake_fee_too_high() {
+        let fee = |numerator, denominator| Fee {
+            numerator,
+            denominator,
+        };
+        let valid = InstantUnstake {
+            target_buffer: fee(1, 10),
+            min_fee: fee(1, 1_000),
+            max_fee: fee(1, 100),
+        };
+        assert!(FeeType::InstantUnstake(Some(valid))
+            .check_too_high()
+            .is_ok());
+        assert!(FeeType::InstantUnstake(None).check_too_high().is_ok());
+        let invalid = [
+            InstantUnstake {
+                target_buffer: fee(11, 10),
+                ..valid
+            },
+            InstantUnstake {
+                max_fee: fee(2, 1),
+                ..valid
+            },
+            InstantUnstake {
+                min_fee: fee(1, 10),
+                ..valid
+            },
+        ];
+        for instant_unstake in invalid {
+            assert_eq!(
+                FeeType::InstantUnstake(Some(instant_unstake)).check_too_high(),
+                Err(StakePoolError::FeeTooHigh)
+            );
+        }
+    }
+
+    #[test]
+    fn instant_unstake_update_fee() {
+        let fee = |numerator, denominator| Fee {
+            numerator,
+            denominator,
+        };
+        let instant_unstake = InstantUnstake {
+            target_buffer: fee(1, 10),
+            min_fee: fee(1, 1_000),
+            max_fee: fee(15, 10_000),
+        };
+        let mut stake_pool = StakePool {
+            scheduled_decrease_lamports: LAMPORTS_PER_SOL,
+            ..StakePool::default()
+        };
+
+        // a new configuration is staged for the next epoch
+        stake_pool
+            .update_fee(&FeeType::InstantUnstake(Some(instant_unstake)))
+            .unwrap();
+        assert_eq!(stake_pool.instant_unstake, None);
+        assert_eq!(stake_pool.next_instant_unstake, Some(instant_unstake));
+
+        // fees may only rise by a limited factor over the current ones
+        let too_high = InstantUnstake {
+            max_fee: fee(2, 1_000),
+            ..instant_unstake
+        };
+        assert_eq!(
+            stake_pool.update_fee(&FeeType::InstantUnstake(Some(too_high))),
+            Err(StakePoolError::FeeIncreaseTooHigh)
+        );
+        stake_pool.instant_unstake = Some(instant_unstake);
+        stake_pool
+            .update_fee(&FeeType::InstantUnstake(Some(too_high)))
+            .unwrap();
+        let too_high = InstantUnstake {
+            min_fee: fee(2, 1_000),
+            ..instant_unstake
+        };
+        assert_eq!(
+            stake_pool.update_fee(&FeeType::InstantUnstake(Some(too_high))),
+            Err(StakePoolError::FeeIncreaseTooHigh)
+        );
+
+        // disabling takes effect immediately
+        stake_pool
+            .update_fee(&FeeType::InstantUnstake(None))
+            .unwrap();
+        assert_eq!(stake_pool.instant_unstake, None);
+        assert_eq!(stake_pool.next_instant_unstake, None);
+        assert_eq!(stake_pool.scheduled_decrease_lamports, 0);
+    }
+
+    #[test]
+    fn validator_history_rolling_totals() {
+        let vote_account_address = Pubkey::new_unique();
+        let mut history = ValidatorHistory::new(vote_account_address);
+        assert_eq!(
+            get_instance_packed_len(&history).unwrap(),
+            ValidatorHistory::LEN
+        );
+        assert_eq!(history.totals(100), (0, 0));
+
+        for epoch in 1..=5 {
+            history.record(epoch, 1_000, epoch);
+        }
+        assert_eq!(history.last_update_epoch, 5);
+        assert_eq!(history.totals(5), (5_000, 15));
+        // epochs 1 and 2 are out of the window
+        assert_eq!(history.totals(12), (3_000, 12));
+
+        // entries wrap around, overwriting the oldest epochs
+        for epoch in 6..=13 {
+            history.record(epoch, 2_000, 1);
+        }
+        assert_eq!(history.totals(13), (18_000, 17));
+
+        let mut data = history.try_to_vec().unwrap();
+        assert!(ValidatorHistory::memcmp_pubkey(
+            &data,
+            vote_account_address.as_ref()
+        ));
+        data[0] ^= 1;
+        assert!(!ValidatorHistory::memcmp_pubkey(
+            &data,
+            vote_account_address.as_ref()
+        ));
+    }
+
     #[test]
     fn approximate_apr_calculation() {
         // 8% / year means roughly .044% / epoch
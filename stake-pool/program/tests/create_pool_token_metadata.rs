@@ -0,0 +1,297 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use {
+    borsh::BorshDeserialize,
+    helpers::*,
+    solana_program::{instruction::InstructionError, pubkey::Pubkey},
+    solana_program_test::*,
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    spl_stake_pool::{
+        error::StakePoolError, id, inline_mpl_token_metadata::pda::find_metadata_account,
+        instruction, MINIMUM_RESERVE_LAMPORTS,
+    },
+};
+
+async fn setup() -> (ProgramTestContext, StakePoolAccounts) {
+    let mut context = program_test_with_metadata_program()
+        .start_with_context()
+        .await;
+    let stake_pool_accounts = StakePoolAccounts::new();
+    stake_pool_accounts
+        .initialize_stake_pool(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            MINIMUM_RESERVE_LAMPORTS,
+        )
+        .await
+        .unwrap();
+
+    (context, stake_pool_accounts)
+}
+
+/// Reads the update authority, name, symbol and uri of a metadata account.
+/// The metadata program pads the strings with null bytes.
+fn get_metadata_fields(data: &[u8]) -> (Pubkey, String, String, String) {
+    // skip the account key
+    let mut data = &data[1..];
+    let update_authority = Pubkey::deserialize(&mut data).unwrap();
+    let _mint = Pubkey::deserialize(&mut data).unwrap();
+    let mut read_string = || {
+        String::deserialize(&mut data)
+            .unwrap()
+            .trim_end_matches(char::from(0))
+            .to_string()
+    };
+    let name = read_string();
+    let symbol = read_string();
+    let uri = read_string();
+    (update_authority, name, symbol, uri)
+}
+
+async fn create_token_metadata(
+    context: &mut ProgramTestContext,
+    stake_pool_accounts: &StakePoolAccounts,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::create_token_metadata(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &stake_pool_accounts.manager.pubkey(),
+            &stake_pool_accounts.pool_mint.pubkey(),
+            &context.payer.pubkey(),
+            name.to_string(),
+            symbol.to_string(),
+            uri.to_string(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_pool_accounts.manager],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn success_create_pool_token_metadata() {
+    let (mut context, stake_pool_accounts) = setup().await;
+
+    create_token_metadata(
+        &mut context,
+        &stake_pool_accounts,
+        "test",
+        "TEST",
+        "test_uri",
+    )
+    .await;
+
+    let (token_metadata, _) = find_metadata_account(&stake_pool_accounts.pool_mint.pubkey());
+    let metadata_account = get_account(&mut context.banks_client, &token_metadata).await;
+    let (update_authority, name, symbol, uri) = get_metadata_fields(&metadata_account.data);
+    assert_eq!(update_authority, stake_pool_accounts.withdraw_authority);
+    assert_eq!(name, "test");
+    assert_eq!(symbol, "TEST");
+    assert_eq!(uri, "test_uri");
+}
+
+#[tokio::test]
+async fn success_update_pool_token_metadata() {
+    let (mut context, stake_pool_accounts) = setup().await;
+
+    create_token_metadata(
+        &mut context,
+        &stake_pool_accounts,
+        "test",
+        "TEST",
+        "test_uri",
+    )
+    .await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::update_token_metadata(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &stake_pool_accounts.manager.pubkey(),
+            &stake_pool_accounts.pool_mint.pubkey(),
+            "updated".to_string(),
+            "UPD".to_string(),
+            "updated_uri".to_string(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_pool_accounts.manager],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let (token_metadata, _) = find_metadata_account(&stake_pool_accounts.pool_mint.pubkey());
+    let metadata_account = get_account(&mut context.banks_client, &token_metadata).await;
+    let (update_authority, name, symbol, uri) = get_metadata_fields(&metadata_account.data);
+    assert_eq!(update_authority, stake_pool_accounts.withdraw_authority);
+    assert_eq!(name, "updated");
+    assert_eq!(symbol, "UPD");
+    assert_eq!(uri, "updated_uri");
+}
+
+#[tokio::test]
+async fn fail_manager_did_not_sign() {
+    let (mut context, stake_pool_accounts) = setup().await;
+
+    let mut ix = instruction::create_token_metadata(
+        &id(),
+        &stake_pool_accounts.stake_pool.pubkey(),
+        &stake_pool_accounts.manager.pubkey(),
+        &stake_pool_accounts.pool_mint.pubkey(),
+        &context.payer.pubkey(),
+        "test".to_string(),
+        "TEST".to_string(),
+        "test_uri".to_string(),
+    );
+    ix.accounts[1].is_signer = false;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    match error {
+        TransactionError::InstructionError(_, InstructionError::Custom(error_index)) => {
+            let program_error = StakePoolError::SignatureMissing as u32;
+            assert_eq!(error_index, program_error);
+        }
+        _ => panic!("Wrong error occurs while manager signature missing"),
+    }
+}
+
+#[tokio::test]
+async fn fail_wrong_manager_signed() {
+    let (mut context, stake_pool_accounts) = setup().await;
+
+    let random_keypair = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::create_token_metadata(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &random_keypair.pubkey(),
+            &stake_pool_accounts.pool_mint.pubkey(),
+            &context.payer.pubkey(),
+            "test".to_string(),
+            "TEST".to_string(),
+            "test_uri".to_string(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &random_keypair],
+        context.last_blockhash,
+    );
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    match error {
+        TransactionError::InstructionError(_, InstructionError::Custom(error_index)) => {
+            let program_error = StakePoolError::WrongManager as u32;
+            assert_eq!(error_index, program_error);
+        }
+        _ => panic!("Wrong error occurs while signing with the wrong manager"),
+    }
+}
+
+#[tokio::test]
+async fn fail_wrong_metadata_program() {
+    let (mut context, stake_pool_accounts) = setup().await;
+
+    let mut ix = instruction::update_token_metadata(
+        &id(),
+        &stake_pool_accounts.stake_pool.pubkey(),
+        &stake_pool_accounts.manager.pubkey(),
+        &stake_pool_accounts.pool_mint.pubkey(),
+        "test".to_string(),
+        "TEST".to_string(),
+        "test_uri".to_string(),
+    );
+    ix.accounts[4].pubkey = Pubkey::new_unique();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_pool_accounts.manager],
+        context.last_blockhash,
+    );
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+}
+
+#[tokio::test]
+async fn fail_wrong_metadata_account() {
+    let (mut context, stake_pool_accounts) = setup().await;
+
+    let mut ix = instruction::update_token_metadata(
+        &id(),
+        &stake_pool_accounts.stake_pool.pubkey(),
+        &stake_pool_accounts.manager.pubkey(),
+        &stake_pool_accounts.pool_mint.pubkey(),
+        "test".to_string(),
+        "TEST".to_string(),
+        "test_uri".to_string(),
+    );
+    ix.accounts[3].pubkey = Pubkey::new_unique();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_pool_accounts.manager],
+        context.last_blockhash,
+    );
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    match error {
+        TransactionError::InstructionError(_, InstructionError::Custom(error_index)) => {
+            let program_error = StakePoolError::InvalidMetadataAccount as u32;
+            assert_eq!(error_index, program_error);
+        }
+        _ => panic!("Wrong error occurs while passing the wrong metadata account"),
+    }
+}
@@ -25,7 +25,7 @@ use {
     spl_stake_pool::{
         find_deposit_authority_program_address, find_stake_program_address,
         find_transient_stake_program_address, find_withdraw_authority_program_address, id,
-        instruction,
+        inline_mpl_token_metadata, instruction,
         processor::Processor,
         state::{self, FeeType, ValidatorList},
         MINIMUM_ACTIVE_STAKE, MINIMUM_RESERVE_LAMPORTS,
@@ -42,6 +42,16 @@ pub fn program_test() -> ProgramTest {
     ProgramTest::new("spl_stake_pool", id(), processor!(Processor::process))
 }
 
+pub fn program_test_with_metadata_program() -> ProgramTest {
+    let mut program_test = program_test();
+    // The program binary was generated by running:
+    //      $ solana program dump -u m metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s \
+    //                            tests/fixtures/mpl_token_metadata.so
+    //
+    program_test.add_program("mpl_token_metadata", inline_mpl_token_metadata::id(), None);
+    program_test
+}
+
 pub async fn get_account(banks_client: &mut BanksClient, pubkey: &Pubkey) -> Account {
     banks_client
         .get_account(*pubkey)
@@ -1313,6 +1323,45 @@ impl StakePoolAccounts {
             .err()
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub async fn redelegate(
+        &self,
+        banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: &Hash,
+        source_validator_stake: &ValidatorStakeAccount,
+        destination_validator_stake: &ValidatorStakeAccount,
+        lamports: u64,
+    ) -> Option<TransportError> {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction::redelegate(
+                &id(),
+                &self.stake_pool.pubkey(),
+                &self.staker.pubkey(),
+                &self.withdraw_authority,
+                &self.validator_list.pubkey(),
+                &self.reserve_stake.pubkey(),
+                &source_validator_stake.stake_account,
+                &source_validator_stake.transient_stake_account,
+                &destination_validator_stake.transient_stake_account,
+                &destination_validator_stake.stake_account,
+                &destination_validator_stake.vote.pubkey(),
+                lamports,
+                source_validator_stake.transient_stake_seed,
+                destination_validator_stake.transient_stake_seed,
+            )],
+            Some(&payer.pubkey()),
+            &[payer, &self.staker],
+            *recent_blockhash,
+        );
+        #[allow(clippy::useless_conversion)] // Remove during upgrade to 1.10
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|e| e.into())
+            .err()
+    }
+
     pub async fn set_preferred_validator(
         &self,
         banks_client: &mut BanksClient,
@@ -1376,6 +1425,10 @@ impl StakePoolAccounts {
             next_sol_withdrawal_fee: None,
             last_epoch_pool_token_supply: 0,
             last_epoch_total_lamports: 0,
+            instant_unstake: None,
+            next_instant_unstake: None,
+            scheduled_decrease_lamports: 0,
+            validator_history: None,
         };
         let mut validator_list = ValidatorList::new(self.max_validators);
         validator_list.validators = vec![];
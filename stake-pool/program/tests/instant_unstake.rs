@@ -0,0 +1,474 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program::{
+        borsh::try_from_slice_unchecked, instruction::InstructionError, pubkey::Pubkey, stake,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    spl_stake_pool::{
+        error::StakePoolError,
+        id, instruction,
+        state::{Fee, FeeType, InstantUnstake, StakePool},
+        MINIMUM_ACTIVE_STAKE, MINIMUM_RESERVE_LAMPORTS,
+    },
+};
+
+const INSTANT_UNSTAKE: InstantUnstake = InstantUnstake {
+    target_buffer: Fee {
+        numerator: 1,
+        denominator: 2,
+    },
+    min_fee: Fee {
+        numerator: 1,
+        denominator: 1_000,
+    },
+    max_fee: Fee {
+        numerator: 15,
+        denominator: 10_000,
+    },
+};
+
+async fn setup() -> (
+    ProgramTestContext,
+    StakePoolAccounts,
+    ValidatorStakeAccount,
+    Keypair,
+    Pubkey,
+    u64,
+) {
+    let mut context = program_test().start_with_context().await;
+    let first_normal_slot = context.genesis_config().epoch_schedule.first_normal_slot;
+    let slots_per_epoch = context.genesis_config().epoch_schedule.slots_per_epoch;
+    let mut slot = first_normal_slot;
+    context.warp_to_slot(slot).unwrap();
+
+    let stake_pool_accounts = StakePoolAccounts::new();
+    stake_pool_accounts
+        .initialize_stake_pool(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            MINIMUM_RESERVE_LAMPORTS,
+        )
+        .await
+        .unwrap();
+
+    let validator_stake = simple_add_validator_to_pool(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &stake_pool_accounts,
+    )
+    .await;
+
+    let _deposit_info = simple_deposit_stake(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &stake_pool_accounts,
+        &validator_stake,
+        4 * TEST_STAKE_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    let user = Keypair::new();
+    let pool_token_account = Keypair::new();
+    create_token_account(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &pool_token_account,
+        &stake_pool_accounts.pool_mint.pubkey(),
+        &user.pubkey(),
+    )
+    .await
+    .unwrap();
+
+    let error = stake_pool_accounts
+        .deposit_sol(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &pool_token_account.pubkey(),
+            TEST_STAKE_AMOUNT,
+            None,
+        )
+        .await;
+    assert!(error.is_none());
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_fee(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &stake_pool_accounts.manager.pubkey(),
+            FeeType::InstantUnstake(Some(INSTANT_UNSTAKE)),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_pool_accounts.manager],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // the configuration takes effect on the next epoch
+    let stake_pool = get_stake_pool(&mut context.banks_client, &stake_pool_accounts).await;
+    assert_eq!(stake_pool.instant_unstake, None);
+    assert_eq!(stake_pool.next_instant_unstake, Some(INSTANT_UNSTAKE));
+
+    slot += slots_per_epoch;
+    context.warp_to_slot(slot).unwrap();
+    stake_pool_accounts
+        .update_all(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &[validator_stake.vote.pubkey()],
+            false,
+        )
+        .await;
+
+    let pool_tokens =
+        get_token_balance(&mut context.banks_client, &pool_token_account.pubkey()).await;
+
+    (
+        context,
+        stake_pool_accounts,
+        validator_stake,
+        user,
+        pool_token_account.pubkey(),
+        pool_tokens,
+    )
+}
+
+async fn get_stake_pool(
+    banks_client: &mut BanksClient,
+    stake_pool_accounts: &StakePoolAccounts,
+) -> StakePool {
+    let stake_pool = get_account(banks_client, &stake_pool_accounts.stake_pool.pubkey()).await;
+    try_from_slice_unchecked::<StakePool>(stake_pool.data.as_slice()).unwrap()
+}
+
+#[tokio::test]
+async fn success_dynamic_fee() {
+    let (mut context, stake_pool_accounts, _, user, pool_token_account, pool_tokens) =
+        setup().await;
+
+    let stake_pool = get_stake_pool(&mut context.banks_client, &stake_pool_accounts).await;
+    assert_eq!(stake_pool.instant_unstake, Some(INSTANT_UNSTAKE));
+    assert_eq!(stake_pool.next_instant_unstake, None);
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let stake_rent = rent.minimum_balance(std::mem::size_of::<stake::state::StakeState>());
+    let reserve_lamports = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.reserve_stake.pubkey(),
+    )
+    .await
+    .lamports;
+    let available_reserve_lamports = reserve_lamports - stake_rent - MINIMUM_RESERVE_LAMPORTS;
+
+    // the buffer is below target, so the fee is more than the minimum
+    let withdraw_pool_tokens = pool_tokens / 2;
+    let instant_unstake_fee = stake_pool
+        .calc_pool_tokens_instant_unstake_fee(withdraw_pool_tokens, available_reserve_lamports)
+        .unwrap();
+    assert!(
+        instant_unstake_fee > INSTANT_UNSTAKE.min_fee.apply(withdraw_pool_tokens).unwrap() as u64
+    );
+
+    let pre_fee_tokens = get_token_balance(
+        &mut context.banks_client,
+        &stake_pool_accounts.pool_fee_account.pubkey(),
+    )
+    .await;
+    let error = stake_pool_accounts
+        .withdraw_sol(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &user,
+            &pool_token_account,
+            withdraw_pool_tokens,
+            None,
+        )
+        .await;
+    assert!(error.is_none());
+
+    let post_fee_tokens = get_token_balance(
+        &mut context.banks_client,
+        &stake_pool_accounts.pool_fee_account.pubkey(),
+    )
+    .await;
+    assert_eq!(
+        post_fee_tokens - pre_fee_tokens,
+        stake_pool_accounts.calculate_withdrawal_fee(withdraw_pool_tokens) + instant_unstake_fee
+    );
+}
+
+#[tokio::test]
+async fn success_scheduled_decrease() {
+    let (mut context, stake_pool_accounts, validator_stake, _, _, _) = setup().await;
+
+    // the buffer is scheduled to be refilled on the next update
+    let stake_pool = get_stake_pool(&mut context.banks_client, &stake_pool_accounts).await;
+    assert_eq!(stake_pool.scheduled_decrease_lamports, 0);
+    let error = stake_pool_accounts
+        .update_stake_pool_balance(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+        )
+        .await;
+    assert!(error.is_none());
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let stake_rent = rent.minimum_balance(std::mem::size_of::<stake::state::StakeState>());
+    let reserve_lamports = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.reserve_stake.pubkey(),
+    )
+    .await
+    .lamports;
+    let stake_pool = get_stake_pool(&mut context.banks_client, &stake_pool_accounts).await;
+    let scheduled_decrease_lamports = stake_pool
+        .calc_instant_unstake_shortfall(reserve_lamports - stake_rent - MINIMUM_RESERVE_LAMPORTS, 0)
+        .unwrap();
+    assert!(scheduled_decrease_lamports > MINIMUM_ACTIVE_STAKE);
+    assert_eq!(
+        stake_pool.scheduled_decrease_lamports,
+        scheduled_decrease_lamports
+    );
+
+    // anyone can decrease up to the scheduled amount
+    let cranker = Keypair::new();
+    let decrease_lamports = MINIMUM_ACTIVE_STAKE;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::decrease_validator_stake(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &cranker.pubkey(),
+            &stake_pool_accounts.withdraw_authority,
+            &stake_pool_accounts.validator_list.pubkey(),
+            &validator_stake.stake_account,
+            &validator_stake.transient_stake_account,
+            decrease_lamports,
+            validator_stake.transient_stake_seed,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &cranker],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let stake_pool = get_stake_pool(&mut context.banks_client, &stake_pool_accounts).await;
+    assert_eq!(
+        stake_pool.scheduled_decrease_lamports,
+        scheduled_decrease_lamports - decrease_lamports
+    );
+
+    // the deactivating transient stake is not scheduled again on update
+    let last_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    let error = stake_pool_accounts
+        .update_stake_pool_balance(&mut context.banks_client, &context.payer, &last_blockhash)
+        .await;
+    assert!(error.is_none());
+    let stake_pool = get_stake_pool(&mut context.banks_client, &stake_pool_accounts).await;
+    assert_eq!(
+        stake_pool.scheduled_decrease_lamports,
+        scheduled_decrease_lamports - decrease_lamports
+    );
+}
+
+#[tokio::test]
+async fn fail_scheduled_decrease_not_largest_validator() {
+    let (mut context, stake_pool_accounts, _, _, _, _) = setup().await;
+    let small_validator_stake = simple_add_validator_to_pool(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &stake_pool_accounts,
+    )
+    .await;
+    let error = stake_pool_accounts
+        .update_stake_pool_balance(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+        )
+        .await;
+    assert!(error.is_none());
+
+    // anyone can only decrease the validator with the most active stake
+    let cranker = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::decrease_validator_stake(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &cranker.pubkey(),
+            &stake_pool_accounts.withdraw_authority,
+            &stake_pool_accounts.validator_list.pubkey(),
+            &small_validator_stake.stake_account,
+            &small_validator_stake.transient_stake_account,
+            MINIMUM_ACTIVE_STAKE,
+            small_validator_stake.transient_stake_seed,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &cranker],
+        context.last_blockhash,
+    );
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(StakePoolError::InvalidScheduledDecreaseValidator as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_decrease_more_than_scheduled() {
+    let (mut context, stake_pool_accounts, validator_stake, _, _, _) = setup().await;
+    let error = stake_pool_accounts
+        .update_stake_pool_balance(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+        )
+        .await;
+    assert!(error.is_none());
+    let stake_pool = get_stake_pool(&mut context.banks_client, &stake_pool_accounts).await;
+
+    let cranker = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::decrease_validator_stake(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &cranker.pubkey(),
+            &stake_pool_accounts.withdraw_authority,
+            &stake_pool_accounts.validator_list.pubkey(),
+            &validator_stake.stake_account,
+            &validator_stake.transient_stake_account,
+            stake_pool.scheduled_decrease_lamports + 1,
+            validator_stake.transient_stake_seed,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &cranker],
+        context.last_blockhash,
+    );
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(StakePoolError::WrongStaker as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_min_fee_above_max_fee() {
+    let (mut context, stake_pool_accounts, _, _, _, _) = setup().await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_fee(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &stake_pool_accounts.manager.pubkey(),
+            FeeType::InstantUnstake(Some(InstantUnstake {
+                min_fee: INSTANT_UNSTAKE.max_fee,
+                max_fee: INSTANT_UNSTAKE.min_fee,
+                ..INSTANT_UNSTAKE
+            })),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_pool_accounts.manager],
+        context.last_blockhash,
+    );
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(StakePoolError::FeeTooHigh as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_fee_increase_too_high() {
+    let (mut context, stake_pool_accounts, _, _, _, _) = setup().await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_fee(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &stake_pool_accounts.manager.pubkey(),
+            FeeType::InstantUnstake(Some(InstantUnstake {
+                max_fee: Fee {
+                    numerator: INSTANT_UNSTAKE.max_fee.numerator * 2,
+                    ..INSTANT_UNSTAKE.max_fee
+                },
+                ..INSTANT_UNSTAKE
+            })),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_pool_accounts.manager],
+        context.last_blockhash,
+    );
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(StakePoolError::FeeIncreaseTooHigh as u32)
+        )
+    );
+}
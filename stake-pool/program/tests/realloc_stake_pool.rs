@@ -0,0 +1,342 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use {
+    borsh::BorshSerialize,
+    helpers::*,
+    solana_program::{
+        borsh::{get_instance_packed_len, get_packed_len, try_from_slice_unchecked},
+        instruction::InstructionError,
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        system_instruction,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        account::{Account, AccountSharedData},
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    spl_stake_pool::{
+        error::StakePoolError,
+        id, instruction,
+        state::{Fee, StakePool, ValidatorHistoryList, LEGACY_STAKE_POOL_LEN},
+        MINIMUM_RESERVE_LAMPORTS,
+    },
+};
+
+async fn setup() -> (ProgramTestContext, StakePoolAccounts, StakePool) {
+    let mut context = program_test().start_with_context().await;
+    let stake_pool_accounts = StakePoolAccounts::new();
+    stake_pool_accounts
+        .initialize_stake_pool(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            MINIMUM_RESERVE_LAMPORTS,
+        )
+        .await
+        .unwrap();
+
+    // store the pool at the size used before instant unstakes and validator
+    // histories, with every optional field set so that the older layout is
+    // used entirely
+    let stake_pool_address = stake_pool_accounts.stake_pool.pubkey();
+    let stake_pool_account = get_account(&mut context.banks_client, &stake_pool_address).await;
+    let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_account.data).unwrap();
+    let fee = Fee {
+        numerator: 1,
+        denominator: 100,
+    };
+    let stake_pool = StakePool {
+        next_epoch_fee: Some(fee),
+        preferred_deposit_validator_vote_address: Some(Pubkey::new_unique()),
+        preferred_withdraw_validator_vote_address: Some(Pubkey::new_unique()),
+        next_stake_withdrawal_fee: Some(fee),
+        sol_deposit_authority: Some(Pubkey::new_unique()),
+        sol_withdraw_authority: Some(Pubkey::new_unique()),
+        next_sol_withdrawal_fee: Some(fee),
+        ..stake_pool
+    };
+    let mut data = vec![0u8; get_packed_len::<StakePool>()];
+    stake_pool.serialize(&mut data.as_mut_slice()).unwrap();
+    data.truncate(LEGACY_STAKE_POOL_LEN);
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let legacy_account = Account {
+        lamports: rent.minimum_balance(LEGACY_STAKE_POOL_LEN),
+        data,
+        owner: id(),
+        executable: false,
+        rent_epoch: stake_pool_account.rent_epoch,
+    };
+    context.set_account(
+        &stake_pool_address,
+        &AccountSharedData::from(legacy_account),
+    );
+
+    (context, stake_pool_accounts, stake_pool)
+}
+
+#[tokio::test]
+async fn success() {
+    let (mut context, stake_pool_accounts, stake_pool) = setup().await;
+
+    // the older layout cannot be used before reallocating
+    let error = stake_pool_accounts
+        .update_stake_pool_balance(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+        )
+        .await;
+    assert!(error.is_some());
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::realloc_stake_pool(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &context.payer.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let stake_pool_account = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.stake_pool.pubkey(),
+    )
+    .await;
+    assert_eq!(stake_pool_account.data.len(), get_packed_len::<StakePool>());
+    assert_eq!(
+        stake_pool_account.lamports,
+        rent.minimum_balance(get_packed_len::<StakePool>())
+    );
+    let reallocated_stake_pool =
+        try_from_slice_unchecked::<StakePool>(&stake_pool_account.data).unwrap();
+    assert_eq!(reallocated_stake_pool, stake_pool);
+    assert_eq!(reallocated_stake_pool.instant_unstake, None);
+    assert_eq!(reallocated_stake_pool.scheduled_decrease_lamports, 0);
+    assert_eq!(reallocated_stake_pool.validator_history, None);
+
+    let error = stake_pool_accounts
+        .update_stake_pool_balance(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+        )
+        .await;
+    assert!(error.is_none());
+
+    // reallocating again does nothing
+    let last_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::realloc_stake_pool(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &context.payer.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let stake_pool_account = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.stake_pool.pubkey(),
+    )
+    .await;
+    assert_eq!(stake_pool_account.data.len(), get_packed_len::<StakePool>());
+}
+
+#[tokio::test]
+async fn success_stale_tail() {
+    let (mut context, stake_pool_accounts, stake_pool) = setup().await;
+
+    // older programs serialized a cleared `next_epoch_fee` shorter, leaving
+    // the end of the previous serialization behind it
+    let stake_pool = StakePool {
+        last_epoch_pool_token_supply: 98 * LAMPORTS_PER_SOL,
+        last_epoch_total_lamports: 99 * LAMPORTS_PER_SOL,
+        ..stake_pool
+    };
+    let mut data = vec![0u8; get_packed_len::<StakePool>()];
+    stake_pool.serialize(&mut data.as_mut_slice()).unwrap();
+    let stake_pool = StakePool {
+        next_epoch_fee: None,
+        ..stake_pool
+    };
+    stake_pool.serialize(&mut data.as_mut_slice()).unwrap();
+    data.truncate(LEGACY_STAKE_POOL_LEN);
+
+    let stake_pool_address = stake_pool_accounts.stake_pool.pubkey();
+    let stake_pool_account = get_account(&mut context.banks_client, &stake_pool_address).await;
+    context.set_account(
+        &stake_pool_address,
+        &AccountSharedData::from(Account {
+            data,
+            ..stake_pool_account
+        }),
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::realloc_stake_pool(
+            &id(),
+            &stake_pool_address,
+            &context.payer.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let stake_pool_account = get_account(&mut context.banks_client, &stake_pool_address).await;
+    let reallocated_stake_pool =
+        try_from_slice_unchecked::<StakePool>(&stake_pool_account.data).unwrap();
+    assert_eq!(reallocated_stake_pool, stake_pool);
+    assert_eq!(reallocated_stake_pool.instant_unstake, None);
+    assert_eq!(reallocated_stake_pool.scheduled_decrease_lamports, 0);
+    assert_eq!(reallocated_stake_pool.validator_history, None);
+}
+
+#[tokio::test]
+async fn success_validator_history() {
+    let (mut context, stake_pool_accounts, _) = setup().await;
+
+    let validator_history = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let space = get_instance_packed_len(&ValidatorHistoryList::new(
+        stake_pool_accounts.max_validators,
+    ))
+    .unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &context.payer.pubkey(),
+            &validator_history.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &id(),
+        ),
+        instruction::initialize_validator_history(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &stake_pool_accounts.manager.pubkey(),
+            &stake_pool_accounts.validator_list.pubkey(),
+            &validator_history.pubkey(),
+        ),
+    ];
+    let signers = [
+        &context.payer,
+        &validator_history,
+        &stake_pool_accounts.manager,
+    ];
+
+    // the history cannot be recorded in the older layout
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &signers,
+        context.last_blockhash,
+    );
+    assert!(context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .is_err());
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::realloc_stake_pool(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &context.payer.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let last_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &signers,
+        last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let stake_pool_account = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.stake_pool.pubkey(),
+    )
+    .await;
+    let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_account.data).unwrap();
+    assert_eq!(
+        stake_pool.validator_history,
+        Some(validator_history.pubkey())
+    );
+}
+
+#[tokio::test]
+async fn fail_not_stake_pool() {
+    let (mut context, stake_pool_accounts, _) = setup().await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::realloc_stake_pool(
+            &id(),
+            &stake_pool_accounts.validator_list.pubkey(),
+            &context.payer.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(StakePoolError::InvalidState as u32)
+        )
+    );
+}
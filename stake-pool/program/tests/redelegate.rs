@@ -0,0 +1,789 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use {
+    bincode::deserialize,
+    helpers::*,
+    solana_program::{
+        borsh::try_from_slice_unchecked, clock::Epoch, instruction::InstructionError,
+        pubkey::Pubkey, stake,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        signature::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    spl_stake_pool::{
+        error::StakePoolError,
+        id, instruction,
+        state::{StakePool, StakeStatus},
+        MINIMUM_ACTIVE_STAKE, MINIMUM_RESERVE_LAMPORTS,
+    },
+};
+
+async fn setup(
+    reserve_lamports: u64,
+) -> (
+    ProgramTestContext,
+    StakePoolAccounts,
+    ValidatorStakeAccount,
+    ValidatorStakeAccount,
+    u64,
+) {
+    let mut context = program_test().start_with_context().await;
+    let first_normal_slot = context.genesis_config().epoch_schedule.first_normal_slot;
+    let slots_per_epoch = context.genesis_config().epoch_schedule.slots_per_epoch;
+    let mut slot = first_normal_slot;
+    context.warp_to_slot(slot).unwrap();
+
+    let stake_pool_accounts = StakePoolAccounts::new();
+    stake_pool_accounts
+        .initialize_stake_pool(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            reserve_lamports,
+        )
+        .await
+        .unwrap();
+
+    let source_validator_stake = simple_add_validator_to_pool(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &stake_pool_accounts,
+    )
+    .await;
+    let destination_validator_stake = simple_add_validator_to_pool(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &stake_pool_accounts,
+    )
+    .await;
+
+    let _deposit_info = simple_deposit_stake(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &stake_pool_accounts,
+        &source_validator_stake,
+        2 * TEST_STAKE_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    slot += slots_per_epoch;
+    context.warp_to_slot(slot).unwrap();
+    stake_pool_accounts
+        .update_all(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &[
+                source_validator_stake.vote.pubkey(),
+                destination_validator_stake.vote.pubkey(),
+            ],
+            false,
+        )
+        .await;
+
+    (
+        context,
+        stake_pool_accounts,
+        source_validator_stake,
+        destination_validator_stake,
+        slot,
+    )
+}
+
+#[tokio::test]
+async fn success() {
+    let (
+        mut context,
+        stake_pool_accounts,
+        source_validator_stake,
+        destination_validator_stake,
+        mut slot,
+    ) = setup(10 * TEST_STAKE_AMOUNT + MINIMUM_RESERVE_LAMPORTS).await;
+
+    let pre_reserve_lamports = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.reserve_stake.pubkey(),
+    )
+    .await
+    .lamports;
+    let pre_source_lamports = get_account(
+        &mut context.banks_client,
+        &source_validator_stake.stake_account,
+    )
+    .await
+    .lamports;
+    let pre_destination_lamports = get_account(
+        &mut context.banks_client,
+        &destination_validator_stake.stake_account,
+    )
+    .await
+    .lamports;
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let stake_rent = rent.minimum_balance(std::mem::size_of::<stake::state::StakeState>());
+    let redelegate_amount = MINIMUM_ACTIVE_STAKE;
+    let error = stake_pool_accounts
+        .redelegate(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &source_validator_stake,
+            &destination_validator_stake,
+            redelegate_amount,
+        )
+        .await;
+    assert!(error.is_none());
+
+    // Check the reserve fronts the destination stake
+    let reserve_stake_account = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.reserve_stake.pubkey(),
+    )
+    .await;
+    assert_eq!(
+        reserve_stake_account.lamports,
+        pre_reserve_lamports - redelegate_amount - stake_rent
+    );
+
+    // Check source transient stake is deactivating
+    let source_transient_account = get_account(
+        &mut context.banks_client,
+        &source_validator_stake.transient_stake_account,
+    )
+    .await;
+    assert_eq!(source_transient_account.lamports, redelegate_amount);
+    let source_transient_state =
+        deserialize::<stake::state::StakeState>(&source_transient_account.data).unwrap();
+    assert_ne!(
+        source_transient_state
+            .delegation()
+            .unwrap()
+            .deactivation_epoch,
+        Epoch::MAX
+    );
+
+    // Check destination transient stake is activating to the destination
+    let destination_transient_account = get_account(
+        &mut context.banks_client,
+        &destination_validator_stake.transient_stake_account,
+    )
+    .await;
+    assert_eq!(
+        destination_transient_account.lamports,
+        redelegate_amount + stake_rent
+    );
+    let destination_delegation =
+        deserialize::<stake::state::StakeState>(&destination_transient_account.data)
+            .unwrap()
+            .delegation()
+            .unwrap();
+    assert_eq!(
+        destination_delegation.voter_pubkey,
+        destination_validator_stake.vote.pubkey()
+    );
+    assert_ne!(destination_delegation.activation_epoch, Epoch::MAX);
+
+    // Check validator list
+    let validator_list = stake_pool_accounts
+        .get_validator_list(&mut context.banks_client)
+        .await;
+    let source_item = validator_list
+        .find(&source_validator_stake.vote.pubkey())
+        .unwrap();
+    assert_eq!(source_item.transient_stake_lamports, redelegate_amount);
+    let destination_item = validator_list
+        .find(&destination_validator_stake.vote.pubkey())
+        .unwrap();
+    assert_eq!(
+        destination_item.transient_stake_lamports,
+        redelegate_amount + stake_rent
+    );
+    assert_eq!(destination_item.status, StakeStatus::Active);
+
+    // Next epoch, the source stake goes back to the reserve, and the
+    // destination stake is merged into the destination validator stake
+    let slots_per_epoch = context.genesis_config().epoch_schedule.slots_per_epoch;
+    slot += slots_per_epoch;
+    context.warp_to_slot(slot).unwrap();
+    stake_pool_accounts
+        .update_all(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &[
+                source_validator_stake.vote.pubkey(),
+                destination_validator_stake.vote.pubkey(),
+            ],
+            false,
+        )
+        .await;
+
+    let source_transient_account = context
+        .banks_client
+        .get_account(source_validator_stake.transient_stake_account)
+        .await
+        .unwrap();
+    assert!(source_transient_account.is_none());
+    let destination_transient_account = context
+        .banks_client
+        .get_account(destination_validator_stake.transient_stake_account)
+        .await
+        .unwrap();
+    assert!(destination_transient_account.is_none());
+
+    let reserve_lamports = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.reserve_stake.pubkey(),
+    )
+    .await
+    .lamports;
+    assert_eq!(reserve_lamports, pre_reserve_lamports);
+    let source_lamports = get_account(
+        &mut context.banks_client,
+        &source_validator_stake.stake_account,
+    )
+    .await
+    .lamports;
+    assert_eq!(source_lamports, pre_source_lamports - redelegate_amount);
+    let destination_lamports = get_account(
+        &mut context.banks_client,
+        &destination_validator_stake.stake_account,
+    )
+    .await
+    .lamports;
+    assert_eq!(
+        destination_lamports,
+        pre_destination_lamports + redelegate_amount
+    );
+}
+
+#[tokio::test]
+async fn success_update_in_same_epoch() {
+    let (
+        mut context,
+        stake_pool_accounts,
+        source_validator_stake,
+        destination_validator_stake,
+        _slot,
+    ) = setup(10 * TEST_STAKE_AMOUNT + MINIMUM_RESERVE_LAMPORTS).await;
+    let pre_stake_pool = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.stake_pool.pubkey(),
+    )
+    .await;
+    let pre_stake_pool =
+        try_from_slice_unchecked::<StakePool>(pre_stake_pool.data.as_slice()).unwrap();
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let stake_rent = rent.minimum_balance(std::mem::size_of::<stake::state::StakeState>());
+    let redelegate_amount = MINIMUM_ACTIVE_STAKE;
+    let error = stake_pool_accounts
+        .redelegate(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &source_validator_stake,
+            &destination_validator_stake,
+            redelegate_amount,
+        )
+        .await;
+    assert!(error.is_none());
+
+    // Neither transient stake can be merged yet: the source stake is still
+    // deactivating, and the destination stake is still activating
+    let last_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    let error = stake_pool_accounts
+        .update_all(
+            &mut context.banks_client,
+            &context.payer,
+            &last_blockhash,
+            &[
+                source_validator_stake.vote.pubkey(),
+                destination_validator_stake.vote.pubkey(),
+            ],
+            false,
+        )
+        .await;
+    assert!(error.is_none());
+
+    let source_transient_account = get_account(
+        &mut context.banks_client,
+        &source_validator_stake.transient_stake_account,
+    )
+    .await;
+    assert_eq!(source_transient_account.lamports, redelegate_amount);
+    let destination_transient_account = get_account(
+        &mut context.banks_client,
+        &destination_validator_stake.transient_stake_account,
+    )
+    .await;
+    assert_eq!(
+        destination_transient_account.lamports,
+        redelegate_amount + stake_rent
+    );
+
+    let validator_list = stake_pool_accounts
+        .get_validator_list(&mut context.banks_client)
+        .await;
+    let source_item = validator_list
+        .find(&source_validator_stake.vote.pubkey())
+        .unwrap();
+    assert_eq!(source_item.transient_stake_lamports, redelegate_amount);
+    let destination_item = validator_list
+        .find(&destination_validator_stake.vote.pubkey())
+        .unwrap();
+    assert_eq!(
+        destination_item.transient_stake_lamports,
+        redelegate_amount + stake_rent
+    );
+
+    // The reserve fronting the destination stake is offset by the source
+    // transient stake, so the pool value doesn't change
+    let stake_pool = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.stake_pool.pubkey(),
+    )
+    .await;
+    let stake_pool = try_from_slice_unchecked::<StakePool>(stake_pool.data.as_slice()).unwrap();
+    assert_eq!(stake_pool.total_lamports, pre_stake_pool.total_lamports);
+}
+
+#[tokio::test]
+async fn success_update_with_no_merge() {
+    let (
+        mut context,
+        stake_pool_accounts,
+        source_validator_stake,
+        destination_validator_stake,
+        mut slot,
+    ) = setup(10 * TEST_STAKE_AMOUNT + MINIMUM_RESERVE_LAMPORTS).await;
+    let pre_stake_pool = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.stake_pool.pubkey(),
+    )
+    .await;
+    let pre_stake_pool =
+        try_from_slice_unchecked::<StakePool>(pre_stake_pool.data.as_slice()).unwrap();
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let stake_rent = rent.minimum_balance(std::mem::size_of::<stake::state::StakeState>());
+    let redelegate_amount = MINIMUM_ACTIVE_STAKE;
+    let error = stake_pool_accounts
+        .redelegate(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &source_validator_stake,
+            &destination_validator_stake,
+            redelegate_amount,
+        )
+        .await;
+    assert!(error.is_none());
+
+    // Next epoch, both transient stakes are ready to be merged, but are only
+    // accounted
+    let slots_per_epoch = context.genesis_config().epoch_schedule.slots_per_epoch;
+    slot += slots_per_epoch;
+    context.warp_to_slot(slot).unwrap();
+    let error = stake_pool_accounts
+        .update_all(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &[
+                source_validator_stake.vote.pubkey(),
+                destination_validator_stake.vote.pubkey(),
+            ],
+            true,
+        )
+        .await;
+    assert!(error.is_none());
+
+    let source_transient_account = get_account(
+        &mut context.banks_client,
+        &source_validator_stake.transient_stake_account,
+    )
+    .await;
+    assert_eq!(source_transient_account.lamports, redelegate_amount);
+    let destination_transient_account = get_account(
+        &mut context.banks_client,
+        &destination_validator_stake.transient_stake_account,
+    )
+    .await;
+    assert_eq!(
+        destination_transient_account.lamports,
+        redelegate_amount + stake_rent
+    );
+
+    let validator_list = stake_pool_accounts
+        .get_validator_list(&mut context.banks_client)
+        .await;
+    let source_item = validator_list
+        .find(&source_validator_stake.vote.pubkey())
+        .unwrap();
+    assert_eq!(source_item.transient_stake_lamports, redelegate_amount);
+    let destination_item = validator_list
+        .find(&destination_validator_stake.vote.pubkey())
+        .unwrap();
+    assert_eq!(
+        destination_item.transient_stake_lamports,
+        redelegate_amount + stake_rent
+    );
+
+    let stake_pool = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.stake_pool.pubkey(),
+    )
+    .await;
+    let stake_pool = try_from_slice_unchecked::<StakePool>(stake_pool.data.as_slice()).unwrap();
+    assert_eq!(stake_pool.total_lamports, pre_stake_pool.total_lamports);
+
+    // Merging both transient stakes keeps the pool value
+    let last_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    let error = stake_pool_accounts
+        .update_all(
+            &mut context.banks_client,
+            &context.payer,
+            &last_blockhash,
+            &[
+                source_validator_stake.vote.pubkey(),
+                destination_validator_stake.vote.pubkey(),
+            ],
+            false,
+        )
+        .await;
+    assert!(error.is_none());
+
+    let validator_list = stake_pool_accounts
+        .get_validator_list(&mut context.banks_client)
+        .await;
+    let source_item = validator_list
+        .find(&source_validator_stake.vote.pubkey())
+        .unwrap();
+    assert_eq!(source_item.transient_stake_lamports, 0);
+    let destination_item = validator_list
+        .find(&destination_validator_stake.vote.pubkey())
+        .unwrap();
+    assert_eq!(destination_item.transient_stake_lamports, 0);
+
+    let stake_pool = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.stake_pool.pubkey(),
+    )
+    .await;
+    let stake_pool = try_from_slice_unchecked::<StakePool>(stake_pool.data.as_slice()).unwrap();
+    assert_eq!(stake_pool.total_lamports, pre_stake_pool.total_lamports);
+}
+
+#[tokio::test]
+async fn success_with_vote() {
+    let (
+        mut context,
+        stake_pool_accounts,
+        source_validator_stake,
+        destination_validator_stake,
+        _slot,
+    ) = setup(10 * TEST_STAKE_AMOUNT + MINIMUM_RESERVE_LAMPORTS).await;
+
+    let stake_pool = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.stake_pool.pubkey(),
+    )
+    .await;
+    let stake_pool = try_from_slice_unchecked::<StakePool>(stake_pool.data.as_slice()).unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::redelegate_with_vote(
+            &id(),
+            &stake_pool,
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &source_validator_stake.vote.pubkey(),
+            source_validator_stake.transient_stake_seed,
+            &destination_validator_stake.vote.pubkey(),
+            destination_validator_stake.transient_stake_seed,
+            MINIMUM_ACTIVE_STAKE,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_pool_accounts.staker],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let destination_transient_account = get_account(
+        &mut context.banks_client,
+        &destination_validator_stake.transient_stake_account,
+    )
+    .await;
+    assert!(destination_transient_account.lamports > MINIMUM_ACTIVE_STAKE);
+}
+
+#[tokio::test]
+async fn fail_with_same_validator() {
+    let (mut context, stake_pool_accounts, source_validator_stake, _, _) =
+        setup(10 * TEST_STAKE_AMOUNT + MINIMUM_RESERVE_LAMPORTS).await;
+
+    let error = stake_pool_accounts
+        .redelegate(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &source_validator_stake,
+            &source_validator_stake,
+            MINIMUM_ACTIVE_STAKE,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(StakePoolError::InvalidStakeAccountAddress as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_with_unknown_validator() {
+    let (mut context, stake_pool_accounts, source_validator_stake, _, _) =
+        setup(10 * TEST_STAKE_AMOUNT + MINIMUM_RESERVE_LAMPORTS).await;
+
+    let unknown_stake = create_unknown_validator_stake(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &stake_pool_accounts.stake_pool.pubkey(),
+    )
+    .await;
+
+    let error = stake_pool_accounts
+        .redelegate(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &source_validator_stake,
+            &unknown_stake,
+            MINIMUM_ACTIVE_STAKE,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(StakePoolError::ValidatorNotFound as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_redelegate_twice() {
+    let (
+        mut context,
+        stake_pool_accounts,
+        source_validator_stake,
+        destination_validator_stake,
+        _slot,
+    ) = setup(10 * TEST_STAKE_AMOUNT + MINIMUM_RESERVE_LAMPORTS).await;
+
+    let error = stake_pool_accounts
+        .redelegate(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &source_validator_stake,
+            &destination_validator_stake,
+            MINIMUM_ACTIVE_STAKE,
+        )
+        .await;
+    assert!(error.is_none());
+
+    let error = stake_pool_accounts
+        .redelegate(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &source_validator_stake,
+            &destination_validator_stake,
+            MINIMUM_ACTIVE_STAKE,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    match error {
+        TransactionError::InstructionError(_, InstructionError::Custom(error_index)) => {
+            let program_error = StakePoolError::TransientAccountInUse as u32;
+            assert_eq!(error_index, program_error);
+        }
+        _ => panic!("Wrong error"),
+    }
+}
+
+#[tokio::test]
+async fn fail_with_small_lamport_amount() {
+    let (
+        mut context,
+        stake_pool_accounts,
+        source_validator_stake,
+        destination_validator_stake,
+        _slot,
+    ) = setup(10 * TEST_STAKE_AMOUNT + MINIMUM_RESERVE_LAMPORTS).await;
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let stake_rent = rent.minimum_balance(std::mem::size_of::<stake::state::StakeState>());
+
+    let error = stake_pool_accounts
+        .redelegate(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &source_validator_stake,
+            &destination_validator_stake,
+            stake_rent,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    match error {
+        TransactionError::InstructionError(_, InstructionError::AccountNotRentExempt) => {}
+        _ => panic!("Wrong error"),
+    }
+}
+
+#[tokio::test]
+async fn fail_overdraw_source() {
+    let (
+        mut context,
+        stake_pool_accounts,
+        source_validator_stake,
+        destination_validator_stake,
+        _slot,
+    ) = setup(10 * TEST_STAKE_AMOUNT + MINIMUM_RESERVE_LAMPORTS).await;
+
+    let source_lamports = get_account(
+        &mut context.banks_client,
+        &source_validator_stake.stake_account,
+    )
+    .await
+    .lamports;
+
+    let error = stake_pool_accounts
+        .redelegate(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &source_validator_stake,
+            &destination_validator_stake,
+            source_lamports,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    match error {
+        TransactionError::InstructionError(_, InstructionError::InsufficientFunds) => {}
+        _ => panic!("Wrong error occurs while overdrawing source stake"),
+    }
+}
+
+#[tokio::test]
+async fn fail_overdraw_reserve() {
+    let (
+        mut context,
+        stake_pool_accounts,
+        source_validator_stake,
+        destination_validator_stake,
+        _slot,
+    ) = setup(MINIMUM_RESERVE_LAMPORTS).await;
+
+    let error = stake_pool_accounts
+        .redelegate(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &source_validator_stake,
+            &destination_validator_stake,
+            TEST_STAKE_AMOUNT,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    match error {
+        TransactionError::InstructionError(_, InstructionError::InsufficientFunds) => {}
+        _ => panic!("Wrong error occurs while overdrawing reserve stake"),
+    }
+}
+
+#[tokio::test]
+async fn fail_with_wrong_withdraw_authority() {
+    let (
+        mut context,
+        stake_pool_accounts,
+        source_validator_stake,
+        destination_validator_stake,
+        _slot,
+    ) = setup(10 * TEST_STAKE_AMOUNT + MINIMUM_RESERVE_LAMPORTS).await;
+
+    let wrong_authority = Pubkey::new_unique();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::redelegate(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &stake_pool_accounts.staker.pubkey(),
+            &wrong_authority,
+            &stake_pool_accounts.validator_list.pubkey(),
+            &stake_pool_accounts.reserve_stake.pubkey(),
+            &source_validator_stake.stake_account,
+            &source_validator_stake.transient_stake_account,
+            &destination_validator_stake.transient_stake_account,
+            &destination_validator_stake.stake_account,
+            &destination_validator_stake.vote.pubkey(),
+            MINIMUM_ACTIVE_STAKE,
+            source_validator_stake.transient_stake_seed,
+            destination_validator_stake.transient_stake_seed,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &stake_pool_accounts.staker],
+        context.last_blockhash,
+    );
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    match error {
+        TransactionError::InstructionError(_, InstructionError::Custom(error_index)) => {
+            let program_error = StakePoolError::InvalidProgramAddress as u32;
+            assert_eq!(error_index, program_error);
+        }
+        _ => panic!("Wrong error"),
+    }
+}
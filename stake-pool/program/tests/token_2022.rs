@@ -0,0 +1,368 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program::{
+        borsh::{get_instance_packed_len, get_packed_len, try_from_slice_unchecked},
+        instruction::{Instruction, InstructionError},
+        pubkey::Pubkey,
+        stake, system_instruction,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    spl_stake_pool::{error::StakePoolError, id, instruction, state, MINIMUM_RESERVE_LAMPORTS},
+    spl_token_2022::{
+        extension::{transfer_fee, ExtensionType, StateWithExtensions},
+        state::{Account, Mint},
+    },
+};
+
+fn program_test_with_token_2022() -> ProgramTest {
+    let mut program_test = program_test();
+    program_test.add_program(
+        "spl_token_2022",
+        spl_token_2022::id(),
+        processor!(spl_token_2022::processor::Processor::process),
+    );
+    program_test
+}
+
+async fn create_token_2022_mint(
+    context: &mut ProgramTestContext,
+    pool_mint: &Keypair,
+    mint_authority: &Pubkey,
+    extension_types: &[ExtensionType],
+    extension_instructions: Vec<Instruction>,
+) {
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let space = ExtensionType::get_account_len::<Mint>(extension_types);
+    let mut instructions = vec![system_instruction::create_account(
+        &context.payer.pubkey(),
+        &pool_mint.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &spl_token_2022::id(),
+    )];
+    instructions.extend(extension_instructions);
+    instructions.push(
+        spl_token_2022::instruction::initialize_mint(
+            &spl_token_2022::id(),
+            &pool_mint.pubkey(),
+            mint_authority,
+            None,
+            0,
+        )
+        .unwrap(),
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, pool_mint],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+async fn create_token_2022_account(
+    context: &mut ProgramTestContext,
+    account: &Keypair,
+    pool_mint: &Pubkey,
+    owner: &Pubkey,
+) {
+    let mint_account = get_account(&mut context.banks_client, pool_mint).await;
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+    let extension_types =
+        ExtensionType::get_required_init_account_extensions(&mint.get_extension_types().unwrap());
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let space = ExtensionType::get_account_len::<Account>(&extension_types);
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::instruction::initialize_account(
+                &spl_token_2022::id(),
+                &account.pubkey(),
+                pool_mint,
+                owner,
+            )
+            .unwrap(),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, account],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+async fn get_token_2022_balance(context: &mut ProgramTestContext, account: &Pubkey) -> u64 {
+    let token_account = get_account(&mut context.banks_client, account).await;
+    StateWithExtensions::<Account>::unpack(&token_account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+async fn initialize_token_2022_stake_pool(
+    context: &mut ProgramTestContext,
+    stake_pool_accounts: &StakePoolAccounts,
+    extension_types: &[ExtensionType],
+    extension_instructions: Vec<Instruction>,
+) -> Result<(), TransactionError> {
+    create_token_2022_mint(
+        context,
+        &stake_pool_accounts.pool_mint,
+        &stake_pool_accounts.withdraw_authority,
+        extension_types,
+        extension_instructions,
+    )
+    .await;
+    create_token_2022_account(
+        context,
+        &stake_pool_accounts.pool_fee_account,
+        &stake_pool_accounts.pool_mint.pubkey(),
+        &stake_pool_accounts.manager.pubkey(),
+    )
+    .await;
+    create_independent_stake_account(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &stake_pool_accounts.reserve_stake,
+        &stake::state::Authorized {
+            staker: stake_pool_accounts.withdraw_authority,
+            withdrawer: stake_pool_accounts.withdraw_authority,
+        },
+        &stake::state::Lockup::default(),
+        MINIMUM_RESERVE_LAMPORTS,
+    )
+    .await;
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let validator_list_size = get_instance_packed_len(&state::ValidatorList::new(
+        stake_pool_accounts.max_validators,
+    ))
+    .unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &stake_pool_accounts.stake_pool.pubkey(),
+                rent.minimum_balance(get_packed_len::<state::StakePool>()),
+                get_packed_len::<state::StakePool>() as u64,
+                &id(),
+            ),
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &stake_pool_accounts.validator_list.pubkey(),
+                rent.minimum_balance(validator_list_size),
+                validator_list_size as u64,
+                &id(),
+            ),
+            instruction::initialize(
+                &id(),
+                &stake_pool_accounts.stake_pool.pubkey(),
+                &stake_pool_accounts.manager.pubkey(),
+                &stake_pool_accounts.staker.pubkey(),
+                &stake_pool_accounts.withdraw_authority,
+                &stake_pool_accounts.validator_list.pubkey(),
+                &stake_pool_accounts.reserve_stake.pubkey(),
+                &stake_pool_accounts.pool_mint.pubkey(),
+                &stake_pool_accounts.pool_fee_account.pubkey(),
+                &spl_token_2022::id(),
+                None,
+                stake_pool_accounts.epoch_fee,
+                stake_pool_accounts.withdrawal_fee,
+                stake_pool_accounts.deposit_fee,
+                stake_pool_accounts.referral_fee,
+                stake_pool_accounts.max_validators,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[
+            &context.payer,
+            &stake_pool_accounts.stake_pool,
+            &stake_pool_accounts.validator_list,
+            &stake_pool_accounts.manager,
+        ],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|e| e.unwrap())
+}
+
+#[tokio::test]
+async fn success() {
+    let mut context = program_test_with_token_2022().start_with_context().await;
+    let stake_pool_accounts = StakePoolAccounts::new();
+    initialize_token_2022_stake_pool(&mut context, &stake_pool_accounts, &[], vec![])
+        .await
+        .unwrap();
+
+    let stake_pool = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.stake_pool.pubkey(),
+    )
+    .await;
+    let stake_pool =
+        try_from_slice_unchecked::<state::StakePool>(stake_pool.data.as_slice()).unwrap();
+    assert_eq!(stake_pool.token_program_id, spl_token_2022::id());
+
+    // pool tokens are minted, burned and transferred through token-2022
+    let user = Keypair::new();
+    let pool_token_account = Keypair::new();
+    create_token_2022_account(
+        &mut context,
+        &pool_token_account,
+        &stake_pool_accounts.pool_mint.pubkey(),
+        &user.pubkey(),
+    )
+    .await;
+    let referrer_token_account = Keypair::new();
+    create_token_2022_account(
+        &mut context,
+        &referrer_token_account,
+        &stake_pool_accounts.pool_mint.pubkey(),
+        &Pubkey::new_unique(),
+    )
+    .await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::deposit_sol(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &stake_pool_accounts.withdraw_authority,
+            &stake_pool_accounts.reserve_stake.pubkey(),
+            &context.payer.pubkey(),
+            &pool_token_account.pubkey(),
+            &stake_pool_accounts.pool_fee_account.pubkey(),
+            &referrer_token_account.pubkey(),
+            &stake_pool_accounts.pool_mint.pubkey(),
+            &spl_token_2022::id(),
+            TEST_STAKE_AMOUNT,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let pool_tokens = get_token_2022_balance(&mut context, &pool_token_account.pubkey()).await;
+    assert!(pool_tokens > 0);
+
+    let pre_fee_tokens =
+        get_token_2022_balance(&mut context, &stake_pool_accounts.pool_fee_account.pubkey()).await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::withdraw_sol(
+            &id(),
+            &stake_pool_accounts.stake_pool.pubkey(),
+            &stake_pool_accounts.withdraw_authority,
+            &user.pubkey(),
+            &pool_token_account.pubkey(),
+            &stake_pool_accounts.reserve_stake.pubkey(),
+            &user.pubkey(),
+            &stake_pool_accounts.pool_fee_account.pubkey(),
+            &stake_pool_accounts.pool_mint.pubkey(),
+            &spl_token_2022::id(),
+            pool_tokens,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &user],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        get_token_2022_balance(&mut context, &pool_token_account.pubkey()).await,
+        0
+    );
+    let post_fee_tokens =
+        get_token_2022_balance(&mut context, &stake_pool_accounts.pool_fee_account.pubkey()).await;
+    assert_eq!(
+        post_fee_tokens - pre_fee_tokens,
+        stake_pool_accounts.calculate_withdrawal_fee(pool_tokens)
+    );
+}
+
+#[tokio::test]
+async fn fail_with_transfer_fee_config() {
+    let mut context = program_test_with_token_2022().start_with_context().await;
+    let stake_pool_accounts = StakePoolAccounts::new();
+    let error = initialize_token_2022_stake_pool(
+        &mut context,
+        &stake_pool_accounts,
+        &[ExtensionType::TransferFeeConfig],
+        vec![transfer_fee::instruction::initialize_transfer_fee_config(
+            &spl_token_2022::id(),
+            &stake_pool_accounts.pool_mint.pubkey(),
+            None,
+            None,
+            100,
+            u64::MAX,
+        )
+        .unwrap()],
+    )
+    .await
+    .unwrap_err();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(StakePoolError::UnsupportedMintExtension as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_with_non_transferable() {
+    let mut context = program_test_with_token_2022().start_with_context().await;
+    let stake_pool_accounts = StakePoolAccounts::new();
+    let error = initialize_token_2022_stake_pool(
+        &mut context,
+        &stake_pool_accounts,
+        &[ExtensionType::NonTransferable],
+        vec![
+            spl_token_2022::instruction::initialize_non_transferable_mint(
+                &spl_token_2022::id(),
+                &stake_pool_accounts.pool_mint.pubkey(),
+            )
+            .unwrap(),
+        ],
+    )
+    .await
+    .unwrap_err();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(StakePoolError::UnsupportedMintExtension as u32)
+        )
+    );
+}
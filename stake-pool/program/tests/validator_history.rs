@@ -0,0 +1,349 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use {
+    helpers::*,
+    solana_program::{
+        borsh::{get_instance_packed_len, try_from_slice_unchecked},
+        instruction::{AccountMeta, InstructionError},
+        system_instruction,
+    },
+    solana_program_test::*,
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+        transport::TransportError,
+    },
+    spl_stake_pool::{
+        error::StakePoolError,
+        id, instruction,
+        state::{AccountType, StakePool, ValidatorHistoryList},
+        MINIMUM_ACTIVE_STAKE, MINIMUM_RESERVE_LAMPORTS,
+    },
+};
+
+async fn setup() -> (
+    ProgramTestContext,
+    StakePoolAccounts,
+    ValidatorStakeAccount,
+    u64,
+) {
+    let mut context = program_test().start_with_context().await;
+    let first_normal_slot = context.genesis_config().epoch_schedule.first_normal_slot;
+    let slots_per_epoch = context.genesis_config().epoch_schedule.slots_per_epoch;
+    let mut slot = first_normal_slot;
+    context.warp_to_slot(slot).unwrap();
+
+    let stake_pool_accounts = StakePoolAccounts::new();
+    stake_pool_accounts
+        .initialize_stake_pool(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            MINIMUM_RESERVE_LAMPORTS,
+        )
+        .await
+        .unwrap();
+
+    let validator_stake = simple_add_validator_to_pool(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &stake_pool_accounts,
+    )
+    .await;
+
+    let _deposit_info = simple_deposit_stake(
+        &mut context.banks_client,
+        &context.payer,
+        &context.last_blockhash,
+        &stake_pool_accounts,
+        &validator_stake,
+        TEST_STAKE_AMOUNT,
+    )
+    .await
+    .unwrap();
+
+    slot += slots_per_epoch;
+    context.warp_to_slot(slot).unwrap();
+    stake_pool_accounts
+        .update_all(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &[validator_stake.vote.pubkey()],
+            false,
+        )
+        .await;
+
+    (context, stake_pool_accounts, validator_stake, slot)
+}
+
+async fn create_validator_history(
+    context: &mut ProgramTestContext,
+    stake_pool_accounts: &StakePoolAccounts,
+    validator_history: &Keypair,
+    max_validators: u32,
+    manager: &Keypair,
+) -> Result<(), TransactionError> {
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let space = get_instance_packed_len(&ValidatorHistoryList::new(max_validators)).unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &validator_history.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &id(),
+            ),
+            instruction::initialize_validator_history(
+                &id(),
+                &stake_pool_accounts.stake_pool.pubkey(),
+                &manager.pubkey(),
+                &stake_pool_accounts.validator_list.pubkey(),
+                &validator_history.pubkey(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, validator_history, manager],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|e| e.unwrap())
+}
+
+#[tokio::test]
+async fn success() {
+    let (mut context, stake_pool_accounts, validator_stake, mut slot) = setup().await;
+
+    let validator_history = Keypair::new();
+    create_validator_history(
+        &mut context,
+        &stake_pool_accounts,
+        &validator_history,
+        stake_pool_accounts.max_validators,
+        &stake_pool_accounts.manager,
+    )
+    .await
+    .unwrap();
+
+    let stake_pool = get_account(
+        &mut context.banks_client,
+        &stake_pool_accounts.stake_pool.pubkey(),
+    )
+    .await;
+    let stake_pool = try_from_slice_unchecked::<StakePool>(stake_pool.data.as_slice()).unwrap();
+    assert_eq!(
+        stake_pool.validator_history,
+        Some(validator_history.pubkey())
+    );
+
+    // the update of the next epoch records the validator
+    slot += context.genesis_config().epoch_schedule.slots_per_epoch;
+    context.warp_to_slot(slot).unwrap();
+    let validator_list = stake_pool_accounts
+        .get_validator_list(&mut context.banks_client)
+        .await;
+    let previous_active_stake_lamports = validator_list.validators[0].active_stake_lamports;
+    let (update_list_instructions, final_instructions) = instruction::update_stake_pool(
+        &id(),
+        &stake_pool,
+        &validator_list,
+        &stake_pool_accounts.stake_pool.pubkey(),
+        false,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[update_list_instructions, final_instructions].concat(),
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let validator_list = stake_pool_accounts
+        .get_validator_list(&mut context.banks_client)
+        .await;
+    let epoch = validator_list.validators[0].last_update_epoch;
+    let history = get_account(&mut context.banks_client, &validator_history.pubkey()).await;
+    let history =
+        try_from_slice_unchecked::<ValidatorHistoryList>(history.data.as_slice()).unwrap();
+    assert_eq!(history.header.account_type, AccountType::ValidatorHistory);
+    assert_eq!(
+        history.header.stake_pool,
+        stake_pool_accounts.stake_pool.pubkey()
+    );
+    assert_eq!(history.validators.len(), 1);
+    let validator = history.find(&validator_stake.vote.pubkey()).unwrap();
+    assert_eq!(validator.last_update_epoch, epoch);
+    assert_eq!(
+        validator.totals(epoch),
+        (
+            (previous_active_stake_lamports + MINIMUM_ACTIVE_STAKE) as u128,
+            (validator_list.validators[0].active_stake_lamports - previous_active_stake_lamports)
+                as u128
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_update_without_history() {
+    let (mut context, stake_pool_accounts, validator_stake, mut slot) = setup().await;
+
+    let validator_history = Keypair::new();
+    create_validator_history(
+        &mut context,
+        &stake_pool_accounts,
+        &validator_history,
+        stake_pool_accounts.max_validators,
+        &stake_pool_accounts.manager,
+    )
+    .await
+    .unwrap();
+
+    // updates cannot skip recording the history of the pool
+    slot += context.genesis_config().epoch_schedule.slots_per_epoch;
+    context.warp_to_slot(slot).unwrap();
+    let error = stake_pool_accounts
+        .update_validator_list_balance(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            &[validator_stake.vote.pubkey()],
+            false,
+        )
+        .await
+        .unwrap();
+
+    match error {
+        TransportError::TransactionError(TransactionError::InstructionError(_, error)) => {
+            assert_eq!(
+                error,
+                InstructionError::Custom(StakePoolError::InvalidValidatorHistory as u32)
+            );
+        }
+        _ => panic!("Wrong error occurs while updating without the validator history"),
+    }
+}
+
+#[tokio::test]
+async fn fail_wrong_manager() {
+    let (mut context, stake_pool_accounts, _, _) = setup().await;
+
+    let error = create_validator_history(
+        &mut context,
+        &stake_pool_accounts,
+        &Keypair::new(),
+        stake_pool_accounts.max_validators,
+        &Keypair::new(),
+    )
+    .await
+    .unwrap_err();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(StakePoolError::WrongManager as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_too_small() {
+    let (mut context, stake_pool_accounts, _, _) = setup().await;
+
+    let error = create_validator_history(
+        &mut context,
+        &stake_pool_accounts,
+        &Keypair::new(),
+        stake_pool_accounts.max_validators - 1,
+        &stake_pool_accounts.manager,
+    )
+    .await
+    .unwrap_err();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(StakePoolError::UnexpectedValidatorListAccountSize as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn fail_update_with_other_pool_history() {
+    let (mut context, stake_pool_accounts, validator_stake, mut slot) = setup().await;
+
+    // a history initialized for another pool cannot be used
+    let other_stake_pool_accounts = StakePoolAccounts::new();
+    other_stake_pool_accounts
+        .initialize_stake_pool(
+            &mut context.banks_client,
+            &context.payer,
+            &context.last_blockhash,
+            MINIMUM_RESERVE_LAMPORTS,
+        )
+        .await
+        .unwrap();
+    let validator_history = Keypair::new();
+    create_validator_history(
+        &mut context,
+        &other_stake_pool_accounts,
+        &validator_history,
+        other_stake_pool_accounts.max_validators,
+        &other_stake_pool_accounts.manager,
+    )
+    .await
+    .unwrap();
+
+    slot += context.genesis_config().epoch_schedule.slots_per_epoch;
+    context.warp_to_slot(slot).unwrap();
+    let validator_list = stake_pool_accounts
+        .get_validator_list(&mut context.banks_client)
+        .await;
+    let mut instruction = instruction::update_validator_list_balance(
+        &id(),
+        &stake_pool_accounts.stake_pool.pubkey(),
+        &stake_pool_accounts.withdraw_authority,
+        &stake_pool_accounts.validator_list.pubkey(),
+        &stake_pool_accounts.reserve_stake.pubkey(),
+        &validator_list,
+        &[validator_stake.vote.pubkey()],
+        0,
+        false,
+    );
+    instruction
+        .accounts
+        .push(AccountMeta::new(validator_history.pubkey(), false));
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let error = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    // the account is not the pool's history, so it is taken as an odd stake account
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(StakePoolError::UnexpectedValidatorListAccountSize as u32)
+        )
+    );
+}
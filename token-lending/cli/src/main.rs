@@ -1,4 +1,7 @@
+mod output;
+
 use {
+    crate::output::{CliLendingMarket, CliObligation, CliObligations, CliReserve},
     clap::{
         crate_description, crate_name, crate_version, value_t, App, AppSettings, Arg, ArgMatches,
         SubCommand,
@@ -9,7 +12,12 @@ use {
         input_validators::{is_amount, is_keypair, is_parsable, is_pubkey, is_url},
         keypair::signer_from_path,
     },
-    solana_client::rpc_client::RpcClient,
+    solana_cli_output::OutputFormat,
+    solana_client::{
+        rpc_client::RpcClient,
+        rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+        rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+    },
     solana_program::{native_token::lamports_to_sol, program_pack::Pack, pubkey::Pubkey},
     solana_sdk::{
         commitment_config::CommitmentConfig,
@@ -27,9 +35,9 @@ use {
         self,
         instruction::{init_lending_market, init_reserve},
         math::WAD,
-        state::{LendingMarket, Reserve, ReserveConfig, ReserveFees},
+        state::{LendingMarket, Obligation, Reserve, ReserveConfig, ReserveFees},
     },
-    std::{borrow::Borrow, process::exit, str::FromStr},
+    std::{borrow::Borrow, cmp::Ordering, process::exit, str::FromStr},
     system_instruction::create_account,
 };
 
@@ -38,6 +46,7 @@ struct Config {
     fee_payer: Box<dyn Signer>,
     lending_program_id: Pubkey,
     verbose: bool,
+    output_format: OutputFormat,
     dry_run: bool,
 }
 
@@ -100,6 +109,15 @@ fn main() {
                 .global(true)
                 .help("Show additional information"),
         )
+        .arg(
+            Arg::with_name("output_format")
+                .long("output")
+                .value_name("FORMAT")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["json", "json-compact"])
+                .help("Return information in specified output format"),
+        )
         .arg(
             Arg::with_name("dry_run")
                 .long("dry-run")
@@ -309,6 +327,72 @@ fn main() {
                         .help("Amount of fee going to host account: [0, 100]"),
                 )
         )
+        .subcommand(
+            SubCommand::with_name("show-market")
+                .about("Show a lending market")
+                .arg(
+                    Arg::with_name("lending_market")
+                        .index(1)
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Lending market address"),
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("show-reserve")
+                .about("Show a reserve, including its utilization, borrow and supply APY")
+                .arg(
+                    Arg::with_name("reserve")
+                        .index(1)
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve address"),
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("show-obligation")
+                .about("Show an obligation, including its borrowed value against the allowed and unhealthy thresholds")
+                .arg(
+                    Arg::with_name("obligation")
+                        .index(1)
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Obligation address"),
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("list-obligations")
+                .about("List all obligations of a lending market")
+                .arg(
+                    Arg::with_name("lending_market")
+                        .index(1)
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Lending market address"),
+                )
+                .arg(
+                    Arg::with_name("obligation_owner")
+                        .long("owner")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .help("Only list obligations owned by this address"),
+                )
+                .arg(
+                    Arg::with_name("unhealthy")
+                        .long("unhealthy")
+                        .takes_value(false)
+                        .help("Only list obligations that can be liquidated"),
+                )
+        )
         .get_matches();
 
     let mut wallet_manager = None;
@@ -336,6 +420,18 @@ fn main() {
 
         let lending_program_id = pubkey_of(&matches, "lending_program_id").unwrap();
         let verbose = matches.is_present("verbose");
+        let output_format = matches
+            .value_of("output_format")
+            .map(|value| match value {
+                "json" => OutputFormat::Json,
+                "json-compact" => OutputFormat::JsonCompact,
+                _ => unreachable!(),
+            })
+            .unwrap_or(if verbose {
+                OutputFormat::DisplayVerbose
+            } else {
+                OutputFormat::Display
+            });
         let dry_run = matches.is_present("dry_run");
 
         Config {
@@ -343,6 +439,7 @@ fn main() {
             fee_payer,
             lending_program_id,
             verbose,
+            output_format,
             dry_run,
         }
     };
@@ -409,6 +506,29 @@ fn main() {
                 pyth_price_pubkey,
             )
         }
+        ("show-market", Some(arg_matches)) => {
+            let lending_market_pubkey = pubkey_of(arg_matches, "lending_market").unwrap();
+            command_show_lending_market(&config, &lending_market_pubkey)
+        }
+        ("show-reserve", Some(arg_matches)) => {
+            let reserve_pubkey = pubkey_of(arg_matches, "reserve").unwrap();
+            command_show_reserve(&config, &reserve_pubkey)
+        }
+        ("show-obligation", Some(arg_matches)) => {
+            let obligation_pubkey = pubkey_of(arg_matches, "obligation").unwrap();
+            command_show_obligation(&config, &obligation_pubkey)
+        }
+        ("list-obligations", Some(arg_matches)) => {
+            let lending_market_pubkey = pubkey_of(arg_matches, "lending_market").unwrap();
+            let obligation_owner = pubkey_of(arg_matches, "obligation_owner");
+            let unhealthy = arg_matches.is_present("unhealthy");
+            command_list_obligations(
+                &config,
+                &lending_market_pubkey,
+                obligation_owner.as_ref(),
+                unhealthy,
+            )
+        }
         _ => unreachable!(),
     }
     .map_err(|err| {
@@ -690,8 +810,132 @@ fn command_add_reserve(
     Ok(())
 }
 
+fn command_show_lending_market(config: &Config, lending_market_pubkey: &Pubkey) -> CommandResult {
+    let lending_market = get_lending_market(config, lending_market_pubkey)?;
+    let (lending_market_authority, _bump_seed) = Pubkey::find_program_address(
+        &[lending_market_pubkey.as_ref()],
+        &config.lending_program_id,
+    );
+    let cli_lending_market = CliLendingMarket::new(
+        lending_market_pubkey,
+        &lending_market,
+        &lending_market_authority,
+    );
+    println!(
+        "{}",
+        config.output_format.formatted_string(&cli_lending_market)
+    );
+    Ok(())
+}
+
+fn command_show_reserve(config: &Config, reserve_pubkey: &Pubkey) -> CommandResult {
+    let reserve_account = config.rpc_client.get_account(reserve_pubkey)?;
+    check_lending_program_owner(config, reserve_pubkey, &reserve_account.owner)?;
+    let reserve = Reserve::unpack(reserve_account.data.borrow())?;
+    let cli_reserve = CliReserve::new(reserve_pubkey, &reserve)?;
+    println!("{}", config.output_format.formatted_string(&cli_reserve));
+    Ok(())
+}
+
+fn command_show_obligation(config: &Config, obligation_pubkey: &Pubkey) -> CommandResult {
+    let obligation_account = config.rpc_client.get_account(obligation_pubkey)?;
+    check_lending_program_owner(config, obligation_pubkey, &obligation_account.owner)?;
+    let obligation = Obligation::unpack(obligation_account.data.borrow())?;
+    let cli_obligation = CliObligation::new(obligation_pubkey, &obligation);
+    println!("{}", config.output_format.formatted_string(&cli_obligation));
+    Ok(())
+}
+
+fn command_list_obligations(
+    config: &Config,
+    lending_market_pubkey: &Pubkey,
+    obligation_owner: Option<&Pubkey>,
+    unhealthy: bool,
+) -> CommandResult {
+    // Make sure the lending market exists before scanning the program accounts
+    get_lending_market(config, lending_market_pubkey)?;
+
+    let mut filters = vec![
+        RpcFilterType::DataSize(Obligation::LEN as u64),
+        // Filter by `Obligation::lending_market`, which begins at byte offset 10
+        RpcFilterType::Memcmp(Memcmp {
+            offset: 10,
+            bytes: MemcmpEncodedBytes::Base58(lending_market_pubkey.to_string()),
+            encoding: None,
+        }),
+    ];
+    if let Some(obligation_owner) = obligation_owner {
+        // Filter by `Obligation::owner`, which begins at byte offset 42
+        filters.push(RpcFilterType::Memcmp(Memcmp {
+            offset: 42,
+            bytes: MemcmpEncodedBytes::Base58(obligation_owner.to_string()),
+            encoding: None,
+        }));
+    }
+
+    let accounts = config.rpc_client.get_program_accounts_with_config(
+        &config.lending_program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(solana_account_decoder::UiAccountEncoding::Base64),
+                commitment: Some(config.rpc_client.commitment()),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    let mut obligations = accounts
+        .into_iter()
+        .filter_map(
+            |(address, account)| match Obligation::unpack(account.data.borrow()) {
+                Ok(obligation) => Some(CliObligation::new(&address, &obligation)),
+                Err(err) => {
+                    eprintln!("Invalid obligation data for {}: {}", address, err);
+                    None
+                }
+            },
+        )
+        .filter(|obligation| !unhealthy || !obligation.healthy)
+        .collect::<Vec<_>>();
+    obligations.sort_by(|a, b| {
+        b.health_ratio
+            .partial_cmp(&a.health_ratio)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let cli_obligations = CliObligations { obligations };
+    println!(
+        "{}",
+        config.output_format.formatted_string(&cli_obligations)
+    );
+    Ok(())
+}
+
 // HELPERS
 
+fn get_lending_market(
+    config: &Config,
+    lending_market_pubkey: &Pubkey,
+) -> Result<LendingMarket, Error> {
+    let lending_market_account = config.rpc_client.get_account(lending_market_pubkey)?;
+    check_lending_program_owner(config, lending_market_pubkey, &lending_market_account.owner)?;
+    Ok(LendingMarket::unpack(lending_market_account.data.borrow())?)
+}
+
+fn check_lending_program_owner(config: &Config, address: &Pubkey, owner: &Pubkey) -> CommandResult {
+    if *owner != config.lending_program_id {
+        Err(format!(
+            "Account {} is owned by {}, not by the lending program {}",
+            address, owner, config.lending_program_id
+        )
+        .into())
+    } else {
+        Ok(())
+    }
+}
+
 fn check_fee_payer_balance(config: &Config, required_balance: u64) -> Result<(), Error> {
     let balance = config.rpc_client.get_balance(&config.fee_payer.pubkey())?;
     if balance < required_balance {
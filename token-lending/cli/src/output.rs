@@ -0,0 +1,454 @@
+use {
+    serde::{Deserialize, Serialize},
+    solana_cli_output::{QuietDisplay, VerboseDisplay},
+    solana_program::{program_error::ProgramError, pubkey::Pubkey},
+    spl_token_lending::{
+        math::{Decimal, Rate, WAD},
+        state::{LendingMarket, Obligation, Reserve, SLOTS_PER_YEAR},
+    },
+    std::{
+        fmt::{Display, Formatter, Result, Write},
+        str::from_utf8,
+    },
+};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliLendingMarket {
+    pub address: String,
+    pub version: u8,
+    pub owner: String,
+    pub lending_market_authority: String,
+    pub quote_currency: String,
+    pub token_program_id: String,
+    pub oracle_program_id: String,
+}
+
+impl CliLendingMarket {
+    pub(crate) fn new(
+        address: &Pubkey,
+        lending_market: &LendingMarket,
+        lending_market_authority: &Pubkey,
+    ) -> Self {
+        Self {
+            address: address.to_string(),
+            version: lending_market.version,
+            owner: lending_market.owner.to_string(),
+            lending_market_authority: lending_market_authority.to_string(),
+            quote_currency: quote_currency_to_string(&lending_market.quote_currency),
+            token_program_id: lending_market.token_program_id.to_string(),
+            oracle_program_id: lending_market.oracle_program_id.to_string(),
+        }
+    }
+}
+
+impl Display for CliLendingMarket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "Lending Market: {}", self.address)?;
+        writeln!(f, "Owner: {}", self.owner)?;
+        writeln!(f, "Authority: {}", self.lending_market_authority)?;
+        writeln!(f, "Quote Currency: {}", self.quote_currency)?;
+        if f.alternate() {
+            writeln!(f, "Version: {}", self.version)?;
+            writeln!(f, "Token Program: {}", self.token_program_id)?;
+            writeln!(f, "Oracle Program: {}", self.oracle_program_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl QuietDisplay for CliLendingMarket {}
+impl VerboseDisplay for CliLendingMarket {
+    fn write_str(&self, w: &mut dyn Write) -> Result {
+        write!(w, "{:#}", self)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliReserve {
+    pub address: String,
+    pub version: u8,
+    pub lending_market: String,
+    pub last_update_slot: u64,
+    pub stale: bool,
+    pub liquidity_mint: String,
+    pub liquidity_mint_decimals: u8,
+    pub liquidity_supply: String,
+    pub liquidity_fee_receiver: String,
+    pub liquidity_oracle: String,
+    pub available_amount: u64,
+    pub borrowed_amount: String,
+    pub total_supply: String,
+    pub market_price: String,
+    pub collateral_mint: String,
+    pub collateral_supply: String,
+    pub collateral_mint_total_supply: u64,
+    /// Collateral tokens minted per liquidity token deposited
+    pub collateral_exchange_rate: f64,
+    pub utilization_rate: f64,
+    pub borrow_rate: f64,
+    pub borrow_apy: f64,
+    pub supply_apy: f64,
+    pub config: CliReserveConfig,
+}
+
+impl CliReserve {
+    pub(crate) fn new(
+        address: &Pubkey,
+        reserve: &Reserve,
+    ) -> std::result::Result<Self, ProgramError> {
+        let utilization_rate = reserve.liquidity.utilization_rate()?;
+        let borrow_rate = reserve.current_borrow_rate()?;
+        let collateral_exchange_rate = reserve.collateral_exchange_rate()?;
+        let total_supply = reserve.liquidity.total_supply()?;
+
+        let utilization_rate = rate_to_f64(utilization_rate);
+        let borrow_rate = rate_to_f64(borrow_rate);
+        let borrow_apy = apr_to_apy(borrow_rate);
+        // All interest paid by borrowers accrues to suppliers
+        let supply_apy = borrow_apy * utilization_rate;
+
+        Ok(Self {
+            address: address.to_string(),
+            version: reserve.version,
+            lending_market: reserve.lending_market.to_string(),
+            last_update_slot: reserve.last_update.slot,
+            stale: reserve.last_update.stale,
+            liquidity_mint: reserve.liquidity.mint_pubkey.to_string(),
+            liquidity_mint_decimals: reserve.liquidity.mint_decimals,
+            liquidity_supply: reserve.liquidity.supply_pubkey.to_string(),
+            liquidity_fee_receiver: reserve.liquidity.fee_receiver.to_string(),
+            liquidity_oracle: reserve.liquidity.oracle_pubkey.to_string(),
+            available_amount: reserve.liquidity.available_amount,
+            borrowed_amount: reserve.liquidity.borrowed_amount_wads.to_string(),
+            total_supply: total_supply.to_string(),
+            market_price: reserve.liquidity.market_price.to_string(),
+            collateral_mint: reserve.collateral.mint_pubkey.to_string(),
+            collateral_supply: reserve.collateral.supply_pubkey.to_string(),
+            collateral_mint_total_supply: reserve.collateral.mint_total_supply,
+            collateral_exchange_rate: rate_to_f64(Rate::from(collateral_exchange_rate)),
+            utilization_rate,
+            borrow_rate,
+            borrow_apy,
+            supply_apy,
+            config: CliReserveConfig {
+                optimal_utilization_rate: reserve.config.optimal_utilization_rate,
+                loan_to_value_ratio: reserve.config.loan_to_value_ratio,
+                liquidation_bonus: reserve.config.liquidation_bonus,
+                liquidation_threshold: reserve.config.liquidation_threshold,
+                min_borrow_rate: reserve.config.min_borrow_rate,
+                optimal_borrow_rate: reserve.config.optimal_borrow_rate,
+                max_borrow_rate: reserve.config.max_borrow_rate,
+                borrow_fee_wad: reserve.config.fees.borrow_fee_wad,
+                flash_loan_fee_wad: reserve.config.fees.flash_loan_fee_wad,
+                host_fee_percentage: reserve.config.fees.host_fee_percentage,
+            },
+        })
+    }
+}
+
+impl Display for CliReserve {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "Reserve: {}", self.address)?;
+        writeln!(f, "Lending Market: {}", self.lending_market)?;
+        writeln!(f, "Liquidity Mint: {}", self.liquidity_mint)?;
+        writeln!(
+            f,
+            "Last Update Slot: {}{}",
+            self.last_update_slot,
+            if self.stale { " (stale)" } else { "" }
+        )?;
+        writeln!(f, "Available Liquidity: {}", self.available_amount)?;
+        writeln!(f, "Borrowed Liquidity: {}", self.borrowed_amount)?;
+        writeln!(f, "Total Liquidity: {}", self.total_supply)?;
+        writeln!(f, "Market Price: {}", self.market_price)?;
+        writeln!(f, "Utilization: {:.2}%", self.utilization_rate * 100.0)?;
+        writeln!(f, "Borrow Rate: {:.2}%", self.borrow_rate * 100.0)?;
+        writeln!(f, "Borrow APY: {:.2}%", self.borrow_apy * 100.0)?;
+        writeln!(f, "Supply APY: {:.2}%", self.supply_apy * 100.0)?;
+        writeln!(
+            f,
+            "Collateral Exchange Rate: {} collateral per liquidity token",
+            self.collateral_exchange_rate
+        )?;
+        if f.alternate() {
+            writeln!(f, "Version: {}", self.version)?;
+            writeln!(
+                f,
+                "Liquidity Mint Decimals: {}",
+                self.liquidity_mint_decimals
+            )?;
+            writeln!(f, "Liquidity Supply: {}", self.liquidity_supply)?;
+            writeln!(f, "Liquidity Fee Receiver: {}", self.liquidity_fee_receiver)?;
+            writeln!(f, "Liquidity Oracle: {}", self.liquidity_oracle)?;
+            writeln!(f, "Collateral Mint: {}", self.collateral_mint)?;
+            writeln!(f, "Collateral Supply: {}", self.collateral_supply)?;
+            writeln!(
+                f,
+                "Collateral Mint Supply: {}",
+                self.collateral_mint_total_supply
+            )?;
+            writeln!(f, "{}", self.config)?;
+        }
+        Ok(())
+    }
+}
+
+impl QuietDisplay for CliReserve {}
+impl VerboseDisplay for CliReserve {
+    fn write_str(&self, w: &mut dyn Write) -> Result {
+        write!(w, "{:#}", self)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliReserveConfig {
+    pub optimal_utilization_rate: u8,
+    pub loan_to_value_ratio: u8,
+    pub liquidation_bonus: u8,
+    pub liquidation_threshold: u8,
+    pub min_borrow_rate: u8,
+    pub optimal_borrow_rate: u8,
+    pub max_borrow_rate: u8,
+    pub borrow_fee_wad: u64,
+    pub flash_loan_fee_wad: u64,
+    pub host_fee_percentage: u8,
+}
+
+impl Display for CliReserveConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "Config")?;
+        writeln!(f, "======")?;
+        writeln!(
+            f,
+            "Optimal Utilization Rate: {}%",
+            self.optimal_utilization_rate
+        )?;
+        writeln!(f, "Loan To Value Ratio: {}%", self.loan_to_value_ratio)?;
+        writeln!(f, "Liquidation Bonus: {}%", self.liquidation_bonus)?;
+        writeln!(f, "Liquidation Threshold: {}%", self.liquidation_threshold)?;
+        writeln!(
+            f,
+            "Borrow Rate: min {}% / optimal {}% / max {}%",
+            self.min_borrow_rate, self.optimal_borrow_rate, self.max_borrow_rate
+        )?;
+        writeln!(
+            f,
+            "Borrow Fee: {}%",
+            self.borrow_fee_wad as f64 / WAD as f64 * 100.0
+        )?;
+        writeln!(
+            f,
+            "Flash Loan Fee: {}%",
+            self.flash_loan_fee_wad as f64 / WAD as f64 * 100.0
+        )?;
+        write!(f, "Host Fee: {}%", self.host_fee_percentage)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliObligation {
+    pub address: String,
+    pub version: u8,
+    pub lending_market: String,
+    pub owner: String,
+    pub last_update_slot: u64,
+    pub stale: bool,
+    pub deposited_value: String,
+    pub borrowed_value: String,
+    pub allowed_borrow_value: String,
+    pub unhealthy_borrow_value: String,
+    pub loan_to_value: f64,
+    /// Ratio of the borrowed value to the unhealthy borrow value, liquidatable at >= 1
+    pub health_ratio: f64,
+    pub healthy: bool,
+    pub deposits: Vec<CliObligationCollateral>,
+    pub borrows: Vec<CliObligationLiquidity>,
+}
+
+impl CliObligation {
+    pub(crate) fn new(address: &Pubkey, obligation: &Obligation) -> Self {
+        let deposited_value = decimal_to_f64(obligation.deposited_value);
+        let borrowed_value = decimal_to_f64(obligation.borrowed_value);
+        let unhealthy_borrow_value = decimal_to_f64(obligation.unhealthy_borrow_value);
+        let loan_to_value = if deposited_value > 0.0 {
+            borrowed_value / deposited_value
+        } else {
+            0.0
+        };
+        let health_ratio = if unhealthy_borrow_value > 0.0 {
+            borrowed_value / unhealthy_borrow_value
+        } else if borrowed_value > 0.0 {
+            f64::INFINITY
+        } else {
+            0.0
+        };
+
+        Self {
+            address: address.to_string(),
+            version: obligation.version,
+            lending_market: obligation.lending_market.to_string(),
+            owner: obligation.owner.to_string(),
+            last_update_slot: obligation.last_update.slot,
+            stale: obligation.last_update.stale,
+            deposited_value: obligation.deposited_value.to_string(),
+            borrowed_value: obligation.borrowed_value.to_string(),
+            allowed_borrow_value: obligation.allowed_borrow_value.to_string(),
+            unhealthy_borrow_value: obligation.unhealthy_borrow_value.to_string(),
+            loan_to_value,
+            health_ratio,
+            healthy: obligation.borrowed_value < obligation.unhealthy_borrow_value
+                || obligation.borrowed_value == Decimal::zero(),
+            deposits: obligation
+                .deposits
+                .iter()
+                .map(|collateral| CliObligationCollateral {
+                    deposit_reserve: collateral.deposit_reserve.to_string(),
+                    deposited_amount: collateral.deposited_amount,
+                    market_value: collateral.market_value.to_string(),
+                })
+                .collect(),
+            borrows: obligation
+                .borrows
+                .iter()
+                .map(|liquidity| CliObligationLiquidity {
+                    borrow_reserve: liquidity.borrow_reserve.to_string(),
+                    borrowed_amount: liquidity.borrowed_amount_wads.to_string(),
+                    market_value: liquidity.market_value.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Display for CliObligation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "Obligation: {}", self.address)?;
+        writeln!(f, "Lending Market: {}", self.lending_market)?;
+        writeln!(f, "Owner: {}", self.owner)?;
+        writeln!(
+            f,
+            "Last Update Slot: {}{}",
+            self.last_update_slot,
+            if self.stale { " (stale)" } else { "" }
+        )?;
+        writeln!(f, "Deposited Value: {}", self.deposited_value)?;
+        writeln!(f, "Borrowed Value: {}", self.borrowed_value)?;
+        writeln!(f, "Allowed Borrow Value: {}", self.allowed_borrow_value)?;
+        writeln!(f, "Unhealthy Borrow Value: {}", self.unhealthy_borrow_value)?;
+        writeln!(f, "Loan To Value: {:.2}%", self.loan_to_value * 100.0)?;
+        writeln!(
+            f,
+            "Health: {} ({:.2}% of liquidation threshold)",
+            if self.healthy { "healthy" } else { "unhealthy" },
+            self.health_ratio * 100.0
+        )?;
+        if f.alternate() {
+            writeln!(f, "Deposits")?;
+            writeln!(f, "========")?;
+            for collateral in &self.deposits {
+                writeln!(
+                    f,
+                    "Reserve: {}\tAmount: {}\tValue: {}",
+                    collateral.deposit_reserve,
+                    collateral.deposited_amount,
+                    collateral.market_value
+                )?;
+            }
+            writeln!(f, "Borrows")?;
+            writeln!(f, "=======")?;
+            for liquidity in &self.borrows {
+                writeln!(
+                    f,
+                    "Reserve: {}\tAmount: {}\tValue: {}",
+                    liquidity.borrow_reserve, liquidity.borrowed_amount, liquidity.market_value
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl QuietDisplay for CliObligation {}
+impl VerboseDisplay for CliObligation {
+    fn write_str(&self, w: &mut dyn Write) -> Result {
+        write!(w, "{:#}", self)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliObligationCollateral {
+    pub deposit_reserve: String,
+    pub deposited_amount: u64,
+    pub market_value: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliObligationLiquidity {
+    pub borrow_reserve: String,
+    pub borrowed_amount: String,
+    pub market_value: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliObligations {
+    pub obligations: Vec<CliObligation>,
+}
+
+impl Display for CliObligations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for obligation in &self.obligations {
+            writeln!(
+                f,
+                "Address: {}\tOwner: {}\tBorrowed: {}\tAllowed: {}\tUnhealthy: {}\t{}{}",
+                obligation.address,
+                obligation.owner,
+                obligation.borrowed_value,
+                obligation.allowed_borrow_value,
+                obligation.unhealthy_borrow_value,
+                if obligation.healthy {
+                    "healthy"
+                } else {
+                    "UNHEALTHY"
+                },
+                if obligation.stale { " (stale)" } else { "" }
+            )?;
+        }
+        writeln!(f, "Total number of obligations: {}", self.obligations.len())?;
+        Ok(())
+    }
+}
+
+impl QuietDisplay for CliObligations {}
+impl VerboseDisplay for CliObligations {}
+
+/// Convert a `Rate` into a floating point fraction for display purposes
+fn rate_to_f64(rate: Rate) -> f64 {
+    rate.to_scaled_val() as f64 / WAD as f64
+}
+
+/// Convert a `Decimal` into a floating point number for display purposes
+fn decimal_to_f64(decimal: Decimal) -> f64 {
+    decimal.to_scaled_val().map_or(f64::NAN, |v| v as f64) / WAD as f64
+}
+
+/// Annualize a borrow rate the way the program compounds it, once per slot
+fn apr_to_apy(apr: f64) -> f64 {
+    let slots_per_year = SLOTS_PER_YEAR as f64;
+    (1.0 + apr / slots_per_year).powf(slots_per_year) - 1.0
+}
+
+fn quote_currency_to_string(quote_currency: &[u8; 32]) -> String {
+    match from_utf8(quote_currency) {
+        Ok(currency) if quote_currency.iter().any(|&b| b == 0) => {
+            currency.trim_end_matches('\0').to_string()
+        }
+        _ => Pubkey::new_from_array(*quote_currency).to_string(),
+    }
+}
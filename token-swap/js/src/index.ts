@@ -17,7 +17,7 @@ import {
 } from '@solana/web3.js';
 
 import * as Layout from './layout';
-import {loadAccount} from './util/account';
+import {loadAccount, loadOwner} from './util/account';
 
 export const TOKEN_SWAP_PROGRAM_ID: PublicKey = new PublicKey(
   'SwapsVeCiPHMUAtzQWZw7RjsKjgCjhwU55QGu4U1Szw',
@@ -101,13 +101,15 @@ export class TokenSwap {
    * @param connection The connection to use
    * @param tokenSwap The token swap account
    * @param swapProgramId The program ID of the token-swap program
-   * @param tokenProgramId The program ID of the token program
+   * @param tokenProgramId The program ID of the pool token program
    * @param poolToken The pool token
    * @param authority The authority over the swap and accounts
    * @param tokenAccountA The token swap's Token A account
    * @param tokenAccountB The token swap's Token B account
    * @param mintA The mint of Token A
    * @param mintB The mint of Token B
+   * @param mintAProgramId The program ID of the token program owning Token A
+   * @param mintBProgramId The program ID of the token program owning Token B
    * @param tradeFeeNumerator The trade fee numerator
    * @param tradeFeeDenominator The trade fee denominator
    * @param ownerTradeFeeNumerator The owner trade fee numerator
@@ -131,6 +133,8 @@ export class TokenSwap {
     public tokenAccountB: PublicKey,
     public mintA: PublicKey,
     public mintB: PublicKey,
+    public mintAProgramId: PublicKey,
+    public mintBProgramId: PublicKey,
     public tradeFeeNumerator: Numberu64,
     public tradeFeeDenominator: Numberu64,
     public ownerTradeFeeNumerator: Numberu64,
@@ -153,6 +157,8 @@ export class TokenSwap {
     this.tokenAccountB = tokenAccountB;
     this.mintA = mintA;
     this.mintB = mintB;
+    this.mintAProgramId = mintAProgramId;
+    this.mintBProgramId = mintBProgramId;
     this.tradeFeeNumerator = tradeFeeNumerator;
     this.tradeFeeDenominator = tradeFeeDenominator;
     this.ownerTradeFeeNumerator = ownerTradeFeeNumerator;
@@ -187,6 +193,8 @@ export class TokenSwap {
     feeAccount: PublicKey,
     tokenAccountPool: PublicKey,
     tokenProgramId: PublicKey,
+    mintA: PublicKey,
+    mintB: PublicKey,
     swapProgramId: PublicKey,
     tradeFeeNumerator: number,
     tradeFeeDenominator: number,
@@ -208,6 +216,8 @@ export class TokenSwap {
       {pubkey: feeAccount, isSigner: false, isWritable: false},
       {pubkey: tokenAccountPool, isSigner: false, isWritable: true},
       {pubkey: tokenProgramId, isSigner: false, isWritable: false},
+      {pubkey: mintA, isSigner: false, isWritable: false},
+      {pubkey: mintB, isSigner: false, isWritable: false},
     ];
     const commandDataLayout = BufferLayout.struct([
       BufferLayout.u8('instruction'),
@@ -280,6 +290,10 @@ export class TokenSwap {
     const mintA = new PublicKey(tokenSwapData.mintA);
     const mintB = new PublicKey(tokenSwapData.mintB);
     const tokenProgramId = new PublicKey(tokenSwapData.tokenProgramId);
+    // Tokens A and B may be owned by either the SPL Token or the Token-2022
+    // program, which is only known from their mints
+    const mintAProgramId = await loadOwner(connection, mintA);
+    const mintBProgramId = await loadOwner(connection, mintB);
 
     const tradeFeeNumerator = Numberu64.fromBuffer(
       tokenSwapData.tradeFeeNumerator,
@@ -319,6 +333,8 @@ export class TokenSwap {
       tokenAccountB,
       mintA,
       mintB,
+      mintAProgramId,
+      mintBProgramId,
       tradeFeeNumerator,
       tradeFeeDenominator,
       ownerTradeFeeNumerator,
@@ -343,8 +359,10 @@ export class TokenSwap {
    * @param tokenAccountB: The token swap's Token B account
    * @param poolToken The pool token
    * @param tokenAccountPool The token swap's pool token account
-   * @param tokenProgramId The program ID of the token program
    * @param swapProgramId The program ID of the token-swap program
+   * @param tokenProgramId The program ID of the pool token program
+   * @param mintAProgramId The program ID of the token program owning Token A
+   * @param mintBProgramId The program ID of the token program owning Token B
    * @param feeNumerator Numerator of the fee ratio
    * @param feeDenominator Denominator of the fee ratio
    * @return Token object for the newly minted token, Public key of the account holding the total supply of new tokens
@@ -363,6 +381,8 @@ export class TokenSwap {
     tokenAccountPool: PublicKey,
     swapProgramId: PublicKey,
     tokenProgramId: PublicKey,
+    mintAProgramId: PublicKey,
+    mintBProgramId: PublicKey,
     tradeFeeNumerator: number,
     tradeFeeDenominator: number,
     ownerTradeFeeNumerator: number,
@@ -388,6 +408,8 @@ export class TokenSwap {
       tokenAccountB,
       mintA,
       mintB,
+      mintAProgramId,
+      mintBProgramId,
       new Numberu64(tradeFeeNumerator),
       new Numberu64(tradeFeeDenominator),
       new Numberu64(ownerTradeFeeNumerator),
@@ -424,6 +446,8 @@ export class TokenSwap {
       feeAccount,
       tokenAccountPool,
       tokenProgramId,
+      mintA,
+      mintB,
       swapProgramId,
       tradeFeeNumerator,
       tradeFeeDenominator,
@@ -471,6 +495,7 @@ export class TokenSwap {
     minimumAmountOut: number | Numberu64,
     confirmOptions?: ConfirmOptions,
   ): Promise<TransactionSignature> {
+    const isAToB = poolSource.equals(this.tokenAccountA);
     return await sendAndConfirmTransaction(
       this.connection,
       new Transaction().add(
@@ -486,6 +511,10 @@ export class TokenSwap {
           this.feeAccount,
           hostFeeAccount,
           this.swapProgramId,
+          isAToB ? this.mintA : this.mintB,
+          isAToB ? this.mintB : this.mintA,
+          isAToB ? this.mintAProgramId : this.mintBProgramId,
+          isAToB ? this.mintBProgramId : this.mintAProgramId,
           this.tokenProgramId,
           amountIn,
           minimumAmountOut,
@@ -508,7 +537,11 @@ export class TokenSwap {
     feeAccount: PublicKey,
     hostFeeAccount: PublicKey | null,
     swapProgramId: PublicKey,
-    tokenProgramId: PublicKey,
+    sourceMint: PublicKey,
+    destinationMint: PublicKey,
+    sourceTokenProgramId: PublicKey,
+    destinationTokenProgramId: PublicKey,
+    poolTokenProgramId: PublicKey,
     amountIn: number | Numberu64,
     minimumAmountOut: number | Numberu64,
   ): TransactionInstruction {
@@ -529,7 +562,7 @@ export class TokenSwap {
     );
 
     const keys = [
-      {pubkey: tokenSwap, isSigner: false, isWritable: false},
+      {pubkey: tokenSwap, isSigner: false, isWritable: true},
       {pubkey: authority, isSigner: false, isWritable: false},
       {pubkey: userTransferAuthority, isSigner: true, isWritable: false},
       {pubkey: userSource, isSigner: false, isWritable: true},
@@ -538,7 +571,11 @@ export class TokenSwap {
       {pubkey: userDestination, isSigner: false, isWritable: true},
       {pubkey: poolMint, isSigner: false, isWritable: true},
       {pubkey: feeAccount, isSigner: false, isWritable: true},
-      {pubkey: tokenProgramId, isSigner: false, isWritable: false},
+      {pubkey: sourceMint, isSigner: false, isWritable: false},
+      {pubkey: destinationMint, isSigner: false, isWritable: false},
+      {pubkey: sourceTokenProgramId, isSigner: false, isWritable: false},
+      {pubkey: destinationTokenProgramId, isSigner: false, isWritable: false},
+      {pubkey: poolTokenProgramId, isSigner: false, isWritable: false},
     ];
     if (hostFeeAccount !== null) {
       keys.push({pubkey: hostFeeAccount, isSigner: false, isWritable: true});
@@ -584,6 +621,10 @@ export class TokenSwap {
           this.poolToken,
           poolAccount,
           this.swapProgramId,
+          this.mintA,
+          this.mintB,
+          this.mintAProgramId,
+          this.mintBProgramId,
           this.tokenProgramId,
           poolTokenAmount,
           maximumTokenA,
@@ -606,7 +647,11 @@ export class TokenSwap {
     poolToken: PublicKey,
     poolAccount: PublicKey,
     swapProgramId: PublicKey,
-    tokenProgramId: PublicKey,
+    mintA: PublicKey,
+    mintB: PublicKey,
+    tokenProgramIdA: PublicKey,
+    tokenProgramIdB: PublicKey,
+    poolTokenProgramId: PublicKey,
     poolTokenAmount: number | Numberu64,
     maximumTokenA: number | Numberu64,
     maximumTokenB: number | Numberu64,
@@ -630,7 +675,7 @@ export class TokenSwap {
     );
 
     const keys = [
-      {pubkey: tokenSwap, isSigner: false, isWritable: false},
+      {pubkey: tokenSwap, isSigner: false, isWritable: true},
       {pubkey: authority, isSigner: false, isWritable: false},
       {pubkey: userTransferAuthority, isSigner: true, isWritable: false},
       {pubkey: sourceA, isSigner: false, isWritable: true},
@@ -639,7 +684,11 @@ export class TokenSwap {
       {pubkey: intoB, isSigner: false, isWritable: true},
       {pubkey: poolToken, isSigner: false, isWritable: true},
       {pubkey: poolAccount, isSigner: false, isWritable: true},
-      {pubkey: tokenProgramId, isSigner: false, isWritable: false},
+      {pubkey: mintA, isSigner: false, isWritable: false},
+      {pubkey: mintB, isSigner: false, isWritable: false},
+      {pubkey: tokenProgramIdA, isSigner: false, isWritable: false},
+      {pubkey: tokenProgramIdB, isSigner: false, isWritable: false},
+      {pubkey: poolTokenProgramId, isSigner: false, isWritable: false},
     ];
     return new TransactionInstruction({
       keys,
@@ -684,6 +733,10 @@ export class TokenSwap {
           userAccountA,
           userAccountB,
           this.swapProgramId,
+          this.mintA,
+          this.mintB,
+          this.mintAProgramId,
+          this.mintBProgramId,
           this.tokenProgramId,
           poolTokenAmount,
           minimumTokenA,
@@ -707,7 +760,11 @@ export class TokenSwap {
     userAccountA: PublicKey,
     userAccountB: PublicKey,
     swapProgramId: PublicKey,
-    tokenProgramId: PublicKey,
+    mintA: PublicKey,
+    mintB: PublicKey,
+    tokenProgramIdA: PublicKey,
+    tokenProgramIdB: PublicKey,
+    poolTokenProgramId: PublicKey,
     poolTokenAmount: number | Numberu64,
     minimumTokenA: number | Numberu64,
     minimumTokenB: number | Numberu64,
@@ -731,7 +788,7 @@ export class TokenSwap {
     );
 
     const keys = [
-      {pubkey: tokenSwap, isSigner: false, isWritable: false},
+      {pubkey: tokenSwap, isSigner: false, isWritable: true},
       {pubkey: authority, isSigner: false, isWritable: false},
       {pubkey: userTransferAuthority, isSigner: true, isWritable: false},
       {pubkey: poolMint, isSigner: false, isWritable: true},
@@ -741,7 +798,11 @@ export class TokenSwap {
       {pubkey: userAccountA, isSigner: false, isWritable: true},
       {pubkey: userAccountB, isSigner: false, isWritable: true},
       {pubkey: feeAccount, isSigner: false, isWritable: true},
-      {pubkey: tokenProgramId, isSigner: false, isWritable: false},
+      {pubkey: mintA, isSigner: false, isWritable: false},
+      {pubkey: mintB, isSigner: false, isWritable: false},
+      {pubkey: tokenProgramIdA, isSigner: false, isWritable: false},
+      {pubkey: tokenProgramIdB, isSigner: false, isWritable: false},
+      {pubkey: poolTokenProgramId, isSigner: false, isWritable: false},
     ];
     return new TransactionInstruction({
       keys,
@@ -750,6 +811,29 @@ export class TokenSwap {
     });
   }
 
+  /**
+   * Get the mint and token program of a user account for token A or B
+   *
+   * @param userAccount User account for token A or B
+   */
+  async getMintAndProgramId(
+    userAccount: PublicKey,
+  ): Promise<[PublicKey, PublicKey]> {
+    const accountInfo = await this.connection.getAccountInfo(userAccount);
+    if (accountInfo === null) {
+      throw new Error('Failed to find account');
+    }
+    // The mint is the first field of token accounts of both token programs
+    const mint = new PublicKey(accountInfo.data.slice(0, 32));
+    if (mint.equals(this.mintA)) {
+      return [this.mintA, this.mintAProgramId];
+    }
+    if (mint.equals(this.mintB)) {
+      return [this.mintB, this.mintBProgramId];
+    }
+    throw new Error(`Invalid mint: ${mint.toBase58()}`);
+  }
+
   /**
    * Deposit one side of tokens into the pool
    * @param userAccount User account to deposit token A or B
@@ -766,6 +850,9 @@ export class TokenSwap {
     minimumPoolTokenAmount: number | Numberu64,
     confirmOptions?: ConfirmOptions,
   ): Promise<TransactionSignature> {
+    const [sourceMint, sourceTokenProgramId] = await this.getMintAndProgramId(
+      userAccount,
+    );
     return await sendAndConfirmTransaction(
       this.connection,
       new Transaction().add(
@@ -779,6 +866,8 @@ export class TokenSwap {
           this.poolToken,
           poolAccount,
           this.swapProgramId,
+          sourceMint,
+          sourceTokenProgramId,
           this.tokenProgramId,
           sourceTokenAmount,
           minimumPoolTokenAmount,
@@ -799,7 +888,9 @@ export class TokenSwap {
     poolToken: PublicKey,
     poolAccount: PublicKey,
     swapProgramId: PublicKey,
-    tokenProgramId: PublicKey,
+    sourceMint: PublicKey,
+    sourceTokenProgramId: PublicKey,
+    poolTokenProgramId: PublicKey,
     sourceTokenAmount: number | Numberu64,
     minimumPoolTokenAmount: number | Numberu64,
   ): TransactionInstruction {
@@ -822,7 +913,7 @@ export class TokenSwap {
     );
 
     const keys = [
-      {pubkey: tokenSwap, isSigner: false, isWritable: false},
+      {pubkey: tokenSwap, isSigner: false, isWritable: true},
       {pubkey: authority, isSigner: false, isWritable: false},
       {pubkey: userTransferAuthority, isSigner: true, isWritable: false},
       {pubkey: source, isSigner: false, isWritable: true},
@@ -830,7 +921,9 @@ export class TokenSwap {
       {pubkey: intoB, isSigner: false, isWritable: true},
       {pubkey: poolToken, isSigner: false, isWritable: true},
       {pubkey: poolAccount, isSigner: false, isWritable: true},
-      {pubkey: tokenProgramId, isSigner: false, isWritable: false},
+      {pubkey: sourceMint, isSigner: false, isWritable: false},
+      {pubkey: sourceTokenProgramId, isSigner: false, isWritable: false},
+      {pubkey: poolTokenProgramId, isSigner: false, isWritable: false},
     ];
     return new TransactionInstruction({
       keys,
@@ -856,6 +949,8 @@ export class TokenSwap {
     maximumPoolTokenAmount: number | Numberu64,
     confirmOptions?: ConfirmOptions,
   ): Promise<TransactionSignature> {
+    const [destinationMint, destinationTokenProgramId] =
+      await this.getMintAndProgramId(userAccount);
     return await sendAndConfirmTransaction(
       this.connection,
       new Transaction().add(
@@ -870,6 +965,8 @@ export class TokenSwap {
           this.tokenAccountB,
           userAccount,
           this.swapProgramId,
+          destinationMint,
+          destinationTokenProgramId,
           this.tokenProgramId,
           destinationTokenAmount,
           maximumPoolTokenAmount,
@@ -891,7 +988,9 @@ export class TokenSwap {
     fromB: PublicKey,
     userAccount: PublicKey,
     swapProgramId: PublicKey,
-    tokenProgramId: PublicKey,
+    destinationMint: PublicKey,
+    destinationTokenProgramId: PublicKey,
+    poolTokenProgramId: PublicKey,
     destinationTokenAmount: number | Numberu64,
     maximumPoolTokenAmount: number | Numberu64,
   ): TransactionInstruction {
@@ -916,7 +1015,7 @@ export class TokenSwap {
     );
 
     const keys = [
-      {pubkey: tokenSwap, isSigner: false, isWritable: false},
+      {pubkey: tokenSwap, isSigner: false, isWritable: true},
       {pubkey: authority, isSigner: false, isWritable: false},
       {pubkey: userTransferAuthority, isSigner: true, isWritable: false},
       {pubkey: poolMint, isSigner: false, isWritable: true},
@@ -925,7 +1024,9 @@ export class TokenSwap {
       {pubkey: fromB, isSigner: false, isWritable: true},
       {pubkey: userAccount, isSigner: false, isWritable: true},
       {pubkey: feeAccount, isSigner: false, isWritable: true},
-      {pubkey: tokenProgramId, isSigner: false, isWritable: false},
+      {pubkey: destinationMint, isSigner: false, isWritable: false},
+      {pubkey: destinationTokenProgramId, isSigner: false, isWritable: false},
+      {pubkey: poolTokenProgramId, isSigner: false, isWritable: false},
     ];
     return new TransactionInstruction({
       keys,
@@ -17,3 +17,15 @@ export async function loadAccount(
 
   return Buffer.from(accountInfo.data);
 }
+
+export async function loadOwner(
+  connection: Connection,
+  address: PublicKey,
+): Promise<PublicKey> {
+  const accountInfo = await connection.getAccountInfo(address);
+  if (accountInfo === null) {
+    throw new Error('Failed to find account');
+  }
+
+  return accountInfo.owner;
+}
@@ -161,6 +161,8 @@ export async function createTokenSwap(
     tokenAccountPool,
     TOKEN_SWAP_PROGRAM_ID,
     TOKEN_PROGRAM_ID,
+    mintA.programId,
+    mintB.programId,
     TRADING_FEE_NUMERATOR,
     TRADING_FEE_DENOMINATOR,
     OWNER_TRADING_FEE_NUMERATOR,
@@ -186,6 +188,8 @@ export async function createTokenSwap(
   assert(fetchedTokenSwap.tokenAccountB.equals(tokenAccountB));
   assert(fetchedTokenSwap.mintA.equals(mintA.publicKey));
   assert(fetchedTokenSwap.mintB.equals(mintB.publicKey));
+  assert(fetchedTokenSwap.mintAProgramId.equals(mintA.programId));
+  assert(fetchedTokenSwap.mintBProgramId.equals(mintB.programId));
   assert(fetchedTokenSwap.poolToken.equals(tokenPool.publicKey));
   assert(fetchedTokenSwap.feeAccount.equals(feeAccount));
   assert(
@@ -411,6 +415,10 @@ export async function createAccountAndSwapAtomic(): Promise<void> {
       tokenSwap.feeAccount,
       null,
       tokenSwap.swapProgramId,
+      tokenSwap.mintA,
+      tokenSwap.mintB,
+      tokenSwap.mintAProgramId,
+      tokenSwap.mintBProgramId,
       tokenSwap.tokenProgramId,
       SWAP_AMOUNT_IN,
       0,
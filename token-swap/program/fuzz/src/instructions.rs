@@ -13,7 +13,9 @@ use {
             constant_product::ConstantProductCurve,
             fees::Fees,
             offset::OffsetCurve,
+            proactive_market_making::ProactiveMarketMakingCurve,
             stable::StableCurve,
+            stake_pool_stable::StakePoolStableCurve,
         },
         error::SwapError,
         instruction::{
@@ -469,6 +471,19 @@ fn get_swap_curve(curve_type: CurveType) -> SwapCurve {
             CurveType::Offset => Arc::new(OffsetCurve {
                 token_b_offset: 100_000_000_000,
             }),
+            CurveType::StakePoolStable => Arc::new(StakePoolStableCurve {
+                amp: 100,
+                total_lamports: 3_150_000_000_000,
+                pool_token_supply: 1_000_000_000_000,
+            }),
+            CurveType::ProactiveMarketMaking => Arc::new(ProactiveMarketMakingCurve {
+                k_numerator: 1,
+                k_denominator: 10,
+                oracle_price: 100_000_000,
+                oracle_exponent: -8,
+                token_a_decimals: 6,
+                token_b_decimals: 6,
+            }),
         },
     }
 }
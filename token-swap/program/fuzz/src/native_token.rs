@@ -40,6 +40,11 @@ pub fn get_token_balance(account_data: &NativeAccountData) -> u64 {
     account.amount
 }
 
+pub fn get_token_account_mint(account_data: &NativeAccountData) -> Pubkey {
+    let account = TokenAccount::unpack(&account_data.data).unwrap();
+    account.mint
+}
+
 pub fn transfer(
     from_account: &mut NativeAccountData,
     to_account: &mut NativeAccountData,
@@ -4,18 +4,32 @@ use crate::native_account_data::NativeAccountData;
 use crate::native_processor::do_process_instruction;
 use crate::native_token;
 
+use std::mem::size_of;
+
 use spl_token_swap::{
-    curve::{base::SwapCurve, fees::Fees},
+    curve::{
+        base::{CurveType, SwapCurve},
+        fees::Fees,
+        proactive_market_making::ProactiveMarketMakingCurve,
+        stake_pool_stable::StakePoolStableCurve,
+    },
     instruction::{
         self, DepositAllTokenTypes, DepositSingleTokenTypeExactAmountIn, Swap,
         WithdrawAllTokenTypes, WithdrawSingleTokenTypeExactAmountOut,
     },
+    pyth,
     state::SwapVersion,
 };
 
 use spl_token::instruction::approve;
 
-use solana_program::{bpf_loader, entrypoint::ProgramResult, pubkey::Pubkey, system_program};
+use spl_stake_pool::state::{AccountType, StakePool};
+
+use borsh::BorshSerialize;
+
+use solana_program::{
+    bpf_loader, entrypoint::ProgramResult, program_pack::Pack, pubkey::Pubkey, system_program,
+};
 
 pub struct NativeTokenSwap {
     pub user_account: NativeAccountData,
@@ -32,6 +46,7 @@ pub struct NativeTokenSwap {
     pub token_b_account: NativeAccountData,
     pub token_b_mint_account: NativeAccountData,
     pub token_program_account: NativeAccountData,
+    pub curve_account: Option<NativeAccountData>,
 }
 
 pub fn create_program_account(program_id: Pubkey) -> NativeAccountData {
@@ -40,6 +55,36 @@ pub fn create_program_account(program_id: Pubkey) -> NativeAccountData {
     account_data
 }
 
+pub fn create_stake_pool_account(
+    pool_mint: &Pubkey,
+    total_lamports: u64,
+    pool_token_supply: u64,
+) -> NativeAccountData {
+    let stake_pool = StakePool {
+        account_type: AccountType::StakePool,
+        pool_mint: *pool_mint,
+        total_lamports,
+        pool_token_supply,
+        ..StakePool::default()
+    };
+    let mut account_data = NativeAccountData::new(0, spl_stake_pool::id());
+    account_data.data = stake_pool.try_to_vec().unwrap();
+    account_data
+}
+
+pub fn create_oracle_account(price: u64, exponent: i32) -> NativeAccountData {
+    let mut account_data = NativeAccountData::new(size_of::<pyth::Price>(), pyth::PROGRAM_IDS[0]);
+    let oracle_price = bytemuck::from_bytes_mut::<pyth::Price>(&mut account_data.data);
+    oracle_price.magic = pyth::MAGIC;
+    oracle_price.ver = pyth::VERSION_2;
+    oracle_price.atype = pyth::AccountType::Price as u32;
+    oracle_price.ptype = pyth::PriceType::Price;
+    oracle_price.expo = exponent;
+    oracle_price.agg.price = price as i64;
+    oracle_price.agg.status = pyth::PriceStatus::Trading;
+    account_data
+}
+
 impl NativeTokenSwap {
     pub fn new(
         fees: Fees,
@@ -69,6 +114,29 @@ impl NativeTokenSwap {
             &authority_account.key,
             token_a_amount,
         );
+        let mut curve_account = match swap_curve.curve_type {
+            CurveType::StakePoolStable => {
+                let mut packed_curve = [0u8; SwapCurve::LEN];
+                swap_curve.pack_into_slice(&mut packed_curve);
+                let curve = StakePoolStableCurve::unpack_from_slice(&packed_curve[1..]).unwrap();
+                Some(create_stake_pool_account(
+                    &token_a_mint_account.key,
+                    curve.total_lamports,
+                    curve.pool_token_supply,
+                ))
+            }
+            CurveType::ProactiveMarketMaking => {
+                let mut packed_curve = [0u8; SwapCurve::LEN];
+                swap_curve.pack_into_slice(&mut packed_curve);
+                let curve =
+                    ProactiveMarketMakingCurve::unpack_from_slice(&packed_curve[1..]).unwrap();
+                Some(create_oracle_account(
+                    curve.oracle_price,
+                    curve.oracle_exponent,
+                ))
+            }
+            _ => None,
+        };
         let mut token_b_mint_account = native_token::create_mint(&user_account.key);
         let mut token_b_account = native_token::create_token_account(
             &mut token_b_mint_account,
@@ -86,25 +154,31 @@ impl NativeTokenSwap {
             &pool_mint_account.key,
             &pool_fee_account.key,
             &pool_token_account.key,
+            &token_a_mint_account.key,
+            &token_b_mint_account.key,
+            curve_account.as_ref().map(|x| &x.key),
+            None,
             fees.clone(),
             swap_curve.clone(),
         )
         .unwrap();
 
-        do_process_instruction(
-            init_instruction,
-            &[
-                swap_account.as_account_info(),
-                authority_account.as_account_info(),
-                token_a_account.as_account_info(),
-                token_b_account.as_account_info(),
-                pool_mint_account.as_account_info(),
-                pool_fee_account.as_account_info(),
-                pool_token_account.as_account_info(),
-                token_program_account.as_account_info(),
-            ],
-        )
-        .unwrap();
+        let mut account_infos = vec![
+            swap_account.as_account_info(),
+            authority_account.as_account_info(),
+            token_a_account.as_account_info(),
+            token_b_account.as_account_info(),
+            pool_mint_account.as_account_info(),
+            pool_fee_account.as_account_info(),
+            pool_token_account.as_account_info(),
+            token_program_account.as_account_info(),
+            token_a_mint_account.as_account_info(),
+            token_b_mint_account.as_account_info(),
+        ];
+        if let Some(curve_account) = curve_account.as_mut() {
+            account_infos.push(curve_account.as_account_info());
+        }
+        do_process_instruction(init_instruction, &account_infos).unwrap();
 
         Self {
             user_account,
@@ -121,9 +195,14 @@ impl NativeTokenSwap {
             token_b_account,
             token_b_mint_account,
             token_program_account,
+            curve_account,
         }
     }
 
+    fn curve_account_key(&self) -> Option<&Pubkey> {
+        self.curve_account.as_ref().map(|x| &x.key)
+    }
+
     pub fn create_pool_account(&mut self) -> NativeAccountData {
         native_token::create_token_account(&mut self.pool_mint_account, &self.user_account.key, 0)
     }
@@ -172,6 +251,8 @@ impl NativeTokenSwap {
         let swap_instruction = instruction::swap(
             &spl_token_swap::id(),
             &spl_token::id(),
+            &spl_token::id(),
+            &spl_token::id(),
             &self.swap_account.key,
             &self.authority_account.key,
             &user_transfer_account.key,
@@ -181,27 +262,36 @@ impl NativeTokenSwap {
             &token_b_account.key,
             &self.pool_mint_account.key,
             &self.pool_fee_account.key,
+            &self.token_a_mint_account.key,
+            &self.token_b_mint_account.key,
+            self.curve_account_key(),
             Some(&self.pool_token_account.key),
             instruction,
         )
         .unwrap();
 
-        do_process_instruction(
-            swap_instruction,
-            &[
-                self.swap_account.as_account_info(),
-                self.authority_account.as_account_info(),
-                user_transfer_account.as_account_info(),
-                token_a_account.as_account_info(),
-                self.token_a_account.as_account_info(),
-                self.token_b_account.as_account_info(),
-                token_b_account.as_account_info(),
-                self.pool_mint_account.as_account_info(),
-                self.pool_fee_account.as_account_info(),
-                self.token_program_account.as_account_info(),
-                self.pool_token_account.as_account_info(),
-            ],
-        )
+        let token_program_info = self.token_program_account.as_account_info();
+        let mut account_infos = vec![
+            self.swap_account.as_account_info(),
+            self.authority_account.as_account_info(),
+            user_transfer_account.as_account_info(),
+            token_a_account.as_account_info(),
+            self.token_a_account.as_account_info(),
+            self.token_b_account.as_account_info(),
+            token_b_account.as_account_info(),
+            self.pool_mint_account.as_account_info(),
+            self.pool_fee_account.as_account_info(),
+            self.token_a_mint_account.as_account_info(),
+            self.token_b_mint_account.as_account_info(),
+            token_program_info.clone(),
+            token_program_info.clone(),
+            token_program_info.clone(),
+        ];
+        if let Some(curve_account) = self.curve_account.as_mut() {
+            account_infos.push(curve_account.as_account_info());
+        }
+        account_infos.push(self.pool_token_account.as_account_info());
+        do_process_instruction(swap_instruction, &account_infos)
     }
 
     pub fn swap_b_to_a(
@@ -233,6 +323,8 @@ impl NativeTokenSwap {
         let swap_instruction = instruction::swap(
             &spl_token_swap::id(),
             &spl_token::id(),
+            &spl_token::id(),
+            &spl_token::id(),
             &self.swap_account.key,
             &self.authority_account.key,
             &user_transfer_account.key,
@@ -242,27 +334,36 @@ impl NativeTokenSwap {
             &token_a_account.key,
             &self.pool_mint_account.key,
             &self.pool_fee_account.key,
+            &self.token_b_mint_account.key,
+            &self.token_a_mint_account.key,
+            self.curve_account_key(),
             Some(&self.pool_token_account.key),
             instruction,
         )
         .unwrap();
 
-        do_process_instruction(
-            swap_instruction,
-            &[
-                self.swap_account.as_account_info(),
-                self.authority_account.as_account_info(),
-                user_transfer_account.as_account_info(),
-                token_b_account.as_account_info(),
-                self.token_b_account.as_account_info(),
-                self.token_a_account.as_account_info(),
-                token_a_account.as_account_info(),
-                self.pool_mint_account.as_account_info(),
-                self.pool_fee_account.as_account_info(),
-                self.token_program_account.as_account_info(),
-                self.pool_token_account.as_account_info(),
-            ],
-        )
+        let token_program_info = self.token_program_account.as_account_info();
+        let mut account_infos = vec![
+            self.swap_account.as_account_info(),
+            self.authority_account.as_account_info(),
+            user_transfer_account.as_account_info(),
+            token_b_account.as_account_info(),
+            self.token_b_account.as_account_info(),
+            self.token_a_account.as_account_info(),
+            token_a_account.as_account_info(),
+            self.pool_mint_account.as_account_info(),
+            self.pool_fee_account.as_account_info(),
+            self.token_b_mint_account.as_account_info(),
+            self.token_a_mint_account.as_account_info(),
+            token_program_info.clone(),
+            token_program_info.clone(),
+            token_program_info.clone(),
+        ];
+        if let Some(curve_account) = self.curve_account.as_mut() {
+            account_infos.push(curve_account.as_account_info());
+        }
+        account_infos.push(self.pool_token_account.as_account_info());
+        do_process_instruction(swap_instruction, &account_infos)
     }
 
     pub fn deposit_all_token_types(
@@ -319,6 +420,8 @@ impl NativeTokenSwap {
         let deposit_instruction = instruction::deposit_all_token_types(
             &spl_token_swap::id(),
             &spl_token::id(),
+            &spl_token::id(),
+            &spl_token::id(),
             &self.swap_account.key,
             &self.authority_account.key,
             &user_transfer_account.key,
@@ -328,10 +431,13 @@ impl NativeTokenSwap {
             &self.token_b_account.key,
             &self.pool_mint_account.key,
             &pool_account.key,
+            &self.token_a_mint_account.key,
+            &self.token_b_mint_account.key,
             instruction,
         )
         .unwrap();
 
+        let token_program_info = self.token_program_account.as_account_info();
         do_process_instruction(
             deposit_instruction,
             &[
@@ -344,7 +450,11 @@ impl NativeTokenSwap {
                 self.token_b_account.as_account_info(),
                 self.pool_mint_account.as_account_info(),
                 pool_account.as_account_info(),
-                self.token_program_account.as_account_info(),
+                self.token_a_mint_account.as_account_info(),
+                self.token_b_mint_account.as_account_info(),
+                token_program_info.clone(),
+                token_program_info.clone(),
+                token_program_info.clone(),
             ],
         )
     }
@@ -385,6 +495,8 @@ impl NativeTokenSwap {
         let withdraw_instruction = instruction::withdraw_all_token_types(
             &spl_token_swap::id(),
             &spl_token::id(),
+            &spl_token::id(),
+            &spl_token::id(),
             &self.swap_account.key,
             &self.authority_account.key,
             &user_transfer_account.key,
@@ -395,10 +507,13 @@ impl NativeTokenSwap {
             &self.token_b_account.key,
             &token_a_account.key,
             &token_b_account.key,
+            &self.token_a_mint_account.key,
+            &self.token_b_mint_account.key,
             instruction,
         )
         .unwrap();
 
+        let token_program_info = self.token_program_account.as_account_info();
         do_process_instruction(
             withdraw_instruction,
             &[
@@ -412,7 +527,11 @@ impl NativeTokenSwap {
                 token_a_account.as_account_info(),
                 token_b_account.as_account_info(),
                 self.pool_fee_account.as_account_info(),
-                self.token_program_account.as_account_info(),
+                self.token_a_mint_account.as_account_info(),
+                self.token_b_mint_account.as_account_info(),
+                token_program_info.clone(),
+                token_program_info.clone(),
+                token_program_info.clone(),
             ],
         )
     }
@@ -449,9 +568,16 @@ impl NativeTokenSwap {
             instruction.minimum_pool_token_amount = 2;
         }
 
+        let source_mint_key = native_token::get_token_account_mint(source_token_account);
+        let mut source_mint_account = if source_mint_key == self.token_a_mint_account.key {
+            self.token_a_mint_account.clone()
+        } else {
+            self.token_b_mint_account.clone()
+        };
         let deposit_instruction = instruction::deposit_single_token_type_exact_amount_in(
             &spl_token_swap::id(),
             &spl_token::id(),
+            &spl_token::id(),
             &self.swap_account.key,
             &self.authority_account.key,
             &user_transfer_account.key,
@@ -460,24 +586,30 @@ impl NativeTokenSwap {
             &self.token_b_account.key,
             &self.pool_mint_account.key,
             &pool_account.key,
+            &source_mint_account.key,
+            self.curve_account_key(),
             instruction,
         )
         .unwrap();
 
-        do_process_instruction(
-            deposit_instruction,
-            &[
-                self.swap_account.as_account_info(),
-                self.authority_account.as_account_info(),
-                user_transfer_account.as_account_info(),
-                source_token_account.as_account_info(),
-                self.token_a_account.as_account_info(),
-                self.token_b_account.as_account_info(),
-                self.pool_mint_account.as_account_info(),
-                pool_account.as_account_info(),
-                self.token_program_account.as_account_info(),
-            ],
-        )
+        let token_program_info = self.token_program_account.as_account_info();
+        let mut account_infos = vec![
+            self.swap_account.as_account_info(),
+            self.authority_account.as_account_info(),
+            user_transfer_account.as_account_info(),
+            source_token_account.as_account_info(),
+            self.token_a_account.as_account_info(),
+            self.token_b_account.as_account_info(),
+            self.pool_mint_account.as_account_info(),
+            pool_account.as_account_info(),
+            source_mint_account.as_account_info(),
+            token_program_info.clone(),
+            token_program_info.clone(),
+        ];
+        if let Some(curve_account) = self.curve_account.as_mut() {
+            account_infos.push(curve_account.as_account_info());
+        }
+        do_process_instruction(deposit_instruction, &account_infos)
     }
 
     pub fn withdraw_single_token_type_exact_amount_out(
@@ -512,9 +644,17 @@ impl NativeTokenSwap {
         )
         .unwrap();
 
+        let destination_mint_key = native_token::get_token_account_mint(destination_token_account);
+        let mut destination_mint_account = if destination_mint_key == self.token_a_mint_account.key
+        {
+            self.token_a_mint_account.clone()
+        } else {
+            self.token_b_mint_account.clone()
+        };
         let withdraw_instruction = instruction::withdraw_single_token_type_exact_amount_out(
             &spl_token_swap::id(),
             &spl_token::id(),
+            &spl_token::id(),
             &self.swap_account.key,
             &self.authority_account.key,
             &user_transfer_account.key,
@@ -524,25 +664,31 @@ impl NativeTokenSwap {
             &self.token_a_account.key,
             &self.token_b_account.key,
             &destination_token_account.key,
+            &destination_mint_account.key,
+            self.curve_account_key(),
             instruction,
         )
         .unwrap();
 
-        do_process_instruction(
-            withdraw_instruction,
-            &[
-                self.swap_account.as_account_info(),
-                self.authority_account.as_account_info(),
-                user_transfer_account.as_account_info(),
-                self.pool_mint_account.as_account_info(),
-                pool_account.as_account_info(),
-                self.token_a_account.as_account_info(),
-                self.token_b_account.as_account_info(),
-                destination_token_account.as_account_info(),
-                self.pool_fee_account.as_account_info(),
-                self.token_program_account.as_account_info(),
-            ],
-        )
+        let token_program_info = self.token_program_account.as_account_info();
+        let mut account_infos = vec![
+            self.swap_account.as_account_info(),
+            self.authority_account.as_account_info(),
+            user_transfer_account.as_account_info(),
+            self.pool_mint_account.as_account_info(),
+            pool_account.as_account_info(),
+            self.token_a_account.as_account_info(),
+            self.token_b_account.as_account_info(),
+            destination_token_account.as_account_info(),
+            self.pool_fee_account.as_account_info(),
+            destination_mint_account.as_account_info(),
+            token_program_info.clone(),
+            token_program_info.clone(),
+        ];
+        if let Some(curve_account) = self.curve_account.as_mut() {
+            account_infos.push(curve_account.as_account_info());
+        }
+        do_process_instruction(withdraw_instruction, &account_infos)
     }
 
     pub fn withdraw_all(
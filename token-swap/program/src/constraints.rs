@@ -3,11 +3,11 @@
 use crate::{
     curve::{
         base::{CurveType, SwapCurve},
-        fees::Fees,
+        fees::{DynamicFees, Fees},
     },
     error::SwapError,
 };
-use solana_program::program_error::ProgramError;
+use solana_program::{program_error::ProgramError, program_option::COption, pubkey::Pubkey};
 
 #[cfg(feature = "production")]
 use std::env;
@@ -25,6 +25,12 @@ pub struct SwapConstraints<'a> {
     pub valid_curve_types: &'a [CurveType],
     /// Valid fees
     pub fees: &'a Fees,
+    /// Required pause authority for all new swaps, if any
+    pub pause_authority: Option<&'a str>,
+    /// Maximum dynamic fees
+    pub max_dynamic_fees: &'a DynamicFees,
+    /// Required owner program of oracle accounts, if any
+    pub oracle_program_id: Option<&'a str>,
 }
 
 impl<'a> SwapConstraints<'a> {
@@ -57,6 +63,59 @@ impl<'a> SwapConstraints<'a> {
             Err(SwapError::InvalidFee.into())
         }
     }
+
+    /// Checks that the provided dynamic fees are within the given constraints
+    pub fn validate_dynamic_fees(&self, dynamic_fees: &DynamicFees) -> Result<(), ProgramError> {
+        if !dynamic_fees.is_enabled() {
+            return Ok(());
+        }
+        let max_dynamic_fees = self.max_dynamic_fees;
+        // compare volatility fee fractions by cross-multiplying
+        let volatility_fee = u128::from(dynamic_fees.volatility_fee_numerator)
+            * u128::from(max_dynamic_fees.volatility_fee_denominator);
+        let max_volatility_fee = u128::from(max_dynamic_fees.volatility_fee_numerator)
+            * u128::from(dynamic_fees.volatility_fee_denominator);
+        if max_dynamic_fees.is_enabled()
+            && dynamic_fees.decay_half_life_slots <= max_dynamic_fees.decay_half_life_slots
+            && volatility_fee <= max_volatility_fee
+            && dynamic_fees.max_volatility_fee_numerator
+                <= max_dynamic_fees.max_volatility_fee_numerator
+        {
+            Ok(())
+        } else {
+            Err(SwapError::InvalidFee.into())
+        }
+    }
+
+    /// Checks that the provided pause authority is valid for the given constraints
+    pub fn validate_pause_authority(
+        &self,
+        pause_authority: &COption<Pubkey>,
+    ) -> Result<(), ProgramError> {
+        if let Some(required_pause_authority) = self.pause_authority {
+            let required_pause_authority = required_pause_authority
+                .parse::<Pubkey>()
+                .map_err(|_| SwapError::InvalidPauseAuthority)?;
+            if *pause_authority != COption::Some(required_pause_authority) {
+                return Err(SwapError::InvalidPauseAuthority.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the provided oracle account is owned by the required
+    /// oracle program
+    pub fn validate_oracle_owner(&self, oracle_owner: &Pubkey) -> Result<(), ProgramError> {
+        if let Some(oracle_program_id) = self.oracle_program_id {
+            let oracle_program_id = oracle_program_id
+                .parse::<Pubkey>()
+                .map_err(|_| SwapError::InvalidOracle)?;
+            if *oracle_owner != oracle_program_id {
+                return Err(SwapError::InvalidOracle.into());
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(feature = "production")]
@@ -73,6 +132,15 @@ const FEES: &Fees = &Fees {
     host_fee_denominator: 100,
 };
 #[cfg(feature = "production")]
+const MAX_DYNAMIC_FEES: &DynamicFees = &DynamicFees {
+    decay_half_life_slots: 9_000,
+    volatility_fee_numerator: 1,
+    volatility_fee_denominator: 10,
+    max_volatility_fee_numerator: 100,
+};
+#[cfg(feature = "production")]
+const PYTH_PROGRAM_ID: &str = "FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH";
+#[cfg(feature = "production")]
 const VALID_CURVE_TYPES: &[CurveType] = &[CurveType::ConstantPrice, CurveType::ConstantProduct];
 
 /// Fee structure defined by program creator in order to enforce certain
@@ -80,7 +148,10 @@ const VALID_CURVE_TYPES: &[CurveType] = &[CurveType::ConstantPrice, CurveType::C
 /// swapping to ensure the correct fees and account owners are passed.
 /// Fees provided during production build currently are considered min
 /// fees that creator of the pool can specify. Host fee is a fixed
-/// percentage that host receives as a portion of owner fees
+/// percentage that host receives as a portion of owner fees.
+/// All production pools can be paused by the program owner in case of an
+/// emergency.  Dynamic fees can raise the trade fee by at most 1%, decaying
+/// over at most an hour.  Oracle prices must come from the Pyth program.
 pub const SWAP_CONSTRAINTS: Option<SwapConstraints> = {
     #[cfg(feature = "production")]
     {
@@ -88,6 +159,9 @@ pub const SWAP_CONSTRAINTS: Option<SwapConstraints> = {
             owner_key: OWNER_KEY,
             valid_curve_types: VALID_CURVE_TYPES,
             fees: FEES,
+            pause_authority: Some(OWNER_KEY),
+            max_dynamic_fees: MAX_DYNAMIC_FEES,
+            oracle_program_id: Some(PYTH_PROGRAM_ID),
         })
     }
     #[cfg(not(feature = "production"))]
@@ -133,6 +207,9 @@ mod tests {
             owner_key,
             valid_curve_types: &[curve_type],
             fees: &valid_fees,
+            pause_authority: None,
+            max_dynamic_fees: &DynamicFees::default(),
+            oracle_program_id: None,
         };
 
         constraints.validate_curve(&swap_curve).unwrap();
@@ -193,4 +270,140 @@ mod tests {
             constraints.validate_curve(&swap_curve),
         );
     }
+
+    #[test]
+    fn validate_pause_authority() {
+        let pause_authority = Pubkey::new_unique();
+        let pause_authority_str = pause_authority.to_string();
+        let fees = Fees::default();
+        let constraints = SwapConstraints {
+            owner_key: "",
+            valid_curve_types: &[],
+            fees: &fees,
+            pause_authority: Some(&pause_authority_str),
+            max_dynamic_fees: &DynamicFees::default(),
+            oracle_program_id: None,
+        };
+
+        constraints
+            .validate_pause_authority(&COption::Some(pause_authority))
+            .unwrap();
+        assert_eq!(
+            Err(SwapError::InvalidPauseAuthority.into()),
+            constraints.validate_pause_authority(&COption::None),
+        );
+        assert_eq!(
+            Err(SwapError::InvalidPauseAuthority.into()),
+            constraints.validate_pause_authority(&COption::Some(Pubkey::new_unique())),
+        );
+
+        let constraints = SwapConstraints {
+            pause_authority: None,
+            ..constraints
+        };
+        constraints
+            .validate_pause_authority(&COption::None)
+            .unwrap();
+        constraints
+            .validate_pause_authority(&COption::Some(pause_authority))
+            .unwrap();
+    }
+
+    #[test]
+    fn validate_oracle_owner() {
+        let oracle_program_id = Pubkey::new_unique();
+        let oracle_program_id_str = oracle_program_id.to_string();
+        let fees = Fees::default();
+        let constraints = SwapConstraints {
+            owner_key: "",
+            valid_curve_types: &[],
+            fees: &fees,
+            pause_authority: None,
+            max_dynamic_fees: &DynamicFees::default(),
+            oracle_program_id: Some(&oracle_program_id_str),
+        };
+
+        constraints
+            .validate_oracle_owner(&oracle_program_id)
+            .unwrap();
+        assert_eq!(
+            Err(SwapError::InvalidOracle.into()),
+            constraints.validate_oracle_owner(&Pubkey::new_unique()),
+        );
+
+        let constraints = SwapConstraints {
+            oracle_program_id: None,
+            ..constraints
+        };
+        constraints
+            .validate_oracle_owner(&Pubkey::new_unique())
+            .unwrap();
+    }
+
+    #[test]
+    fn validate_dynamic_fees() {
+        let fees = Fees::default();
+        let max_dynamic_fees = DynamicFees {
+            decay_half_life_slots: 100,
+            volatility_fee_numerator: 1,
+            volatility_fee_denominator: 10,
+            max_volatility_fee_numerator: 50,
+        };
+        let constraints = SwapConstraints {
+            owner_key: "",
+            valid_curve_types: &[],
+            fees: &fees,
+            pause_authority: None,
+            max_dynamic_fees: &max_dynamic_fees,
+            oracle_program_id: None,
+        };
+
+        constraints
+            .validate_dynamic_fees(&max_dynamic_fees)
+            .unwrap();
+        constraints
+            .validate_dynamic_fees(&DynamicFees::default())
+            .unwrap();
+        // same volatility fee with a different denominator is ok
+        constraints
+            .validate_dynamic_fees(&DynamicFees {
+                volatility_fee_numerator: 10,
+                volatility_fee_denominator: 100,
+                ..max_dynamic_fees.clone()
+            })
+            .unwrap();
+
+        assert_eq!(
+            Err(SwapError::InvalidFee.into()),
+            constraints.validate_dynamic_fees(&DynamicFees {
+                decay_half_life_slots: 101,
+                ..max_dynamic_fees.clone()
+            }),
+        );
+        assert_eq!(
+            Err(SwapError::InvalidFee.into()),
+            constraints.validate_dynamic_fees(&DynamicFees {
+                volatility_fee_numerator: 11,
+                volatility_fee_denominator: 100,
+                ..max_dynamic_fees.clone()
+            }),
+        );
+        assert_eq!(
+            Err(SwapError::InvalidFee.into()),
+            constraints.validate_dynamic_fees(&DynamicFees {
+                max_volatility_fee_numerator: 51,
+                ..max_dynamic_fees.clone()
+            }),
+        );
+
+        // no dynamic fees allowed
+        let constraints = SwapConstraints {
+            max_dynamic_fees: &DynamicFees::default(),
+            ..constraints
+        };
+        assert_eq!(
+            Err(SwapError::InvalidFee.into()),
+            constraints.validate_dynamic_fees(&max_dynamic_fees),
+        );
+    }
 }
@@ -11,7 +11,9 @@ use crate::curve::{
     constant_product::ConstantProductCurve,
     fees::Fees,
     offset::OffsetCurve,
+    proactive_market_making::ProactiveMarketMakingCurve,
     stable::StableCurve,
+    stake_pool_stable::StakePoolStableCurve,
 };
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use std::convert::{TryFrom, TryInto};
@@ -34,6 +36,12 @@ pub enum CurveType {
     Stable,
     /// Offset curve, like Uniswap, but the token B side has a faked offset
     Offset,
+    /// Stable curve pegged to the exchange rate of a stake pool, token A is
+    /// the stake pool token and token B is SOL
+    StakePoolStable,
+    /// Proactive market making curve, concentrating liquidity around the
+    /// price of token A in token B given by an oracle
+    ProactiveMarketMaking,
 }
 
 /// Encodes all results of swapping from a source token to a destination token
@@ -224,6 +232,12 @@ impl Pack for SwapCurve {
                 }
                 CurveType::Stable => Arc::new(StableCurve::unpack_from_slice(calculator)?),
                 CurveType::Offset => Arc::new(OffsetCurve::unpack_from_slice(calculator)?),
+                CurveType::StakePoolStable => {
+                    Arc::new(StakePoolStableCurve::unpack_from_slice(calculator)?)
+                }
+                CurveType::ProactiveMarketMaking => {
+                    Arc::new(ProactiveMarketMakingCurve::unpack_from_slice(calculator)?)
+                }
             },
         })
     }
@@ -254,6 +268,8 @@ impl TryFrom<u8> for CurveType {
             1 => Ok(CurveType::ConstantPrice),
             2 => Ok(CurveType::Stable),
             3 => Ok(CurveType::Offset),
+            4 => Ok(CurveType::StakePoolStable),
+            5 => Ok(CurveType::ProactiveMarketMaking),
             _ => Err(ProgramError::InvalidAccountData),
         }
     }
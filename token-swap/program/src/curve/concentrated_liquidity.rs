@@ -0,0 +1,566 @@
+//! Concentrated liquidity math, for pools where liquidity is provided in
+//! price ranges bounded by ticks.
+//!
+//! Following Uniswap V3, the price of token A in token B at tick `i` is
+//! `1.0001^i`, and prices are stored as their square roots in Q64.64 fixed
+//! point, so that token amounts are linear in the square root of the price
+//! for a given liquidity.
+
+use {
+    crate::curve::{
+        calculator::{RoundDirection, TradeDirection, TradingTokenResult},
+        fees::Fees,
+    },
+    spl_math::uint::U256,
+    std::convert::TryFrom,
+};
+
+/// Lowest tick, at which the price is about `2^-64`
+pub const MIN_TICK: i32 = -443_636;
+/// Highest tick, at which the price is about `2^64`
+pub const MAX_TICK: i32 = 443_636;
+/// Square root of the price at `MIN_TICK`, in Q64.64
+pub const MIN_SQRT_PRICE: u128 = 4_295_048_016;
+/// Square root of the price at `MAX_TICK`, in Q64.64
+pub const MAX_SQRT_PRICE: u128 = 79_226_673_515_401_279_963_822_778_343;
+
+/// Number of fractional bits of the square root prices
+const RESOLUTION: u32 = 64;
+
+/// `sqrt(1.0001)^(2^i)` in Q64.64, rounded down, for each bit `i` of the
+/// absolute value of a tick
+const SQRT_PRICE_FACTORS: [u128; 19] = [
+    18_447_666_387_855_959_850,
+    18_448_588_748_116_922_571,
+    18_450_433_606_991_734_263,
+    18_454_123_878_217_468_680,
+    18_461_506_635_090_006_701,
+    18_476_281_010_653_910_144,
+    18_505_865_242_158_250_041,
+    18_565_175_891_880_433_522,
+    18_684_368_066_214_940_582,
+    18_925_053_041_275_764_671,
+    19_415_764_168_677_886_926,
+    20_435_687_552_633_177_494,
+    22_639_080_592_224_303_007,
+    27_784_196_929_998_399_742,
+    41_848_122_137_994_986_128,
+    94_936_283_578_220_370_716,
+    488_590_176_327_622_479_860,
+    12_941_056_668_319_229_769_860,
+    9_078_618_265_828_848_800_676_189,
+];
+
+/// Encodes the result of swapping within a single range of constant
+/// liquidity
+#[derive(Debug, PartialEq)]
+pub struct SwapStepResult {
+    /// Square root of the price after the step, in Q64.64
+    pub sqrt_price_next: u128,
+    /// Amount of source token swapped, without fees
+    pub amount_in: u128,
+    /// Amount of destination token swapped
+    pub amount_out: u128,
+    /// Amount of source token taken as fees
+    pub fee_amount: u128,
+}
+
+fn div_ceil(numerator: U256, denominator: U256) -> Option<U256> {
+    let quotient = numerator.checked_div(denominator)?;
+    if quotient.checked_mul(denominator)? == numerator {
+        Some(quotient)
+    } else {
+        quotient.checked_add(U256::one())
+    }
+}
+
+/// Square root of the price at the given tick, in Q64.64
+pub fn sqrt_price_at_tick(tick: i32) -> Option<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return None;
+    }
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = U256::one() << RESOLUTION;
+    for (bit, factor) in SQRT_PRICE_FACTORS.iter().enumerate() {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = ratio.checked_mul(U256::from(*factor))? >> RESOLUTION;
+        }
+    }
+    if tick < 0 {
+        ratio = (U256::one() << (2 * RESOLUTION)).checked_div(ratio)?;
+    }
+    u128::try_from(ratio).ok()
+}
+
+/// Greatest tick whose square root price is not above the given one
+pub fn tick_at_sqrt_price(sqrt_price: u128) -> Option<i32> {
+    if !(MIN_SQRT_PRICE..=MAX_SQRT_PRICE).contains(&sqrt_price) {
+        return None;
+    }
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let middle = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(middle)? <= sqrt_price {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    Some(low)
+}
+
+/// Amount of token A provided by the given liquidity between two square root
+/// prices, `L / sqrt(P_lower) - L / sqrt(P_upper)`
+pub fn token_a_amount_delta(
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    liquidity: u128,
+    round_direction: RoundDirection,
+) -> Option<u128> {
+    if sqrt_price_lower == 0 || sqrt_price_lower > sqrt_price_upper {
+        return None;
+    }
+    let numerator = U256::from(liquidity) << RESOLUTION;
+    let (sqrt_price_lower, sqrt_price_upper) =
+        (U256::from(sqrt_price_lower), U256::from(sqrt_price_upper));
+    let amount = match round_direction {
+        RoundDirection::Floor => numerator
+            .checked_div(sqrt_price_lower)?
+            .saturating_sub(div_ceil(numerator, sqrt_price_upper)?),
+        RoundDirection::Ceiling => div_ceil(numerator, sqrt_price_lower)?
+            .checked_sub(numerator.checked_div(sqrt_price_upper)?)?,
+    };
+    u128::try_from(amount).ok()
+}
+
+/// Amount of token B provided by the given liquidity between two square root
+/// prices, `L * (sqrt(P_upper) - sqrt(P_lower))`
+pub fn token_b_amount_delta(
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    liquidity: u128,
+    round_direction: RoundDirection,
+) -> Option<u128> {
+    let product = U256::from(liquidity)
+        .checked_mul(U256::from(sqrt_price_upper.checked_sub(sqrt_price_lower)?))?;
+    let amount = match round_direction {
+        RoundDirection::Floor => product >> RESOLUTION,
+        RoundDirection::Ceiling => div_ceil(product, U256::one() << RESOLUTION)?,
+    };
+    u128::try_from(amount).ok()
+}
+
+/// Token amounts backing the given liquidity in a tick range, at the current
+/// price of the pool.  Below the range, the liquidity is all in token A, and
+/// above the range, all in token B.
+pub fn token_amounts_for_liquidity(
+    tick_current: i32,
+    sqrt_price: u128,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+    round_direction: RoundDirection,
+) -> Option<TradingTokenResult> {
+    let sqrt_price_lower = sqrt_price_at_tick(tick_lower)?;
+    let sqrt_price_upper = sqrt_price_at_tick(tick_upper)?;
+    let (token_a_amount, token_b_amount) = if tick_current < tick_lower {
+        (
+            token_a_amount_delta(
+                sqrt_price_lower,
+                sqrt_price_upper,
+                liquidity,
+                round_direction,
+            )?,
+            0,
+        )
+    } else if tick_current < tick_upper {
+        (
+            token_a_amount_delta(sqrt_price, sqrt_price_upper, liquidity, round_direction)?,
+            token_b_amount_delta(sqrt_price_lower, sqrt_price, liquidity, round_direction)?,
+        )
+    } else {
+        (
+            0,
+            token_b_amount_delta(
+                sqrt_price_lower,
+                sqrt_price_upper,
+                liquidity,
+                round_direction,
+            )?,
+        )
+    };
+    Some(TradingTokenResult {
+        token_a_amount,
+        token_b_amount,
+    })
+}
+
+/// Square root price reached by swapping the given amount of source token
+/// into the given liquidity, rounded against the trader
+fn next_sqrt_price_from_input(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount_in: u128,
+    trade_direction: TradeDirection,
+) -> Option<u128> {
+    let liquidity = U256::from(liquidity) << RESOLUTION;
+    let next = match trade_direction {
+        // token A in, price down: L / (L / sqrt(P) + amount)
+        TradeDirection::AtoB => {
+            let denominator = liquidity
+                .checked_div(U256::from(sqrt_price))?
+                .checked_add(U256::from(amount_in))?;
+            div_ceil(liquidity, denominator)?
+        }
+        // token B in, price up: sqrt(P) + amount / L
+        TradeDirection::BtoA => U256::from(sqrt_price)
+            .checked_add((U256::from(amount_in) << (2 * RESOLUTION)).checked_div(liquidity)?)?,
+    };
+    u128::try_from(next).ok()
+}
+
+/// Combined trade and owner fee rate of the pool, as a numerator and
+/// denominator, with the owner part of the numerator.  Returns `None` if the
+/// fees add up to the whole amount.
+pub fn total_trade_fee(fees: &Fees) -> Option<(u128, u128, u128)> {
+    let fraction = |numerator: u64, denominator: u64| {
+        if numerator == 0 || denominator == 0 {
+            (0u128, 1u128)
+        } else {
+            (u128::from(numerator), u128::from(denominator))
+        }
+    };
+    let (trade_numerator, trade_denominator) =
+        fraction(fees.trade_fee_numerator, fees.trade_fee_denominator);
+    let (owner_numerator, owner_denominator) = fraction(
+        fees.owner_trade_fee_numerator,
+        fees.owner_trade_fee_denominator,
+    );
+    let owner_part = owner_numerator.checked_mul(trade_denominator)?;
+    let numerator = trade_numerator
+        .checked_mul(owner_denominator)?
+        .checked_add(owner_part)?;
+    let denominator = trade_denominator.checked_mul(owner_denominator)?;
+    if numerator >= denominator {
+        None
+    } else {
+        Some((numerator, denominator, owner_part))
+    }
+}
+
+/// Swap within a range of constant liquidity, from the current square root
+/// price towards the target one, until either the target is reached or the
+/// remaining amount runs out.  Fees are taken on the amount swapped, at the
+/// given rate.
+pub fn compute_swap_step(
+    sqrt_price: u128,
+    sqrt_price_target: u128,
+    liquidity: u128,
+    amount_remaining: u128,
+    fee_numerator: u128,
+    fee_denominator: u128,
+    trade_direction: TradeDirection,
+) -> Option<SwapStepResult> {
+    let fee_complement = fee_denominator.checked_sub(fee_numerator)?;
+    let amount_less_fee = u128::try_from(
+        U256::from(amount_remaining)
+            .checked_mul(U256::from(fee_complement))?
+            .checked_div(U256::from(fee_denominator))?,
+    )
+    .ok()?;
+    let amount_in_to_target = match trade_direction {
+        TradeDirection::AtoB => token_a_amount_delta(
+            sqrt_price_target,
+            sqrt_price,
+            liquidity,
+            RoundDirection::Ceiling,
+        )?,
+        TradeDirection::BtoA => token_b_amount_delta(
+            sqrt_price,
+            sqrt_price_target,
+            liquidity,
+            RoundDirection::Ceiling,
+        )?,
+    };
+    let reached_target = amount_less_fee >= amount_in_to_target;
+    let sqrt_price_next = if reached_target {
+        sqrt_price_target
+    } else {
+        next_sqrt_price_from_input(sqrt_price, liquidity, amount_less_fee, trade_direction)?
+    };
+    let (amount_in, amount_out) = match trade_direction {
+        TradeDirection::AtoB => (
+            token_a_amount_delta(
+                sqrt_price_next,
+                sqrt_price,
+                liquidity,
+                RoundDirection::Ceiling,
+            )?,
+            token_b_amount_delta(
+                sqrt_price_next,
+                sqrt_price,
+                liquidity,
+                RoundDirection::Floor,
+            )?,
+        ),
+        TradeDirection::BtoA => (
+            token_b_amount_delta(
+                sqrt_price,
+                sqrt_price_next,
+                liquidity,
+                RoundDirection::Ceiling,
+            )?,
+            token_a_amount_delta(
+                sqrt_price,
+                sqrt_price_next,
+                liquidity,
+                RoundDirection::Floor,
+            )?,
+        ),
+    };
+    let fee_amount = if reached_target {
+        u128::try_from(div_ceil(
+            U256::from(amount_in).checked_mul(U256::from(fee_numerator))?,
+            U256::from(fee_complement),
+        )?)
+        .ok()?
+    } else {
+        // the price can't move any further, so the rest goes to fees
+        amount_remaining.checked_sub(amount_in)?
+    };
+    Some(SwapStepResult {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE: u128 = 1 << RESOLUTION;
+
+    #[test]
+    fn sqrt_price_bounds() {
+        assert_eq!(sqrt_price_at_tick(0), Some(ONE));
+        assert_eq!(sqrt_price_at_tick(1), Some(SQRT_PRICE_FACTORS[0]));
+        assert_eq!(sqrt_price_at_tick(-1), Some(18_445_821_805_675_392_312));
+        assert_eq!(sqrt_price_at_tick(MIN_TICK), Some(MIN_SQRT_PRICE));
+        assert_eq!(sqrt_price_at_tick(MAX_TICK), Some(MAX_SQRT_PRICE));
+        assert_eq!(sqrt_price_at_tick(MIN_TICK - 1), None);
+        assert_eq!(sqrt_price_at_tick(MAX_TICK + 1), None);
+        // price of 4 at tick log(4) / log(1.0001) = 13863.6
+        let sqrt_price = sqrt_price_at_tick(13_863).unwrap();
+        assert!(sqrt_price < 2 * ONE);
+        assert!(sqrt_price_at_tick(13_864).unwrap() > 2 * ONE);
+    }
+
+    #[test]
+    fn tick_from_sqrt_price() {
+        for tick in [MIN_TICK, -200_000, -13_864, -1, 0, 1, 60, 13_863, MAX_TICK] {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(tick_at_sqrt_price(sqrt_price), Some(tick));
+            if tick != MAX_TICK {
+                assert_eq!(tick_at_sqrt_price(sqrt_price + 1), Some(tick));
+            }
+            if tick != MIN_TICK {
+                assert_eq!(tick_at_sqrt_price(sqrt_price - 1), Some(tick - 1));
+            }
+        }
+        assert_eq!(tick_at_sqrt_price(2 * ONE), Some(13_863));
+        assert_eq!(tick_at_sqrt_price(MIN_SQRT_PRICE - 1), None);
+        assert_eq!(tick_at_sqrt_price(MAX_SQRT_PRICE + 1), None);
+    }
+
+    #[test]
+    fn amount_deltas() {
+        let liquidity = 1_000_000;
+        // between prices 1 and 4, L / 1 - L / 2 of token A and L * (2 - 1) of
+        // token B
+        assert_eq!(
+            token_a_amount_delta(ONE, 2 * ONE, liquidity, RoundDirection::Floor),
+            Some(500_000)
+        );
+        assert_eq!(
+            token_b_amount_delta(ONE, 2 * ONE, liquidity, RoundDirection::Floor),
+            Some(1_000_000)
+        );
+        // inexact amounts round in the given direction
+        let sqrt_price_upper = sqrt_price_at_tick(60).unwrap();
+        let floor =
+            token_a_amount_delta(ONE, sqrt_price_upper, liquidity, RoundDirection::Floor).unwrap();
+        let ceiling =
+            token_a_amount_delta(ONE, sqrt_price_upper, liquidity, RoundDirection::Ceiling)
+                .unwrap();
+        assert_eq!(floor, 2_995);
+        assert_eq!(ceiling, 2_996);
+        let floor =
+            token_b_amount_delta(ONE, sqrt_price_upper, liquidity, RoundDirection::Floor).unwrap();
+        let ceiling =
+            token_b_amount_delta(ONE, sqrt_price_upper, liquidity, RoundDirection::Ceiling)
+                .unwrap();
+        assert_eq!(floor, 3_004);
+        assert_eq!(ceiling, 3_005);
+        // reversed bounds are rejected
+        assert_eq!(
+            token_a_amount_delta(2 * ONE, ONE, liquidity, RoundDirection::Floor),
+            None
+        );
+        assert_eq!(
+            token_b_amount_delta(2 * ONE, ONE, liquidity, RoundDirection::Floor),
+            None
+        );
+    }
+
+    #[test]
+    fn amounts_for_liquidity() {
+        let liquidity = 1_000_000;
+        let round_direction = RoundDirection::Floor;
+        // price of 1, at tick 0
+        let in_range =
+            token_amounts_for_liquidity(0, ONE, -13_864, 13_864, liquidity, round_direction)
+                .unwrap();
+        assert!(in_range.token_a_amount > 0);
+        assert_eq!(in_range.token_a_amount, in_range.token_b_amount);
+        let below =
+            token_amounts_for_liquidity(0, ONE, 60, 120, liquidity, round_direction).unwrap();
+        assert!(below.token_a_amount > 0);
+        assert_eq!(below.token_b_amount, 0);
+        let above =
+            token_amounts_for_liquidity(0, ONE, -120, -60, liquidity, round_direction).unwrap();
+        assert_eq!(above.token_a_amount, 0);
+        assert!(above.token_b_amount > 0);
+        // the current tick is inside the range from its lower bound
+        let at_lower =
+            token_amounts_for_liquidity(0, ONE, 0, 60, liquidity, round_direction).unwrap();
+        assert_eq!(at_lower.token_b_amount, 0);
+        let at_upper =
+            token_amounts_for_liquidity(0, ONE, -60, 0, liquidity, round_direction).unwrap();
+        assert_eq!(at_upper.token_a_amount, 0);
+    }
+
+    #[test]
+    fn total_fees() {
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        assert_eq!(total_trade_fee(&fees), Some((300_000, 100_000_000, 50_000)));
+        assert_eq!(total_trade_fee(&Fees::default()), Some((0, 1, 0)));
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 2,
+            owner_trade_fee_numerator: 1,
+            owner_trade_fee_denominator: 2,
+            ..Fees::default()
+        };
+        assert_eq!(total_trade_fee(&fees), None);
+    }
+
+    #[test]
+    fn swap_step_within_range() {
+        let liquidity = 1_000_000_000;
+        let sqrt_price_target = sqrt_price_at_tick(-60).unwrap();
+        let step = compute_swap_step(
+            ONE,
+            sqrt_price_target,
+            liquidity,
+            1_000,
+            3,
+            1_000,
+            TradeDirection::AtoB,
+        )
+        .unwrap();
+        assert!(step.sqrt_price_next < ONE);
+        assert!(step.sqrt_price_next > sqrt_price_target);
+        assert_eq!(step.amount_in + step.fee_amount, 1_000);
+        assert_eq!(step.fee_amount, 3);
+        // around a price of one, the output is about the input less fees
+        assert_eq!(step.amount_out, 996);
+
+        let step = compute_swap_step(
+            ONE,
+            sqrt_price_at_tick(60).unwrap(),
+            liquidity,
+            1_000,
+            3,
+            1_000,
+            TradeDirection::BtoA,
+        )
+        .unwrap();
+        assert!(step.sqrt_price_next > ONE);
+        assert_eq!(step.amount_in + step.fee_amount, 1_000);
+        assert_eq!(step.amount_out, 996);
+    }
+
+    #[test]
+    fn swap_step_to_target() {
+        let liquidity = 1_000_000;
+        let sqrt_price_target = sqrt_price_at_tick(-60).unwrap();
+        let step = compute_swap_step(
+            ONE,
+            sqrt_price_target,
+            liquidity,
+            1_000_000,
+            3,
+            1_000,
+            TradeDirection::AtoB,
+        )
+        .unwrap();
+        assert_eq!(step.sqrt_price_next, sqrt_price_target);
+        assert_eq!(
+            step.amount_in,
+            token_a_amount_delta(sqrt_price_target, ONE, liquidity, RoundDirection::Ceiling)
+                .unwrap()
+        );
+        assert_eq!(
+            step.amount_out,
+            token_b_amount_delta(sqrt_price_target, ONE, liquidity, RoundDirection::Floor).unwrap()
+        );
+        assert!(step.amount_in + step.fee_amount < 1_000_000);
+        assert!(step.fee_amount >= step.amount_in * 3 / 997);
+
+        // no liquidity, so the price jumps straight to the target
+        let step = compute_swap_step(
+            ONE,
+            sqrt_price_target,
+            0,
+            1_000,
+            3,
+            1_000,
+            TradeDirection::AtoB,
+        )
+        .unwrap();
+        assert_eq!(
+            step,
+            SwapStepResult {
+                sqrt_price_next: sqrt_price_target,
+                amount_in: 0,
+                amount_out: 0,
+                fee_amount: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn swap_step_rounds_against_trader() {
+        // too small to move the price of a deep range: the trader gets nothing
+        let step = compute_swap_step(
+            ONE,
+            sqrt_price_at_tick(-60).unwrap(),
+            u64::MAX.into(),
+            1,
+            0,
+            1,
+            TradeDirection::AtoB,
+        )
+        .unwrap();
+        assert_eq!(step.amount_out, 0);
+        assert_eq!(step.amount_in + step.fee_amount, 1);
+    }
+}
@@ -6,7 +6,10 @@ use solana_program::{
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
 };
-use std::convert::TryFrom;
+use std::convert::{TryFrom, TryInto};
+
+/// Price movement tracked for dynamic fees is measured in parts per million
+pub const VOLATILITY_PRECISION: u64 = 1_000_000;
 
 /// Encapsulates all fee information and calculations for swap operations
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -125,6 +128,112 @@ impl Fees {
     }
 }
 
+/// Dynamic trade fee parameters.  Every swap adds its price impact to a
+/// volatility accumulator kept in the swap state, which decays by half every
+/// `decay_half_life_slots`.  The trade fee is raised in proportion to the
+/// accumulated volatility, up to a maximum.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DynamicFees {
+    /// Number of slots for the volatility accumulator to decay by half,
+    /// dynamic fees are disabled if zero
+    pub decay_half_life_slots: u64,
+
+    /// Additional trade fee charged for each unit of accumulated volatility.
+    /// Volatility fee numerator
+    pub volatility_fee_numerator: u64,
+    /// Volatility fee denominator
+    pub volatility_fee_denominator: u64,
+
+    /// Maximum additional trade fee numerator, over the trade fee denominator
+    pub max_volatility_fee_numerator: u64,
+}
+
+/// Calculate the price impact of a swap in parts per million, as the relative
+/// decrease of the price of the source token given by the ratio of the swap
+/// token amounts
+pub fn price_impact(
+    swap_source_amount: u128,
+    swap_destination_amount: u128,
+    new_swap_source_amount: u128,
+    new_swap_destination_amount: u128,
+) -> Option<u64> {
+    let precision = u128::from(VOLATILITY_PRECISION);
+    let destination_ratio = new_swap_destination_amount
+        .checked_mul(precision)?
+        .checked_div(swap_destination_amount)?;
+    let source_ratio = swap_source_amount
+        .checked_mul(precision)?
+        .checked_div(new_swap_source_amount)?;
+    let price_ratio = destination_ratio
+        .checked_mul(source_ratio)?
+        .checked_div(precision)?;
+    precision.saturating_sub(price_ratio).try_into().ok()
+}
+
+impl DynamicFees {
+    /// Are dynamic fees charged on top of the static trade fee
+    pub fn is_enabled(&self) -> bool {
+        self.decay_half_life_slots != 0
+    }
+
+    /// Decay the volatility accumulator over the elapsed slots, halving it
+    /// every half-life and interpolating linearly in between
+    pub fn decay_volatility(&self, volatility: u64, slots_elapsed: u64) -> u64 {
+        if !self.is_enabled() {
+            return 0;
+        }
+        let half_lives = slots_elapsed / self.decay_half_life_slots;
+        let volatility = u32::try_from(half_lives)
+            .ok()
+            .and_then(|half_lives| volatility.checked_shr(half_lives))
+            .unwrap_or(0);
+        let remaining_slots = slots_elapsed % self.decay_half_life_slots;
+        let decay = u128::from(volatility) * u128::from(remaining_slots)
+            / (2 * u128::from(self.decay_half_life_slots));
+        volatility.saturating_sub(decay as u64)
+    }
+
+    /// Calculate the fees to charge given the current volatility, raising the
+    /// trade fee of the static fees
+    pub fn trade_fees(&self, fees: &Fees, volatility: u64) -> Option<Fees> {
+        if !self.is_enabled() {
+            return Some(fees.clone());
+        }
+        let volatility_fee_numerator = u128::from(volatility)
+            .checked_mul(u128::from(self.volatility_fee_numerator))?
+            .checked_mul(u128::from(fees.trade_fee_denominator))?
+            .checked_div(
+                u128::from(self.volatility_fee_denominator)
+                    .checked_mul(u128::from(VOLATILITY_PRECISION))?,
+            )?;
+        let volatility_fee_numerator = std::cmp::min(
+            volatility_fee_numerator,
+            u128::from(self.max_volatility_fee_numerator),
+        );
+        Some(Fees {
+            trade_fee_numerator: fees
+                .trade_fee_numerator
+                .checked_add(volatility_fee_numerator.try_into().ok()?)?,
+            ..fees.clone()
+        })
+    }
+
+    /// Validate that the dynamic fees are reasonable for the given static fees
+    pub fn validate(&self, fees: &Fees) -> Result<(), SwapError> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        if self.volatility_fee_denominator == 0 {
+            return Err(SwapError::InvalidFee);
+        }
+        let max_trade_fee_numerator = fees
+            .trade_fee_numerator
+            .checked_add(self.max_volatility_fee_numerator)
+            .ok_or(SwapError::InvalidFee)?;
+        validate_fraction(max_trade_fee_numerator, fees.trade_fee_denominator)
+    }
+}
+
 /// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
 impl IsInitialized for Fees {
     fn is_initialized(&self) -> bool {
@@ -183,6 +292,48 @@ impl Pack for Fees {
     }
 }
 
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for DynamicFees {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for DynamicFees {}
+impl Pack for DynamicFees {
+    const LEN: usize = 32;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 32];
+        let (
+            decay_half_life_slots,
+            volatility_fee_numerator,
+            volatility_fee_denominator,
+            max_volatility_fee_numerator,
+        ) = mut_array_refs![output, 8, 8, 8, 8];
+        *decay_half_life_slots = self.decay_half_life_slots.to_le_bytes();
+        *volatility_fee_numerator = self.volatility_fee_numerator.to_le_bytes();
+        *volatility_fee_denominator = self.volatility_fee_denominator.to_le_bytes();
+        *max_volatility_fee_numerator = self.max_volatility_fee_numerator.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<DynamicFees, ProgramError> {
+        let input = array_ref![input, 0, 32];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            decay_half_life_slots,
+            volatility_fee_numerator,
+            volatility_fee_denominator,
+            max_volatility_fee_numerator,
+        ) = array_refs![input, 8, 8, 8, 8];
+        Ok(Self {
+            decay_half_life_slots: u64::from_le_bytes(*decay_half_life_slots),
+            volatility_fee_numerator: u64::from_le_bytes(*volatility_fee_numerator),
+            volatility_fee_denominator: u64::from_le_bytes(*volatility_fee_denominator),
+            max_volatility_fee_numerator: u64::from_le_bytes(*max_volatility_fee_numerator),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,4 +376,119 @@ mod tests {
         let unpacked = Fees::unpack_from_slice(&packed).unwrap();
         assert_eq!(fees, unpacked);
     }
+
+    fn dynamic_fees() -> DynamicFees {
+        DynamicFees {
+            decay_half_life_slots: 100,
+            volatility_fee_numerator: 1,
+            volatility_fee_denominator: 10,
+            max_volatility_fee_numerator: 50,
+        }
+    }
+
+    fn static_fees() -> Fees {
+        Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            ..Fees::default()
+        }
+    }
+
+    #[test]
+    fn pack_dynamic_fees() {
+        let dynamic_fees = dynamic_fees();
+        let mut packed = [0u8; DynamicFees::LEN];
+        Pack::pack_into_slice(&dynamic_fees, &mut packed[..]);
+        let unpacked = DynamicFees::unpack_from_slice(&packed).unwrap();
+        assert_eq!(dynamic_fees, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&dynamic_fees.decay_half_life_slots.to_le_bytes());
+        packed.extend_from_slice(&dynamic_fees.volatility_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&dynamic_fees.volatility_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&dynamic_fees.max_volatility_fee_numerator.to_le_bytes());
+        let unpacked = DynamicFees::unpack_from_slice(&packed).unwrap();
+        assert_eq!(dynamic_fees, unpacked);
+    }
+
+    #[test]
+    fn decay_volatility() {
+        let dynamic_fees = dynamic_fees();
+        assert_eq!(dynamic_fees.decay_volatility(1_000, 0), 1_000);
+        assert_eq!(dynamic_fees.decay_volatility(1_000, 50), 750);
+        assert_eq!(dynamic_fees.decay_volatility(1_000, 100), 500);
+        assert_eq!(dynamic_fees.decay_volatility(1_000, 150), 375);
+        assert_eq!(dynamic_fees.decay_volatility(1_000, 1_000), 0);
+        assert_eq!(dynamic_fees.decay_volatility(u64::MAX, u64::MAX), 0);
+        assert_eq!(DynamicFees::default().decay_volatility(1_000, 0), 0);
+    }
+
+    #[test]
+    fn trade_fees() {
+        let dynamic_fees = dynamic_fees();
+        let fees = static_fees();
+        assert_eq!(dynamic_fees.trade_fees(&fees, 0).unwrap(), fees);
+
+        // 2% of price movement adds 0.2% of trade fee
+        let raised_fees = dynamic_fees.trade_fees(&fees, 20_000).unwrap();
+        assert_eq!(raised_fees.trade_fee_numerator, 45);
+        assert_eq!(
+            raised_fees,
+            Fees {
+                trade_fee_numerator: 45,
+                ..fees
+            }
+        );
+
+        // capped to the maximum
+        let raised_fees = dynamic_fees.trade_fees(&fees, u64::MAX).unwrap();
+        assert_eq!(raised_fees.trade_fee_numerator, 75);
+
+        // disabled
+        assert_eq!(
+            DynamicFees::default().trade_fees(&fees, u64::MAX).unwrap(),
+            fees
+        );
+    }
+
+    #[test]
+    fn validate_dynamic_fees() {
+        let fees = static_fees();
+        dynamic_fees().validate(&fees).unwrap();
+        DynamicFees::default().validate(&fees).unwrap();
+        assert_eq!(
+            Err(SwapError::InvalidFee),
+            DynamicFees {
+                volatility_fee_denominator: 0,
+                ..dynamic_fees()
+            }
+            .validate(&fees)
+        );
+        assert_eq!(
+            Err(SwapError::InvalidFee),
+            DynamicFees {
+                max_volatility_fee_numerator: 9_975,
+                ..dynamic_fees()
+            }
+            .validate(&fees)
+        );
+        assert_eq!(
+            Err(SwapError::InvalidFee),
+            DynamicFees {
+                max_volatility_fee_numerator: u64::MAX,
+                ..dynamic_fees()
+            }
+            .validate(&fees)
+        );
+    }
+
+    #[test]
+    fn price_impact_of_swap() {
+        assert_eq!(price_impact(1_000, 1_000, 1_000, 1_000).unwrap(), 0);
+        // constant product swap of 10% of the pool
+        assert_eq!(price_impact(1_000, 1_000, 1_100, 910).unwrap(), 172_729);
+        assert_eq!(price_impact(1_000, 0, 1_000, 1_000), None);
+    }
 }
@@ -0,0 +1,486 @@
+//! Curves of pools holding more than two tokens, swapped between any pair
+
+use {
+    crate::{
+        curve::{
+            base::SwapResult, calculator::RoundDirection, fees::Fees, stable::StableCurve, weighted,
+        },
+        error::SwapError,
+    },
+    solana_program::program_error::ProgramError,
+    std::convert::TryFrom,
+};
+
+/// Minimum number of tokens in a multi-asset pool
+pub const MIN_MULTI_ASSET_TOKENS: usize = 2;
+
+/// Maximum number of tokens in a multi-asset pool, bounding the size of its
+/// account
+pub const MAX_MULTI_ASSET_TOKENS: usize = 8;
+
+/// Tokens of a weighted pool must each weigh at least this fraction of the
+/// total weight, to bound the exponents of the weighted invariant
+pub const MIN_NORMALIZED_WEIGHT: (u128, u128) = (1, 100);
+
+/// Curve types supported by multi-asset pools
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MultiAssetCurveType {
+    /// Balancer-style weighted curve, invariant = prod(token_amount ** weight)
+    Weighted,
+    /// Stable curve extended to any number of tokens
+    Stable,
+}
+
+impl TryFrom<u8> for MultiAssetCurveType {
+    type Error = ProgramError;
+
+    fn try_from(curve_type: u8) -> Result<Self, Self::Error> {
+        match curve_type {
+            0 => Ok(MultiAssetCurveType::Weighted),
+            1 => Ok(MultiAssetCurveType::Stable),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+/// Curve of a multi-asset pool, with the weights of its tokens
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiAssetCurve {
+    /// Type of the curve
+    pub curve_type: MultiAssetCurveType,
+    /// Amplifier constant of the stable curve, unused by the weighted curve
+    pub amp: u64,
+    /// Weights of the tokens, in the order of the pool, only used by the
+    /// weighted curve
+    pub weights: Vec<u64>,
+}
+
+impl MultiAssetCurve {
+    /// Number of tokens in the pool
+    pub fn token_count(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Checks the number of tokens and the curve parameters
+    pub fn validate(&self) -> Result<(), SwapError> {
+        if !(MIN_MULTI_ASSET_TOKENS..=MAX_MULTI_ASSET_TOKENS).contains(&self.token_count()) {
+            return Err(SwapError::InvalidCurve);
+        }
+        match self.curve_type {
+            MultiAssetCurveType::Weighted => {
+                let total_weight = self.total_weight().ok_or(SwapError::InvalidCurve)?;
+                for weight in self.weights.iter() {
+                    let (numerator, denominator) = MIN_NORMALIZED_WEIGHT;
+                    if u128::from(*weight) * denominator < total_weight * numerator || *weight == 0
+                    {
+                        return Err(SwapError::InvalidCurve);
+                    }
+                }
+            }
+            MultiAssetCurveType::Stable => {
+                if self.amp == 0 {
+                    return Err(SwapError::InvalidCurve);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn total_weight(&self) -> Option<u128> {
+        self.weights.iter().try_fold(0u128, |total, weight| {
+            total.checked_add(u128::from(*weight))
+        })
+    }
+
+    /// Trade fee on a single-sided deposit or withdrawal, charged on the part
+    /// of the amount that is effectively swapped for the other tokens: all
+    /// but the normalized weight of the token, or an equal share of the
+    /// tokens for the stable curve. With two tokens of equal weight, this is
+    /// the fee on half the amount, like two-token swaps. Reference at:
+    /// https://github.com/balancer-labs/balancer-core/blob/f4ed5d65362a8d6cec21662fb6eae233b0babc1f/contracts/BMath.sol#L117
+    fn single_token_trade_fee(
+        &self,
+        amount: u128,
+        token_index: usize,
+        fees: &Fees,
+    ) -> Option<u128> {
+        let (weight, total_weight) = match self.curve_type {
+            MultiAssetCurveType::Weighted => (
+                u128::from(*self.weights.get(token_index)?),
+                self.total_weight()?,
+            ),
+            MultiAssetCurveType::Stable => (1, u128::try_from(self.token_count()).ok()?),
+        };
+        let swapped_amount = amount
+            .checked_mul(total_weight.checked_sub(weight)?)?
+            .checked_div(total_weight)?;
+        fees.trading_fee(std::cmp::max(1, swapped_amount))
+    }
+
+    fn check_indices(&self, indices: &[usize], swap_token_amounts: &[u128]) -> Option<()> {
+        if swap_token_amounts.len() != self.token_count()
+            || indices.iter().any(|index| *index >= self.token_count())
+        {
+            None
+        } else {
+            Some(())
+        }
+    }
+
+    /// Subtract fees and calculate how much destination token will be
+    /// provided given an amount of source token
+    pub fn swap(
+        &self,
+        source_amount: u128,
+        source_index: usize,
+        destination_index: usize,
+        swap_token_amounts: &[u128],
+        fees: &Fees,
+    ) -> Option<SwapResult> {
+        self.check_indices(&[source_index, destination_index], swap_token_amounts)?;
+        if source_index == destination_index {
+            return None;
+        }
+        // debit the fee to calculate the amount swapped
+        let trade_fee = fees.trading_fee(source_amount)?;
+        let owner_fee = fees.owner_trading_fee(source_amount)?;
+
+        let total_fees = trade_fee.checked_add(owner_fee)?;
+        let source_amount_less_fees = source_amount.checked_sub(total_fees)?;
+
+        let destination_amount_swapped = self.swap_without_fees(
+            source_amount_less_fees,
+            source_index,
+            destination_index,
+            swap_token_amounts,
+        )?;
+        Some(SwapResult {
+            new_swap_source_amount: swap_token_amounts[source_index].checked_add(source_amount)?,
+            new_swap_destination_amount: swap_token_amounts[destination_index]
+                .checked_sub(destination_amount_swapped)?,
+            source_amount_swapped: source_amount,
+            destination_amount_swapped,
+            trade_fee,
+            owner_fee,
+        })
+    }
+
+    fn swap_without_fees(
+        &self,
+        source_amount: u128,
+        source_index: usize,
+        destination_index: usize,
+        swap_token_amounts: &[u128],
+    ) -> Option<u128> {
+        match self.curve_type {
+            MultiAssetCurveType::Weighted => weighted::swap_without_fees(
+                source_amount,
+                swap_token_amounts[source_index],
+                u128::from(self.weights[source_index]),
+                swap_token_amounts[destination_index],
+                u128::from(self.weights[destination_index]),
+            ),
+            MultiAssetCurveType::Stable => StableCurve { amp: self.amp }
+                .multi_asset_swap_without_fees(
+                    source_amount,
+                    source_index,
+                    destination_index,
+                    swap_token_amounts,
+                ),
+        }
+    }
+
+    /// Get the amount of each token for the given amount of pool tokens,
+    /// provided the total amounts of the pool and supply of pool tokens
+    pub fn pool_tokens_to_trading_tokens(
+        &self,
+        pool_tokens: u128,
+        pool_token_supply: u128,
+        swap_token_amounts: &[u128],
+        round_direction: RoundDirection,
+    ) -> Option<Vec<u128>> {
+        self.check_indices(&[], swap_token_amounts)?;
+        swap_token_amounts
+            .iter()
+            .map(|swap_token_amount| {
+                let amount = pool_tokens
+                    .checked_mul(*swap_token_amount)?
+                    .checked_div(pool_token_supply)?;
+                let remainder = pool_tokens
+                    .checked_mul(*swap_token_amount)?
+                    .checked_rem(pool_token_supply)?;
+                match round_direction {
+                    RoundDirection::Ceiling if remainder > 0 && amount > 0 => amount.checked_add(1),
+                    _ => Some(amount),
+                }
+            })
+            .collect()
+    }
+
+    /// Get the amount of pool tokens for the deposited amount of one token,
+    /// after the trade fee on the part effectively swapped
+    pub fn deposit_single_token_type(
+        &self,
+        source_amount: u128,
+        token_index: usize,
+        swap_token_amounts: &[u128],
+        pool_supply: u128,
+        fees: &Fees,
+    ) -> Option<u128> {
+        self.check_indices(&[token_index], swap_token_amounts)?;
+        if source_amount == 0 {
+            return Some(0);
+        }
+        let trade_fee = self.single_token_trade_fee(source_amount, token_index, fees)?;
+        let source_amount = source_amount.checked_sub(trade_fee)?;
+        self.deposit_single_token_type_without_fees(
+            source_amount,
+            token_index,
+            swap_token_amounts,
+            pool_supply,
+        )
+    }
+
+    /// Get the amount of pool tokens worth the given amount of one token
+    /// held by the pool, without fees
+    pub fn deposit_single_token_type_without_fees(
+        &self,
+        source_amount: u128,
+        token_index: usize,
+        swap_token_amounts: &[u128],
+        pool_supply: u128,
+    ) -> Option<u128> {
+        self.check_indices(&[token_index], swap_token_amounts)?;
+        match self.curve_type {
+            MultiAssetCurveType::Weighted => weighted::deposit_single_token_type(
+                source_amount,
+                swap_token_amounts[token_index],
+                u128::from(self.weights[token_index]),
+                self.total_weight()?,
+                pool_supply,
+            ),
+            MultiAssetCurveType::Stable => StableCurve { amp: self.amp }
+                .multi_asset_deposit_single_token_type(
+                    source_amount,
+                    token_index,
+                    swap_token_amounts,
+                    pool_supply,
+                ),
+        }
+    }
+
+    /// Get the amount of pool tokens to burn for the withdrawn amount of one
+    /// token, including the trade fee on the part effectively swapped
+    pub fn withdraw_single_token_type_exact_out(
+        &self,
+        destination_amount: u128,
+        token_index: usize,
+        swap_token_amounts: &[u128],
+        pool_supply: u128,
+        fees: &Fees,
+    ) -> Option<u128> {
+        self.check_indices(&[token_index], swap_token_amounts)?;
+        if destination_amount == 0 {
+            return Some(0);
+        }
+        let trade_fee = self.single_token_trade_fee(destination_amount, token_index, fees)?;
+        let destination_amount = destination_amount.checked_add(trade_fee)?;
+        match self.curve_type {
+            MultiAssetCurveType::Weighted => weighted::withdraw_single_token_type_exact_out(
+                destination_amount,
+                swap_token_amounts[token_index],
+                u128::from(self.weights[token_index]),
+                self.total_weight()?,
+                pool_supply,
+            ),
+            MultiAssetCurveType::Stable => StableCurve { amp: self.amp }
+                .multi_asset_withdraw_single_token_type_exact_out(
+                    destination_amount,
+                    token_index,
+                    swap_token_amounts,
+                    pool_supply,
+                ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, sim::StableSwapModel};
+
+    fn weighted_curve(weights: Vec<u64>) -> MultiAssetCurve {
+        MultiAssetCurve {
+            curve_type: MultiAssetCurveType::Weighted,
+            amp: 0,
+            weights,
+        }
+    }
+
+    fn stable_curve(amp: u64, token_count: usize) -> MultiAssetCurve {
+        MultiAssetCurve {
+            curve_type: MultiAssetCurveType::Stable,
+            amp,
+            weights: vec![0; token_count],
+        }
+    }
+
+    fn fees() -> Fees {
+        Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            ..Fees::default()
+        }
+    }
+
+    #[test]
+    fn validate_curves() {
+        assert!(weighted_curve(vec![50, 30, 20]).validate().is_ok());
+        assert_eq!(
+            weighted_curve(vec![50]).validate(),
+            Err(SwapError::InvalidCurve)
+        );
+        assert_eq!(
+            weighted_curve(vec![1; MAX_MULTI_ASSET_TOKENS + 1]).validate(),
+            Err(SwapError::InvalidCurve)
+        );
+        assert_eq!(
+            weighted_curve(vec![50, 0, 50]).validate(),
+            Err(SwapError::InvalidCurve)
+        );
+        assert_eq!(
+            weighted_curve(vec![1_000, 5]).validate(),
+            Err(SwapError::InvalidCurve)
+        );
+        assert!(weighted_curve(vec![99, 1]).validate().is_ok());
+        assert!(stable_curve(100, 4).validate().is_ok());
+        assert_eq!(stable_curve(0, 4).validate(), Err(SwapError::InvalidCurve));
+    }
+
+    #[test]
+    fn swap_with_fees() {
+        let curve = weighted_curve(vec![50, 25, 25]);
+        let amounts = [1_000_000, 500_000, 500_000];
+        let result = curve.swap(10_000, 0, 2, &amounts, &fees()).unwrap();
+        assert_eq!(result.trade_fee, 25);
+        assert_eq!(result.owner_fee, 5);
+        assert_eq!(result.source_amount_swapped, 10_000);
+        assert_eq!(result.new_swap_source_amount, 1_010_000);
+        // the price of token A in token C is 1/2, since it has twice the
+        // weight and twice the amount
+        let expected = 500_000.0 * (1.0 - (1_000_000.0f64 / 1_009_970.0).powf(2.0));
+        assert!((result.destination_amount_swapped as f64 - expected).abs() <= 1.0);
+        assert_eq!(
+            result.new_swap_destination_amount,
+            500_000 - result.destination_amount_swapped
+        );
+
+        assert!(curve.swap(10_000, 1, 1, &amounts, &fees()).is_none());
+        assert!(curve.swap(10_000, 0, 3, &amounts, &fees()).is_none());
+        assert!(curve.swap(10_000, 0, 1, &amounts[..2], &fees()).is_none());
+    }
+
+    #[test]
+    fn stable_swap_matches_model() {
+        let amp = 100;
+        let curve = stable_curve(amp, 3);
+        for amounts in [
+            [1_000_000_000u128, 1_000_000_000, 1_000_000_000],
+            [1_000_000_000, 2_000_000_000, 500_000_000],
+            [5_000_000, 1_000_000_000_000, 20_000_000_000],
+        ] {
+            let mut model = StableSwapModel::new(amp.into(), amounts.to_vec(), 3);
+            model.target_prices = vec![1_000_000_000_000_000_000; 3];
+            for (source_amount, i, j) in [(1_000_000, 0, 1), (50_000_000, 2, 0), (1_000_000, 1, 2)]
+            {
+                let result = curve
+                    .swap_without_fees(source_amount, i, j, &amounts)
+                    .unwrap();
+                let sim_result = model.sim_exchange(i as u128, j as u128, source_amount);
+                let diff = (sim_result as i128 - result as i128).abs();
+                assert!(
+                    diff <= 2,
+                    "result={}, sim_result={}, amounts={:?}",
+                    result,
+                    sim_result,
+                    amounts
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stable_two_tokens_matches_stable_curve() {
+        use crate::curve::calculator::{CurveCalculator, TradeDirection};
+        let amp = 85;
+        let curve = stable_curve(amp, 2);
+        let amounts = [1_000_000_000, 1_500_000_000];
+        let expected = StableCurve { amp }
+            .swap_without_fees(12_345_678, amounts[0], amounts[1], TradeDirection::AtoB)
+            .unwrap();
+        let result = curve.swap_without_fees(12_345_678, 0, 1, &amounts).unwrap();
+        assert!(result.abs_diff(expected.destination_amount_swapped) <= 1);
+        let expected = StableCurve { amp }
+            .deposit_single_token_type(
+                12_345_678,
+                amounts[0],
+                amounts[1],
+                1_000_000,
+                TradeDirection::AtoB,
+            )
+            .unwrap();
+        let result = curve
+            .deposit_single_token_type_without_fees(12_345_678, 0, &amounts, 1_000_000)
+            .unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn proportional_deposit_and_withdraw() {
+        let curve = stable_curve(100, 3);
+        let amounts = [1_000, 2_001, 5];
+        assert_eq!(
+            curve
+                .pool_tokens_to_trading_tokens(10, 100, &amounts, RoundDirection::Ceiling)
+                .unwrap(),
+            vec![100, 201, 0]
+        );
+        assert_eq!(
+            curve
+                .pool_tokens_to_trading_tokens(10, 100, &amounts, RoundDirection::Floor)
+                .unwrap(),
+            vec![100, 200, 0]
+        );
+    }
+
+    #[test]
+    fn single_token_fees() {
+        // with two equal weights, the fee is charged on half the amount
+        let curve = weighted_curve(vec![1, 1]);
+        assert_eq!(curve.single_token_trade_fee(10_000, 0, &fees()), Some(12));
+        // a token with a fifth of the weight swaps four fifths of the amount
+        let curve = weighted_curve(vec![20, 40, 40]);
+        assert_eq!(curve.single_token_trade_fee(10_000, 0, &fees()), Some(20));
+        // equal shares of the stable curve
+        let curve = stable_curve(100, 4);
+        assert_eq!(curve.single_token_trade_fee(10_000, 0, &fees()), Some(18));
+
+        for curve in [weighted_curve(vec![20, 40, 40]), stable_curve(100, 3)] {
+            let amounts = [1_000_000, 2_000_000, 2_000_000];
+            let supply = 1_000_000_000;
+            let minted = curve
+                .deposit_single_token_type(10_000, 0, &amounts, supply, &fees())
+                .unwrap();
+            let minted_without_fees = curve
+                .deposit_single_token_type_without_fees(10_000, 0, &amounts, supply)
+                .unwrap();
+            assert!(minted < minted_without_fees);
+            let burned = curve
+                .withdraw_single_token_type_exact_out(10_000, 0, &amounts, supply, &fees())
+                .unwrap();
+            assert!(burned > minted_without_fees);
+        }
+    }
+}
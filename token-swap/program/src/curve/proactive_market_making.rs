@@ -0,0 +1,984 @@
+//! Proactive market making curve, concentrating liquidity around the price
+//! given by an oracle.
+//!
+//! The curve follows the PMM algorithm described at
+//! <https://dodoex.github.io/docs/docs/pmmDetails> and
+//! <https://dodoex.github.io/docs/docs/math>, with token A as the base token
+//! and token B as the quote token.
+
+// required for clippy
+#![allow(clippy::assign_op_pattern)]
+#![allow(clippy::ptr_offset_with_cast)]
+#![allow(clippy::manual_range_contains)]
+
+use {
+    crate::{
+        curve::{
+            calculator::{
+                CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult, TradeDirection,
+                TradingTokenResult,
+            },
+            constant_product::pool_tokens_to_trading_tokens,
+        },
+        error::SwapError,
+    },
+    arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
+    solana_program::{
+        program_error::ProgramError,
+        program_pack::{IsInitialized, Pack, Sealed},
+    },
+    spl_math::{
+        precise_number::{PreciseNumber, ONE},
+        uint::U256,
+    },
+    std::convert::TryFrom,
+    uint::construct_uint,
+};
+
+construct_uint! {
+    /// U512 with 512 bits consisting of 8 x 64-bit words, required because
+    /// the squared balances are multiplied by the slippage factor
+    pub struct U512(8);
+}
+
+/// ProactiveMarketMakingCurve struct implementing CurveCalculator
+///
+/// Both balances are converted into a common value unit at the oracle price
+/// `i`.  Each side has a regression target, `B_0` for token A and `Q_0` for
+/// token B, and the marginal price moves away from `i` as a balance moves
+/// away from its target:
+///
+/// * if `B < B_0`, `P = i * (1 - k + k * (B_0 / B) ^ 2)`
+/// * if `Q < Q_0`, `P = i / (1 - k + k * (Q_0 / Q) ^ 2)`
+/// * otherwise `P = i`
+///
+/// Curves in this program have no state besides their parameters, so the
+/// targets are not stored, but derived from the balances assuming that they
+/// are worth the same at the oracle price.  A pool holding the same value of
+/// both tokens trades at exactly the oracle price, and `k = 1` gives the
+/// constant product curve centered on the oracle price.
+///
+/// The oracle price is refreshed from the oracle account on every
+/// instruction that needs it, the stored values only reflect the oracle at
+/// the time of the last refresh.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProactiveMarketMakingCurve {
+    /// Numerator of the slippage factor `k`, between 0 (excluded) and 1
+    pub k_numerator: u64,
+    /// Denominator of the slippage factor `k`
+    pub k_denominator: u64,
+    /// Oracle price of one token A in token B, without decimals
+    pub oracle_price: u64,
+    /// Decimal exponent of the oracle price
+    pub oracle_exponent: i32,
+    /// Decimals of the token A mint
+    pub token_a_decimals: u8,
+    /// Decimals of the token B mint
+    pub token_b_decimals: u8,
+}
+
+/// Greatest common divisor, used to keep the value scales small
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        let remainder = a % b;
+        a = b;
+        b = remainder;
+    }
+    a
+}
+
+/// Division in the given direction
+fn checked_div(
+    numerator: U512,
+    denominator: U512,
+    round_direction: RoundDirection,
+) -> Option<U512> {
+    if denominator.is_zero() {
+        return None;
+    }
+    let (quotient, remainder) = numerator.div_mod(denominator);
+    match round_direction {
+        RoundDirection::Ceiling if !remainder.is_zero() => quotient.checked_add(U512::one()),
+        _ => Some(quotient),
+    }
+}
+
+/// Integer square root in the given direction
+fn sqrt(value: U512, round_direction: RoundDirection) -> Option<U512> {
+    let root = value.integer_sqrt();
+    match round_direction {
+        RoundDirection::Ceiling if root.checked_mul(root)? != value => {
+            root.checked_add(U512::one())
+        }
+        _ => Some(root),
+    }
+}
+
+/// Common target of both sides, given the value of the side below its target
+/// and the value of the side above its target.
+///
+/// Solves `long - t = (t - short) * (1 - k + k * t / short)`, the integral of
+/// the marginal price between the short balance and the target, which gives:
+///
+/// t = short * ((1 - k) * short + long) / ((1 - k) * short + sqrt((1 - k) * short ^ 2 + k * short * long))
+fn compute_target(
+    short: U512,
+    long: U512,
+    k_numerator: u64,
+    k_denominator: u64,
+    round_direction: RoundDirection,
+) -> Option<U512> {
+    if short.is_zero() {
+        return None;
+    }
+    let k_numerator = U512::from(k_numerator);
+    let k_denominator = U512::from(k_denominator);
+    let complement = k_denominator.checked_sub(k_numerator)?;
+    let complement_short = complement.checked_mul(short)?;
+    // scaled by `k_denominator ^ 2` under the root
+    let discriminant = k_denominator.checked_mul(
+        complement_short
+            .checked_mul(short)?
+            .checked_add(k_numerator.checked_mul(short)?.checked_mul(long)?)?,
+    )?;
+    // a smaller root gives a bigger target
+    let root_direction = match round_direction {
+        RoundDirection::Floor => RoundDirection::Ceiling,
+        RoundDirection::Ceiling => RoundDirection::Floor,
+    };
+    let root = sqrt(discriminant, root_direction)?;
+    let numerator =
+        short.checked_mul(complement_short.checked_add(k_denominator.checked_mul(long)?)?)?;
+    let denominator = complement_short.checked_add(root)?;
+    checked_div(numerator, denominator, round_direction)
+}
+
+/// Balance of the destination side on the curve going through the given
+/// target, when the source side holds `source`, rounded up.
+fn compute_destination(
+    source: U512,
+    target: U512,
+    k_numerator: u64,
+    k_denominator: u64,
+) -> Option<U512> {
+    let k_numerator = U512::from(k_numerator);
+    let k_denominator = U512::from(k_denominator);
+    let complement = k_denominator.checked_sub(k_numerator)?;
+    if source <= target {
+        // The destination is above its target by the integral of the price:
+        // (t - source) * (1 - k + k * t / source)
+        let excess = checked_div(
+            target.checked_sub(source)?.checked_mul(
+                complement
+                    .checked_mul(source)?
+                    .checked_add(k_numerator.checked_mul(target)?)?,
+            )?,
+            k_denominator.checked_mul(source)?,
+            RoundDirection::Ceiling,
+        )?;
+        target.checked_add(excess)
+    } else {
+        // The destination is below its target, solve for y:
+        // source - t = (t - y) * (1 - k + k * t / y)
+        // (1 - k) * y ^ 2 + (source - t - (1 - 2k) * t) * y - k * t ^ 2 = 0
+        let delta = k_denominator.checked_mul(source.checked_sub(target)?)?;
+        let double_k_numerator = k_numerator.checked_mul(U512::from(2))?;
+        let (b, b_is_negative) = if double_k_numerator >= k_denominator {
+            (
+                delta.checked_add(
+                    double_k_numerator
+                        .checked_sub(k_denominator)?
+                        .checked_mul(target)?,
+                )?,
+                false,
+            )
+        } else {
+            let offset = k_denominator
+                .checked_sub(double_k_numerator)?
+                .checked_mul(target)?;
+            if delta >= offset {
+                (delta.checked_sub(offset)?, false)
+            } else {
+                (offset.checked_sub(delta)?, true)
+            }
+        };
+        let k_target_squared = k_numerator.checked_mul(target)?.checked_mul(target)?;
+        let discriminant = b.checked_mul(b)?.checked_add(
+            complement
+                .checked_mul(k_target_squared)?
+                .checked_mul(U512::from(4))?,
+        )?;
+        if b_is_negative {
+            // only possible with k < 1/2, so the complement is never zero
+            checked_div(
+                b.checked_add(sqrt(discriminant, RoundDirection::Ceiling)?)?,
+                complement.checked_mul(U512::from(2))?,
+                RoundDirection::Ceiling,
+            )
+        } else {
+            // equivalent form avoiding the cancellation of `-b + sqrt(...)`
+            checked_div(
+                k_target_squared.checked_mul(U512::from(2))?,
+                b.checked_add(sqrt(discriminant, RoundDirection::Floor)?)?,
+                RoundDirection::Ceiling,
+            )
+        }
+    }
+}
+
+impl ProactiveMarketMakingCurve {
+    /// Scales converting token amounts into a common value unit, such that
+    /// `token_a_amount * token_a_scale` and `token_b_amount * token_b_scale`
+    /// are worth the same at the oracle price.
+    pub fn value_scales(&self) -> Option<(u128, u128)> {
+        let exponent = i64::from(self.oracle_exponent)
+            .checked_add(i64::from(self.token_b_decimals))?
+            .checked_sub(i64::from(self.token_a_decimals))?;
+        let power = 10u128.checked_pow(u32::try_from(exponent.checked_abs()?).ok()?)?;
+        let (token_a_scale, token_b_scale) = if exponent >= 0 {
+            ((self.oracle_price as u128).checked_mul(power)?, 1)
+        } else {
+            (self.oracle_price as u128, power)
+        };
+        let divisor = gcd(token_a_scale, token_b_scale);
+        Some((token_a_scale / divisor, token_b_scale / divisor))
+    }
+
+    /// Value of both sides of the pool, in common value units
+    fn values(&self, swap_token_a_amount: u128, swap_token_b_amount: u128) -> Option<(U512, U512)> {
+        let (token_a_scale, token_b_scale) = self.value_scales()?;
+        Some((
+            U512::from(swap_token_a_amount).checked_mul(U512::from(token_a_scale))?,
+            U512::from(swap_token_b_amount).checked_mul(U512::from(token_b_scale))?,
+        ))
+    }
+
+    /// Common target of both sides, in common value units
+    fn target(
+        &self,
+        token_a_value: U512,
+        token_b_value: U512,
+        round_direction: RoundDirection,
+    ) -> Option<U512> {
+        let (short, long) = if token_a_value <= token_b_value {
+            (token_a_value, token_b_value)
+        } else {
+            (token_b_value, token_a_value)
+        };
+        compute_target(
+            short,
+            long,
+            self.k_numerator,
+            self.k_denominator,
+            round_direction,
+        )
+    }
+
+    /// Amount of pool tokens corresponding to the change in targets from
+    /// adding or removing `source_amount`
+    fn single_token_type_pool_tokens(
+        &self,
+        source_amount: u128,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+        pool_supply: u128,
+        trade_direction: TradeDirection,
+        round_direction: RoundDirection,
+    ) -> Option<u128> {
+        let (token_a_scale, token_b_scale) = self.value_scales()?;
+        let (token_a_value, token_b_value) =
+            self.values(swap_token_a_amount, swap_token_b_amount)?;
+        let target = self.target(token_a_value, token_b_value, RoundDirection::Ceiling)?;
+        let source_scale = match trade_direction {
+            TradeDirection::AtoB => token_a_scale,
+            TradeDirection::BtoA => token_b_scale,
+        };
+        let source_value = U512::from(source_amount).checked_mul(U512::from(source_scale))?;
+        let (new_token_a_value, new_token_b_value) = match (trade_direction, round_direction) {
+            (TradeDirection::AtoB, RoundDirection::Floor) => {
+                (token_a_value.checked_add(source_value)?, token_b_value)
+            }
+            (TradeDirection::BtoA, RoundDirection::Floor) => {
+                (token_a_value, token_b_value.checked_add(source_value)?)
+            }
+            (TradeDirection::AtoB, RoundDirection::Ceiling) => {
+                (token_a_value.checked_sub(source_value)?, token_b_value)
+            }
+            (TradeDirection::BtoA, RoundDirection::Ceiling) => {
+                (token_a_value, token_b_value.checked_sub(source_value)?)
+            }
+        };
+        let new_target =
+            self.target(new_token_a_value, new_token_b_value, RoundDirection::Floor)?;
+        let difference = match round_direction {
+            RoundDirection::Floor => new_target.saturating_sub(target),
+            RoundDirection::Ceiling => target.checked_sub(new_target)?,
+        };
+        let pool_tokens = checked_div(
+            U512::from(pool_supply).checked_mul(difference)?,
+            target,
+            round_direction,
+        )?;
+        u128::try_from(pool_tokens).ok()
+    }
+}
+
+impl CurveCalculator for ProactiveMarketMakingCurve {
+    /// Integral of the marginal price between the current and the new
+    /// balances
+    fn swap_without_fees(
+        &self,
+        source_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let (token_a_scale, token_b_scale) = self.value_scales()?;
+        let (source_scale, destination_scale) = match trade_direction {
+            TradeDirection::AtoB => (token_a_scale, token_b_scale),
+            TradeDirection::BtoA => (token_b_scale, token_a_scale),
+        };
+        let source_scale = U512::from(source_scale);
+        let destination_scale = U512::from(destination_scale);
+        let swap_source_value = U512::from(swap_source_amount).checked_mul(source_scale)?;
+        let swap_destination_value =
+            U512::from(swap_destination_amount).checked_mul(destination_scale)?;
+
+        // The current balances are almost never exactly on the curve, so the
+        // new destination balance is taken on the curve of the rounded up
+        // target, which is always in favor of the pool
+        let (short, long) = if swap_source_value <= swap_destination_value {
+            (swap_source_value, swap_destination_value)
+        } else {
+            (swap_destination_value, swap_source_value)
+        };
+        let target = compute_target(
+            short,
+            long,
+            self.k_numerator,
+            self.k_denominator,
+            RoundDirection::Ceiling,
+        )?;
+        let new_swap_source_value = U512::from(source_amount)
+            .checked_mul(source_scale)?
+            .checked_add(swap_source_value)?;
+        let new_swap_destination_value = compute_destination(
+            new_swap_source_value,
+            target,
+            self.k_numerator,
+            self.k_denominator,
+        )?;
+        let destination_value = swap_destination_value.saturating_sub(new_swap_destination_value);
+        let destination_amount_swapped = destination_value.checked_div(destination_scale)?;
+
+        // The curve is symmetric in value units, so the source balance needed
+        // for the rounded down destination amount is found the same way, only
+        // taking as much source token as required
+        let new_swap_destination_value = swap_destination_value
+            .checked_sub(destination_amount_swapped.checked_mul(destination_scale)?)?;
+        let new_swap_source_value = compute_destination(
+            new_swap_destination_value,
+            target,
+            self.k_numerator,
+            self.k_denominator,
+        )?;
+        let source_amount_swapped = checked_div(
+            new_swap_source_value.saturating_sub(swap_source_value),
+            source_scale,
+            RoundDirection::Ceiling,
+        )?;
+        let source_amount_swapped =
+            std::cmp::min(u128::try_from(source_amount_swapped).ok()?, source_amount);
+
+        Some(SwapWithoutFeesResult {
+            source_amount_swapped,
+            destination_amount_swapped: u128::try_from(destination_amount_swapped).ok()?,
+        })
+    }
+
+    /// Remove pool tokens from the pool in exchange for trading tokens
+    fn pool_tokens_to_trading_tokens(
+        &self,
+        pool_tokens: u128,
+        pool_token_supply: u128,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+        round_direction: RoundDirection,
+    ) -> Option<TradingTokenResult> {
+        pool_tokens_to_trading_tokens(
+            pool_tokens,
+            pool_token_supply,
+            swap_token_a_amount,
+            swap_token_b_amount,
+            round_direction,
+        )
+    }
+
+    /// Pool tokens are minted in proportion to the increase of the targets,
+    /// so the depositor pays for moving the pool away from the oracle price
+    fn deposit_single_token_type(
+        &self,
+        source_amount: u128,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+        pool_supply: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        self.single_token_type_pool_tokens(
+            source_amount,
+            swap_token_a_amount,
+            swap_token_b_amount,
+            pool_supply,
+            trade_direction,
+            RoundDirection::Floor,
+        )
+    }
+
+    /// Pool tokens are burned in proportion to the decrease of the targets
+    fn withdraw_single_token_type_exact_out(
+        &self,
+        source_amount: u128,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+        pool_supply: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        self.single_token_type_pool_tokens(
+            source_amount,
+            swap_token_a_amount,
+            swap_token_b_amount,
+            pool_supply,
+            trade_direction,
+            RoundDirection::Ceiling,
+        )
+    }
+
+    fn validate(&self) -> Result<(), SwapError> {
+        if self.k_numerator == 0
+            || self.k_numerator > self.k_denominator
+            || self.oracle_price == 0
+            || self.value_scales().is_none()
+        {
+            Err(SwapError::InvalidCurve)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The normalized value of the proactive market making curve is the
+    /// common target of both sides, measured in whichever token is worth
+    /// more, so that rounding a single token never moves it by more than one.
+    fn normalized_value(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<PreciseNumber> {
+        let (token_a_scale, token_b_scale) = self.value_scales()?;
+        let (token_a_value, token_b_value) =
+            self.values(swap_token_a_amount, swap_token_b_amount)?;
+        // the target is linear in the balances, so scaling them up keeps the
+        // precision of the result
+        let one = U512::from(ONE);
+        let target = self.target(
+            token_a_value.checked_mul(one)?,
+            token_b_value.checked_mul(one)?,
+            RoundDirection::Floor,
+        )?;
+        let value = target.checked_div(U512::from(std::cmp::max(token_a_scale, token_b_scale)))?;
+        Some(PreciseNumber {
+            value: U256::from(u128::try_from(value).ok()?),
+        })
+    }
+}
+
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for ProactiveMarketMakingCurve {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+impl Sealed for ProactiveMarketMakingCurve {}
+impl Pack for ProactiveMarketMakingCurve {
+    const LEN: usize = 30;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        (self as &dyn DynPack).pack_into_slice(output);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<ProactiveMarketMakingCurve, ProgramError> {
+        let input = array_ref![input, 0, 30];
+        let (
+            k_numerator,
+            k_denominator,
+            oracle_price,
+            oracle_exponent,
+            token_a_decimals,
+            token_b_decimals,
+        ) = array_refs![input, 8, 8, 8, 4, 1, 1];
+        Ok(Self {
+            k_numerator: u64::from_le_bytes(*k_numerator),
+            k_denominator: u64::from_le_bytes(*k_denominator),
+            oracle_price: u64::from_le_bytes(*oracle_price),
+            oracle_exponent: i32::from_le_bytes(*oracle_exponent),
+            token_a_decimals: token_a_decimals[0],
+            token_b_decimals: token_b_decimals[0],
+        })
+    }
+}
+
+impl DynPack for ProactiveMarketMakingCurve {
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 30];
+        let (
+            k_numerator,
+            k_denominator,
+            oracle_price,
+            oracle_exponent,
+            token_a_decimals,
+            token_b_decimals,
+        ) = mut_array_refs![output, 8, 8, 8, 4, 1, 1];
+        *k_numerator = self.k_numerator.to_le_bytes();
+        *k_denominator = self.k_denominator.to_le_bytes();
+        *oracle_price = self.oracle_price.to_le_bytes();
+        *oracle_exponent = self.oracle_exponent.to_le_bytes();
+        token_a_decimals[0] = self.token_a_decimals;
+        token_b_decimals[0] = self.token_b_decimals;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::{
+        calculator::{
+            test::{
+                check_curve_value_from_swap, check_deposit_token_conversion,
+                check_pool_value_from_deposit, check_pool_value_from_withdraw,
+                check_withdraw_token_conversion, total_and_intermediate,
+                CONVERSION_BASIS_POINTS_GUARANTEE,
+            },
+            INITIAL_SWAP_POOL_AMOUNT,
+        },
+        constant_product::ConstantProductCurve,
+    };
+    use proptest::prelude::*;
+
+    /// Token A worth 2 token B, with k = 0.1
+    fn test_curve() -> ProactiveMarketMakingCurve {
+        ProactiveMarketMakingCurve {
+            k_numerator: 1,
+            k_denominator: 10,
+            oracle_price: 2,
+            oracle_exponent: 0,
+            token_a_decimals: 6,
+            token_b_decimals: 6,
+        }
+    }
+
+    #[test]
+    fn initial_pool_amount() {
+        let calculator = test_curve();
+        assert_eq!(calculator.new_pool_supply(), INITIAL_SWAP_POOL_AMOUNT);
+    }
+
+    #[test]
+    fn validate_parameters() {
+        assert_eq!(test_curve().validate(), Ok(()));
+        let curve = ProactiveMarketMakingCurve {
+            k_numerator: 0,
+            ..test_curve()
+        };
+        assert_eq!(curve.validate(), Err(SwapError::InvalidCurve));
+        let curve = ProactiveMarketMakingCurve {
+            k_numerator: 11,
+            ..test_curve()
+        };
+        assert_eq!(curve.validate(), Err(SwapError::InvalidCurve));
+        let curve = ProactiveMarketMakingCurve {
+            k_numerator: 10,
+            ..test_curve()
+        };
+        assert_eq!(curve.validate(), Ok(()));
+        let curve = ProactiveMarketMakingCurve {
+            oracle_price: 0,
+            ..test_curve()
+        };
+        assert_eq!(curve.validate(), Err(SwapError::InvalidCurve));
+        let curve = ProactiveMarketMakingCurve {
+            oracle_exponent: 40,
+            ..test_curve()
+        };
+        assert_eq!(curve.validate(), Err(SwapError::InvalidCurve));
+    }
+
+    #[test]
+    fn value_scales() {
+        // 150.12345678 USDC per SOL, with 9 decimals for SOL and 6 for USDC,
+        // gives 0.15012345678 raw USDC per lamport
+        let curve = ProactiveMarketMakingCurve {
+            oracle_price: 15_012_345_678,
+            oracle_exponent: -8,
+            token_a_decimals: 9,
+            token_b_decimals: 6,
+            ..test_curve()
+        };
+        assert_eq!(curve.value_scales(), Some((7_506_172_839, 50_000_000_000)));
+        let curve = ProactiveMarketMakingCurve {
+            oracle_price: 3,
+            oracle_exponent: 2,
+            token_a_decimals: 0,
+            token_b_decimals: 2,
+            ..test_curve()
+        };
+        assert_eq!(curve.value_scales(), Some((30_000, 1)));
+    }
+
+    #[test]
+    fn swap_zero() {
+        let curve = test_curve();
+        let result = curve.swap_without_fees(0, 100, 1_000_000_000_000_000, TradeDirection::AtoB);
+
+        let result = result.unwrap();
+        assert_eq!(result.source_amount_swapped, 0);
+        assert_eq!(result.destination_amount_swapped, 0);
+    }
+
+    #[test]
+    fn swap_at_oracle_price() {
+        // balanced in value, swaps close to the oracle price
+        let curve = test_curve();
+        let result = curve
+            .swap_without_fees(1_000, 1_000_000, 2_000_000, TradeDirection::AtoB)
+            .unwrap();
+        assert_eq!(result.source_amount_swapped, 1_000);
+        assert_eq!(result.destination_amount_swapped, 1_999);
+        // only the source amount required for the rounded down destination
+        // amount is taken
+        let result = curve
+            .swap_without_fees(2_000, 2_000_000, 1_000_000, TradeDirection::BtoA)
+            .unwrap();
+        assert_eq!(result.source_amount_swapped, 1_999);
+        assert_eq!(result.destination_amount_swapped, 999);
+
+        // token A is scarce, selling it gets a premium over the oracle price
+        let result = curve
+            .swap_without_fees(1_000, 500_000, 2_000_000, TradeDirection::AtoB)
+            .unwrap();
+        assert!(result.destination_amount_swapped > 2_000);
+        let result = curve
+            .swap_without_fees(2_000, 2_000_000, 500_000, TradeDirection::BtoA)
+            .unwrap();
+        assert!(result.destination_amount_swapped < 1_000);
+    }
+
+    #[test]
+    fn swap_through_equilibrium() {
+        let curve = test_curve();
+        // going from scarce token A to scarce token B in one trade is the same
+        // as stopping at the balanced point on the way
+        let token_b_amount = 2_000_000;
+        let first = curve
+            .swap_without_fees(100_000, 900_000, token_b_amount, TradeDirection::AtoB)
+            .unwrap();
+        let whole = curve
+            .swap_without_fees(200_000, 900_000, token_b_amount, TradeDirection::AtoB)
+            .unwrap();
+        let second = curve
+            .swap_without_fees(
+                100_000,
+                1_000_000,
+                token_b_amount - first.destination_amount_swapped,
+                TradeDirection::AtoB,
+            )
+            .unwrap();
+        let split = first.destination_amount_swapped + second.destination_amount_swapped;
+        assert!(split <= whole.destination_amount_swapped + 2);
+        assert!(split + 2 >= whole.destination_amount_swapped);
+    }
+
+    #[test]
+    fn k_one_is_constant_product() {
+        let curve = ProactiveMarketMakingCurve {
+            k_numerator: 1,
+            k_denominator: 1,
+            ..test_curve()
+        };
+        let constant_product = ConstantProductCurve {};
+        for (source_amount, swap_source_amount, swap_destination_amount, trade_direction) in [
+            (1_000, 1_000_000, 2_000_000, TradeDirection::AtoB),
+            (100_000, 1_000_000, 3_000_000, TradeDirection::AtoB),
+            (5_000, 4_000_000, 1_000_000, TradeDirection::BtoA),
+        ] {
+            let result = curve
+                .swap_without_fees(
+                    source_amount,
+                    swap_source_amount,
+                    swap_destination_amount,
+                    trade_direction,
+                )
+                .unwrap();
+            let expected = constant_product
+                .swap_without_fees(
+                    source_amount,
+                    swap_source_amount,
+                    swap_destination_amount,
+                    trade_direction,
+                )
+                .unwrap();
+            // rounding the target up moves the destination balance by a few
+            // tokens at most
+            assert!(result.destination_amount_swapped <= expected.destination_amount_swapped);
+            assert!(result.destination_amount_swapped + 3 >= expected.destination_amount_swapped);
+        }
+    }
+
+    #[test]
+    fn concentrated_liquidity() {
+        // a smaller k gives less slippage than the constant product curve
+        let curve = test_curve();
+        let constant_product = ConstantProductCurve {};
+        let result = curve
+            .swap_without_fees(100_000, 1_000_000, 2_000_000, TradeDirection::AtoB)
+            .unwrap();
+        let expected = constant_product
+            .swap_without_fees(100_000, 1_000_000, 2_000_000, TradeDirection::AtoB)
+            .unwrap();
+        assert!(result.destination_amount_swapped > expected.destination_amount_swapped);
+        assert!(result.destination_amount_swapped < 200_000);
+    }
+
+    #[test]
+    fn single_sided_deposit() {
+        let curve = test_curve();
+        // depositing at the balanced point still moves the price, so the
+        // depositor gets slightly less than their share of value
+        let pool_tokens = curve
+            .deposit_single_token_type(
+                10_000,
+                1_000_000,
+                2_000_000,
+                1_000_000,
+                TradeDirection::AtoB,
+            )
+            .unwrap();
+        assert!(pool_tokens < 5_000);
+        assert!(pool_tokens > 4_900);
+        // depositing the scarce token is worth more
+        let pool_tokens = curve
+            .deposit_single_token_type(10_000, 500_000, 2_000_000, 1_000_000, TradeDirection::AtoB)
+            .unwrap();
+        assert!(pool_tokens > 5_000);
+        // and withdrawing it costs more
+        let withdraw_pool_tokens = curve
+            .withdraw_single_token_type_exact_out(
+                10_000,
+                500_000,
+                2_000_000,
+                1_000_000,
+                TradeDirection::AtoB,
+            )
+            .unwrap();
+        assert!(withdraw_pool_tokens > pool_tokens);
+    }
+
+    #[test]
+    fn pack_curve() {
+        let curve = ProactiveMarketMakingCurve {
+            k_numerator: 1,
+            k_denominator: 1_000,
+            oracle_price: 15_012_345_678,
+            oracle_exponent: -8,
+            token_a_decimals: 9,
+            token_b_decimals: 6,
+        };
+
+        let mut packed = [0u8; ProactiveMarketMakingCurve::LEN];
+        Pack::pack_into_slice(&curve, &mut packed[..]);
+        let unpacked = ProactiveMarketMakingCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&curve.k_numerator.to_le_bytes());
+        packed.extend_from_slice(&curve.k_denominator.to_le_bytes());
+        packed.extend_from_slice(&curve.oracle_price.to_le_bytes());
+        packed.extend_from_slice(&curve.oracle_exponent.to_le_bytes());
+        packed.push(curve.token_a_decimals);
+        packed.push(curve.token_b_decimals);
+        let unpacked = ProactiveMarketMakingCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+    }
+
+    prop_compose! {
+        pub fn curve()(k_numerator in 1..=1_000u64,
+                       oracle_price in 1..1_000_000_000u64,
+                       oracle_exponent in -9..=0i32,
+                       token_a_decimals in 0..=9u8,
+                       token_b_decimals in 0..=9u8)
+                      -> ProactiveMarketMakingCurve {
+           ProactiveMarketMakingCurve {
+               k_numerator,
+               k_denominator: 1_000,
+               oracle_price,
+               oracle_exponent,
+               token_a_decimals,
+               token_b_decimals,
+           }
+       }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_deposit(
+            pool_token_amount in 1..u64::MAX,
+            pool_token_supply in 1..u64::MAX,
+            swap_token_a_amount in 1..u64::MAX,
+            swap_token_b_amount in 1..u64::MAX,
+            curve in curve(),
+        ) {
+            let pool_token_amount = pool_token_amount as u128;
+            let pool_token_supply = pool_token_supply as u128;
+            let swap_token_a_amount = swap_token_a_amount as u128;
+            let swap_token_b_amount = swap_token_b_amount as u128;
+            // Make sure we will get at least one trading token out for each
+            // side, otherwise the calculation fails
+            prop_assume!(pool_token_amount * swap_token_a_amount / pool_token_supply >= 1);
+            prop_assume!(pool_token_amount * swap_token_b_amount / pool_token_supply >= 1);
+            check_pool_value_from_deposit(
+                &curve,
+                pool_token_amount,
+                pool_token_supply,
+                swap_token_a_amount,
+                swap_token_b_amount,
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_withdraw(
+            (pool_token_supply, pool_token_amount) in total_and_intermediate(),
+            swap_token_a_amount in 1..u64::MAX,
+            swap_token_b_amount in 1..u64::MAX,
+            curve in curve(),
+        ) {
+            let pool_token_amount = pool_token_amount as u128;
+            let pool_token_supply = pool_token_supply as u128;
+            let swap_token_a_amount = swap_token_a_amount as u128;
+            let swap_token_b_amount = swap_token_b_amount as u128;
+            // Make sure we will get at least one trading token out for each
+            // side, otherwise the calculation fails
+            prop_assume!(pool_token_amount * swap_token_a_amount / pool_token_supply >= 1);
+            prop_assume!(pool_token_amount * swap_token_b_amount / pool_token_supply >= 1);
+            // The pool must keep some of both sides to be priced
+            prop_assume!(pool_token_amount < pool_token_supply);
+            check_pool_value_from_withdraw(
+                &curve,
+                pool_token_amount,
+                pool_token_supply,
+                swap_token_a_amount,
+                swap_token_b_amount,
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_a_to_b(
+            source_token_amount in 1..u32::MAX as u64,
+            swap_source_amount in 1..u32::MAX as u64,
+            swap_destination_amount in 1..u32::MAX as u64,
+            curve in curve(),
+        ) {
+            check_curve_value_from_swap(
+                &curve,
+                source_token_amount as u128,
+                swap_source_amount as u128,
+                swap_destination_amount as u128,
+                TradeDirection::AtoB
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap_b_to_a(
+            source_token_amount in 1..u32::MAX as u64,
+            swap_source_amount in 1..u32::MAX as u64,
+            swap_destination_amount in 1..u32::MAX as u64,
+            curve in curve(),
+        ) {
+            check_curve_value_from_swap(
+                &curve,
+                source_token_amount as u128,
+                swap_source_amount as u128,
+                swap_destination_amount as u128,
+                TradeDirection::BtoA
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn deposit_token_conversion(
+            // in the pool token conversion calcs, we simulate trading half of
+            // source_token_amount, so this needs to be at least 2
+            source_token_amount in 2..u32::MAX as u64,
+            // converting a one-sided deposit into a swap and a deposit only
+            // holds for deposits that are small compared to a pool that is
+            // not too far from the oracle price
+            pool_multiplier in 100..10_000u128,
+            imbalance_percent in 10..1_000u128,
+            pool_supply in INITIAL_SWAP_POOL_AMOUNT..u64::MAX as u128,
+            curve in curve(),
+        ) {
+            let (token_a_scale, token_b_scale) = curve.value_scales().unwrap();
+            for (trade_direction, source_scale, destination_scale) in [
+                (TradeDirection::AtoB, token_a_scale, token_b_scale),
+                (TradeDirection::BtoA, token_b_scale, token_a_scale),
+            ] {
+                let swap_source_amount = source_token_amount as u128 * pool_multiplier;
+                let swap_destination_amount = swap_source_amount * source_scale
+                    * imbalance_percent / 100 / destination_scale;
+                prop_assume!(swap_destination_amount >= 2);
+                prop_assume!(swap_destination_amount <= u64::MAX as u128);
+                check_deposit_token_conversion(
+                    &curve,
+                    source_token_amount as u128,
+                    swap_source_amount,
+                    swap_destination_amount,
+                    trade_direction,
+                    pool_supply,
+                    CONVERSION_BASIS_POINTS_GUARANTEE,
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn withdraw_token_conversion(
+            (pool_token_supply, pool_token_amount) in total_and_intermediate(),
+            swap_token_a_amount in 1..u32::MAX as u64,
+            swap_token_b_amount in 1..u32::MAX as u64,
+            curve in curve(),
+        ) {
+            check_withdraw_token_conversion(
+                &curve,
+                pool_token_amount as u128,
+                pool_token_supply as u128,
+                swap_token_a_amount as u128,
+                swap_token_b_amount as u128,
+                TradeDirection::AtoB,
+                CONVERSION_BASIS_POINTS_GUARANTEE
+            );
+            check_withdraw_token_conversion(
+                &curve,
+                pool_token_amount as u128,
+                pool_token_supply as u128,
+                swap_token_a_amount as u128,
+                swap_token_b_amount as u128,
+                TradeDirection::BtoA,
+                CONVERSION_BASIS_POINTS_GUARANTEE
+            );
+        }
+    }
+}
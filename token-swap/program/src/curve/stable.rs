@@ -30,8 +30,8 @@ const ITERATIONS: u8 = 32;
 /// There is little information to document this choice, but the original contracts
 /// use this same convention, see a comment in the code at:
 /// https://github.com/curvefi/curve-contract/blob/b0bbf77f8f93c9c5f4e415bce9cd71f0cdee960e/contracts/pool-templates/base/SwapTemplateBase.vy#L136
-fn compute_a(amp: u64) -> Option<u64> {
-    amp.checked_mul(N_COINS as u64)
+fn compute_a(amp: u64, n_coins: u8) -> Option<u64> {
+    amp.checked_mul(n_coins as u64)
 }
 
 /// Returns self to the power of b
@@ -60,14 +60,20 @@ pub struct StableCurve {
 }
 
 /// d = (leverage * sum_x + d_product * n_coins) * initial_d / ((leverage - 1) * initial_d + (n_coins + 1) * d_product)
-fn calculate_step(initial_d: &U256, leverage: u64, sum_x: u128, d_product: &U256) -> Option<U256> {
+fn calculate_step(
+    initial_d: &U256,
+    leverage: u64,
+    sum_x: u128,
+    d_product: &U256,
+    n_coins: u8,
+) -> Option<U256> {
     let leverage_mul = U256::from(leverage).checked_mul(sum_x.into())?;
-    let d_p_mul = checked_u8_mul(d_product, N_COINS)?;
+    let d_p_mul = checked_u8_mul(d_product, n_coins)?;
 
     let l_val = leverage_mul.checked_add(d_p_mul)?.checked_mul(*initial_d)?;
 
     let leverage_sub = initial_d.checked_mul((leverage.checked_sub(1)?).into())?;
-    let n_coins_sum = checked_u8_mul(d_product, N_COINS.checked_add(1)?)?;
+    let n_coins_sum = checked_u8_mul(d_product, n_coins.checked_add(1)?)?;
 
     let r_val = leverage_sub.checked_add(n_coins_sum)?;
 
@@ -77,12 +83,15 @@ fn calculate_step(initial_d: &U256, leverage: u64, sum_x: u128, d_product: &U256
 /// Compute stable swap invariant (D)
 /// Equation:
 /// A * sum(x_i) * n**n + D = A * D * n**n + D**(n+1) / (n**n * prod(x_i))
-fn compute_d(leverage: u64, amount_a: u128, amount_b: u128) -> Option<u128> {
-    let amount_a_times_coins =
-        checked_u8_mul(&U256::from(amount_a), N_COINS)?.checked_add(U256::one())?;
-    let amount_b_times_coins =
-        checked_u8_mul(&U256::from(amount_b), N_COINS)?.checked_add(U256::one())?;
-    let sum_x = amount_a.checked_add(amount_b)?; // sum(x_i), a.k.a S
+fn compute_d(leverage: u64, amounts: &[u128]) -> Option<u128> {
+    let n_coins = u8::try_from(amounts.len()).ok()?;
+    let amounts_times_coins = amounts
+        .iter()
+        .map(|amount| checked_u8_mul(&U256::from(*amount), n_coins)?.checked_add(U256::one()))
+        .collect::<Option<Vec<_>>>()?;
+    let sum_x = amounts
+        .iter()
+        .try_fold(0u128, |sum, amount| sum.checked_add(*amount))?; // sum(x_i), a.k.a S
     if sum_x == 0 {
         Some(0)
     } else {
@@ -92,15 +101,12 @@ fn compute_d(leverage: u64, amount_a: u128, amount_b: u128) -> Option<u128> {
         // Newton's method to approximate D
         for _ in 0..ITERATIONS {
             let mut d_product = d;
-            d_product = d_product
-                .checked_mul(d)?
-                .checked_div(amount_a_times_coins)?;
-            d_product = d_product
-                .checked_mul(d)?
-                .checked_div(amount_b_times_coins)?;
+            for amount_times_coins in amounts_times_coins.iter() {
+                d_product = d_product.checked_mul(d)?.checked_div(*amount_times_coins)?;
+            }
             d_previous = d;
             //d = (leverage * sum_x + d_p * n_coins) * d / ((leverage - 1) * d + (n_coins + 1) * d_p);
-            d = calculate_step(&d, leverage, sum_x, &d_product)?;
+            d = calculate_step(&d, leverage, sum_x, &d_product, n_coins)?;
             // Equality with the precision of 1
             if d == d_previous {
                 break;
@@ -146,6 +152,128 @@ fn compute_new_destination_amount(
     u128::try_from(y).ok()
 }
 
+/// Compute the amount `y` of the token at `destination_index` keeping the
+/// invariant, given the amounts of the other tokens of a pool with any number
+/// of tokens. Re-implementation of `get_y`:
+///
+/// <https://github.com/curvefi/curve-contract/blob/80bbe179083c9a7062e4c482b0be3bfb7501f2bd/contracts/pool-templates/base/SwapTemplateBase.vy#L355>
+///
+/// Solve for y:
+/// y**2 + y * (sum' - (A*n**n - 1) * D / (A * n**n)) = D ** (n + 1) / (n ** (2 * n) * prod' * A)
+/// y**2 + b*y = c
+fn compute_y(
+    leverage: u64,
+    amounts: &[u128],
+    destination_index: usize,
+    d_val: u128,
+) -> Option<u128> {
+    let n_coins = u8::try_from(amounts.len()).ok()?;
+    let leverage: U256 = leverage.into();
+    let d_val: U256 = d_val.into();
+
+    // c = D ** (n + 1) / (n ** (2 * n) * prod' * A), dividing step by step
+    // to keep the intermediate values small
+    let mut c = d_val;
+    let mut sum = U256::zero();
+    for (index, amount) in amounts.iter().enumerate() {
+        if index == destination_index {
+            continue;
+        }
+        let amount = U256::from(*amount);
+        sum = sum.checked_add(amount)?;
+        c = c
+            .checked_mul(d_val)?
+            .checked_div(checked_u8_mul(&amount, n_coins)?)?;
+    }
+    c = c
+        .checked_mul(d_val)?
+        .checked_div(checked_u8_mul(&leverage, n_coins)?)?;
+
+    // b = sum' - (A*n**n - 1) * D / (A * n**n)
+    let b = sum.checked_add(d_val.checked_div(leverage)?)?;
+
+    // Solve for y by approximating: y**2 + b*y = c
+    let mut y = d_val;
+    for _ in 0..ITERATIONS {
+        let (y_new, _) = (checked_u8_power(&y, 2)?.checked_add(c)?)
+            .checked_ceil_div(checked_u8_mul(&y, 2)?.checked_add(b)?.checked_sub(d_val)?)?;
+        if y_new == y {
+            break;
+        } else {
+            y = y_new;
+        }
+    }
+    u128::try_from(y).ok()
+}
+
+impl StableCurve {
+    /// Swap between any two tokens of a multi-asset pool, returning the
+    /// amount of destination tokens
+    pub fn multi_asset_swap_without_fees(
+        &self,
+        source_amount: u128,
+        source_index: usize,
+        destination_index: usize,
+        swap_token_amounts: &[u128],
+    ) -> Option<u128> {
+        if source_amount == 0 {
+            return Some(0);
+        }
+        let leverage = compute_a(self.amp, u8::try_from(swap_token_amounts.len()).ok()?)?;
+        let d_val = compute_d(leverage, swap_token_amounts)?;
+        let mut new_amounts = swap_token_amounts.to_vec();
+        new_amounts[source_index] = new_amounts[source_index].checked_add(source_amount)?;
+        let new_destination_amount = compute_y(leverage, &new_amounts, destination_index, d_val)?;
+        swap_token_amounts[destination_index].checked_sub(new_destination_amount)
+    }
+
+    /// Get the amount of pool tokens for the deposited amount of one token of
+    /// a multi-asset pool, from the change of the invariant
+    pub fn multi_asset_deposit_single_token_type(
+        &self,
+        source_amount: u128,
+        token_index: usize,
+        swap_token_amounts: &[u128],
+        pool_supply: u128,
+    ) -> Option<u128> {
+        if source_amount == 0 {
+            return Some(0);
+        }
+        let leverage = compute_a(self.amp, u8::try_from(swap_token_amounts.len()).ok()?)?;
+        let d0 = PreciseNumber::new(compute_d(leverage, swap_token_amounts)?)?;
+        let mut new_amounts = swap_token_amounts.to_vec();
+        new_amounts[token_index] = new_amounts[token_index].checked_add(source_amount)?;
+        let d1 = PreciseNumber::new(compute_d(leverage, &new_amounts)?)?;
+        let diff = d1.checked_sub(&d0)?;
+        let final_amount =
+            (diff.checked_mul(&PreciseNumber::new(pool_supply)?))?.checked_div(&d0)?;
+        final_amount.floor()?.to_imprecise()
+    }
+
+    /// Get the amount of pool tokens to burn for the withdrawn amount of one
+    /// token of a multi-asset pool, from the change of the invariant
+    pub fn multi_asset_withdraw_single_token_type_exact_out(
+        &self,
+        destination_amount: u128,
+        token_index: usize,
+        swap_token_amounts: &[u128],
+        pool_supply: u128,
+    ) -> Option<u128> {
+        if destination_amount == 0 {
+            return Some(0);
+        }
+        let leverage = compute_a(self.amp, u8::try_from(swap_token_amounts.len()).ok()?)?;
+        let d0 = PreciseNumber::new(compute_d(leverage, swap_token_amounts)?)?;
+        let mut new_amounts = swap_token_amounts.to_vec();
+        new_amounts[token_index] = new_amounts[token_index].checked_sub(destination_amount)?;
+        let d1 = PreciseNumber::new(compute_d(leverage, &new_amounts)?)?;
+        let diff = d0.checked_sub(&d1)?;
+        let final_amount =
+            (diff.checked_mul(&PreciseNumber::new(pool_supply)?))?.checked_div(&d0)?;
+        final_amount.ceiling()?.to_imprecise()
+    }
+}
+
 impl CurveCalculator for StableCurve {
     /// Stable curve
     fn swap_without_fees(
@@ -161,13 +289,13 @@ impl CurveCalculator for StableCurve {
                 destination_amount_swapped: 0,
             });
         }
-        let leverage = compute_a(self.amp)?;
+        let leverage = compute_a(self.amp, N_COINS)?;
 
         let new_source_amount = swap_source_amount.checked_add(source_amount)?;
         let new_destination_amount = compute_new_destination_amount(
             leverage,
             new_source_amount,
-            compute_d(leverage, swap_source_amount, swap_destination_amount)?,
+            compute_d(leverage, &[swap_source_amount, swap_destination_amount])?,
         )?;
 
         let amount_swapped = swap_destination_amount.checked_sub(new_destination_amount)?;
@@ -235,11 +363,10 @@ impl CurveCalculator for StableCurve {
         if source_amount == 0 {
             return Some(0);
         }
-        let leverage = compute_a(self.amp)?;
+        let leverage = compute_a(self.amp, N_COINS)?;
         let d0 = PreciseNumber::new(compute_d(
             leverage,
-            swap_token_a_amount,
-            swap_token_b_amount,
+            &[swap_token_a_amount, swap_token_b_amount],
         )?)?;
         let (deposit_token_amount, other_token_amount) = match trade_direction {
             TradeDirection::AtoB => (swap_token_a_amount, swap_token_b_amount),
@@ -248,8 +375,7 @@ impl CurveCalculator for StableCurve {
         let updated_deposit_token_amount = deposit_token_amount.checked_add(source_amount)?;
         let d1 = PreciseNumber::new(compute_d(
             leverage,
-            updated_deposit_token_amount,
-            other_token_amount,
+            &[updated_deposit_token_amount, other_token_amount],
         )?)?;
         let diff = d1.checked_sub(&d0)?;
         let final_amount =
@@ -268,11 +394,10 @@ impl CurveCalculator for StableCurve {
         if source_amount == 0 {
             return Some(0);
         }
-        let leverage = compute_a(self.amp)?;
+        let leverage = compute_a(self.amp, N_COINS)?;
         let d0 = PreciseNumber::new(compute_d(
             leverage,
-            swap_token_a_amount,
-            swap_token_b_amount,
+            &[swap_token_a_amount, swap_token_b_amount],
         )?)?;
         let (withdraw_token_amount, other_token_amount) = match trade_direction {
             TradeDirection::AtoB => (swap_token_a_amount, swap_token_b_amount),
@@ -281,8 +406,7 @@ impl CurveCalculator for StableCurve {
         let updated_deposit_token_amount = withdraw_token_amount.checked_sub(source_amount)?;
         let d1 = PreciseNumber::new(compute_d(
             leverage,
-            updated_deposit_token_amount,
-            other_token_amount,
+            &[updated_deposit_token_amount, other_token_amount],
         )?)?;
         let diff = d0.checked_sub(&d1)?;
         let final_amount =
@@ -297,11 +421,10 @@ impl CurveCalculator for StableCurve {
     ) -> Option<PreciseNumber> {
         #[cfg(not(any(test, feature = "fuzz")))]
         {
-            let leverage = compute_a(self.amp)?;
+            let leverage = compute_a(self.amp, N_COINS)?;
             PreciseNumber::new(compute_d(
                 leverage,
-                swap_token_a_amount,
-                swap_token_b_amount,
+                &[swap_token_a_amount, swap_token_b_amount],
             )?)
         }
         #[cfg(any(test, feature = "fuzz"))]
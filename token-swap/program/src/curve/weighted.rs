@@ -0,0 +1,286 @@
+//! Balancer-style weighted invariant, for pools of tokens held in fixed
+//! proportions of value other than one half.
+//!
+//! The invariant is `prod(x_i ** w_i)`, where `w_i` are the normalized
+//! weights of the tokens. Reference at:
+//! <https://github.com/balancer-labs/balancer-core/blob/f4ed5d65362a8d6cec21662fb6eae233b0babc1f/contracts/BMath.sol>
+
+use spl_math::{precise_number::PreciseNumber, uint::U256};
+
+/// Maximum number of iterations of the series approximating fractional
+/// powers
+const MAX_POW_ITERATIONS: u128 = 100;
+
+/// Terms of the series smaller than this, in precise units, end the
+/// approximation of fractional powers
+const POW_PRECISION: u128 = 100;
+
+/// Swaps can only add up to this fraction of the source token amount of the
+/// pool, to keep the approximation of fractional powers accurate
+pub const MAX_IN_RATIO: (u128, u128) = (1, 2);
+
+/// Swaps can only take up to this fraction of the destination token amount of
+/// the pool, to keep the approximation of fractional powers accurate
+pub const MAX_OUT_RATIO: (u128, u128) = (1, 3);
+
+fn within_ratio(amount: u128, swap_token_amount: u128, ratio: (u128, u128)) -> Option<bool> {
+    Some(amount.checked_mul(ratio.1)? <= swap_token_amount.checked_mul(ratio.0)?)
+}
+
+/// Approximates `base ** exponent` for a fractional exponent with the binomial
+/// series around 1, which converges for bases strictly between 0 and 2:
+///
+/// (1 + x) ** a = 1 + a * x + a * (a - 1) / 2! * x ** 2 + ...
+///
+/// The whole part of the exponent is applied as a regular power. Returns
+/// `None` for bases out of range.
+pub fn checked_pow_fraction(
+    base: &PreciseNumber,
+    exponent: &PreciseNumber,
+) -> Option<PreciseNumber> {
+    let one = PreciseNumber::new(1)?;
+    let two = PreciseNumber::new(2)?;
+    if base.value == U256::zero() || base.greater_than_or_equal(&two) {
+        return None;
+    }
+    let whole_exponent = exponent.floor()?;
+    let whole_pow = base.checked_pow(whole_exponent.to_imprecise()?)?;
+    let fraction = exponent.checked_sub(&whole_exponent)?;
+    if fraction.value == U256::zero() {
+        return Some(whole_pow);
+    }
+
+    // t_k = t_k-1 * (a - (k - 1)) * x / k
+    let (x, x_negative) = base.unsigned_sub(&one);
+    let mut term = one.clone();
+    let mut sum = one.clone();
+    let mut negative = false;
+    for k in 1..MAX_POW_ITERATIONS {
+        let big_k = PreciseNumber::new(k)?;
+        let (c, c_negative) = fraction.unsigned_sub(&big_k.checked_sub(&one)?);
+        term = term.checked_mul(&c.checked_mul(&x)?)?.checked_div(&big_k)?;
+        if term.value == U256::zero() {
+            break;
+        }
+        if x_negative {
+            negative = !negative;
+        }
+        if c_negative {
+            negative = !negative;
+        }
+        sum = if negative {
+            sum.checked_sub(&term)?
+        } else {
+            sum.checked_add(&term)?
+        };
+        if term.value < U256::from(POW_PRECISION) {
+            break;
+        }
+    }
+    whole_pow.checked_mul(&sum)
+}
+
+/// Amount of destination tokens for the given amount of source tokens:
+///
+/// out = B_o * (1 - (B_i / (B_i + A_i)) ** (w_i / w_o))
+///
+/// Returns `None` if the swap goes over `MAX_IN_RATIO` or `MAX_OUT_RATIO`.
+pub fn swap_without_fees(
+    source_amount: u128,
+    swap_source_amount: u128,
+    source_weight: u128,
+    swap_destination_amount: u128,
+    destination_weight: u128,
+) -> Option<u128> {
+    if source_amount == 0 {
+        return Some(0);
+    }
+    if !within_ratio(source_amount, swap_source_amount, MAX_IN_RATIO)? {
+        return None;
+    }
+    let base = PreciseNumber::new(swap_source_amount)?.checked_div(&PreciseNumber::new(
+        swap_source_amount.checked_add(source_amount)?,
+    )?)?;
+    let exponent =
+        PreciseNumber::new(source_weight)?.checked_div(&PreciseNumber::new(destination_weight)?)?;
+    let ratio = PreciseNumber::new(1)?.checked_sub(&checked_pow_fraction(&base, &exponent)?)?;
+    let destination_amount = PreciseNumber::new(swap_destination_amount)?
+        .checked_mul(&ratio)?
+        .floor()?
+        .to_imprecise()?;
+    if !within_ratio(destination_amount, swap_destination_amount, MAX_OUT_RATIO)? {
+        return None;
+    }
+    Some(destination_amount)
+}
+
+/// Amount of pool tokens for the deposit of one token with the given
+/// normalized weight, `weight / total_weight`:
+///
+/// P = S * (((B + A) / B) ** w - 1)
+///
+/// Returns `None` if the deposit goes over `MAX_IN_RATIO`.
+pub fn deposit_single_token_type(
+    source_amount: u128,
+    swap_token_amount: u128,
+    weight: u128,
+    total_weight: u128,
+    pool_supply: u128,
+) -> Option<u128> {
+    if source_amount == 0 {
+        return Some(0);
+    }
+    if !within_ratio(source_amount, swap_token_amount, MAX_IN_RATIO)? {
+        return None;
+    }
+    let base = PreciseNumber::new(swap_token_amount.checked_add(source_amount)?)?
+        .checked_div(&PreciseNumber::new(swap_token_amount)?)?;
+    let exponent = PreciseNumber::new(weight)?.checked_div(&PreciseNumber::new(total_weight)?)?;
+    let ratio = checked_pow_fraction(&base, &exponent)?.checked_sub(&PreciseNumber::new(1)?)?;
+    PreciseNumber::new(pool_supply)?
+        .checked_mul(&ratio)?
+        .floor()?
+        .to_imprecise()
+}
+
+/// Amount of pool tokens to burn for the withdrawal of one token with the
+/// given normalized weight, `weight / total_weight`:
+///
+/// P = S * (1 - ((B - A) / B) ** w)
+///
+/// Returns `None` if the withdrawal goes over `MAX_OUT_RATIO`.
+pub fn withdraw_single_token_type_exact_out(
+    destination_amount: u128,
+    swap_token_amount: u128,
+    weight: u128,
+    total_weight: u128,
+    pool_supply: u128,
+) -> Option<u128> {
+    if destination_amount == 0 {
+        return Some(0);
+    }
+    if !within_ratio(destination_amount, swap_token_amount, MAX_OUT_RATIO)? {
+        return None;
+    }
+    let base = PreciseNumber::new(swap_token_amount.checked_sub(destination_amount)?)?
+        .checked_div(&PreciseNumber::new(swap_token_amount)?)?;
+    let exponent = PreciseNumber::new(weight)?.checked_div(&PreciseNumber::new(total_weight)?)?;
+    let ratio = PreciseNumber::new(1)?.checked_sub(&checked_pow_fraction(&base, &exponent)?)?;
+    PreciseNumber::new(pool_supply)?
+        .checked_mul(&ratio)?
+        .ceiling()?
+        .to_imprecise()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_pow(base: f64, exponent: f64) {
+        let precise_base = PreciseNumber::new((base * 1_000_000.0) as u128)
+            .unwrap()
+            .checked_div(&PreciseNumber::new(1_000_000).unwrap())
+            .unwrap();
+        let precise_exponent = PreciseNumber::new((exponent * 1_000_000.0) as u128)
+            .unwrap()
+            .checked_div(&PreciseNumber::new(1_000_000).unwrap())
+            .unwrap();
+        let result = checked_pow_fraction(&precise_base, &precise_exponent)
+            .unwrap()
+            .checked_mul(&PreciseNumber::new(1_000_000_000).unwrap())
+            .unwrap()
+            .to_imprecise()
+            .unwrap();
+        let expected = (base.powf(exponent) * 1_000_000_000.0).round() as u128;
+        assert!(
+            result.abs_diff(expected) <= 1,
+            "{}^{}: result={}, expected={}",
+            base,
+            exponent,
+            result,
+            expected
+        );
+    }
+
+    #[test]
+    fn pow_fraction() {
+        check_pow(0.5, 0.5);
+        check_pow(1.5, 0.5);
+        check_pow(0.8, 0.25);
+        check_pow(1.2, 0.75);
+        check_pow(0.9, 4.0);
+        check_pow(0.7, 2.5);
+        check_pow(1.5, 0.1);
+        check_pow(1.0, 0.3);
+
+        let one = PreciseNumber::new(1).unwrap();
+        assert!(checked_pow_fraction(&PreciseNumber::new(0).unwrap(), &one).is_none());
+        assert!(checked_pow_fraction(&PreciseNumber::new(2).unwrap(), &one).is_none());
+    }
+
+    #[test]
+    fn swap_equal_weights() {
+        // equal weights give the constant product curve
+        let destination_amount = swap_without_fees(1_000, 100_000, 1, 200_000, 1).unwrap();
+        assert_eq!(destination_amount, 1_980);
+    }
+
+    #[test]
+    fn swap_unequal_weights() {
+        // 80/20 pool, where token A is priced 4 times token B in the pool
+        // amounts
+        let source_amount = 1_000u128;
+        let destination_amount =
+            swap_without_fees(source_amount, 400_000, 80, 100_000, 20).unwrap();
+        let expected = 100_000.0 * (1.0 - (400_000.0f64 / 401_000.0).powf(4.0));
+        assert!((destination_amount as f64 - expected).abs() <= 1.0);
+        // the weighted invariant does not decrease
+        let before = 400_000f64.powf(0.8) * 100_000f64.powf(0.2);
+        let after = 401_000f64.powf(0.8) * ((100_000 - destination_amount) as f64).powf(0.2);
+        assert!(after >= before);
+
+        let destination_amount =
+            swap_without_fees(source_amount, 100_000, 20, 400_000, 80).unwrap();
+        let expected = 400_000.0 * (1.0 - (100_000.0f64 / 101_000.0).powf(0.25));
+        assert!((destination_amount as f64 - expected).abs() <= 1.0);
+    }
+
+    #[test]
+    fn swap_limits() {
+        assert_eq!(swap_without_fees(0, 100_000, 1, 100_000, 1), Some(0));
+        assert!(swap_without_fees(50_000, 100_000, 1, 100_000, 1).is_some());
+        assert!(swap_without_fees(50_001, 100_000, 1, 100_000, 1).is_none());
+        // a third of the destination amount is reached before half the source
+        assert!(swap_without_fees(50_000, 100_000, 4, 100_000, 1).is_none());
+    }
+
+    #[test]
+    fn single_token_deposit_and_withdraw() {
+        let pool_supply = 1_000_000_000;
+        let pool_tokens = deposit_single_token_type(10_000, 1_000_000, 1, 4, pool_supply).unwrap();
+        let expected = pool_supply as f64 * (1.01f64.powf(0.25) - 1.0);
+        assert!((pool_tokens as f64 - expected).abs() <= 1.0);
+
+        let burned =
+            withdraw_single_token_type_exact_out(10_000, 1_000_000, 1, 4, pool_supply).unwrap();
+        let expected = pool_supply as f64 * (1.0 - 0.99f64.powf(0.25));
+        assert!((burned as f64 - expected).abs() <= 1.0);
+
+        // depositing then withdrawing the same amount never mints more
+        // than it burns
+        let burned = withdraw_single_token_type_exact_out(
+            10_000,
+            1_010_000,
+            1,
+            4,
+            pool_supply + pool_tokens,
+        )
+        .unwrap();
+        assert!(burned >= pool_tokens);
+
+        assert!(deposit_single_token_type(500_001, 1_000_000, 1, 4, pool_supply).is_none());
+        assert!(
+            withdraw_single_token_type_exact_out(333_334, 1_000_000, 1, 4, pool_supply).is_none()
+        );
+    }
+}
@@ -102,6 +102,67 @@ pub enum SwapError {
     /// The operation cannot be performed on the given curve
     #[error("The operation cannot be performed on the given curve")]
     UnsupportedCurveOperation,
+    /// The operation is not supported by the version of the swap account
+    #[error("The operation is not supported by the version of the swap account")]
+    UnsupportedSwapVersion,
+    /// The swap is paused, only withdrawals of all token types are allowed
+    #[error("The swap is paused")]
+    SwapPaused,
+
+    // 30.
+    /// The pause authority is missing, did not sign, or does not match the swap
+    #[error("The pause authority is missing, did not sign, or does not match the swap")]
+    InvalidPauseAuthority,
+    /// The provided stake pool does not match the token A mint of the swap
+    #[error("The provided stake pool does not match the token A mint of the swap")]
+    IncorrectStakePool,
+    /// The exchange rate of the stake pool has not been updated this epoch
+    #[error("The exchange rate of the stake pool has not been updated this epoch")]
+    StaleStakePool,
+    /// The provided oracle account does not match the oracle of the swap
+    #[error("The provided oracle account does not match the oracle of the swap")]
+    IncorrectOracle,
+    /// The oracle account does not hold a valid trading price
+    #[error("The oracle account does not hold a valid trading price")]
+    InvalidOracle,
+
+    // 35.
+    /// The oracle price has not been updated recently
+    #[error("The oracle price has not been updated recently")]
+    StaleOracle,
+    /// The owner of the pool fee account is missing, did not sign, or does not
+    /// match the swap
+    #[error(
+        "The owner of the pool fee account is missing, did not sign, or does not match the swap"
+    )]
+    InvalidFeeAccountOwner,
+    /// The provided token mint does not match the mint of the swap account
+    #[error("The provided token mint does not match the mint of the swap account")]
+    IncorrectMint,
+    /// The token mint or account has an extension the swap does not support
+    #[error("The token mint or account has an extension the swap does not support")]
+    UnsupportedTokenExtension,
+    /// The tick is out of bounds or not a multiple of the tick spacing
+    #[error("The tick is out of bounds or not a multiple of the tick spacing")]
+    InvalidTick,
+
+    // 40.
+    /// The concentrated pool cannot hold any more initialized ticks
+    #[error("The concentrated pool cannot hold any more initialized ticks")]
+    TooManyTicks,
+    /// The provided position does not belong to the concentrated pool
+    #[error("The provided position does not belong to the concentrated pool")]
+    IncorrectPosition,
+    /// The owner of the position is missing, did not sign, or does not match
+    #[error("The owner of the position is missing, did not sign, or does not match")]
+    InvalidPositionOwner,
+    /// The position or the pool holds less liquidity than requested
+    #[error("The position or the pool holds less liquidity than requested")]
+    InsufficientLiquidity,
+    /// The position would hold less than the minimum liquidity, without
+    /// being emptied
+    #[error("The position would hold less than the minimum liquidity")]
+    PositionLiquidityTooLow,
 }
 impl From<SwapError> for ProgramError {
     fn from(e: SwapError) -> Self {
@@ -2,7 +2,11 @@
 
 #![allow(clippy::too_many_arguments)]
 
-use crate::curve::{base::SwapCurve, fees::Fees};
+use crate::curve::{
+    base::SwapCurve,
+    fees::{DynamicFees, Fees},
+    multi_asset::{MultiAssetCurve, MultiAssetCurveType},
+};
 use crate::error::SwapError;
 use solana_program::{
     instruction::{AccountMeta, Instruction},
@@ -10,7 +14,7 @@ use solana_program::{
     program_pack::Pack,
     pubkey::Pubkey,
 };
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::mem::size_of;
 
 #[cfg(feature = "fuzz")]
@@ -90,6 +94,95 @@ pub struct WithdrawSingleTokenTypeExactAmountOut {
     pub maximum_pool_token_amount: u64,
 }
 
+/// InitializeConcentratedPool instruction data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct InitializeConcentratedPool {
+    /// trade and owner trade fees, the other fees being unused
+    pub fees: Fees,
+    /// positions can only be bounded by multiples of the tick spacing
+    pub tick_spacing: u16,
+    /// square root of the initial price of token A in token B, in Q64.64
+    pub initial_sqrt_price: u128,
+}
+
+/// OpenPosition instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpenPosition {
+    /// Lower tick of the price range, inclusive
+    pub tick_lower: i32,
+    /// Upper tick of the price range, exclusive
+    pub tick_upper: i32,
+}
+
+/// IncreaseLiquidity instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct IncreaseLiquidity {
+    /// Liquidity to add to the position. Token A and B amounts are set by
+    /// the current price and the price range of the position, which must
+    /// then hold at least `MIN_POSITION_LIQUIDITY`
+    pub liquidity: u128,
+    /// Maximum token A amount to deposit, prevents excessive slippage
+    pub maximum_token_a_amount: u64,
+    /// Maximum token B amount to deposit, prevents excessive slippage
+    pub maximum_token_b_amount: u64,
+}
+
+/// DecreaseLiquidity instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecreaseLiquidity {
+    /// Liquidity to remove from the position, which may be zero to only
+    /// collect fees, and must leave either none or at least
+    /// `MIN_POSITION_LIQUIDITY`
+    pub liquidity: u128,
+    /// Minimum amount of token A to receive, prevents excessive slippage
+    pub minimum_token_a_amount: u64,
+    /// Minimum amount of token B to receive, prevents excessive slippage
+    pub minimum_token_b_amount: u64,
+}
+
+/// InitializeMultiAssetPool instruction data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct InitializeMultiAssetPool {
+    /// all pool fees
+    pub fees: Fees,
+    /// curve of the pool, whose weights give the number of tokens
+    pub curve: MultiAssetCurve,
+}
+
+/// MultiAssetDepositAllTokenTypes instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiAssetDepositAllTokenTypes {
+    /// Pool token amount to transfer. The token amounts are set by the
+    /// current exchange rate and size of the pool
+    pub pool_token_amount: u64,
+    /// Maximum amount of each token to deposit, in the order of the pool,
+    /// prevents excessive slippage
+    pub maximum_token_amounts: Vec<u64>,
+}
+
+/// MultiAssetWithdrawAllTokenTypes instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiAssetWithdrawAllTokenTypes {
+    /// Amount of pool tokens to burn. User receives an output of each token
+    /// based on the percentage of the pool tokens that are returned.
+    pub pool_token_amount: u64,
+    /// Minimum amount of each token to receive, in the order of the pool,
+    /// prevents excessive slippage
+    pub minimum_token_amounts: Vec<u64>,
+}
+
 /// Instructions supported by the token swap program.
 #[repr(C)]
 #[derive(Debug, PartialEq)]
@@ -105,12 +198,19 @@ pub enum SwapInstruction {
     ///   Must be empty, not owned by swap authority
     ///   6. `[writable]` Pool Token Account to deposit the initial pool token
     ///   supply.  Must be empty, not owned by swap authority.
-    ///   7. `[]` Token program id
+    ///   7. `[]` Pool Token program id
+    ///   8. `[]` Token A mint
+    ///   9. `[]` Token B mint
+    ///   10. `[]` Curve pricing account: the stake pool of the token A mint
+    ///   for the stake pool stable curve, or the oracle price account for the
+    ///   proactive market making curve.  Must be omitted for other curves.
+    ///   11. `[optional]` Pause authority, allowed to halt and resume the swap.
+    ///   The swap can never be paused if not provided.
     Initialize(Initialize),
 
     ///   Swap the tokens in the pool.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap, to accumulate prices and update dynamic fees
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` token_(A|B) SOURCE Account, amount is transferable by user transfer authority,
@@ -119,15 +219,22 @@ pub enum SwapInstruction {
     ///   6. `[writable]` token_(A|B) DESTINATION Account assigned to USER as the owner.
     ///   7. `[writable]` Pool token mint, to generate trading fees
     ///   8. `[writable]` Fee account, to receive trading fees
-    ///   9. `[]` Token program id
-    ///   10. `[optional, writable]` Host fee account to receive additional trading fees
+    ///   9. `[]` Token (A|B) SOURCE mint
+    ///   10. `[]` Token (A|B) DESTINATION mint
+    ///   11. `[]` Token (A|B) SOURCE program id
+    ///   12. `[]` Token (A|B) DESTINATION program id
+    ///   13. `[]` Pool Token program id
+    ///   14. `[]` Curve pricing account: the stake pool of the token A mint
+    ///   for the stake pool stable curve, or the oracle price account for the
+    ///   proactive market making curve.  Must be omitted for other curves.
+    ///   15. `[optional, writable]` Host fee account to receive additional trading fees
     Swap(Swap),
 
     ///   Deposit both types of tokens into the pool.  The output is a "pool"
     ///   token representing ownership in the pool. Inputs are converted to
     ///   the current ratio.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap, to accumulate prices
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` token_a user transfer authority can transfer amount,
@@ -136,14 +243,18 @@ pub enum SwapInstruction {
     ///   6. `[writable]` token_b Base Account to deposit into.
     ///   7. `[writable]` Pool MINT account, swap authority is the owner.
     ///   8. `[writable]` Pool Account to deposit the generated tokens, user is the owner.
-    ///   9. `[]` Token program id
+    ///   9. `[]` Token A mint
+    ///   10. `[]` Token B mint
+    ///   11. `[]` Token A program id
+    ///   12. `[]` Token B program id
+    ///   13. `[]` Pool Token program id
     DepositAllTokenTypes(DepositAllTokenTypes),
 
     ///   Withdraw both types of tokens from the pool at the current ratio, given
     ///   pool tokens.  The pool tokens are burned in exchange for an equivalent
     ///   amount of token A and B.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap, to accumulate prices
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` Pool mint account, swap authority is the owner
@@ -153,14 +264,18 @@ pub enum SwapInstruction {
     ///   7. `[writable]` token_a user Account to credit.
     ///   8. `[writable]` token_b user Account to credit.
     ///   9. `[writable]` Fee account, to receive withdrawal fees
-    ///   10. `[]` Token program id
+    ///   10. `[]` Token A mint
+    ///   11. `[]` Token B mint
+    ///   12. `[]` Token A program id
+    ///   13. `[]` Token B program id
+    ///   14. `[]` Pool Token program id
     WithdrawAllTokenTypes(WithdrawAllTokenTypes),
 
     ///   Deposit one type of tokens into the pool.  The output is a "pool" token
     ///   representing ownership into the pool. Input token is converted as if
     ///   a swap and deposit all token types were performed.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap, to accumulate prices and update dynamic fees
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` token_(A|B) SOURCE Account, amount is transferable by user transfer authority,
@@ -168,13 +283,18 @@ pub enum SwapInstruction {
     ///   5. `[writable]` token_b Swap Account, may deposit INTO.
     ///   6. `[writable]` Pool MINT account, swap authority is the owner.
     ///   7. `[writable]` Pool Account to deposit the generated tokens, user is the owner.
-    ///   8. `[]` Token program id
+    ///   8. `[]` Token (A|B) SOURCE mint
+    ///   9. `[]` Token (A|B) SOURCE program id
+    ///   10. `[]` Pool Token program id
+    ///   11. `[]` Curve pricing account: the stake pool of the token A mint
+    ///   for the stake pool stable curve, or the oracle price account for the
+    ///   proactive market making curve.  Must be omitted for other curves.
     DepositSingleTokenTypeExactAmountIn(DepositSingleTokenTypeExactAmountIn),
 
     ///   Withdraw one token type from the pool at the current ratio given the
     ///   exact amount out expected.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap, to accumulate prices and update dynamic fees
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` Pool mint account, swap authority is the owner
@@ -183,8 +303,218 @@ pub enum SwapInstruction {
     ///   6. `[writable]` token_b Swap Account to potentially withdraw from.
     ///   7. `[writable]` token_(A|B) User Account to credit
     ///   8. `[writable]` Fee account, to receive withdrawal fees
-    ///   9. `[]` Token program id
+    ///   9. `[]` Token (A|B) DESTINATION mint
+    ///   10. `[]` Token (A|B) DESTINATION program id
+    ///   11. `[]` Pool Token program id
+    ///   12. `[]` Curve pricing account: the stake pool of the token A mint
+    ///   for the stake pool stable curve, or the oracle price account for the
+    ///   proactive market making curve.  Must be omitted for other curves.
     WithdrawSingleTokenTypeExactAmountOut(WithdrawSingleTokenTypeExactAmountOut),
+
+    ///   Halt swaps, deposits and single token withdrawals in case of an
+    ///   emergency.  Withdrawing all token types stays available, so liquidity
+    ///   providers can always exit the pool.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[signer]` Pause authority
+    Pause,
+
+    ///   Resume normal operations of a paused swap.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[signer]` Pause authority
+    Resume,
+
+    ///   Set the dynamic fee parameters, raising the trade fee with the price
+    ///   impact of recent swaps.  Dynamic fees are disabled by setting the
+    ///   decay half-life to zero.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` Pool fee account of the swap
+    ///   2. `[signer]` Owner of the pool fee account
+    SetDynamicFees(DynamicFees),
+
+    ///   Initializes a new concentrated pool, where liquidity is provided in
+    ///   price ranges through positions instead of pool tokens.
+    ///
+    ///   0. `[writable, signer]` New concentrated pool to create.
+    ///   1. `[]` pool authority derived from `create_program_address(&[concentrated pool account])`
+    ///   2. `[]` token_a Account. Owned by pool authority.
+    ///   3. `[]` token_b Account. Owned by pool authority.
+    ///   4. `[]` Token A mint
+    ///   5. `[]` Token B mint
+    ///   6. `[]` Protocol fee owner, allowed to collect the owner trade fees
+    InitializeConcentratedPool(InitializeConcentratedPool),
+
+    ///   Opens an empty position in a price range of a concentrated pool.
+    ///
+    ///   0. `[]` Concentrated pool
+    ///   1. `[writable, signer]` New position to create
+    ///   2. `[signer]` Owner of the position
+    OpenPosition(OpenPosition),
+
+    ///   Adds liquidity to a position, depositing tokens A and B as required
+    ///   by the current price.
+    ///
+    ///   0. `[writable]` Concentrated pool
+    ///   1. `[]` pool authority
+    ///   2. `[writable]` Position
+    ///   3. `[signer]` Owner of the position
+    ///   4. `[signer]` user transfer authority
+    ///   5. `[writable]` token_a user transfer authority can transfer amount,
+    ///   6. `[writable]` token_b user transfer authority can transfer amount,
+    ///   7. `[writable]` token_a Base Account to deposit into.
+    ///   8. `[writable]` token_b Base Account to deposit into.
+    ///   9. `[]` Token A mint
+    ///   10. `[]` Token B mint
+    ///   11. `[]` Token A program id
+    ///   12. `[]` Token B program id
+    IncreaseLiquidity(IncreaseLiquidity),
+
+    ///   Removes liquidity from a position, withdrawing tokens A and B along
+    ///   with all fees earned by the position.
+    ///
+    ///   0. `[writable]` Concentrated pool
+    ///   1. `[]` pool authority
+    ///   2. `[writable]` Position
+    ///   3. `[signer]` Owner of the position
+    ///   4. `[writable]` token_a Base Account to withdraw FROM.
+    ///   5. `[writable]` token_b Base Account to withdraw FROM.
+    ///   6. `[writable]` token_a user Account to credit.
+    ///   7. `[writable]` token_b user Account to credit.
+    ///   8. `[]` Token A mint
+    ///   9. `[]` Token B mint
+    ///   10. `[]` Token A program id
+    ///   11. `[]` Token B program id
+    DecreaseLiquidity(DecreaseLiquidity),
+
+    ///   Swap the tokens in a concentrated pool, across as many price ranges
+    ///   as needed.
+    ///
+    ///   0. `[writable]` Concentrated pool
+    ///   1. `[]` pool authority
+    ///   2. `[]` user transfer authority
+    ///   3. `[writable]` token_(A|B) SOURCE Account, amount is transferable by user transfer authority,
+    ///   4. `[writable]` token_(A|B) Base Account to swap INTO.  Must be the SOURCE token.
+    ///   5. `[writable]` token_(A|B) Base Account to swap FROM.  Must be the DESTINATION token.
+    ///   6. `[writable]` token_(A|B) DESTINATION Account assigned to USER as the owner.
+    ///   7. `[]` Token (A|B) SOURCE mint
+    ///   8. `[]` Token (A|B) DESTINATION mint
+    ///   9. `[]` Token (A|B) SOURCE program id
+    ///   10. `[]` Token (A|B) DESTINATION program id
+    SwapConcentrated(Swap),
+
+    ///   Collects the owner trade fees set aside by a concentrated pool.
+    ///
+    ///   0. `[writable]` Concentrated pool
+    ///   1. `[]` pool authority
+    ///   2. `[signer]` Protocol fee owner
+    ///   3. `[writable]` token_a Base Account to withdraw FROM.
+    ///   4. `[writable]` token_b Base Account to withdraw FROM.
+    ///   5. `[writable]` token_a Account to credit.
+    ///   6. `[writable]` token_b Account to credit.
+    ///   7. `[]` Token A mint
+    ///   8. `[]` Token B mint
+    ///   9. `[]` Token A program id
+    ///   10. `[]` Token B program id
+    CollectProtocolFees,
+
+    ///   Initializes a new multi-asset pool of 2 to 8 tokens, priced by a
+    ///   weighted or stable curve. The pool tokens are listed after the fixed
+    ///   accounts, in the order of the curve weights.
+    ///
+    ///   0. `[writable, signer]` New multi-asset pool to create.
+    ///   1. `[]` pool authority derived from `create_program_address(&[multi-asset pool account])`
+    ///   2. `[writable]` Pool Token Mint. Must be empty, owned by pool authority.
+    ///   3. `[]` Pool Token Account to deposit trading and withdraw fees.
+    ///   Must be empty, not owned by pool authority
+    ///   4. `[writable]` Pool Token Account to deposit the initial pool token
+    ///   supply.  Must be empty, not owned by pool authority.
+    ///   5. `[]` Pool Token program id
+    ///   6. + 2 * i. `[]` token_i Account. Must be non zero, owned by pool authority.
+    ///   7. + 2 * i. `[]` token_i mint
+    InitializeMultiAssetPool(InitializeMultiAssetPool),
+
+    ///   Swap between any two tokens of a multi-asset pool. The tokens are
+    ///   found from the SOURCE and DESTINATION mints, and the pricing needs
+    ///   the amounts of all the tokens of the pool.
+    ///
+    ///   0. `[]` Multi-asset pool
+    ///   1. `[]` pool authority
+    ///   2. `[signer]` user transfer authority
+    ///   3. `[writable]` SOURCE Account, amount is transferable by user transfer authority,
+    ///   4. `[writable]` DESTINATION Account assigned to USER as the owner.
+    ///   5. `[writable]` Pool token mint, to generate trading fees
+    ///   6. `[writable]` Fee account, to receive trading fees
+    ///   7. `[]` SOURCE mint
+    ///   8. `[]` DESTINATION mint
+    ///   9. `[]` SOURCE program id
+    ///   10. `[]` DESTINATION program id
+    ///   11. `[]` Pool Token program id
+    ///   12. + i. `[writable]` token_i Base Account, in the order of the pool
+    MultiAssetSwap(Swap),
+
+    ///   Deposit all tokens of a multi-asset pool. The output is a "pool"
+    ///   token representing ownership in the pool. Inputs are converted to
+    ///   the current ratio.
+    ///
+    ///   0. `[]` Multi-asset pool
+    ///   1. `[]` pool authority
+    ///   2. `[signer]` user transfer authority
+    ///   3. `[writable]` Pool MINT account, pool authority is the owner.
+    ///   4. `[writable]` Pool Account to deposit the generated tokens, user is the owner.
+    ///   5. `[]` Pool Token program id
+    ///   6. + 4 * i. `[writable]` token_i user transfer authority can transfer amount,
+    ///   7. + 4 * i. `[writable]` token_i Base Account to deposit into.
+    ///   8. + 4 * i. `[]` token_i mint
+    ///   9. + 4 * i. `[]` token_i program id
+    MultiAssetDepositAllTokenTypes(MultiAssetDepositAllTokenTypes),
+
+    ///   Withdraw all tokens of a multi-asset pool at the current ratio.
+    ///
+    ///   0. `[]` Multi-asset pool
+    ///   1. `[]` pool authority
+    ///   2. `[signer]` user transfer authority
+    ///   3. `[writable]` Pool mint account, pool authority is the owner
+    ///   4. `[writable]` SOURCE Pool account, amount is transferable by user transfer authority.
+    ///   5. `[writable]` Fee account, to receive withdrawal fees
+    ///   6. `[]` Pool Token program id
+    ///   7. + 4 * i. `[writable]` token_i Base Account to withdraw FROM.
+    ///   8. + 4 * i. `[writable]` token_i user Account to credit.
+    ///   9. + 4 * i. `[]` token_i mint
+    ///   10. + 4 * i. `[]` token_i program id
+    MultiAssetWithdrawAllTokenTypes(MultiAssetWithdrawAllTokenTypes),
+
+    ///   Deposit one token of a multi-asset pool, exact amount in. The token
+    ///   is found from the SOURCE mint.
+    ///
+    ///   0. `[]` Multi-asset pool
+    ///   1. `[]` pool authority
+    ///   2. `[signer]` user transfer authority
+    ///   3. `[writable]` SOURCE Account, amount is transferable by user transfer authority,
+    ///   4. `[writable]` Pool MINT account, pool authority is the owner.
+    ///   5. `[writable]` Pool Account to deposit the generated tokens, user is the owner.
+    ///   6. `[]` SOURCE mint
+    ///   7. `[]` SOURCE program id
+    ///   8. `[]` Pool Token program id
+    ///   9. + i. `[writable]` token_i Base Account, in the order of the pool
+    MultiAssetDepositSingleTokenTypeExactAmountIn(DepositSingleTokenTypeExactAmountIn),
+
+    ///   Withdraw one token of a multi-asset pool, exact amount out. The
+    ///   token is found from the DESTINATION mint.
+    ///
+    ///   0. `[]` Multi-asset pool
+    ///   1. `[]` pool authority
+    ///   2. `[signer]` user transfer authority
+    ///   3. `[writable]` Pool mint account, pool authority is the owner
+    ///   4. `[writable]` SOURCE Pool account, amount is transferable by user transfer authority.
+    ///   5. `[writable]` DESTINATION Account assigned to USER as the owner.
+    ///   6. `[writable]` Fee account, to receive withdrawal fees
+    ///   7. `[]` DESTINATION mint
+    ///   8. `[]` DESTINATION program id
+    ///   9. `[]` Pool Token program id
+    ///   10. + i. `[writable]` token_i Base Account, in the order of the pool
+    MultiAssetWithdrawSingleTokenTypeExactAmountOut(WithdrawSingleTokenTypeExactAmountOut),
 }
 
 impl SwapInstruction {
@@ -246,6 +576,134 @@ impl SwapInstruction {
                     maximum_pool_token_amount,
                 })
             }
+            6 => Self::Pause,
+            7 => Self::Resume,
+            8 => {
+                if rest.len() >= DynamicFees::LEN {
+                    let (dynamic_fees, _rest) = rest.split_at(DynamicFees::LEN);
+                    Self::SetDynamicFees(DynamicFees::unpack_unchecked(dynamic_fees)?)
+                } else {
+                    return Err(SwapError::InvalidInstruction.into());
+                }
+            }
+            9 => {
+                if rest.len() >= Fees::LEN {
+                    let (fees, rest) = rest.split_at(Fees::LEN);
+                    let fees = Fees::unpack_unchecked(fees)?;
+                    let (tick_spacing, rest) = Self::unpack_u16(rest)?;
+                    let (initial_sqrt_price, _rest) = Self::unpack_u128(rest)?;
+                    Self::InitializeConcentratedPool(InitializeConcentratedPool {
+                        fees,
+                        tick_spacing,
+                        initial_sqrt_price,
+                    })
+                } else {
+                    return Err(SwapError::InvalidInstruction.into());
+                }
+            }
+            10 => {
+                let (tick_lower, rest) = Self::unpack_i32(rest)?;
+                let (tick_upper, _rest) = Self::unpack_i32(rest)?;
+                Self::OpenPosition(OpenPosition {
+                    tick_lower,
+                    tick_upper,
+                })
+            }
+            11 => {
+                let (liquidity, rest) = Self::unpack_u128(rest)?;
+                let (maximum_token_a_amount, rest) = Self::unpack_u64(rest)?;
+                let (maximum_token_b_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::IncreaseLiquidity(IncreaseLiquidity {
+                    liquidity,
+                    maximum_token_a_amount,
+                    maximum_token_b_amount,
+                })
+            }
+            12 => {
+                let (liquidity, rest) = Self::unpack_u128(rest)?;
+                let (minimum_token_a_amount, rest) = Self::unpack_u64(rest)?;
+                let (minimum_token_b_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::DecreaseLiquidity(DecreaseLiquidity {
+                    liquidity,
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                })
+            }
+            13 => {
+                let (amount_in, rest) = Self::unpack_u64(rest)?;
+                let (minimum_amount_out, _rest) = Self::unpack_u64(rest)?;
+                Self::SwapConcentrated(Swap {
+                    amount_in,
+                    minimum_amount_out,
+                })
+            }
+            14 => Self::CollectProtocolFees,
+            15 => {
+                if rest.len() >= Fees::LEN {
+                    let (fees, rest) = rest.split_at(Fees::LEN);
+                    let fees = Fees::unpack_unchecked(fees)?;
+                    let (&curve_type, rest) =
+                        rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                    let curve_type = MultiAssetCurveType::try_from(curve_type)
+                        .map_err(|_| SwapError::InvalidInstruction)?;
+                    let (amp, rest) = Self::unpack_u64(rest)?;
+                    let (weights, _rest) = Self::unpack_u64_vec(rest)?;
+                    Self::InitializeMultiAssetPool(InitializeMultiAssetPool {
+                        fees,
+                        curve: MultiAssetCurve {
+                            curve_type,
+                            amp,
+                            weights,
+                        },
+                    })
+                } else {
+                    return Err(SwapError::InvalidInstruction.into());
+                }
+            }
+            16 => {
+                let (amount_in, rest) = Self::unpack_u64(rest)?;
+                let (minimum_amount_out, _rest) = Self::unpack_u64(rest)?;
+                Self::MultiAssetSwap(Swap {
+                    amount_in,
+                    minimum_amount_out,
+                })
+            }
+            17 => {
+                let (pool_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (maximum_token_amounts, _rest) = Self::unpack_u64_vec(rest)?;
+                Self::MultiAssetDepositAllTokenTypes(MultiAssetDepositAllTokenTypes {
+                    pool_token_amount,
+                    maximum_token_amounts,
+                })
+            }
+            18 => {
+                let (pool_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (minimum_token_amounts, _rest) = Self::unpack_u64_vec(rest)?;
+                Self::MultiAssetWithdrawAllTokenTypes(MultiAssetWithdrawAllTokenTypes {
+                    pool_token_amount,
+                    minimum_token_amounts,
+                })
+            }
+            19 => {
+                let (source_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (minimum_pool_token_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::MultiAssetDepositSingleTokenTypeExactAmountIn(
+                    DepositSingleTokenTypeExactAmountIn {
+                        source_token_amount,
+                        minimum_pool_token_amount,
+                    },
+                )
+            }
+            20 => {
+                let (destination_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (maximum_pool_token_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::MultiAssetWithdrawSingleTokenTypeExactAmountOut(
+                    WithdrawSingleTokenTypeExactAmountOut {
+                        destination_token_amount,
+                        maximum_pool_token_amount,
+                    },
+                )
+            }
             _ => return Err(SwapError::InvalidInstruction.into()),
         })
     }
@@ -264,6 +722,67 @@ impl SwapInstruction {
         }
     }
 
+    /// Unpacks a list of `u64`, prefixed by its length as a `u8`
+    fn unpack_u64_vec(input: &[u8]) -> Result<(Vec<u64>, &[u8]), ProgramError> {
+        let (&count, mut rest) = input.split_first().ok_or(SwapError::InvalidInstruction)?;
+        let mut values = Vec::with_capacity(usize::from(count));
+        for _ in 0..count {
+            let (value, next) = Self::unpack_u64(rest)?;
+            values.push(value);
+            rest = next;
+        }
+        Ok((values, rest))
+    }
+
+    fn pack_u64_vec(buf: &mut Vec<u8>, values: &[u64]) {
+        buf.push(values.len() as u8);
+        for value in values {
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    fn unpack_u16(input: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
+        if input.len() >= 2 {
+            let (amount, rest) = input.split_at(2);
+            let amount = amount
+                .get(..2)
+                .and_then(|slice| slice.try_into().ok())
+                .map(u16::from_le_bytes)
+                .ok_or(SwapError::InvalidInstruction)?;
+            Ok((amount, rest))
+        } else {
+            Err(SwapError::InvalidInstruction.into())
+        }
+    }
+
+    fn unpack_i32(input: &[u8]) -> Result<(i32, &[u8]), ProgramError> {
+        if input.len() >= 4 {
+            let (value, rest) = input.split_at(4);
+            let value = value
+                .get(..4)
+                .and_then(|slice| slice.try_into().ok())
+                .map(i32::from_le_bytes)
+                .ok_or(SwapError::InvalidInstruction)?;
+            Ok((value, rest))
+        } else {
+            Err(SwapError::InvalidInstruction.into())
+        }
+    }
+
+    fn unpack_u128(input: &[u8]) -> Result<(u128, &[u8]), ProgramError> {
+        if input.len() >= 16 {
+            let (amount, rest) = input.split_at(16);
+            let amount = amount
+                .get(..16)
+                .and_then(|slice| slice.try_into().ok())
+                .map(u128::from_le_bytes)
+                .ok_or(SwapError::InvalidInstruction)?;
+            Ok((amount, rest))
+        } else {
+            Err(SwapError::InvalidInstruction.into())
+        }
+    }
+
     /// Packs a [SwapInstruction](enum.SwapInstruction.html) into a byte buffer.
     pub fn pack(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(size_of::<Self>());
@@ -323,6 +842,122 @@ impl SwapInstruction {
                 buf.extend_from_slice(&destination_token_amount.to_le_bytes());
                 buf.extend_from_slice(&maximum_pool_token_amount.to_le_bytes());
             }
+            Self::Pause => {
+                buf.push(6);
+            }
+            Self::Resume => {
+                buf.push(7);
+            }
+            Self::SetDynamicFees(dynamic_fees) => {
+                buf.push(8);
+                let mut dynamic_fees_slice = [0u8; DynamicFees::LEN];
+                Pack::pack_into_slice(dynamic_fees, &mut dynamic_fees_slice[..]);
+                buf.extend_from_slice(&dynamic_fees_slice);
+            }
+            Self::InitializeConcentratedPool(InitializeConcentratedPool {
+                fees,
+                tick_spacing,
+                initial_sqrt_price,
+            }) => {
+                buf.push(9);
+                let mut fees_slice = [0u8; Fees::LEN];
+                Pack::pack_into_slice(fees, &mut fees_slice[..]);
+                buf.extend_from_slice(&fees_slice);
+                buf.extend_from_slice(&tick_spacing.to_le_bytes());
+                buf.extend_from_slice(&initial_sqrt_price.to_le_bytes());
+            }
+            Self::OpenPosition(OpenPosition {
+                tick_lower,
+                tick_upper,
+            }) => {
+                buf.push(10);
+                buf.extend_from_slice(&tick_lower.to_le_bytes());
+                buf.extend_from_slice(&tick_upper.to_le_bytes());
+            }
+            Self::IncreaseLiquidity(IncreaseLiquidity {
+                liquidity,
+                maximum_token_a_amount,
+                maximum_token_b_amount,
+            }) => {
+                buf.push(11);
+                buf.extend_from_slice(&liquidity.to_le_bytes());
+                buf.extend_from_slice(&maximum_token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&maximum_token_b_amount.to_le_bytes());
+            }
+            Self::DecreaseLiquidity(DecreaseLiquidity {
+                liquidity,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+            }) => {
+                buf.push(12);
+                buf.extend_from_slice(&liquidity.to_le_bytes());
+                buf.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
+            }
+            Self::SwapConcentrated(Swap {
+                amount_in,
+                minimum_amount_out,
+            }) => {
+                buf.push(13);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+            }
+            Self::CollectProtocolFees => {
+                buf.push(14);
+            }
+            Self::InitializeMultiAssetPool(InitializeMultiAssetPool { fees, curve }) => {
+                buf.push(15);
+                let mut fees_slice = [0u8; Fees::LEN];
+                Pack::pack_into_slice(fees, &mut fees_slice[..]);
+                buf.extend_from_slice(&fees_slice);
+                buf.push(curve.curve_type as u8);
+                buf.extend_from_slice(&curve.amp.to_le_bytes());
+                Self::pack_u64_vec(&mut buf, &curve.weights);
+            }
+            Self::MultiAssetSwap(Swap {
+                amount_in,
+                minimum_amount_out,
+            }) => {
+                buf.push(16);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+            }
+            Self::MultiAssetDepositAllTokenTypes(MultiAssetDepositAllTokenTypes {
+                pool_token_amount,
+                maximum_token_amounts,
+            }) => {
+                buf.push(17);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                Self::pack_u64_vec(&mut buf, maximum_token_amounts);
+            }
+            Self::MultiAssetWithdrawAllTokenTypes(MultiAssetWithdrawAllTokenTypes {
+                pool_token_amount,
+                minimum_token_amounts,
+            }) => {
+                buf.push(18);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                Self::pack_u64_vec(&mut buf, minimum_token_amounts);
+            }
+            Self::MultiAssetDepositSingleTokenTypeExactAmountIn(
+                DepositSingleTokenTypeExactAmountIn {
+                    source_token_amount,
+                    minimum_pool_token_amount,
+                },
+            ) => {
+                buf.push(19);
+                buf.extend_from_slice(&source_token_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_pool_token_amount.to_le_bytes());
+            }
+            Self::MultiAssetWithdrawSingleTokenTypeExactAmountOut(
+                WithdrawSingleTokenTypeExactAmountOut {
+                    destination_token_amount,
+                    maximum_pool_token_amount,
+                },
+            ) => {
+                buf.push(20);
+                buf.extend_from_slice(&destination_token_amount.to_le_bytes());
+                buf.extend_from_slice(&maximum_pool_token_amount.to_le_bytes());
+            }
         }
         buf
     }
@@ -331,7 +966,7 @@ impl SwapInstruction {
 /// Creates an 'initialize' instruction.
 pub fn initialize(
     program_id: &Pubkey,
-    token_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
     swap_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
     token_a_pubkey: &Pubkey,
@@ -339,13 +974,17 @@ pub fn initialize(
     pool_pubkey: &Pubkey,
     fee_pubkey: &Pubkey,
     destination_pubkey: &Pubkey,
+    token_a_mint_pubkey: &Pubkey,
+    token_b_mint_pubkey: &Pubkey,
+    curve_account_pubkey: Option<&Pubkey>,
+    pause_authority_pubkey: Option<&Pubkey>,
     fees: Fees,
     swap_curve: SwapCurve,
 ) -> Result<Instruction, ProgramError> {
     let init_data = SwapInstruction::Initialize(Initialize { fees, swap_curve });
     let data = init_data.pack();
 
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(*swap_pubkey, true),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*token_a_pubkey, false),
@@ -353,8 +992,16 @@ pub fn initialize(
         AccountMeta::new(*pool_pubkey, false),
         AccountMeta::new_readonly(*fee_pubkey, false),
         AccountMeta::new(*destination_pubkey, false),
-        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
     ];
+    if let Some(curve_account_pubkey) = curve_account_pubkey {
+        accounts.push(AccountMeta::new_readonly(*curve_account_pubkey, false));
+    }
+    if let Some(pause_authority_pubkey) = pause_authority_pubkey {
+        accounts.push(AccountMeta::new_readonly(*pause_authority_pubkey, false));
+    }
 
     Ok(Instruction {
         program_id: *program_id,
@@ -366,7 +1013,9 @@ pub fn initialize(
 /// Creates a 'deposit_all_token_types' instruction.
 pub fn deposit_all_token_types(
     program_id: &Pubkey,
-    token_program_id: &Pubkey,
+    token_a_program_id: &Pubkey,
+    token_b_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
     swap_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
     user_transfer_authority_pubkey: &Pubkey,
@@ -376,12 +1025,14 @@ pub fn deposit_all_token_types(
     swap_token_b_pubkey: &Pubkey,
     pool_mint_pubkey: &Pubkey,
     destination_pubkey: &Pubkey,
+    token_a_mint_pubkey: &Pubkey,
+    token_b_mint_pubkey: &Pubkey,
     instruction: DepositAllTokenTypes,
 ) -> Result<Instruction, ProgramError> {
     let data = SwapInstruction::DepositAllTokenTypes(instruction).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*deposit_token_a_pubkey, false),
@@ -390,7 +1041,11 @@ pub fn deposit_all_token_types(
         AccountMeta::new(*swap_token_b_pubkey, false),
         AccountMeta::new(*pool_mint_pubkey, false),
         AccountMeta::new(*destination_pubkey, false),
-        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_a_program_id, false),
+        AccountMeta::new_readonly(*token_b_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
     ];
 
     Ok(Instruction {
@@ -403,7 +1058,9 @@ pub fn deposit_all_token_types(
 /// Creates a 'withdraw_all_token_types' instruction.
 pub fn withdraw_all_token_types(
     program_id: &Pubkey,
-    token_program_id: &Pubkey,
+    token_a_program_id: &Pubkey,
+    token_b_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
     swap_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
     user_transfer_authority_pubkey: &Pubkey,
@@ -414,12 +1071,14 @@ pub fn withdraw_all_token_types(
     swap_token_b_pubkey: &Pubkey,
     destination_token_a_pubkey: &Pubkey,
     destination_token_b_pubkey: &Pubkey,
+    token_a_mint_pubkey: &Pubkey,
+    token_b_mint_pubkey: &Pubkey,
     instruction: WithdrawAllTokenTypes,
 ) -> Result<Instruction, ProgramError> {
     let data = SwapInstruction::WithdrawAllTokenTypes(instruction).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*pool_mint_pubkey, false),
@@ -429,7 +1088,11 @@ pub fn withdraw_all_token_types(
         AccountMeta::new(*destination_token_a_pubkey, false),
         AccountMeta::new(*destination_token_b_pubkey, false),
         AccountMeta::new(*fee_account_pubkey, false),
-        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_a_program_id, false),
+        AccountMeta::new_readonly(*token_b_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
     ];
 
     Ok(Instruction {
@@ -442,7 +1105,8 @@ pub fn withdraw_all_token_types(
 /// Creates a 'deposit_single_token_type_exact_amount_in' instruction.
 pub fn deposit_single_token_type_exact_amount_in(
     program_id: &Pubkey,
-    token_program_id: &Pubkey,
+    source_token_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
     swap_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
     user_transfer_authority_pubkey: &Pubkey,
@@ -451,12 +1115,14 @@ pub fn deposit_single_token_type_exact_amount_in(
     swap_token_b_pubkey: &Pubkey,
     pool_mint_pubkey: &Pubkey,
     destination_pubkey: &Pubkey,
+    source_mint_pubkey: &Pubkey,
+    curve_account_pubkey: Option<&Pubkey>,
     instruction: DepositSingleTokenTypeExactAmountIn,
 ) -> Result<Instruction, ProgramError> {
     let data = SwapInstruction::DepositSingleTokenTypeExactAmountIn(instruction).pack();
 
-    let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*source_token_pubkey, false),
@@ -464,8 +1130,13 @@ pub fn deposit_single_token_type_exact_amount_in(
         AccountMeta::new(*swap_token_b_pubkey, false),
         AccountMeta::new(*pool_mint_pubkey, false),
         AccountMeta::new(*destination_pubkey, false),
-        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(*source_mint_pubkey, false),
+        AccountMeta::new_readonly(*source_token_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
     ];
+    if let Some(curve_account_pubkey) = curve_account_pubkey {
+        accounts.push(AccountMeta::new_readonly(*curve_account_pubkey, false));
+    }
 
     Ok(Instruction {
         program_id: *program_id,
@@ -477,7 +1148,8 @@ pub fn deposit_single_token_type_exact_amount_in(
 /// Creates a 'withdraw_single_token_type_exact_amount_out' instruction.
 pub fn withdraw_single_token_type_exact_amount_out(
     program_id: &Pubkey,
-    token_program_id: &Pubkey,
+    destination_token_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
     swap_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
     user_transfer_authority_pubkey: &Pubkey,
@@ -487,12 +1159,14 @@ pub fn withdraw_single_token_type_exact_amount_out(
     swap_token_a_pubkey: &Pubkey,
     swap_token_b_pubkey: &Pubkey,
     destination_pubkey: &Pubkey,
+    destination_mint_pubkey: &Pubkey,
+    curve_account_pubkey: Option<&Pubkey>,
     instruction: WithdrawSingleTokenTypeExactAmountOut,
 ) -> Result<Instruction, ProgramError> {
     let data = SwapInstruction::WithdrawSingleTokenTypeExactAmountOut(instruction).pack();
 
-    let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*pool_mint_pubkey, false),
@@ -501,8 +1175,13 @@ pub fn withdraw_single_token_type_exact_amount_out(
         AccountMeta::new(*swap_token_b_pubkey, false),
         AccountMeta::new(*destination_pubkey, false),
         AccountMeta::new(*fee_account_pubkey, false),
-        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(*destination_mint_pubkey, false),
+        AccountMeta::new_readonly(*destination_token_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
     ];
+    if let Some(curve_account_pubkey) = curve_account_pubkey {
+        accounts.push(AccountMeta::new_readonly(*curve_account_pubkey, false));
+    }
 
     Ok(Instruction {
         program_id: *program_id,
@@ -514,7 +1193,9 @@ pub fn withdraw_single_token_type_exact_amount_out(
 /// Creates a 'swap' instruction.
 pub fn swap(
     program_id: &Pubkey,
-    token_program_id: &Pubkey,
+    source_token_program_id: &Pubkey,
+    destination_token_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
     swap_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
     user_transfer_authority_pubkey: &Pubkey,
@@ -524,13 +1205,16 @@ pub fn swap(
     destination_pubkey: &Pubkey,
     pool_mint_pubkey: &Pubkey,
     pool_fee_pubkey: &Pubkey,
+    source_mint_pubkey: &Pubkey,
+    destination_mint_pubkey: &Pubkey,
+    curve_account_pubkey: Option<&Pubkey>,
     host_fee_pubkey: Option<&Pubkey>,
     instruction: Swap,
 ) -> Result<Instruction, ProgramError> {
     let data = SwapInstruction::Swap(instruction).pack();
 
     let mut accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*source_pubkey, false),
@@ -539,8 +1223,15 @@ pub fn swap(
         AccountMeta::new(*destination_pubkey, false),
         AccountMeta::new(*pool_mint_pubkey, false),
         AccountMeta::new(*pool_fee_pubkey, false),
-        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(*source_mint_pubkey, false),
+        AccountMeta::new_readonly(*destination_mint_pubkey, false),
+        AccountMeta::new_readonly(*source_token_program_id, false),
+        AccountMeta::new_readonly(*destination_token_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
     ];
+    if let Some(curve_account_pubkey) = curve_account_pubkey {
+        accounts.push(AccountMeta::new_readonly(*curve_account_pubkey, false));
+    }
     if let Some(host_fee_pubkey) = host_fee_pubkey {
         accounts.push(AccountMeta::new(*host_fee_pubkey, false));
     }
@@ -552,15 +1243,569 @@ pub fn swap(
     })
 }
 
-/// Unpacks a reference from a bytes buffer.
-/// TODO actually pack / unpack instead of relying on normal memory layout.
-pub fn unpack<T>(input: &[u8]) -> Result<&T, ProgramError> {
-    if input.len() < size_of::<u8>() + size_of::<T>() {
-        return Err(ProgramError::InvalidAccountData);
-    }
-    #[allow(clippy::cast_ptr_alignment)]
-    let val: &T = unsafe { &*(&input[1] as *const u8 as *const T) };
-    Ok(val)
+/// Creates a 'pause' instruction.
+pub fn pause(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    pause_authority_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Pause.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*pause_authority_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'resume' instruction.
+pub fn resume(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    pause_authority_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Resume.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*pause_authority_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set dynamic fees' instruction.
+pub fn set_dynamic_fees(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    pool_fee_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    dynamic_fees: DynamicFees,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::SetDynamicFees(dynamic_fees).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*pool_fee_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'initialize concentrated pool' instruction.
+pub fn initialize_concentrated_pool(
+    program_id: &Pubkey,
+    pool_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    token_a_pubkey: &Pubkey,
+    token_b_pubkey: &Pubkey,
+    token_a_mint_pubkey: &Pubkey,
+    token_b_mint_pubkey: &Pubkey,
+    protocol_fee_owner_pubkey: &Pubkey,
+    fees: Fees,
+    tick_spacing: u16,
+    initial_sqrt_price: u128,
+) -> Result<Instruction, ProgramError> {
+    let init_data = SwapInstruction::InitializeConcentratedPool(InitializeConcentratedPool {
+        fees,
+        tick_spacing,
+        initial_sqrt_price,
+    });
+    let data = init_data.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*pool_pubkey, true),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*token_a_pubkey, false),
+        AccountMeta::new_readonly(*token_b_pubkey, false),
+        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
+        AccountMeta::new_readonly(*protocol_fee_owner_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'open position' instruction.
+pub fn open_position(
+    program_id: &Pubkey,
+    pool_pubkey: &Pubkey,
+    position_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    instruction: OpenPosition,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::OpenPosition(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*pool_pubkey, false),
+        AccountMeta::new(*position_pubkey, true),
+        AccountMeta::new_readonly(*owner_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'increase liquidity' instruction.
+pub fn increase_liquidity(
+    program_id: &Pubkey,
+    token_a_program_id: &Pubkey,
+    token_b_program_id: &Pubkey,
+    pool_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    position_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    deposit_token_a_pubkey: &Pubkey,
+    deposit_token_b_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    token_a_mint_pubkey: &Pubkey,
+    token_b_mint_pubkey: &Pubkey,
+    instruction: IncreaseLiquidity,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::IncreaseLiquidity(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*pool_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*position_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, true),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*deposit_token_a_pubkey, false),
+        AccountMeta::new(*deposit_token_b_pubkey, false),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_a_program_id, false),
+        AccountMeta::new_readonly(*token_b_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'decrease liquidity' instruction.
+pub fn decrease_liquidity(
+    program_id: &Pubkey,
+    token_a_program_id: &Pubkey,
+    token_b_program_id: &Pubkey,
+    pool_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    position_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    destination_token_a_pubkey: &Pubkey,
+    destination_token_b_pubkey: &Pubkey,
+    token_a_mint_pubkey: &Pubkey,
+    token_b_mint_pubkey: &Pubkey,
+    instruction: DecreaseLiquidity,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::DecreaseLiquidity(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*pool_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*position_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, true),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*destination_token_a_pubkey, false),
+        AccountMeta::new(*destination_token_b_pubkey, false),
+        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_a_program_id, false),
+        AccountMeta::new_readonly(*token_b_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'swap concentrated' instruction.
+pub fn swap_concentrated(
+    program_id: &Pubkey,
+    source_token_program_id: &Pubkey,
+    destination_token_program_id: &Pubkey,
+    pool_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_source_pubkey: &Pubkey,
+    swap_destination_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    source_mint_pubkey: &Pubkey,
+    destination_mint_pubkey: &Pubkey,
+    instruction: Swap,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::SwapConcentrated(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*pool_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*swap_source_pubkey, false),
+        AccountMeta::new(*swap_destination_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*source_mint_pubkey, false),
+        AccountMeta::new_readonly(*destination_mint_pubkey, false),
+        AccountMeta::new_readonly(*source_token_program_id, false),
+        AccountMeta::new_readonly(*destination_token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'collect protocol fees' instruction.
+pub fn collect_protocol_fees(
+    program_id: &Pubkey,
+    token_a_program_id: &Pubkey,
+    token_b_program_id: &Pubkey,
+    pool_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    protocol_fee_owner_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    destination_token_a_pubkey: &Pubkey,
+    destination_token_b_pubkey: &Pubkey,
+    token_a_mint_pubkey: &Pubkey,
+    token_b_mint_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CollectProtocolFees.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*pool_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*protocol_fee_owner_pubkey, true),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*destination_token_a_pubkey, false),
+        AccountMeta::new(*destination_token_b_pubkey, false),
+        AccountMeta::new_readonly(*token_a_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_b_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_a_program_id, false),
+        AccountMeta::new_readonly(*token_b_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'initialize_multi_asset_pool' instruction.
+pub fn initialize_multi_asset_pool(
+    program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    pool_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    fee_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    token_pubkeys: &[Pubkey],
+    token_mint_pubkeys: &[Pubkey],
+    fees: Fees,
+    curve: MultiAssetCurve,
+) -> Result<Instruction, ProgramError> {
+    if token_pubkeys.len() != curve.token_count() || token_mint_pubkeys.len() != curve.token_count()
+    {
+        return Err(SwapError::InvalidInstruction.into());
+    }
+    let data =
+        SwapInstruction::InitializeMultiAssetPool(InitializeMultiAssetPool { fees, curve }).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*pool_pubkey, true),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new_readonly(*fee_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+    ];
+    for (token_pubkey, token_mint_pubkey) in token_pubkeys.iter().zip(token_mint_pubkeys) {
+        accounts.push(AccountMeta::new_readonly(*token_pubkey, false));
+        accounts.push(AccountMeta::new_readonly(*token_mint_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'multi_asset_swap' instruction. The token accounts of the pool
+/// are given in the order of the pool.
+pub fn multi_asset_swap(
+    program_id: &Pubkey,
+    source_token_program_id: &Pubkey,
+    destination_token_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    pool_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    pool_fee_pubkey: &Pubkey,
+    source_mint_pubkey: &Pubkey,
+    destination_mint_pubkey: &Pubkey,
+    swap_token_pubkeys: &[Pubkey],
+    instruction: Swap,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::MultiAssetSwap(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*pool_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*pool_fee_pubkey, false),
+        AccountMeta::new_readonly(*source_mint_pubkey, false),
+        AccountMeta::new_readonly(*destination_mint_pubkey, false),
+        AccountMeta::new_readonly(*source_token_program_id, false),
+        AccountMeta::new_readonly(*destination_token_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+    ];
+    for swap_token_pubkey in swap_token_pubkeys {
+        accounts.push(AccountMeta::new(*swap_token_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'multi_asset_deposit_all_token_types' instruction. The token
+/// accounts, mints and programs are given in the order of the pool.
+pub fn multi_asset_deposit_all_token_types(
+    program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    pool_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    source_pubkeys: &[Pubkey],
+    swap_token_pubkeys: &[Pubkey],
+    token_mint_pubkeys: &[Pubkey],
+    token_program_ids: &[Pubkey],
+    instruction: MultiAssetDepositAllTokenTypes,
+) -> Result<Instruction, ProgramError> {
+    let token_count = instruction.maximum_token_amounts.len();
+    if source_pubkeys.len() != token_count
+        || swap_token_pubkeys.len() != token_count
+        || token_mint_pubkeys.len() != token_count
+        || token_program_ids.len() != token_count
+    {
+        return Err(SwapError::InvalidInstruction.into());
+    }
+    let data = SwapInstruction::MultiAssetDepositAllTokenTypes(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*pool_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+    ];
+    for i in 0..token_count {
+        accounts.push(AccountMeta::new(source_pubkeys[i], false));
+        accounts.push(AccountMeta::new(swap_token_pubkeys[i], false));
+        accounts.push(AccountMeta::new_readonly(token_mint_pubkeys[i], false));
+        accounts.push(AccountMeta::new_readonly(token_program_ids[i], false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'multi_asset_withdraw_all_token_types' instruction. The token
+/// accounts, mints and programs are given in the order of the pool.
+pub fn multi_asset_withdraw_all_token_types(
+    program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    pool_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    fee_account_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_token_pubkeys: &[Pubkey],
+    destination_pubkeys: &[Pubkey],
+    token_mint_pubkeys: &[Pubkey],
+    token_program_ids: &[Pubkey],
+    instruction: MultiAssetWithdrawAllTokenTypes,
+) -> Result<Instruction, ProgramError> {
+    let token_count = instruction.minimum_token_amounts.len();
+    if swap_token_pubkeys.len() != token_count
+        || destination_pubkeys.len() != token_count
+        || token_mint_pubkeys.len() != token_count
+        || token_program_ids.len() != token_count
+    {
+        return Err(SwapError::InvalidInstruction.into());
+    }
+    let data = SwapInstruction::MultiAssetWithdrawAllTokenTypes(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*pool_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*fee_account_pubkey, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+    ];
+    for i in 0..token_count {
+        accounts.push(AccountMeta::new(swap_token_pubkeys[i], false));
+        accounts.push(AccountMeta::new(destination_pubkeys[i], false));
+        accounts.push(AccountMeta::new_readonly(token_mint_pubkeys[i], false));
+        accounts.push(AccountMeta::new_readonly(token_program_ids[i], false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'multi_asset_deposit_single_token_type_exact_amount_in'
+/// instruction. The token accounts of the pool are given in the order of the
+/// pool.
+pub fn multi_asset_deposit_single_token_type_exact_amount_in(
+    program_id: &Pubkey,
+    source_token_program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    pool_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    source_mint_pubkey: &Pubkey,
+    swap_token_pubkeys: &[Pubkey],
+    instruction: DepositSingleTokenTypeExactAmountIn,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::MultiAssetDepositSingleTokenTypeExactAmountIn(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*pool_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*source_mint_pubkey, false),
+        AccountMeta::new_readonly(*source_token_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+    ];
+    for swap_token_pubkey in swap_token_pubkeys {
+        accounts.push(AccountMeta::new(*swap_token_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'multi_asset_withdraw_single_token_type_exact_amount_out'
+/// instruction. The token accounts of the pool are given in the order of the
+/// pool.
+pub fn multi_asset_withdraw_single_token_type_exact_amount_out(
+    program_id: &Pubkey,
+    pool_token_program_id: &Pubkey,
+    destination_token_program_id: &Pubkey,
+    pool_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    fee_account_pubkey: &Pubkey,
+    destination_mint_pubkey: &Pubkey,
+    swap_token_pubkeys: &[Pubkey],
+    instruction: WithdrawSingleTokenTypeExactAmountOut,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::MultiAssetWithdrawSingleTokenTypeExactAmountOut(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*pool_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*fee_account_pubkey, false),
+        AccountMeta::new_readonly(*destination_mint_pubkey, false),
+        AccountMeta::new_readonly(*destination_token_program_id, false),
+        AccountMeta::new_readonly(*pool_token_program_id, false),
+    ];
+    for swap_token_pubkey in swap_token_pubkeys {
+        accounts.push(AccountMeta::new(*swap_token_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Unpacks a reference from a bytes buffer.
+/// TODO actually pack / unpack instead of relying on normal memory layout.
+pub fn unpack<T>(input: &[u8]) -> Result<&T, ProgramError> {
+    if input.len() < size_of::<u8>() + size_of::<T>() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    #[allow(clippy::cast_ptr_alignment)]
+    let val: &T = unsafe { &*(&input[1] as *const u8 as *const T) };
+    Ok(val)
 }
 
 #[cfg(test)]
@@ -709,4 +1954,275 @@ mod tests {
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
     }
+
+    #[test]
+    fn pack_pause_resume() {
+        let check = SwapInstruction::Pause;
+        let packed = check.pack();
+        let expect = vec![6];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::Resume;
+        let packed = check.pack();
+        let expect = vec![7];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_set_dynamic_fees() {
+        let decay_half_life_slots: u64 = 150;
+        let volatility_fee_numerator: u64 = 1;
+        let volatility_fee_denominator: u64 = 10;
+        let max_volatility_fee_numerator: u64 = 20;
+        let check = SwapInstruction::SetDynamicFees(DynamicFees {
+            decay_half_life_slots,
+            volatility_fee_numerator,
+            volatility_fee_denominator,
+            max_volatility_fee_numerator,
+        });
+        let packed = check.pack();
+        let mut expect = vec![8];
+        expect.extend_from_slice(&decay_half_life_slots.to_le_bytes());
+        expect.extend_from_slice(&volatility_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&volatility_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&max_volatility_fee_numerator.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_initialize_concentrated_pool() {
+        let trade_fee_numerator: u64 = 1;
+        let trade_fee_denominator: u64 = 4;
+        let owner_trade_fee_numerator: u64 = 2;
+        let owner_trade_fee_denominator: u64 = 5;
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            ..Fees::default()
+        };
+        let tick_spacing: u16 = 64;
+        let initial_sqrt_price: u128 = 1 << 64;
+        let check = SwapInstruction::InitializeConcentratedPool(InitializeConcentratedPool {
+            fees,
+            tick_spacing,
+            initial_sqrt_price,
+        });
+        let packed = check.pack();
+        let mut expect = vec![9];
+        expect.extend_from_slice(&trade_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&trade_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&owner_trade_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&owner_trade_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&[0u8; 32]);
+        expect.extend_from_slice(&tick_spacing.to_le_bytes());
+        expect.extend_from_slice(&initial_sqrt_price.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_positions() {
+        let tick_lower: i32 = -120;
+        let tick_upper: i32 = 60;
+        let check = SwapInstruction::OpenPosition(OpenPosition {
+            tick_lower,
+            tick_upper,
+        });
+        let packed = check.pack();
+        let mut expect = vec![10];
+        expect.extend_from_slice(&tick_lower.to_le_bytes());
+        expect.extend_from_slice(&tick_upper.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let liquidity: u128 = 1 << 80;
+        let maximum_token_a_amount: u64 = 3;
+        let maximum_token_b_amount: u64 = 4;
+        let check = SwapInstruction::IncreaseLiquidity(IncreaseLiquidity {
+            liquidity,
+            maximum_token_a_amount,
+            maximum_token_b_amount,
+        });
+        let packed = check.pack();
+        let mut expect = vec![11];
+        expect.extend_from_slice(&liquidity.to_le_bytes());
+        expect.extend_from_slice(&maximum_token_a_amount.to_le_bytes());
+        expect.extend_from_slice(&maximum_token_b_amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let minimum_token_a_amount: u64 = 5;
+        let minimum_token_b_amount: u64 = 6;
+        let check = SwapInstruction::DecreaseLiquidity(DecreaseLiquidity {
+            liquidity,
+            minimum_token_a_amount,
+            minimum_token_b_amount,
+        });
+        let packed = check.pack();
+        let mut expect = vec![12];
+        expect.extend_from_slice(&liquidity.to_le_bytes());
+        expect.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
+        expect.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_swap_concentrated() {
+        let amount_in: u64 = 2;
+        let minimum_amount_out: u64 = 10;
+        let check = SwapInstruction::SwapConcentrated(Swap {
+            amount_in,
+            minimum_amount_out,
+        });
+        let packed = check.pack();
+        let mut expect = vec![13];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::CollectProtocolFees;
+        let packed = check.pack();
+        let expect = vec![14];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_initialize_multi_asset_pool() {
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 4,
+            owner_trade_fee_numerator: 3,
+            owner_trade_fee_denominator: 10,
+            owner_withdraw_fee_numerator: 2,
+            owner_withdraw_fee_denominator: 7,
+            host_fee_numerator: 5,
+            host_fee_denominator: 20,
+        };
+        let check = SwapInstruction::InitializeMultiAssetPool(InitializeMultiAssetPool {
+            fees: fees.clone(),
+            curve: MultiAssetCurve {
+                curve_type: MultiAssetCurveType::Weighted,
+                amp: 0,
+                weights: vec![50, 30, 20],
+            },
+        });
+        let packed = check.pack();
+        let mut expect = vec![15];
+        let mut fees_slice = [0u8; Fees::LEN];
+        Pack::pack_into_slice(&fees, &mut fees_slice[..]);
+        expect.extend_from_slice(&fees_slice);
+        expect.push(0);
+        expect.extend_from_slice(&0u64.to_le_bytes());
+        expect.push(3);
+        expect.extend_from_slice(&50u64.to_le_bytes());
+        expect.extend_from_slice(&30u64.to_le_bytes());
+        expect.extend_from_slice(&20u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        // missing weights
+        expect.truncate(expect.len() - 8);
+        assert_eq!(
+            SwapInstruction::unpack(&expect),
+            Err(SwapError::InvalidInstruction.into())
+        );
+        // unknown curve type
+        expect[1 + Fees::LEN] = 2;
+        assert_eq!(
+            SwapInstruction::unpack(&expect),
+            Err(SwapError::InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    fn pack_multi_asset_instructions() {
+        let check = SwapInstruction::MultiAssetSwap(Swap {
+            amount_in: 2,
+            minimum_amount_out: 10,
+        });
+        let packed = check.pack();
+        let mut expect = vec![16];
+        expect.extend_from_slice(&2u64.to_le_bytes());
+        expect.extend_from_slice(&10u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check =
+            SwapInstruction::MultiAssetDepositAllTokenTypes(MultiAssetDepositAllTokenTypes {
+                pool_token_amount: 5,
+                maximum_token_amounts: vec![10, 20, 30, 40],
+            });
+        let packed = check.pack();
+        let mut expect = vec![17];
+        expect.extend_from_slice(&5u64.to_le_bytes());
+        expect.push(4);
+        for amount in [10u64, 20, 30, 40] {
+            expect.extend_from_slice(&amount.to_le_bytes());
+        }
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check =
+            SwapInstruction::MultiAssetWithdrawAllTokenTypes(MultiAssetWithdrawAllTokenTypes {
+                pool_token_amount: 1212438012089,
+                minimum_token_amounts: vec![102198761982612, 2011239855213],
+            });
+        let packed = check.pack();
+        let mut expect = vec![18];
+        expect.extend_from_slice(&1212438012089u64.to_le_bytes());
+        expect.push(2);
+        expect.extend_from_slice(&102198761982612u64.to_le_bytes());
+        expect.extend_from_slice(&2011239855213u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::MultiAssetDepositSingleTokenTypeExactAmountIn(
+            DepositSingleTokenTypeExactAmountIn {
+                source_token_amount: 102198761982612,
+                minimum_pool_token_amount: 101098761982612,
+            },
+        );
+        let packed = check.pack();
+        let mut expect = vec![19];
+        expect.extend_from_slice(&102198761982612u64.to_le_bytes());
+        expect.extend_from_slice(&101098761982612u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::MultiAssetWithdrawSingleTokenTypeExactAmountOut(
+            WithdrawSingleTokenTypeExactAmountOut {
+                destination_token_amount: 1212438012089,
+                maximum_pool_token_amount: 1212438012089,
+            },
+        );
+        let packed = check.pack();
+        let mut expect = vec![20];
+        expect.extend_from_slice(&1212438012089u64.to_le_bytes());
+        expect.extend_from_slice(&1212438012089u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
 }
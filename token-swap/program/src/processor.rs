@@ -3,20 +3,33 @@
 use crate::constraints::{SwapConstraints, SWAP_CONSTRAINTS};
 use crate::{
     curve::{
-        base::SwapCurve,
-        calculator::{RoundDirection, TradeDirection},
-        fees::Fees,
+        base::{CurveType, SwapCurve},
+        calculator::{RoundDirection, TradeDirection, INITIAL_SWAP_POOL_AMOUNT},
+        concentrated_liquidity::{tick_at_sqrt_price, total_trade_fee},
+        fees::{price_impact, DynamicFees, Fees},
+        multi_asset::MultiAssetCurve,
+        proactive_market_making::ProactiveMarketMakingCurve,
+        stake_pool_stable::StakePoolStableCurve,
     },
     error::SwapError,
     instruction::{
-        DepositAllTokenTypes, DepositSingleTokenTypeExactAmountIn, Initialize, Swap,
+        DecreaseLiquidity, DepositAllTokenTypes, DepositSingleTokenTypeExactAmountIn,
+        IncreaseLiquidity, Initialize, InitializeConcentratedPool, InitializeMultiAssetPool,
+        MultiAssetDepositAllTokenTypes, MultiAssetWithdrawAllTokenTypes, OpenPosition, Swap,
         SwapInstruction, WithdrawAllTokenTypes, WithdrawSingleTokenTypeExactAmountOut,
     },
-    state::{SwapState, SwapV1, SwapVersion},
+    pyth,
+    state::{
+        is_tagged_account, ConcentratedPool, MultiAssetPool, MultiAssetToken, Position, SwapState,
+        SwapV2, SwapV3, SwapVersion,
+    },
+    twap::PriceAccumulator,
 };
 use num_traits::FromPrimitive;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    borsh::try_from_slice_unchecked,
+    clock::Clock,
     decode_error::DecodeError,
     entrypoint::ProgramResult,
     msg,
@@ -25,38 +38,344 @@ use solana_program::{
     program_option::COption,
     program_pack::Pack,
     pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_stake_pool::state::StakePool;
+use spl_token_2022::{
+    check_spl_token_program_account,
+    extension::{
+        memo_transfer::MemoTransfer,
+        mint_close_authority::MintCloseAuthority,
+        non_transferable::NonTransferable,
+        transfer_fee::{TransferFee, TransferFeeConfig, MAX_FEE_BASIS_POINTS},
+        StateWithExtensions,
+    },
+    state::{Account, Mint},
+};
+use std::{
+    convert::{TryFrom, TryInto},
+    mem::size_of,
+    sync::Arc,
 };
-use std::convert::TryInto;
 
 /// Program state handler.
 pub struct Processor {}
 impl Processor {
-    /// Unpacks a spl_token `Account`.
+    /// Unpacks a spl_token or spl_token_2022 `Account`.
     pub fn unpack_token_account(
         account_info: &AccountInfo,
         token_program_id: &Pubkey,
-    ) -> Result<spl_token::state::Account, SwapError> {
+    ) -> Result<Account, SwapError> {
         if account_info.owner != token_program_id {
             Err(SwapError::IncorrectTokenProgramId)
         } else {
-            spl_token::state::Account::unpack(&account_info.data.borrow())
+            StateWithExtensions::<Account>::unpack(&account_info.data.borrow())
+                .map(|account| account.base)
                 .map_err(|_| SwapError::ExpectedAccount)
         }
     }
 
-    /// Unpacks a spl_token `Mint`.
+    /// Unpacks a spl_token or spl_token_2022 `Mint`.
     pub fn unpack_mint(
         account_info: &AccountInfo,
         token_program_id: &Pubkey,
-    ) -> Result<spl_token::state::Mint, SwapError> {
+    ) -> Result<Mint, SwapError> {
         if account_info.owner != token_program_id {
             Err(SwapError::IncorrectTokenProgramId)
         } else {
-            spl_token::state::Mint::unpack(&account_info.data.borrow())
+            StateWithExtensions::<Mint>::unpack(&account_info.data.borrow())
+                .map(|mint| mint.base)
                 .map_err(|_| SwapError::ExpectedMint)
         }
     }
 
+    /// Checks that the mint of token A or B matches the swap, and that it is
+    /// owned by the given token program, which may be either spl_token or
+    /// spl_token_2022.
+    pub fn check_token_mint(
+        mint_info: &AccountInfo,
+        token_program_info: &AccountInfo,
+        expected_mint: &Pubkey,
+    ) -> ProgramResult {
+        if *mint_info.key != *expected_mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+        check_spl_token_program_account(token_program_info.key)
+            .map_err(|_| SwapError::IncorrectTokenProgramId)?;
+        if mint_info.owner != token_program_info.key {
+            return Err(SwapError::IncorrectTokenProgramId.into());
+        }
+        Ok(())
+    }
+
+    /// Checks that a token A or B mint has no extension preventing the swap
+    /// from moving its tokens.
+    fn check_token_mint_extensions(mint_info: &AccountInfo) -> ProgramResult {
+        let mint_data = mint_info.data.borrow();
+        let mint =
+            StateWithExtensions::<Mint>::unpack(&mint_data).map_err(|_| SwapError::ExpectedMint)?;
+        if mint.get_extension::<NonTransferable>().is_ok() {
+            return Err(SwapError::UnsupportedTokenExtension.into());
+        }
+        Ok(())
+    }
+
+    /// Checks that the pool mint has no extension changing the amounts of
+    /// pool tokens moved by the swap, or allowing it to be closed.
+    fn check_pool_mint_extensions(pool_mint_info: &AccountInfo) -> ProgramResult {
+        let pool_mint_data = pool_mint_info.data.borrow();
+        let pool_mint = StateWithExtensions::<Mint>::unpack(&pool_mint_data)
+            .map_err(|_| SwapError::ExpectedMint)?;
+        if pool_mint.get_extension::<NonTransferable>().is_ok()
+            || pool_mint.get_extension::<TransferFeeConfig>().is_ok()
+        {
+            return Err(SwapError::UnsupportedTokenExtension.into());
+        }
+        if let Ok(mint_close_authority) = pool_mint.get_extension::<MintCloseAuthority>() {
+            if Option::<Pubkey>::from(mint_close_authority.close_authority).is_some() {
+                return Err(SwapError::InvalidCloseAuthority.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a token account receiving tokens from users does not
+    /// require transfers to carry a memo.
+    fn check_token_account_extensions(account_info: &AccountInfo) -> ProgramResult {
+        let account_data = account_info.data.borrow();
+        let account = StateWithExtensions::<Account>::unpack(&account_data)
+            .map_err(|_| SwapError::ExpectedAccount)?;
+        if let Ok(memo_transfer) = account.get_extension::<MemoTransfer>() {
+            if bool::from(memo_transfer.require_incoming_transfer_memos) {
+                return Err(SwapError::UnsupportedTokenExtension.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets the transfer fee charged by a spl_token_2022 mint in the current
+    /// epoch, if any.
+    fn epoch_transfer_fee(mint_info: &AccountInfo) -> Result<Option<TransferFee>, ProgramError> {
+        let mint_data = mint_info.data.borrow();
+        let mint =
+            StateWithExtensions::<Mint>::unpack(&mint_data).map_err(|_| SwapError::ExpectedMint)?;
+        if let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() {
+            let epoch = Clock::get()?.epoch;
+            Ok(Some(*transfer_fee_config.get_epoch_fee(epoch)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Calculates the amount received by the destination of a transfer,
+    /// after the transfer fee of the mint is withheld.
+    pub fn amount_after_transfer_fee(
+        mint_info: &AccountInfo,
+        amount: u64,
+    ) -> Result<u64, ProgramError> {
+        match Self::epoch_transfer_fee(mint_info)? {
+            Some(transfer_fee) => {
+                let fee = transfer_fee
+                    .calculate(amount)
+                    .ok_or(SwapError::FeeCalculationFailure)?;
+                Ok(amount
+                    .checked_sub(fee)
+                    .ok_or(SwapError::FeeCalculationFailure)?)
+            }
+            None => Ok(amount),
+        }
+    }
+
+    /// Calculates the amount to transfer for the destination to receive the
+    /// given amount, once the transfer fee of the mint is withheld.
+    pub fn amount_before_transfer_fee(
+        mint_info: &AccountInfo,
+        amount: u64,
+    ) -> Result<u64, ProgramError> {
+        match Self::epoch_transfer_fee(mint_info)? {
+            Some(transfer_fee) => Ok(
+                pre_fee_amount(&transfer_fee, amount).ok_or(SwapError::FeeCalculationFailure)?
+            ),
+            None => Ok(amount),
+        }
+    }
+
+    /// Unpacks a spl_stake_pool `StakePool`, checking that it mints the given
+    /// pool tokens and that its exchange rate is up to date.
+    pub fn unpack_stake_pool(
+        stake_pool_info: &AccountInfo,
+        pool_mint: &Pubkey,
+    ) -> Result<StakePool, ProgramError> {
+        if *stake_pool_info.owner != spl_stake_pool::id() {
+            return Err(SwapError::IncorrectStakePool.into());
+        }
+        let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_info.data.borrow())
+            .map_err(|_| SwapError::IncorrectStakePool)?;
+        if !stake_pool.is_valid() || stake_pool.pool_mint != *pool_mint {
+            return Err(SwapError::IncorrectStakePool.into());
+        }
+        if stake_pool.last_update_epoch < Clock::get()?.epoch {
+            return Err(SwapError::StaleStakePool.into());
+        }
+        Ok(stake_pool)
+    }
+
+    /// Reads the aggregate price of a pyth price account, checking that it is
+    /// trading and has been published recently.
+    pub fn get_oracle_price(oracle_info: &AccountInfo) -> Result<(u64, i32), ProgramError> {
+        const STALE_AFTER_SLOTS_ELAPSED: u64 = 5;
+
+        let oracle_data = oracle_info.try_borrow_data()?;
+        if oracle_data.len() < size_of::<pyth::Price>() {
+            return Err(SwapError::InvalidOracle.into());
+        }
+        let oracle_price = pyth::load::<pyth::Price>(&oracle_data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if oracle_price.magic != pyth::MAGIC
+            || oracle_price.ver != pyth::VERSION_2
+            || oracle_price.atype != pyth::AccountType::Price as u32
+            || oracle_price.ptype != pyth::PriceType::Price
+            || oracle_price.agg.status != pyth::PriceStatus::Trading
+        {
+            return Err(SwapError::InvalidOracle.into());
+        }
+
+        let slots_elapsed = Clock::get()?
+            .slot
+            .checked_sub(oracle_price.valid_slot)
+            .ok_or(SwapError::InvalidOracle)?;
+        if slots_elapsed >= STALE_AFTER_SLOTS_ELAPSED {
+            return Err(SwapError::StaleOracle.into());
+        }
+
+        let price = oracle_price
+            .agg
+            .price
+            .try_into()
+            .map_err(|_| SwapError::InvalidOracle)?;
+        Ok((price, oracle_price.expo))
+    }
+
+    /// Calculates the fees of a trade, raising the trade fee with the
+    /// volatility accumulator decayed to the current slot.  Also returns the
+    /// decayed volatility and the current slot, both zero if dynamic fees are
+    /// disabled.
+    pub fn dynamic_trade_fees(
+        token_swap: &dyn SwapState,
+    ) -> Result<(Fees, u64, u64), ProgramError> {
+        let dynamic_fees = token_swap.dynamic_fees();
+        if !dynamic_fees.is_enabled() {
+            return Ok((token_swap.fees().clone(), 0, 0));
+        }
+        let slot = Clock::get()?.slot;
+        let volatility = dynamic_fees.decay_volatility(
+            token_swap.volatility(),
+            slot.saturating_sub(token_swap.volatility_update_slot()),
+        );
+        let fees = dynamic_fees
+            .trade_fees(token_swap.fees(), volatility)
+            .ok_or(SwapError::FeeCalculationFailure)?;
+        Ok((fees, volatility, slot))
+    }
+
+    /// Adds the price impact of a trade to the volatility accumulator, after
+    /// decaying it to `slot` with `dynamic_trade_fees`
+    pub fn update_volatility(
+        swap_info: &AccountInfo,
+        volatility: u64,
+        slot: u64,
+        price_impact: u64,
+    ) -> ProgramResult {
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        token_swap.volatility = volatility.saturating_add(price_impact);
+        token_swap.volatility_update_slot = slot;
+        SwapVersion::pack_latest(token_swap, &mut swap_info.data.borrow_mut())
+    }
+
+    /// Accumulates the prices given by the swap token amounts from before the
+    /// instruction up to the current time, if the swap holds cumulative prices
+    pub fn accumulate_prices(
+        swap_info: &AccountInfo,
+        token_swap: &dyn SwapState,
+        token_a_amount: u64,
+        token_b_amount: u64,
+    ) -> ProgramResult {
+        if token_swap.price_accumulator().is_none() {
+            return Ok(());
+        }
+        let timestamp = Clock::get()?.unix_timestamp;
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        token_swap
+            .price_accumulator
+            .accumulate(token_a_amount, token_b_amount, timestamp);
+        SwapVersion::pack_latest(token_swap, &mut swap_info.data.borrow_mut())
+    }
+
+    /// Gets the next account if the curve requires an account to price
+    /// the swap, such as the stake pool account for the stake pool stable
+    /// curve or the oracle for the proactive market making curve.
+    pub fn next_curve_account_info<'a, 'b, I: Iterator<Item = &'a AccountInfo<'b>>>(
+        swap_curve: &SwapCurve,
+        account_info_iter: &mut I,
+    ) -> Result<Option<I::Item>, ProgramError> {
+        match swap_curve.curve_type {
+            CurveType::StakePoolStable | CurveType::ProactiveMarketMaking => {
+                Ok(Some(next_account_info(account_info_iter)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Refreshes the parameters of curves priced from another account.
+    /// Returns `None` if the given curve can be used as is.
+    ///
+    /// The oracle is the one stored in the swap, or the provided one on
+    /// initialization.
+    pub fn refresh_swap_curve(
+        swap_curve: &SwapCurve,
+        token_a_mint: &Pubkey,
+        oracle: Option<&Pubkey>,
+        curve_account_info: Option<&AccountInfo>,
+    ) -> Result<Option<SwapCurve>, ProgramError> {
+        match (swap_curve.curve_type, curve_account_info) {
+            (CurveType::StakePoolStable, Some(stake_pool_info)) => {
+                let stake_pool = Self::unpack_stake_pool(stake_pool_info, token_a_mint)?;
+                let mut packed_curve = [0u8; SwapCurve::LEN];
+                swap_curve.pack_into_slice(&mut packed_curve);
+                let calculator = StakePoolStableCurve::unpack_from_slice(&packed_curve[1..])?;
+                Ok(Some(SwapCurve {
+                    curve_type: swap_curve.curve_type,
+                    calculator: Arc::new(StakePoolStableCurve {
+                        total_lamports: stake_pool.total_lamports,
+                        pool_token_supply: stake_pool.pool_token_supply,
+                        ..calculator
+                    }),
+                }))
+            }
+            (CurveType::ProactiveMarketMaking, Some(oracle_info)) => {
+                if oracle != Some(oracle_info.key) {
+                    return Err(SwapError::IncorrectOracle.into());
+                }
+                let (oracle_price, oracle_exponent) = Self::get_oracle_price(oracle_info)?;
+                let mut packed_curve = [0u8; SwapCurve::LEN];
+                swap_curve.pack_into_slice(&mut packed_curve);
+                let calculator = ProactiveMarketMakingCurve::unpack_from_slice(&packed_curve[1..])?;
+                Ok(Some(SwapCurve {
+                    curve_type: swap_curve.curve_type,
+                    calculator: Arc::new(ProactiveMarketMakingCurve {
+                        oracle_price,
+                        oracle_exponent,
+                        ..calculator
+                    }),
+                }))
+            }
+            (CurveType::StakePoolStable, None) | (CurveType::ProactiveMarketMaking, None) => {
+                Err(ProgramError::NotEnoughAccountKeys)
+            }
+            _ => Ok(None),
+        }
+    }
+
     /// Calculates the authority id by generating a program address.
     pub fn authority_id(
         program_id: &Pubkey,
@@ -81,7 +400,7 @@ impl Processor {
         let authority_signature_seeds = [&swap_bytes[..32], &[bump_seed]];
         let signers = &[&authority_signature_seeds[..]];
 
-        let ix = spl_token::instruction::burn(
+        let ix = spl_token_2022::instruction::burn(
             token_program.key,
             burn_account.key,
             mint.key,
@@ -110,7 +429,7 @@ impl Processor {
         let swap_bytes = swap.to_bytes();
         let authority_signature_seeds = [&swap_bytes[..32], &[bump_seed]];
         let signers = &[&authority_signature_seeds[..]];
-        let ix = spl_token::instruction::mint_to(
+        let ix = spl_token_2022::instruction::mint_to(
             token_program.key,
             mint.key,
             destination.key,
@@ -122,30 +441,35 @@ impl Processor {
         invoke_signed(&ix, &[mint, destination, authority, token_program], signers)
     }
 
-    /// Issue a spl_token `Transfer` instruction.
+    /// Issue a spl_token `TransferChecked` instruction.
+    #[allow(clippy::too_many_arguments)]
     pub fn token_transfer<'a>(
         swap: &Pubkey,
         token_program: AccountInfo<'a>,
         source: AccountInfo<'a>,
+        mint: AccountInfo<'a>,
         destination: AccountInfo<'a>,
         authority: AccountInfo<'a>,
         bump_seed: u8,
         amount: u64,
+        decimals: u8,
     ) -> Result<(), ProgramError> {
         let swap_bytes = swap.to_bytes();
         let authority_signature_seeds = [&swap_bytes[..32], &[bump_seed]];
         let signers = &[&authority_signature_seeds[..]];
-        let ix = spl_token::instruction::transfer(
+        let ix = spl_token_2022::instruction::transfer_checked(
             token_program.key,
             source.key,
+            mint.key,
             destination.key,
             authority.key,
             &[],
             amount,
+            decimals,
         )?;
         invoke_signed(
             &ix,
-            &[source, destination, authority, token_program],
+            &[source, mint, destination, authority, token_program],
             signers,
         )
     }
@@ -159,7 +483,7 @@ impl Processor {
         token_a_info: &AccountInfo,
         token_b_info: &AccountInfo,
         pool_mint_info: &AccountInfo,
-        token_program_info: &AccountInfo,
+        pool_token_program_info: &AccountInfo,
         user_token_a_info: Option<&AccountInfo>,
         user_token_b_info: Option<&AccountInfo>,
         pool_fee_account_info: Option<&AccountInfo>,
@@ -181,7 +505,7 @@ impl Processor {
         if *pool_mint_info.key != *token_swap.pool_mint() {
             return Err(SwapError::IncorrectPoolMint.into());
         }
-        if *token_program_info.key != *token_swap.token_program_id() {
+        if *pool_token_program_info.key != *token_swap.token_program_id() {
             return Err(SwapError::IncorrectTokenProgramId.into());
         }
         if let Some(user_token_a_info) = user_token_a_info {
@@ -218,10 +542,21 @@ impl Processor {
         let pool_mint_info = next_account_info(account_info_iter)?;
         let fee_account_info = next_account_info(account_info_iter)?;
         let destination_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
+        let token_a_mint_info = next_account_info(account_info_iter)?;
+        let token_b_mint_info = next_account_info(account_info_iter)?;
+        let curve_account_info = Self::next_curve_account_info(&swap_curve, account_info_iter)?;
+        let pause_authority = match next_account_info(account_info_iter) {
+            Ok(pause_authority_info) => COption::Some(*pause_authority_info.key),
+            Err(_) => COption::None,
+        };
 
-        let token_program_id = *token_program_info.key;
-        if SwapVersion::is_initialized(&swap_info.data.borrow()) {
+        let token_program_id = *pool_token_program_info.key;
+        check_spl_token_program_account(&token_program_id)
+            .map_err(|_| SwapError::IncorrectTokenProgramId)?;
+        if SwapVersion::is_initialized(&swap_info.data.borrow())
+            || is_tagged_account(&swap_info.data.borrow())
+        {
             return Err(SwapError::AlreadyInUse.into());
         }
 
@@ -230,8 +565,13 @@ impl Processor {
         if *authority_info.key != swap_authority {
             return Err(SwapError::InvalidProgramAddress.into());
         }
-        let token_a = Self::unpack_token_account(token_a_info, &token_program_id)?;
-        let token_b = Self::unpack_token_account(token_b_info, &token_program_id)?;
+        // Tokens A and B may be owned by either token program
+        check_spl_token_program_account(token_a_info.owner)
+            .map_err(|_| SwapError::IncorrectTokenProgramId)?;
+        check_spl_token_program_account(token_b_info.owner)
+            .map_err(|_| SwapError::IncorrectTokenProgramId)?;
+        let token_a = Self::unpack_token_account(token_a_info, token_a_info.owner)?;
+        let token_b = Self::unpack_token_account(token_b_info, token_b_info.owner)?;
         let fee_account = Self::unpack_token_account(fee_account_info, &token_program_id)?;
         let destination = Self::unpack_token_account(destination_info, &token_program_id)?;
         let pool_mint = Self::unpack_mint(pool_mint_info, &token_program_id)?;
@@ -254,6 +594,31 @@ impl Processor {
         if token_a.mint == token_b.mint {
             return Err(SwapError::RepeatedMint.into());
         }
+        if *token_a_mint_info.key != token_a.mint || *token_b_mint_info.key != token_b.mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+        Self::check_token_mint_extensions(token_a_mint_info)?;
+        Self::check_token_mint_extensions(token_b_mint_info)?;
+        Self::check_token_account_extensions(token_a_info)?;
+        Self::check_token_account_extensions(token_b_info)?;
+        let oracle = match (swap_curve.curve_type, curve_account_info) {
+            (CurveType::ProactiveMarketMaking, Some(oracle_info)) => {
+                // Any program can write a price account layout, so only trust
+                // accounts owned by a Pyth program
+                if !pyth::PROGRAM_IDS.contains(oracle_info.owner) {
+                    return Err(SwapError::InvalidOracle.into());
+                }
+                COption::Some(*oracle_info.key)
+            }
+            _ => COption::None,
+        };
+        let swap_curve = Self::refresh_swap_curve(
+            &swap_curve,
+            &token_a.mint,
+            oracle.as_ref().into(),
+            curve_account_info,
+        )?
+        .unwrap_or(swap_curve);
         swap_curve
             .calculator
             .validate_supply(token_a.amount, token_b.amount)?;
@@ -279,6 +644,8 @@ impl Processor {
         if *pool_mint_info.key != fee_account.mint {
             return Err(SwapError::IncorrectPoolMint.into());
         }
+        Self::check_pool_mint_extensions(pool_mint_info)?;
+        Self::check_token_account_extensions(fee_account_info)?;
 
         if let Some(swap_constraints) = swap_constraints {
             let owner_key = swap_constraints
@@ -290,6 +657,14 @@ impl Processor {
             }
             swap_constraints.validate_curve(&swap_curve)?;
             swap_constraints.validate_fees(&fees)?;
+            swap_constraints.validate_pause_authority(&pause_authority)?;
+            // The oracle is pinned in the swap, so its owner only needs to be
+            // checked once
+            if let (CurveType::ProactiveMarketMaking, Some(oracle_info)) =
+                (swap_curve.curve_type, curve_account_info)
+            {
+                swap_constraints.validate_oracle_owner(oracle_info.owner)?;
+            }
         }
         fees.validate()?;
         swap_curve.calculator.validate()?;
@@ -298,7 +673,7 @@ impl Processor {
 
         Self::token_mint_to(
             swap_info.key,
-            token_program_info.clone(),
+            pool_token_program_info.clone(),
             pool_mint_info.clone(),
             destination_info.clone(),
             authority_info.clone(),
@@ -306,7 +681,7 @@ impl Processor {
             to_u64(initial_amount)?,
         )?;
 
-        let obj = SwapVersion::SwapV1(SwapV1 {
+        let swap_v3 = SwapV3 {
             is_initialized: true,
             bump_seed,
             token_program_id,
@@ -318,7 +693,27 @@ impl Processor {
             pool_fee_account: *fee_account_info.key,
             fees,
             swap_curve,
-        });
+            pause_authority,
+            is_paused: false,
+            oracle,
+            dynamic_fees: DynamicFees::default(),
+            volatility: 0,
+            volatility_update_slot: 0,
+            price_accumulator: PriceAccumulator::new(Clock::get()?.unix_timestamp),
+        };
+        // Accounts sized for previous layouts can still hold swaps that don't
+        // accumulate prices, or that also don't need to be paused or priced by
+        // an oracle
+        let obj = if swap_info.data_len() >= SwapVersion::LATEST_LEN {
+            SwapVersion::SwapV3(swap_v3)
+        } else if swap_v3.pause_authority.is_none()
+            && swap_v3.oracle.is_none()
+            && swap_info.data_len() < 1 + SwapV2::LEN
+        {
+            SwapVersion::SwapV1(swap_v3.into())
+        } else {
+            SwapVersion::SwapV2(swap_v3.into())
+        };
         SwapVersion::pack(obj, &mut swap_info.data.borrow_mut())?;
         Ok(())
     }
@@ -340,12 +735,21 @@ impl Processor {
         let destination_info = next_account_info(account_info_iter)?;
         let pool_mint_info = next_account_info(account_info_iter)?;
         let pool_fee_account_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
+        let source_token_mint_info = next_account_info(account_info_iter)?;
+        let destination_token_mint_info = next_account_info(account_info_iter)?;
+        let source_token_program_info = next_account_info(account_info_iter)?;
+        let destination_token_program_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
 
         if swap_info.owner != program_id {
             return Err(ProgramError::IncorrectProgramId);
         }
         let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        if token_swap.is_paused() {
+            return Err(SwapError::SwapPaused.into());
+        }
+        let curve_account_info =
+            Self::next_curve_account_info(token_swap.swap_curve(), account_info_iter)?;
 
         if *authority_info.key
             != Self::authority_id(program_id, swap_info.key, token_swap.bump_seed())?
@@ -377,35 +781,97 @@ impl Processor {
         if *pool_fee_account_info.key != *token_swap.pool_fee_account() {
             return Err(SwapError::IncorrectFeeAccount.into());
         }
-        if *token_program_info.key != *token_swap.token_program_id() {
+        if *pool_token_program_info.key != *token_swap.token_program_id() {
             return Err(SwapError::IncorrectTokenProgramId.into());
         }
 
-        let source_account =
-            Self::unpack_token_account(swap_source_info, token_swap.token_program_id())?;
-        let dest_account =
-            Self::unpack_token_account(swap_destination_info, token_swap.token_program_id())?;
-        let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
-
         let trade_direction = if *swap_source_info.key == *token_swap.token_a_account() {
             TradeDirection::AtoB
         } else {
             TradeDirection::BtoA
         };
-        let result = token_swap
-            .swap_curve()
+        let (source_mint, destination_mint) = match trade_direction {
+            TradeDirection::AtoB => (token_swap.token_a_mint(), token_swap.token_b_mint()),
+            TradeDirection::BtoA => (token_swap.token_b_mint(), token_swap.token_a_mint()),
+        };
+        Self::check_token_mint(
+            source_token_mint_info,
+            source_token_program_info,
+            source_mint,
+        )?;
+        Self::check_token_mint(
+            destination_token_mint_info,
+            destination_token_program_info,
+            destination_mint,
+        )?;
+
+        let source_account =
+            Self::unpack_token_account(swap_source_info, source_token_program_info.key)?;
+        let dest_account =
+            Self::unpack_token_account(swap_destination_info, destination_token_program_info.key)?;
+        let source_token_mint =
+            Self::unpack_mint(source_token_mint_info, source_token_program_info.key)?;
+        let destination_token_mint = Self::unpack_mint(
+            destination_token_mint_info,
+            destination_token_program_info.key,
+        )?;
+        let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
+
+        let refreshed_swap_curve = Self::refresh_swap_curve(
+            token_swap.swap_curve(),
+            token_swap.token_a_mint(),
+            token_swap.oracle(),
+            curve_account_info,
+        )?;
+        let swap_curve = refreshed_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| token_swap.swap_curve());
+        let (fees, volatility, slot) = Self::dynamic_trade_fees(token_swap.as_ref())?;
+
+        // Only the amount left after the transfer fee reaches the pool
+        let actual_amount_in = Self::amount_after_transfer_fee(source_token_mint_info, amount_in)?;
+        let result = swap_curve
             .swap(
-                to_u128(amount_in)?,
+                to_u128(actual_amount_in)?,
                 to_u128(source_account.amount)?,
                 to_u128(dest_account.amount)?,
                 trade_direction,
-                token_swap.fees(),
+                &fees,
             )
             .ok_or(SwapError::ZeroTradingTokens)?;
-        if result.destination_amount_swapped < to_u128(minimum_amount_out)? {
+
+        // The user receives the amount left after the transfer fee
+        let destination_transfer_amount = to_u64(result.destination_amount_swapped)?;
+        let destination_amount_received = Self::amount_after_transfer_fee(
+            destination_token_mint_info,
+            destination_transfer_amount,
+        )?;
+        if destination_amount_received < minimum_amount_out {
             return Err(SwapError::ExceededSlippage.into());
         }
 
+        let (token_a_amount, token_b_amount) = match trade_direction {
+            TradeDirection::AtoB => (source_account.amount, dest_account.amount),
+            TradeDirection::BtoA => (dest_account.amount, source_account.amount),
+        };
+        Self::accumulate_prices(
+            swap_info,
+            token_swap.as_ref(),
+            token_a_amount,
+            token_b_amount,
+        )?;
+
+        if token_swap.dynamic_fees().is_enabled() {
+            let price_impact = price_impact(
+                to_u128(source_account.amount)?,
+                to_u128(dest_account.amount)?,
+                result.new_swap_source_amount,
+                result.new_swap_destination_amount,
+            )
+            .ok_or(SwapError::FeeCalculationFailure)?;
+            Self::update_volatility(swap_info, volatility, slot, price_impact)?;
+        }
+
         let (swap_token_a_amount, swap_token_b_amount) = match trade_direction {
             TradeDirection::AtoB => (
                 result.new_swap_source_amount,
@@ -417,25 +883,36 @@ impl Processor {
             ),
         };
 
+        // Curves may not use the whole amount in, in which case the transfer
+        // must cover the transfer fee on the amount actually swapped
+        let source_transfer_amount = if result.source_amount_swapped == to_u128(actual_amount_in)? {
+            amount_in
+        } else {
+            Self::amount_before_transfer_fee(
+                source_token_mint_info,
+                to_u64(result.source_amount_swapped)?,
+            )?
+        };
         Self::token_transfer(
             swap_info.key,
-            token_program_info.clone(),
+            source_token_program_info.clone(),
             source_info.clone(),
+            source_token_mint_info.clone(),
             swap_source_info.clone(),
             user_transfer_authority_info.clone(),
             token_swap.bump_seed(),
-            to_u64(result.source_amount_swapped)?,
+            source_transfer_amount,
+            source_token_mint.decimals,
         )?;
 
-        let mut pool_token_amount = token_swap
-            .swap_curve()
+        let mut pool_token_amount = swap_curve
             .withdraw_single_token_type_exact_out(
                 result.owner_fee,
                 swap_token_a_amount,
                 swap_token_b_amount,
                 to_u128(pool_mint.supply)?,
                 trade_direction,
-                token_swap.fees(),
+                &fees,
             )
             .ok_or(SwapError::FeeCalculationFailure)?;
 
@@ -459,7 +936,7 @@ impl Processor {
                         .ok_or(SwapError::FeeCalculationFailure)?;
                     Self::token_mint_to(
                         swap_info.key,
-                        token_program_info.clone(),
+                        pool_token_program_info.clone(),
                         pool_mint_info.clone(),
                         host_fee_account_info.clone(),
                         authority_info.clone(),
@@ -470,7 +947,7 @@ impl Processor {
             }
             Self::token_mint_to(
                 swap_info.key,
-                token_program_info.clone(),
+                pool_token_program_info.clone(),
                 pool_mint_info.clone(),
                 pool_fee_account_info.clone(),
                 authority_info.clone(),
@@ -481,12 +958,14 @@ impl Processor {
 
         Self::token_transfer(
             swap_info.key,
-            token_program_info.clone(),
+            destination_token_program_info.clone(),
             swap_destination_info.clone(),
+            destination_token_mint_info.clone(),
             destination_info.clone(),
             authority_info.clone(),
             token_swap.bump_seed(),
-            to_u64(result.destination_amount_swapped)?,
+            destination_transfer_amount,
+            destination_token_mint.decimals,
         )?;
 
         Ok(())
@@ -510,9 +989,16 @@ impl Processor {
         let token_b_info = next_account_info(account_info_iter)?;
         let pool_mint_info = next_account_info(account_info_iter)?;
         let dest_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
+        let token_a_mint_info = next_account_info(account_info_iter)?;
+        let token_b_mint_info = next_account_info(account_info_iter)?;
+        let token_a_program_info = next_account_info(account_info_iter)?;
+        let token_b_program_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
 
         let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        if token_swap.is_paused() {
+            return Err(SwapError::SwapPaused.into());
+        }
         let calculator = &token_swap.swap_curve().calculator;
         if !calculator.allows_deposits() {
             return Err(SwapError::UnsupportedCurveOperation.into());
@@ -525,14 +1011,26 @@ impl Processor {
             token_a_info,
             token_b_info,
             pool_mint_info,
-            token_program_info,
+            pool_token_program_info,
             Some(source_a_info),
             Some(source_b_info),
             None,
         )?;
+        Self::check_token_mint(
+            token_a_mint_info,
+            token_a_program_info,
+            token_swap.token_a_mint(),
+        )?;
+        Self::check_token_mint(
+            token_b_mint_info,
+            token_b_program_info,
+            token_swap.token_b_mint(),
+        )?;
 
-        let token_a = Self::unpack_token_account(token_a_info, token_swap.token_program_id())?;
-        let token_b = Self::unpack_token_account(token_b_info, token_swap.token_program_id())?;
+        let token_a = Self::unpack_token_account(token_a_info, token_a_program_info.key)?;
+        let token_b = Self::unpack_token_account(token_b_info, token_b_program_info.key)?;
+        let token_a_mint = Self::unpack_mint(token_a_mint_info, token_a_program_info.key)?;
+        let token_b_mint = Self::unpack_mint(token_b_mint_info, token_b_program_info.key)?;
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
         let current_pool_mint_supply = to_u128(pool_mint.supply)?;
         let (pool_token_amount, pool_mint_supply) = if current_pool_mint_supply > 0 {
@@ -550,44 +1048,58 @@ impl Processor {
                 RoundDirection::Ceiling,
             )
             .ok_or(SwapError::ZeroTradingTokens)?;
+        // The pool must receive the required amounts after transfer fees
         let token_a_amount = to_u64(results.token_a_amount)?;
-        if token_a_amount > maximum_token_a_amount {
-            return Err(SwapError::ExceededSlippage.into());
-        }
         if token_a_amount == 0 {
             return Err(SwapError::ZeroTradingTokens.into());
         }
-        let token_b_amount = to_u64(results.token_b_amount)?;
-        if token_b_amount > maximum_token_b_amount {
+        let token_a_amount = Self::amount_before_transfer_fee(token_a_mint_info, token_a_amount)?;
+        if token_a_amount > maximum_token_a_amount {
             return Err(SwapError::ExceededSlippage.into());
         }
+        let token_b_amount = to_u64(results.token_b_amount)?;
         if token_b_amount == 0 {
             return Err(SwapError::ZeroTradingTokens.into());
         }
+        let token_b_amount = Self::amount_before_transfer_fee(token_b_mint_info, token_b_amount)?;
+        if token_b_amount > maximum_token_b_amount {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+
+        Self::accumulate_prices(
+            swap_info,
+            token_swap.as_ref(),
+            token_a.amount,
+            token_b.amount,
+        )?;
 
         let pool_token_amount = to_u64(pool_token_amount)?;
 
         Self::token_transfer(
             swap_info.key,
-            token_program_info.clone(),
+            token_a_program_info.clone(),
             source_a_info.clone(),
+            token_a_mint_info.clone(),
             token_a_info.clone(),
             user_transfer_authority_info.clone(),
             token_swap.bump_seed(),
             token_a_amount,
+            token_a_mint.decimals,
         )?;
         Self::token_transfer(
             swap_info.key,
-            token_program_info.clone(),
+            token_b_program_info.clone(),
             source_b_info.clone(),
+            token_b_mint_info.clone(),
             token_b_info.clone(),
             user_transfer_authority_info.clone(),
             token_swap.bump_seed(),
             token_b_amount,
+            token_b_mint.decimals,
         )?;
         Self::token_mint_to(
             swap_info.key,
-            token_program_info.clone(),
+            pool_token_program_info.clone(),
             pool_mint_info.clone(),
             dest_info.clone(),
             authority_info.clone(),
@@ -617,7 +1129,11 @@ impl Processor {
         let dest_token_a_info = next_account_info(account_info_iter)?;
         let dest_token_b_info = next_account_info(account_info_iter)?;
         let pool_fee_account_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
+        let token_a_mint_info = next_account_info(account_info_iter)?;
+        let token_b_mint_info = next_account_info(account_info_iter)?;
+        let token_a_program_info = next_account_info(account_info_iter)?;
+        let token_b_program_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
 
         let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
         Self::check_accounts(
@@ -628,14 +1144,26 @@ impl Processor {
             token_a_info,
             token_b_info,
             pool_mint_info,
-            token_program_info,
+            pool_token_program_info,
             Some(dest_token_a_info),
             Some(dest_token_b_info),
             Some(pool_fee_account_info),
         )?;
+        Self::check_token_mint(
+            token_a_mint_info,
+            token_a_program_info,
+            token_swap.token_a_mint(),
+        )?;
+        Self::check_token_mint(
+            token_b_mint_info,
+            token_b_program_info,
+            token_swap.token_b_mint(),
+        )?;
 
-        let token_a = Self::unpack_token_account(token_a_info, token_swap.token_program_id())?;
-        let token_b = Self::unpack_token_account(token_b_info, token_swap.token_program_id())?;
+        let token_a = Self::unpack_token_account(token_a_info, token_a_program_info.key)?;
+        let token_b = Self::unpack_token_account(token_b_info, token_b_program_info.key)?;
+        let token_a_mint = Self::unpack_mint(token_a_mint_info, token_a_program_info.key)?;
+        let token_b_mint = Self::unpack_mint(token_b_mint_info, token_b_program_info.key)?;
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
 
         let calculator = &token_swap.swap_curve().calculator;
@@ -662,9 +1190,12 @@ impl Processor {
                 RoundDirection::Floor,
             )
             .ok_or(SwapError::ZeroTradingTokens)?;
+        // The user receives the amounts left after transfer fees
         let token_a_amount = to_u64(results.token_a_amount)?;
         let token_a_amount = std::cmp::min(token_a.amount, token_a_amount);
-        if token_a_amount < minimum_token_a_amount {
+        let token_a_amount_received =
+            Self::amount_after_transfer_fee(token_a_mint_info, token_a_amount)?;
+        if token_a_amount_received < minimum_token_a_amount {
             return Err(SwapError::ExceededSlippage.into());
         }
         if token_a_amount == 0 && token_a.amount != 0 {
@@ -672,27 +1203,38 @@ impl Processor {
         }
         let token_b_amount = to_u64(results.token_b_amount)?;
         let token_b_amount = std::cmp::min(token_b.amount, token_b_amount);
-        if token_b_amount < minimum_token_b_amount {
+        let token_b_amount_received =
+            Self::amount_after_transfer_fee(token_b_mint_info, token_b_amount)?;
+        if token_b_amount_received < minimum_token_b_amount {
             return Err(SwapError::ExceededSlippage.into());
         }
         if token_b_amount == 0 && token_b.amount != 0 {
             return Err(SwapError::ZeroTradingTokens.into());
         }
 
+        Self::accumulate_prices(
+            swap_info,
+            token_swap.as_ref(),
+            token_a.amount,
+            token_b.amount,
+        )?;
+
         if withdraw_fee > 0 {
             Self::token_transfer(
                 swap_info.key,
-                token_program_info.clone(),
+                pool_token_program_info.clone(),
                 source_info.clone(),
+                pool_mint_info.clone(),
                 pool_fee_account_info.clone(),
                 user_transfer_authority_info.clone(),
                 token_swap.bump_seed(),
                 to_u64(withdraw_fee)?,
+                pool_mint.decimals,
             )?;
         }
         Self::token_burn(
             swap_info.key,
-            token_program_info.clone(),
+            pool_token_program_info.clone(),
             source_info.clone(),
             pool_mint_info.clone(),
             user_transfer_authority_info.clone(),
@@ -703,23 +1245,27 @@ impl Processor {
         if token_a_amount > 0 {
             Self::token_transfer(
                 swap_info.key,
-                token_program_info.clone(),
+                token_a_program_info.clone(),
                 token_a_info.clone(),
+                token_a_mint_info.clone(),
                 dest_token_a_info.clone(),
                 authority_info.clone(),
                 token_swap.bump_seed(),
                 token_a_amount,
+                token_a_mint.decimals,
             )?;
         }
         if token_b_amount > 0 {
             Self::token_transfer(
                 swap_info.key,
-                token_program_info.clone(),
+                token_b_program_info.clone(),
                 token_b_info.clone(),
+                token_b_mint_info.clone(),
                 dest_token_b_info.clone(),
                 authority_info.clone(),
                 token_swap.bump_seed(),
                 token_b_amount,
+                token_b_mint.decimals,
             )?;
         }
         Ok(())
@@ -741,19 +1287,26 @@ impl Processor {
         let swap_token_b_info = next_account_info(account_info_iter)?;
         let pool_mint_info = next_account_info(account_info_iter)?;
         let destination_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
+        let source_token_mint_info = next_account_info(account_info_iter)?;
+        let source_token_program_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
 
         let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        if token_swap.is_paused() {
+            return Err(SwapError::SwapPaused.into());
+        }
+        let curve_account_info =
+            Self::next_curve_account_info(token_swap.swap_curve(), account_info_iter)?;
         let calculator = &token_swap.swap_curve().calculator;
         if !calculator.allows_deposits() {
             return Err(SwapError::UnsupportedCurveOperation.into());
         }
         let source_account =
-            Self::unpack_token_account(source_info, token_swap.token_program_id())?;
-        let swap_token_a =
-            Self::unpack_token_account(swap_token_a_info, token_swap.token_program_id())?;
-        let swap_token_b =
-            Self::unpack_token_account(swap_token_b_info, token_swap.token_program_id())?;
+            Self::unpack_token_account(source_info, source_token_program_info.key)?;
+        // The swap token accounts are checked against the swap below, and
+        // may be owned by either token program
+        let swap_token_a = Self::unpack_token_account(swap_token_a_info, swap_token_a_info.owner)?;
+        let swap_token_b = Self::unpack_token_account(swap_token_b_info, swap_token_b_info.owner)?;
 
         let trade_direction = if source_account.mint == swap_token_a.mint {
             TradeDirection::AtoB
@@ -776,24 +1329,48 @@ impl Processor {
             swap_token_a_info,
             swap_token_b_info,
             pool_mint_info,
-            token_program_info,
+            pool_token_program_info,
             source_a_info,
             source_b_info,
             None,
         )?;
-
+        let (swap_source_info, source_mint) = match trade_direction {
+            TradeDirection::AtoB => (swap_token_a_info, token_swap.token_a_mint()),
+            TradeDirection::BtoA => (swap_token_b_info, token_swap.token_b_mint()),
+        };
+        Self::check_token_mint(
+            source_token_mint_info,
+            source_token_program_info,
+            source_mint,
+        )?;
+        let source_token_mint =
+            Self::unpack_mint(source_token_mint_info, source_token_program_info.key)?;
+
+        let refreshed_swap_curve = Self::refresh_swap_curve(
+            token_swap.swap_curve(),
+            token_swap.token_a_mint(),
+            token_swap.oracle(),
+            curve_account_info,
+        )?;
+        let swap_curve = refreshed_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| token_swap.swap_curve());
+
+        // Only the amount left after the transfer fee is deposited
+        let source_token_amount_received =
+            Self::amount_after_transfer_fee(source_token_mint_info, source_token_amount)?;
+        let (fees, volatility, slot) = Self::dynamic_trade_fees(token_swap.as_ref())?;
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
         let pool_mint_supply = to_u128(pool_mint.supply)?;
         let pool_token_amount = if pool_mint_supply > 0 {
-            token_swap
-                .swap_curve()
+            swap_curve
                 .deposit_single_token_type(
-                    to_u128(source_token_amount)?,
+                    to_u128(source_token_amount_received)?,
                     to_u128(swap_token_a.amount)?,
                     to_u128(swap_token_b.amount)?,
                     pool_mint_supply,
                     trade_direction,
-                    token_swap.fees(),
+                    &fees,
                 )
                 .ok_or(SwapError::ZeroTradingTokens)?
         } else {
@@ -808,33 +1385,47 @@ impl Processor {
             return Err(SwapError::ZeroTradingTokens.into());
         }
 
-        match trade_direction {
-            TradeDirection::AtoB => {
-                Self::token_transfer(
-                    swap_info.key,
-                    token_program_info.clone(),
-                    source_info.clone(),
-                    swap_token_a_info.clone(),
-                    user_transfer_authority_info.clone(),
-                    token_swap.bump_seed(),
-                    source_token_amount,
-                )?;
-            }
-            TradeDirection::BtoA => {
-                Self::token_transfer(
-                    swap_info.key,
-                    token_program_info.clone(),
-                    source_info.clone(),
-                    swap_token_b_info.clone(),
-                    user_transfer_authority_info.clone(),
-                    token_swap.bump_seed(),
-                    source_token_amount,
-                )?;
-            }
+        Self::accumulate_prices(
+            swap_info,
+            token_swap.as_ref(),
+            swap_token_a.amount,
+            swap_token_b.amount,
+        )?;
+
+        // depositing a single token trades part of it for the other one
+        if token_swap.dynamic_fees().is_enabled() && pool_mint_supply > 0 {
+            let (swap_source_amount, swap_destination_amount) = match trade_direction {
+                TradeDirection::AtoB => (swap_token_a.amount, swap_token_b.amount),
+                TradeDirection::BtoA => (swap_token_b.amount, swap_token_a.amount),
+            };
+            let swap_source_amount = to_u128(swap_source_amount)?;
+            let swap_destination_amount = to_u128(swap_destination_amount)?;
+            let price_impact = price_impact(
+                swap_source_amount,
+                swap_destination_amount,
+                swap_source_amount
+                    .checked_add(to_u128(source_token_amount_received)?)
+                    .ok_or(SwapError::CalculationFailure)?,
+                swap_destination_amount,
+            )
+            .ok_or(SwapError::FeeCalculationFailure)?;
+            Self::update_volatility(swap_info, volatility, slot, price_impact)?;
         }
+
+        Self::token_transfer(
+            swap_info.key,
+            source_token_program_info.clone(),
+            source_info.clone(),
+            source_token_mint_info.clone(),
+            swap_source_info.clone(),
+            user_transfer_authority_info.clone(),
+            token_swap.bump_seed(),
+            source_token_amount,
+            source_token_mint.decimals,
+        )?;
         Self::token_mint_to(
             swap_info.key,
-            token_program_info.clone(),
+            pool_token_program_info.clone(),
             pool_mint_info.clone(),
             destination_info.clone(),
             authority_info.clone(),
@@ -862,15 +1453,22 @@ impl Processor {
         let swap_token_b_info = next_account_info(account_info_iter)?;
         let destination_info = next_account_info(account_info_iter)?;
         let pool_fee_account_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
+        let destination_token_mint_info = next_account_info(account_info_iter)?;
+        let destination_token_program_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
 
         let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        if token_swap.is_paused() {
+            return Err(SwapError::SwapPaused.into());
+        }
+        let curve_account_info =
+            Self::next_curve_account_info(token_swap.swap_curve(), account_info_iter)?;
         let destination_account =
-            Self::unpack_token_account(destination_info, token_swap.token_program_id())?;
-        let swap_token_a =
-            Self::unpack_token_account(swap_token_a_info, token_swap.token_program_id())?;
-        let swap_token_b =
-            Self::unpack_token_account(swap_token_b_info, token_swap.token_program_id())?;
+            Self::unpack_token_account(destination_info, destination_token_program_info.key)?;
+        // The swap token accounts are checked against the swap below, and
+        // may be owned by either token program
+        let swap_token_a = Self::unpack_token_account(swap_token_a_info, swap_token_a_info.owner)?;
+        let swap_token_b = Self::unpack_token_account(swap_token_b_info, swap_token_b_info.owner)?;
 
         let trade_direction = if destination_account.mint == swap_token_a.mint {
             TradeDirection::AtoB
@@ -892,26 +1490,55 @@ impl Processor {
             swap_token_a_info,
             swap_token_b_info,
             pool_mint_info,
-            token_program_info,
+            pool_token_program_info,
             destination_a_info,
             destination_b_info,
             Some(pool_fee_account_info),
         )?;
+        let (swap_destination_info, destination_mint) = match trade_direction {
+            TradeDirection::AtoB => (swap_token_a_info, token_swap.token_a_mint()),
+            TradeDirection::BtoA => (swap_token_b_info, token_swap.token_b_mint()),
+        };
+        Self::check_token_mint(
+            destination_token_mint_info,
+            destination_token_program_info,
+            destination_mint,
+        )?;
+        let destination_token_mint = Self::unpack_mint(
+            destination_token_mint_info,
+            destination_token_program_info.key,
+        )?;
 
+        let refreshed_swap_curve = Self::refresh_swap_curve(
+            token_swap.swap_curve(),
+            token_swap.token_a_mint(),
+            token_swap.oracle(),
+            curve_account_info,
+        )?;
+        let swap_curve = refreshed_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| token_swap.swap_curve());
+
+        // The pool sends enough for the user to receive the exact amount out
+        // once the transfer fee is withheld
+        let destination_transfer_amount = Self::amount_before_transfer_fee(
+            destination_token_mint_info,
+            destination_token_amount,
+        )?;
+        let (fees, volatility, slot) = Self::dynamic_trade_fees(token_swap.as_ref())?;
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
         let pool_mint_supply = to_u128(pool_mint.supply)?;
         let swap_token_a_amount = to_u128(swap_token_a.amount)?;
         let swap_token_b_amount = to_u128(swap_token_b.amount)?;
 
-        let burn_pool_token_amount = token_swap
-            .swap_curve()
+        let burn_pool_token_amount = swap_curve
             .withdraw_single_token_type_exact_out(
-                to_u128(destination_token_amount)?,
+                to_u128(destination_transfer_amount)?,
                 swap_token_a_amount,
                 swap_token_b_amount,
                 pool_mint_supply,
                 trade_direction,
-                token_swap.fees(),
+                &fees,
             )
             .ok_or(SwapError::ZeroTradingTokens)?;
 
@@ -935,20 +1562,47 @@ impl Processor {
             return Err(SwapError::ZeroTradingTokens.into());
         }
 
+        Self::accumulate_prices(
+            swap_info,
+            token_swap.as_ref(),
+            swap_token_a.amount,
+            swap_token_b.amount,
+        )?;
+
+        // withdrawing a single token trades the share of the other one for it
+        if token_swap.dynamic_fees().is_enabled() {
+            let (swap_source_amount, swap_destination_amount) = match trade_direction {
+                TradeDirection::AtoB => (swap_token_b_amount, swap_token_a_amount),
+                TradeDirection::BtoA => (swap_token_a_amount, swap_token_b_amount),
+            };
+            let price_impact = price_impact(
+                swap_source_amount,
+                swap_destination_amount,
+                swap_source_amount,
+                swap_destination_amount
+                    .checked_sub(to_u128(destination_transfer_amount)?)
+                    .ok_or(SwapError::CalculationFailure)?,
+            )
+            .ok_or(SwapError::FeeCalculationFailure)?;
+            Self::update_volatility(swap_info, volatility, slot, price_impact)?;
+        }
+
         if withdraw_fee > 0 {
             Self::token_transfer(
                 swap_info.key,
-                token_program_info.clone(),
+                pool_token_program_info.clone(),
                 source_info.clone(),
+                pool_mint_info.clone(),
                 pool_fee_account_info.clone(),
                 user_transfer_authority_info.clone(),
                 token_swap.bump_seed(),
                 to_u64(withdraw_fee)?,
+                pool_mint.decimals,
             )?;
         }
         Self::token_burn(
             swap_info.key,
-            token_program_info.clone(),
+            pool_token_program_info.clone(),
             source_info.clone(),
             pool_mint_info.clone(),
             user_transfer_authority_info.clone(),
@@ -956,1851 +1610,5744 @@ impl Processor {
             to_u64(burn_pool_token_amount)?,
         )?;
 
-        match trade_direction {
-            TradeDirection::AtoB => {
-                Self::token_transfer(
-                    swap_info.key,
-                    token_program_info.clone(),
-                    swap_token_a_info.clone(),
-                    destination_info.clone(),
-                    authority_info.clone(),
-                    token_swap.bump_seed(),
-                    destination_token_amount,
-                )?;
-            }
-            TradeDirection::BtoA => {
-                Self::token_transfer(
-                    swap_info.key,
-                    token_program_info.clone(),
-                    swap_token_b_info.clone(),
-                    destination_info.clone(),
-                    authority_info.clone(),
-                    token_swap.bump_seed(),
-                    destination_token_amount,
-                )?;
-            }
-        }
+        Self::token_transfer(
+            swap_info.key,
+            destination_token_program_info.clone(),
+            swap_destination_info.clone(),
+            destination_token_mint_info.clone(),
+            destination_info.clone(),
+            authority_info.clone(),
+            token_swap.bump_seed(),
+            destination_transfer_amount,
+            destination_token_mint.decimals,
+        )?;
 
         Ok(())
     }
 
-    /// Processes an [Instruction](enum.Instruction.html).
-    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
-        Self::process_with_constraints(program_id, accounts, input, &SWAP_CONSTRAINTS)
-    }
-
-    /// Processes an instruction given extra constraint
-    pub fn process_with_constraints(
+    /// Processes a [Pause](enum.Instruction.html) or [Resume](enum.Instruction.html).
+    pub fn process_set_paused(
         program_id: &Pubkey,
+        is_paused: bool,
         accounts: &[AccountInfo],
-        input: &[u8],
-        swap_constraints: &Option<SwapConstraints>,
     ) -> ProgramResult {
-        let instruction = SwapInstruction::unpack(input)?;
-        match instruction {
-            SwapInstruction::Initialize(Initialize { fees, swap_curve }) => {
-                msg!("Instruction: Init");
-                Self::process_initialize(program_id, fees, swap_curve, accounts, swap_constraints)
-            }
-            SwapInstruction::Swap(Swap {
-                amount_in,
-                minimum_amount_out,
-            }) => {
-                msg!("Instruction: Swap");
-                Self::process_swap(program_id, amount_in, minimum_amount_out, accounts)
-            }
-            SwapInstruction::DepositAllTokenTypes(DepositAllTokenTypes {
-                pool_token_amount,
-                maximum_token_a_amount,
-                maximum_token_b_amount,
-            }) => {
-                msg!("Instruction: DepositAllTokenTypes");
-                Self::process_deposit_all_token_types(
-                    program_id,
-                    pool_token_amount,
-                    maximum_token_a_amount,
-                    maximum_token_b_amount,
-                    accounts,
-                )
-            }
-            SwapInstruction::WithdrawAllTokenTypes(WithdrawAllTokenTypes {
-                pool_token_amount,
-                minimum_token_a_amount,
-                minimum_token_b_amount,
-            }) => {
-                msg!("Instruction: WithdrawAllTokenTypes");
-                Self::process_withdraw_all_token_types(
-                    program_id,
-                    pool_token_amount,
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                    accounts,
-                )
-            }
-            SwapInstruction::DepositSingleTokenTypeExactAmountIn(
-                DepositSingleTokenTypeExactAmountIn {
-                    source_token_amount,
-                    minimum_pool_token_amount,
-                },
-            ) => {
-                msg!("Instruction: DepositSingleTokenTypeExactAmountIn");
-                Self::process_deposit_single_token_type_exact_amount_in(
-                    program_id,
-                    source_token_amount,
-                    minimum_pool_token_amount,
-                    accounts,
-                )
-            }
-            SwapInstruction::WithdrawSingleTokenTypeExactAmountOut(
-                WithdrawSingleTokenTypeExactAmountOut {
-                    destination_token_amount,
-                    maximum_pool_token_amount,
-                },
-            ) => {
-                msg!("Instruction: WithdrawSingleTokenTypeExactAmountOut");
-                Self::process_withdraw_single_token_type_exact_amount_out(
-                    program_id,
-                    destination_token_amount,
-                    maximum_pool_token_amount,
-                    accounts,
-                )
-            }
-        }
-    }
-}
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let pause_authority_info = next_account_info(account_info_iter)?;
 
-impl PrintProgramError for SwapError {
-    fn print<E>(&self)
-    where
-        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
-    {
-        match self {
-            SwapError::AlreadyInUse => msg!("Error: Swap account already in use"),
-            SwapError::InvalidProgramAddress => {
-                msg!("Error: Invalid program address generated from bump seed and key")
-            }
-            SwapError::InvalidOwner => {
-                msg!("Error: The input account owner is not the program address")
-            }
-            SwapError::InvalidOutputOwner => {
-                msg!("Error: Output pool account owner cannot be the program address")
-            }
-            SwapError::ExpectedMint => msg!("Error: Deserialized account is not an SPL Token mint"),
-            SwapError::ExpectedAccount => {
-                msg!("Error: Deserialized account is not an SPL Token account")
-            }
-            SwapError::EmptySupply => msg!("Error: Input token account empty"),
-            SwapError::InvalidSupply => msg!("Error: Pool token mint has a non-zero supply"),
-            SwapError::RepeatedMint => msg!("Error: Swap input token accounts have the same mint"),
-            SwapError::InvalidDelegate => msg!("Error: Token account has a delegate"),
-            SwapError::InvalidInput => msg!("Error: InvalidInput"),
-            SwapError::IncorrectSwapAccount => {
-                msg!("Error: Address of the provided swap token account is incorrect")
-            }
-            SwapError::IncorrectPoolMint => {
-                msg!("Error: Address of the provided pool token mint is incorrect")
-            }
-            SwapError::InvalidOutput => msg!("Error: InvalidOutput"),
-            SwapError::CalculationFailure => msg!("Error: CalculationFailure"),
-            SwapError::InvalidInstruction => msg!("Error: InvalidInstruction"),
-            SwapError::ExceededSlippage => {
-                msg!("Error: Swap instruction exceeds desired slippage limit")
-            }
-            SwapError::InvalidCloseAuthority => msg!("Error: Token account has a close authority"),
-            SwapError::InvalidFreezeAuthority => {
-                msg!("Error: Pool token mint has a freeze authority")
-            }
-            SwapError::IncorrectFeeAccount => msg!("Error: Pool fee token account incorrect"),
-            SwapError::ZeroTradingTokens => {
-                msg!("Error: Given pool token amount results in zero trading tokens")
-            }
-            SwapError::FeeCalculationFailure => msg!(
-                "Error: The fee calculation failed due to overflow, underflow, or unexpected 0"
-            ),
-            SwapError::ConversionFailure => msg!("Error: Conversion to or from u64 failed."),
-            SwapError::InvalidFee => {
-                msg!("Error: The provided fee does not match the program owner's constraints")
-            }
-            SwapError::IncorrectTokenProgramId => {
-                msg!("Error: The provided token program does not match the token program expected by the swap")
-            }
-            SwapError::UnsupportedCurveType => {
-                msg!("Error: The provided curve type is not supported by the program owner")
-            }
-            SwapError::InvalidCurve => {
-                msg!("Error: The provided curve parameters are invalid")
-            }
-            SwapError::UnsupportedCurveOperation => {
-                msg!("Error: The operation cannot be performed on the given curve")
-            }
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        if !pause_authority_info.is_signer
+            || token_swap.pause_authority != COption::Some(*pause_authority_info.key)
+        {
+            return Err(SwapError::InvalidPauseAuthority.into());
         }
-    }
-}
-
-fn to_u128(val: u64) -> Result<u128, SwapError> {
-    val.try_into().map_err(|_| SwapError::ConversionFailure)
-}
-
-fn to_u64(val: u128) -> Result<u64, SwapError> {
-    val.try_into().map_err(|_| SwapError::ConversionFailure)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        curve::calculator::{CurveCalculator, INITIAL_SWAP_POOL_AMOUNT},
-        curve::{
-            base::CurveType, constant_price::ConstantPriceCurve,
-            constant_product::ConstantProductCurve, offset::OffsetCurve,
-        },
-        instruction::{
-            deposit_all_token_types, deposit_single_token_type_exact_amount_in, initialize, swap,
-            withdraw_all_token_types, withdraw_single_token_type_exact_amount_out,
-        },
-    };
-    use solana_program::{instruction::Instruction, program_stubs, rent::Rent};
-    use solana_sdk::account::{create_account_for_test, create_is_signer_account_infos, Account};
-    use spl_token::{
-        error::TokenError,
-        instruction::{
-            approve, initialize_account, initialize_mint, mint_to, revoke, set_authority,
-            AuthorityType,
-        },
-    };
-    use std::sync::Arc;
 
-    // Test program id for the swap program.
-    const SWAP_PROGRAM_ID: Pubkey = Pubkey::new_from_array([2u8; 32]);
+        token_swap.is_paused = is_paused;
+        SwapVersion::pack_latest(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
 
-    struct TestSyscallStubs {}
-    impl program_stubs::SyscallStubs for TestSyscallStubs {
-        fn sol_invoke_signed(
-            &self,
-            instruction: &Instruction,
-            account_infos: &[AccountInfo],
-            signers_seeds: &[&[&[u8]]],
-        ) -> ProgramResult {
-            msg!("TestSyscallStubs::sol_invoke_signed()");
+    /// Processes a [SetDynamicFees](enum.Instruction.html).
+    pub fn process_set_dynamic_fees(
+        program_id: &Pubkey,
+        dynamic_fees: DynamicFees,
+        accounts: &[AccountInfo],
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let pool_fee_account_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
 
-            let mut new_account_infos = vec![];
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut token_swap = SwapVersion::unpack_latest(&swap_info.data.borrow())?;
+        if *pool_fee_account_info.key != token_swap.pool_fee_account {
+            return Err(SwapError::IncorrectFeeAccount.into());
+        }
+        let pool_fee_account =
+            Self::unpack_token_account(pool_fee_account_info, &token_swap.token_program_id)?;
+        if !owner_info.is_signer || pool_fee_account.owner != *owner_info.key {
+            return Err(SwapError::InvalidFeeAccountOwner.into());
+        }
 
-            // mimic check for token program in accounts
-            if !account_infos.iter().any(|x| *x.key == spl_token::id()) {
-                return Err(ProgramError::InvalidAccountData);
+        if let Some(swap_constraints) = swap_constraints {
+            let owner_key = swap_constraints
+                .owner_key
+                .parse::<Pubkey>()
+                .map_err(|_| SwapError::InvalidOwner)?;
+            if *owner_info.key != owner_key {
+                return Err(SwapError::InvalidFeeAccountOwner.into());
             }
+            swap_constraints.validate_dynamic_fees(&dynamic_fees)?;
+        }
+        dynamic_fees.validate(&token_swap.fees)?;
 
-            for meta in instruction.accounts.iter() {
-                for account_info in account_infos.iter() {
-                    if meta.pubkey == *account_info.key {
-                        let mut new_account_info = account_info.clone();
-                        for seeds in signers_seeds.iter() {
-                            let signer =
-                                Pubkey::create_program_address(seeds, &SWAP_PROGRAM_ID).unwrap();
-                            if *account_info.key == signer {
-                                new_account_info.is_signer = true;
-                            }
-                        }
-                        new_account_infos.push(new_account_info);
-                    }
-                }
-            }
+        token_swap.dynamic_fees = dynamic_fees;
+        SwapVersion::pack_latest(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
 
-            spl_token::processor::Processor::process(
-                &instruction.program_id,
-                &new_account_infos,
-                &instruction.data,
-            )
+    /// Checks that a token mint of a concentrated or multi-asset pool can be
+    /// used. These pools price on the amounts received by the pool, so mints
+    /// withholding transfer fees are not supported.
+    fn check_concentrated_mint_extensions(mint_info: &AccountInfo) -> ProgramResult {
+        Self::check_token_mint_extensions(mint_info)?;
+        let mint_data = mint_info.data.borrow();
+        let mint =
+            StateWithExtensions::<Mint>::unpack(&mint_data).map_err(|_| SwapError::ExpectedMint)?;
+        if mint.get_extension::<TransferFeeConfig>().is_ok() {
+            return Err(SwapError::UnsupportedTokenExtension.into());
         }
+        Ok(())
     }
 
-    fn test_syscall_stubs() {
-        use std::sync::Once;
-        static ONCE: Once = Once::new();
+    #[allow(clippy::too_many_arguments)]
+    fn check_concentrated_accounts(
+        pool: &ConcentratedPool,
+        program_id: &Pubkey,
+        pool_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        token_a_info: &AccountInfo,
+        token_b_info: &AccountInfo,
+        token_a_mint_info: &AccountInfo,
+        token_b_mint_info: &AccountInfo,
+        token_a_program_info: &AccountInfo,
+        token_b_program_info: &AccountInfo,
+    ) -> ProgramResult {
+        if *authority_info.key != Self::authority_id(program_id, pool_info.key, pool.bump_seed)? {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if *token_a_info.key != pool.token_a {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *token_b_info.key != pool.token_b {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        Self::check_token_mint(token_a_mint_info, token_a_program_info, &pool.token_a_mint)?;
+        Self::check_token_mint(token_b_mint_info, token_b_program_info, &pool.token_b_mint)?;
+        Ok(())
+    }
 
-        ONCE.call_once(|| {
-            program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs {}));
-        });
+    fn check_position(
+        position: &Position,
+        pool_info: &AccountInfo,
+        owner_info: &AccountInfo,
+    ) -> ProgramResult {
+        if position.pool != *pool_info.key {
+            return Err(SwapError::IncorrectPosition.into());
+        }
+        if !owner_info.is_signer || position.owner != *owner_info.key {
+            return Err(SwapError::InvalidPositionOwner.into());
+        }
+        Ok(())
     }
 
-    struct SwapAccountInfo {
-        bump_seed: u8,
-        authority_key: Pubkey,
+    /// Processes an [InitializeConcentratedPool](enum.Instruction.html).
+    pub fn process_initialize_concentrated_pool(
+        program_id: &Pubkey,
         fees: Fees,
-        swap_curve: SwapCurve,
-        swap_key: Pubkey,
-        swap_account: Account,
-        pool_mint_key: Pubkey,
-        pool_mint_account: Account,
-        pool_fee_key: Pubkey,
-        pool_fee_account: Account,
-        pool_token_key: Pubkey,
-        pool_token_account: Account,
-        token_a_key: Pubkey,
-        token_a_account: Account,
-        token_a_mint_key: Pubkey,
-        token_a_mint_account: Account,
-        token_b_key: Pubkey,
-        token_b_account: Account,
-        token_b_mint_key: Pubkey,
-        token_b_mint_account: Account,
-    }
-
-    impl SwapAccountInfo {
-        pub fn new(
-            user_key: &Pubkey,
-            fees: Fees,
-            swap_curve: SwapCurve,
-            token_a_amount: u64,
-            token_b_amount: u64,
-        ) -> Self {
-            let swap_key = Pubkey::new_unique();
-            let swap_account = Account::new(0, SwapVersion::LATEST_LEN, &SWAP_PROGRAM_ID);
-            let (authority_key, bump_seed) =
-                Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
-
-            let (pool_mint_key, mut pool_mint_account) =
-                create_mint(&spl_token::id(), &authority_key, None);
-            let (pool_token_key, pool_token_account) = mint_token(
-                &spl_token::id(),
-                &pool_mint_key,
-                &mut pool_mint_account,
-                &authority_key,
-                user_key,
-                0,
-            );
-            let (pool_fee_key, pool_fee_account) = mint_token(
-                &spl_token::id(),
-                &pool_mint_key,
-                &mut pool_mint_account,
-                &authority_key,
-                user_key,
-                0,
-            );
-            let (token_a_mint_key, mut token_a_mint_account) =
-                create_mint(&spl_token::id(), user_key, None);
-            let (token_a_key, token_a_account) = mint_token(
-                &spl_token::id(),
-                &token_a_mint_key,
-                &mut token_a_mint_account,
-                user_key,
-                &authority_key,
-                token_a_amount,
-            );
-            let (token_b_mint_key, mut token_b_mint_account) =
-                create_mint(&spl_token::id(), user_key, None);
-            let (token_b_key, token_b_account) = mint_token(
-                &spl_token::id(),
-                &token_b_mint_key,
-                &mut token_b_mint_account,
-                user_key,
-                &authority_key,
-                token_b_amount,
-            );
+        tick_spacing: u16,
+        initial_sqrt_price: u128,
+        accounts: &[AccountInfo],
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let token_a_mint_info = next_account_info(account_info_iter)?;
+        let token_b_mint_info = next_account_info(account_info_iter)?;
+        let protocol_fee_owner_info = next_account_info(account_info_iter)?;
 
-            SwapAccountInfo {
-                bump_seed,
-                authority_key,
-                fees,
-                swap_curve,
-                swap_key,
-                swap_account,
-                pool_mint_key,
-                pool_mint_account,
-                pool_fee_key,
-                pool_fee_account,
-                pool_token_key,
-                pool_token_account,
-                token_a_key,
-                token_a_account,
-                token_a_mint_key,
-                token_a_mint_account,
-                token_b_key,
-                token_b_account,
-                token_b_mint_key,
-                token_b_mint_account,
-            }
+        if pool_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
         }
-
-        pub fn initialize_swap(&mut self) -> ProgramResult {
-            do_process_instruction(
-                initialize(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &self.swap_key,
-                    &self.authority_key,
-                    &self.token_a_key,
-                    &self.token_b_key,
-                    &self.pool_mint_key,
-                    &self.pool_fee_key,
-                    &self.pool_token_key,
-                    self.fees.clone(),
-                    self.swap_curve.clone(),
-                )
-                .unwrap(),
-                vec![
-                    &mut self.swap_account,
-                    &mut Account::default(),
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    &mut self.pool_mint_account,
-                    &mut self.pool_fee_account,
-                    &mut self.pool_token_account,
-                    &mut Account::default(),
-                ],
-            )
+        if is_tagged_account(&pool_info.data.borrow())
+            || SwapVersion::is_initialized(&pool_info.data.borrow())
+        {
+            return Err(SwapError::AlreadyInUse.into());
         }
 
-        pub fn setup_token_accounts(
-            &mut self,
-            mint_owner: &Pubkey,
-            account_owner: &Pubkey,
-            a_amount: u64,
-            b_amount: u64,
-            pool_amount: u64,
-        ) -> (Pubkey, Account, Pubkey, Account, Pubkey, Account) {
-            let (token_a_key, token_a_account) = mint_token(
-                &spl_token::id(),
-                &self.token_a_mint_key,
-                &mut self.token_a_mint_account,
-                mint_owner,
-                account_owner,
-                a_amount,
-            );
-            let (token_b_key, token_b_account) = mint_token(
-                &spl_token::id(),
-                &self.token_b_mint_key,
-                &mut self.token_b_mint_account,
-                mint_owner,
-                account_owner,
-                b_amount,
-            );
-            let (pool_key, pool_account) = mint_token(
-                &spl_token::id(),
-                &self.pool_mint_key,
-                &mut self.pool_mint_account,
-                &self.authority_key,
-                account_owner,
-                pool_amount,
-            );
-            (
-                token_a_key,
-                token_a_account,
-                token_b_key,
-                token_b_account,
-                pool_key,
-                pool_account,
-            )
+        let (pool_authority, bump_seed) =
+            Pubkey::find_program_address(&[&pool_info.key.to_bytes()], program_id);
+        if *authority_info.key != pool_authority {
+            return Err(SwapError::InvalidProgramAddress.into());
         }
-
-        fn get_token_account(&self, account_key: &Pubkey) -> &Account {
-            if *account_key == self.token_a_key {
-                return &self.token_a_account;
-            } else if *account_key == self.token_b_key {
-                return &self.token_b_account;
-            }
-            panic!("Could not find matching swap token account");
+        check_spl_token_program_account(token_a_info.owner)
+            .map_err(|_| SwapError::IncorrectTokenProgramId)?;
+        check_spl_token_program_account(token_b_info.owner)
+            .map_err(|_| SwapError::IncorrectTokenProgramId)?;
+        let token_a = Self::unpack_token_account(token_a_info, token_a_info.owner)?;
+        let token_b = Self::unpack_token_account(token_b_info, token_b_info.owner)?;
+        if *authority_info.key != token_a.owner {
+            return Err(SwapError::InvalidOwner.into());
+        }
+        if *authority_info.key != token_b.owner {
+            return Err(SwapError::InvalidOwner.into());
+        }
+        if token_a.mint == token_b.mint {
+            return Err(SwapError::RepeatedMint.into());
+        }
+        if *token_a_mint_info.key != token_a.mint || *token_b_mint_info.key != token_b.mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+        Self::check_concentrated_mint_extensions(token_a_mint_info)?;
+        Self::check_concentrated_mint_extensions(token_b_mint_info)?;
+        Self::check_token_account_extensions(token_a_info)?;
+        Self::check_token_account_extensions(token_b_info)?;
+        if token_a.delegate.is_some() {
+            return Err(SwapError::InvalidDelegate.into());
+        }
+        if token_b.delegate.is_some() {
+            return Err(SwapError::InvalidDelegate.into());
+        }
+        if token_a.close_authority.is_some() {
+            return Err(SwapError::InvalidCloseAuthority.into());
+        }
+        if token_b.close_authority.is_some() {
+            return Err(SwapError::InvalidCloseAuthority.into());
         }
 
-        fn set_token_account(&mut self, account_key: &Pubkey, account: Account) {
-            if *account_key == self.token_a_key {
-                self.token_a_account = account;
-                return;
-            } else if *account_key == self.token_b_key {
-                self.token_b_account = account;
-                return;
+        if let Some(swap_constraints) = swap_constraints {
+            let owner_key = swap_constraints
+                .owner_key
+                .parse::<Pubkey>()
+                .map_err(|_| SwapError::InvalidOwner)?;
+            if *protocol_fee_owner_info.key != owner_key {
+                return Err(SwapError::InvalidOwner.into());
             }
-            panic!("Could not find matching swap token account");
+            swap_constraints.validate_fees(&fees)?;
+        }
+        fees.validate()?;
+        total_trade_fee(&fees).ok_or(SwapError::InvalidFee)?;
+        if tick_spacing == 0 {
+            return Err(SwapError::InvalidCurve.into());
         }
+        let tick_current = tick_at_sqrt_price(initial_sqrt_price).ok_or(SwapError::InvalidCurve)?;
 
-        #[allow(clippy::too_many_arguments)]
-        pub fn swap(
-            &mut self,
-            user_key: &Pubkey,
-            user_source_key: &Pubkey,
-            user_source_account: &mut Account,
-            swap_source_key: &Pubkey,
-            swap_destination_key: &Pubkey,
-            user_destination_key: &Pubkey,
-            user_destination_account: &mut Account,
-            amount_in: u64,
-            minimum_amount_out: u64,
-        ) -> ProgramResult {
-            let user_transfer_key = Pubkey::new_unique();
-            // approve moving from user source account
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    user_source_key,
-                    &user_transfer_key,
-                    user_key,
-                    &[],
-                    amount_in,
-                )
-                .unwrap(),
-                vec![
-                    user_source_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
+        let pool = ConcentratedPool {
+            bump_seed,
+            token_a: *token_a_info.key,
+            token_b: *token_b_info.key,
+            token_a_mint: token_a.mint,
+            token_b_mint: token_b.mint,
+            protocol_fee_owner: *protocol_fee_owner_info.key,
+            fees,
+            tick_spacing,
+            tick_current,
+            sqrt_price: initial_sqrt_price,
+            ..ConcentratedPool::default()
+        };
+        ConcentratedPool::pack(pool, &mut pool_info.data.borrow_mut())?;
+        Ok(())
+    }
 
-            let mut swap_source_account = self.get_token_account(swap_source_key).clone();
-            let mut swap_destination_account = self.get_token_account(swap_destination_key).clone();
-
-            // perform the swap
-            do_process_instruction(
-                swap(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &self.swap_key,
-                    &self.authority_key,
-                    &user_transfer_key,
-                    user_source_key,
-                    swap_source_key,
-                    swap_destination_key,
-                    user_destination_key,
-                    &self.pool_mint_key,
-                    &self.pool_fee_key,
-                    None,
-                    Swap {
-                        amount_in,
-                        minimum_amount_out,
-                    },
-                )
-                .unwrap(),
-                vec![
-                    &mut self.swap_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                    user_source_account,
-                    &mut swap_source_account,
-                    &mut swap_destination_account,
-                    user_destination_account,
-                    &mut self.pool_mint_account,
-                    &mut self.pool_fee_account,
-                    &mut Account::default(),
-                ],
-            )?;
-
-            self.set_token_account(swap_source_key, swap_source_account);
-            self.set_token_account(swap_destination_key, swap_destination_account);
+    /// Processes an [OpenPosition](enum.Instruction.html).
+    pub fn process_open_position(
+        program_id: &Pubkey,
+        tick_lower: i32,
+        tick_upper: i32,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let position_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
 
-            Ok(())
+        if pool_info.owner != program_id || position_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let pool = ConcentratedPool::unpack(&pool_info.data.borrow())?;
+        if !position_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if is_tagged_account(&position_info.data.borrow()) {
+            return Err(SwapError::AlreadyInUse.into());
+        }
+        if !owner_info.is_signer {
+            return Err(SwapError::InvalidPositionOwner.into());
+        }
+        pool.check_tick(tick_lower)?;
+        pool.check_tick(tick_upper)?;
+        if tick_lower >= tick_upper {
+            return Err(SwapError::InvalidTick.into());
         }
 
-        #[allow(clippy::too_many_arguments)]
-        pub fn deposit_all_token_types(
-            &mut self,
-            depositor_key: &Pubkey,
-            depositor_token_a_key: &Pubkey,
-            depositor_token_a_account: &mut Account,
-            depositor_token_b_key: &Pubkey,
-            depositor_token_b_account: &mut Account,
-            depositor_pool_key: &Pubkey,
-            depositor_pool_account: &mut Account,
-            pool_token_amount: u64,
-            maximum_token_a_amount: u64,
-            maximum_token_b_amount: u64,
-        ) -> ProgramResult {
-            let user_transfer_authority = Pubkey::new_unique();
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    depositor_token_a_key,
-                    &user_transfer_authority,
-                    depositor_key,
-                    &[],
-                    maximum_token_a_amount,
-                )
-                .unwrap(),
-                vec![
-                    depositor_token_a_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
+        let position = Position {
+            pool: *pool_info.key,
+            owner: *owner_info.key,
+            tick_lower,
+            tick_upper,
+            ..Position::default()
+        };
+        Position::pack(position, &mut position_info.data.borrow_mut())?;
+        Ok(())
+    }
 
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    depositor_token_b_key,
-                    &user_transfer_authority,
-                    depositor_key,
-                    &[],
-                    maximum_token_b_amount,
-                )
-                .unwrap(),
-                vec![
-                    depositor_token_b_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
+    /// Processes an [IncreaseLiquidity](enum.Instruction.html).
+    pub fn process_increase_liquidity(
+        program_id: &Pubkey,
+        liquidity: u128,
+        maximum_token_a_amount: u64,
+        maximum_token_b_amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let position_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let source_a_info = next_account_info(account_info_iter)?;
+        let source_b_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let token_a_mint_info = next_account_info(account_info_iter)?;
+        let token_b_mint_info = next_account_info(account_info_iter)?;
+        let token_a_program_info = next_account_info(account_info_iter)?;
+        let token_b_program_info = next_account_info(account_info_iter)?;
 
-            do_process_instruction(
-                deposit_all_token_types(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &self.swap_key,
-                    &self.authority_key,
-                    &user_transfer_authority,
-                    depositor_token_a_key,
-                    depositor_token_b_key,
-                    &self.token_a_key,
-                    &self.token_b_key,
-                    &self.pool_mint_key,
-                    depositor_pool_key,
-                    DepositAllTokenTypes {
-                        pool_token_amount,
-                        maximum_token_a_amount,
-                        maximum_token_b_amount,
-                    },
-                )
-                .unwrap(),
-                vec![
-                    &mut self.swap_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                    depositor_token_a_account,
-                    depositor_token_b_account,
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    &mut self.pool_mint_account,
-                    depositor_pool_account,
-                    &mut Account::default(),
-                ],
-            )
+        if pool_info.owner != program_id || position_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut pool = ConcentratedPool::unpack(&pool_info.data.borrow())?;
+        let mut position = Position::unpack(&position_info.data.borrow())?;
+        Self::check_concentrated_accounts(
+            &pool,
+            program_id,
+            pool_info,
+            authority_info,
+            token_a_info,
+            token_b_info,
+            token_a_mint_info,
+            token_b_mint_info,
+            token_a_program_info,
+            token_b_program_info,
+        )?;
+        Self::check_position(&position, pool_info, owner_info)?;
+        if source_a_info.key == token_a_info.key || source_b_info.key == token_b_info.key {
+            return Err(SwapError::InvalidInput.into());
         }
 
-        #[allow(clippy::too_many_arguments)]
-        pub fn withdraw_all_token_types(
-            &mut self,
-            user_key: &Pubkey,
-            pool_key: &Pubkey,
-            pool_account: &mut Account,
-            token_a_key: &Pubkey,
-            token_a_account: &mut Account,
-            token_b_key: &Pubkey,
-            token_b_account: &mut Account,
-            pool_token_amount: u64,
-            minimum_token_a_amount: u64,
-            minimum_token_b_amount: u64,
-        ) -> ProgramResult {
-            let user_transfer_authority_key = Pubkey::new_unique();
-            // approve user transfer authority to take out pool tokens
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    pool_key,
-                    &user_transfer_authority_key,
-                    user_key,
-                    &[],
-                    pool_token_amount,
-                )
-                .unwrap(),
-                vec![
-                    pool_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
+        let liquidity_delta =
+            i128::try_from(liquidity).map_err(|_| SwapError::ConversionFailure)?;
+        let results = pool.modify_position(&mut position, liquidity_delta)?;
+        let token_a_amount = to_u64(results.token_a_amount)?;
+        let token_b_amount = to_u64(results.token_b_amount)?;
+        if token_a_amount == 0 && token_b_amount == 0 {
+            return Err(SwapError::ZeroTradingTokens.into());
+        }
+        if token_a_amount > maximum_token_a_amount {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+        if token_b_amount > maximum_token_b_amount {
+            return Err(SwapError::ExceededSlippage.into());
+        }
 
-            // withdraw token a and b correctly
-            do_process_instruction(
-                withdraw_all_token_types(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &self.swap_key,
-                    &self.authority_key,
-                    &user_transfer_authority_key,
-                    &self.pool_mint_key,
-                    &self.pool_fee_key,
-                    pool_key,
-                    &self.token_a_key,
-                    &self.token_b_key,
-                    token_a_key,
-                    token_b_key,
-                    WithdrawAllTokenTypes {
-                        pool_token_amount,
-                        minimum_token_a_amount,
-                        minimum_token_b_amount,
-                    },
-                )
-                .unwrap(),
-                vec![
-                    &mut self.swap_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                    &mut self.pool_mint_account,
-                    pool_account,
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    token_a_account,
-                    token_b_account,
-                    &mut self.pool_fee_account,
-                    &mut Account::default(),
-                ],
-            )
+        if token_a_amount > 0 {
+            let token_a_mint = Self::unpack_mint(token_a_mint_info, token_a_program_info.key)?;
+            Self::token_transfer(
+                pool_info.key,
+                token_a_program_info.clone(),
+                source_a_info.clone(),
+                token_a_mint_info.clone(),
+                token_a_info.clone(),
+                user_transfer_authority_info.clone(),
+                pool.bump_seed,
+                token_a_amount,
+                token_a_mint.decimals,
+            )?;
+        }
+        if token_b_amount > 0 {
+            let token_b_mint = Self::unpack_mint(token_b_mint_info, token_b_program_info.key)?;
+            Self::token_transfer(
+                pool_info.key,
+                token_b_program_info.clone(),
+                source_b_info.clone(),
+                token_b_mint_info.clone(),
+                token_b_info.clone(),
+                user_transfer_authority_info.clone(),
+                pool.bump_seed,
+                token_b_amount,
+                token_b_mint.decimals,
+            )?;
         }
 
-        #[allow(clippy::too_many_arguments)]
-        pub fn deposit_single_token_type_exact_amount_in(
-            &mut self,
-            depositor_key: &Pubkey,
-            deposit_account_key: &Pubkey,
-            deposit_token_account: &mut Account,
-            deposit_pool_key: &Pubkey,
-            deposit_pool_account: &mut Account,
-            source_token_amount: u64,
-            minimum_pool_token_amount: u64,
-        ) -> ProgramResult {
-            let user_transfer_authority_key = Pubkey::new_unique();
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    deposit_account_key,
-                    &user_transfer_authority_key,
-                    depositor_key,
-                    &[],
-                    source_token_amount,
-                )
-                .unwrap(),
-                vec![
-                    deposit_token_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
+        ConcentratedPool::pack(pool, &mut pool_info.data.borrow_mut())?;
+        Position::pack(position, &mut position_info.data.borrow_mut())?;
+        Ok(())
+    }
 
-            do_process_instruction(
-                deposit_single_token_type_exact_amount_in(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &self.swap_key,
-                    &self.authority_key,
-                    &user_transfer_authority_key,
-                    deposit_account_key,
-                    &self.token_a_key,
-                    &self.token_b_key,
-                    &self.pool_mint_key,
-                    deposit_pool_key,
-                    DepositSingleTokenTypeExactAmountIn {
-                        source_token_amount,
-                        minimum_pool_token_amount,
-                    },
-                )
-                .unwrap(),
-                vec![
-                    &mut self.swap_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                    deposit_token_account,
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    &mut self.pool_mint_account,
-                    deposit_pool_account,
-                    &mut Account::default(),
-                ],
-            )
-        }
+    /// Processes a [DecreaseLiquidity](enum.Instruction.html).
+    pub fn process_decrease_liquidity(
+        program_id: &Pubkey,
+        liquidity: u128,
+        minimum_token_a_amount: u64,
+        minimum_token_b_amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let position_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let dest_token_a_info = next_account_info(account_info_iter)?;
+        let dest_token_b_info = next_account_info(account_info_iter)?;
+        let token_a_mint_info = next_account_info(account_info_iter)?;
+        let token_b_mint_info = next_account_info(account_info_iter)?;
+        let token_a_program_info = next_account_info(account_info_iter)?;
+        let token_b_program_info = next_account_info(account_info_iter)?;
 
-        #[allow(clippy::too_many_arguments)]
-        pub fn withdraw_single_token_type_exact_amount_out(
-            &mut self,
-            user_key: &Pubkey,
-            pool_key: &Pubkey,
-            pool_account: &mut Account,
-            destination_key: &Pubkey,
-            destination_account: &mut Account,
-            destination_token_amount: u64,
-            maximum_pool_token_amount: u64,
-        ) -> ProgramResult {
-            let user_transfer_authority_key = Pubkey::new_unique();
-            // approve user transfer authority to take out pool tokens
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    pool_key,
-                    &user_transfer_authority_key,
-                    user_key,
-                    &[],
-                    maximum_pool_token_amount,
-                )
-                .unwrap(),
-                vec![
-                    pool_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
+        if pool_info.owner != program_id || position_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut pool = ConcentratedPool::unpack(&pool_info.data.borrow())?;
+        let mut position = Position::unpack(&position_info.data.borrow())?;
+        Self::check_concentrated_accounts(
+            &pool,
+            program_id,
+            pool_info,
+            authority_info,
+            token_a_info,
+            token_b_info,
+            token_a_mint_info,
+            token_b_mint_info,
+            token_a_program_info,
+            token_b_program_info,
+        )?;
+        Self::check_position(&position, pool_info, owner_info)?;
+        if dest_token_a_info.key == token_a_info.key || dest_token_b_info.key == token_b_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
 
-            do_process_instruction(
-                withdraw_single_token_type_exact_amount_out(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &self.swap_key,
-                    &self.authority_key,
-                    &user_transfer_authority_key,
-                    &self.pool_mint_key,
-                    &self.pool_fee_key,
-                    pool_key,
-                    &self.token_a_key,
-                    &self.token_b_key,
-                    destination_key,
-                    WithdrawSingleTokenTypeExactAmountOut {
-                        destination_token_amount,
-                        maximum_pool_token_amount,
-                    },
-                )
-                .unwrap(),
-                vec![
-                    &mut self.swap_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                    &mut self.pool_mint_account,
-                    pool_account,
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    destination_account,
-                    &mut self.pool_fee_account,
-                    &mut Account::default(),
-                ],
-            )
+        let liquidity_delta = i128::try_from(liquidity)
+            .ok()
+            .and_then(i128::checked_neg)
+            .ok_or(SwapError::ConversionFailure)?;
+        let results = pool.modify_position(&mut position, liquidity_delta)?;
+        // The fees earned by the position are always withdrawn with its
+        // liquidity
+        let token_a_amount = to_u64(results.token_a_amount)?
+            .checked_add(position.fees_owed_a)
+            .ok_or(SwapError::CalculationFailure)?;
+        let token_b_amount = to_u64(results.token_b_amount)?
+            .checked_add(position.fees_owed_b)
+            .ok_or(SwapError::CalculationFailure)?;
+        position.fees_owed_a = 0;
+        position.fees_owed_b = 0;
+        if token_a_amount == 0 && token_b_amount == 0 {
+            return Err(SwapError::ZeroTradingTokens.into());
+        }
+        if token_a_amount < minimum_token_a_amount {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+        if token_b_amount < minimum_token_b_amount {
+            return Err(SwapError::ExceededSlippage.into());
         }
-    }
 
-    fn mint_minimum_balance() -> u64 {
-        Rent::default().minimum_balance(spl_token::state::Mint::get_packed_len())
-    }
+        if token_a_amount > 0 {
+            let token_a_mint = Self::unpack_mint(token_a_mint_info, token_a_program_info.key)?;
+            Self::token_transfer(
+                pool_info.key,
+                token_a_program_info.clone(),
+                token_a_info.clone(),
+                token_a_mint_info.clone(),
+                dest_token_a_info.clone(),
+                authority_info.clone(),
+                pool.bump_seed,
+                token_a_amount,
+                token_a_mint.decimals,
+            )?;
+        }
+        if token_b_amount > 0 {
+            let token_b_mint = Self::unpack_mint(token_b_mint_info, token_b_program_info.key)?;
+            Self::token_transfer(
+                pool_info.key,
+                token_b_program_info.clone(),
+                token_b_info.clone(),
+                token_b_mint_info.clone(),
+                dest_token_b_info.clone(),
+                authority_info.clone(),
+                pool.bump_seed,
+                token_b_amount,
+                token_b_mint.decimals,
+            )?;
+        }
 
-    fn account_minimum_balance() -> u64 {
-        Rent::default().minimum_balance(spl_token::state::Account::get_packed_len())
+        ConcentratedPool::pack(pool, &mut pool_info.data.borrow_mut())?;
+        Position::pack(position, &mut position_info.data.borrow_mut())?;
+        Ok(())
     }
 
-    fn do_process_instruction_with_fee_constraints(
-        instruction: Instruction,
-        accounts: Vec<&mut Account>,
-        swap_constraints: &Option<SwapConstraints>,
+    /// Processes a [SwapConcentrated](enum.Instruction.html).
+    pub fn process_swap_concentrated(
+        program_id: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        accounts: &[AccountInfo],
     ) -> ProgramResult {
-        test_syscall_stubs();
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let swap_destination_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let source_token_mint_info = next_account_info(account_info_iter)?;
+        let destination_token_mint_info = next_account_info(account_info_iter)?;
+        let source_token_program_info = next_account_info(account_info_iter)?;
+        let destination_token_program_info = next_account_info(account_info_iter)?;
 
-        // approximate the logic in the actual runtime which runs the instruction
-        // and only updates accounts if the instruction is successful
-        let mut account_clones = accounts.iter().map(|x| (*x).clone()).collect::<Vec<_>>();
-        let mut meta = instruction
-            .accounts
-            .iter()
-            .zip(account_clones.iter_mut())
-            .map(|(account_meta, account)| (&account_meta.pubkey, account_meta.is_signer, account))
-            .collect::<Vec<_>>();
-        let mut account_infos = create_is_signer_account_infos(&mut meta);
-        let res = if instruction.program_id == SWAP_PROGRAM_ID {
-            Processor::process_with_constraints(
-                &instruction.program_id,
-                &account_infos,
-                &instruction.data,
-                swap_constraints,
-            )
+        if pool_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut pool = ConcentratedPool::unpack(&pool_info.data.borrow())?;
+        if *authority_info.key != Self::authority_id(program_id, pool_info.key, pool.bump_seed)? {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        let trade_direction = if *swap_source_info.key == pool.token_a
+            && *swap_destination_info.key == pool.token_b
+        {
+            TradeDirection::AtoB
+        } else if *swap_source_info.key == pool.token_b
+            && *swap_destination_info.key == pool.token_a
+        {
+            TradeDirection::BtoA
         } else {
-            spl_token::processor::Processor::process(
-                &instruction.program_id,
-                &account_infos,
-                &instruction.data,
-            )
+            return Err(SwapError::IncorrectSwapAccount.into());
         };
-
-        if res.is_ok() {
-            let mut account_metas = instruction
-                .accounts
-                .iter()
-                .zip(accounts)
-                .map(|(account_meta, account)| (&account_meta.pubkey, account))
-                .collect::<Vec<_>>();
-            for account_info in account_infos.iter_mut() {
-                for account_meta in account_metas.iter_mut() {
-                    if account_info.key == account_meta.0 {
-                        let account = &mut account_meta.1;
-                        account.owner = *account_info.owner;
-                        account.lamports = **account_info.lamports.borrow();
-                        account.data = account_info.data.borrow().to_vec();
-                    }
-                }
-            }
+        if swap_source_info.key == source_info.key {
+            return Err(SwapError::InvalidInput.into());
         }
-        res
-    }
+        if swap_destination_info.key == destination_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let (source_mint, destination_mint) = match trade_direction {
+            TradeDirection::AtoB => (&pool.token_a_mint, &pool.token_b_mint),
+            TradeDirection::BtoA => (&pool.token_b_mint, &pool.token_a_mint),
+        };
+        Self::check_token_mint(
+            source_token_mint_info,
+            source_token_program_info,
+            source_mint,
+        )?;
+        Self::check_token_mint(
+            destination_token_mint_info,
+            destination_token_program_info,
+            destination_mint,
+        )?;
 
-    fn do_process_instruction(
-        instruction: Instruction,
-        accounts: Vec<&mut Account>,
-    ) -> ProgramResult {
-        do_process_instruction_with_fee_constraints(instruction, accounts, &SWAP_CONSTRAINTS)
-    }
+        let source_account =
+            Self::unpack_token_account(swap_source_info, source_token_program_info.key)?;
+        let dest_account =
+            Self::unpack_token_account(swap_destination_info, destination_token_program_info.key)?;
+        let source_token_mint =
+            Self::unpack_mint(source_token_mint_info, source_token_program_info.key)?;
+        let destination_token_mint = Self::unpack_mint(
+            destination_token_mint_info,
+            destination_token_program_info.key,
+        )?;
 
-    fn mint_token(
-        program_id: &Pubkey,
-        mint_key: &Pubkey,
-        mint_account: &mut Account,
-        mint_authority_key: &Pubkey,
-        account_owner_key: &Pubkey,
-        amount: u64,
-    ) -> (Pubkey, Account) {
-        let account_key = Pubkey::new_unique();
-        let mut account_account = Account::new(
-            account_minimum_balance(),
-            spl_token::state::Account::get_packed_len(),
-            program_id,
-        );
-        let mut mint_authority_account = Account::default();
-        let mut rent_sysvar_account = create_account_for_test(&Rent::free());
-
-        do_process_instruction(
-            initialize_account(program_id, &account_key, mint_key, account_owner_key).unwrap(),
-            vec![
-                &mut account_account,
-                mint_account,
-                &mut mint_authority_account,
-                &mut rent_sysvar_account,
-            ],
-        )
-        .unwrap();
-
-        if amount > 0 {
-            do_process_instruction(
-                mint_to(
-                    program_id,
-                    mint_key,
-                    &account_key,
-                    mint_authority_key,
-                    &[],
-                    amount,
-                )
-                .unwrap(),
-                vec![
-                    mint_account,
-                    &mut account_account,
-                    &mut mint_authority_account,
-                ],
+        let result = pool
+            .swap(
+                to_u128(amount_in)?,
+                to_u128(source_account.amount)?,
+                to_u128(dest_account.amount)?,
+                trade_direction,
             )
-            .unwrap();
+            .ok_or(SwapError::ZeroTradingTokens)?;
+        let source_amount = to_u64(result.source_amount_swapped)?;
+        let destination_amount = to_u64(result.destination_amount_swapped)?;
+        if destination_amount < minimum_amount_out {
+            return Err(SwapError::ExceededSlippage.into());
         }
 
-        (account_key, account_account)
+        Self::token_transfer(
+            pool_info.key,
+            source_token_program_info.clone(),
+            source_info.clone(),
+            source_token_mint_info.clone(),
+            swap_source_info.clone(),
+            user_transfer_authority_info.clone(),
+            pool.bump_seed,
+            source_amount,
+            source_token_mint.decimals,
+        )?;
+        Self::token_transfer(
+            pool_info.key,
+            destination_token_program_info.clone(),
+            swap_destination_info.clone(),
+            destination_token_mint_info.clone(),
+            destination_info.clone(),
+            authority_info.clone(),
+            pool.bump_seed,
+            destination_amount,
+            destination_token_mint.decimals,
+        )?;
+
+        ConcentratedPool::pack(pool, &mut pool_info.data.borrow_mut())?;
+        Ok(())
     }
 
-    fn create_mint(
+    /// Processes a [CollectProtocolFees](enum.Instruction.html).
+    pub fn process_collect_protocol_fees(
         program_id: &Pubkey,
-        authority_key: &Pubkey,
-        freeze_authority: Option<&Pubkey>,
-    ) -> (Pubkey, Account) {
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account = Account::new(
-            mint_minimum_balance(),
-            spl_token::state::Mint::get_packed_len(),
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let protocol_fee_owner_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let dest_token_a_info = next_account_info(account_info_iter)?;
+        let dest_token_b_info = next_account_info(account_info_iter)?;
+        let token_a_mint_info = next_account_info(account_info_iter)?;
+        let token_b_mint_info = next_account_info(account_info_iter)?;
+        let token_a_program_info = next_account_info(account_info_iter)?;
+        let token_b_program_info = next_account_info(account_info_iter)?;
+
+        if pool_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut pool = ConcentratedPool::unpack(&pool_info.data.borrow())?;
+        Self::check_concentrated_accounts(
+            &pool,
             program_id,
-        );
-        let mut rent_sysvar_account = create_account_for_test(&Rent::free());
+            pool_info,
+            authority_info,
+            token_a_info,
+            token_b_info,
+            token_a_mint_info,
+            token_b_mint_info,
+            token_a_program_info,
+            token_b_program_info,
+        )?;
+        if !protocol_fee_owner_info.is_signer
+            || *protocol_fee_owner_info.key != pool.protocol_fee_owner
+        {
+            return Err(SwapError::InvalidFeeAccountOwner.into());
+        }
+        if dest_token_a_info.key == token_a_info.key || dest_token_b_info.key == token_b_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
 
-        do_process_instruction(
-            initialize_mint(program_id, &mint_key, authority_key, freeze_authority, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar_account],
-        )
-        .unwrap();
+        if pool.protocol_fees_a > 0 {
+            let token_a_mint = Self::unpack_mint(token_a_mint_info, token_a_program_info.key)?;
+            Self::token_transfer(
+                pool_info.key,
+                token_a_program_info.clone(),
+                token_a_info.clone(),
+                token_a_mint_info.clone(),
+                dest_token_a_info.clone(),
+                authority_info.clone(),
+                pool.bump_seed,
+                pool.protocol_fees_a,
+                token_a_mint.decimals,
+            )?;
+        }
+        if pool.protocol_fees_b > 0 {
+            let token_b_mint = Self::unpack_mint(token_b_mint_info, token_b_program_info.key)?;
+            Self::token_transfer(
+                pool_info.key,
+                token_b_program_info.clone(),
+                token_b_info.clone(),
+                token_b_mint_info.clone(),
+                dest_token_b_info.clone(),
+                authority_info.clone(),
+                pool.bump_seed,
+                pool.protocol_fees_b,
+                token_b_mint.decimals,
+            )?;
+        }
 
-        (mint_key, mint_account)
+        pool.protocol_fees_a = 0;
+        pool.protocol_fees_b = 0;
+        ConcentratedPool::pack(pool, &mut pool_info.data.borrow_mut())?;
+        Ok(())
     }
 
-    #[test]
-    fn test_token_program_id_error() {
-        test_syscall_stubs();
-        let swap_key = Pubkey::new_unique();
-        let mut mint = (Pubkey::new_unique(), Account::default());
-        let mut destination = (Pubkey::new_unique(), Account::default());
-        let token_program = (spl_token::id(), Account::default());
-        let (authority_key, bump_seed) =
-            Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
-        let mut authority = (authority_key, Account::default());
-        let swap_bytes = swap_key.to_bytes();
-        let authority_signature_seeds = [&swap_bytes[..32], &[bump_seed]];
-        let signers = &[&authority_signature_seeds[..]];
-        let ix = mint_to(
-            &token_program.0,
-            &mint.0,
-            &destination.0,
-            &authority.0,
-            &[],
-            10,
-        )
-        .unwrap();
-        let mint = (&mut mint).into();
-        let destination = (&mut destination).into();
-        let authority = (&mut authority).into();
-
-        let err = invoke_signed(&ix, &[mint, destination, authority], signers).unwrap_err();
-        assert_eq!(err, ProgramError::InvalidAccountData);
+    #[allow(clippy::too_many_arguments)]
+    fn check_multi_asset_accounts(
+        pool: &MultiAssetPool,
+        program_id: &Pubkey,
+        pool_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        pool_mint_info: &AccountInfo,
+        pool_token_program_info: &AccountInfo,
+        pool_fee_account_info: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        if pool_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if *authority_info.key != Self::authority_id(program_id, pool_info.key, pool.bump_seed)? {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if *pool_mint_info.key != pool.pool_mint {
+            return Err(SwapError::IncorrectPoolMint.into());
+        }
+        if *pool_token_program_info.key != pool.token_program_id {
+            return Err(SwapError::IncorrectTokenProgramId.into());
+        }
+        if let Some(pool_fee_account_info) = pool_fee_account_info {
+            if *pool_fee_account_info.key != pool.pool_fee_account {
+                return Err(SwapError::IncorrectFeeAccount.into());
+            }
+        }
+        Ok(())
     }
 
-    #[test]
-    fn test_initialize() {
-        let user_key = Pubkey::new_unique();
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 2;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 10;
-        let owner_withdraw_fee_numerator = 1;
-        let owner_withdraw_fee_denominator = 5;
-        let host_fee_numerator = 20;
-        let host_fee_denominator = 100;
-        let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
-        };
+    /// Gets the token accounts of a multi-asset pool, given in the order of
+    /// the pool, along with their amounts
+    fn next_multi_asset_token_infos<'a, 'b: 'a, I: Iterator<Item = &'a AccountInfo<'b>>>(
+        pool: &MultiAssetPool,
+        account_info_iter: &mut I,
+    ) -> Result<(Vec<&'a AccountInfo<'b>>, Vec<u128>), ProgramError> {
+        let mut token_infos = Vec::with_capacity(pool.tokens.len());
+        let mut token_amounts = Vec::with_capacity(pool.tokens.len());
+        for token in pool.tokens.iter() {
+            let token_info = next_account_info(account_info_iter)?;
+            if *token_info.key != token.account {
+                return Err(SwapError::IncorrectSwapAccount.into());
+            }
+            let token_account = Self::unpack_token_account(token_info, token_info.owner)?;
+            token_infos.push(token_info);
+            token_amounts.push(to_u128(token_account.amount)?);
+        }
+        Ok((token_infos, token_amounts))
+    }
 
-        let token_a_amount = 1000;
-        let token_b_amount = 2000;
-        let pool_token_amount = 10;
-        let curve_type = CurveType::ConstantProduct;
-        let swap_curve = SwapCurve {
-            curve_type,
-            calculator: Arc::new(ConstantProductCurve {}),
-        };
+    /// Index of the token of a multi-asset pool with the given mint
+    fn multi_asset_token_index(
+        pool: &MultiAssetPool,
+        mint_info: &AccountInfo,
+    ) -> Result<usize, ProgramError> {
+        pool.tokens
+            .iter()
+            .position(|token| token.mint == *mint_info.key)
+            .ok_or_else(|| SwapError::IncorrectMint.into())
+    }
 
-        let mut accounts =
-            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+    /// Processes an [InitializeMultiAssetPool](enum.Instruction.html).
+    pub fn process_initialize_multi_asset_pool(
+        program_id: &Pubkey,
+        fees: Fees,
+        curve: MultiAssetCurve,
+        accounts: &[AccountInfo],
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let fee_account_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
 
-        // uninitialized token a account
-        {
-            let old_account = accounts.token_a_account;
-            accounts.token_a_account = Account::new(0, 0, &spl_token::id());
-            assert_eq!(
-                Err(SwapError::ExpectedAccount.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_a_account = old_account;
+        if pool_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
         }
-
-        // uninitialized token b account
+        if is_tagged_account(&pool_info.data.borrow())
+            || SwapVersion::is_initialized(&pool_info.data.borrow())
         {
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = Account::new(0, 0, &spl_token::id());
-            assert_eq!(
-                Err(SwapError::ExpectedAccount.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_b_account = old_account;
+            return Err(SwapError::AlreadyInUse.into());
         }
+        let token_program_id = *pool_token_program_info.key;
+        check_spl_token_program_account(&token_program_id)
+            .map_err(|_| SwapError::IncorrectTokenProgramId)?;
 
-        // uninitialized pool mint
-        {
-            let old_account = accounts.pool_mint_account;
-            accounts.pool_mint_account = Account::new(0, 0, &spl_token::id());
-            assert_eq!(
-                Err(SwapError::ExpectedMint.into()),
-                accounts.initialize_swap()
-            );
-            accounts.pool_mint_account = old_account;
+        let (pool_authority, bump_seed) =
+            Pubkey::find_program_address(&[&pool_info.key.to_bytes()], program_id);
+        if *authority_info.key != pool_authority {
+            return Err(SwapError::InvalidProgramAddress.into());
         }
-
-        // token A account owner is not swap authority
-        {
-            let (_token_a_key, token_a_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_a_mint_key,
-                &mut accounts.token_a_mint_account,
-                &user_key,
-                &user_key,
-                0,
-            );
-            let old_account = accounts.token_a_account;
-            accounts.token_a_account = token_a_account;
-            assert_eq!(
-                Err(SwapError::InvalidOwner.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_a_account = old_account;
+        curve.validate()?;
+
+        let mut tokens = Vec::with_capacity(curve.token_count());
+        for _ in 0..curve.token_count() {
+            let token_info = next_account_info(account_info_iter)?;
+            let token_mint_info = next_account_info(account_info_iter)?;
+            // Each token may be owned by either token program
+            check_spl_token_program_account(token_info.owner)
+                .map_err(|_| SwapError::IncorrectTokenProgramId)?;
+            let token = Self::unpack_token_account(token_info, token_info.owner)?;
+            if *authority_info.key != token.owner {
+                return Err(SwapError::InvalidOwner.into());
+            }
+            if *token_mint_info.key != token.mint {
+                return Err(SwapError::IncorrectMint.into());
+            }
+            if tokens
+                .iter()
+                .any(|other: &MultiAssetToken| other.mint == token.mint)
+            {
+                return Err(SwapError::RepeatedMint.into());
+            }
+            Self::check_concentrated_mint_extensions(token_mint_info)?;
+            Self::check_token_account_extensions(token_info)?;
+            if token.amount == 0 {
+                return Err(SwapError::EmptySupply.into());
+            }
+            if token.delegate.is_some() {
+                return Err(SwapError::InvalidDelegate.into());
+            }
+            if token.close_authority.is_some() {
+                return Err(SwapError::InvalidCloseAuthority.into());
+            }
+            tokens.push(MultiAssetToken {
+                account: *token_info.key,
+                mint: token.mint,
+            });
         }
 
-        // token B account owner is not swap authority
-        {
-            let (_token_b_key, token_b_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_b_mint_key,
-                &mut accounts.token_b_mint_account,
-                &user_key,
-                &user_key,
-                0,
-            );
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = token_b_account;
-            assert_eq!(
-                Err(SwapError::InvalidOwner.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_b_account = old_account;
+        let fee_account = Self::unpack_token_account(fee_account_info, &token_program_id)?;
+        let destination = Self::unpack_token_account(destination_info, &token_program_id)?;
+        let pool_mint = Self::unpack_mint(pool_mint_info, &token_program_id)?;
+        if *authority_info.key == destination.owner {
+            return Err(SwapError::InvalidOutputOwner.into());
         }
-
-        // pool token account owner is swap authority
-        {
-            let (_pool_token_key, pool_token_account) = mint_token(
-                &spl_token::id(),
-                &accounts.pool_mint_key,
-                &mut accounts.pool_mint_account,
-                &accounts.authority_key,
-                &accounts.authority_key,
-                0,
-            );
-            let old_account = accounts.pool_token_account;
-            accounts.pool_token_account = pool_token_account;
-            assert_eq!(
-                Err(SwapError::InvalidOutputOwner.into()),
-                accounts.initialize_swap()
-            );
-            accounts.pool_token_account = old_account;
+        if *authority_info.key == fee_account.owner {
+            return Err(SwapError::InvalidOutputOwner.into());
+        }
+        if COption::Some(*authority_info.key) != pool_mint.mint_authority {
+            return Err(SwapError::InvalidOwner.into());
+        }
+        if pool_mint.supply != 0 {
+            return Err(SwapError::InvalidSupply.into());
+        }
+        if pool_mint.freeze_authority.is_some() {
+            return Err(SwapError::InvalidFreezeAuthority.into());
+        }
+        if *pool_mint_info.key != fee_account.mint {
+            return Err(SwapError::IncorrectPoolMint.into());
         }
+        Self::check_pool_mint_extensions(pool_mint_info)?;
+        Self::check_token_account_extensions(fee_account_info)?;
 
-        // pool fee account owner is swap authority
-        {
-            let (_pool_fee_key, pool_fee_account) = mint_token(
-                &spl_token::id(),
-                &accounts.pool_mint_key,
-                &mut accounts.pool_mint_account,
-                &accounts.authority_key,
-                &accounts.authority_key,
-                0,
-            );
-            let old_account = accounts.pool_fee_account;
-            accounts.pool_fee_account = pool_fee_account;
-            assert_eq!(
-                Err(SwapError::InvalidOutputOwner.into()),
-                accounts.initialize_swap()
-            );
-            accounts.pool_fee_account = old_account;
+        if let Some(swap_constraints) = swap_constraints {
+            let owner_key = swap_constraints
+                .owner_key
+                .parse::<Pubkey>()
+                .map_err(|_| SwapError::InvalidOwner)?;
+            if fee_account.owner != owner_key {
+                return Err(SwapError::InvalidOwner.into());
+            }
+            swap_constraints.validate_fees(&fees)?;
         }
+        fees.validate()?;
 
-        // pool mint authority is not swap authority
-        {
-            let (_pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &user_key, None);
-            let old_mint = accounts.pool_mint_account;
-            accounts.pool_mint_account = pool_mint_account;
-            assert_eq!(
-                Err(SwapError::InvalidOwner.into()),
-                accounts.initialize_swap()
-            );
-            accounts.pool_mint_account = old_mint;
+        Self::token_mint_to(
+            pool_info.key,
+            pool_token_program_info.clone(),
+            pool_mint_info.clone(),
+            destination_info.clone(),
+            authority_info.clone(),
+            bump_seed,
+            to_u64(INITIAL_SWAP_POOL_AMOUNT)?,
+        )?;
+
+        let pool = MultiAssetPool {
+            bump_seed,
+            token_program_id,
+            pool_mint: *pool_mint_info.key,
+            pool_fee_account: *fee_account_info.key,
+            fees,
+            curve,
+            tokens,
+        };
+        MultiAssetPool::pack(pool, &mut pool_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [MultiAssetSwap](enum.Instruction.html).
+    pub fn process_multi_asset_swap(
+        program_id: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let pool_fee_account_info = next_account_info(account_info_iter)?;
+        let source_token_mint_info = next_account_info(account_info_iter)?;
+        let destination_token_mint_info = next_account_info(account_info_iter)?;
+        let source_token_program_info = next_account_info(account_info_iter)?;
+        let destination_token_program_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
+
+        if pool_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let pool = MultiAssetPool::unpack(&pool_info.data.borrow())?;
+        Self::check_multi_asset_accounts(
+            &pool,
+            program_id,
+            pool_info,
+            authority_info,
+            pool_mint_info,
+            pool_token_program_info,
+            Some(pool_fee_account_info),
+        )?;
+        let (token_infos, token_amounts) =
+            Self::next_multi_asset_token_infos(&pool, account_info_iter)?;
+        let source_index = Self::multi_asset_token_index(&pool, source_token_mint_info)?;
+        let destination_index = Self::multi_asset_token_index(&pool, destination_token_mint_info)?;
+        if source_index == destination_index {
+            return Err(SwapError::RepeatedMint.into());
         }
+        let swap_source_info = token_infos[source_index];
+        let swap_destination_info = token_infos[destination_index];
+        if swap_source_info.key == source_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if swap_destination_info.key == destination_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
+        Self::check_token_mint(
+            source_token_mint_info,
+            source_token_program_info,
+            &pool.tokens[source_index].mint,
+        )?;
+        Self::check_token_mint(
+            destination_token_mint_info,
+            destination_token_program_info,
+            &pool.tokens[destination_index].mint,
+        )?;
+        let source_token_mint =
+            Self::unpack_mint(source_token_mint_info, source_token_program_info.key)?;
+        let destination_token_mint = Self::unpack_mint(
+            destination_token_mint_info,
+            destination_token_program_info.key,
+        )?;
+        let pool_mint = Self::unpack_mint(pool_mint_info, &pool.token_program_id)?;
 
-        // pool mint token has freeze authority
-        {
-            let (_pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &accounts.authority_key, Some(&user_key));
-            let old_mint = accounts.pool_mint_account;
-            accounts.pool_mint_account = pool_mint_account;
-            assert_eq!(
-                Err(SwapError::InvalidFreezeAuthority.into()),
-                accounts.initialize_swap()
-            );
-            accounts.pool_mint_account = old_mint;
+        let result = pool
+            .curve
+            .swap(
+                to_u128(amount_in)?,
+                source_index,
+                destination_index,
+                &token_amounts,
+                &pool.fees,
+            )
+            .ok_or(SwapError::ZeroTradingTokens)?;
+        if result.destination_amount_swapped < to_u128(minimum_amount_out)? {
+            return Err(SwapError::ExceededSlippage.into());
         }
 
-        // token A account owned by wrong program
-        {
-            let (_token_a_key, mut token_a_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_a_mint_key,
-                &mut accounts.token_a_mint_account,
-                &user_key,
-                &accounts.authority_key,
-                token_a_amount,
-            );
-            token_a_account.owner = SWAP_PROGRAM_ID;
-            let old_account = accounts.token_a_account;
-            accounts.token_a_account = token_a_account;
-            assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_a_account = old_account;
+        Self::token_transfer(
+            pool_info.key,
+            source_token_program_info.clone(),
+            source_info.clone(),
+            source_token_mint_info.clone(),
+            swap_source_info.clone(),
+            user_transfer_authority_info.clone(),
+            pool.bump_seed,
+            to_u64(result.source_amount_swapped)?,
+            source_token_mint.decimals,
+        )?;
+
+        // The owner fee stays in the pool, and the fee account receives the
+        // pool tokens it is worth
+        let mut new_token_amounts = token_amounts;
+        new_token_amounts[source_index] = result
+            .new_swap_source_amount
+            .checked_sub(result.owner_fee)
+            .ok_or(SwapError::FeeCalculationFailure)?;
+        new_token_amounts[destination_index] = result.new_swap_destination_amount;
+        let pool_token_amount = pool
+            .curve
+            .deposit_single_token_type_without_fees(
+                result.owner_fee,
+                source_index,
+                &new_token_amounts,
+                to_u128(pool_mint.supply)?,
+            )
+            .ok_or(SwapError::FeeCalculationFailure)?;
+        if pool_token_amount > 0 {
+            Self::token_mint_to(
+                pool_info.key,
+                pool_token_program_info.clone(),
+                pool_mint_info.clone(),
+                pool_fee_account_info.clone(),
+                authority_info.clone(),
+                pool.bump_seed,
+                to_u64(pool_token_amount)?,
+            )?;
         }
 
-        // token B account owned by wrong program
-        {
-            let (_token_b_key, mut token_b_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_b_mint_key,
-                &mut accounts.token_b_mint_account,
-                &user_key,
-                &accounts.authority_key,
-                token_b_amount,
-            );
-            token_b_account.owner = SWAP_PROGRAM_ID;
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = token_b_account;
-            assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_b_account = old_account;
+        Self::token_transfer(
+            pool_info.key,
+            destination_token_program_info.clone(),
+            swap_destination_info.clone(),
+            destination_token_mint_info.clone(),
+            destination_info.clone(),
+            authority_info.clone(),
+            pool.bump_seed,
+            to_u64(result.destination_amount_swapped)?,
+            destination_token_mint.decimals,
+        )?;
+        Ok(())
+    }
+
+    /// Processes a [MultiAssetDepositAllTokenTypes](enum.Instruction.html).
+    pub fn process_multi_asset_deposit_all_token_types(
+        program_id: &Pubkey,
+        pool_token_amount: u64,
+        maximum_token_amounts: &[u64],
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
+
+        if pool_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let pool = MultiAssetPool::unpack(&pool_info.data.borrow())?;
+        Self::check_multi_asset_accounts(
+            &pool,
+            program_id,
+            pool_info,
+            authority_info,
+            pool_mint_info,
+            pool_token_program_info,
+            None,
+        )?;
+        if maximum_token_amounts.len() != pool.tokens.len() {
+            return Err(SwapError::InvalidInstruction.into());
+        }
+
+        let mut token_infos = Vec::with_capacity(pool.tokens.len());
+        let mut token_amounts = Vec::with_capacity(pool.tokens.len());
+        for token in pool.tokens.iter() {
+            let source_info = next_account_info(account_info_iter)?;
+            let token_info = next_account_info(account_info_iter)?;
+            let token_mint_info = next_account_info(account_info_iter)?;
+            let token_program_info = next_account_info(account_info_iter)?;
+            if *token_info.key != token.account {
+                return Err(SwapError::IncorrectSwapAccount.into());
+            }
+            if source_info.key == token_info.key {
+                return Err(SwapError::InvalidInput.into());
+            }
+            Self::check_token_mint(token_mint_info, token_program_info, &token.mint)?;
+            let token_account = Self::unpack_token_account(token_info, token_program_info.key)?;
+            token_amounts.push(to_u128(token_account.amount)?);
+            token_infos.push((source_info, token_info, token_mint_info, token_program_info));
         }
+        let pool_mint = Self::unpack_mint(pool_mint_info, &pool.token_program_id)?;
+        let pool_token_amount = to_u128(pool_token_amount)?;
 
-        // empty token A account
-        {
-            let (_token_a_key, token_a_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_a_mint_key,
-                &mut accounts.token_a_mint_account,
-                &user_key,
-                &accounts.authority_key,
-                0,
-            );
-            let old_account = accounts.token_a_account;
-            accounts.token_a_account = token_a_account;
-            assert_eq!(
-                Err(SwapError::EmptySupply.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_a_account = old_account;
+        let results = pool
+            .curve
+            .pool_tokens_to_trading_tokens(
+                pool_token_amount,
+                to_u128(pool_mint.supply)?,
+                &token_amounts,
+                RoundDirection::Ceiling,
+            )
+            .ok_or(SwapError::ZeroTradingTokens)?;
+        let mut transfer_amounts = Vec::with_capacity(results.len());
+        for (token_amount, maximum_token_amount) in results.iter().zip(maximum_token_amounts) {
+            let token_amount = to_u64(*token_amount)?;
+            if token_amount > *maximum_token_amount {
+                return Err(SwapError::ExceededSlippage.into());
+            }
+            if token_amount == 0 {
+                return Err(SwapError::ZeroTradingTokens.into());
+            }
+            transfer_amounts.push(token_amount);
         }
 
-        // empty token B account
+        for ((source_info, token_info, token_mint_info, token_program_info), amount) in
+            token_infos.into_iter().zip(transfer_amounts)
         {
-            let (_token_b_key, token_b_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_b_mint_key,
-                &mut accounts.token_b_mint_account,
-                &user_key,
-                &accounts.authority_key,
-                0,
-            );
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = token_b_account;
-            assert_eq!(
-                Err(SwapError::EmptySupply.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_b_account = old_account;
+            let token_mint = Self::unpack_mint(token_mint_info, token_program_info.key)?;
+            Self::token_transfer(
+                pool_info.key,
+                token_program_info.clone(),
+                source_info.clone(),
+                token_mint_info.clone(),
+                token_info.clone(),
+                user_transfer_authority_info.clone(),
+                pool.bump_seed,
+                amount,
+                token_mint.decimals,
+            )?;
         }
+        Self::token_mint_to(
+            pool_info.key,
+            pool_token_program_info.clone(),
+            pool_mint_info.clone(),
+            destination_info.clone(),
+            authority_info.clone(),
+            pool.bump_seed,
+            to_u64(pool_token_amount)?,
+        )?;
+        Ok(())
+    }
 
-        // invalid pool tokens
-        {
-            let old_mint = accounts.pool_mint_account;
-            let old_pool_account = accounts.pool_token_account;
+    /// Processes a [MultiAssetWithdrawAllTokenTypes](enum.Instruction.html).
+    pub fn process_multi_asset_withdraw_all_token_types(
+        program_id: &Pubkey,
+        pool_token_amount: u64,
+        minimum_token_amounts: &[u64],
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let pool_fee_account_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
 
-            let (_pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &accounts.authority_key, None);
-            accounts.pool_mint_account = pool_mint_account;
+        if pool_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let pool = MultiAssetPool::unpack(&pool_info.data.borrow())?;
+        Self::check_multi_asset_accounts(
+            &pool,
+            program_id,
+            pool_info,
+            authority_info,
+            pool_mint_info,
+            pool_token_program_info,
+            Some(pool_fee_account_info),
+        )?;
+        if minimum_token_amounts.len() != pool.tokens.len() {
+            return Err(SwapError::InvalidInstruction.into());
+        }
+
+        let mut token_infos = Vec::with_capacity(pool.tokens.len());
+        let mut token_amounts = Vec::with_capacity(pool.tokens.len());
+        for token in pool.tokens.iter() {
+            let token_info = next_account_info(account_info_iter)?;
+            let destination_info = next_account_info(account_info_iter)?;
+            let token_mint_info = next_account_info(account_info_iter)?;
+            let token_program_info = next_account_info(account_info_iter)?;
+            if *token_info.key != token.account {
+                return Err(SwapError::IncorrectSwapAccount.into());
+            }
+            if destination_info.key == token_info.key {
+                return Err(SwapError::InvalidInput.into());
+            }
+            Self::check_token_mint(token_mint_info, token_program_info, &token.mint)?;
+            let token_account = Self::unpack_token_account(token_info, token_program_info.key)?;
+            token_amounts.push(to_u128(token_account.amount)?);
+            token_infos.push((
+                token_info,
+                destination_info,
+                token_mint_info,
+                token_program_info,
+            ));
+        }
+        let pool_mint = Self::unpack_mint(pool_mint_info, &pool.token_program_id)?;
 
-            let (_empty_pool_token_key, empty_pool_token_account) = mint_token(
-                &spl_token::id(),
-                &accounts.pool_mint_key,
-                &mut accounts.pool_mint_account,
-                &accounts.authority_key,
-                &user_key,
-                0,
-            );
+        let withdraw_fee: u128 = if *pool_fee_account_info.key == *source_info.key {
+            // withdrawing from the fee account, don't assess withdraw fee
+            0
+        } else {
+            pool.fees
+                .owner_withdraw_fee(to_u128(pool_token_amount)?)
+                .ok_or(SwapError::FeeCalculationFailure)?
+        };
+        let pool_token_amount = to_u128(pool_token_amount)?
+            .checked_sub(withdraw_fee)
+            .ok_or(SwapError::CalculationFailure)?;
 
-            let (_pool_token_key, pool_token_account) = mint_token(
-                &spl_token::id(),
-                &accounts.pool_mint_key,
-                &mut accounts.pool_mint_account,
-                &accounts.authority_key,
-                &user_key,
+        let results = pool
+            .curve
+            .pool_tokens_to_trading_tokens(
                 pool_token_amount,
-            );
-
-            // non-empty pool token account
-            accounts.pool_token_account = pool_token_account;
-            assert_eq!(
-                Err(SwapError::InvalidSupply.into()),
-                accounts.initialize_swap()
-            );
-
-            // pool tokens already in circulation
-            accounts.pool_token_account = empty_pool_token_account;
-            assert_eq!(
-                Err(SwapError::InvalidSupply.into()),
-                accounts.initialize_swap()
-            );
-
-            accounts.pool_mint_account = old_mint;
-            accounts.pool_token_account = old_pool_account;
+                to_u128(pool_mint.supply)?,
+                &token_amounts,
+                RoundDirection::Floor,
+            )
+            .ok_or(SwapError::ZeroTradingTokens)?;
+        let mut transfer_amounts = Vec::with_capacity(results.len());
+        for ((token_amount, minimum_token_amount), swap_token_amount) in results
+            .iter()
+            .zip(minimum_token_amounts)
+            .zip(token_amounts.iter())
+        {
+            let token_amount = to_u64(std::cmp::min(*token_amount, *swap_token_amount))?;
+            if token_amount < *minimum_token_amount {
+                return Err(SwapError::ExceededSlippage.into());
+            }
+            if token_amount == 0 && *swap_token_amount != 0 {
+                return Err(SwapError::ZeroTradingTokens.into());
+            }
+            transfer_amounts.push(token_amount);
         }
 
-        // pool fee account has wrong mint
-        {
-            let (_pool_fee_key, pool_fee_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_a_mint_key,
-                &mut accounts.token_a_mint_account,
-                &user_key,
-                &user_key,
-                0,
-            );
-            let old_account = accounts.pool_fee_account;
-            accounts.pool_fee_account = pool_fee_account;
-            assert_eq!(
-                Err(SwapError::IncorrectPoolMint.into()),
-                accounts.initialize_swap()
-            );
-            accounts.pool_fee_account = old_account;
+        if withdraw_fee > 0 {
+            Self::token_transfer(
+                pool_info.key,
+                pool_token_program_info.clone(),
+                source_info.clone(),
+                pool_mint_info.clone(),
+                pool_fee_account_info.clone(),
+                user_transfer_authority_info.clone(),
+                pool.bump_seed,
+                to_u64(withdraw_fee)?,
+                pool_mint.decimals,
+            )?;
         }
+        Self::token_burn(
+            pool_info.key,
+            pool_token_program_info.clone(),
+            source_info.clone(),
+            pool_mint_info.clone(),
+            user_transfer_authority_info.clone(),
+            pool.bump_seed,
+            to_u64(pool_token_amount)?,
+        )?;
 
-        // token A account is delegated
+        for ((token_info, destination_info, token_mint_info, token_program_info), amount) in
+            token_infos.into_iter().zip(transfer_amounts)
         {
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    &accounts.token_a_key,
-                    &user_key,
-                    &accounts.authority_key,
-                    &[],
-                    1,
-                )
-                .unwrap(),
-                vec![
-                    &mut accounts.token_a_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
-            assert_eq!(
-                Err(SwapError::InvalidDelegate.into()),
-                accounts.initialize_swap()
-            );
-
-            do_process_instruction(
-                revoke(
-                    &spl_token::id(),
-                    &accounts.token_a_key,
-                    &accounts.authority_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut accounts.token_a_account, &mut Account::default()],
-            )
-            .unwrap();
+            if amount > 0 {
+                let token_mint = Self::unpack_mint(token_mint_info, token_program_info.key)?;
+                Self::token_transfer(
+                    pool_info.key,
+                    token_program_info.clone(),
+                    token_info.clone(),
+                    token_mint_info.clone(),
+                    destination_info.clone(),
+                    authority_info.clone(),
+                    pool.bump_seed,
+                    amount,
+                    token_mint.decimals,
+                )?;
+            }
         }
+        Ok(())
+    }
 
-        // token B account is delegated
-        {
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    &accounts.token_b_key,
-                    &user_key,
-                    &accounts.authority_key,
-                    &[],
-                    1,
-                )
-                .unwrap(),
-                vec![
-                    &mut accounts.token_b_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
-            assert_eq!(
-                Err(SwapError::InvalidDelegate.into()),
-                accounts.initialize_swap()
-            );
+    /// Processes a
+    /// [MultiAssetDepositSingleTokenTypeExactAmountIn](enum.Instruction.html).
+    pub fn process_multi_asset_deposit_single_token_type_exact_amount_in(
+        program_id: &Pubkey,
+        source_token_amount: u64,
+        minimum_pool_token_amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let source_token_mint_info = next_account_info(account_info_iter)?;
+        let source_token_program_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
 
-            do_process_instruction(
-                revoke(
-                    &spl_token::id(),
-                    &accounts.token_b_key,
-                    &accounts.authority_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut accounts.token_b_account, &mut Account::default()],
+        if pool_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let pool = MultiAssetPool::unpack(&pool_info.data.borrow())?;
+        Self::check_multi_asset_accounts(
+            &pool,
+            program_id,
+            pool_info,
+            authority_info,
+            pool_mint_info,
+            pool_token_program_info,
+            None,
+        )?;
+        let (token_infos, token_amounts) =
+            Self::next_multi_asset_token_infos(&pool, account_info_iter)?;
+        let token_index = Self::multi_asset_token_index(&pool, source_token_mint_info)?;
+        let swap_token_info = token_infos[token_index];
+        if swap_token_info.key == source_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
+        Self::check_token_mint(
+            source_token_mint_info,
+            source_token_program_info,
+            &pool.tokens[token_index].mint,
+        )?;
+        let source_token_mint =
+            Self::unpack_mint(source_token_mint_info, source_token_program_info.key)?;
+        let pool_mint = Self::unpack_mint(pool_mint_info, &pool.token_program_id)?;
+
+        let pool_token_amount = pool
+            .curve
+            .deposit_single_token_type(
+                to_u128(source_token_amount)?,
+                token_index,
+                &token_amounts,
+                to_u128(pool_mint.supply)?,
+                &pool.fees,
             )
-            .unwrap();
+            .ok_or(SwapError::ZeroTradingTokens)?;
+        let pool_token_amount = to_u64(pool_token_amount)?;
+        if pool_token_amount < minimum_pool_token_amount {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+        if pool_token_amount == 0 {
+            return Err(SwapError::ZeroTradingTokens.into());
         }
 
-        // token A account has close authority
-        {
-            do_process_instruction(
-                set_authority(
-                    &spl_token::id(),
-                    &accounts.token_a_key,
-                    Some(&user_key),
-                    AuthorityType::CloseAccount,
-                    &accounts.authority_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut accounts.token_a_account, &mut Account::default()],
-            )
-            .unwrap();
-            assert_eq!(
-                Err(SwapError::InvalidCloseAuthority.into()),
-                accounts.initialize_swap()
-            );
+        Self::token_transfer(
+            pool_info.key,
+            source_token_program_info.clone(),
+            source_info.clone(),
+            source_token_mint_info.clone(),
+            swap_token_info.clone(),
+            user_transfer_authority_info.clone(),
+            pool.bump_seed,
+            source_token_amount,
+            source_token_mint.decimals,
+        )?;
+        Self::token_mint_to(
+            pool_info.key,
+            pool_token_program_info.clone(),
+            pool_mint_info.clone(),
+            destination_info.clone(),
+            authority_info.clone(),
+            pool.bump_seed,
+            pool_token_amount,
+        )?;
+        Ok(())
+    }
 
-            do_process_instruction(
-                set_authority(
-                    &spl_token::id(),
-                    &accounts.token_a_key,
-                    None,
-                    AuthorityType::CloseAccount,
-                    &user_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut accounts.token_a_account, &mut Account::default()],
-            )
-            .unwrap();
+    /// Processes a
+    /// [MultiAssetWithdrawSingleTokenTypeExactAmountOut](enum.Instruction.html).
+    pub fn process_multi_asset_withdraw_single_token_type_exact_amount_out(
+        program_id: &Pubkey,
+        destination_token_amount: u64,
+        maximum_pool_token_amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pool_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let pool_fee_account_info = next_account_info(account_info_iter)?;
+        let destination_token_mint_info = next_account_info(account_info_iter)?;
+        let destination_token_program_info = next_account_info(account_info_iter)?;
+        let pool_token_program_info = next_account_info(account_info_iter)?;
+
+        if pool_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let pool = MultiAssetPool::unpack(&pool_info.data.borrow())?;
+        Self::check_multi_asset_accounts(
+            &pool,
+            program_id,
+            pool_info,
+            authority_info,
+            pool_mint_info,
+            pool_token_program_info,
+            Some(pool_fee_account_info),
+        )?;
+        let (token_infos, token_amounts) =
+            Self::next_multi_asset_token_infos(&pool, account_info_iter)?;
+        let token_index = Self::multi_asset_token_index(&pool, destination_token_mint_info)?;
+        let swap_token_info = token_infos[token_index];
+        if swap_token_info.key == destination_info.key {
+            return Err(SwapError::InvalidInput.into());
         }
+        Self::check_token_mint(
+            destination_token_mint_info,
+            destination_token_program_info,
+            &pool.tokens[token_index].mint,
+        )?;
+        let destination_token_mint = Self::unpack_mint(
+            destination_token_mint_info,
+            destination_token_program_info.key,
+        )?;
+        let pool_mint = Self::unpack_mint(pool_mint_info, &pool.token_program_id)?;
 
-        // token B account has close authority
-        {
-            do_process_instruction(
-                set_authority(
-                    &spl_token::id(),
-                    &accounts.token_b_key,
-                    Some(&user_key),
-                    AuthorityType::CloseAccount,
-                    &accounts.authority_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut accounts.token_b_account, &mut Account::default()],
+        let burn_pool_token_amount = pool
+            .curve
+            .withdraw_single_token_type_exact_out(
+                to_u128(destination_token_amount)?,
+                token_index,
+                &token_amounts,
+                to_u128(pool_mint.supply)?,
+                &pool.fees,
             )
-            .unwrap();
-            assert_eq!(
-                Err(SwapError::InvalidCloseAuthority.into()),
-                accounts.initialize_swap()
-            );
+            .ok_or(SwapError::ZeroTradingTokens)?;
 
-            do_process_instruction(
-                set_authority(
-                    &spl_token::id(),
-                    &accounts.token_b_key,
-                    None,
-                    AuthorityType::CloseAccount,
-                    &user_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut accounts.token_b_account, &mut Account::default()],
-            )
-            .unwrap();
-        }
+        let withdraw_fee: u128 = if *pool_fee_account_info.key == *source_info.key {
+            // withdrawing from the fee account, don't assess withdraw fee
+            0
+        } else {
+            pool.fees
+                .owner_withdraw_fee(burn_pool_token_amount)
+                .ok_or(SwapError::FeeCalculationFailure)?
+        };
+        let pool_token_amount = burn_pool_token_amount
+            .checked_add(withdraw_fee)
+            .ok_or(SwapError::CalculationFailure)?;
 
-        // wrong token program id
-        {
-            let wrong_program_id = Pubkey::new_unique();
-            assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
-                do_process_instruction(
-                    initialize(
-                        &SWAP_PROGRAM_ID,
-                        &wrong_program_id,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        &accounts.pool_token_key,
-                        accounts.fees.clone(),
-                        accounts.swap_curve.clone(),
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut accounts.pool_fee_account,
-                        &mut accounts.pool_token_account,
-                        &mut Account::default(),
-                    ],
-                )
-            );
+        if to_u64(pool_token_amount)? > maximum_pool_token_amount {
+            return Err(SwapError::ExceededSlippage.into());
         }
-
-        // create swap with same token A and B
-        {
-            let (_token_a_repeat_key, token_a_repeat_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_a_mint_key,
-                &mut accounts.token_a_mint_account,
-                &user_key,
-                &accounts.authority_key,
-                10,
-            );
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = token_a_repeat_account;
-            assert_eq!(
-                Err(SwapError::RepeatedMint.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_b_account = old_account;
+        if pool_token_amount == 0 {
+            return Err(SwapError::ZeroTradingTokens.into());
         }
 
-        // create valid swap
-        accounts.initialize_swap().unwrap();
-
-        // create invalid flat swap
-        {
-            let token_b_price = 0;
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let swap_curve = SwapCurve {
-                curve_type: CurveType::ConstantPrice,
-                calculator: Arc::new(ConstantPriceCurve { token_b_price }),
-            };
-            let mut accounts =
-                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
-            assert_eq!(
-                Err(SwapError::InvalidCurve.into()),
-                accounts.initialize_swap()
-            );
+        if withdraw_fee > 0 {
+            Self::token_transfer(
+                pool_info.key,
+                pool_token_program_info.clone(),
+                source_info.clone(),
+                pool_mint_info.clone(),
+                pool_fee_account_info.clone(),
+                user_transfer_authority_info.clone(),
+                pool.bump_seed,
+                to_u64(withdraw_fee)?,
+                pool_mint.decimals,
+            )?;
         }
+        Self::token_burn(
+            pool_info.key,
+            pool_token_program_info.clone(),
+            source_info.clone(),
+            pool_mint_info.clone(),
+            user_transfer_authority_info.clone(),
+            pool.bump_seed,
+            to_u64(burn_pool_token_amount)?,
+        )?;
 
-        // create valid flat swap
-        {
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let token_b_price = 10_000;
-            let swap_curve = SwapCurve {
-                curve_type: CurveType::ConstantPrice,
-                calculator: Arc::new(ConstantPriceCurve { token_b_price }),
-            };
-            let mut accounts =
-                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
-            accounts.initialize_swap().unwrap();
-        }
+        Self::token_transfer(
+            pool_info.key,
+            destination_token_program_info.clone(),
+            swap_token_info.clone(),
+            destination_token_mint_info.clone(),
+            destination_info.clone(),
+            authority_info.clone(),
+            pool.bump_seed,
+            destination_token_amount,
+            destination_token_mint.decimals,
+        )?;
+        Ok(())
+    }
 
-        // create invalid offset swap
-        {
-            let token_b_offset = 0;
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let swap_curve = SwapCurve {
-                curve_type: CurveType::Offset,
-                calculator: Arc::new(OffsetCurve { token_b_offset }),
-            };
-            let mut accounts =
-                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
-            assert_eq!(
-                Err(SwapError::InvalidCurve.into()),
-                accounts.initialize_swap()
-            );
-        }
+    /// Processes an [Instruction](enum.Instruction.html).
+    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
+        Self::process_with_constraints(program_id, accounts, input, &SWAP_CONSTRAINTS)
+    }
 
-        // create valid offset swap
-        {
-            let token_b_offset = 10;
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
+    /// Processes an instruction given extra constraint
+    pub fn process_with_constraints(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        input: &[u8],
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        let instruction = SwapInstruction::unpack(input)?;
+        match instruction {
+            SwapInstruction::Initialize(Initialize { fees, swap_curve }) => {
+                msg!("Instruction: Init");
+                Self::process_initialize(program_id, fees, swap_curve, accounts, swap_constraints)
+            }
+            SwapInstruction::Swap(Swap {
+                amount_in,
+                minimum_amount_out,
+            }) => {
+                msg!("Instruction: Swap");
+                Self::process_swap(program_id, amount_in, minimum_amount_out, accounts)
+            }
+            SwapInstruction::DepositAllTokenTypes(DepositAllTokenTypes {
+                pool_token_amount,
+                maximum_token_a_amount,
+                maximum_token_b_amount,
+            }) => {
+                msg!("Instruction: DepositAllTokenTypes");
+                Self::process_deposit_all_token_types(
+                    program_id,
+                    pool_token_amount,
+                    maximum_token_a_amount,
+                    maximum_token_b_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::WithdrawAllTokenTypes(WithdrawAllTokenTypes {
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+            }) => {
+                msg!("Instruction: WithdrawAllTokenTypes");
+                Self::process_withdraw_all_token_types(
+                    program_id,
+                    pool_token_amount,
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::DepositSingleTokenTypeExactAmountIn(
+                DepositSingleTokenTypeExactAmountIn {
+                    source_token_amount,
+                    minimum_pool_token_amount,
+                },
+            ) => {
+                msg!("Instruction: DepositSingleTokenTypeExactAmountIn");
+                Self::process_deposit_single_token_type_exact_amount_in(
+                    program_id,
+                    source_token_amount,
+                    minimum_pool_token_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::WithdrawSingleTokenTypeExactAmountOut(
+                WithdrawSingleTokenTypeExactAmountOut {
+                    destination_token_amount,
+                    maximum_pool_token_amount,
+                },
+            ) => {
+                msg!("Instruction: WithdrawSingleTokenTypeExactAmountOut");
+                Self::process_withdraw_single_token_type_exact_amount_out(
+                    program_id,
+                    destination_token_amount,
+                    maximum_pool_token_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::Pause => {
+                msg!("Instruction: Pause");
+                Self::process_set_paused(program_id, true, accounts)
+            }
+            SwapInstruction::Resume => {
+                msg!("Instruction: Resume");
+                Self::process_set_paused(program_id, false, accounts)
+            }
+            SwapInstruction::SetDynamicFees(dynamic_fees) => {
+                msg!("Instruction: SetDynamicFees");
+                Self::process_set_dynamic_fees(program_id, dynamic_fees, accounts, swap_constraints)
+            }
+            SwapInstruction::InitializeConcentratedPool(InitializeConcentratedPool {
+                fees,
+                tick_spacing,
+                initial_sqrt_price,
+            }) => {
+                msg!("Instruction: InitializeConcentratedPool");
+                Self::process_initialize_concentrated_pool(
+                    program_id,
+                    fees,
+                    tick_spacing,
+                    initial_sqrt_price,
+                    accounts,
+                    swap_constraints,
+                )
+            }
+            SwapInstruction::OpenPosition(OpenPosition {
+                tick_lower,
+                tick_upper,
+            }) => {
+                msg!("Instruction: OpenPosition");
+                Self::process_open_position(program_id, tick_lower, tick_upper, accounts)
+            }
+            SwapInstruction::IncreaseLiquidity(IncreaseLiquidity {
+                liquidity,
+                maximum_token_a_amount,
+                maximum_token_b_amount,
+            }) => {
+                msg!("Instruction: IncreaseLiquidity");
+                Self::process_increase_liquidity(
+                    program_id,
+                    liquidity,
+                    maximum_token_a_amount,
+                    maximum_token_b_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::DecreaseLiquidity(DecreaseLiquidity {
+                liquidity,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+            }) => {
+                msg!("Instruction: DecreaseLiquidity");
+                Self::process_decrease_liquidity(
+                    program_id,
+                    liquidity,
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::SwapConcentrated(Swap {
+                amount_in,
+                minimum_amount_out,
+            }) => {
+                msg!("Instruction: SwapConcentrated");
+                Self::process_swap_concentrated(program_id, amount_in, minimum_amount_out, accounts)
+            }
+            SwapInstruction::CollectProtocolFees => {
+                msg!("Instruction: CollectProtocolFees");
+                Self::process_collect_protocol_fees(program_id, accounts)
+            }
+            SwapInstruction::InitializeMultiAssetPool(InitializeMultiAssetPool { fees, curve }) => {
+                msg!("Instruction: InitializeMultiAssetPool");
+                Self::process_initialize_multi_asset_pool(
+                    program_id,
+                    fees,
+                    curve,
+                    accounts,
+                    swap_constraints,
+                )
+            }
+            SwapInstruction::MultiAssetSwap(Swap {
+                amount_in,
+                minimum_amount_out,
+            }) => {
+                msg!("Instruction: MultiAssetSwap");
+                Self::process_multi_asset_swap(program_id, amount_in, minimum_amount_out, accounts)
+            }
+            SwapInstruction::MultiAssetDepositAllTokenTypes(MultiAssetDepositAllTokenTypes {
+                pool_token_amount,
+                maximum_token_amounts,
+            }) => {
+                msg!("Instruction: MultiAssetDepositAllTokenTypes");
+                Self::process_multi_asset_deposit_all_token_types(
+                    program_id,
+                    pool_token_amount,
+                    &maximum_token_amounts,
+                    accounts,
+                )
+            }
+            SwapInstruction::MultiAssetWithdrawAllTokenTypes(MultiAssetWithdrawAllTokenTypes {
+                pool_token_amount,
+                minimum_token_amounts,
+            }) => {
+                msg!("Instruction: MultiAssetWithdrawAllTokenTypes");
+                Self::process_multi_asset_withdraw_all_token_types(
+                    program_id,
+                    pool_token_amount,
+                    &minimum_token_amounts,
+                    accounts,
+                )
+            }
+            SwapInstruction::MultiAssetDepositSingleTokenTypeExactAmountIn(
+                DepositSingleTokenTypeExactAmountIn {
+                    source_token_amount,
+                    minimum_pool_token_amount,
+                },
+            ) => {
+                msg!("Instruction: MultiAssetDepositSingleTokenTypeExactAmountIn");
+                Self::process_multi_asset_deposit_single_token_type_exact_amount_in(
+                    program_id,
+                    source_token_amount,
+                    minimum_pool_token_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::MultiAssetWithdrawSingleTokenTypeExactAmountOut(
+                WithdrawSingleTokenTypeExactAmountOut {
+                    destination_token_amount,
+                    maximum_pool_token_amount,
+                },
+            ) => {
+                msg!("Instruction: MultiAssetWithdrawSingleTokenTypeExactAmountOut");
+                Self::process_multi_asset_withdraw_single_token_type_exact_amount_out(
+                    program_id,
+                    destination_token_amount,
+                    maximum_pool_token_amount,
+                    accounts,
+                )
+            }
+        }
+    }
+}
+
+impl PrintProgramError for SwapError {
+    fn print<E>(&self)
+    where
+        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
+    {
+        match self {
+            SwapError::AlreadyInUse => msg!("Error: Swap account already in use"),
+            SwapError::InvalidProgramAddress => {
+                msg!("Error: Invalid program address generated from bump seed and key")
+            }
+            SwapError::InvalidOwner => {
+                msg!("Error: The input account owner is not the program address")
+            }
+            SwapError::InvalidOutputOwner => {
+                msg!("Error: Output pool account owner cannot be the program address")
+            }
+            SwapError::ExpectedMint => msg!("Error: Deserialized account is not an SPL Token mint"),
+            SwapError::ExpectedAccount => {
+                msg!("Error: Deserialized account is not an SPL Token account")
+            }
+            SwapError::EmptySupply => msg!("Error: Input token account empty"),
+            SwapError::InvalidSupply => msg!("Error: Pool token mint has a non-zero supply"),
+            SwapError::RepeatedMint => msg!("Error: Swap input token accounts have the same mint"),
+            SwapError::InvalidDelegate => msg!("Error: Token account has a delegate"),
+            SwapError::InvalidInput => msg!("Error: InvalidInput"),
+            SwapError::IncorrectSwapAccount => {
+                msg!("Error: Address of the provided swap token account is incorrect")
+            }
+            SwapError::IncorrectPoolMint => {
+                msg!("Error: Address of the provided pool token mint is incorrect")
+            }
+            SwapError::InvalidOutput => msg!("Error: InvalidOutput"),
+            SwapError::CalculationFailure => msg!("Error: CalculationFailure"),
+            SwapError::InvalidInstruction => msg!("Error: InvalidInstruction"),
+            SwapError::ExceededSlippage => {
+                msg!("Error: Swap instruction exceeds desired slippage limit")
+            }
+            SwapError::InvalidCloseAuthority => msg!("Error: Token account has a close authority"),
+            SwapError::InvalidFreezeAuthority => {
+                msg!("Error: Pool token mint has a freeze authority")
+            }
+            SwapError::IncorrectFeeAccount => msg!("Error: Pool fee token account incorrect"),
+            SwapError::ZeroTradingTokens => {
+                msg!("Error: Given pool token amount results in zero trading tokens")
+            }
+            SwapError::FeeCalculationFailure => msg!(
+                "Error: The fee calculation failed due to overflow, underflow, or unexpected 0"
+            ),
+            SwapError::ConversionFailure => msg!("Error: Conversion to or from u64 failed."),
+            SwapError::InvalidFee => {
+                msg!("Error: The provided fee does not match the program owner's constraints")
+            }
+            SwapError::IncorrectTokenProgramId => {
+                msg!("Error: The provided token program does not match the token program expected by the swap")
+            }
+            SwapError::UnsupportedCurveType => {
+                msg!("Error: The provided curve type is not supported by the program owner")
+            }
+            SwapError::InvalidCurve => {
+                msg!("Error: The provided curve parameters are invalid")
+            }
+            SwapError::UnsupportedCurveOperation => {
+                msg!("Error: The operation cannot be performed on the given curve")
+            }
+            SwapError::UnsupportedSwapVersion => {
+                msg!("Error: The operation is not supported by the version of the swap account")
+            }
+            SwapError::SwapPaused => msg!("Error: The swap is paused"),
+            SwapError::InvalidPauseAuthority => msg!(
+                "Error: The pause authority is missing, did not sign, or does not match the swap"
+            ),
+            SwapError::IncorrectStakePool => {
+                msg!("Error: The provided stake pool does not match the token A mint of the swap")
+            }
+            SwapError::StaleStakePool => {
+                msg!("Error: The exchange rate of the stake pool has not been updated this epoch")
+            }
+            SwapError::IncorrectOracle => {
+                msg!("Error: The provided oracle account does not match the oracle of the swap")
+            }
+            SwapError::InvalidOracle => {
+                msg!("Error: The oracle account does not hold a valid trading price")
+            }
+            SwapError::StaleOracle => msg!("Error: The oracle price has not been updated recently"),
+            SwapError::InvalidFeeAccountOwner => msg!(
+                "Error: The owner of the pool fee account is missing, did not sign, or does not match the swap"
+            ),
+            SwapError::IncorrectMint => {
+                msg!("Error: The provided token mint does not match the mint of the swap account")
+            }
+            SwapError::UnsupportedTokenExtension => {
+                msg!("Error: The token mint or account has an extension the swap does not support")
+            }
+            SwapError::InvalidTick => {
+                msg!("Error: The tick is out of bounds or not a multiple of the tick spacing")
+            }
+            SwapError::TooManyTicks => {
+                msg!("Error: The concentrated pool cannot hold any more initialized ticks")
+            }
+            SwapError::IncorrectPosition => {
+                msg!("Error: The provided position does not belong to the concentrated pool")
+            }
+            SwapError::InvalidPositionOwner => {
+                msg!("Error: The owner of the position is missing, did not sign, or does not match")
+            }
+            SwapError::InsufficientLiquidity => {
+                msg!("Error: The position or the pool holds less liquidity than requested")
+            }
+            SwapError::PositionLiquidityTooLow => {
+                msg!("Error: The position would hold less than the minimum liquidity")
+            }
+        }
+    }
+}
+
+fn to_u128(val: u64) -> Result<u128, SwapError> {
+    val.try_into().map_err(|_| SwapError::ConversionFailure)
+}
+
+fn to_u64(val: u128) -> Result<u64, SwapError> {
+    val.try_into().map_err(|_| SwapError::ConversionFailure)
+}
+
+/// Calculates the smallest amount to transfer for the destination to receive
+/// `post_fee_amount` once the transfer fee is withheld.
+fn pre_fee_amount(transfer_fee: &TransferFee, post_fee_amount: u64) -> Option<u64> {
+    let transfer_fee_basis_points = u16::from(transfer_fee.transfer_fee_basis_points);
+    let maximum_fee = u64::from(transfer_fee.maximum_fee);
+    if transfer_fee_basis_points == 0 || post_fee_amount == 0 {
+        Some(post_fee_amount)
+    } else if transfer_fee_basis_points >= MAX_FEE_BASIS_POINTS {
+        post_fee_amount.checked_add(maximum_fee)
+    } else {
+        // the fee is rounded up, so the amount received is rounded down
+        let numerator = u128::from(post_fee_amount).checked_mul(MAX_FEE_BASIS_POINTS.into())?;
+        let denominator = u128::from(MAX_FEE_BASIS_POINTS - transfer_fee_basis_points);
+        let uncapped_amount = numerator
+            .checked_add(denominator.checked_sub(1)?)?
+            .checked_div(denominator)?;
+        let capped_amount = u128::from(post_fee_amount).checked_add(maximum_fee.into())?;
+        u64::try_from(std::cmp::min(uncapped_amount, capped_amount)).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        curve::calculator::{CurveCalculator, INITIAL_SWAP_POOL_AMOUNT},
+        curve::{
+            base::CurveType,
+            concentrated_liquidity::{token_amounts_for_liquidity, MAX_SQRT_PRICE},
+            constant_price::ConstantPriceCurve,
+            constant_product::ConstantProductCurve,
+            multi_asset::MultiAssetCurveType,
+            offset::OffsetCurve,
+        },
+        instruction::{
+            collect_protocol_fees, decrease_liquidity, deposit_all_token_types,
+            deposit_single_token_type_exact_amount_in, increase_liquidity, initialize,
+            initialize_concentrated_pool, initialize_multi_asset_pool,
+            multi_asset_deposit_all_token_types,
+            multi_asset_deposit_single_token_type_exact_amount_in, multi_asset_swap,
+            multi_asset_withdraw_all_token_types,
+            multi_asset_withdraw_single_token_type_exact_amount_out, open_position, pause, resume,
+            set_dynamic_fees, swap, swap_concentrated, withdraw_all_token_types,
+            withdraw_single_token_type_exact_amount_out,
+        },
+        state::SwapV1,
+        twap::PRICE_FRACTIONAL_BITS,
+    };
+    use borsh::BorshSerialize;
+    use solana_program::{instruction::Instruction, program_stubs, rent::Rent};
+    use solana_sdk::account::{create_account_for_test, create_is_signer_account_infos, Account};
+    use spl_stake_pool::state::AccountType;
+    use spl_token::error::TokenError;
+    use spl_token_2022::{
+        extension::{
+            memo_transfer::instruction::enable_required_transfer_memos,
+            transfer_fee::instruction::initialize_transfer_fee_config, ExtensionType,
+        },
+        instruction::{
+            approve, initialize_account, initialize_immutable_owner, initialize_mint,
+            initialize_mint_close_authority, initialize_non_transferable_mint, mint_to, revoke,
+            set_authority, AuthorityType,
+        },
+    };
+    use std::sync::Arc;
+
+    // Test program id for the swap program.
+    const SWAP_PROGRAM_ID: Pubkey = Pubkey::new_from_array([2u8; 32]);
+
+    // Current epoch returned by the clock sysvar.
+    const TEST_EPOCH: u64 = 1;
+
+    // Current slot returned by the clock sysvar.
+    const TEST_SLOT: u64 = 100;
+
+    // Current unix timestamp returned by the clock sysvar.
+    const TEST_UNIX_TIMESTAMP: i64 = 1_650_000_000;
+
+    // Transfer fee of the Token-2022 test mints with a transfer fee.
+    const TEST_TRANSFER_FEE_BASIS_POINTS: u16 = 100;
+    const TEST_MAXIMUM_TRANSFER_FEE: u64 = 1_000_000_000;
+
+    struct TestSyscallStubs {}
+    impl program_stubs::SyscallStubs for TestSyscallStubs {
+        fn sol_invoke_signed(
+            &self,
+            instruction: &Instruction,
+            account_infos: &[AccountInfo],
+            signers_seeds: &[&[&[u8]]],
+        ) -> ProgramResult {
+            msg!("TestSyscallStubs::sol_invoke_signed()");
+
+            let mut new_account_infos = vec![];
+
+            // mimic check for token program in accounts
+            if !account_infos
+                .iter()
+                .any(|x| *x.key == instruction.program_id)
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            for meta in instruction.accounts.iter() {
+                for account_info in account_infos.iter() {
+                    if meta.pubkey == *account_info.key {
+                        let mut new_account_info = account_info.clone();
+                        for seeds in signers_seeds.iter() {
+                            let signer =
+                                Pubkey::create_program_address(seeds, &SWAP_PROGRAM_ID).unwrap();
+                            if *account_info.key == signer {
+                                new_account_info.is_signer = true;
+                            }
+                        }
+                        new_account_infos.push(new_account_info);
+                    }
+                }
+            }
+
+            if instruction.program_id == spl_token_2022::id() {
+                spl_token_2022::processor::Processor::process(
+                    &instruction.program_id,
+                    &new_account_infos,
+                    &instruction.data,
+                )
+            } else {
+                spl_token::processor::Processor::process(
+                    &instruction.program_id,
+                    &new_account_infos,
+                    &instruction.data,
+                )
+            }
+        }
+
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe {
+                *(var_addr as *mut _ as *mut Clock) = Clock {
+                    slot: TEST_SLOT,
+                    epoch: TEST_EPOCH,
+                    unix_timestamp: TEST_UNIX_TIMESTAMP,
+                    ..Clock::default()
+                };
+            }
+            solana_program::entrypoint::SUCCESS
+        }
+    }
+
+    fn test_syscall_stubs() {
+        use std::sync::Once;
+        static ONCE: Once = Once::new();
+
+        ONCE.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs {}));
+        });
+    }
+
+    struct SwapAccountInfo {
+        bump_seed: u8,
+        authority_key: Pubkey,
+        fees: Fees,
+        swap_curve: SwapCurve,
+        swap_key: Pubkey,
+        swap_account: Account,
+        pool_mint_key: Pubkey,
+        pool_mint_account: Account,
+        pool_fee_key: Pubkey,
+        pool_fee_account: Account,
+        pool_token_key: Pubkey,
+        pool_token_account: Account,
+        token_a_key: Pubkey,
+        token_a_account: Account,
+        token_a_mint_key: Pubkey,
+        token_a_mint_account: Account,
+        token_b_key: Pubkey,
+        token_b_account: Account,
+        token_b_mint_key: Pubkey,
+        token_b_mint_account: Account,
+        pause_authority_key: Option<Pubkey>,
+        curve_account_key: Option<Pubkey>,
+        curve_account: Account,
+        pool_token_program_id: Pubkey,
+        token_a_program_id: Pubkey,
+        token_b_program_id: Pubkey,
+    }
+
+    impl SwapAccountInfo {
+        pub fn new(
+            user_key: &Pubkey,
+            fees: Fees,
+            swap_curve: SwapCurve,
+            token_a_amount: u64,
+            token_b_amount: u64,
+        ) -> Self {
+            Self::new_with_token_programs(
+                user_key,
+                fees,
+                swap_curve,
+                token_a_amount,
+                token_b_amount,
+                &spl_token::id(),
+                &spl_token::id(),
+                &spl_token::id(),
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn new_with_token_programs(
+            user_key: &Pubkey,
+            fees: Fees,
+            swap_curve: SwapCurve,
+            token_a_amount: u64,
+            token_b_amount: u64,
+            pool_token_program_id: &Pubkey,
+            token_a_program_id: &Pubkey,
+            token_b_program_id: &Pubkey,
+        ) -> Self {
+            let swap_key = Pubkey::new_unique();
+            let swap_account = Account::new(0, SwapVersion::LATEST_LEN, &SWAP_PROGRAM_ID);
+            let (authority_key, bump_seed) =
+                Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
+
+            let (pool_mint_key, mut pool_mint_account) =
+                create_mint(pool_token_program_id, &authority_key, None);
+            let (pool_token_key, pool_token_account) = mint_token(
+                pool_token_program_id,
+                &pool_mint_key,
+                &mut pool_mint_account,
+                &authority_key,
+                user_key,
+                0,
+            );
+            let (pool_fee_key, pool_fee_account) = mint_token(
+                pool_token_program_id,
+                &pool_mint_key,
+                &mut pool_mint_account,
+                &authority_key,
+                user_key,
+                0,
+            );
+            let (token_a_mint_key, mut token_a_mint_account) =
+                create_mint(token_a_program_id, user_key, None);
+            let (token_a_key, token_a_account) = mint_token(
+                token_a_program_id,
+                &token_a_mint_key,
+                &mut token_a_mint_account,
+                user_key,
+                &authority_key,
+                token_a_amount,
+            );
+            let (token_b_mint_key, mut token_b_mint_account) =
+                create_mint(token_b_program_id, user_key, None);
+            let (token_b_key, token_b_account) = mint_token(
+                token_b_program_id,
+                &token_b_mint_key,
+                &mut token_b_mint_account,
+                user_key,
+                &authority_key,
+                token_b_amount,
+            );
+
+            SwapAccountInfo {
+                bump_seed,
+                authority_key,
+                fees,
+                swap_curve,
+                swap_key,
+                swap_account,
+                pool_mint_key,
+                pool_mint_account,
+                pool_fee_key,
+                pool_fee_account,
+                pool_token_key,
+                pool_token_account,
+                token_a_key,
+                token_a_account,
+                token_a_mint_key,
+                token_a_mint_account,
+                token_b_key,
+                token_b_account,
+                token_b_mint_key,
+                token_b_mint_account,
+                pause_authority_key: None,
+                curve_account_key: None,
+                curve_account: Account::default(),
+                pool_token_program_id: *pool_token_program_id,
+                token_a_program_id: *token_a_program_id,
+                token_b_program_id: *token_b_program_id,
+            }
+        }
+
+        pub fn initialize_swap(&mut self) -> ProgramResult {
+            do_process_instruction(
+                initialize(
+                    &SWAP_PROGRAM_ID,
+                    &self.pool_token_program_id,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    &self.pool_token_key,
+                    &self.token_a_mint_key,
+                    &self.token_b_mint_key,
+                    self.curve_account_key.as_ref(),
+                    self.pause_authority_key.as_ref(),
+                    self.fees.clone(),
+                    self.swap_curve.clone(),
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.pool_mint_account,
+                    &mut self.pool_fee_account,
+                    &mut self.pool_token_account,
+                    &mut Account::default(),
+                    &mut self.token_a_mint_account,
+                    &mut self.token_b_mint_account,
+                    &mut self.curve_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn set_paused(
+            &mut self,
+            pause_authority_key: &Pubkey,
+            is_paused: bool,
+        ) -> ProgramResult {
+            let instruction = if is_paused {
+                pause(&SWAP_PROGRAM_ID, &self.swap_key, pause_authority_key)
+            } else {
+                resume(&SWAP_PROGRAM_ID, &self.swap_key, pause_authority_key)
+            };
+            do_process_instruction(
+                instruction.unwrap(),
+                vec![&mut self.swap_account, &mut Account::default()],
+            )
+        }
+
+        pub fn set_dynamic_fees(
+            &mut self,
+            owner_key: &Pubkey,
+            dynamic_fees: DynamicFees,
+            swap_constraints: &Option<SwapConstraints>,
+        ) -> ProgramResult {
+            do_process_instruction_with_fee_constraints(
+                set_dynamic_fees(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.pool_fee_key,
+                    owner_key,
+                    dynamic_fees,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut self.pool_fee_account,
+                    &mut Account::default(),
+                ],
+                swap_constraints,
+            )
+        }
+
+        pub fn setup_token_accounts(
+            &mut self,
+            mint_owner: &Pubkey,
+            account_owner: &Pubkey,
+            a_amount: u64,
+            b_amount: u64,
+            pool_amount: u64,
+        ) -> (Pubkey, Account, Pubkey, Account, Pubkey, Account) {
+            let (token_a_key, token_a_account) = mint_token(
+                &self.token_a_program_id,
+                &self.token_a_mint_key,
+                &mut self.token_a_mint_account,
+                mint_owner,
+                account_owner,
+                a_amount,
+            );
+            let (token_b_key, token_b_account) = mint_token(
+                &self.token_b_program_id,
+                &self.token_b_mint_key,
+                &mut self.token_b_mint_account,
+                mint_owner,
+                account_owner,
+                b_amount,
+            );
+            let (pool_key, pool_account) = mint_token(
+                &self.pool_token_program_id,
+                &self.pool_mint_key,
+                &mut self.pool_mint_account,
+                &self.authority_key,
+                account_owner,
+                pool_amount,
+            );
+            (
+                token_a_key,
+                token_a_account,
+                token_b_key,
+                token_b_account,
+                pool_key,
+                pool_account,
+            )
+        }
+
+        fn get_token_account(&self, account_key: &Pubkey) -> &Account {
+            if *account_key == self.token_a_key {
+                return &self.token_a_account;
+            } else if *account_key == self.token_b_key {
+                return &self.token_b_account;
+            }
+            panic!("Could not find matching swap token account");
+        }
+
+        fn get_token_mint(&self, account_key: &Pubkey) -> (Pubkey, Account, Pubkey) {
+            if *account_key == self.token_a_key {
+                return (
+                    self.token_a_mint_key,
+                    self.token_a_mint_account.clone(),
+                    self.token_a_program_id,
+                );
+            } else if *account_key == self.token_b_key {
+                return (
+                    self.token_b_mint_key,
+                    self.token_b_mint_account.clone(),
+                    self.token_b_program_id,
+                );
+            }
+            panic!("Could not find matching swap token mint");
+        }
+
+        fn get_user_token_mint(&self, account: &Account) -> (Pubkey, Account, Pubkey) {
+            let mint_key =
+                StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+                    .map(|account| account.base.mint)
+                    .unwrap_or_default();
+            if mint_key == self.token_b_mint_key {
+                self.get_token_mint(&self.token_b_key)
+            } else {
+                self.get_token_mint(&self.token_a_key)
+            }
+        }
+
+        fn set_token_account(&mut self, account_key: &Pubkey, account: Account) {
+            if *account_key == self.token_a_key {
+                self.token_a_account = account;
+                return;
+            } else if *account_key == self.token_b_key {
+                self.token_b_account = account;
+                return;
+            }
+            panic!("Could not find matching swap token account");
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn swap(
+            &mut self,
+            user_key: &Pubkey,
+            user_source_key: &Pubkey,
+            user_source_account: &mut Account,
+            swap_source_key: &Pubkey,
+            swap_destination_key: &Pubkey,
+            user_destination_key: &Pubkey,
+            user_destination_account: &mut Account,
+            amount_in: u64,
+            minimum_amount_out: u64,
+        ) -> ProgramResult {
+            let user_transfer_key = Pubkey::new_unique();
+            let (source_mint_key, mut source_mint_account, source_token_program_id) =
+                self.get_token_mint(swap_source_key);
+            let (destination_mint_key, mut destination_mint_account, destination_token_program_id) =
+                self.get_token_mint(swap_destination_key);
+            // approve moving from user source account
+            do_process_instruction(
+                approve(
+                    &source_token_program_id,
+                    user_source_key,
+                    &user_transfer_key,
+                    user_key,
+                    &[],
+                    amount_in,
+                )
+                .unwrap(),
+                vec![
+                    user_source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            let mut swap_source_account = self.get_token_account(swap_source_key).clone();
+            let mut swap_destination_account = self.get_token_account(swap_destination_key).clone();
+
+            // perform the swap
+            do_process_instruction(
+                swap(
+                    &SWAP_PROGRAM_ID,
+                    &source_token_program_id,
+                    &destination_token_program_id,
+                    &self.pool_token_program_id,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &user_transfer_key,
+                    user_source_key,
+                    swap_source_key,
+                    swap_destination_key,
+                    user_destination_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    &source_mint_key,
+                    &destination_mint_key,
+                    self.curve_account_key.as_ref(),
+                    None,
+                    Swap {
+                        amount_in,
+                        minimum_amount_out,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    user_source_account,
+                    &mut swap_source_account,
+                    &mut swap_destination_account,
+                    user_destination_account,
+                    &mut self.pool_mint_account,
+                    &mut self.pool_fee_account,
+                    &mut source_mint_account,
+                    &mut destination_mint_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut self.curve_account,
+                ],
+            )?;
+
+            self.set_token_account(swap_source_key, swap_source_account);
+            self.set_token_account(swap_destination_key, swap_destination_account);
+
+            Ok(())
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        #[allow(clippy::too_many_arguments)]
+        pub fn deposit_all_token_types(
+            &mut self,
+            depositor_key: &Pubkey,
+            depositor_token_a_key: &Pubkey,
+            depositor_token_a_account: &mut Account,
+            depositor_token_b_key: &Pubkey,
+            depositor_token_b_account: &mut Account,
+            depositor_pool_key: &Pubkey,
+            depositor_pool_account: &mut Account,
+            pool_token_amount: u64,
+            maximum_token_a_amount: u64,
+            maximum_token_b_amount: u64,
+        ) -> ProgramResult {
+            let user_transfer_authority = Pubkey::new_unique();
+            do_process_instruction(
+                approve(
+                    &self.token_a_program_id,
+                    depositor_token_a_key,
+                    &user_transfer_authority,
+                    depositor_key,
+                    &[],
+                    maximum_token_a_amount,
+                )
+                .unwrap(),
+                vec![
+                    depositor_token_a_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                approve(
+                    &self.token_b_program_id,
+                    depositor_token_b_key,
+                    &user_transfer_authority,
+                    depositor_key,
+                    &[],
+                    maximum_token_b_amount,
+                )
+                .unwrap(),
+                vec![
+                    depositor_token_b_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                deposit_all_token_types(
+                    &SWAP_PROGRAM_ID,
+                    &self.token_a_program_id,
+                    &self.token_b_program_id,
+                    &self.pool_token_program_id,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &user_transfer_authority,
+                    depositor_token_a_key,
+                    depositor_token_b_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    &self.pool_mint_key,
+                    depositor_pool_key,
+                    &self.token_a_mint_key,
+                    &self.token_b_mint_key,
+                    DepositAllTokenTypes {
+                        pool_token_amount,
+                        maximum_token_a_amount,
+                        maximum_token_b_amount,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    depositor_token_a_account,
+                    depositor_token_b_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.pool_mint_account,
+                    depositor_pool_account,
+                    &mut self.token_a_mint_account,
+                    &mut self.token_b_mint_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_all_token_types(
+            &mut self,
+            user_key: &Pubkey,
+            pool_key: &Pubkey,
+            pool_account: &mut Account,
+            token_a_key: &Pubkey,
+            token_a_account: &mut Account,
+            token_b_key: &Pubkey,
+            token_b_account: &mut Account,
+            pool_token_amount: u64,
+            minimum_token_a_amount: u64,
+            minimum_token_b_amount: u64,
+        ) -> ProgramResult {
+            let user_transfer_authority_key = Pubkey::new_unique();
+            // approve user transfer authority to take out pool tokens
+            do_process_instruction(
+                approve(
+                    &self.pool_token_program_id,
+                    pool_key,
+                    &user_transfer_authority_key,
+                    user_key,
+                    &[],
+                    pool_token_amount,
+                )
+                .unwrap(),
+                vec![
+                    pool_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            // withdraw token a and b correctly
+            do_process_instruction(
+                withdraw_all_token_types(
+                    &SWAP_PROGRAM_ID,
+                    &self.token_a_program_id,
+                    &self.token_b_program_id,
+                    &self.pool_token_program_id,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &user_transfer_authority_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    pool_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    token_a_key,
+                    token_b_key,
+                    &self.token_a_mint_key,
+                    &self.token_b_mint_key,
+                    WithdrawAllTokenTypes {
+                        pool_token_amount,
+                        minimum_token_a_amount,
+                        minimum_token_b_amount,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut self.pool_mint_account,
+                    pool_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    token_a_account,
+                    token_b_account,
+                    &mut self.pool_fee_account,
+                    &mut self.token_a_mint_account,
+                    &mut self.token_b_mint_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn deposit_single_token_type_exact_amount_in(
+            &mut self,
+            depositor_key: &Pubkey,
+            deposit_account_key: &Pubkey,
+            deposit_token_account: &mut Account,
+            deposit_pool_key: &Pubkey,
+            deposit_pool_account: &mut Account,
+            source_token_amount: u64,
+            minimum_pool_token_amount: u64,
+        ) -> ProgramResult {
+            let user_transfer_authority_key = Pubkey::new_unique();
+            let (source_mint_key, mut source_mint_account, source_token_program_id) =
+                self.get_user_token_mint(deposit_token_account);
+            do_process_instruction(
+                approve(
+                    &source_token_program_id,
+                    deposit_account_key,
+                    &user_transfer_authority_key,
+                    depositor_key,
+                    &[],
+                    source_token_amount,
+                )
+                .unwrap(),
+                vec![
+                    deposit_token_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                deposit_single_token_type_exact_amount_in(
+                    &SWAP_PROGRAM_ID,
+                    &source_token_program_id,
+                    &self.pool_token_program_id,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &user_transfer_authority_key,
+                    deposit_account_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    &self.pool_mint_key,
+                    deposit_pool_key,
+                    &source_mint_key,
+                    self.curve_account_key.as_ref(),
+                    DepositSingleTokenTypeExactAmountIn {
+                        source_token_amount,
+                        minimum_pool_token_amount,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    deposit_token_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.pool_mint_account,
+                    deposit_pool_account,
+                    &mut source_mint_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut self.curve_account,
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_single_token_type_exact_amount_out(
+            &mut self,
+            user_key: &Pubkey,
+            pool_key: &Pubkey,
+            pool_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            destination_token_amount: u64,
+            maximum_pool_token_amount: u64,
+        ) -> ProgramResult {
+            let user_transfer_authority_key = Pubkey::new_unique();
+            let (destination_mint_key, mut destination_mint_account, destination_token_program_id) =
+                self.get_user_token_mint(destination_account);
+            // approve user transfer authority to take out pool tokens
+            do_process_instruction(
+                approve(
+                    &self.pool_token_program_id,
+                    pool_key,
+                    &user_transfer_authority_key,
+                    user_key,
+                    &[],
+                    maximum_pool_token_amount,
+                )
+                .unwrap(),
+                vec![
+                    pool_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                withdraw_single_token_type_exact_amount_out(
+                    &SWAP_PROGRAM_ID,
+                    &destination_token_program_id,
+                    &self.pool_token_program_id,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &user_transfer_authority_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    pool_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    destination_key,
+                    &destination_mint_key,
+                    self.curve_account_key.as_ref(),
+                    WithdrawSingleTokenTypeExactAmountOut {
+                        destination_token_amount,
+                        maximum_pool_token_amount,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut self.pool_mint_account,
+                    pool_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    destination_account,
+                    &mut self.pool_fee_account,
+                    &mut destination_mint_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut self.curve_account,
+                ],
+            )
+        }
+    }
+
+    fn do_process_instruction_with_fee_constraints(
+        instruction: Instruction,
+        accounts: Vec<&mut Account>,
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        test_syscall_stubs();
+
+        // approximate the logic in the actual runtime which runs the instruction
+        // and only updates accounts if the instruction is successful
+        let mut account_clones = accounts.iter().map(|x| (*x).clone()).collect::<Vec<_>>();
+        let mut meta = instruction
+            .accounts
+            .iter()
+            .zip(account_clones.iter_mut())
+            .map(|(account_meta, account)| (&account_meta.pubkey, account_meta.is_signer, account))
+            .collect::<Vec<_>>();
+        let mut account_infos = create_is_signer_account_infos(&mut meta);
+        let res = if instruction.program_id == SWAP_PROGRAM_ID {
+            Processor::process_with_constraints(
+                &instruction.program_id,
+                &account_infos,
+                &instruction.data,
+                swap_constraints,
+            )
+        } else if instruction.program_id == spl_token_2022::id() {
+            spl_token_2022::processor::Processor::process(
+                &instruction.program_id,
+                &account_infos,
+                &instruction.data,
+            )
+        } else {
+            spl_token::processor::Processor::process(
+                &instruction.program_id,
+                &account_infos,
+                &instruction.data,
+            )
+        };
+
+        if res.is_ok() {
+            let mut account_metas = instruction
+                .accounts
+                .iter()
+                .zip(accounts)
+                .map(|(account_meta, account)| (&account_meta.pubkey, account))
+                .collect::<Vec<_>>();
+            for account_info in account_infos.iter_mut() {
+                for account_meta in account_metas.iter_mut() {
+                    if account_info.key == account_meta.0 {
+                        let account = &mut account_meta.1;
+                        account.owner = *account_info.owner;
+                        account.lamports = **account_info.lamports.borrow();
+                        account.data = account_info.data.borrow().to_vec();
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    fn do_process_instruction(
+        instruction: Instruction,
+        accounts: Vec<&mut Account>,
+    ) -> ProgramResult {
+        do_process_instruction_with_fee_constraints(instruction, accounts, &SWAP_CONSTRAINTS)
+    }
+
+    fn mint_token(
+        program_id: &Pubkey,
+        mint_key: &Pubkey,
+        mint_account: &mut Account,
+        mint_authority_key: &Pubkey,
+        account_owner_key: &Pubkey,
+        amount: u64,
+    ) -> (Pubkey, Account) {
+        let account_key = Pubkey::new_unique();
+        let mint_extension_types = StateWithExtensions::<Mint>::unpack(&mint_account.data)
+            .unwrap()
+            .get_extension_types()
+            .unwrap();
+        let mut account_extension_types =
+            ExtensionType::get_required_init_account_extensions(&mint_extension_types);
+        // non-transferable tokens may only be held by immutably-owned accounts
+        let immutable_owner = mint_extension_types.contains(&ExtensionType::NonTransferable);
+        if immutable_owner {
+            account_extension_types.push(ExtensionType::ImmutableOwner);
+        }
+        let space = ExtensionType::get_account_len::<spl_token_2022::state::Account>(
+            &account_extension_types,
+        );
+        let mut account_account =
+            Account::new(Rent::default().minimum_balance(space), space, program_id);
+        let mut mint_authority_account = Account::default();
+        let mut rent_sysvar_account = create_account_for_test(&Rent::free());
+
+        if immutable_owner {
+            do_process_instruction(
+                initialize_immutable_owner(program_id, &account_key).unwrap(),
+                vec![&mut account_account],
+            )
+            .unwrap();
+        }
+
+        do_process_instruction(
+            initialize_account(program_id, &account_key, mint_key, account_owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                mint_account,
+                &mut mint_authority_account,
+                &mut rent_sysvar_account,
+            ],
+        )
+        .unwrap();
+
+        if amount > 0 {
+            do_process_instruction(
+                mint_to(
+                    program_id,
+                    mint_key,
+                    &account_key,
+                    mint_authority_key,
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+                vec![
+                    mint_account,
+                    &mut account_account,
+                    &mut mint_authority_account,
+                ],
+            )
+            .unwrap();
+        }
+
+        (account_key, account_account)
+    }
+
+    fn create_mint(
+        program_id: &Pubkey,
+        authority_key: &Pubkey,
+        freeze_authority: Option<&Pubkey>,
+    ) -> (Pubkey, Account) {
+        create_mint_with_extensions(program_id, authority_key, freeze_authority, &[])
+    }
+
+    fn create_mint_with_extensions(
+        program_id: &Pubkey,
+        authority_key: &Pubkey,
+        freeze_authority: Option<&Pubkey>,
+        extension_types: &[ExtensionType],
+    ) -> (Pubkey, Account) {
+        let mint_key = Pubkey::new_unique();
+        let space = ExtensionType::get_account_len::<Mint>(extension_types);
+        let mut mint_account =
+            Account::new(Rent::default().minimum_balance(space), space, program_id);
+        for extension_type in extension_types {
+            let instruction = match extension_type {
+                ExtensionType::TransferFeeConfig => initialize_transfer_fee_config(
+                    program_id,
+                    &mint_key,
+                    Some(authority_key),
+                    Some(authority_key),
+                    TEST_TRANSFER_FEE_BASIS_POINTS,
+                    TEST_MAXIMUM_TRANSFER_FEE,
+                ),
+                ExtensionType::NonTransferable => {
+                    initialize_non_transferable_mint(program_id, &mint_key)
+                }
+                ExtensionType::MintCloseAuthority => {
+                    initialize_mint_close_authority(program_id, &mint_key, Some(authority_key))
+                }
+                _ => panic!("Unsupported mint extension {:?}", extension_type),
+            };
+            do_process_instruction(instruction.unwrap(), vec![&mut mint_account]).unwrap();
+        }
+        let mut rent_sysvar_account = create_account_for_test(&Rent::free());
+
+        do_process_instruction(
+            initialize_mint(program_id, &mint_key, authority_key, freeze_authority, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar_account],
+        )
+        .unwrap();
+
+        (mint_key, mint_account)
+    }
+
+    #[test]
+    fn test_token_program_id_error() {
+        test_syscall_stubs();
+        let swap_key = Pubkey::new_unique();
+        let mut mint = (Pubkey::new_unique(), Account::default());
+        let mut destination = (Pubkey::new_unique(), Account::default());
+        let token_program = (spl_token::id(), Account::default());
+        let (authority_key, bump_seed) =
+            Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
+        let mut authority = (authority_key, Account::default());
+        let swap_bytes = swap_key.to_bytes();
+        let authority_signature_seeds = [&swap_bytes[..32], &[bump_seed]];
+        let signers = &[&authority_signature_seeds[..]];
+        let ix = mint_to(
+            &token_program.0,
+            &mint.0,
+            &destination.0,
+            &authority.0,
+            &[],
+            10,
+        )
+        .unwrap();
+        let mint = (&mut mint).into();
+        let destination = (&mut destination).into();
+        let authority = (&mut authority).into();
+
+        let err = invoke_signed(&ix, &[mint, destination, authority], signers).unwrap_err();
+        assert_eq!(err, ProgramError::InvalidAccountData);
+    }
+
+    #[test]
+    fn test_initialize() {
+        let user_key = Pubkey::new_unique();
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 2;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 10;
+        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_denominator = 5;
+        let host_fee_numerator = 20;
+        let host_fee_denominator = 100;
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let token_a_amount = 1000;
+        let token_b_amount = 2000;
+        let pool_token_amount = 10;
+        let curve_type = CurveType::ConstantProduct;
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+
+        // uninitialized token a account
+        {
+            let old_account = accounts.token_a_account;
+            accounts.token_a_account = Account::new(0, 0, &spl_token::id());
+            assert_eq!(
+                Err(SwapError::ExpectedAccount.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account = old_account;
+        }
+
+        // uninitialized token b account
+        {
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = Account::new(0, 0, &spl_token::id());
+            assert_eq!(
+                Err(SwapError::ExpectedAccount.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // uninitialized pool mint
+        {
+            let old_account = accounts.pool_mint_account;
+            accounts.pool_mint_account = Account::new(0, 0, &spl_token::id());
+            assert_eq!(
+                Err(SwapError::ExpectedMint.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_mint_account = old_account;
+        }
+
+        // token A account owner is not swap authority
+        {
+            let (_token_a_key, token_a_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &user_key,
+                0,
+            );
+            let old_account = accounts.token_a_account;
+            accounts.token_a_account = token_a_account;
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account = old_account;
+        }
+
+        // token B account owner is not swap authority
+        {
+            let (_token_b_key, token_b_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_b_mint_key,
+                &mut accounts.token_b_mint_account,
+                &user_key,
+                &user_key,
+                0,
+            );
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = token_b_account;
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // pool token account owner is swap authority
+        {
+            let (_pool_token_key, pool_token_account) = mint_token(
+                &spl_token::id(),
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &accounts.authority_key,
+                0,
+            );
+            let old_account = accounts.pool_token_account;
+            accounts.pool_token_account = pool_token_account;
+            assert_eq!(
+                Err(SwapError::InvalidOutputOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_token_account = old_account;
+        }
+
+        // pool fee account owner is swap authority
+        {
+            let (_pool_fee_key, pool_fee_account) = mint_token(
+                &spl_token::id(),
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &accounts.authority_key,
+                0,
+            );
+            let old_account = accounts.pool_fee_account;
+            accounts.pool_fee_account = pool_fee_account;
+            assert_eq!(
+                Err(SwapError::InvalidOutputOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_fee_account = old_account;
+        }
+
+        // pool mint authority is not swap authority
+        {
+            let (_pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &user_key, None);
+            let old_mint = accounts.pool_mint_account;
+            accounts.pool_mint_account = pool_mint_account;
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_mint_account = old_mint;
+        }
+
+        // pool mint token has freeze authority
+        {
+            let (_pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &accounts.authority_key, Some(&user_key));
+            let old_mint = accounts.pool_mint_account;
+            accounts.pool_mint_account = pool_mint_account;
+            assert_eq!(
+                Err(SwapError::InvalidFreezeAuthority.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_mint_account = old_mint;
+        }
+
+        // token A account owned by wrong program
+        {
+            let (_token_a_key, mut token_a_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                token_a_amount,
+            );
+            token_a_account.owner = SWAP_PROGRAM_ID;
+            let old_account = accounts.token_a_account;
+            accounts.token_a_account = token_a_account;
+            assert_eq!(
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account = old_account;
+        }
+
+        // token B account owned by wrong program
+        {
+            let (_token_b_key, mut token_b_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_b_mint_key,
+                &mut accounts.token_b_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                token_b_amount,
+            );
+            token_b_account.owner = SWAP_PROGRAM_ID;
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = token_b_account;
+            assert_eq!(
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // empty token A account
+        {
+            let (_token_a_key, token_a_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                0,
+            );
+            let old_account = accounts.token_a_account;
+            accounts.token_a_account = token_a_account;
+            assert_eq!(
+                Err(SwapError::EmptySupply.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account = old_account;
+        }
+
+        // empty token B account
+        {
+            let (_token_b_key, token_b_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_b_mint_key,
+                &mut accounts.token_b_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                0,
+            );
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = token_b_account;
+            assert_eq!(
+                Err(SwapError::EmptySupply.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // invalid pool tokens
+        {
+            let old_mint = accounts.pool_mint_account;
+            let old_pool_account = accounts.pool_token_account;
+
+            let (_pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &accounts.authority_key, None);
+            accounts.pool_mint_account = pool_mint_account;
+
+            let (_empty_pool_token_key, empty_pool_token_account) = mint_token(
+                &spl_token::id(),
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &user_key,
+                0,
+            );
+
+            let (_pool_token_key, pool_token_account) = mint_token(
+                &spl_token::id(),
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &user_key,
+                pool_token_amount,
+            );
+
+            // non-empty pool token account
+            accounts.pool_token_account = pool_token_account;
+            assert_eq!(
+                Err(SwapError::InvalidSupply.into()),
+                accounts.initialize_swap()
+            );
+
+            // pool tokens already in circulation
+            accounts.pool_token_account = empty_pool_token_account;
+            assert_eq!(
+                Err(SwapError::InvalidSupply.into()),
+                accounts.initialize_swap()
+            );
+
+            accounts.pool_mint_account = old_mint;
+            accounts.pool_token_account = old_pool_account;
+        }
+
+        // pool fee account has wrong mint
+        {
+            let (_pool_fee_key, pool_fee_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &user_key,
+                0,
+            );
+            let old_account = accounts.pool_fee_account;
+            accounts.pool_fee_account = pool_fee_account;
+            assert_eq!(
+                Err(SwapError::IncorrectPoolMint.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_fee_account = old_account;
+        }
+
+        // token A account is delegated
+        {
+            do_process_instruction(
+                approve(
+                    &spl_token::id(),
+                    &accounts.token_a_key,
+                    &user_key,
+                    &accounts.authority_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.token_a_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidDelegate.into()),
+                accounts.initialize_swap()
+            );
+
+            do_process_instruction(
+                revoke(
+                    &spl_token::id(),
+                    &accounts.token_a_key,
+                    &accounts.authority_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_a_account, &mut Account::default()],
+            )
+            .unwrap();
+        }
+
+        // token B account is delegated
+        {
+            do_process_instruction(
+                approve(
+                    &spl_token::id(),
+                    &accounts.token_b_key,
+                    &user_key,
+                    &accounts.authority_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.token_b_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidDelegate.into()),
+                accounts.initialize_swap()
+            );
+
+            do_process_instruction(
+                revoke(
+                    &spl_token::id(),
+                    &accounts.token_b_key,
+                    &accounts.authority_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_b_account, &mut Account::default()],
+            )
+            .unwrap();
+        }
+
+        // token A account has close authority
+        {
+            do_process_instruction(
+                set_authority(
+                    &spl_token::id(),
+                    &accounts.token_a_key,
+                    Some(&user_key),
+                    AuthorityType::CloseAccount,
+                    &accounts.authority_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_a_account, &mut Account::default()],
+            )
+            .unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidCloseAuthority.into()),
+                accounts.initialize_swap()
+            );
+
+            do_process_instruction(
+                set_authority(
+                    &spl_token::id(),
+                    &accounts.token_a_key,
+                    None,
+                    AuthorityType::CloseAccount,
+                    &user_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_a_account, &mut Account::default()],
+            )
+            .unwrap();
+        }
+
+        // token B account has close authority
+        {
+            do_process_instruction(
+                set_authority(
+                    &spl_token::id(),
+                    &accounts.token_b_key,
+                    Some(&user_key),
+                    AuthorityType::CloseAccount,
+                    &accounts.authority_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_b_account, &mut Account::default()],
+            )
+            .unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidCloseAuthority.into()),
+                accounts.initialize_swap()
+            );
+
+            do_process_instruction(
+                set_authority(
+                    &spl_token::id(),
+                    &accounts.token_b_key,
+                    None,
+                    AuthorityType::CloseAccount,
+                    &user_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_b_account, &mut Account::default()],
+            )
+            .unwrap();
+        }
+
+        // wrong token program id
+        {
+            let wrong_program_id = Pubkey::new_unique();
+            assert_eq!(
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                do_process_instruction(
+                    initialize(
+                        &SWAP_PROGRAM_ID,
+                        &wrong_program_id,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &accounts.pool_token_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        None,
+                        None,
+                        accounts.fees.clone(),
+                        accounts.swap_curve.clone(),
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.pool_token_account,
+                        &mut Account::default(),
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
+                    ],
+                )
+            );
+        }
+
+        // create swap with same token A and B
+        {
+            let (_token_a_repeat_key, token_a_repeat_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                10,
+            );
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = token_a_repeat_account;
+            assert_eq!(
+                Err(SwapError::RepeatedMint.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // create valid swap
+        accounts.initialize_swap().unwrap();
+
+        // create invalid flat swap
+        {
+            let token_b_price = 0;
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::ConstantPrice,
+                calculator: Arc::new(ConstantPriceCurve { token_b_price }),
+            };
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+            assert_eq!(
+                Err(SwapError::InvalidCurve.into()),
+                accounts.initialize_swap()
+            );
+        }
+
+        // create valid flat swap
+        {
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let token_b_price = 10_000;
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::ConstantPrice,
+                calculator: Arc::new(ConstantPriceCurve { token_b_price }),
+            };
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+            accounts.initialize_swap().unwrap();
+        }
+
+        // create invalid offset swap
+        {
+            let token_b_offset = 0;
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::Offset,
+                calculator: Arc::new(OffsetCurve { token_b_offset }),
+            };
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+            assert_eq!(
+                Err(SwapError::InvalidCurve.into()),
+                accounts.initialize_swap()
+            );
+        }
+
+        // create valid offset swap
+        {
+            let token_b_offset = 10;
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::Offset,
+                calculator: Arc::new(OffsetCurve { token_b_offset }),
+            };
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+            accounts.initialize_swap().unwrap();
+        }
+
+        // wrong owner key in constraint
+        {
+            let new_key = Pubkey::new_unique();
+            let trade_fee_numerator = 25;
+            let trade_fee_denominator = 10000;
+            let owner_trade_fee_numerator = 5;
+            let owner_trade_fee_denominator = 10000;
+            let host_fee_numerator = 20;
+            let host_fee_denominator = 100;
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let curve = ConstantProductCurve {};
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::ConstantProduct,
+                calculator: Arc::new(curve),
+            };
+            let owner_key = &new_key.to_string();
+            let valid_curve_types = &[CurveType::ConstantProduct];
+            let max_dynamic_fees = &DynamicFees::default();
+            let constraints = Some(SwapConstraints {
+                owner_key,
+                valid_curve_types,
+                fees: &fees,
+                pause_authority: None,
+                max_dynamic_fees,
+                oracle_program_id: None,
+            });
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                fees.clone(),
+                swap_curve,
+                token_a_amount,
+                token_b_amount,
+            );
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                do_process_instruction_with_fee_constraints(
+                    initialize(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &accounts.pool_token_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        None,
+                        None,
+                        accounts.fees.clone(),
+                        accounts.swap_curve.clone(),
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.pool_token_account,
+                        &mut Account::default(),
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
+                    ],
+                    &constraints,
+                )
+            );
+        }
+
+        // wrong fee in constraint
+        {
+            let trade_fee_numerator = 25;
+            let trade_fee_denominator = 10000;
+            let owner_trade_fee_numerator = 5;
+            let owner_trade_fee_denominator = 10000;
+            let host_fee_numerator = 20;
+            let host_fee_denominator = 100;
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let curve = ConstantProductCurve {};
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::ConstantProduct,
+                calculator: Arc::new(curve),
+            };
+            let owner_key = &user_key.to_string();
+            let valid_curve_types = &[CurveType::ConstantProduct];
+            let max_dynamic_fees = &DynamicFees::default();
+            let constraints = Some(SwapConstraints {
+                owner_key,
+                valid_curve_types,
+                fees: &fees,
+                pause_authority: None,
+                max_dynamic_fees,
+                oracle_program_id: None,
+            });
+            let mut bad_fees = fees.clone();
+            bad_fees.trade_fee_numerator = trade_fee_numerator - 1;
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                bad_fees,
+                swap_curve,
+                token_a_amount,
+                token_b_amount,
+            );
+            assert_eq!(
+                Err(SwapError::InvalidFee.into()),
+                do_process_instruction_with_fee_constraints(
+                    initialize(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &accounts.pool_token_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        None,
+                        None,
+                        accounts.fees.clone(),
+                        accounts.swap_curve.clone(),
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.pool_token_account,
+                        &mut Account::default(),
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
+                    ],
+                    &constraints,
+                )
+            );
+        }
+
+        // create valid swap with constraints
+        {
+            let trade_fee_numerator = 25;
+            let trade_fee_denominator = 10000;
+            let owner_trade_fee_numerator = 5;
+            let owner_trade_fee_denominator = 10000;
+            let host_fee_numerator = 20;
+            let host_fee_denominator = 100;
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
                 owner_withdraw_fee_numerator,
                 owner_withdraw_fee_denominator,
                 host_fee_numerator,
                 host_fee_denominator,
             };
+            let curve = ConstantProductCurve {};
             let swap_curve = SwapCurve {
-                curve_type: CurveType::Offset,
-                calculator: Arc::new(OffsetCurve { token_b_offset }),
+                curve_type: CurveType::ConstantProduct,
+                calculator: Arc::new(curve),
             };
-            let mut accounts =
-                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
-            accounts.initialize_swap().unwrap();
+            let owner_key = &user_key.to_string();
+            let valid_curve_types = &[CurveType::ConstantProduct];
+            let max_dynamic_fees = &DynamicFees::default();
+            let constraints = Some(SwapConstraints {
+                owner_key,
+                valid_curve_types,
+                fees: &fees,
+                pause_authority: None,
+                max_dynamic_fees,
+                oracle_program_id: None,
+            });
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                fees.clone(),
+                swap_curve,
+                token_a_amount,
+                token_b_amount,
+            );
+            do_process_instruction_with_fee_constraints(
+                initialize(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &accounts.swap_key,
+                    &accounts.authority_key,
+                    &accounts.token_a_key,
+                    &accounts.token_b_key,
+                    &accounts.pool_mint_key,
+                    &accounts.pool_fee_key,
+                    &accounts.pool_token_key,
+                    &accounts.token_a_mint_key,
+                    &accounts.token_b_mint_key,
+                    None,
+                    None,
+                    accounts.fees,
+                    accounts.swap_curve.clone(),
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.swap_account,
+                    &mut Account::default(),
+                    &mut accounts.token_a_account,
+                    &mut accounts.token_b_account,
+                    &mut accounts.pool_mint_account,
+                    &mut accounts.pool_fee_account,
+                    &mut accounts.pool_token_account,
+                    &mut Account::default(),
+                    &mut accounts.token_a_mint_account,
+                    &mut accounts.token_b_mint_account,
+                ],
+                &constraints,
+            )
+            .unwrap();
+        }
+
+        // create again
+        {
+            assert_eq!(
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.initialize_swap()
+            );
+        }
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert!(swap_state.is_initialized());
+        assert_eq!(swap_state.bump_seed(), accounts.bump_seed);
+        assert_eq!(
+            swap_state.swap_curve().curve_type,
+            accounts.swap_curve.curve_type
+        );
+        assert_eq!(*swap_state.token_a_account(), accounts.token_a_key);
+        assert_eq!(*swap_state.token_b_account(), accounts.token_b_key);
+        assert_eq!(*swap_state.pool_mint(), accounts.pool_mint_key);
+        assert_eq!(*swap_state.token_a_mint(), accounts.token_a_mint_key);
+        assert_eq!(*swap_state.token_b_mint(), accounts.token_b_mint_key);
+        assert_eq!(*swap_state.pool_fee_account(), accounts.pool_fee_key);
+        let token_a = spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+        assert_eq!(token_a.amount, token_a_amount);
+        let token_b = spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+        assert_eq!(token_b.amount, token_b_amount);
+        let pool_account =
+            spl_token::state::Account::unpack(&accounts.pool_token_account.data).unwrap();
+        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+        assert_eq!(pool_mint.supply, pool_account.amount);
+    }
+
+    #[test]
+    fn test_deposit() {
+        let user_key = Pubkey::new_unique();
+        let depositor_key = Pubkey::new_unique();
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 2;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 10;
+        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_denominator = 5;
+        let host_fee_numerator = 20;
+        let host_fee_denominator = 100;
+
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let token_a_amount = 1000;
+        let token_b_amount = 9000;
+        let curve_type = CurveType::ConstantProduct;
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+
+        // depositing 10% of the current pool amount in token A and B means
+        // that our pool tokens will be worth 1 / 10 of the current pool amount
+        let pool_amount = INITIAL_SWAP_POOL_AMOUNT / 10;
+        let deposit_a = token_a_amount / 10;
+        let deposit_b = token_b_amount / 10;
+
+        // swap not initialized
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong owner for swap account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let old_swap_account = accounts.swap_account;
+            let mut wrong_swap_account = old_swap_account.clone();
+            wrong_swap_account.owner = spl_token::id();
+            accounts.swap_account = wrong_swap_account;
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+            accounts.swap_account = old_swap_account;
+        }
+
+        // wrong bump seed for authority_key
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _bump_seed) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &spl_token::id(),
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // not enough token A
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &depositor_key,
+                deposit_a / 2,
+                deposit_b,
+                0,
+            );
+            assert_eq!(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+        }
+
+        // not enough token B
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &depositor_key,
+                deposit_a,
+                deposit_b / 2,
+                0,
+            );
+            assert_eq!(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+        }
+
+        // wrong swap token accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+        }
+
+        // wrong pool token account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                mut _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let (
+                wrong_token_key,
+                mut wrong_token_account,
+                _token_b_key,
+                mut _token_b_account,
+                _pool_key,
+                mut _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &wrong_token_key,
+                    &mut wrong_token_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+        }
+
+        // no approval
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let user_transfer_authority_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(TokenError::OwnerMismatch.into()),
+                do_process_instruction(
+                    deposit_all_token_types(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &spl_token::id(),
+                        &spl_token::id(),
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &user_transfer_authority_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &accounts.pool_mint_key,
+                        &pool_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        DepositAllTokenTypes {
+                            pool_token_amount: pool_amount.try_into().unwrap(),
+                            maximum_token_a_amount: deposit_a,
+                            maximum_token_b_amount: deposit_b,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
+                )
+            );
+        }
+
+        // wrong token program id
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let wrong_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                do_process_instruction(
+                    deposit_all_token_types(
+                        &SWAP_PROGRAM_ID,
+                        &wrong_key,
+                        &wrong_key,
+                        &wrong_key,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &accounts.pool_mint_key,
+                        &pool_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        DepositAllTokenTypes {
+                            pool_token_amount: pool_amount.try_into().unwrap(),
+                            maximum_token_a_amount: deposit_a,
+                            maximum_token_b_amount: deposit_b,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
+                )
+            );
+        }
+
+        // wrong swap token accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+
+            let old_a_key = accounts.token_a_key;
+            let old_a_account = accounts.token_a_account;
+
+            accounts.token_a_key = token_a_key;
+            accounts.token_a_account = token_a_account.clone();
+
+            // wrong swap token a account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+
+            accounts.token_a_key = old_a_key;
+            accounts.token_a_account = old_a_account;
+
+            let old_b_key = accounts.token_b_key;
+            let old_b_account = accounts.token_b_account;
+
+            accounts.token_b_key = token_b_key;
+            accounts.token_b_account = token_b_account.clone();
+
+            // wrong swap token b account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+
+            accounts.token_b_key = old_b_key;
+            accounts.token_b_account = old_b_account;
+        }
+
+        // wrong mint
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let (pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &accounts.authority_key, None);
+            let old_pool_key = accounts.pool_mint_key;
+            let old_pool_account = accounts.pool_mint_account;
+            accounts.pool_mint_key = pool_mint_key;
+            accounts.pool_mint_account = pool_mint_account;
+
+            assert_eq!(
+                Err(SwapError::IncorrectPoolMint.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+
+            accounts.pool_mint_key = old_pool_key;
+            accounts.pool_mint_account = old_pool_account;
+        }
+
+        // deposit 1 pool token fails beacuse it equates to 0 swap tokens
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(SwapError::ZeroTradingTokens.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    1,
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+        }
+
+        // slippage exceeded
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            // maximum A amount in too low
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a / 10,
+                    deposit_b,
+                )
+            );
+            // maximum B amount in too low
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b / 10,
+                )
+            );
+        }
+
+        // invalid input: can't use swap pool tokens as source
+        {
+            let (
+                _token_a_key,
+                _token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let swap_token_a_key = accounts.token_a_key;
+            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
+            let swap_token_b_key = accounts.token_b_key;
+            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
+            let authority_key = accounts.authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.deposit_all_token_types(
+                    &authority_key,
+                    &swap_token_a_key,
+                    &mut swap_token_a_account,
+                    &swap_token_b_key,
+                    &mut swap_token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+        }
+
+        // correctly deposit
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            accounts
+                .deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+                .unwrap();
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+            assert_eq!(swap_token_a.amount, deposit_a + token_a_amount);
+            let swap_token_b =
+                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+            assert_eq!(swap_token_b.amount, deposit_b + token_b_amount);
+            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+            assert_eq!(token_a.amount, 0);
+            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+            assert_eq!(token_b.amount, 0);
+            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
+            let swap_pool_account =
+                spl_token::state::Account::unpack(&accounts.pool_token_account.data).unwrap();
+            let pool_mint =
+                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+            assert_eq!(
+                pool_mint.supply,
+                pool_account.amount + swap_pool_account.amount
+            );
+        }
+    }
+
+    #[test]
+    fn test_withdraw() {
+        let user_key = Pubkey::new_unique();
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 2;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 10;
+        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_denominator = 5;
+        let host_fee_numerator = 7;
+        let host_fee_denominator = 100;
+
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let token_a_amount = 1000;
+        let token_b_amount = 2000;
+        let curve_type = CurveType::ConstantProduct;
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+
+        let withdrawer_key = Pubkey::new_unique();
+        let initial_a = token_a_amount / 10;
+        let initial_b = token_b_amount / 10;
+        let initial_pool = swap_curve.calculator.new_pool_supply() / 10;
+        let withdraw_amount = initial_pool / 4;
+        let minimum_token_a_amount = initial_a / 40;
+        let minimum_token_b_amount = initial_b / 40;
+
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+
+        // swap not initialized
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong owner for swap account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            let old_swap_account = accounts.swap_account;
+            let mut wrong_swap_account = old_swap_account.clone();
+            wrong_swap_account.owner = spl_token::id();
+            accounts.swap_account = wrong_swap_account;
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
+            );
+            accounts.swap_account = old_swap_account;
+        }
+
+        // wrong bump seed for authority_key
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _bump_seed) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &spl_token::id(),
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // not enough pool tokens
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                to_u64(withdraw_amount).unwrap() / 2u64,
+            );
+            assert_eq!(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount / 2,
+                    minimum_token_b_amount / 2,
+                )
+            );
+        }
+
+        // wrong token a / b accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount.try_into().unwrap(),
+            );
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
+            );
+        }
+
+        // wrong pool token account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount.try_into().unwrap(),
+            );
+            let (
+                wrong_token_a_key,
+                mut wrong_token_a_account,
+                _token_b_key,
+                _token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                withdraw_amount.try_into().unwrap(),
+                initial_b,
+                withdraw_amount.try_into().unwrap(),
+            );
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &wrong_token_a_key,
+                    &mut wrong_token_a_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
+            );
+        }
+
+        // wrong pool fee account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                wrong_pool_key,
+                wrong_pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount.try_into().unwrap(),
+            );
+            let (
+                _token_a_key,
+                _token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount.try_into().unwrap(),
+            );
+            let old_pool_fee_account = accounts.pool_fee_account;
+            let old_pool_fee_key = accounts.pool_fee_key;
+            accounts.pool_fee_account = wrong_pool_account;
+            accounts.pool_fee_key = wrong_pool_key;
+            assert_eq!(
+                Err(SwapError::IncorrectFeeAccount.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                ),
+            );
+            accounts.pool_fee_account = old_pool_fee_account;
+            accounts.pool_fee_key = old_pool_fee_key;
+        }
+
+        // no approval
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                0,
+                0,
+                withdraw_amount.try_into().unwrap(),
+            );
+            let user_transfer_authority_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(TokenError::OwnerMismatch.into()),
+                do_process_instruction(
+                    withdraw_all_token_types(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &spl_token::id(),
+                        &spl_token::id(),
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &user_transfer_authority_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &pool_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        WithdrawAllTokenTypes {
+                            pool_token_amount: withdraw_amount.try_into().unwrap(),
+                            minimum_token_a_amount,
+                            minimum_token_b_amount,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
+                )
+            );
+        }
+
+        // wrong token program id
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount.try_into().unwrap(),
+            );
+            let wrong_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                do_process_instruction(
+                    withdraw_all_token_types(
+                        &SWAP_PROGRAM_ID,
+                        &wrong_key,
+                        &wrong_key,
+                        &wrong_key,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.authority_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &pool_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        WithdrawAllTokenTypes {
+                            pool_token_amount: withdraw_amount.try_into().unwrap(),
+                            minimum_token_a_amount,
+                            minimum_token_b_amount,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
+                )
+            );
+        }
+
+        // wrong swap token accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
+            );
+
+            let old_a_key = accounts.token_a_key;
+            let old_a_account = accounts.token_a_account;
+
+            accounts.token_a_key = token_a_key;
+            accounts.token_a_account = token_a_account.clone();
+
+            // wrong swap token a account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
+            );
+
+            accounts.token_a_key = old_a_key;
+            accounts.token_a_account = old_a_account;
+
+            let old_b_key = accounts.token_b_key;
+            let old_b_account = accounts.token_b_account;
+
+            accounts.token_b_key = token_b_key;
+            accounts.token_b_account = token_b_account.clone();
+
+            // wrong swap token b account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
+            );
+
+            accounts.token_b_key = old_b_key;
+            accounts.token_b_account = old_b_account;
+        }
+
+        // wrong mint
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
+            );
+            let (pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &accounts.authority_key, None);
+            let old_pool_key = accounts.pool_mint_key;
+            let old_pool_account = accounts.pool_mint_account;
+            accounts.pool_mint_key = pool_mint_key;
+            accounts.pool_mint_account = pool_mint_account;
+
+            assert_eq!(
+                Err(SwapError::IncorrectPoolMint.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
+            );
+
+            accounts.pool_mint_key = old_pool_key;
+            accounts.pool_mint_account = old_pool_account;
+        }
+
+        // withdrawing 1 pool token fails because it equates to 0 output tokens
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
+            );
+            assert_eq!(
+                Err(SwapError::ZeroTradingTokens.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    1,
+                    0,
+                    0,
+                )
+            );
+        }
+
+        // slippage exceeded
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
+            );
+            // minimum A amount out too high
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount * 10,
+                    minimum_token_b_amount,
+                )
+            );
+            // minimum B amount out too high
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount * 10,
+                )
+            );
+        }
+
+        // invalid input: can't use swap pool tokens as destination
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
+            );
+            let swap_token_a_key = accounts.token_a_key;
+            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &swap_token_a_key,
+                    &mut swap_token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
+            );
+            let swap_token_b_key = accounts.token_b_key;
+            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_b_key,
+                    &mut swap_token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
+            );
+        }
+
+        // correct withdrawal
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
+            );
+
+            accounts
+                .withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
+                .unwrap();
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+            let swap_token_b =
+                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+            let pool_mint =
+                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+            let withdraw_fee = accounts.fees.owner_withdraw_fee(withdraw_amount).unwrap();
+            let results = accounts
+                .swap_curve
+                .calculator
+                .pool_tokens_to_trading_tokens(
+                    withdraw_amount - withdraw_fee,
+                    pool_mint.supply.try_into().unwrap(),
+                    swap_token_a.amount.try_into().unwrap(),
+                    swap_token_b.amount.try_into().unwrap(),
+                    RoundDirection::Floor,
+                )
+                .unwrap();
+            assert_eq!(
+                swap_token_a.amount,
+                token_a_amount - to_u64(results.token_a_amount).unwrap()
+            );
+            assert_eq!(
+                swap_token_b.amount,
+                token_b_amount - to_u64(results.token_b_amount).unwrap()
+            );
+            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+            assert_eq!(
+                token_a.amount,
+                initial_a + to_u64(results.token_a_amount).unwrap()
+            );
+            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+            assert_eq!(
+                token_b.amount,
+                initial_b + to_u64(results.token_b_amount).unwrap()
+            );
+            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
+            assert_eq!(
+                pool_account.amount,
+                to_u64(initial_pool - withdraw_amount).unwrap()
+            );
+            let fee_account =
+                spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
+            assert_eq!(
+                fee_account.amount,
+                TryInto::<u64>::try_into(withdraw_fee).unwrap()
+            );
+        }
+
+        // correct withdrawal from fee account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                mut _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 0);
+
+            let pool_fee_key = accounts.pool_fee_key;
+            let mut pool_fee_account = accounts.pool_fee_account.clone();
+            let fee_account = spl_token::state::Account::unpack(&pool_fee_account.data).unwrap();
+            let pool_fee_amount = fee_account.amount;
+
+            accounts
+                .withdraw_all_token_types(
+                    &user_key,
+                    &pool_fee_key,
+                    &mut pool_fee_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    pool_fee_amount,
+                    0,
+                    0,
+                )
+                .unwrap();
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+            let swap_token_b =
+                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+            let pool_mint =
+                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+            let results = accounts
+                .swap_curve
+                .calculator
+                .pool_tokens_to_trading_tokens(
+                    pool_fee_amount.try_into().unwrap(),
+                    pool_mint.supply.try_into().unwrap(),
+                    swap_token_a.amount.try_into().unwrap(),
+                    swap_token_b.amount.try_into().unwrap(),
+                    RoundDirection::Floor,
+                )
+                .unwrap();
+            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+            assert_eq!(
+                token_a.amount,
+                TryInto::<u64>::try_into(results.token_a_amount).unwrap()
+            );
+            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+            assert_eq!(
+                token_b.amount,
+                TryInto::<u64>::try_into(results.token_b_amount).unwrap()
+            );
         }
+    }
 
-        // wrong owner key in constraint
+    #[test]
+    fn test_deposit_one_exact_in() {
+        let user_key = Pubkey::new_unique();
+        let depositor_key = Pubkey::new_unique();
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 2;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 10;
+        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_denominator = 5;
+        let host_fee_numerator = 20;
+        let host_fee_denominator = 100;
+
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let token_a_amount = 1000;
+        let token_b_amount = 9000;
+        let curve_type = CurveType::ConstantProduct;
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+
+        let deposit_a = token_a_amount / 10;
+        let deposit_b = token_b_amount / 10;
+        let pool_amount = to_u64(INITIAL_SWAP_POOL_AMOUNT / 100).unwrap();
+
+        // swap not initialized
         {
-            let new_key = Pubkey::new_unique();
-            let trade_fee_numerator = 25;
-            let trade_fee_denominator = 10000;
-            let owner_trade_fee_numerator = 5;
-            let owner_trade_fee_denominator = 10000;
-            let host_fee_numerator = 20;
-            let host_fee_denominator = 100;
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let curve = ConstantProductCurve {};
-            let swap_curve = SwapCurve {
-                curve_type: CurveType::ConstantProduct,
-                calculator: Arc::new(curve),
-            };
-            let owner_key = &new_key.to_string();
-            let valid_curve_types = &[CurveType::ConstantProduct];
-            let constraints = Some(SwapConstraints {
-                owner_key,
-                valid_curve_types,
-                fees: &fees,
-            });
-            let mut accounts = SwapAccountInfo::new(
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    pool_amount,
+                )
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong owner for swap account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let old_swap_account = accounts.swap_account;
+            let mut wrong_swap_account = old_swap_account.clone();
+            wrong_swap_account.owner = spl_token::id();
+            accounts.swap_account = wrong_swap_account;
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    pool_amount,
+                )
+            );
+            accounts.swap_account = old_swap_account;
+        }
+
+        // wrong bump seed for authority_key
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _bump_seed) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &spl_token::id(),
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    pool_amount,
+                )
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // not enough token A / B
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
                 &user_key,
-                fees.clone(),
-                swap_curve,
-                token_a_amount,
-                token_b_amount,
+                &depositor_key,
+                deposit_a / 2,
+                deposit_b / 2,
+                0,
             );
             assert_eq!(
-                Err(SwapError::InvalidOwner.into()),
-                do_process_instruction_with_fee_constraints(
-                    initialize(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    0,
+                )
+            );
+            assert_eq!(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_b,
+                    0,
+                )
+            );
+        }
+
+        // wrong pool token account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                mut _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    deposit_a,
+                    pool_amount,
+                )
+            );
+        }
+
+        // no approval
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let user_transfer_authority_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(TokenError::OwnerMismatch.into()),
+                do_process_instruction(
+                    deposit_single_token_type_exact_amount_in(
                         &SWAP_PROGRAM_ID,
                         &spl_token::id(),
+                        &spl_token::id(),
                         &accounts.swap_key,
                         &accounts.authority_key,
+                        &user_transfer_authority_key,
+                        &token_a_key,
                         &accounts.token_a_key,
                         &accounts.token_b_key,
                         &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        &accounts.pool_token_key,
-                        accounts.fees.clone(),
-                        accounts.swap_curve.clone(),
+                        &pool_key,
+                        &accounts.token_a_mint_key,
+                        None,
+                        DepositSingleTokenTypeExactAmountIn {
+                            source_token_amount: deposit_a,
+                            minimum_pool_token_amount: pool_amount,
+                        },
                     )
                     .unwrap(),
                     vec![
                         &mut accounts.swap_account,
                         &mut Account::default(),
+                        &mut Account::default(),
+                        &mut token_a_account,
                         &mut accounts.token_a_account,
                         &mut accounts.token_b_account,
                         &mut accounts.pool_mint_account,
-                        &mut accounts.pool_fee_account,
-                        &mut accounts.pool_token_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut Account::default(),
                         &mut Account::default(),
                     ],
-                    &constraints,
                 )
             );
         }
 
-        // wrong fee in constraint
+        // wrong token program id
         {
-            let trade_fee_numerator = 25;
-            let trade_fee_denominator = 10000;
-            let owner_trade_fee_numerator = 5;
-            let owner_trade_fee_denominator = 10000;
-            let host_fee_numerator = 20;
-            let host_fee_denominator = 100;
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let curve = ConstantProductCurve {};
-            let swap_curve = SwapCurve {
-                curve_type: CurveType::ConstantProduct,
-                calculator: Arc::new(curve),
-            };
-            let owner_key = &user_key.to_string();
-            let valid_curve_types = &[CurveType::ConstantProduct];
-            let constraints = Some(SwapConstraints {
-                owner_key,
-                valid_curve_types,
-                fees: &fees,
-            });
-            let mut bad_fees = fees.clone();
-            bad_fees.trade_fee_numerator = trade_fee_numerator - 1;
-            let mut accounts = SwapAccountInfo::new(
-                &user_key,
-                bad_fees,
-                swap_curve,
-                token_a_amount,
-                token_b_amount,
-            );
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let wrong_key = Pubkey::new_unique();
             assert_eq!(
-                Err(SwapError::InvalidFee.into()),
-                do_process_instruction_with_fee_constraints(
-                    initialize(
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                do_process_instruction(
+                    deposit_single_token_type_exact_amount_in(
                         &SWAP_PROGRAM_ID,
-                        &spl_token::id(),
+                        &wrong_key,
+                        &wrong_key,
                         &accounts.swap_key,
                         &accounts.authority_key,
+                        &accounts.authority_key,
+                        &token_a_key,
                         &accounts.token_a_key,
                         &accounts.token_b_key,
                         &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        &accounts.pool_token_key,
-                        accounts.fees.clone(),
-                        accounts.swap_curve.clone(),
+                        &pool_key,
+                        &accounts.token_a_mint_key,
+                        None,
+                        DepositSingleTokenTypeExactAmountIn {
+                            source_token_amount: deposit_a,
+                            minimum_pool_token_amount: pool_amount,
+                        },
                     )
                     .unwrap(),
                     vec![
                         &mut accounts.swap_account,
                         &mut Account::default(),
+                        &mut Account::default(),
+                        &mut token_a_account,
                         &mut accounts.token_a_account,
                         &mut accounts.token_b_account,
                         &mut accounts.pool_mint_account,
-                        &mut accounts.pool_fee_account,
-                        &mut accounts.pool_token_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut Account::default(),
                         &mut Account::default(),
                     ],
-                    &constraints,
                 )
             );
         }
 
-        // create valid swap with constraints
+        // wrong swap token accounts
         {
-            let trade_fee_numerator = 25;
-            let trade_fee_denominator = 10000;
-            let owner_trade_fee_numerator = 5;
-            let owner_trade_fee_denominator = 10000;
-            let host_fee_numerator = 20;
-            let host_fee_denominator = 100;
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let curve = ConstantProductCurve {};
-            let swap_curve = SwapCurve {
-                curve_type: CurveType::ConstantProduct,
-                calculator: Arc::new(curve),
-            };
-            let owner_key = &user_key.to_string();
-            let valid_curve_types = &[CurveType::ConstantProduct];
-            let constraints = Some(SwapConstraints {
-                owner_key,
-                valid_curve_types,
-                fees: &fees,
-            });
-            let mut accounts = SwapAccountInfo::new(
-                &user_key,
-                fees.clone(),
-                swap_curve,
-                token_a_amount,
-                token_b_amount,
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+
+            let old_a_key = accounts.token_a_key;
+            let old_a_account = accounts.token_a_account;
+
+            accounts.token_a_key = token_a_key;
+            accounts.token_a_account = token_a_account.clone();
+
+            // wrong swap token a account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    pool_amount,
+                )
+            );
+
+            accounts.token_a_key = old_a_key;
+            accounts.token_a_account = old_a_account;
+
+            let old_b_key = accounts.token_b_key;
+            let old_b_account = accounts.token_b_account;
+
+            accounts.token_b_key = token_b_key;
+            accounts.token_b_account = token_b_account;
+
+            // wrong swap token b account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    pool_amount,
+                )
+            );
+
+            accounts.token_b_key = old_b_key;
+            accounts.token_b_account = old_b_account;
+        }
+
+        // wrong mint
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let (pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &accounts.authority_key, None);
+            let old_pool_key = accounts.pool_mint_key;
+            let old_pool_account = accounts.pool_mint_account;
+            accounts.pool_mint_key = pool_mint_key;
+            accounts.pool_mint_account = pool_mint_account;
+
+            assert_eq!(
+                Err(SwapError::IncorrectPoolMint.into()),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    pool_amount,
+                )
+            );
+
+            accounts.pool_mint_key = old_pool_key;
+            accounts.pool_mint_account = old_pool_account;
+        }
+
+        // slippage exceeded
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            // minimum pool amount too high
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a / 10,
+                    pool_amount,
+                )
+            );
+            // minimum pool amount too high
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_b / 10,
+                    pool_amount,
+                )
+            );
+        }
+
+        // invalid input: can't use swap pool tokens as source
+        {
+            let (
+                _token_a_key,
+                _token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let swap_token_a_key = accounts.token_a_key;
+            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
+            let swap_token_b_key = accounts.token_b_key;
+            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
+            let authority_key = accounts.authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &authority_key,
+                    &swap_token_a_key,
+                    &mut swap_token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    pool_amount,
+                )
             );
-            do_process_instruction_with_fee_constraints(
-                initialize(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &accounts.swap_key,
-                    &accounts.authority_key,
-                    &accounts.token_a_key,
-                    &accounts.token_b_key,
-                    &accounts.pool_mint_key,
-                    &accounts.pool_fee_key,
-                    &accounts.pool_token_key,
-                    accounts.fees,
-                    accounts.swap_curve.clone(),
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &authority_key,
+                    &swap_token_b_key,
+                    &mut swap_token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_b,
+                    pool_amount,
                 )
-                .unwrap(),
-                vec![
-                    &mut accounts.swap_account,
-                    &mut Account::default(),
-                    &mut accounts.token_a_account,
-                    &mut accounts.token_b_account,
-                    &mut accounts.pool_mint_account,
-                    &mut accounts.pool_fee_account,
-                    &mut accounts.pool_token_account,
-                    &mut Account::default(),
-                ],
-                &constraints,
-            )
-            .unwrap();
+            );
         }
 
-        // create again
+        // correctly deposit
         {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            accounts
+                .deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    pool_amount,
+                )
+                .unwrap();
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+            assert_eq!(swap_token_a.amount, deposit_a + token_a_amount);
+
+            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+            assert_eq!(token_a.amount, 0);
+
+            accounts
+                .deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_b,
+                    pool_amount,
+                )
+                .unwrap();
+            let swap_token_b =
+                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+            assert_eq!(swap_token_b.amount, deposit_b + token_b_amount);
+
+            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+            assert_eq!(token_b.amount, 0);
+
+            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
+            let swap_pool_account =
+                spl_token::state::Account::unpack(&accounts.pool_token_account.data).unwrap();
+            let pool_mint =
+                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
             assert_eq!(
-                Err(SwapError::AlreadyInUse.into()),
-                accounts.initialize_swap()
+                pool_mint.supply,
+                pool_account.amount + swap_pool_account.amount
             );
         }
-        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
-        assert!(swap_state.is_initialized());
-        assert_eq!(swap_state.bump_seed(), accounts.bump_seed);
-        assert_eq!(
-            swap_state.swap_curve().curve_type,
-            accounts.swap_curve.curve_type
-        );
-        assert_eq!(*swap_state.token_a_account(), accounts.token_a_key);
-        assert_eq!(*swap_state.token_b_account(), accounts.token_b_key);
-        assert_eq!(*swap_state.pool_mint(), accounts.pool_mint_key);
-        assert_eq!(*swap_state.token_a_mint(), accounts.token_a_mint_key);
-        assert_eq!(*swap_state.token_b_mint(), accounts.token_b_mint_key);
-        assert_eq!(*swap_state.pool_fee_account(), accounts.pool_fee_key);
-        let token_a = spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-        assert_eq!(token_a.amount, token_a_amount);
-        let token_b = spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-        assert_eq!(token_b.amount, token_b_amount);
-        let pool_account =
-            spl_token::state::Account::unpack(&accounts.pool_token_account.data).unwrap();
-        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-        assert_eq!(pool_mint.supply, pool_account.amount);
     }
 
     #[test]
-    fn test_deposit() {
+    fn test_withdraw_one_exact_out() {
         let user_key = Pubkey::new_unique();
-        let depositor_key = Pubkey::new_unique();
         let trade_fee_numerator = 1;
         let trade_fee_denominator = 2;
         let owner_trade_fee_numerator = 1;
         let owner_trade_fee_denominator = 10;
         let owner_withdraw_fee_numerator = 1;
         let owner_withdraw_fee_denominator = 5;
-        let host_fee_numerator = 20;
+        let host_fee_numerator = 7;
         let host_fee_denominator = 100;
 
         let fees = Fees {
@@ -2814,152 +7361,340 @@ mod tests {
             host_fee_denominator,
         };
 
-        let token_a_amount = 1000;
-        let token_b_amount = 9000;
+        let token_a_amount = 100_000;
+        let token_b_amount = 200_000;
         let curve_type = CurveType::ConstantProduct;
         let swap_curve = SwapCurve {
             curve_type,
             calculator: Arc::new(ConstantProductCurve {}),
         };
 
+        let withdrawer_key = Pubkey::new_unique();
+        let initial_a = token_a_amount / 10;
+        let initial_b = token_b_amount / 10;
+        let initial_pool = swap_curve.calculator.new_pool_supply() / 10;
+        let maximum_pool_token_amount = to_u64(initial_pool / 4).unwrap();
+        let destination_a_amount = initial_a / 40;
+        let destination_b_amount = initial_b / 40;
+
         let mut accounts =
             SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
 
-        // depositing 10% of the current pool amount in token A and B means
-        // that our pool tokens will be worth 1 / 10 of the current pool amount
-        let pool_amount = INITIAL_SWAP_POOL_AMOUNT / 10;
-        let deposit_a = token_a_amount / 10;
-        let deposit_b = token_b_amount / 10;
-
         // swap not initialized
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
             assert_eq!(
                 Err(ProgramError::UninitializedAccount),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    destination_a_amount,
+                    maximum_pool_token_amount,
+                )
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong owner for swap account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            let old_swap_account = accounts.swap_account;
+            let mut wrong_swap_account = old_swap_account.clone();
+            wrong_swap_account.owner = spl_token::id();
+            accounts.swap_account = wrong_swap_account;
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
                     &token_a_key,
                     &mut token_a_account,
+                    destination_a_amount,
+                    maximum_pool_token_amount,
+                )
+            );
+            accounts.swap_account = old_swap_account;
+        }
+
+        // wrong bump seed for authority_key
+        {
+            let (
+                _token_a_key,
+                _token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _bump_seed) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &spl_token::id(),
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
                     &token_b_key,
                     &mut token_b_account,
+                    destination_b_amount,
+                    maximum_pool_token_amount,
+                )
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // not enough pool tokens
+        {
+            let (
+                _token_a_key,
+                _token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                maximum_pool_token_amount / 1000,
+            );
+            assert_eq!(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b,
+                    &token_b_key,
+                    &mut token_b_account,
+                    destination_b_amount,
+                    maximum_pool_token_amount,
                 )
             );
         }
 
-        accounts.initialize_swap().unwrap();
-
-        // wrong owner for swap account
+        // wrong pool token account
         {
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let old_swap_account = accounts.swap_account;
-            let mut wrong_swap_account = old_swap_account.clone();
-            wrong_swap_account.owner = spl_token::id();
-            accounts.swap_account = wrong_swap_account;
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                maximum_pool_token_amount,
+                initial_b,
+                maximum_pool_token_amount,
+            );
             assert_eq!(
-                Err(ProgramError::IncorrectProgramId),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
+                Err(TokenError::MintMismatch.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
                     &token_a_key,
                     &mut token_a_account,
                     &token_b_key,
                     &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b,
+                    destination_b_amount,
+                    maximum_pool_token_amount,
                 )
             );
-            accounts.swap_account = old_swap_account;
         }
 
-        // wrong bump seed for authority_key
+        // wrong pool fee account
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
+                wrong_pool_key,
+                wrong_pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                maximum_pool_token_amount,
+            );
+            let (
+                _token_a_key,
+                _token_a_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _bump_seed) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &spl_token::id(),
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                maximum_pool_token_amount,
             );
-            accounts.authority_key = bad_authority_key;
+            let old_pool_fee_account = accounts.pool_fee_account;
+            let old_pool_fee_key = accounts.pool_fee_key;
+            accounts.pool_fee_account = wrong_pool_account;
+            accounts.pool_fee_key = wrong_pool_key;
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
+                Err(SwapError::IncorrectFeeAccount.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b,
+                    &token_a_key,
+                    &mut token_a_account,
+                    destination_a_amount,
+                    maximum_pool_token_amount,
                 )
             );
-            accounts.authority_key = old_authority;
+            accounts.pool_fee_account = old_pool_fee_account;
+            accounts.pool_fee_key = old_pool_fee_key;
         }
 
-        // not enough token A
+        // no approval
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
             ) = accounts.setup_token_accounts(
                 &user_key,
-                &depositor_key,
-                deposit_a / 2,
-                deposit_b,
+                &withdrawer_key,
+                0,
                 0,
+                maximum_pool_token_amount,
             );
+            let user_transfer_authority_key = Pubkey::new_unique();
             assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b,
+                Err(TokenError::OwnerMismatch.into()),
+                do_process_instruction(
+                    withdraw_single_token_type_exact_amount_out(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &spl_token::id(),
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &user_transfer_authority_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &pool_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        &accounts.token_a_mint_key,
+                        None,
+                        WithdrawSingleTokenTypeExactAmountOut {
+                            destination_token_amount: destination_a_amount,
+                            maximum_pool_token_amount,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_a_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
                 )
             );
         }
 
-        // not enough token B
+        // wrong token program id
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                maximum_pool_token_amount,
+            );
+            let wrong_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                do_process_instruction(
+                    withdraw_single_token_type_exact_amount_out(
+                        &SWAP_PROGRAM_ID,
+                        &wrong_key,
+                        &wrong_key,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.authority_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &pool_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        &accounts.token_a_mint_key,
+                        None,
+                        WithdrawSingleTokenTypeExactAmountOut {
+                            destination_token_amount: destination_a_amount,
+                            maximum_pool_token_amount,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_a_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
+                )
+            );
+        }
+
+        // wrong swap token accounts
         {
             let (
                 token_a_key,
@@ -2970,451 +7705,755 @@ mod tests {
                 mut pool_account,
             ) = accounts.setup_token_accounts(
                 &user_key,
-                &depositor_key,
-                deposit_a,
-                deposit_b / 2,
-                0,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
             );
+
+            let old_a_key = accounts.token_a_key;
+            let old_a_account = accounts.token_a_account;
+
+            accounts.token_a_key = token_a_key;
+            accounts.token_a_account = token_a_account.clone();
+
+            // wrong swap token a account
             assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
+                    destination_a_amount,
+                    maximum_pool_token_amount,
+                )
+            );
+
+            accounts.token_a_key = old_a_key;
+            accounts.token_a_account = old_a_account;
+
+            let old_b_key = accounts.token_b_key;
+            let old_b_account = accounts.token_b_account;
+
+            accounts.token_b_key = token_b_key;
+            accounts.token_b_account = token_b_account.clone();
+
+            // wrong swap token b account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b,
+                    &token_b_key,
+                    &mut token_b_account,
+                    destination_b_amount,
+                    maximum_pool_token_amount,
                 )
             );
+
+            accounts.token_b_key = old_b_key;
+            accounts.token_b_account = old_b_account;
         }
 
-        // wrong swap token accounts
+        // wrong mint
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
+            );
+            let (pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &accounts.authority_key, None);
+            let old_pool_key = accounts.pool_mint_key;
+            let old_pool_account = accounts.pool_mint_account;
+            accounts.pool_mint_key = pool_mint_key;
+            accounts.pool_mint_account = pool_mint_account;
+
             assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &token_a_key,
-                    &mut token_a_account,
+                Err(SwapError::IncorrectPoolMint.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b,
+                    &token_a_key,
+                    &mut token_a_account,
+                    destination_a_amount,
+                    maximum_pool_token_amount,
                 )
             );
+
+            accounts.pool_mint_key = old_pool_key;
+            accounts.pool_mint_account = old_pool_account;
         }
 
-        // wrong pool token account
+        // slippage exceeded
         {
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                _pool_key,
-                mut _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let (
-                wrong_token_key,
-                mut wrong_token_account,
-                _token_b_key,
-                mut _token_b_account,
-                _pool_key,
-                mut _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                maximum_pool_token_amount,
+            );
+
+            // maximum pool token amount too low
             assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
                     &token_a_key,
                     &mut token_a_account,
+                    destination_a_amount,
+                    maximum_pool_token_amount / 1000,
+                )
+            );
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
                     &token_b_key,
                     &mut token_b_account,
-                    &wrong_token_key,
-                    &mut wrong_token_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b,
+                    destination_b_amount,
+                    maximum_pool_token_amount / 1000,
                 )
             );
         }
 
-        // no approval
+        // invalid input: can't use swap pool tokens as destination
         {
             let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_a_key,
+                _token_a_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let user_transfer_authority_key = Pubkey::new_unique();
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                maximum_pool_token_amount,
+            );
+            let swap_token_a_key = accounts.token_a_key;
+            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
             assert_eq!(
-                Err(TokenError::OwnerMismatch.into()),
-                do_process_instruction(
-                    deposit_all_token_types(
-                        &SWAP_PROGRAM_ID,
-                        &spl_token::id(),
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &user_transfer_authority_key,
-                        &token_a_key,
-                        &token_b_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &accounts.pool_mint_key,
-                        &pool_key,
-                        DepositAllTokenTypes {
-                            pool_token_amount: pool_amount.try_into().unwrap(),
-                            maximum_token_a_amount: deposit_a,
-                            maximum_token_b_amount: deposit_b,
-                        },
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut Account::default(),
-                        &mut token_a_account,
-                        &mut token_b_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut Account::default(),
-                    ],
+                Err(SwapError::InvalidInput.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &swap_token_a_key,
+                    &mut swap_token_a_account,
+                    destination_a_amount,
+                    maximum_pool_token_amount,
+                )
+            );
+            let swap_token_b_key = accounts.token_b_key;
+            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &swap_token_b_key,
+                    &mut swap_token_b_account,
+                    destination_b_amount,
+                    maximum_pool_token_amount,
                 )
             );
         }
 
-        // wrong token program id
+        // correct withdrawal
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let wrong_key = Pubkey::new_unique();
-            assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
-                do_process_instruction(
-                    deposit_all_token_types(
-                        &SWAP_PROGRAM_ID,
-                        &wrong_key,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.authority_key,
-                        &token_a_key,
-                        &token_b_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &accounts.pool_mint_key,
-                        &pool_key,
-                        DepositAllTokenTypes {
-                            pool_token_amount: pool_amount.try_into().unwrap(),
-                            maximum_token_a_amount: deposit_a,
-                            maximum_token_b_amount: deposit_b,
-                        },
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut Account::default(),
-                        &mut token_a_account,
-                        &mut token_b_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut Account::default(),
-                    ],
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
+            );
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+            let swap_token_b =
+                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+            let pool_mint =
+                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+
+            let pool_token_amount = accounts
+                .swap_curve
+                .withdraw_single_token_type_exact_out(
+                    destination_a_amount.try_into().unwrap(),
+                    swap_token_a.amount.try_into().unwrap(),
+                    swap_token_b.amount.try_into().unwrap(),
+                    pool_mint.supply.try_into().unwrap(),
+                    TradeDirection::AtoB,
+                    &accounts.fees,
+                )
+                .unwrap();
+            let withdraw_fee = accounts.fees.owner_withdraw_fee(pool_token_amount).unwrap();
+
+            accounts
+                .withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    destination_a_amount,
+                    maximum_pool_token_amount,
                 )
+                .unwrap();
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+
+            assert_eq!(swap_token_a.amount, token_a_amount - destination_a_amount);
+            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+            assert_eq!(token_a.amount, initial_a + destination_a_amount);
+
+            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
+            assert_eq!(
+                pool_account.amount,
+                to_u64(initial_pool - pool_token_amount - withdraw_fee).unwrap()
             );
+            let fee_account =
+                spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
+            assert_eq!(fee_account.amount, to_u64(withdraw_fee).unwrap());
         }
 
-        // wrong swap token accounts
+        // correct withdrawal from fee account
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+                _token_b_key,
+                _token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+
+            let fee_a_amount = 2;
+            let pool_fee_key = accounts.pool_fee_key;
+            let mut pool_fee_account = accounts.pool_fee_account.clone();
+            let fee_account = spl_token::state::Account::unpack(&pool_fee_account.data).unwrap();
+            let pool_fee_amount = fee_account.amount;
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+
+            let token_a_amount = swap_token_a.amount;
+            accounts
+                .withdraw_single_token_type_exact_amount_out(
+                    &user_key,
+                    &pool_fee_key,
+                    &mut pool_fee_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    fee_a_amount,
+                    pool_fee_amount,
+                )
+                .unwrap();
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+
+            assert_eq!(swap_token_a.amount, token_a_amount - fee_a_amount);
+            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+            assert_eq!(token_a.amount, initial_a + fee_a_amount);
+        }
+    }
+
+    fn check_valid_swap_curve(
+        fees: Fees,
+        curve_type: CurveType,
+        calculator: Arc<dyn CurveCalculator + Send + Sync>,
+        token_a_amount: u64,
+        token_b_amount: u64,
+    ) {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            swap_curve.clone(),
+            token_a_amount,
+            token_b_amount,
+        );
+        let initial_a = token_a_amount / 5;
+        let initial_b = token_b_amount / 5;
+        accounts.initialize_swap().unwrap();
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+        // swap one way
+        let a_to_b_amount = initial_a / 10;
+        let minimum_token_b_amount = 0;
+        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+        let initial_supply = pool_mint.supply;
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                minimum_token_b_amount,
+            )
+            .unwrap();
+
+        let results = swap_curve
+            .swap(
+                a_to_b_amount.try_into().unwrap(),
+                token_a_amount.try_into().unwrap(),
+                token_b_amount.try_into().unwrap(),
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+
+        let swap_token_a =
+            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+        let token_a_amount = swap_token_a.amount;
+        assert_eq!(
+            token_a_amount,
+            TryInto::<u64>::try_into(results.new_swap_source_amount).unwrap()
+        );
+        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+        assert_eq!(token_a.amount, initial_a - a_to_b_amount);
+
+        let swap_token_b =
+            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+        let token_b_amount = swap_token_b.amount;
+        assert_eq!(
+            token_b_amount,
+            TryInto::<u64>::try_into(results.new_swap_destination_amount).unwrap()
+        );
+        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        assert_eq!(
+            token_b.amount,
+            initial_b + to_u64(results.destination_amount_swapped).unwrap()
+        );
+
+        let first_fee = swap_curve
+            .withdraw_single_token_type_exact_out(
+                results.owner_fee,
+                token_a_amount.try_into().unwrap(),
+                token_b_amount.try_into().unwrap(),
+                initial_supply.try_into().unwrap(),
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        let fee_account =
+            spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
+        assert_eq!(
+            fee_account.amount,
+            TryInto::<u64>::try_into(first_fee).unwrap()
+        );
+
+        let first_swap_amount = results.destination_amount_swapped;
+
+        // swap the other way
+        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+        let initial_supply = pool_mint.supply;
+
+        let b_to_a_amount = initial_b / 10;
+        let minimum_a_amount = 0;
+        accounts
+            .swap(
+                &swapper_key,
+                &token_b_key,
+                &mut token_b_account,
+                &swap_token_b_key,
+                &swap_token_a_key,
+                &token_a_key,
+                &mut token_a_account,
+                b_to_a_amount,
+                minimum_a_amount,
+            )
+            .unwrap();
+
+        let results = swap_curve
+            .swap(
+                b_to_a_amount.try_into().unwrap(),
+                token_b_amount.try_into().unwrap(),
+                token_a_amount.try_into().unwrap(),
+                TradeDirection::BtoA,
+                &fees,
+            )
+            .unwrap();
+
+        let swap_token_a =
+            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+        let token_a_amount = swap_token_a.amount;
+        assert_eq!(
+            token_a_amount,
+            TryInto::<u64>::try_into(results.new_swap_destination_amount).unwrap()
+        );
+        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+        assert_eq!(
+            token_a.amount,
+            initial_a - a_to_b_amount + to_u64(results.destination_amount_swapped).unwrap()
+        );
+
+        let swap_token_b =
+            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+        let token_b_amount = swap_token_b.amount;
+        assert_eq!(
+            token_b_amount,
+            TryInto::<u64>::try_into(results.new_swap_source_amount).unwrap()
+        );
+        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        assert_eq!(
+            token_b.amount,
+            initial_b + to_u64(first_swap_amount).unwrap()
+                - to_u64(results.source_amount_swapped).unwrap()
+        );
+
+        let second_fee = swap_curve
+            .withdraw_single_token_type_exact_out(
+                results.owner_fee,
+                token_a_amount.try_into().unwrap(),
+                token_b_amount.try_into().unwrap(),
+                initial_supply.try_into().unwrap(),
+                TradeDirection::BtoA,
+                &fees,
+            )
+            .unwrap();
+        let fee_account =
+            spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
+        assert_eq!(fee_account.amount, to_u64(first_fee + second_fee).unwrap());
+    }
+
+    #[test]
+    fn test_valid_swap_curves_all_fees() {
+        // All fees
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 10;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 30;
+        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_denominator = 30;
+        let host_fee_numerator = 20;
+        let host_fee_denominator = 100;
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
 
-            let old_a_key = accounts.token_a_key;
-            let old_a_account = accounts.token_a_account;
+        let token_a_amount = 10_000_000_000;
+        let token_b_amount = 50_000_000_000;
 
-            accounts.token_a_key = token_a_key;
-            accounts.token_a_account = token_a_account.clone();
+        check_valid_swap_curve(
+            fees.clone(),
+            CurveType::ConstantProduct,
+            Arc::new(ConstantProductCurve {}),
+            token_a_amount,
+            token_b_amount,
+        );
+        let token_b_price = 1;
+        check_valid_swap_curve(
+            fees.clone(),
+            CurveType::ConstantPrice,
+            Arc::new(ConstantPriceCurve { token_b_price }),
+            token_a_amount,
+            token_b_amount,
+        );
+        let token_b_offset = 10_000_000_000;
+        check_valid_swap_curve(
+            fees,
+            CurveType::Offset,
+            Arc::new(OffsetCurve { token_b_offset }),
+            token_a_amount,
+            token_b_amount,
+        );
+    }
 
-            // wrong swap token a account
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b,
-                )
-            );
+    #[test]
+    fn test_valid_swap_curves_trade_fee_only() {
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 10;
+        let owner_trade_fee_numerator = 0;
+        let owner_trade_fee_denominator = 0;
+        let owner_withdraw_fee_numerator = 0;
+        let owner_withdraw_fee_denominator = 0;
+        let host_fee_numerator = 0;
+        let host_fee_denominator = 0;
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
 
-            accounts.token_a_key = old_a_key;
-            accounts.token_a_account = old_a_account;
+        let token_a_amount = 10_000_000_000;
+        let token_b_amount = 50_000_000_000;
 
-            let old_b_key = accounts.token_b_key;
-            let old_b_account = accounts.token_b_account;
+        check_valid_swap_curve(
+            fees.clone(),
+            CurveType::ConstantProduct,
+            Arc::new(ConstantProductCurve {}),
+            token_a_amount,
+            token_b_amount,
+        );
+        let token_b_price = 10_000;
+        check_valid_swap_curve(
+            fees.clone(),
+            CurveType::ConstantPrice,
+            Arc::new(ConstantPriceCurve { token_b_price }),
+            token_a_amount,
+            token_b_amount / token_b_price,
+        );
+        let token_b_offset = 1;
+        check_valid_swap_curve(
+            fees,
+            CurveType::Offset,
+            Arc::new(OffsetCurve { token_b_offset }),
+            token_a_amount,
+            token_b_amount,
+        );
+    }
 
-            accounts.token_b_key = token_b_key;
-            accounts.token_b_account = token_b_account.clone();
+    #[test]
+    fn test_valid_swap_with_fee_constraints() {
+        let owner_key = Pubkey::new_unique();
 
-            // wrong swap token b account
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b,
-                )
-            );
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 10;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 30;
+        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_denominator = 30;
+        let host_fee_numerator = 10;
+        let host_fee_denominator = 100;
 
-            accounts.token_b_key = old_b_key;
-            accounts.token_b_account = old_b_account;
-        }
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 5_000_000;
 
-        // wrong mint
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let (pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &accounts.authority_key, None);
-            let old_pool_key = accounts.pool_mint_key;
-            let old_pool_account = accounts.pool_mint_account;
-            accounts.pool_mint_key = pool_mint_key;
-            accounts.pool_mint_account = pool_mint_account;
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
 
-            assert_eq!(
-                Err(SwapError::IncorrectPoolMint.into()),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b,
-                )
-            );
+        let curve = ConstantProductCurve {};
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(curve),
+        };
 
-            accounts.pool_mint_key = old_pool_key;
-            accounts.pool_mint_account = old_pool_account;
-        }
+        let owner_key_str = &owner_key.to_string();
+        let valid_curve_types = &[CurveType::ConstantProduct];
+        let max_dynamic_fees = &DynamicFees::default();
+        let constraints = Some(SwapConstraints {
+            owner_key: owner_key_str,
+            valid_curve_types,
+            fees: &fees,
+            pause_authority: None,
+            max_dynamic_fees,
+            oracle_program_id: None,
+        });
+        let mut accounts = SwapAccountInfo::new(
+            &owner_key,
+            fees.clone(),
+            swap_curve,
+            token_a_amount,
+            token_b_amount,
+        );
 
-        // deposit 1 pool token fails beacuse it equates to 0 swap tokens
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            assert_eq!(
-                Err(SwapError::ZeroTradingTokens.into()),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    1,
-                    deposit_a,
-                    deposit_b,
-                )
-            );
-        }
+        // initialize swap
+        do_process_instruction_with_fee_constraints(
+            initialize(
+                &SWAP_PROGRAM_ID,
+                &spl_token::id(),
+                &accounts.swap_key,
+                &accounts.authority_key,
+                &accounts.token_a_key,
+                &accounts.token_b_key,
+                &accounts.pool_mint_key,
+                &accounts.pool_fee_key,
+                &accounts.pool_token_key,
+                &accounts.token_a_mint_key,
+                &accounts.token_b_mint_key,
+                None,
+                None,
+                accounts.fees.clone(),
+                accounts.swap_curve.clone(),
+            )
+            .unwrap(),
+            vec![
+                &mut accounts.swap_account,
+                &mut Account::default(),
+                &mut accounts.token_a_account,
+                &mut accounts.token_b_account,
+                &mut accounts.pool_mint_account,
+                &mut accounts.pool_fee_account,
+                &mut accounts.pool_token_account,
+                &mut Account::default(),
+                &mut accounts.token_a_mint_account,
+                &mut accounts.token_b_mint_account,
+            ],
+            &constraints,
+        )
+        .unwrap();
 
-        // slippage exceeded
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            // maximum A amount in too low
-            assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a / 10,
-                    deposit_b,
-                )
-            );
-            // maximum B amount in too low
-            assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b / 10,
-                )
-            );
-        }
+        let authority_key = accounts.authority_key;
 
-        // invalid input: can't use swap pool tokens as source
-        {
-            let (
-                _token_a_key,
-                _token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let swap_token_a_key = accounts.token_a_key;
-            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
-            let swap_token_b_key = accounts.token_b_key;
-            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
-            let authority_key = accounts.authority_key;
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.deposit_all_token_types(
-                    &authority_key,
-                    &swap_token_a_key,
-                    &mut swap_token_a_account,
-                    &swap_token_b_key,
-                    &mut swap_token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b,
-                )
-            );
-        }
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(
+            &owner_key,
+            &authority_key,
+            token_a_amount,
+            token_b_amount,
+            0,
+        );
 
-        // correctly deposit
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            accounts
-                .deposit_all_token_types(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b,
-                )
-                .unwrap();
+        let amount_in = token_a_amount / 2;
+        let minimum_amount_out = 0;
 
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-            assert_eq!(swap_token_a.amount, deposit_a + token_a_amount);
-            let swap_token_b =
-                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-            assert_eq!(swap_token_b.amount, deposit_b + token_b_amount);
-            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-            assert_eq!(token_a.amount, 0);
-            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-            assert_eq!(token_b.amount, 0);
-            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
-            let swap_pool_account =
-                spl_token::state::Account::unpack(&accounts.pool_token_account.data).unwrap();
-            let pool_mint =
-                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-            assert_eq!(
-                pool_mint.supply,
-                pool_account.amount + swap_pool_account.amount
-            );
-        }
+        // perform the swap
+        do_process_instruction_with_fee_constraints(
+            swap(
+                &SWAP_PROGRAM_ID,
+                &spl_token::id(),
+                &spl_token::id(),
+                &spl_token::id(),
+                &accounts.swap_key,
+                &accounts.authority_key,
+                &accounts.authority_key,
+                &token_a_key,
+                &accounts.token_a_key,
+                &accounts.token_b_key,
+                &token_b_key,
+                &accounts.pool_mint_key,
+                &accounts.pool_fee_key,
+                &accounts.token_a_mint_key,
+                &accounts.token_b_mint_key,
+                None,
+                Some(&pool_key),
+                Swap {
+                    amount_in,
+                    minimum_amount_out,
+                },
+            )
+            .unwrap(),
+            vec![
+                &mut accounts.swap_account,
+                &mut Account::default(),
+                &mut Account::default(),
+                &mut token_a_account,
+                &mut accounts.token_a_account,
+                &mut accounts.token_b_account,
+                &mut token_b_account,
+                &mut accounts.pool_mint_account,
+                &mut accounts.pool_fee_account,
+                &mut accounts.token_a_mint_account,
+                &mut accounts.token_b_mint_account,
+                &mut Account::default(),
+                &mut Account::default(),
+                &mut Account::default(),
+                &mut pool_account,
+            ],
+            &constraints,
+        )
+        .unwrap();
+
+        // check that fees were taken in the host fee account
+        let host_fee_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
+        let owner_fee_account =
+            spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
+        let total_fee = owner_fee_account.amount * host_fee_denominator
+            / (host_fee_denominator - host_fee_numerator);
+        assert_eq!(
+            total_fee,
+            host_fee_account.amount + owner_fee_account.amount
+        );
     }
 
     #[test]
-    fn test_withdraw() {
+    fn test_invalid_swap() {
         let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
         let trade_fee_numerator = 1;
-        let trade_fee_denominator = 2;
+        let trade_fee_denominator = 4;
         let owner_trade_fee_numerator = 1;
         let owner_trade_fee_denominator = 10;
         let owner_withdraw_fee_numerator = 1;
         let owner_withdraw_fee_denominator = 5;
-        let host_fee_numerator = 7;
+        let host_fee_numerator = 9;
         let host_fee_denominator = 100;
-
         let fees = Fees {
             trade_fee_numerator,
             trade_fee_denominator,
@@ -3427,24 +8466,22 @@ mod tests {
         };
 
         let token_a_amount = 1000;
-        let token_b_amount = 2000;
+        let token_b_amount = 5000;
         let curve_type = CurveType::ConstantProduct;
         let swap_curve = SwapCurve {
             curve_type,
             calculator: Arc::new(ConstantProductCurve {}),
         };
-
-        let withdrawer_key = Pubkey::new_unique();
-        let initial_a = token_a_amount / 10;
-        let initial_b = token_b_amount / 10;
-        let initial_pool = swap_curve.calculator.new_pool_supply() / 10;
-        let withdraw_amount = initial_pool / 4;
-        let minimum_token_a_amount = initial_a / 40;
-        let minimum_token_b_amount = initial_b / 40;
-
         let mut accounts =
             SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
 
+        let initial_a = token_a_amount / 5;
+        let initial_b = token_b_amount / 5;
+        let minimum_token_b_amount = initial_b / 2;
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+
         // swap not initialized
         {
             let (
@@ -3452,21 +8489,20 @@ mod tests {
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             assert_eq!(
                 Err(ProgramError::UninitializedAccount),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                accounts.swap(
+                    &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
+                    initial_a,
                     minimum_token_b_amount,
                 )
             );
@@ -3474,48 +8510,47 @@ mod tests {
 
         accounts.initialize_swap().unwrap();
 
-        // wrong owner for swap account
+        // wrong swap account program id
         {
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             let old_swap_account = accounts.swap_account;
             let mut wrong_swap_account = old_swap_account.clone();
             wrong_swap_account.owner = spl_token::id();
             accounts.swap_account = wrong_swap_account;
             assert_eq!(
                 Err(ProgramError::IncorrectProgramId),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                accounts.swap(
+                    &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
+                    initial_a,
                     minimum_token_b_amount,
                 )
             );
             accounts.swap_account = old_swap_account;
         }
 
-        // wrong bump seed for authority_key
+        // wrong bump seed
         {
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             let old_authority = accounts.authority_key;
             let (bad_authority_key, _bump_seed) = Pubkey::find_program_address(
                 &[&accounts.swap_key.to_bytes()[..]],
@@ -3524,183 +8559,278 @@ mod tests {
             accounts.authority_key = bad_authority_key;
             assert_eq!(
                 Err(SwapError::InvalidProgramAddress.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                accounts.swap(
+                    &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
+                    initial_a,
                     minimum_token_b_amount,
                 )
             );
             accounts.authority_key = old_authority;
         }
 
-        // not enough pool tokens
+        // wrong token program id
         {
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                to_u64(withdraw_amount).unwrap() / 2u64,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let wrong_program_id = Pubkey::new_unique();
+            assert_eq!(
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                do_process_instruction(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &wrong_program_id,
+                        &wrong_program_id,
+                        &wrong_program_id,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_b_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        None,
+                        None,
+                        Swap {
+                            amount_in: initial_a,
+                            minimum_amount_out: minimum_token_b_amount,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
+                ),
             );
+        }
+
+        // not enough token a to swap
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             assert_eq!(
                 Err(TokenError::InsufficientFunds.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                accounts.swap(
+                    &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount / 2,
-                    minimum_token_b_amount / 2,
+                    initial_a * 2,
+                    minimum_token_b_amount * 2,
                 )
             );
         }
 
-        // wrong token a / b accounts
+        // wrong swap token A / B accounts
         {
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount.try_into().unwrap(),
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let user_transfer_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                do_process_instruction(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &spl_token::id(),
+                        &spl_token::id(),
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &user_transfer_key,
+                        &token_a_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &token_b_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        None,
+                        None,
+                        Swap {
+                            amount_in: initial_a,
+                            minimum_amount_out: minimum_token_b_amount,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut token_a_account.clone(),
+                        &mut token_a_account,
+                        &mut token_b_account.clone(),
+                        &mut token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
+                ),
             );
+        }
+
+        // wrong user token A / B accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             assert_eq!(
                 Err(TokenError::MintMismatch.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                accounts.swap(
+                    &swapper_key,
                     &token_b_key,
                     &mut token_b_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_a_key,
                     &mut token_a_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
+                    initial_a,
                     minimum_token_b_amount,
                 )
             );
         }
 
-        // wrong pool token account
+        // swap from a to a
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount.try_into().unwrap(),
-            );
-            let (
-                wrong_token_a_key,
-                mut wrong_token_a_account,
                 _token_b_key,
                 _token_b_account,
                 _pool_key,
                 _pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                withdraw_amount.try_into().unwrap(),
-                initial_b,
-                withdraw_amount.try_into().unwrap(),
-            );
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &wrong_token_a_key,
-                    &mut wrong_token_a_account,
+                Err(SwapError::InvalidInput.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account.clone(),
+                    &swap_token_a_key,
+                    &swap_token_a_key,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
+                    initial_a,
                     minimum_token_b_amount,
                 )
             );
         }
 
-        // wrong pool fee account
+        // incorrect mint provided
         {
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                wrong_pool_key,
-                wrong_pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount.try_into().unwrap(),
-            );
-            let (
-                _token_a_key,
-                _token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount.try_into().unwrap(),
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let (pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &accounts.authority_key, None);
+            let old_pool_key = accounts.pool_mint_key;
+            let old_pool_account = accounts.pool_mint_account;
+            accounts.pool_mint_key = pool_mint_key;
+            accounts.pool_mint_account = pool_mint_account;
+
+            assert_eq!(
+                Err(SwapError::IncorrectPoolMint.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    initial_a,
+                    minimum_token_b_amount,
+                )
             );
+
+            accounts.pool_mint_key = old_pool_key;
+            accounts.pool_mint_account = old_pool_account;
+        }
+
+        // incorrect fee account provided
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                wrong_pool_key,
+                wrong_pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             let old_pool_fee_account = accounts.pool_fee_account;
             let old_pool_fee_key = accounts.pool_fee_key;
             accounts.pool_fee_account = wrong_pool_account;
             accounts.pool_fee_key = wrong_pool_key;
             assert_eq!(
                 Err(SwapError::IncorrectFeeAccount.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                accounts.swap(
+                    &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
+                    initial_a,
                     minimum_token_b_amount,
-                ),
+                )
             );
             accounts.pool_fee_account = old_pool_fee_account;
             accounts.pool_fee_key = old_pool_fee_key;
@@ -3713,93 +8843,292 @@ mod tests {
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                0,
-                0,
-                withdraw_amount.try_into().unwrap(),
-            );
-            let user_transfer_authority_key = Pubkey::new_unique();
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let user_transfer_key = Pubkey::new_unique();
             assert_eq!(
                 Err(TokenError::OwnerMismatch.into()),
                 do_process_instruction(
-                    withdraw_all_token_types(
+                    swap(
                         &SWAP_PROGRAM_ID,
                         &spl_token::id(),
+                        &spl_token::id(),
+                        &spl_token::id(),
                         &accounts.swap_key,
                         &accounts.authority_key,
-                        &user_transfer_authority_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        &pool_key,
+                        &user_transfer_key,
+                        &token_a_key,
                         &accounts.token_a_key,
                         &accounts.token_b_key,
-                        &token_a_key,
                         &token_b_key,
-                        WithdrawAllTokenTypes {
-                            pool_token_amount: withdraw_amount.try_into().unwrap(),
-                            minimum_token_a_amount,
-                            minimum_token_b_amount,
-                        }
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        None,
+                        None,
+                        Swap {
+                            amount_in: initial_a,
+                            minimum_amount_out: minimum_token_b_amount,
+                        },
                     )
                     .unwrap(),
                     vec![
                         &mut accounts.swap_account,
                         &mut Account::default(),
                         &mut Account::default(),
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
+                        &mut token_a_account,
                         &mut accounts.token_a_account,
                         &mut accounts.token_b_account,
-                        &mut token_a_account,
                         &mut token_b_account,
+                        &mut accounts.pool_mint_account,
                         &mut accounts.pool_fee_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
                         &mut Account::default(),
                     ],
+                ),
+            );
+        }
+
+        // output token value 0
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(SwapError::ZeroTradingTokens.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &swap_token_b_key,
+                    &swap_token_a_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    1,
+                    1,
                 )
             );
         }
 
-        // wrong token program id
+        // slippage exceeded: minimum out amount too high
         {
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount.try_into().unwrap(),
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    initial_a,
+                    minimum_token_b_amount * 2,
+                )
             );
-            let wrong_key = Pubkey::new_unique();
+        }
+
+        // invalid input: can't use swap pool as user source / dest
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
+            let authority_key = accounts.authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.swap(
+                    &authority_key,
+                    &swap_token_a_key,
+                    &mut swap_token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    initial_a,
+                    minimum_token_b_amount,
+                )
+            );
+            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &swap_token_b_key,
+                    &mut swap_token_b_account,
+                    initial_a,
+                    minimum_token_b_amount,
+                )
+            );
+        }
+
+        // still correct: constraint specified, no host fee account
+        {
+            let authority_key = accounts.authority_key;
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &authority_key, initial_a, initial_b, 0);
+            let owner_key = &swapper_key.to_string();
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let max_dynamic_fees = &DynamicFees::default();
+            let constraints = Some(SwapConstraints {
+                owner_key,
+                valid_curve_types: &[],
+                fees: &fees,
+                pause_authority: None,
+                max_dynamic_fees,
+                oracle_program_id: None,
+            });
+            do_process_instruction_with_fee_constraints(
+                swap(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &spl_token::id(),
+                    &spl_token::id(),
+                    &accounts.swap_key,
+                    &accounts.authority_key,
+                    &accounts.authority_key,
+                    &token_a_key,
+                    &accounts.token_a_key,
+                    &accounts.token_b_key,
+                    &token_b_key,
+                    &accounts.pool_mint_key,
+                    &accounts.pool_fee_key,
+                    &accounts.token_a_mint_key,
+                    &accounts.token_b_mint_key,
+                    None,
+                    None,
+                    Swap {
+                        amount_in: initial_a,
+                        minimum_amount_out: minimum_token_b_amount,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut token_a_account,
+                    &mut accounts.token_a_account,
+                    &mut accounts.token_b_account,
+                    &mut token_b_account,
+                    &mut accounts.pool_mint_account,
+                    &mut accounts.pool_fee_account,
+                    &mut accounts.token_a_mint_account,
+                    &mut accounts.token_b_mint_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+                &constraints,
+            )
+            .unwrap();
+        }
+
+        // invalid mint for host fee account
+        {
+            let authority_key = accounts.authority_key;
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &authority_key, initial_a, initial_b, 0);
+            let (
+                bad_token_a_key,
+                mut bad_token_a_account,
+                _token_b_key,
+                mut _token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &authority_key, initial_a, initial_b, 0);
+            let owner_key = &swapper_key.to_string();
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let max_dynamic_fees = &DynamicFees::default();
+            let constraints = Some(SwapConstraints {
+                owner_key,
+                valid_curve_types: &[],
+                fees: &fees,
+                pause_authority: None,
+                max_dynamic_fees,
+                oracle_program_id: None,
+            });
             assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
-                do_process_instruction(
-                    withdraw_all_token_types(
+                Err(SwapError::IncorrectPoolMint.into()),
+                do_process_instruction_with_fee_constraints(
+                    swap(
                         &SWAP_PROGRAM_ID,
-                        &wrong_key,
+                        &spl_token::id(),
+                        &spl_token::id(),
+                        &spl_token::id(),
                         &accounts.swap_key,
                         &accounts.authority_key,
                         &accounts.authority_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        &pool_key,
+                        &token_a_key,
                         &accounts.token_a_key,
                         &accounts.token_b_key,
-                        &token_a_key,
                         &token_b_key,
-                        WithdrawAllTokenTypes {
-                            pool_token_amount: withdraw_amount.try_into().unwrap(),
-                            minimum_token_a_amount,
-                            minimum_token_b_amount,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        None,
+                        Some(&bad_token_a_key),
+                        Swap {
+                            amount_in: initial_a,
+                            minimum_amount_out: 0,
                         },
                     )
                     .unwrap(),
@@ -3807,167 +9136,149 @@ mod tests {
                         &mut accounts.swap_account,
                         &mut Account::default(),
                         &mut Account::default(),
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
+                        &mut token_a_account,
                         &mut accounts.token_a_account,
                         &mut accounts.token_b_account,
-                        &mut token_a_account,
                         &mut token_b_account,
+                        &mut accounts.pool_mint_account,
                         &mut accounts.pool_fee_account,
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
                         &mut Account::default(),
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut bad_token_a_account,
                     ],
-                )
+                    &constraints,
+                ),
             );
         }
+    }
 
-        // wrong swap token accounts
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
-            );
-
-            let old_a_key = accounts.token_a_key;
-            let old_a_account = accounts.token_a_account;
+    #[test]
+    fn test_overdraw_offset_curve() {
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 10;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 30;
+        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_denominator = 30;
+        let host_fee_numerator = 10;
+        let host_fee_denominator = 100;
 
-            accounts.token_a_key = token_a_key;
-            accounts.token_a_account = token_a_account.clone();
+        let token_a_amount = 1_000_000_000;
+        let token_b_amount = 0;
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
 
-            // wrong swap token a account
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
-            );
+        let token_b_offset = 2_000_000;
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::Offset,
+            calculator: Arc::new(OffsetCurve { token_b_offset }),
+        };
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
 
-            accounts.token_a_key = old_a_key;
-            accounts.token_a_account = old_a_account;
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
 
-            let old_b_key = accounts.token_b_key;
-            let old_b_account = accounts.token_b_account;
+        accounts.initialize_swap().unwrap();
 
-            accounts.token_b_key = token_b_key;
-            accounts.token_b_account = token_b_account.clone();
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let initial_a = 500_000;
+        let initial_b = 1_000;
 
-            // wrong swap token b account
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
-            );
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
 
-            accounts.token_b_key = old_b_key;
-            accounts.token_b_account = old_b_account;
-        }
+        // swap a to b way, fails, there's no liquidity
+        let a_to_b_amount = initial_a;
+        let minimum_token_b_amount = 0;
 
-        // wrong mint
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
-            );
-            let (pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &accounts.authority_key, None);
-            let old_pool_key = accounts.pool_mint_key;
-            let old_pool_account = accounts.pool_mint_account;
-            accounts.pool_mint_key = pool_mint_key;
-            accounts.pool_mint_account = pool_mint_account;
+        assert_eq!(
+            Err(SwapError::ZeroTradingTokens.into()),
+            accounts.swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                minimum_token_b_amount,
+            )
+        );
 
-            assert_eq!(
-                Err(SwapError::IncorrectPoolMint.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
-            );
+        // swap b to a, succeeds at offset price
+        let b_to_a_amount = initial_b;
+        let minimum_token_a_amount = 0;
+        accounts
+            .swap(
+                &swapper_key,
+                &token_b_key,
+                &mut token_b_account,
+                &swap_token_b_key,
+                &swap_token_a_key,
+                &token_a_key,
+                &mut token_a_account,
+                b_to_a_amount,
+                minimum_token_a_amount,
+            )
+            .unwrap();
 
-            accounts.pool_mint_key = old_pool_key;
-            accounts.pool_mint_account = old_pool_account;
-        }
+        // try a to b again, succeeds due to new liquidity
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                minimum_token_b_amount,
+            )
+            .unwrap();
 
-        // withdrawing 1 pool token fails because it equates to 0 output tokens
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
-            );
-            assert_eq!(
-                Err(SwapError::ZeroTradingTokens.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    1,
-                    0,
-                    0,
-                )
-            );
-        }
+        // try a to b again, fails due to no more liquidity
+        assert_eq!(
+            Err(SwapError::ZeroTradingTokens.into()),
+            accounts.swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                minimum_token_b_amount,
+            )
+        );
 
-        // slippage exceeded
+        // Try to deposit, fails because deposits are not allowed for offset
+        // curve swaps
         {
+            let initial_a = 100;
+            let initial_b = 100;
+            let pool_amount = 100;
             let (
                 token_a_key,
                 mut token_a_account,
@@ -3975,254 +9286,291 @@ mod tests {
                 mut token_b_account,
                 pool_key,
                 mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
-            );
-            // minimum A amount out too high
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                Err(SwapError::UnsupportedCurveOperation.into()),
+                accounts.deposit_all_token_types(
+                    &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount * 10,
-                    minimum_token_b_amount,
-                )
-            );
-            // minimum B amount out too high
-            assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
                     &pool_key,
                     &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount * 10,
+                    pool_amount,
+                    initial_a,
+                    initial_b,
                 )
             );
         }
+    }
 
-        // invalid input: can't use swap pool tokens as destination
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
+    #[test]
+    fn test_withdraw_all_offset_curve() {
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 10;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 30;
+        let owner_withdraw_fee_numerator = 0;
+        let owner_withdraw_fee_denominator = 30;
+        let host_fee_numerator = 10;
+        let host_fee_denominator = 100;
+
+        let token_a_amount = 1_000_000_000;
+        let token_b_amount = 10;
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let token_b_offset = 2_000_000;
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::Offset,
+            calculator: Arc::new(OffsetCurve { token_b_offset }),
+        };
+        let total_pool = swap_curve.calculator.new_pool_supply();
+        let user_key = Pubkey::new_unique();
+        let withdrawer_key = Pubkey::new_unique();
+
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+
+        accounts.initialize_swap().unwrap();
+
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 0);
+
+        let pool_key = accounts.pool_token_key;
+        let mut pool_account = accounts.pool_token_account.clone();
+
+        // WithdrawAllTokenTypes takes all tokens for A and B.
+        // The curve's calculation for token B will say to transfer
+        // `token_b_offset + token_b_amount`, but only `token_b_amount` will be
+        // moved.
+        accounts
+            .withdraw_all_token_types(
                 &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
-            );
-            let swap_token_a_key = accounts.token_a_key;
-            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &swap_token_a_key,
-                    &mut swap_token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
-            );
-            let swap_token_b_key = accounts.token_b_key;
-            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_b_key,
-                    &mut swap_token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
-            );
-        }
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                total_pool.try_into().unwrap(),
+                0,
+                0,
+            )
+            .unwrap();
+
+        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+        assert_eq!(token_a.amount, token_a_amount);
+        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        assert_eq!(token_b.amount, token_b_amount);
+        let swap_token_a =
+            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+        assert_eq!(swap_token_a.amount, 0);
+        let swap_token_b =
+            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+        assert_eq!(swap_token_b.amount, 0);
+    }
+
+    #[test]
+    fn test_withdraw_all_constant_price_curve() {
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 10;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 30;
+        let owner_withdraw_fee_numerator = 0;
+        let owner_withdraw_fee_denominator = 30;
+        let host_fee_numerator = 10;
+        let host_fee_denominator = 100;
+
+        // initialize "unbalanced", so that withdrawing all will have some issues
+        // A: 1_000_000_000
+        // B: 2_000_000_000 (1_000 * 2_000_000)
+        let swap_token_a_amount = 1_000_000_000;
+        let swap_token_b_amount = 1_000;
+        let token_b_price = 2_000_000;
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantPrice,
+            calculator: Arc::new(ConstantPriceCurve { token_b_price }),
+        };
+        let total_pool = swap_curve.calculator.new_pool_supply();
+        let user_key = Pubkey::new_unique();
+        let withdrawer_key = Pubkey::new_unique();
 
-        // correct withdrawal
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees,
+            swap_curve,
+            swap_token_a_amount,
+            swap_token_b_amount,
+        );
+
+        accounts.initialize_swap().unwrap();
+
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 0);
+
+        let pool_key = accounts.pool_token_key;
+        let mut pool_account = accounts.pool_token_account.clone();
+
+        // WithdrawAllTokenTypes will not take all token A and B, since their
+        // ratio is unbalanced.  It will try to take 1_500_000_000 worth of
+        // each token, which means 1_500_000_000 token A, and 750 token B.
+        // With no slippage, this will leave 250 token B in the pool.
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.withdraw_all_token_types(
                 &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
-            );
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                total_pool.try_into().unwrap(),
+                swap_token_a_amount,
+                swap_token_b_amount,
+            )
+        );
 
-            accounts
-                .withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
-                .unwrap();
+        accounts
+            .withdraw_all_token_types(
+                &user_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                total_pool.try_into().unwrap(),
+                0,
+                0,
+            )
+            .unwrap();
 
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-            let swap_token_b =
-                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-            let pool_mint =
-                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-            let withdraw_fee = accounts.fees.owner_withdraw_fee(withdraw_amount).unwrap();
-            let results = accounts
-                .swap_curve
-                .calculator
-                .pool_tokens_to_trading_tokens(
-                    withdraw_amount - withdraw_fee,
-                    pool_mint.supply.try_into().unwrap(),
-                    swap_token_a.amount.try_into().unwrap(),
-                    swap_token_b.amount.try_into().unwrap(),
-                    RoundDirection::Floor,
-                )
-                .unwrap();
-            assert_eq!(
-                swap_token_a.amount,
-                token_a_amount - to_u64(results.token_a_amount).unwrap()
-            );
-            assert_eq!(
-                swap_token_b.amount,
-                token_b_amount - to_u64(results.token_b_amount).unwrap()
-            );
-            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-            assert_eq!(
-                token_a.amount,
-                initial_a + to_u64(results.token_a_amount).unwrap()
-            );
-            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-            assert_eq!(
-                token_b.amount,
-                initial_b + to_u64(results.token_b_amount).unwrap()
-            );
-            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
-            assert_eq!(
-                pool_account.amount,
-                to_u64(initial_pool - withdraw_amount).unwrap()
-            );
-            let fee_account =
-                spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
-            assert_eq!(
-                fee_account.amount,
-                TryInto::<u64>::try_into(withdraw_fee).unwrap()
-            );
-        }
+        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+        assert_eq!(token_a.amount, swap_token_a_amount);
+        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        assert_eq!(token_b.amount, 750);
+        let swap_token_a =
+            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+        assert_eq!(swap_token_a.amount, 0);
+        let swap_token_b =
+            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+        assert_eq!(swap_token_b.amount, 250);
 
-        // correct withdrawal from fee account
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                mut _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 0);
+        // deposit now, not enough to cover the tokens already in there
+        let token_b_amount = 10;
+        let token_a_amount = token_b_amount * token_b_price;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(
+            &user_key,
+            &withdrawer_key,
+            token_a_amount,
+            token_b_amount,
+            0,
+        );
 
-            let pool_fee_key = accounts.pool_fee_key;
-            let mut pool_fee_account = accounts.pool_fee_account.clone();
-            let fee_account = spl_token::state::Account::unpack(&pool_fee_account.data).unwrap();
-            let pool_fee_amount = fee_account.amount;
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.deposit_all_token_types(
+                &withdrawer_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                1, // doesn't matter
+                token_a_amount,
+                token_b_amount,
+            )
+        );
 
-            accounts
-                .withdraw_all_token_types(
-                    &user_key,
-                    &pool_fee_key,
-                    &mut pool_fee_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    pool_fee_amount,
-                    0,
-                    0,
-                )
-                .unwrap();
+        // deposit enough tokens, success!
+        let token_b_amount = 125;
+        let token_a_amount = token_b_amount * token_b_price;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(
+            &user_key,
+            &withdrawer_key,
+            token_a_amount,
+            token_b_amount,
+            0,
+        );
 
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-            let swap_token_b =
-                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-            let pool_mint =
-                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-            let results = accounts
-                .swap_curve
-                .calculator
-                .pool_tokens_to_trading_tokens(
-                    pool_fee_amount.try_into().unwrap(),
-                    pool_mint.supply.try_into().unwrap(),
-                    swap_token_a.amount.try_into().unwrap(),
-                    swap_token_b.amount.try_into().unwrap(),
-                    RoundDirection::Floor,
-                )
-                .unwrap();
-            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-            assert_eq!(
-                token_a.amount,
-                TryInto::<u64>::try_into(results.token_a_amount).unwrap()
-            );
-            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-            assert_eq!(
-                token_b.amount,
-                TryInto::<u64>::try_into(results.token_b_amount).unwrap()
-            );
-        }
+        accounts
+            .deposit_all_token_types(
+                &withdrawer_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                1, // doesn't matter
+                token_a_amount,
+                token_b_amount,
+            )
+            .unwrap();
     }
 
     #[test]
-    fn test_deposit_one_exact_in() {
-        let user_key = Pubkey::new_unique();
-        let depositor_key = Pubkey::new_unique();
+    fn test_deposits_allowed_single_token() {
         let trade_fee_numerator = 1;
-        let trade_fee_denominator = 2;
+        let trade_fee_denominator = 10;
         let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 10;
-        let owner_withdraw_fee_numerator = 1;
-        let owner_withdraw_fee_denominator = 5;
-        let host_fee_numerator = 20;
+        let owner_trade_fee_denominator = 30;
+        let owner_withdraw_fee_numerator = 0;
+        let owner_withdraw_fee_denominator = 30;
+        let host_fee_numerator = 10;
         let host_fee_denominator = 100;
 
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 0;
         let fees = Fees {
             trade_fee_numerator,
             trade_fee_denominator,
@@ -4234,1158 +9582,1473 @@ mod tests {
             host_fee_denominator,
         };
 
-        let token_a_amount = 1000;
-        let token_b_amount = 9000;
-        let curve_type = CurveType::ConstantProduct;
+        let token_b_offset = 2_000_000;
         let swap_curve = SwapCurve {
-            curve_type,
-            calculator: Arc::new(ConstantProductCurve {}),
+            curve_type: CurveType::Offset,
+            calculator: Arc::new(OffsetCurve { token_b_offset }),
         };
+        let creator_key = Pubkey::new_unique();
+        let depositor_key = Pubkey::new_unique();
 
-        let mut accounts =
-            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
-
-        let deposit_a = token_a_amount / 10;
-        let deposit_b = token_b_amount / 10;
-        let pool_amount = to_u64(INITIAL_SWAP_POOL_AMOUNT / 100).unwrap();
-
-        // swap not initialized
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    pool_amount,
-                )
-            );
-        }
+        let mut accounts = SwapAccountInfo::new(
+            &creator_key,
+            fees,
+            swap_curve,
+            token_a_amount,
+            token_b_amount,
+        );
 
         accounts.initialize_swap().unwrap();
 
-        // wrong owner for swap account
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let old_swap_account = accounts.swap_account;
-            let mut wrong_swap_account = old_swap_account.clone();
-            wrong_swap_account.owner = spl_token::id();
-            accounts.swap_account = wrong_swap_account;
-            assert_eq!(
-                Err(ProgramError::IncorrectProgramId),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    pool_amount,
-                )
-            );
-            accounts.swap_account = old_swap_account;
-        }
-
-        // wrong bump seed for authority_key
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _bump_seed) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &spl_token::id(),
-            );
-            accounts.authority_key = bad_authority_key;
-            assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    pool_amount,
-                )
-            );
-            accounts.authority_key = old_authority;
-        }
+        let initial_a = 1_000_000;
+        let initial_b = 2_000_000;
+        let (
+            _depositor_token_a_key,
+            _depositor_token_a_account,
+            depositor_token_b_key,
+            mut depositor_token_b_account,
+            depositor_pool_key,
+            mut depositor_pool_account,
+        ) = accounts.setup_token_accounts(&creator_key, &depositor_key, initial_a, initial_b, 0);
 
-        // not enough token A / B
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
+        assert_eq!(
+            Err(SwapError::UnsupportedCurveOperation.into()),
+            accounts.deposit_single_token_type_exact_amount_in(
                 &depositor_key,
-                deposit_a / 2,
-                deposit_b / 2,
+                &depositor_token_b_key,
+                &mut depositor_token_b_account,
+                &depositor_pool_key,
+                &mut depositor_pool_account,
+                initial_b,
                 0,
-            );
-            assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    0,
-                )
-            );
-            assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_b,
-                    0,
-                )
-            );
-        }
-
-        // wrong pool token account
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                mut _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    deposit_a,
-                    pool_amount,
-                )
-            );
-        }
+            )
+        );
+    }
 
-        // no approval
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let user_transfer_authority_key = Pubkey::new_unique();
-            assert_eq!(
-                Err(TokenError::OwnerMismatch.into()),
-                do_process_instruction(
-                    deposit_single_token_type_exact_amount_in(
-                        &SWAP_PROGRAM_ID,
-                        &spl_token::id(),
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &user_transfer_authority_key,
-                        &token_a_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &accounts.pool_mint_key,
-                        &pool_key,
-                        DepositSingleTokenTypeExactAmountIn {
-                            source_token_amount: deposit_a,
-                            minimum_pool_token_amount: pool_amount,
-                        },
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut Account::default(),
-                        &mut token_a_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut Account::default(),
-                    ],
-                )
+    #[test]
+    fn test_pause() {
+        let user_key = Pubkey::new_unique();
+        let pause_authority_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 10,
+            owner_trade_fee_numerator: 1,
+            owner_trade_fee_denominator: 30,
+            owner_withdraw_fee_numerator: 1,
+            owner_withdraw_fee_denominator: 30,
+            host_fee_numerator: 20,
+            host_fee_denominator: 100,
+        };
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 5_000_000;
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+
+        // no pause authority, cannot pause
+        {
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                fees.clone(),
+                swap_curve.clone(),
+                token_a_amount,
+                token_b_amount,
+            );
+            accounts.initialize_swap().unwrap();
+            let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_state.pause_authority(), None);
+            assert!(!swap_state.is_paused());
+            assert_eq!(
+                Err(SwapError::InvalidPauseAuthority.into()),
+                accounts.set_paused(&pause_authority_key, true)
             );
         }
 
-        // wrong token program id
+        // account sized for the previous layout, no pause authority
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let wrong_key = Pubkey::new_unique();
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                fees.clone(),
+                swap_curve.clone(),
+                token_a_amount,
+                token_b_amount,
+            );
+            accounts.swap_account = Account::new(0, 1 + SwapV1::LEN, &SWAP_PROGRAM_ID);
+            accounts.initialize_swap().unwrap();
+            assert_eq!(accounts.swap_account.data[0], 1);
             assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
-                do_process_instruction(
-                    deposit_single_token_type_exact_amount_in(
+                Err(SwapError::UnsupportedSwapVersion.into()),
+                accounts.set_paused(&pause_authority_key, true)
+            );
+
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                fees.clone(),
+                swap_curve.clone(),
+                token_a_amount,
+                token_b_amount,
+            );
+            accounts.swap_account = Account::new(0, 1 + SwapV1::LEN, &SWAP_PROGRAM_ID);
+            accounts.pause_authority_key = Some(pause_authority_key);
+            assert_eq!(
+                Err(ProgramError::InvalidAccountData),
+                accounts.initialize_swap()
+            );
+        }
+
+        // pause authority required by constraints
+        {
+            let owner_key = &user_key.to_string();
+            let valid_curve_types = &[CurveType::ConstantProduct];
+            let max_dynamic_fees = &DynamicFees::default();
+            let constraints = Some(SwapConstraints {
+                owner_key,
+                valid_curve_types,
+                fees: &fees,
+                pause_authority: Some(owner_key),
+                max_dynamic_fees,
+                oracle_program_id: None,
+            });
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                fees.clone(),
+                swap_curve.clone(),
+                token_a_amount,
+                token_b_amount,
+            );
+            let (pool_fee_key, pool_fee_account) = mint_token(
+                &spl_token::id(),
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &user_key,
+                0,
+            );
+            accounts.pool_fee_key = pool_fee_key;
+            accounts.pool_fee_account = pool_fee_account;
+
+            let mut initialize_with = |pause_authority_key: Option<&Pubkey>| {
+                do_process_instruction_with_fee_constraints(
+                    initialize(
                         &SWAP_PROGRAM_ID,
-                        &wrong_key,
+                        &spl_token::id(),
                         &accounts.swap_key,
                         &accounts.authority_key,
-                        &accounts.authority_key,
-                        &token_a_key,
                         &accounts.token_a_key,
                         &accounts.token_b_key,
                         &accounts.pool_mint_key,
-                        &pool_key,
-                        DepositSingleTokenTypeExactAmountIn {
-                            source_token_amount: deposit_a,
-                            minimum_pool_token_amount: pool_amount,
-                        },
+                        &accounts.pool_fee_key,
+                        &accounts.pool_token_key,
+                        &accounts.token_a_mint_key,
+                        &accounts.token_b_mint_key,
+                        None,
+                        pause_authority_key,
+                        accounts.fees.clone(),
+                        accounts.swap_curve.clone(),
                     )
                     .unwrap(),
                     vec![
                         &mut accounts.swap_account,
                         &mut Account::default(),
-                        &mut Account::default(),
-                        &mut token_a_account,
                         &mut accounts.token_a_account,
                         &mut accounts.token_b_account,
                         &mut accounts.pool_mint_account,
-                        &mut pool_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.pool_token_account,
+                        &mut Account::default(),
+                        &mut accounts.token_a_mint_account,
+                        &mut accounts.token_b_mint_account,
                         &mut Account::default(),
                     ],
+                    &constraints,
                 )
-            );
-        }
-
-        // wrong swap token accounts
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-
-            let old_a_key = accounts.token_a_key;
-            let old_a_account = accounts.token_a_account;
-
-            accounts.token_a_key = token_a_key;
-            accounts.token_a_account = token_a_account.clone();
-
-            // wrong swap token a account
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    pool_amount,
-                )
-            );
-
-            accounts.token_a_key = old_a_key;
-            accounts.token_a_account = old_a_account;
-
-            let old_b_key = accounts.token_b_key;
-            let old_b_account = accounts.token_b_account;
-
-            accounts.token_b_key = token_b_key;
-            accounts.token_b_account = token_b_account;
-
-            // wrong swap token b account
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    pool_amount,
-                )
-            );
-
-            accounts.token_b_key = old_b_key;
-            accounts.token_b_account = old_b_account;
-        }
-
-        // wrong mint
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let (pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &accounts.authority_key, None);
-            let old_pool_key = accounts.pool_mint_key;
-            let old_pool_account = accounts.pool_mint_account;
-            accounts.pool_mint_key = pool_mint_key;
-            accounts.pool_mint_account = pool_mint_account;
-
-            assert_eq!(
-                Err(SwapError::IncorrectPoolMint.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    pool_amount,
-                )
-            );
-
-            accounts.pool_mint_key = old_pool_key;
-            accounts.pool_mint_account = old_pool_account;
-        }
-
-        // slippage exceeded
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            // minimum pool amount too high
-            assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a / 10,
-                    pool_amount,
-                )
-            );
-            // minimum pool amount too high
-            assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_b / 10,
-                    pool_amount,
-                )
-            );
-        }
-
-        // invalid input: can't use swap pool tokens as source
-        {
-            let (
-                _token_a_key,
-                _token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let swap_token_a_key = accounts.token_a_key;
-            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
-            let swap_token_b_key = accounts.token_b_key;
-            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
-            let authority_key = accounts.authority_key;
+            };
             assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &authority_key,
-                    &swap_token_a_key,
-                    &mut swap_token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    pool_amount,
-                )
+                Err(SwapError::InvalidPauseAuthority.into()),
+                initialize_with(None)
             );
             assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &authority_key,
-                    &swap_token_b_key,
-                    &mut swap_token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_b,
-                    pool_amount,
-                )
+                Err(SwapError::InvalidPauseAuthority.into()),
+                initialize_with(Some(&pause_authority_key))
             );
+            initialize_with(Some(&user_key)).unwrap();
         }
 
-        // correctly deposit
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+        accounts.pause_authority_key = Some(pause_authority_key);
+        accounts.initialize_swap().unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_state.pause_authority(), Some(&pause_authority_key));
+        assert!(!swap_state.is_paused());
+
+        // wrong pause authority
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            accounts
-                .deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    pool_amount,
+            let wrong_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(SwapError::InvalidPauseAuthority.into()),
+                accounts.set_paused(&wrong_key, true)
+            );
+        }
+
+        // pause authority did not sign
+        {
+            let mut instruction =
+                pause(&SWAP_PROGRAM_ID, &accounts.swap_key, &pause_authority_key).unwrap();
+            instruction.accounts[1].is_signer = false;
+            assert_eq!(
+                Err(SwapError::InvalidPauseAuthority.into()),
+                do_process_instruction(
+                    instruction,
+                    vec![&mut accounts.swap_account, &mut Account::default()],
                 )
-                .unwrap();
+            );
+        }
 
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-            assert_eq!(swap_token_a.amount, deposit_a + token_a_amount);
+        accounts.set_paused(&pause_authority_key, true).unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert!(swap_state.is_paused());
 
-            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-            assert_eq!(token_a.amount, 0);
+        let initial_a = token_a_amount / 5;
+        let initial_b = token_b_amount / 5;
+        let initial_pool = accounts.swap_curve.calculator.new_pool_supply() as u64 / 10;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &user_key, initial_a, initial_b, 0);
 
-            accounts
-                .deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_b,
-                    pool_amount,
-                )
-                .unwrap();
-            let swap_token_b =
-                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-            assert_eq!(swap_token_b.amount, deposit_b + token_b_amount);
+        // swap paused
+        assert_eq!(
+            Err(SwapError::SwapPaused.into()),
+            accounts.swap(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &accounts.token_a_key.clone(),
+                &accounts.token_b_key.clone(),
+                &token_b_key,
+                &mut token_b_account,
+                initial_a,
+                0,
+            )
+        );
 
-            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-            assert_eq!(token_b.amount, 0);
+        // deposits paused
+        assert_eq!(
+            Err(SwapError::SwapPaused.into()),
+            accounts.deposit_all_token_types(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                initial_pool,
+                initial_a,
+                initial_b,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::SwapPaused.into()),
+            accounts.deposit_single_token_type_exact_amount_in(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                initial_a,
+                0,
+            )
+        );
 
-            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
-            let swap_pool_account =
-                spl_token::state::Account::unpack(&accounts.pool_token_account.data).unwrap();
-            let pool_mint =
-                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+        // single-sided withdrawal paused, full withdrawal still allowed
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &user_key, 0, 0, initial_pool);
+        assert_eq!(
+            Err(SwapError::SwapPaused.into()),
+            accounts.withdraw_single_token_type_exact_amount_out(
+                &user_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                1,
+                initial_pool,
+            )
+        );
+        accounts
+            .withdraw_all_token_types(
+                &user_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                initial_pool,
+                0,
+                0,
+            )
+            .unwrap();
+
+        // resume restores swapping
+        accounts.set_paused(&pause_authority_key, false).unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert!(!swap_state.is_paused());
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &user_key, initial_a, 0, 0);
+        accounts
+            .swap(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &accounts.token_a_key.clone(),
+                &accounts.token_b_key.clone(),
+                &token_b_key,
+                &mut token_b_account,
+                initial_a,
+                0,
+            )
+            .unwrap();
+
+        // version 1 swaps cannot be paused
+        {
+            let swap_state = SwapVersion::unpack_latest(&accounts.swap_account.data).unwrap();
+            let swap_v1 = SwapVersion::SwapV1(swap_state.into());
+            SwapVersion::pack(swap_v1, &mut accounts.swap_account.data).unwrap();
             assert_eq!(
-                pool_mint.supply,
-                pool_account.amount + swap_pool_account.amount
+                Err(SwapError::UnsupportedSwapVersion.into()),
+                accounts.set_paused(&pause_authority_key, true)
             );
         }
     }
 
+    fn create_stake_pool(
+        pool_mint: &Pubkey,
+        total_lamports: u64,
+        pool_token_supply: u64,
+        last_update_epoch: u64,
+    ) -> Account {
+        let stake_pool = StakePool {
+            account_type: AccountType::StakePool,
+            pool_mint: *pool_mint,
+            total_lamports,
+            pool_token_supply,
+            last_update_epoch,
+            ..StakePool::default()
+        };
+        let mut account = Account::new(0, 0, &spl_stake_pool::id());
+        account.data = stake_pool.try_to_vec().unwrap();
+        account
+    }
+
     #[test]
-    fn test_withdraw_one_exact_out() {
+    fn test_stake_pool_stable_curve() {
         let user_key = Pubkey::new_unique();
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 2;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 10;
-        let owner_withdraw_fee_numerator = 1;
-        let owner_withdraw_fee_denominator = 5;
-        let host_fee_numerator = 7;
-        let host_fee_denominator = 100;
-
         let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1_000,
+            owner_trade_fee_numerator: 1,
+            owner_trade_fee_denominator: 1_000,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
         };
-
-        let token_a_amount = 100_000;
-        let token_b_amount = 200_000;
-        let curve_type = CurveType::ConstantProduct;
+        let token_a_amount = 1_000_000_000;
+        let token_b_amount = 1_050_000_000;
+        let total_lamports = 105_000_000_000;
+        let pool_token_supply = 100_000_000_000;
+        let amp = 100;
+        // the peg is refreshed from the stake pool on initialization
         let swap_curve = SwapCurve {
-            curve_type,
-            calculator: Arc::new(ConstantProductCurve {}),
+            curve_type: CurveType::StakePoolStable,
+            calculator: Arc::new(StakePoolStableCurve {
+                amp,
+                total_lamports: 0,
+                pool_token_supply: 0,
+            }),
         };
+        let stake_pool_key = Pubkey::new_unique();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            swap_curve,
+            token_a_amount,
+            token_b_amount,
+        );
 
-        let withdrawer_key = Pubkey::new_unique();
-        let initial_a = token_a_amount / 10;
-        let initial_b = token_b_amount / 10;
-        let initial_pool = swap_curve.calculator.new_pool_supply() / 10;
-        let maximum_pool_token_amount = to_u64(initial_pool / 4).unwrap();
-        let destination_a_amount = initial_a / 40;
-        let destination_b_amount = initial_b / 40;
+        // stake pool account missing
+        assert_eq!(
+            Err(ProgramError::NotEnoughAccountKeys),
+            accounts.initialize_swap()
+        );
 
-        let mut accounts =
-            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+        accounts.curve_account_key = Some(stake_pool_key);
 
-        // swap not initialized
+        // stake pool owned by the wrong program
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
-                )
+            let mut stake_pool_account = create_stake_pool(
+                &accounts.token_a_mint_key,
+                total_lamports,
+                pool_token_supply,
+                TEST_EPOCH,
             );
-        }
-
-        accounts.initialize_swap().unwrap();
-
-        // wrong owner for swap account
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
-            let old_swap_account = accounts.swap_account;
-            let mut wrong_swap_account = old_swap_account.clone();
-            wrong_swap_account.owner = spl_token::id();
-            accounts.swap_account = wrong_swap_account;
+            stake_pool_account.owner = Pubkey::new_unique();
+            accounts.curve_account = stake_pool_account;
             assert_eq!(
-                Err(ProgramError::IncorrectProgramId),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
-                )
+                Err(SwapError::IncorrectStakePool.into()),
+                accounts.initialize_swap()
             );
-            accounts.swap_account = old_swap_account;
         }
 
-        // wrong bump seed for authority_key
+        // stake pool for another mint
         {
-            let (
-                _token_a_key,
-                _token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _bump_seed) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &spl_token::id(),
+            accounts.curve_account = create_stake_pool(
+                &accounts.token_b_mint_key,
+                total_lamports,
+                pool_token_supply,
+                TEST_EPOCH,
             );
-            accounts.authority_key = bad_authority_key;
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    destination_b_amount,
-                    maximum_pool_token_amount,
-                )
+                Err(SwapError::IncorrectStakePool.into()),
+                accounts.initialize_swap()
             );
-            accounts.authority_key = old_authority;
         }
 
-        // not enough pool tokens
+        // stake pool not updated this epoch
         {
-            let (
-                _token_a_key,
-                _token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                maximum_pool_token_amount / 1000,
+            accounts.curve_account = create_stake_pool(
+                &accounts.token_a_mint_key,
+                total_lamports,
+                pool_token_supply,
+                TEST_EPOCH - 1,
             );
             assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    destination_b_amount,
-                    maximum_pool_token_amount,
-                )
+                Err(SwapError::StaleStakePool.into()),
+                accounts.initialize_swap()
             );
         }
 
-        // wrong pool token account
+        // empty stake pool
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                maximum_pool_token_amount,
-                initial_b,
-                maximum_pool_token_amount,
-            );
+            accounts.curve_account =
+                create_stake_pool(&accounts.token_a_mint_key, 0, 0, TEST_EPOCH);
             assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    destination_b_amount,
-                    maximum_pool_token_amount,
-                )
+                Err(SwapError::InvalidCurve.into()),
+                accounts.initialize_swap()
             );
         }
 
-        // wrong pool fee account
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                wrong_pool_key,
-                wrong_pool_account,
-            ) = accounts.setup_token_accounts(
+        accounts.curve_account = create_stake_pool(
+            &accounts.token_a_mint_key,
+            total_lamports,
+            pool_token_supply,
+            TEST_EPOCH,
+        );
+        accounts.initialize_swap().unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        let expected_curve = SwapCurve {
+            curve_type: CurveType::StakePoolStable,
+            calculator: Arc::new(StakePoolStableCurve {
+                amp,
+                total_lamports,
+                pool_token_supply,
+            }),
+        };
+        assert_eq!(*swap_state.swap_curve(), expected_curve);
+
+        // the exchange rate moves up, swaps use the new rate
+        let total_lamports = 110_000_000_000;
+        accounts.curve_account = create_stake_pool(
+            &accounts.token_a_mint_key,
+            total_lamports,
+            pool_token_supply,
+            TEST_EPOCH,
+        );
+        let refreshed_curve = SwapCurve {
+            curve_type: CurveType::StakePoolStable,
+            calculator: Arc::new(StakePoolStableCurve {
+                amp,
+                total_lamports,
+                pool_token_supply,
+            }),
+        };
+
+        let initial_a = token_a_amount / 10;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &user_key, initial_a, 0, 0);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let a_to_b_amount = initial_a / 10;
+        let expected_result = refreshed_curve
+            .swap(
+                a_to_b_amount as u128,
+                token_a_amount as u128,
+                token_b_amount as u128,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        let stale_result = expected_curve
+            .swap(
+                a_to_b_amount as u128,
+                token_a_amount as u128,
+                token_b_amount as u128,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        assert!(
+            expected_result.destination_amount_swapped > stale_result.destination_amount_swapped
+        );
+        accounts
+            .swap(
                 &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                maximum_pool_token_amount,
-            );
-            let (
-                _token_a_key,
-                _token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                0,
+            )
+            .unwrap();
+        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        assert_eq!(
+            token_b.amount as u128,
+            expected_result.destination_amount_swapped
+        );
+
+        // single token deposits and withdrawals use the stake pool as well
+        accounts
+            .deposit_single_token_type_exact_amount_in(
                 &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                maximum_pool_token_amount,
-            );
-            let old_pool_fee_account = accounts.pool_fee_account;
-            let old_pool_fee_key = accounts.pool_fee_key;
-            accounts.pool_fee_account = wrong_pool_account;
-            accounts.pool_fee_key = wrong_pool_key;
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                a_to_b_amount,
+                0,
+            )
+            .unwrap();
+        let pool = spl_token::state::Account::unpack(&pool_account.data).unwrap();
+        assert!(pool.amount > 0);
+        accounts
+            .withdraw_single_token_type_exact_amount_out(
+                &user_key,
+                &pool_key,
+                &mut pool_account,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                pool.amount,
+            )
+            .unwrap();
+
+        // the stake pool cannot be omitted
+        accounts.curve_account_key = None;
+        assert_eq!(
+            Err(ProgramError::NotEnoughAccountKeys),
+            accounts.swap(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                0,
+            )
+        );
+
+        // nor replaced by a stale one
+        accounts.curve_account_key = Some(stake_pool_key);
+        accounts.curve_account = create_stake_pool(
+            &accounts.token_a_mint_key,
+            total_lamports,
+            pool_token_supply,
+            TEST_EPOCH - 1,
+        );
+        assert_eq!(
+            Err(SwapError::StaleStakePool.into()),
+            accounts.swap(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                0,
+            )
+        );
+    }
+
+    fn create_oracle(price: i64, exponent: i32, valid_slot: u64) -> Account {
+        let mut account = Account::new(0, size_of::<pyth::Price>(), &pyth::PROGRAM_IDS[0]);
+        let oracle_price = bytemuck::from_bytes_mut::<pyth::Price>(&mut account.data);
+        oracle_price.magic = pyth::MAGIC;
+        oracle_price.ver = pyth::VERSION_2;
+        oracle_price.atype = pyth::AccountType::Price as u32;
+        oracle_price.ptype = pyth::PriceType::Price;
+        oracle_price.expo = exponent;
+        oracle_price.valid_slot = valid_slot;
+        oracle_price.agg.price = price;
+        oracle_price.agg.status = pyth::PriceStatus::Trading;
+        account
+    }
+
+    #[test]
+    fn test_proactive_market_making_curve() {
+        let user_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1_000,
+            owner_trade_fee_numerator: 1,
+            owner_trade_fee_denominator: 1_000,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+        let token_a_amount = 1_000_000_000;
+        let token_b_amount = 2_000_000_000;
+        let k_numerator = 1;
+        let k_denominator = 10;
+        let oracle_exponent = -8;
+        // the price is refreshed from the oracle on initialization
+        let pmm_curve = |oracle_price| SwapCurve {
+            curve_type: CurveType::ProactiveMarketMaking,
+            calculator: Arc::new(ProactiveMarketMakingCurve {
+                k_numerator,
+                k_denominator,
+                oracle_price,
+                oracle_exponent,
+                token_a_decimals: 6,
+                token_b_decimals: 6,
+            }),
+        };
+        let oracle_key = Pubkey::new_unique();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            pmm_curve(1),
+            token_a_amount,
+            token_b_amount,
+        );
+
+        // oracle account missing
+        assert_eq!(
+            Err(ProgramError::NotEnoughAccountKeys),
+            accounts.initialize_swap()
+        );
+
+        accounts.curve_account_key = Some(oracle_key);
+
+        // oracle account not owned by a pyth program
+        {
+            let mut oracle_account = create_oracle(200_000_000, oracle_exponent, TEST_SLOT);
+            oracle_account.owner = Pubkey::new_unique();
+            accounts.curve_account = oracle_account;
             assert_eq!(
-                Err(SwapError::IncorrectFeeAccount.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
-                )
+                Err(SwapError::InvalidOracle.into()),
+                accounts.initialize_swap()
             );
-            accounts.pool_fee_account = old_pool_fee_account;
-            accounts.pool_fee_key = old_pool_fee_key;
         }
 
-        // no approval
+        // oracle account too small
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                0,
-                0,
-                maximum_pool_token_amount,
+            accounts.curve_account = Account::new(0, 0, &pyth::PROGRAM_IDS[0]);
+            assert_eq!(
+                Err(SwapError::InvalidOracle.into()),
+                accounts.initialize_swap()
             );
-            let user_transfer_authority_key = Pubkey::new_unique();
+        }
+
+        // oracle not trading
+        {
+            let mut oracle_account = create_oracle(200_000_000, oracle_exponent, TEST_SLOT);
+            bytemuck::from_bytes_mut::<pyth::Price>(&mut oracle_account.data)
+                .agg
+                .status = pyth::PriceStatus::Halted;
+            accounts.curve_account = oracle_account;
             assert_eq!(
-                Err(TokenError::OwnerMismatch.into()),
-                do_process_instruction(
-                    withdraw_single_token_type_exact_amount_out(
-                        &SWAP_PROGRAM_ID,
-                        &spl_token::id(),
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &user_transfer_authority_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        &pool_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_a_key,
-                        WithdrawSingleTokenTypeExactAmountOut {
-                            destination_token_amount: destination_a_amount,
-                            maximum_pool_token_amount,
-                        }
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut Account::default(),
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_a_account,
-                        &mut accounts.pool_fee_account,
-                        &mut Account::default(),
-                    ],
-                )
+                Err(SwapError::InvalidOracle.into()),
+                accounts.initialize_swap()
             );
         }
 
-        // wrong token program id
+        // negative price
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                maximum_pool_token_amount,
+            accounts.curve_account = create_oracle(-200_000_000, oracle_exponent, TEST_SLOT);
+            assert_eq!(
+                Err(SwapError::InvalidOracle.into()),
+                accounts.initialize_swap()
             );
-            let wrong_key = Pubkey::new_unique();
+        }
+
+        // oracle not updated recently
+        {
+            accounts.curve_account = create_oracle(200_000_000, oracle_exponent, TEST_SLOT - 5);
             assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
-                do_process_instruction(
-                    withdraw_single_token_type_exact_amount_out(
-                        &SWAP_PROGRAM_ID,
-                        &wrong_key,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.authority_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        &pool_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_a_key,
-                        WithdrawSingleTokenTypeExactAmountOut {
-                            destination_token_amount: destination_a_amount,
-                            maximum_pool_token_amount,
-                        }
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut Account::default(),
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_a_account,
-                        &mut accounts.pool_fee_account,
-                        &mut Account::default(),
-                    ],
-                )
+                Err(SwapError::StaleOracle.into()),
+                accounts.initialize_swap()
             );
         }
 
-        // wrong swap token accounts
+        // zero price
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
+            accounts.curve_account = create_oracle(0, oracle_exponent, TEST_SLOT);
+            assert_eq!(
+                Err(SwapError::InvalidCurve.into()),
+                accounts.initialize_swap()
             );
+        }
 
-            let old_a_key = accounts.token_a_key;
-            let old_a_account = accounts.token_a_account;
+        accounts.curve_account = create_oracle(200_000_000, oracle_exponent, TEST_SLOT - 4);
+        accounts.initialize_swap().unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        let expected_curve = pmm_curve(200_000_000);
+        assert_eq!(*swap_state.swap_curve(), expected_curve);
+        assert_eq!(swap_state.oracle(), Some(&oracle_key));
 
-            accounts.token_a_key = token_a_key;
-            accounts.token_a_account = token_a_account.clone();
+        // the price of token A moves up, swaps use the new price
+        accounts.curve_account = create_oracle(250_000_000, oracle_exponent, TEST_SLOT);
+        let refreshed_curve = pmm_curve(250_000_000);
 
-            // wrong swap token a account
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
-                )
-            );
+        let initial_a = token_a_amount / 10;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &user_key, initial_a, 0, 0);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let a_to_b_amount = initial_a / 10;
+        let expected_result = refreshed_curve
+            .swap(
+                a_to_b_amount as u128,
+                token_a_amount as u128,
+                token_b_amount as u128,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        let stale_result = expected_curve
+            .swap(
+                a_to_b_amount as u128,
+                token_a_amount as u128,
+                token_b_amount as u128,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        assert!(
+            expected_result.destination_amount_swapped > stale_result.destination_amount_swapped
+        );
+        accounts
+            .swap(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                0,
+            )
+            .unwrap();
+        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+        assert_eq!(
+            token_a.amount as u128,
+            initial_a as u128 - expected_result.source_amount_swapped
+        );
+        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        assert_eq!(
+            token_b.amount as u128,
+            expected_result.destination_amount_swapped
+        );
 
-            accounts.token_a_key = old_a_key;
-            accounts.token_a_account = old_a_account;
+        // single sided deposits and withdrawals use the oracle as well
+        accounts
+            .deposit_single_token_type_exact_amount_in(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                a_to_b_amount,
+                0,
+            )
+            .unwrap();
+        let pool = spl_token::state::Account::unpack(&pool_account.data).unwrap();
+        assert!(pool.amount > 0);
+        accounts
+            .withdraw_single_token_type_exact_amount_out(
+                &user_key,
+                &pool_key,
+                &mut pool_account,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                pool.amount,
+            )
+            .unwrap();
 
-            let old_b_key = accounts.token_b_key;
-            let old_b_account = accounts.token_b_account;
+        // the oracle cannot be omitted
+        accounts.curve_account_key = None;
+        assert_eq!(
+            Err(ProgramError::NotEnoughAccountKeys),
+            accounts.swap(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                0,
+            )
+        );
 
-            accounts.token_b_key = token_b_key;
-            accounts.token_b_account = token_b_account.clone();
+        // nor replaced by another price account
+        accounts.curve_account_key = Some(Pubkey::new_unique());
+        assert_eq!(
+            Err(SwapError::IncorrectOracle.into()),
+            accounts.swap(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                0,
+            )
+        );
 
-            // wrong swap token b account
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    destination_b_amount,
-                    maximum_pool_token_amount,
+        // nor be stale
+        accounts.curve_account_key = Some(oracle_key);
+        accounts.curve_account = create_oracle(250_000_000, oracle_exponent, TEST_SLOT - 5);
+        assert_eq!(
+            Err(SwapError::StaleOracle.into()),
+            accounts.swap(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                0,
+            )
+        );
+    }
+
+    #[test]
+    fn test_proactive_market_making_oracle_owner() {
+        let user_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1_000,
+            owner_trade_fee_numerator: 1,
+            owner_trade_fee_denominator: 1_000,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+        let oracle_exponent = -8;
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ProactiveMarketMaking,
+            calculator: Arc::new(ProactiveMarketMakingCurve {
+                k_numerator: 1,
+                k_denominator: 10,
+                oracle_price: 1,
+                oracle_exponent,
+                token_a_decimals: 6,
+                token_b_decimals: 6,
+            }),
+        };
+        let oracle_program_id = pyth::PROGRAM_IDS[1];
+        let owner_key = &user_key.to_string();
+        let oracle_program_id_str = &oracle_program_id.to_string();
+        let valid_curve_types = &[CurveType::ProactiveMarketMaking];
+        let max_dynamic_fees = &DynamicFees::default();
+        let constraints = Some(SwapConstraints {
+            owner_key,
+            valid_curve_types,
+            fees: &fees,
+            pause_authority: None,
+            max_dynamic_fees,
+            oracle_program_id: Some(oracle_program_id_str),
+        });
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            swap_curve,
+            1_000_000_000,
+            2_000_000_000,
+        );
+        let (pool_fee_key, pool_fee_account) = mint_token(
+            &spl_token::id(),
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &accounts.authority_key,
+            &user_key,
+            0,
+        );
+        accounts.pool_fee_key = pool_fee_key;
+        accounts.pool_fee_account = pool_fee_account;
+        let oracle_key = Pubkey::new_unique();
+
+        let mut initialize_with = |oracle_account: &mut Account| {
+            do_process_instruction_with_fee_constraints(
+                initialize(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &accounts.swap_key,
+                    &accounts.authority_key,
+                    &accounts.token_a_key,
+                    &accounts.token_b_key,
+                    &accounts.pool_mint_key,
+                    &accounts.pool_fee_key,
+                    &accounts.pool_token_key,
+                    &accounts.token_a_mint_key,
+                    &accounts.token_b_mint_key,
+                    Some(&oracle_key),
+                    None,
+                    accounts.fees.clone(),
+                    accounts.swap_curve.clone(),
                 )
-            );
+                .unwrap(),
+                vec![
+                    &mut accounts.swap_account,
+                    &mut Account::default(),
+                    &mut accounts.token_a_account,
+                    &mut accounts.token_b_account,
+                    &mut accounts.pool_mint_account,
+                    &mut accounts.pool_fee_account,
+                    &mut accounts.pool_token_account,
+                    &mut Account::default(),
+                    &mut accounts.token_a_mint_account,
+                    &mut accounts.token_b_mint_account,
+                    oracle_account,
+                ],
+                &constraints,
+            )
+        };
 
-            accounts.token_b_key = old_b_key;
-            accounts.token_b_account = old_b_account;
-        }
+        // oracle holding a valid price, owned by another pyth program
+        let mut spoofed_oracle = create_oracle(200_000_000, oracle_exponent, TEST_SLOT);
+        assert_eq!(
+            Err(SwapError::InvalidOracle.into()),
+            initialize_with(&mut spoofed_oracle)
+        );
 
-        // wrong mint
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
-            );
-            let (pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &accounts.authority_key, None);
-            let old_pool_key = accounts.pool_mint_key;
-            let old_pool_account = accounts.pool_mint_account;
-            accounts.pool_mint_key = pool_mint_key;
-            accounts.pool_mint_account = pool_mint_account;
+        let mut oracle = create_oracle(200_000_000, oracle_exponent, TEST_SLOT);
+        oracle.owner = oracle_program_id;
+        initialize_with(&mut oracle).unwrap();
+    }
 
-            assert_eq!(
-                Err(SwapError::IncorrectPoolMint.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
-                )
-            );
+    #[test]
+    fn test_dynamic_fees() {
+        let user_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+        let dynamic_fees = DynamicFees {
+            decay_half_life_slots: 150,
+            volatility_fee_numerator: 1,
+            volatility_fee_denominator: 10,
+            max_volatility_fee_numerator: 100,
+        };
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            swap_curve.clone(),
+            token_a_amount,
+            token_b_amount,
+        );
+        accounts.initialize_swap().unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert!(!swap_state.dynamic_fees().is_enabled());
 
-            accounts.pool_mint_key = old_pool_key;
-            accounts.pool_mint_account = old_pool_account;
-        }
+        // not the owner of the pool fee account
+        assert_eq!(
+            Err(SwapError::InvalidFeeAccountOwner.into()),
+            accounts.set_dynamic_fees(&Pubkey::new_unique(), dynamic_fees.clone(), &None)
+        );
 
-        // slippage exceeded
+        // owner did not sign
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
+            let mut instruction = set_dynamic_fees(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &accounts.pool_fee_key,
                 &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                maximum_pool_token_amount,
-            );
-
-            // maximum pool token amount too low
-            assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount / 1000,
-                )
-            );
+                dynamic_fees.clone(),
+            )
+            .unwrap();
+            instruction.accounts[2].is_signer = false;
             assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    destination_b_amount,
-                    maximum_pool_token_amount / 1000,
+                Err(SwapError::InvalidFeeAccountOwner.into()),
+                do_process_instruction(
+                    instruction,
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut accounts.pool_fee_account,
+                        &mut Account::default(),
+                    ],
                 )
             );
         }
 
-        // invalid input: can't use swap pool tokens as destination
+        // wrong pool fee account
         {
-            let (
-                _token_a_key,
-                _token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
+            let (pool_fee_key, mut pool_fee_account) = mint_token(
+                &spl_token::id(),
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
                 &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                maximum_pool_token_amount,
-            );
-            let swap_token_a_key = accounts.token_a_key;
-            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &swap_token_a_key,
-                    &mut swap_token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
-                )
+                0,
             );
-            let swap_token_b_key = accounts.token_b_key;
-            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
             assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &swap_token_b_key,
-                    &mut swap_token_b_account,
-                    destination_b_amount,
-                    maximum_pool_token_amount,
+                Err(SwapError::IncorrectFeeAccount.into()),
+                do_process_instruction(
+                    set_dynamic_fees(
+                        &SWAP_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &pool_fee_key,
+                        &user_key,
+                        dynamic_fees.clone(),
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut pool_fee_account,
+                        &mut Account::default(),
+                    ],
                 )
             );
         }
 
-        // correct withdrawal
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
-            );
-
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-            let swap_token_b =
-                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-            let pool_mint =
-                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-
-            let pool_token_amount = accounts
-                .swap_curve
-                .withdraw_single_token_type_exact_out(
-                    destination_a_amount.try_into().unwrap(),
-                    swap_token_a.amount.try_into().unwrap(),
-                    swap_token_b.amount.try_into().unwrap(),
-                    pool_mint.supply.try_into().unwrap(),
-                    TradeDirection::AtoB,
-                    &accounts.fees,
-                )
-                .unwrap();
-            let withdraw_fee = accounts.fees.owner_withdraw_fee(pool_token_amount).unwrap();
-
-            accounts
-                .withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
-                )
-                .unwrap();
-
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-
-            assert_eq!(swap_token_a.amount, token_a_amount - destination_a_amount);
-            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-            assert_eq!(token_a.amount, initial_a + destination_a_amount);
+        // maximum trade fee over 100%
+        assert_eq!(
+            Err(SwapError::InvalidFee.into()),
+            accounts.set_dynamic_fees(
+                &user_key,
+                DynamicFees {
+                    max_volatility_fee_numerator: 9_975,
+                    ..dynamic_fees
+                },
+                &None
+            )
+        );
 
-            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
+        // constrained by the program owner
+        {
+            let owner_key = &user_key.to_string();
+            let valid_curve_types = &[CurveType::ConstantProduct];
+            let max_dynamic_fees = &DynamicFees {
+                max_volatility_fee_numerator: 50,
+                ..dynamic_fees
+            };
+            let constraints = Some(SwapConstraints {
+                owner_key,
+                valid_curve_types,
+                fees: &fees,
+                pause_authority: None,
+                max_dynamic_fees,
+                oracle_program_id: None,
+            });
             assert_eq!(
-                pool_account.amount,
-                to_u64(initial_pool - pool_token_amount - withdraw_fee).unwrap()
+                Err(SwapError::InvalidFee.into()),
+                accounts.set_dynamic_fees(&user_key, dynamic_fees.clone(), &constraints)
+            );
+            let other_owner_key = &Pubkey::new_unique().to_string();
+            let constraints = Some(SwapConstraints {
+                owner_key: other_owner_key,
+                valid_curve_types,
+                fees: &fees,
+                pause_authority: None,
+                max_dynamic_fees,
+                oracle_program_id: None,
+            });
+            assert_eq!(
+                Err(SwapError::InvalidFeeAccountOwner.into()),
+                accounts.set_dynamic_fees(&user_key, max_dynamic_fees.clone(), &constraints)
             );
-            let fee_account =
-                spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
-            assert_eq!(fee_account.amount, to_u64(withdraw_fee).unwrap());
         }
 
-        // correct withdrawal from fee account
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+        accounts
+            .set_dynamic_fees(&user_key, dynamic_fees.clone(), &None)
+            .unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(*swap_state.dynamic_fees(), dynamic_fees);
+        assert_eq!(swap_state.volatility(), 0);
 
-            let fee_a_amount = 2;
-            let pool_fee_key = accounts.pool_fee_key;
-            let mut pool_fee_account = accounts.pool_fee_account.clone();
-            let fee_account = spl_token::state::Account::unpack(&pool_fee_account.data).unwrap();
-            let pool_fee_amount = fee_account.amount;
+        let initial_a = token_a_amount / 2;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &user_key, initial_a, 0, 0);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let a_to_b_amount = initial_a / 5;
 
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+        // no volatility yet, the static fees are charged and the price impact
+        // of the swap is accumulated
+        let expected_result = swap_curve
+            .swap(
+                a_to_b_amount as u128,
+                token_a_amount as u128,
+                token_b_amount as u128,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        accounts
+            .swap(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                0,
+            )
+            .unwrap();
+        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        assert_eq!(
+            token_b.amount as u128,
+            expected_result.destination_amount_swapped
+        );
+        let expected_volatility = price_impact(
+            token_a_amount as u128,
+            token_b_amount as u128,
+            expected_result.new_swap_source_amount,
+            expected_result.new_swap_destination_amount,
+        )
+        .unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert!(expected_volatility > 0);
+        assert_eq!(swap_state.volatility(), expected_volatility);
+        assert_eq!(swap_state.volatility_update_slot(), TEST_SLOT);
+
+        // the next swap pays a higher trade fee
+        let swap_token_a = spl_token::state::Account::unpack(&accounts.token_a_account.data)
+            .unwrap()
+            .amount;
+        let swap_token_b = spl_token::state::Account::unpack(&accounts.token_b_account.data)
+            .unwrap()
+            .amount;
+        let raised_fees = dynamic_fees.trade_fees(&fees, expected_volatility).unwrap();
+        assert!(raised_fees.trade_fee_numerator > fees.trade_fee_numerator);
+        let expected_result = swap_curve
+            .swap(
+                a_to_b_amount as u128,
+                swap_token_a as u128,
+                swap_token_b as u128,
+                TradeDirection::AtoB,
+                &raised_fees,
+            )
+            .unwrap();
+        let static_result = swap_curve
+            .swap(
+                a_to_b_amount as u128,
+                swap_token_a as u128,
+                swap_token_b as u128,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        assert!(
+            expected_result.destination_amount_swapped < static_result.destination_amount_swapped
+        );
+        accounts
+            .swap(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                0,
+            )
+            .unwrap();
+        let token_b_amount = token_b.amount;
+        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        assert_eq!(
+            (token_b.amount - token_b_amount) as u128,
+            expected_result.destination_amount_swapped
+        );
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert!(swap_state.volatility() > expected_volatility);
 
-            let token_a_amount = swap_token_a.amount;
-            accounts
-                .withdraw_single_token_type_exact_amount_out(
-                    &user_key,
-                    &pool_fee_key,
-                    &mut pool_fee_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    fee_a_amount,
-                    pool_fee_amount,
-                )
-                .unwrap();
+        // disabling dynamic fees goes back to the static fees
+        accounts
+            .set_dynamic_fees(&user_key, DynamicFees::default(), &None)
+            .unwrap();
+        let swap_token_a = spl_token::state::Account::unpack(&accounts.token_a_account.data)
+            .unwrap()
+            .amount;
+        let swap_token_b = spl_token::state::Account::unpack(&accounts.token_b_account.data)
+            .unwrap()
+            .amount;
+        let expected_result = swap_curve
+            .swap(
+                a_to_b_amount as u128,
+                swap_token_a as u128,
+                swap_token_b as u128,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        accounts
+            .swap(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                0,
+            )
+            .unwrap();
+        let token_b_amount = token_b.amount;
+        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        assert_eq!(
+            (token_b.amount - token_b_amount) as u128,
+            expected_result.destination_amount_swapped
+        );
+    }
 
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+    #[test]
+    fn test_dynamic_fees_single_token_type() {
+        let user_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+        let dynamic_fees = DynamicFees {
+            decay_half_life_slots: 150,
+            volatility_fee_numerator: 1,
+            volatility_fee_denominator: 10,
+            max_volatility_fee_numerator: 100,
+        };
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
+        };
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            swap_curve.clone(),
+            token_a_amount,
+            token_b_amount,
+        );
+        accounts.initialize_swap().unwrap();
+        accounts
+            .set_dynamic_fees(&user_key, dynamic_fees.clone(), &None)
+            .unwrap();
 
-            assert_eq!(swap_token_a.amount, token_a_amount - fee_a_amount);
-            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-            assert_eq!(token_a.amount, initial_a + fee_a_amount);
-        }
+        let deposit_a = token_a_amount / 10;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &user_key, deposit_a, 0, 0);
+
+        // a single token deposit trades part of it, so its price impact is
+        // accumulated
+        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+        let expected_pool_tokens = swap_curve
+            .deposit_single_token_type(
+                deposit_a as u128,
+                token_a_amount as u128,
+                token_b_amount as u128,
+                pool_mint.supply as u128,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        accounts
+            .deposit_single_token_type_exact_amount_in(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                deposit_a,
+                0,
+            )
+            .unwrap();
+        let pool_tokens = spl_token::state::Account::unpack(&pool_account.data)
+            .unwrap()
+            .amount;
+        assert_eq!(pool_tokens as u128, expected_pool_tokens);
+        let expected_volatility = price_impact(
+            token_a_amount as u128,
+            token_b_amount as u128,
+            (token_a_amount + deposit_a) as u128,
+            token_b_amount as u128,
+        )
+        .unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert!(expected_volatility > 0);
+        assert_eq!(swap_state.volatility(), expected_volatility);
+        assert_eq!(swap_state.volatility_update_slot(), TEST_SLOT);
+
+        // a single token withdrawal pays the raised trade fee, and adds its
+        // own price impact
+        let withdraw_b = token_b_amount / 20;
+        let raised_fees = dynamic_fees.trade_fees(&fees, expected_volatility).unwrap();
+        assert!(raised_fees.trade_fee_numerator > fees.trade_fee_numerator);
+        let swap_token_a = token_a_amount + deposit_a;
+        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+        let expected_burn = swap_curve
+            .withdraw_single_token_type_exact_out(
+                withdraw_b as u128,
+                swap_token_a as u128,
+                token_b_amount as u128,
+                pool_mint.supply as u128,
+                TradeDirection::BtoA,
+                &raised_fees,
+            )
+            .unwrap();
+        accounts
+            .withdraw_single_token_type_exact_amount_out(
+                &user_key,
+                &pool_key,
+                &mut pool_account,
+                &token_b_key,
+                &mut token_b_account,
+                withdraw_b,
+                pool_tokens,
+            )
+            .unwrap();
+        let remaining_pool_tokens = spl_token::state::Account::unpack(&pool_account.data)
+            .unwrap()
+            .amount;
+        assert_eq!((pool_tokens - remaining_pool_tokens) as u128, expected_burn);
+        let withdraw_impact = price_impact(
+            swap_token_a as u128,
+            token_b_amount as u128,
+            swap_token_a as u128,
+            (token_b_amount - withdraw_b) as u128,
+        )
+        .unwrap();
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(
+            swap_state.volatility(),
+            expected_volatility + withdraw_impact
+        );
     }
 
-    fn check_valid_swap_curve(
-        fees: Fees,
-        curve_type: CurveType,
-        calculator: Arc<dyn CurveCalculator + Send + Sync>,
-        token_a_amount: u64,
-        token_b_amount: u64,
-    ) {
-        let user_key = Pubkey::new_unique();
-        let swapper_key = Pubkey::new_unique();
+    #[test]
+    fn test_price_accumulator() {
+        // pretend that the last update happened some seconds ago, returning
+        // the accumulator expected once the swap token amounts are accumulated
+        fn rewind(accounts: &mut SwapAccountInfo, seconds: i64) -> PriceAccumulator {
+            let mut token_swap = SwapVersion::unpack_latest(&accounts.swap_account.data).unwrap();
+            token_swap.price_accumulator.last_update_timestamp = TEST_UNIX_TIMESTAMP - seconds;
+            let price_accumulator = token_swap.price_accumulator;
+            SwapVersion::pack_latest(token_swap, &mut accounts.swap_account.data).unwrap();
+            price_accumulator.observe(
+                token_account_amount(&accounts.token_a_account),
+                token_account_amount(&accounts.token_b_account),
+                TEST_UNIX_TIMESTAMP,
+            )
+        }
+        fn price_accumulator(accounts: &SwapAccountInfo) -> PriceAccumulator {
+            *SwapVersion::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .price_accumulator()
+                .unwrap()
+        }
 
+        let user_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 4_000_000;
         let swap_curve = SwapCurve {
-            curve_type,
-            calculator,
+            curve_type: CurveType::ConstantProduct,
+            calculator: Arc::new(ConstantProductCurve {}),
         };
-
         let mut accounts = SwapAccountInfo::new(
             &user_key,
             fees.clone(),
@@ -5393,1538 +11056,2376 @@ mod tests {
             token_a_amount,
             token_b_amount,
         );
-        let initial_a = token_a_amount / 5;
-        let initial_b = token_b_amount / 5;
         accounts.initialize_swap().unwrap();
-
-        let swap_token_a_key = accounts.token_a_key;
-        let swap_token_b_key = accounts.token_b_key;
+        assert_eq!(accounts.swap_account.data[0], 3);
+        assert_eq!(
+            price_accumulator(&accounts),
+            PriceAccumulator::new(TEST_UNIX_TIMESTAMP)
+        );
 
         let (
             token_a_key,
             mut token_a_account,
             token_b_key,
             mut token_b_account,
-            _pool_key,
-            _pool_account,
-        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-        // swap one way
-        let a_to_b_amount = initial_a / 10;
-        let minimum_token_b_amount = 0;
-        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-        let initial_supply = pool_mint.supply;
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &user_key, token_a_amount, token_b_amount, 0);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+
+        // swap, accumulating the initial price for 100 seconds
+        let expected = rewind(&mut accounts, 100);
+        assert_eq!(
+            expected.price_a_cumulative,
+            (4 * 100) << PRICE_FRACTIONAL_BITS
+        );
+        assert_eq!(
+            expected.price_b_cumulative,
+            (100 << PRICE_FRACTIONAL_BITS) / 4
+        );
         accounts
             .swap(
-                &swapper_key,
+                &user_key,
                 &token_a_key,
                 &mut token_a_account,
                 &swap_token_a_key,
                 &swap_token_b_key,
                 &token_b_key,
                 &mut token_b_account,
-                a_to_b_amount,
-                minimum_token_b_amount,
-            )
-            .unwrap();
-
-        let results = swap_curve
-            .swap(
-                a_to_b_amount.try_into().unwrap(),
-                token_a_amount.try_into().unwrap(),
-                token_b_amount.try_into().unwrap(),
-                TradeDirection::AtoB,
-                &fees,
-            )
-            .unwrap();
-
-        let swap_token_a =
-            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-        let token_a_amount = swap_token_a.amount;
-        assert_eq!(
-            token_a_amount,
-            TryInto::<u64>::try_into(results.new_swap_source_amount).unwrap()
-        );
-        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-        assert_eq!(token_a.amount, initial_a - a_to_b_amount);
-
-        let swap_token_b =
-            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-        let token_b_amount = swap_token_b.amount;
-        assert_eq!(
-            token_b_amount,
-            TryInto::<u64>::try_into(results.new_swap_destination_amount).unwrap()
-        );
-        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-        assert_eq!(
-            token_b.amount,
-            initial_b + to_u64(results.destination_amount_swapped).unwrap()
-        );
-
-        let first_fee = swap_curve
-            .withdraw_single_token_type_exact_out(
-                results.owner_fee,
-                token_a_amount.try_into().unwrap(),
-                token_b_amount.try_into().unwrap(),
-                initial_supply.try_into().unwrap(),
-                TradeDirection::AtoB,
-                &fees,
+                token_a_amount / 10,
+                0,
             )
             .unwrap();
-        let fee_account =
-            spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
-        assert_eq!(
-            fee_account.amount,
-            TryInto::<u64>::try_into(first_fee).unwrap()
-        );
-
-        let first_swap_amount = results.destination_amount_swapped;
+        assert_eq!(price_accumulator(&accounts), expected);
 
-        // swap the other way
-        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-        let initial_supply = pool_mint.supply;
-
-        let b_to_a_amount = initial_b / 10;
-        let minimum_a_amount = 0;
+        // nothing more accumulated in the same second
         accounts
             .swap(
-                &swapper_key,
+                &user_key,
                 &token_b_key,
                 &mut token_b_account,
                 &swap_token_b_key,
                 &swap_token_a_key,
                 &token_a_key,
                 &mut token_a_account,
-                b_to_a_amount,
-                minimum_a_amount,
+                token_b_amount / 10,
+                0,
             )
             .unwrap();
+        assert_eq!(price_accumulator(&accounts), expected);
 
-        let results = swap_curve
-            .swap(
-                b_to_a_amount.try_into().unwrap(),
-                token_b_amount.try_into().unwrap(),
-                token_a_amount.try_into().unwrap(),
-                TradeDirection::BtoA,
-                &fees,
+        // deposit all
+        let expected = rewind(&mut accounts, 50);
+        accounts
+            .deposit_all_token_types(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                10_000_000,
+                u64::MAX,
+                u64::MAX,
             )
             .unwrap();
+        assert_eq!(price_accumulator(&accounts), expected);
 
-        let swap_token_a =
-            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-        let token_a_amount = swap_token_a.amount;
-        assert_eq!(
-            token_a_amount,
-            TryInto::<u64>::try_into(results.new_swap_destination_amount).unwrap()
-        );
-        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-        assert_eq!(
-            token_a.amount,
-            initial_a - a_to_b_amount + to_u64(results.destination_amount_swapped).unwrap()
-        );
-
-        let swap_token_b =
-            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-        let token_b_amount = swap_token_b.amount;
-        assert_eq!(
-            token_b_amount,
-            TryInto::<u64>::try_into(results.new_swap_source_amount).unwrap()
-        );
-        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-        assert_eq!(
-            token_b.amount,
-            initial_b + to_u64(first_swap_amount).unwrap()
-                - to_u64(results.source_amount_swapped).unwrap()
-        );
-
-        let second_fee = swap_curve
-            .withdraw_single_token_type_exact_out(
-                results.owner_fee,
-                token_a_amount.try_into().unwrap(),
-                token_b_amount.try_into().unwrap(),
-                initial_supply.try_into().unwrap(),
-                TradeDirection::BtoA,
-                &fees,
+        // withdraw all
+        let expected = rewind(&mut accounts, 25);
+        accounts
+            .withdraw_all_token_types(
+                &user_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                5_000_000,
+                0,
+                0,
             )
             .unwrap();
-        let fee_account =
-            spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
-        assert_eq!(fee_account.amount, to_u64(first_fee + second_fee).unwrap());
-    }
-
-    #[test]
-    fn test_valid_swap_curves_all_fees() {
-        // All fees
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 10;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 30;
-        let owner_withdraw_fee_numerator = 1;
-        let owner_withdraw_fee_denominator = 30;
-        let host_fee_numerator = 20;
-        let host_fee_denominator = 100;
-        let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
-        };
-
-        let token_a_amount = 10_000_000_000;
-        let token_b_amount = 50_000_000_000;
-
-        check_valid_swap_curve(
-            fees.clone(),
-            CurveType::ConstantProduct,
-            Arc::new(ConstantProductCurve {}),
-            token_a_amount,
-            token_b_amount,
-        );
-        let token_b_price = 1;
-        check_valid_swap_curve(
-            fees.clone(),
-            CurveType::ConstantPrice,
-            Arc::new(ConstantPriceCurve { token_b_price }),
-            token_a_amount,
-            token_b_amount,
-        );
-        let token_b_offset = 10_000_000_000;
-        check_valid_swap_curve(
-            fees,
-            CurveType::Offset,
-            Arc::new(OffsetCurve { token_b_offset }),
-            token_a_amount,
-            token_b_amount,
-        );
-    }
+        assert_eq!(price_accumulator(&accounts), expected);
 
-    #[test]
-    fn test_valid_swap_curves_trade_fee_only() {
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 10;
-        let owner_trade_fee_numerator = 0;
-        let owner_trade_fee_denominator = 0;
-        let owner_withdraw_fee_numerator = 0;
-        let owner_withdraw_fee_denominator = 0;
-        let host_fee_numerator = 0;
-        let host_fee_denominator = 0;
-        let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
-        };
+        // deposit single
+        let expected = rewind(&mut accounts, 10);
+        accounts
+            .deposit_single_token_type_exact_amount_in(
+                &user_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                10_000,
+                0,
+            )
+            .unwrap();
+        assert_eq!(price_accumulator(&accounts), expected);
 
-        let token_a_amount = 10_000_000_000;
-        let token_b_amount = 50_000_000_000;
+        // withdraw single
+        let expected = rewind(&mut accounts, 5);
+        accounts
+            .withdraw_single_token_type_exact_amount_out(
+                &user_key,
+                &pool_key,
+                &mut pool_account,
+                &token_b_key,
+                &mut token_b_account,
+                1_000,
+                u64::MAX,
+            )
+            .unwrap();
+        assert_eq!(price_accumulator(&accounts), expected);
 
-        check_valid_swap_curve(
-            fees.clone(),
-            CurveType::ConstantProduct,
-            Arc::new(ConstantProductCurve {}),
-            token_a_amount,
-            token_b_amount,
-        );
-        let token_b_price = 10_000;
-        check_valid_swap_curve(
-            fees.clone(),
-            CurveType::ConstantPrice,
-            Arc::new(ConstantPriceCurve { token_b_price }),
-            token_a_amount,
-            token_b_amount / token_b_price,
-        );
-        let token_b_offset = 1;
-        check_valid_swap_curve(
-            fees,
-            CurveType::Offset,
-            Arc::new(OffsetCurve { token_b_offset }),
-            token_a_amount,
-            token_b_amount,
-        );
+        // account sized for the previous layout, the swap works without
+        // accumulating prices
+        {
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+            accounts.swap_account = Account::new(0, 1 + SwapV2::LEN, &SWAP_PROGRAM_ID);
+            accounts.pause_authority_key = Some(user_key);
+            accounts.initialize_swap().unwrap();
+            assert_eq!(accounts.swap_account.data[0], 2);
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &user_key, token_a_amount, 0, 0);
+            let swap_token_a_key = accounts.token_a_key;
+            let swap_token_b_key = accounts.token_b_key;
+            accounts
+                .swap(
+                    &user_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    token_a_amount / 10,
+                    0,
+                )
+                .unwrap();
+            assert_eq!(accounts.swap_account.data[0], 2);
+            let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_state.price_accumulator(), None);
+            accounts.set_paused(&user_key, true).unwrap();
+            assert_eq!(accounts.swap_account.data[0], 2);
+        }
     }
 
-    #[test]
-    fn test_valid_swap_with_fee_constraints() {
-        let owner_key = Pubkey::new_unique();
+    fn token_account_amount(account: &Account) -> u64 {
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
+    }
 
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 10;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 30;
-        let owner_withdraw_fee_numerator = 1;
-        let owner_withdraw_fee_denominator = 30;
-        let host_fee_numerator = 10;
-        let host_fee_denominator = 100;
+    fn mint_supply(account: &Account) -> u64 {
+        StateWithExtensions::<Mint>::unpack(&account.data)
+            .unwrap()
+            .base
+            .supply
+    }
 
-        let token_a_amount = 1_000_000;
-        let token_b_amount = 5_000_000;
+    fn test_transfer_fee() -> TransferFee {
+        TransferFee {
+            epoch: 0.into(),
+            maximum_fee: TEST_MAXIMUM_TRANSFER_FEE.into(),
+            transfer_fee_basis_points: TEST_TRANSFER_FEE_BASIS_POINTS.into(),
+        }
+    }
 
+    #[test]
+    fn test_pre_fee_amount() {
+        for (transfer_fee_basis_points, maximum_fee) in [
+            (0, 0),
+            (1, u64::MAX),
+            (100, 5),
+            (100, u64::MAX),
+            (3_333, 1_000),
+            (9_999, u64::MAX),
+            (MAX_FEE_BASIS_POINTS, 7),
+        ] {
+            let transfer_fee = TransferFee {
+                epoch: 0.into(),
+                maximum_fee: maximum_fee.into(),
+                transfer_fee_basis_points: transfer_fee_basis_points.into(),
+            };
+            for post_fee_amount in 0..2_000 {
+                let pre_fee_amount = pre_fee_amount(&transfer_fee, post_fee_amount).unwrap();
+                let fee = transfer_fee.calculate(pre_fee_amount).unwrap();
+                assert_eq!(pre_fee_amount - fee, post_fee_amount);
+                if pre_fee_amount > 0 {
+                    // any smaller transfer does not cover the fee
+                    let fee = transfer_fee.calculate(pre_fee_amount - 1).unwrap();
+                    assert!(pre_fee_amount - 1 - fee < post_fee_amount);
+                }
+            }
+        }
+
+        let transfer_fee = test_transfer_fee();
+        assert_eq!(pre_fee_amount(&transfer_fee, u64::MAX), None);
+    }
+
+    #[test]
+    fn test_token_2022_initialize() {
+        let user_key = Pubkey::new_unique();
         let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1000,
+            ..Fees::default()
         };
-
-        let curve = ConstantProductCurve {};
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
         let swap_curve = SwapCurve {
             curve_type: CurveType::ConstantProduct,
-            calculator: Arc::new(curve),
+            calculator: Arc::new(ConstantProductCurve {}),
         };
-
-        let owner_key_str = &owner_key.to_string();
-        let valid_curve_types = &[CurveType::ConstantProduct];
-        let constraints = Some(SwapConstraints {
-            owner_key: owner_key_str,
-            valid_curve_types,
-            fees: &fees,
-        });
-        let mut accounts = SwapAccountInfo::new(
-            &owner_key,
-            fees.clone(),
-            swap_curve,
-            token_a_amount,
-            token_b_amount,
-        );
-
-        // initialize swap
-        do_process_instruction_with_fee_constraints(
-            initialize(
-                &SWAP_PROGRAM_ID,
+        let new_accounts = || {
+            SwapAccountInfo::new_with_token_programs(
+                &user_key,
+                fees.clone(),
+                swap_curve.clone(),
+                token_a_amount,
+                token_b_amount,
+                &spl_token_2022::id(),
+                &spl_token_2022::id(),
                 &spl_token::id(),
-                &accounts.swap_key,
-                &accounts.authority_key,
-                &accounts.token_a_key,
-                &accounts.token_b_key,
-                &accounts.pool_mint_key,
-                &accounts.pool_fee_key,
-                &accounts.pool_token_key,
-                accounts.fees.clone(),
-                accounts.swap_curve.clone(),
             )
-            .unwrap(),
-            vec![
-                &mut accounts.swap_account,
-                &mut Account::default(),
-                &mut accounts.token_a_account,
-                &mut accounts.token_b_account,
-                &mut accounts.pool_mint_account,
-                &mut accounts.pool_fee_account,
-                &mut accounts.pool_token_account,
-                &mut Account::default(),
-            ],
-            &constraints,
-        )
-        .unwrap();
+        };
 
-        let authority_key = accounts.authority_key;
+        // token A mint does not match the token A account
+        {
+            let mut accounts = new_accounts();
+            accounts.token_a_mint_key = accounts.token_b_mint_key;
+            accounts.token_a_mint_account = accounts.token_b_mint_account.clone();
+            assert_eq!(
+                Err(SwapError::IncorrectMint.into()),
+                accounts.initialize_swap()
+            );
+        }
 
-        let (
-            token_a_key,
-            mut token_a_account,
-            token_b_key,
-            mut token_b_account,
-            pool_key,
-            mut pool_account,
-        ) = accounts.setup_token_accounts(
-            &owner_key,
-            &authority_key,
-            token_a_amount,
-            token_b_amount,
-            0,
-        );
+        // non-transferable token A
+        {
+            let mut accounts = new_accounts();
+            let (token_a_mint_key, mut token_a_mint_account) = create_mint_with_extensions(
+                &spl_token_2022::id(),
+                &user_key,
+                None,
+                &[ExtensionType::NonTransferable],
+            );
+            let (token_a_key, token_a_account) = mint_token(
+                &spl_token_2022::id(),
+                &token_a_mint_key,
+                &mut token_a_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                token_a_amount,
+            );
+            accounts.token_a_mint_key = token_a_mint_key;
+            accounts.token_a_mint_account = token_a_mint_account;
+            accounts.token_a_key = token_a_key;
+            accounts.token_a_account = token_a_account;
+            assert_eq!(
+                Err(SwapError::UnsupportedTokenExtension.into()),
+                accounts.initialize_swap()
+            );
+        }
 
-        let amount_in = token_a_amount / 2;
-        let minimum_amount_out = 0;
+        // token A account requires memos on incoming transfers
+        {
+            let mut accounts = new_accounts();
+            let token_a_key = Pubkey::new_unique();
+            let space = ExtensionType::get_account_len::<spl_token_2022::state::Account>(&[
+                ExtensionType::MemoTransfer,
+            ]);
+            let mut token_a_account = Account::new(
+                Rent::default().minimum_balance(space),
+                space,
+                &spl_token_2022::id(),
+            );
+            do_process_instruction(
+                initialize_account(
+                    &spl_token_2022::id(),
+                    &token_a_key,
+                    &accounts.token_a_mint_key,
+                    &user_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut token_a_account,
+                    &mut accounts.token_a_mint_account,
+                    &mut Account::default(),
+                    &mut create_account_for_test(&Rent::free()),
+                ],
+            )
+            .unwrap();
+            do_process_instruction(
+                enable_required_transfer_memos(&spl_token_2022::id(), &token_a_key, &user_key, &[])
+                    .unwrap(),
+                vec![&mut token_a_account, &mut Account::default()],
+            )
+            .unwrap();
+            do_process_instruction(
+                mint_to(
+                    &spl_token_2022::id(),
+                    &accounts.token_a_mint_key,
+                    &token_a_key,
+                    &user_key,
+                    &[],
+                    token_a_amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.token_a_mint_account,
+                    &mut token_a_account,
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+            do_process_instruction(
+                set_authority(
+                    &spl_token_2022::id(),
+                    &token_a_key,
+                    Some(&accounts.authority_key),
+                    AuthorityType::AccountOwner,
+                    &user_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut token_a_account, &mut Account::default()],
+            )
+            .unwrap();
+            accounts.token_a_key = token_a_key;
+            accounts.token_a_account = token_a_account;
+            assert_eq!(
+                Err(SwapError::UnsupportedTokenExtension.into()),
+                accounts.initialize_swap()
+            );
+        }
 
-        // perform the swap
-        do_process_instruction_with_fee_constraints(
-            swap(
-                &SWAP_PROGRAM_ID,
-                &spl_token::id(),
-                &accounts.swap_key,
+        // pool mint with a transfer fee or close authority
+        for (extension_type, error) in [
+            (
+                ExtensionType::TransferFeeConfig,
+                SwapError::UnsupportedTokenExtension,
+            ),
+            (
+                ExtensionType::MintCloseAuthority,
+                SwapError::InvalidCloseAuthority,
+            ),
+        ] {
+            let mut accounts = new_accounts();
+            let (pool_mint_key, mut pool_mint_account) = create_mint_with_extensions(
+                &spl_token_2022::id(),
                 &accounts.authority_key,
+                None,
+                &[extension_type],
+            );
+            let (pool_token_key, pool_token_account) = mint_token(
+                &spl_token_2022::id(),
+                &pool_mint_key,
+                &mut pool_mint_account,
                 &accounts.authority_key,
-                &token_a_key,
-                &accounts.token_a_key,
-                &accounts.token_b_key,
-                &token_b_key,
-                &accounts.pool_mint_key,
-                &accounts.pool_fee_key,
-                Some(&pool_key),
-                Swap {
-                    amount_in,
-                    minimum_amount_out,
-                },
-            )
-            .unwrap(),
-            vec![
-                &mut accounts.swap_account,
-                &mut Account::default(),
-                &mut Account::default(),
-                &mut token_a_account,
-                &mut accounts.token_a_account,
-                &mut accounts.token_b_account,
-                &mut token_b_account,
-                &mut accounts.pool_mint_account,
-                &mut accounts.pool_fee_account,
-                &mut Account::default(),
-                &mut pool_account,
-            ],
-            &constraints,
-        )
-        .unwrap();
+                &user_key,
+                0,
+            );
+            let (pool_fee_key, pool_fee_account) = mint_token(
+                &spl_token_2022::id(),
+                &pool_mint_key,
+                &mut pool_mint_account,
+                &accounts.authority_key,
+                &user_key,
+                0,
+            );
+            accounts.pool_mint_key = pool_mint_key;
+            accounts.pool_mint_account = pool_mint_account;
+            accounts.pool_token_key = pool_token_key;
+            accounts.pool_token_account = pool_token_account;
+            accounts.pool_fee_key = pool_fee_key;
+            accounts.pool_fee_account = pool_fee_account;
+            assert_eq!(Err(error.into()), accounts.initialize_swap());
+        }
 
-        // check that fees were taken in the host fee account
-        let host_fee_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
-        let owner_fee_account =
-            spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
-        let total_fee = owner_fee_account.amount * host_fee_denominator
-            / (host_fee_denominator - host_fee_numerator);
-        assert_eq!(
-            total_fee,
-            host_fee_account.amount + owner_fee_account.amount
-        );
+        // token A and pool tokens under Token-2022, token B under SPL Token
+        {
+            let mut accounts = new_accounts();
+            accounts.initialize_swap().unwrap();
+            let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(*swap_state.token_program_id(), spl_token_2022::id());
+            assert_eq!(*swap_state.token_a_mint(), accounts.token_a_mint_key);
+            assert_eq!(*swap_state.token_b_mint(), accounts.token_b_mint_key);
+            assert_eq!(
+                token_account_amount(&accounts.pool_token_account),
+                to_u64(swap_curve.calculator.new_pool_supply()).unwrap()
+            );
+        }
     }
 
     #[test]
-    fn test_invalid_swap() {
+    fn test_token_2022_transfer_fees() {
         let user_key = Pubkey::new_unique();
-        let swapper_key = Pubkey::new_unique();
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 4;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 10;
-        let owner_withdraw_fee_numerator = 1;
-        let owner_withdraw_fee_denominator = 5;
-        let host_fee_numerator = 9;
-        let host_fee_denominator = 100;
         let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1000,
+            ..Fees::default()
         };
-
-        let token_a_amount = 1000;
-        let token_b_amount = 5000;
-        let curve_type = CurveType::ConstantProduct;
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
         let swap_curve = SwapCurve {
-            curve_type,
+            curve_type: CurveType::ConstantProduct,
             calculator: Arc::new(ConstantProductCurve {}),
         };
-        let mut accounts =
-            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+        let transfer_fee = test_transfer_fee();
+        let mut accounts = SwapAccountInfo::new_with_token_programs(
+            &user_key,
+            fees.clone(),
+            swap_curve.clone(),
+            token_a_amount,
+            token_b_amount,
+            &spl_token_2022::id(),
+            &spl_token_2022::id(),
+            &spl_token::id(),
+        );
+        let (token_a_mint_key, mut token_a_mint_account) = create_mint_with_extensions(
+            &spl_token_2022::id(),
+            &user_key,
+            None,
+            &[ExtensionType::TransferFeeConfig],
+        );
+        let (token_a_key, token_a_account) = mint_token(
+            &spl_token_2022::id(),
+            &token_a_mint_key,
+            &mut token_a_mint_account,
+            &user_key,
+            &accounts.authority_key,
+            token_a_amount,
+        );
+        accounts.token_a_mint_key = token_a_mint_key;
+        accounts.token_a_mint_account = token_a_mint_account;
+        accounts.token_a_key = token_a_key;
+        accounts.token_a_account = token_a_account;
+        accounts.initialize_swap().unwrap();
 
-        let initial_a = token_a_amount / 5;
-        let initial_b = token_b_amount / 5;
-        let minimum_token_b_amount = initial_b / 2;
+        let initial_a = 100_000;
+        let initial_b = 100_000;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &user_key, initial_a, initial_b, 0);
 
-        let swap_token_a_key = accounts.token_a_key;
-        let swap_token_b_key = accounts.token_b_key;
+        // swap A to B, only the amount left after the transfer fee is swapped
+        {
+            let amount_in = 10_000;
+            let swap_token_a_amount = token_account_amount(&accounts.token_a_account);
+            let swap_token_b_amount = token_account_amount(&accounts.token_b_account);
+            let user_token_a_amount = token_account_amount(&token_a_account);
+            let user_token_b_amount = token_account_amount(&token_b_account);
+            let amount_received = amount_in - transfer_fee.calculate(amount_in).unwrap();
+            let results = swap_curve
+                .swap(
+                    amount_received.into(),
+                    swap_token_a_amount.into(),
+                    swap_token_b_amount.into(),
+                    TradeDirection::AtoB,
+                    &fees,
+                )
+                .unwrap();
+            accounts
+                .swap(
+                    &user_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &accounts.token_a_key.clone(),
+                    &accounts.token_b_key.clone(),
+                    &token_b_key,
+                    &mut token_b_account,
+                    amount_in,
+                    0,
+                )
+                .unwrap();
+            assert_eq!(
+                token_account_amount(&accounts.token_a_account),
+                swap_token_a_amount + amount_received
+            );
+            assert_eq!(
+                token_account_amount(&token_a_account),
+                user_token_a_amount - amount_in
+            );
+            assert_eq!(
+                token_account_amount(&token_b_account),
+                user_token_b_amount + to_u64(results.destination_amount_swapped).unwrap()
+            );
+        }
 
-        // swap not initialized
+        // swap B to A, the user receives the amount left after the transfer fee
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let amount_in = 10_000;
+            let swap_token_a_amount = token_account_amount(&accounts.token_a_account);
+            let swap_token_b_amount = token_account_amount(&accounts.token_b_account);
+            let user_token_a_amount = token_account_amount(&token_a_account);
+            let results = swap_curve
+                .swap(
+                    amount_in.into(),
+                    swap_token_b_amount.into(),
+                    swap_token_a_amount.into(),
+                    TradeDirection::BtoA,
+                    &fees,
+                )
+                .unwrap();
+            let amount_out = to_u64(results.destination_amount_swapped).unwrap();
+            let amount_received = amount_out - transfer_fee.calculate(amount_out).unwrap();
             assert_eq!(
-                Err(ProgramError::UninitializedAccount),
+                Err(SwapError::ExceededSlippage.into()),
                 accounts.swap(
-                    &swapper_key,
+                    &user_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &accounts.token_b_key.clone(),
+                    &accounts.token_a_key.clone(),
                     &token_a_key,
                     &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
+                    amount_in,
+                    amount_received + 1,
+                )
+            );
+            accounts
+                .swap(
+                    &user_key,
                     &token_b_key,
                     &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
+                    &accounts.token_b_key.clone(),
+                    &accounts.token_a_key.clone(),
+                    &token_a_key,
+                    &mut token_a_account,
+                    amount_in,
+                    amount_received,
                 )
+                .unwrap();
+            assert_eq!(
+                token_account_amount(&accounts.token_a_account),
+                swap_token_a_amount - amount_out
+            );
+            assert_eq!(
+                token_account_amount(&token_a_account),
+                user_token_a_amount + amount_received
             );
         }
 
-        accounts.initialize_swap().unwrap();
-
-        // wrong swap account program id
+        // deposit all, the pool receives the required amounts
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let old_swap_account = accounts.swap_account;
-            let mut wrong_swap_account = old_swap_account.clone();
-            wrong_swap_account.owner = spl_token::id();
-            accounts.swap_account = wrong_swap_account;
+            let pool_token_amount = 10_000_000;
+            let swap_token_a_amount = token_account_amount(&accounts.token_a_account);
+            let swap_token_b_amount = token_account_amount(&accounts.token_b_account);
+            let user_token_a_amount = token_account_amount(&token_a_account);
+            let results = swap_curve
+                .calculator
+                .pool_tokens_to_trading_tokens(
+                    pool_token_amount.into(),
+                    mint_supply(&accounts.pool_mint_account).into(),
+                    swap_token_a_amount.into(),
+                    swap_token_b_amount.into(),
+                    RoundDirection::Ceiling,
+                )
+                .unwrap();
+            let required_a = to_u64(results.token_a_amount).unwrap();
+            let transferred_a = pre_fee_amount(&transfer_fee, required_a).unwrap();
+            assert!(transferred_a > required_a);
             assert_eq!(
-                Err(ProgramError::IncorrectProgramId),
-                accounts.swap(
-                    &swapper_key,
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.deposit_all_token_types(
+                    &user_key,
                     &token_a_key,
                     &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_token_amount,
+                    required_a,
+                    u64::MAX,
                 )
             );
-            accounts.swap_account = old_swap_account;
+            accounts
+                .deposit_all_token_types(
+                    &user_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_token_amount,
+                    transferred_a,
+                    u64::MAX,
+                )
+                .unwrap();
+            assert_eq!(
+                token_account_amount(&accounts.token_a_account),
+                swap_token_a_amount + required_a
+            );
+            assert_eq!(
+                token_account_amount(&token_a_account),
+                user_token_a_amount - transferred_a
+            );
+            assert_eq!(token_account_amount(&pool_account), pool_token_amount);
         }
 
-        // wrong bump seed
+        // withdraw all, the user receives the amounts left after the transfer fee
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _bump_seed) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &spl_token::id(),
-            );
-            accounts.authority_key = bad_authority_key;
+            let pool_token_amount = 5_000_000;
+            let swap_token_a_amount = token_account_amount(&accounts.token_a_account);
+            let user_token_a_amount = token_account_amount(&token_a_account);
+            let results = swap_curve
+                .calculator
+                .pool_tokens_to_trading_tokens(
+                    pool_token_amount.into(),
+                    mint_supply(&accounts.pool_mint_account).into(),
+                    swap_token_a_amount.into(),
+                    token_account_amount(&accounts.token_b_account).into(),
+                    RoundDirection::Floor,
+                )
+                .unwrap();
+            let withdrawn_a = to_u64(results.token_a_amount).unwrap();
+            let received_a = withdrawn_a - transfer_fee.calculate(withdrawn_a).unwrap();
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.swap(
-                    &swapper_key,
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.withdraw_all_token_types(
+                    &user_key,
+                    &pool_key,
+                    &mut pool_account,
                     &token_a_key,
                     &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
+                    pool_token_amount,
+                    withdrawn_a,
+                    0,
                 )
             );
-            accounts.authority_key = old_authority;
+            accounts
+                .withdraw_all_token_types(
+                    &user_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    pool_token_amount,
+                    received_a,
+                    0,
+                )
+                .unwrap();
+            assert_eq!(
+                token_account_amount(&accounts.token_a_account),
+                swap_token_a_amount - withdrawn_a
+            );
+            assert_eq!(
+                token_account_amount(&token_a_account),
+                user_token_a_amount + received_a
+            );
         }
 
-        // wrong token program id
+        // deposit token A, pool tokens are minted for the amount received
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let wrong_program_id = Pubkey::new_unique();
-            assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
-                do_process_instruction(
-                    swap(
-                        &SWAP_PROGRAM_ID,
-                        &wrong_program_id,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.authority_key,
-                        &token_a_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_b_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        None,
-                        Swap {
-                            amount_in: initial_a,
-                            minimum_amount_out: minimum_token_b_amount,
-                        },
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut Account::default(),
-                        &mut token_a_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut accounts.pool_fee_account,
-                        &mut Account::default(),
-                    ],
-                ),
+            let source_token_amount = 10_000;
+            let swap_token_a_amount = token_account_amount(&accounts.token_a_account);
+            let user_pool_amount = token_account_amount(&pool_account);
+            let amount_received =
+                source_token_amount - transfer_fee.calculate(source_token_amount).unwrap();
+            let pool_token_amount = swap_curve
+                .deposit_single_token_type(
+                    amount_received.into(),
+                    swap_token_a_amount.into(),
+                    token_account_amount(&accounts.token_b_account).into(),
+                    mint_supply(&accounts.pool_mint_account).into(),
+                    TradeDirection::AtoB,
+                    &fees,
+                )
+                .unwrap();
+            accounts
+                .deposit_single_token_type_exact_amount_in(
+                    &user_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    source_token_amount,
+                    0,
+                )
+                .unwrap();
+            assert_eq!(
+                token_account_amount(&accounts.token_a_account),
+                swap_token_a_amount + amount_received
+            );
+            assert_eq!(
+                token_account_amount(&pool_account),
+                user_pool_amount + to_u64(pool_token_amount).unwrap()
             );
         }
 
-        // not enough token a to swap
+        // withdraw exactly the amount of token A requested
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.swap(
-                    &swapper_key,
+            let destination_token_amount = 1_000;
+            let swap_token_a_amount = token_account_amount(&accounts.token_a_account);
+            let user_token_a_amount = token_account_amount(&token_a_account);
+            accounts
+                .withdraw_single_token_type_exact_amount_out(
+                    &user_key,
+                    &pool_key,
+                    &mut pool_account,
                     &token_a_key,
                     &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a * 2,
-                    minimum_token_b_amount * 2,
+                    destination_token_amount,
+                    u64::MAX,
                 )
+                .unwrap();
+            assert_eq!(
+                token_account_amount(&accounts.token_a_account),
+                swap_token_a_amount
+                    - pre_fee_amount(&transfer_fee, destination_token_amount).unwrap()
+            );
+            assert_eq!(
+                token_account_amount(&token_a_account),
+                user_token_a_amount + destination_token_amount
             );
         }
+    }
 
-        // wrong swap token A / B accounts
-        {
-            let (
+    struct ConcentratedPoolAccountInfo {
+        pool_key: Pubkey,
+        pool_account: Account,
+        authority_key: Pubkey,
+        fees: Fees,
+        tick_spacing: u16,
+        initial_sqrt_price: u128,
+        protocol_fee_owner_key: Pubkey,
+        token_a_key: Pubkey,
+        token_a_account: Account,
+        token_a_mint_key: Pubkey,
+        token_a_mint_account: Account,
+        token_b_key: Pubkey,
+        token_b_account: Account,
+        token_b_mint_key: Pubkey,
+        token_b_mint_account: Account,
+    }
+
+    impl ConcentratedPoolAccountInfo {
+        pub fn new(
+            user_key: &Pubkey,
+            fees: Fees,
+            tick_spacing: u16,
+            initial_sqrt_price: u128,
+        ) -> Self {
+            let pool_key = Pubkey::new_unique();
+            let pool_account = Account::new(0, ConcentratedPool::LEN, &SWAP_PROGRAM_ID);
+            let (authority_key, _bump_seed) =
+                Pubkey::find_program_address(&[&pool_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
+            let (token_a_mint_key, mut token_a_mint_account) =
+                create_mint(&spl_token::id(), user_key, None);
+            let (token_a_key, token_a_account) = mint_token(
+                &spl_token::id(),
+                &token_a_mint_key,
+                &mut token_a_mint_account,
+                user_key,
+                &authority_key,
+                0,
+            );
+            let (token_b_mint_key, mut token_b_mint_account) =
+                create_mint(&spl_token::id(), user_key, None);
+            let (token_b_key, token_b_account) = mint_token(
+                &spl_token::id(),
+                &token_b_mint_key,
+                &mut token_b_mint_account,
+                user_key,
+                &authority_key,
+                0,
+            );
+            Self {
+                pool_key,
+                pool_account,
+                authority_key,
+                fees,
+                tick_spacing,
+                initial_sqrt_price,
+                protocol_fee_owner_key: *user_key,
                 token_a_key,
-                mut token_a_account,
+                token_a_account,
+                token_a_mint_key,
+                token_a_mint_account,
                 token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+                token_b_account,
+                token_b_mint_key,
+                token_b_mint_account,
+            }
+        }
+
+        pub fn initialize_pool(&mut self) -> ProgramResult {
+            do_process_instruction(
+                initialize_concentrated_pool(
+                    &SWAP_PROGRAM_ID,
+                    &self.pool_key,
+                    &self.authority_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    &self.token_a_mint_key,
+                    &self.token_b_mint_key,
+                    &self.protocol_fee_owner_key,
+                    self.fees.clone(),
+                    self.tick_spacing,
+                    self.initial_sqrt_price,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.pool_account,
+                    &mut Account::default(),
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.token_a_mint_account,
+                    &mut self.token_b_mint_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn setup_token_accounts(
+            &mut self,
+            user_key: &Pubkey,
+            a_amount: u64,
+            b_amount: u64,
+        ) -> (Pubkey, Account, Pubkey, Account) {
+            let (token_a_key, token_a_account) = mint_token(
+                &spl_token::id(),
+                &self.token_a_mint_key,
+                &mut self.token_a_mint_account,
+                user_key,
+                user_key,
+                a_amount,
+            );
+            let (token_b_key, token_b_account) = mint_token(
+                &spl_token::id(),
+                &self.token_b_mint_key,
+                &mut self.token_b_mint_account,
+                user_key,
+                user_key,
+                b_amount,
+            );
+            (token_a_key, token_a_account, token_b_key, token_b_account)
+        }
+
+        pub fn open_position(
+            &mut self,
+            owner_key: &Pubkey,
+            tick_lower: i32,
+            tick_upper: i32,
+        ) -> Result<(Pubkey, Account), ProgramError> {
+            let position_key = Pubkey::new_unique();
+            let mut position_account = Account::new(0, Position::LEN, &SWAP_PROGRAM_ID);
+            do_process_instruction(
+                open_position(
+                    &SWAP_PROGRAM_ID,
+                    &self.pool_key,
+                    &position_key,
+                    owner_key,
+                    OpenPosition {
+                        tick_lower,
+                        tick_upper,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.pool_account,
+                    &mut position_account,
+                    &mut Account::default(),
+                ],
+            )?;
+            Ok((position_key, position_account))
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn increase_liquidity(
+            &mut self,
+            owner_key: &Pubkey,
+            position_key: &Pubkey,
+            position_account: &mut Account,
+            source_a_key: &Pubkey,
+            source_a_account: &mut Account,
+            source_b_key: &Pubkey,
+            source_b_account: &mut Account,
+            liquidity: u128,
+            maximum_token_a_amount: u64,
+            maximum_token_b_amount: u64,
+        ) -> ProgramResult {
             let user_transfer_key = Pubkey::new_unique();
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
+            for (source_key, source_account, amount) in [
+                (source_a_key, &mut *source_a_account, maximum_token_a_amount),
+                (source_b_key, &mut *source_b_account, maximum_token_b_amount),
+            ] {
+                let source_owner_key = spl_token::state::Account::unpack(&source_account.data)
+                    .unwrap()
+                    .owner;
                 do_process_instruction(
-                    swap(
-                        &SWAP_PROGRAM_ID,
+                    approve(
                         &spl_token::id(),
-                        &accounts.swap_key,
-                        &accounts.authority_key,
+                        source_key,
                         &user_transfer_key,
-                        &token_a_key,
-                        &token_a_key,
-                        &token_b_key,
-                        &token_b_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        None,
-                        Swap {
-                            amount_in: initial_a,
-                            minimum_amount_out: minimum_token_b_amount,
-                        },
+                        &source_owner_key,
+                        &[],
+                        amount,
                     )
                     .unwrap(),
                     vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
+                        source_account,
                         &mut Account::default(),
-                        &mut token_a_account.clone(),
-                        &mut token_a_account,
-                        &mut token_b_account.clone(),
-                        &mut token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut accounts.pool_fee_account,
                         &mut Account::default(),
                     ],
+                )
+                .unwrap();
+            }
+
+            do_process_instruction(
+                increase_liquidity(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &spl_token::id(),
+                    &self.pool_key,
+                    &self.authority_key,
+                    position_key,
+                    owner_key,
+                    &user_transfer_key,
+                    source_a_key,
+                    source_b_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    &self.token_a_mint_key,
+                    &self.token_b_mint_key,
+                    IncreaseLiquidity {
+                        liquidity,
+                        maximum_token_a_amount,
+                        maximum_token_b_amount,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.pool_account,
+                    &mut Account::default(),
+                    position_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    source_a_account,
+                    source_b_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.token_a_mint_account,
+                    &mut self.token_b_mint_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn decrease_liquidity(
+            &mut self,
+            owner_key: &Pubkey,
+            position_key: &Pubkey,
+            position_account: &mut Account,
+            destination_a_key: &Pubkey,
+            destination_a_account: &mut Account,
+            destination_b_key: &Pubkey,
+            destination_b_account: &mut Account,
+            liquidity: u128,
+            minimum_token_a_amount: u64,
+            minimum_token_b_amount: u64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                decrease_liquidity(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &spl_token::id(),
+                    &self.pool_key,
+                    &self.authority_key,
+                    position_key,
+                    owner_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    destination_a_key,
+                    destination_b_key,
+                    &self.token_a_mint_key,
+                    &self.token_b_mint_key,
+                    DecreaseLiquidity {
+                        liquidity,
+                        minimum_token_a_amount,
+                        minimum_token_b_amount,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.pool_account,
+                    &mut Account::default(),
+                    position_account,
+                    &mut Account::default(),
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    destination_a_account,
+                    destination_b_account,
+                    &mut self.token_a_mint_account,
+                    &mut self.token_b_mint_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn swap(
+            &mut self,
+            user_key: &Pubkey,
+            user_source_key: &Pubkey,
+            user_source_account: &mut Account,
+            user_destination_key: &Pubkey,
+            user_destination_account: &mut Account,
+            trade_direction: TradeDirection,
+            amount_in: u64,
+            minimum_amount_out: u64,
+        ) -> ProgramResult {
+            let user_transfer_key = Pubkey::new_unique();
+            do_process_instruction(
+                approve(
+                    &spl_token::id(),
+                    user_source_key,
+                    &user_transfer_key,
+                    user_key,
+                    &[],
+                    amount_in,
+                )
+                .unwrap(),
+                vec![
+                    user_source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            let (
+                swap_source_key,
+                swap_source_account,
+                source_mint_key,
+                source_mint_account,
+                swap_destination_key,
+                swap_destination_account,
+                destination_mint_key,
+                destination_mint_account,
+            ) = match trade_direction {
+                TradeDirection::AtoB => (
+                    self.token_a_key,
+                    &mut self.token_a_account,
+                    self.token_a_mint_key,
+                    &mut self.token_a_mint_account,
+                    self.token_b_key,
+                    &mut self.token_b_account,
+                    self.token_b_mint_key,
+                    &mut self.token_b_mint_account,
                 ),
-            );
+                TradeDirection::BtoA => (
+                    self.token_b_key,
+                    &mut self.token_b_account,
+                    self.token_b_mint_key,
+                    &mut self.token_b_mint_account,
+                    self.token_a_key,
+                    &mut self.token_a_account,
+                    self.token_a_mint_key,
+                    &mut self.token_a_mint_account,
+                ),
+            };
+            do_process_instruction(
+                swap_concentrated(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &spl_token::id(),
+                    &self.pool_key,
+                    &self.authority_key,
+                    &user_transfer_key,
+                    user_source_key,
+                    &swap_source_key,
+                    &swap_destination_key,
+                    user_destination_key,
+                    &source_mint_key,
+                    &destination_mint_key,
+                    Swap {
+                        amount_in,
+                        minimum_amount_out,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.pool_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    user_source_account,
+                    swap_source_account,
+                    swap_destination_account,
+                    user_destination_account,
+                    source_mint_account,
+                    destination_mint_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
         }
 
-        // wrong user token A / B accounts
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    initial_a,
-                    minimum_token_b_amount,
+        pub fn collect_protocol_fees(
+            &mut self,
+            protocol_fee_owner_key: &Pubkey,
+            destination_a_key: &Pubkey,
+            destination_a_account: &mut Account,
+            destination_b_key: &Pubkey,
+            destination_b_account: &mut Account,
+        ) -> ProgramResult {
+            do_process_instruction(
+                collect_protocol_fees(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &spl_token::id(),
+                    &self.pool_key,
+                    &self.authority_key,
+                    protocol_fee_owner_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    destination_a_key,
+                    destination_b_key,
+                    &self.token_a_mint_key,
+                    &self.token_b_mint_key,
                 )
-            );
+                .unwrap(),
+                vec![
+                    &mut self.pool_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    destination_a_account,
+                    destination_b_account,
+                    &mut self.token_a_mint_account,
+                    &mut self.token_b_mint_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
         }
+    }
 
-        // swap from a to a
+    #[test]
+    fn test_concentrated_pool() {
+        let user_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        let one = 1u128 << 64;
+
+        // invalid parameters
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let mut accounts = ConcentratedPoolAccountInfo::new(&user_key, fees.clone(), 0, one);
             assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account.clone(),
-                    &swap_token_a_key,
-                    &swap_token_a_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    initial_a,
-                    minimum_token_b_amount,
-                )
+                Err(SwapError::InvalidCurve.into()),
+                accounts.initialize_pool()
+            );
+            accounts.tick_spacing = 10;
+            accounts.initial_sqrt_price = MAX_SQRT_PRICE + 1;
+            assert_eq!(
+                Err(SwapError::InvalidCurve.into()),
+                accounts.initialize_pool()
+            );
+            accounts.initial_sqrt_price = one;
+            accounts.fees = Fees {
+                trade_fee_numerator: 1,
+                trade_fee_denominator: 2,
+                owner_trade_fee_numerator: 1,
+                owner_trade_fee_denominator: 2,
+                ..Fees::default()
+            };
+            assert_eq!(
+                Err(SwapError::InvalidFee.into()),
+                accounts.initialize_pool()
             );
         }
 
-        // incorrect mint provided
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let (pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &accounts.authority_key, None);
-            let old_pool_key = accounts.pool_mint_key;
-            let old_pool_account = accounts.pool_mint_account;
-            accounts.pool_mint_key = pool_mint_key;
-            accounts.pool_mint_account = pool_mint_account;
+        let mut accounts = ConcentratedPoolAccountInfo::new(&user_key, fees, 10, one);
+        accounts.initialize_pool().unwrap();
+        assert_eq!(
+            Err(SwapError::AlreadyInUse.into()),
+            accounts.initialize_pool()
+        );
+        let pool = ConcentratedPool::unpack(&accounts.pool_account.data).unwrap();
+        assert_eq!(pool.tick_current, 0);
+        assert_eq!(pool.sqrt_price, one);
+        assert_eq!(pool.liquidity, 0);
 
-            assert_eq!(
-                Err(SwapError::IncorrectPoolMint.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
-                )
-            );
+        // open positions, bounded by multiples of the tick spacing
+        assert_eq!(
+            Err(SwapError::InvalidTick.into()),
+            accounts.open_position(&user_key, -105, 100).map(|_| ())
+        );
+        assert_eq!(
+            Err(SwapError::InvalidTick.into()),
+            accounts.open_position(&user_key, 100, 100).map(|_| ())
+        );
+        let (in_range_key, mut in_range_account) =
+            accounts.open_position(&user_key, -100, 100).unwrap();
+        let (below_key, mut below_account) = accounts.open_position(&user_key, -200, -100).unwrap();
+
+        // add liquidity
+        let initial_amount = 100_000_000;
+        let (user_a_key, mut user_a_account, user_b_key, mut user_b_account) =
+            accounts.setup_token_accounts(&user_key, initial_amount, initial_amount);
+        let liquidity = 1_000_000_000;
+        let expected =
+            token_amounts_for_liquidity(0, one, -100, 100, liquidity, RoundDirection::Ceiling)
+                .unwrap();
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.increase_liquidity(
+                &user_key,
+                &in_range_key,
+                &mut in_range_account,
+                &user_a_key,
+                &mut user_a_account,
+                &user_b_key,
+                &mut user_b_account,
+                liquidity,
+                expected.token_a_amount as u64 - 1,
+                u64::MAX,
+            )
+        );
+        let wrong_owner_key = Pubkey::new_unique();
+        assert_eq!(
+            Err(SwapError::InvalidPositionOwner.into()),
+            accounts.increase_liquidity(
+                &wrong_owner_key,
+                &in_range_key,
+                &mut in_range_account,
+                &user_a_key,
+                &mut user_a_account,
+                &user_b_key,
+                &mut user_b_account,
+                liquidity,
+                u64::MAX,
+                u64::MAX,
+            )
+        );
+        accounts
+            .increase_liquidity(
+                &user_key,
+                &in_range_key,
+                &mut in_range_account,
+                &user_a_key,
+                &mut user_a_account,
+                &user_b_key,
+                &mut user_b_account,
+                liquidity,
+                u64::MAX,
+                u64::MAX,
+            )
+            .unwrap();
+        assert_eq!(
+            token_account_amount(&accounts.token_a_account) as u128,
+            expected.token_a_amount
+        );
+        assert_eq!(
+            token_account_amount(&accounts.token_b_account) as u128,
+            expected.token_b_amount
+        );
+        accounts
+            .increase_liquidity(
+                &user_key,
+                &below_key,
+                &mut below_account,
+                &user_a_key,
+                &mut user_a_account,
+                &user_b_key,
+                &mut user_b_account,
+                2 * liquidity,
+                u64::MAX,
+                u64::MAX,
+            )
+            .unwrap();
+        let pool = ConcentratedPool::unpack(&accounts.pool_account.data).unwrap();
+        assert_eq!(pool.liquidity, liquidity);
+        assert_eq!(pool.ticks.len(), 3);
+        let position = Position::unpack(&below_account.data).unwrap();
+        assert_eq!(position.liquidity, 2 * liquidity);
+
+        // swap across the lower tick of the position in range
+        let amount_in = 8_000_000;
+        let mut expected_pool = pool;
+        let expected_result = expected_pool
+            .swap(
+                amount_in as u128,
+                token_account_amount(&accounts.token_a_account) as u128,
+                token_account_amount(&accounts.token_b_account) as u128,
+                TradeDirection::AtoB,
+            )
+            .unwrap();
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.swap(
+                &user_key,
+                &user_a_key,
+                &mut user_a_account,
+                &user_b_key,
+                &mut user_b_account,
+                TradeDirection::AtoB,
+                amount_in,
+                expected_result.destination_amount_swapped as u64 + 1,
+            )
+        );
+        let user_a_amount = token_account_amount(&user_a_account);
+        let user_b_amount = token_account_amount(&user_b_account);
+        accounts
+            .swap(
+                &user_key,
+                &user_a_key,
+                &mut user_a_account,
+                &user_b_key,
+                &mut user_b_account,
+                TradeDirection::AtoB,
+                amount_in,
+                0,
+            )
+            .unwrap();
+        assert_eq!(
+            token_account_amount(&user_a_account),
+            user_a_amount - amount_in
+        );
+        assert_eq!(
+            token_account_amount(&user_b_account) as u128,
+            user_b_amount as u128 + expected_result.destination_amount_swapped
+        );
+        assert_eq!(
+            token_account_amount(&accounts.token_a_account) as u128,
+            expected_result.new_swap_source_amount
+        );
+        let pool = ConcentratedPool::unpack(&accounts.pool_account.data).unwrap();
+        assert_eq!(pool, expected_pool);
+        assert!(pool.tick_current < -100);
+        assert_eq!(pool.liquidity, 2 * liquidity);
+        assert_eq!(pool.protocol_fees_a as u128, expected_result.owner_fee);
+
+        // withdraw the liquidity in range with its fees
+        let mut expected_position = Position::unpack(&in_range_account.data).unwrap();
+        let expected = expected_pool
+            .modify_position(&mut expected_position, -(liquidity as i128))
+            .unwrap();
+        assert!(expected_position.fees_owed_a > 0);
+        assert_eq!(expected_position.fees_owed_b, 0);
+        let user_a_amount = token_account_amount(&user_a_account);
+        let user_b_amount = token_account_amount(&user_b_account);
+        assert_eq!(
+            Err(SwapError::InsufficientLiquidity.into()),
+            accounts.decrease_liquidity(
+                &user_key,
+                &in_range_key,
+                &mut in_range_account,
+                &user_a_key,
+                &mut user_a_account,
+                &user_b_key,
+                &mut user_b_account,
+                liquidity + 1,
+                0,
+                0,
+            )
+        );
+        let mut other_accounts =
+            ConcentratedPoolAccountInfo::new(&user_key, Fees::default(), 10, one);
+        other_accounts.initialize_pool().unwrap();
+        let (_, mut other_position_account) =
+            other_accounts.open_position(&user_key, -100, 100).unwrap();
+        assert_eq!(
+            Err(SwapError::IncorrectPosition.into()),
+            accounts.decrease_liquidity(
+                &user_key,
+                &in_range_key,
+                &mut other_position_account,
+                &user_a_key,
+                &mut user_a_account,
+                &user_b_key,
+                &mut user_b_account,
+                liquidity,
+                0,
+                0,
+            )
+        );
+        accounts
+            .decrease_liquidity(
+                &user_key,
+                &in_range_key,
+                &mut in_range_account,
+                &user_a_key,
+                &mut user_a_account,
+                &user_b_key,
+                &mut user_b_account,
+                liquidity,
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(
+            token_account_amount(&user_a_account) as u128,
+            user_a_amount as u128 + expected.token_a_amount + expected_position.fees_owed_a as u128
+        );
+        assert_eq!(
+            token_account_amount(&user_b_account) as u128,
+            user_b_amount as u128 + expected.token_b_amount
+        );
+        let position = Position::unpack(&in_range_account.data).unwrap();
+        assert_eq!(position.liquidity, 0);
+        assert_eq!(position.fees_owed_a, 0);
+        let pool = ConcentratedPool::unpack(&accounts.pool_account.data).unwrap();
+        assert_eq!(pool.ticks.len(), 2);
 
-            accounts.pool_mint_key = old_pool_key;
-            accounts.pool_mint_account = old_pool_account;
-        }
+        // nothing left to withdraw
+        assert_eq!(
+            Err(SwapError::ZeroTradingTokens.into()),
+            accounts.decrease_liquidity(
+                &user_key,
+                &in_range_key,
+                &mut in_range_account,
+                &user_a_key,
+                &mut user_a_account,
+                &user_b_key,
+                &mut user_b_account,
+                0,
+                0,
+                0,
+            )
+        );
+
+        // collect the owner trade fees
+        let (fee_a_key, mut fee_a_account, fee_b_key, mut fee_b_account) =
+            accounts.setup_token_accounts(&user_key, 0, 0);
+        assert_eq!(
+            Err(SwapError::InvalidFeeAccountOwner.into()),
+            accounts.collect_protocol_fees(
+                &wrong_owner_key,
+                &fee_a_key,
+                &mut fee_a_account,
+                &fee_b_key,
+                &mut fee_b_account,
+            )
+        );
+        accounts
+            .collect_protocol_fees(
+                &user_key,
+                &fee_a_key,
+                &mut fee_a_account,
+                &fee_b_key,
+                &mut fee_b_account,
+            )
+            .unwrap();
+        assert_eq!(token_account_amount(&fee_a_account), pool.protocol_fees_a);
+        assert_eq!(token_account_amount(&fee_b_account), 0);
+        let pool = ConcentratedPool::unpack(&accounts.pool_account.data).unwrap();
+        assert_eq!(pool.protocol_fees_a, 0);
+    }
+
+    struct MultiAssetPoolAccountInfo {
+        pool_key: Pubkey,
+        pool_account: Account,
+        authority_key: Pubkey,
+        fees: Fees,
+        curve: MultiAssetCurve,
+        pool_mint_key: Pubkey,
+        pool_mint_account: Account,
+        pool_fee_key: Pubkey,
+        pool_fee_account: Account,
+        pool_token_key: Pubkey,
+        pool_token_account: Account,
+        token_keys: Vec<Pubkey>,
+        token_accounts: Vec<Account>,
+        token_mint_keys: Vec<Pubkey>,
+        token_mint_accounts: Vec<Account>,
+    }
 
-        // incorrect fee account provided
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                wrong_pool_key,
-                wrong_pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let old_pool_fee_account = accounts.pool_fee_account;
-            let old_pool_fee_key = accounts.pool_fee_key;
-            accounts.pool_fee_account = wrong_pool_account;
-            accounts.pool_fee_key = wrong_pool_key;
-            assert_eq!(
-                Err(SwapError::IncorrectFeeAccount.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
-                )
+    impl MultiAssetPoolAccountInfo {
+        pub fn new(
+            user_key: &Pubkey,
+            fees: Fees,
+            curve: MultiAssetCurve,
+            token_amounts: &[u64],
+        ) -> Self {
+            let pool_key = Pubkey::new_unique();
+            let pool_account = Account::new(0, MultiAssetPool::LEN, &SWAP_PROGRAM_ID);
+            let (authority_key, _bump_seed) =
+                Pubkey::find_program_address(&[&pool_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
+            let (pool_mint_key, mut pool_mint_account) =
+                create_mint(&spl_token::id(), &authority_key, None);
+            let (pool_token_key, pool_token_account) = mint_token(
+                &spl_token::id(),
+                &pool_mint_key,
+                &mut pool_mint_account,
+                &authority_key,
+                user_key,
+                0,
             );
-            accounts.pool_fee_account = old_pool_fee_account;
-            accounts.pool_fee_key = old_pool_fee_key;
-        }
-
-        // no approval
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let user_transfer_key = Pubkey::new_unique();
-            assert_eq!(
-                Err(TokenError::OwnerMismatch.into()),
-                do_process_instruction(
-                    swap(
-                        &SWAP_PROGRAM_ID,
-                        &spl_token::id(),
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &user_transfer_key,
-                        &token_a_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_b_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        None,
-                        Swap {
-                            amount_in: initial_a,
-                            minimum_amount_out: minimum_token_b_amount,
-                        },
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut Account::default(),
-                        &mut token_a_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut accounts.pool_fee_account,
-                        &mut Account::default(),
-                    ],
-                ),
+            let (pool_fee_key, pool_fee_account) = mint_token(
+                &spl_token::id(),
+                &pool_mint_key,
+                &mut pool_mint_account,
+                &authority_key,
+                user_key,
+                0,
             );
+            let mut token_keys = vec![];
+            let mut token_accounts = vec![];
+            let mut token_mint_keys = vec![];
+            let mut token_mint_accounts = vec![];
+            for amount in token_amounts {
+                let (token_mint_key, mut token_mint_account) =
+                    create_mint(&spl_token::id(), user_key, None);
+                let (token_key, token_account) = mint_token(
+                    &spl_token::id(),
+                    &token_mint_key,
+                    &mut token_mint_account,
+                    user_key,
+                    &authority_key,
+                    *amount,
+                );
+                token_keys.push(token_key);
+                token_accounts.push(token_account);
+                token_mint_keys.push(token_mint_key);
+                token_mint_accounts.push(token_mint_account);
+            }
+            Self {
+                pool_key,
+                pool_account,
+                authority_key,
+                fees,
+                curve,
+                pool_mint_key,
+                pool_mint_account,
+                pool_fee_key,
+                pool_fee_account,
+                pool_token_key,
+                pool_token_account,
+                token_keys,
+                token_accounts,
+                token_mint_keys,
+                token_mint_accounts,
+            }
         }
 
-        // output token value 0
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(SwapError::ZeroTradingTokens.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &swap_token_b_key,
-                    &swap_token_a_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    1,
-                    1,
+        pub fn initialize_pool(&mut self) -> ProgramResult {
+            let mut accounts = vec![
+                &mut self.pool_account,
+                &mut self.pool_mint_account,
+                &mut self.pool_fee_account,
+                &mut self.pool_token_account,
+            ];
+            for (token_account, token_mint_account) in self
+                .token_accounts
+                .iter_mut()
+                .zip(self.token_mint_accounts.iter_mut())
+            {
+                accounts.push(token_account);
+                accounts.push(token_mint_account);
+            }
+            let mut authority_account = Account::default();
+            let mut pool_token_program_account = Account::default();
+            accounts.insert(1, &mut authority_account);
+            accounts.insert(5, &mut pool_token_program_account);
+            do_process_instruction(
+                initialize_multi_asset_pool(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &self.pool_key,
+                    &self.authority_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    &self.pool_token_key,
+                    &self.token_keys,
+                    &self.token_mint_keys,
+                    self.fees.clone(),
+                    self.curve.clone(),
                 )
-            );
+                .unwrap(),
+                accounts,
+            )
         }
 
-        // slippage exceeded: minimum out amount too high
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount * 2,
-                )
-            );
+        pub fn setup_token_accounts(
+            &mut self,
+            user_key: &Pubkey,
+            amount: u64,
+        ) -> (Vec<Pubkey>, Vec<Account>) {
+            self.token_mint_keys
+                .iter()
+                .zip(self.token_mint_accounts.iter_mut())
+                .map(|(token_mint_key, token_mint_account)| {
+                    mint_token(
+                        &spl_token::id(),
+                        token_mint_key,
+                        token_mint_account,
+                        user_key,
+                        user_key,
+                        amount,
+                    )
+                })
+                .unzip()
         }
 
-        // invalid input: can't use swap pool as user source / dest
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
-            let authority_key = accounts.authority_key;
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.swap(
-                    &authority_key,
-                    &swap_token_a_key,
-                    &mut swap_token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
-                )
-            );
-            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &swap_token_b_key,
-                    &mut swap_token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
-                )
-            );
+        pub fn token_amounts(&self) -> Vec<u128> {
+            self.token_accounts
+                .iter()
+                .map(|account| token_account_amount(account) as u128)
+                .collect()
         }
 
-        // still correct: constraint specified, no host fee account
-        {
-            let authority_key = accounts.authority_key;
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &authority_key, initial_a, initial_b, 0);
-            let owner_key = &swapper_key.to_string();
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let constraints = Some(SwapConstraints {
-                owner_key,
-                valid_curve_types: &[],
-                fees: &fees,
-            });
-            do_process_instruction_with_fee_constraints(
-                swap(
-                    &SWAP_PROGRAM_ID,
+        fn approve(
+            user_key: &Pubkey,
+            source_key: &Pubkey,
+            source_account: &mut Account,
+            amount: u64,
+        ) -> Pubkey {
+            let user_transfer_key = Pubkey::new_unique();
+            do_process_instruction(
+                approve(
                     &spl_token::id(),
-                    &accounts.swap_key,
-                    &accounts.authority_key,
-                    &accounts.authority_key,
-                    &token_a_key,
-                    &accounts.token_a_key,
-                    &accounts.token_b_key,
-                    &token_b_key,
-                    &accounts.pool_mint_key,
-                    &accounts.pool_fee_key,
-                    None,
-                    Swap {
-                        amount_in: initial_a,
-                        minimum_amount_out: minimum_token_b_amount,
-                    },
+                    source_key,
+                    &user_transfer_key,
+                    user_key,
+                    &[],
+                    amount,
                 )
                 .unwrap(),
                 vec![
-                    &mut accounts.swap_account,
+                    source_account,
                     &mut Account::default(),
                     &mut Account::default(),
-                    &mut token_a_account,
-                    &mut accounts.token_a_account,
-                    &mut accounts.token_b_account,
-                    &mut token_b_account,
-                    &mut accounts.pool_mint_account,
-                    &mut accounts.pool_fee_account,
-                    &mut Account::default(),
                 ],
-                &constraints,
             )
             .unwrap();
+            user_transfer_key
         }
 
-        // invalid mint for host fee account
-        {
-            let authority_key = accounts.authority_key;
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &authority_key, initial_a, initial_b, 0);
-            let (
-                bad_token_a_key,
-                mut bad_token_a_account,
-                _token_b_key,
-                mut _token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &authority_key, initial_a, initial_b, 0);
-            let owner_key = &swapper_key.to_string();
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let constraints = Some(SwapConstraints {
-                owner_key,
-                valid_curve_types: &[],
-                fees: &fees,
-            });
-            assert_eq!(
-                Err(SwapError::IncorrectPoolMint.into()),
-                do_process_instruction_with_fee_constraints(
-                    swap(
-                        &SWAP_PROGRAM_ID,
+        #[allow(clippy::too_many_arguments)]
+        pub fn swap(
+            &mut self,
+            user_key: &Pubkey,
+            source_index: usize,
+            user_source_key: &Pubkey,
+            user_source_account: &mut Account,
+            destination_index: usize,
+            user_destination_key: &Pubkey,
+            user_destination_account: &mut Account,
+            amount_in: u64,
+            minimum_amount_out: u64,
+        ) -> ProgramResult {
+            let user_transfer_key =
+                Self::approve(user_key, user_source_key, user_source_account, amount_in);
+            let mut source_mint_account = self.token_mint_accounts[source_index].clone();
+            let mut destination_mint_account = self.token_mint_accounts[destination_index].clone();
+            let mut accounts = vec![
+                &mut self.pool_account,
+                &mut self.pool_mint_account,
+                &mut self.pool_fee_account,
+                &mut source_mint_account,
+                &mut destination_mint_account,
+            ];
+            accounts.extend(self.token_accounts.iter_mut());
+            let mut authority_account = Account::default();
+            let mut user_transfer_account = Account::default();
+            let mut program_accounts = vec![Account::default(); 3];
+            accounts.insert(1, &mut authority_account);
+            accounts.insert(2, &mut user_transfer_account);
+            accounts.insert(3, user_source_account);
+            accounts.insert(4, user_destination_account);
+            for (i, program_account) in program_accounts.iter_mut().enumerate() {
+                accounts.insert(9 + i, program_account);
+            }
+            do_process_instruction(
+                multi_asset_swap(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &spl_token::id(),
+                    &spl_token::id(),
+                    &self.pool_key,
+                    &self.authority_key,
+                    &user_transfer_key,
+                    user_source_key,
+                    user_destination_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    &self.token_mint_keys[source_index],
+                    &self.token_mint_keys[destination_index],
+                    &self.token_keys,
+                    Swap {
+                        amount_in,
+                        minimum_amount_out,
+                    },
+                )
+                .unwrap(),
+                accounts,
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn deposit_all_token_types(
+            &mut self,
+            user_key: &Pubkey,
+            source_keys: &[Pubkey],
+            source_accounts: &mut [Account],
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            pool_token_amount: u64,
+            maximum_token_amounts: &[u64],
+        ) -> ProgramResult {
+            let user_transfer_key = Pubkey::new_unique();
+            for ((source_key, source_account), amount) in source_keys
+                .iter()
+                .zip(source_accounts.iter_mut())
+                .zip(maximum_token_amounts)
+            {
+                do_process_instruction(
+                    approve(
                         &spl_token::id(),
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.authority_key,
-                        &token_a_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_b_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        Some(&bad_token_a_key),
-                        Swap {
-                            amount_in: initial_a,
-                            minimum_amount_out: 0,
-                        },
+                        source_key,
+                        &user_transfer_key,
+                        user_key,
+                        &[],
+                        *amount,
                     )
                     .unwrap(),
                     vec![
-                        &mut accounts.swap_account,
+                        source_account,
                         &mut Account::default(),
                         &mut Account::default(),
-                        &mut token_a_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut accounts.pool_fee_account,
-                        &mut Account::default(),
-                        &mut bad_token_a_account,
                     ],
-                    &constraints,
-                ),
-            );
-        }
-    }
-
-    #[test]
-    fn test_overdraw_offset_curve() {
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 10;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 30;
-        let owner_withdraw_fee_numerator = 1;
-        let owner_withdraw_fee_denominator = 30;
-        let host_fee_numerator = 10;
-        let host_fee_denominator = 100;
-
-        let token_a_amount = 1_000_000_000;
-        let token_b_amount = 0;
-        let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
-        };
-
-        let token_b_offset = 2_000_000;
-        let swap_curve = SwapCurve {
-            curve_type: CurveType::Offset,
-            calculator: Arc::new(OffsetCurve { token_b_offset }),
-        };
-        let user_key = Pubkey::new_unique();
-        let swapper_key = Pubkey::new_unique();
-
-        let mut accounts =
-            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
-
-        accounts.initialize_swap().unwrap();
-
-        let swap_token_a_key = accounts.token_a_key;
-        let swap_token_b_key = accounts.token_b_key;
-        let initial_a = 500_000;
-        let initial_b = 1_000;
-
-        let (
-            token_a_key,
-            mut token_a_account,
-            token_b_key,
-            mut token_b_account,
-            _pool_key,
-            _pool_account,
-        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-
-        // swap a to b way, fails, there's no liquidity
-        let a_to_b_amount = initial_a;
-        let minimum_token_b_amount = 0;
-
-        assert_eq!(
-            Err(SwapError::ZeroTradingTokens.into()),
-            accounts.swap(
-                &swapper_key,
-                &token_a_key,
-                &mut token_a_account,
-                &swap_token_a_key,
-                &swap_token_b_key,
-                &token_b_key,
-                &mut token_b_account,
-                a_to_b_amount,
-                minimum_token_b_amount,
-            )
-        );
-
-        // swap b to a, succeeds at offset price
-        let b_to_a_amount = initial_b;
-        let minimum_token_a_amount = 0;
-        accounts
-            .swap(
-                &swapper_key,
-                &token_b_key,
-                &mut token_b_account,
-                &swap_token_b_key,
-                &swap_token_a_key,
-                &token_a_key,
-                &mut token_a_account,
-                b_to_a_amount,
-                minimum_token_a_amount,
-            )
-            .unwrap();
-
-        // try a to b again, succeeds due to new liquidity
-        accounts
-            .swap(
-                &swapper_key,
-                &token_a_key,
-                &mut token_a_account,
-                &swap_token_a_key,
-                &swap_token_b_key,
-                &token_b_key,
-                &mut token_b_account,
-                a_to_b_amount,
-                minimum_token_b_amount,
+                )
+                .unwrap();
+            }
+            let token_count = self.token_keys.len();
+            let mut authority_account = Account::default();
+            let mut user_transfer_account = Account::default();
+            let mut pool_token_program_account = Account::default();
+            let mut program_accounts = vec![Account::default(); token_count];
+            let mut accounts = vec![
+                &mut self.pool_account,
+                &mut authority_account,
+                &mut user_transfer_account,
+                &mut self.pool_mint_account,
+                destination_account,
+                &mut pool_token_program_account,
+            ];
+            for (((source_account, token_account), token_mint_account), program_account) in
+                source_accounts
+                    .iter_mut()
+                    .zip(self.token_accounts.iter_mut())
+                    .zip(self.token_mint_accounts.iter_mut())
+                    .zip(program_accounts.iter_mut())
+            {
+                accounts.push(source_account);
+                accounts.push(token_account);
+                accounts.push(token_mint_account);
+                accounts.push(program_account);
+            }
+            do_process_instruction(
+                multi_asset_deposit_all_token_types(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &self.pool_key,
+                    &self.authority_key,
+                    &user_transfer_key,
+                    &self.pool_mint_key,
+                    destination_key,
+                    source_keys,
+                    &self.token_keys,
+                    &self.token_mint_keys,
+                    &vec![spl_token::id(); token_count],
+                    MultiAssetDepositAllTokenTypes {
+                        pool_token_amount,
+                        maximum_token_amounts: maximum_token_amounts.to_vec(),
+                    },
+                )
+                .unwrap(),
+                accounts,
             )
-            .unwrap();
+        }
 
-        // try a to b again, fails due to no more liquidity
-        assert_eq!(
-            Err(SwapError::ZeroTradingTokens.into()),
-            accounts.swap(
-                &swapper_key,
-                &token_a_key,
-                &mut token_a_account,
-                &swap_token_a_key,
-                &swap_token_b_key,
-                &token_b_key,
-                &mut token_b_account,
-                a_to_b_amount,
-                minimum_token_b_amount,
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_all_token_types(
+            &mut self,
+            user_key: &Pubkey,
+            source_key: &Pubkey,
+            source_account: &mut Account,
+            destination_keys: &[Pubkey],
+            destination_accounts: &mut [Account],
+            pool_token_amount: u64,
+            minimum_token_amounts: &[u64],
+        ) -> ProgramResult {
+            let user_transfer_key =
+                Self::approve(user_key, source_key, source_account, pool_token_amount);
+            let token_count = self.token_keys.len();
+            let mut authority_account = Account::default();
+            let mut user_transfer_account = Account::default();
+            let mut pool_token_program_account = Account::default();
+            let mut program_accounts = vec![Account::default(); token_count];
+            let mut accounts = vec![
+                &mut self.pool_account,
+                &mut authority_account,
+                &mut user_transfer_account,
+                &mut self.pool_mint_account,
+                source_account,
+                &mut self.pool_fee_account,
+                &mut pool_token_program_account,
+            ];
+            for (((token_account, destination_account), token_mint_account), program_account) in
+                self.token_accounts
+                    .iter_mut()
+                    .zip(destination_accounts.iter_mut())
+                    .zip(self.token_mint_accounts.iter_mut())
+                    .zip(program_accounts.iter_mut())
+            {
+                accounts.push(token_account);
+                accounts.push(destination_account);
+                accounts.push(token_mint_account);
+                accounts.push(program_account);
+            }
+            do_process_instruction(
+                multi_asset_withdraw_all_token_types(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &self.pool_key,
+                    &self.authority_key,
+                    &user_transfer_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    source_key,
+                    &self.token_keys,
+                    destination_keys,
+                    &self.token_mint_keys,
+                    &vec![spl_token::id(); token_count],
+                    MultiAssetWithdrawAllTokenTypes {
+                        pool_token_amount,
+                        minimum_token_amounts: minimum_token_amounts.to_vec(),
+                    },
+                )
+                .unwrap(),
+                accounts,
             )
-        );
+        }
 
-        // Try to deposit, fails because deposits are not allowed for offset
-        // curve swaps
-        {
-            let initial_a = 100;
-            let initial_b = 100;
-            let pool_amount = 100;
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(SwapError::UnsupportedCurveOperation.into()),
-                accounts.deposit_all_token_types(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    pool_amount,
-                    initial_a,
-                    initial_b,
+        #[allow(clippy::too_many_arguments)]
+        pub fn deposit_single_token_type_exact_amount_in(
+            &mut self,
+            user_key: &Pubkey,
+            token_index: usize,
+            source_key: &Pubkey,
+            source_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            source_token_amount: u64,
+            minimum_pool_token_amount: u64,
+        ) -> ProgramResult {
+            let user_transfer_key =
+                Self::approve(user_key, source_key, source_account, source_token_amount);
+            let mut source_mint_account = self.token_mint_accounts[token_index].clone();
+            let mut authority_account = Account::default();
+            let mut user_transfer_account = Account::default();
+            let mut source_program_account = Account::default();
+            let mut pool_token_program_account = Account::default();
+            let mut accounts = vec![
+                &mut self.pool_account,
+                &mut authority_account,
+                &mut user_transfer_account,
+                source_account,
+                &mut self.pool_mint_account,
+                destination_account,
+                &mut source_mint_account,
+                &mut source_program_account,
+                &mut pool_token_program_account,
+            ];
+            accounts.extend(self.token_accounts.iter_mut());
+            do_process_instruction(
+                multi_asset_deposit_single_token_type_exact_amount_in(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &spl_token::id(),
+                    &self.pool_key,
+                    &self.authority_key,
+                    &user_transfer_key,
+                    source_key,
+                    &self.pool_mint_key,
+                    destination_key,
+                    &self.token_mint_keys[token_index],
+                    &self.token_keys,
+                    DepositSingleTokenTypeExactAmountIn {
+                        source_token_amount,
+                        minimum_pool_token_amount,
+                    },
                 )
+                .unwrap(),
+                accounts,
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_single_token_type_exact_amount_out(
+            &mut self,
+            user_key: &Pubkey,
+            token_index: usize,
+            source_key: &Pubkey,
+            source_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            destination_token_amount: u64,
+            maximum_pool_token_amount: u64,
+        ) -> ProgramResult {
+            let user_transfer_key = Self::approve(
+                user_key,
+                source_key,
+                source_account,
+                maximum_pool_token_amount,
             );
+            let mut destination_mint_account = self.token_mint_accounts[token_index].clone();
+            let mut authority_account = Account::default();
+            let mut user_transfer_account = Account::default();
+            let mut destination_program_account = Account::default();
+            let mut pool_token_program_account = Account::default();
+            let mut accounts = vec![
+                &mut self.pool_account,
+                &mut authority_account,
+                &mut user_transfer_account,
+                &mut self.pool_mint_account,
+                source_account,
+                destination_account,
+                &mut self.pool_fee_account,
+                &mut destination_mint_account,
+                &mut destination_program_account,
+                &mut pool_token_program_account,
+            ];
+            accounts.extend(self.token_accounts.iter_mut());
+            do_process_instruction(
+                multi_asset_withdraw_single_token_type_exact_amount_out(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &spl_token::id(),
+                    &self.pool_key,
+                    &self.authority_key,
+                    &user_transfer_key,
+                    &self.pool_mint_key,
+                    source_key,
+                    destination_key,
+                    &self.pool_fee_key,
+                    &self.token_mint_keys[token_index],
+                    &self.token_keys,
+                    WithdrawSingleTokenTypeExactAmountOut {
+                        destination_token_amount,
+                        maximum_pool_token_amount,
+                    },
+                )
+                .unwrap(),
+                accounts,
+            )
         }
     }
 
     #[test]
-    fn test_withdraw_all_offset_curve() {
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 10;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 30;
-        let owner_withdraw_fee_numerator = 0;
-        let owner_withdraw_fee_denominator = 30;
-        let host_fee_numerator = 10;
-        let host_fee_denominator = 100;
-
-        let token_a_amount = 1_000_000_000;
-        let token_b_amount = 10;
+    fn test_multi_asset_pool() {
+        let user_key = Pubkey::new_unique();
         let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            owner_withdraw_fee_numerator: 1,
+            owner_withdraw_fee_denominator: 1_000,
+            ..Fees::default()
         };
-
-        let token_b_offset = 2_000_000;
-        let swap_curve = SwapCurve {
-            curve_type: CurveType::Offset,
-            calculator: Arc::new(OffsetCurve { token_b_offset }),
+        let weighted_curve = MultiAssetCurve {
+            curve_type: MultiAssetCurveType::Weighted,
+            amp: 0,
+            weights: vec![50, 30, 20],
         };
-        let total_pool = swap_curve.calculator.new_pool_supply();
-        let user_key = Pubkey::new_unique();
-        let withdrawer_key = Pubkey::new_unique();
-
-        let mut accounts =
-            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
-
-        accounts.initialize_swap().unwrap();
-
-        let (
-            token_a_key,
-            mut token_a_account,
-            token_b_key,
-            mut token_b_account,
-            _pool_key,
-            _pool_account,
-        ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 0);
-
-        let pool_key = accounts.pool_token_key;
-        let mut pool_account = accounts.pool_token_account.clone();
+        let token_amounts = [5_000_000, 3_000_000, 2_000_000];
 
-        // WithdrawAllTokenTypes takes all tokens for A and B.
-        // The curve's calculation for token B will say to transfer
-        // `token_b_offset + token_b_amount`, but only `token_b_amount` will be
-        // moved.
-        accounts
-            .withdraw_all_token_types(
+        // invalid parameters
+        {
+            let mut accounts = MultiAssetPoolAccountInfo::new(
                 &user_key,
-                &pool_key,
-                &mut pool_account,
-                &token_a_key,
-                &mut token_a_account,
-                &token_b_key,
-                &mut token_b_account,
-                total_pool.try_into().unwrap(),
-                0,
-                0,
-            )
-            .unwrap();
-
-        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-        assert_eq!(token_a.amount, token_a_amount);
-        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-        assert_eq!(token_b.amount, token_b_amount);
-        let swap_token_a =
-            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-        assert_eq!(swap_token_a.amount, 0);
-        let swap_token_b =
-            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-        assert_eq!(swap_token_b.amount, 0);
-    }
-
-    #[test]
-    fn test_withdraw_all_constant_price_curve() {
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 10;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 30;
-        let owner_withdraw_fee_numerator = 0;
-        let owner_withdraw_fee_denominator = 30;
-        let host_fee_numerator = 10;
-        let host_fee_denominator = 100;
-
-        // initialize "unbalanced", so that withdrawing all will have some issues
-        // A: 1_000_000_000
-        // B: 2_000_000_000 (1_000 * 2_000_000)
-        let swap_token_a_amount = 1_000_000_000;
-        let swap_token_b_amount = 1_000;
-        let token_b_price = 2_000_000;
-        let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
-        };
-
-        let swap_curve = SwapCurve {
-            curve_type: CurveType::ConstantPrice,
-            calculator: Arc::new(ConstantPriceCurve { token_b_price }),
-        };
-        let total_pool = swap_curve.calculator.new_pool_supply();
-        let user_key = Pubkey::new_unique();
-        let withdrawer_key = Pubkey::new_unique();
+                fees.clone(),
+                MultiAssetCurve {
+                    weights: vec![100, 1, 0],
+                    ..weighted_curve
+                },
+                &token_amounts,
+            );
+            assert_eq!(
+                Err(SwapError::InvalidCurve.into()),
+                accounts.initialize_pool()
+            );
+            let mut accounts = MultiAssetPoolAccountInfo::new(
+                &user_key,
+                fees.clone(),
+                weighted_curve.clone(),
+                &[5_000_000, 0, 2_000_000],
+            );
+            assert_eq!(
+                Err(SwapError::EmptySupply.into()),
+                accounts.initialize_pool()
+            );
+            let mut accounts = MultiAssetPoolAccountInfo::new(
+                &user_key,
+                fees.clone(),
+                weighted_curve.clone(),
+                &token_amounts,
+            );
+            accounts.token_mint_keys.swap(0, 1);
+            accounts.token_mint_accounts.swap(0, 1);
+            assert_eq!(
+                Err(SwapError::IncorrectMint.into()),
+                accounts.initialize_pool()
+            );
+        }
 
-        let mut accounts = SwapAccountInfo::new(
+        let mut accounts = MultiAssetPoolAccountInfo::new(
             &user_key,
-            fees,
-            swap_curve,
-            swap_token_a_amount,
-            swap_token_b_amount,
+            fees.clone(),
+            weighted_curve.clone(),
+            &token_amounts,
+        );
+        accounts.initialize_pool().unwrap();
+        assert_eq!(
+            Err(SwapError::AlreadyInUse.into()),
+            accounts.initialize_pool()
+        );
+        let pool = MultiAssetPool::unpack(&accounts.pool_account.data).unwrap();
+        assert_eq!(pool.curve, weighted_curve);
+        assert_eq!(pool.tokens.len(), 3);
+        assert_eq!(pool.tokens[2].account, accounts.token_keys[2]);
+        assert_eq!(pool.tokens[2].mint, accounts.token_mint_keys[2]);
+        assert_eq!(
+            token_account_amount(&accounts.pool_token_account) as u128,
+            INITIAL_SWAP_POOL_AMOUNT
         );
 
-        accounts.initialize_swap().unwrap();
-
-        let (
-            token_a_key,
-            mut token_a_account,
-            token_b_key,
-            mut token_b_account,
-            _pool_key,
-            _pool_account,
-        ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 0);
-
-        let pool_key = accounts.pool_token_key;
-        let mut pool_account = accounts.pool_token_account.clone();
+        let initial_amount = 1_000_000;
+        let (user_token_keys, mut user_token_accounts) =
+            accounts.setup_token_accounts(&user_key, initial_amount);
+        let (user_pool_key, mut user_pool_account) = mint_token(
+            &spl_token::id(),
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &accounts.authority_key,
+            &user_key,
+            0,
+        );
 
-        // WithdrawAllTokenTypes will not take all token A and B, since their
-        // ratio is unbalanced.  It will try to take 1_500_000_000 worth of
-        // each token, which means 1_500_000_000 token A, and 750 token B.
-        // With no slippage, this will leave 250 token B in the pool.
+        // swap token 0 for token 2
+        let amount_in = 100_000;
+        let expected = pool
+            .curve
+            .swap(amount_in as u128, 0, 2, &accounts.token_amounts(), &fees)
+            .unwrap();
+        let (mut user_source_account, mut user_destination_account) = (
+            user_token_accounts[0].clone(),
+            user_token_accounts[2].clone(),
+        );
         assert_eq!(
             Err(SwapError::ExceededSlippage.into()),
-            accounts.withdraw_all_token_types(
+            accounts.swap(
                 &user_key,
-                &pool_key,
-                &mut pool_account,
-                &token_a_key,
-                &mut token_a_account,
-                &token_b_key,
-                &mut token_b_account,
-                total_pool.try_into().unwrap(),
-                swap_token_a_amount,
-                swap_token_b_amount,
+                0,
+                &user_token_keys[0],
+                &mut user_source_account,
+                2,
+                &user_token_keys[2],
+                &mut user_destination_account,
+                amount_in,
+                expected.destination_amount_swapped as u64 + 1,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::RepeatedMint.into()),
+            accounts.swap(
+                &user_key,
+                0,
+                &user_token_keys[0],
+                &mut user_source_account,
+                0,
+                &user_token_keys[2],
+                &mut user_destination_account,
+                amount_in,
+                0,
             )
         );
-
         accounts
-            .withdraw_all_token_types(
+            .swap(
                 &user_key,
-                &pool_key,
-                &mut pool_account,
-                &token_a_key,
-                &mut token_a_account,
-                &token_b_key,
-                &mut token_b_account,
-                total_pool.try_into().unwrap(),
                 0,
+                &user_token_keys[0],
+                &mut user_source_account,
+                2,
+                &user_token_keys[2],
+                &mut user_destination_account,
+                amount_in,
                 0,
             )
             .unwrap();
-
-        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-        assert_eq!(token_a.amount, swap_token_a_amount);
-        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-        assert_eq!(token_b.amount, 750);
-        let swap_token_a =
-            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-        assert_eq!(swap_token_a.amount, 0);
-        let swap_token_b =
-            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-        assert_eq!(swap_token_b.amount, 250);
-
-        // deposit now, not enough to cover the tokens already in there
-        let token_b_amount = 10;
-        let token_a_amount = token_b_amount * token_b_price;
-        let (
-            token_a_key,
-            mut token_a_account,
-            token_b_key,
-            mut token_b_account,
-            pool_key,
-            mut pool_account,
-        ) = accounts.setup_token_accounts(
-            &user_key,
-            &withdrawer_key,
-            token_a_amount,
-            token_b_amount,
-            0,
+        user_token_accounts[0] = user_source_account;
+        user_token_accounts[2] = user_destination_account;
+        assert_eq!(
+            token_account_amount(&user_token_accounts[0]),
+            initial_amount - amount_in
         );
-
+        assert_eq!(
+            token_account_amount(&user_token_accounts[2]) as u128,
+            initial_amount as u128 + expected.destination_amount_swapped
+        );
+        // the amounts are proportional to the weights, so the spot price is
+        // one, less fees and slippage
+        assert!(expected.destination_amount_swapped < 99_700);
+        assert!(expected.destination_amount_swapped > 95_000);
+        let token_amounts = accounts.token_amounts();
+        assert_eq!(token_amounts[0], expected.new_swap_source_amount);
+        assert_eq!(token_amounts[2], expected.new_swap_destination_amount);
+        let fee_pool_tokens = token_account_amount(&accounts.pool_fee_account);
+        assert!(fee_pool_tokens > 0);
+
+        // deposit all tokens for 1% of the pool tokens
+        let pool_token_amount = mint_supply(&accounts.pool_mint_account) / 100;
+        let expected = pool
+            .curve
+            .pool_tokens_to_trading_tokens(
+                pool_token_amount as u128,
+                mint_supply(&accounts.pool_mint_account) as u128,
+                &token_amounts,
+                RoundDirection::Ceiling,
+            )
+            .unwrap();
+        let maximum_token_amounts: Vec<u64> = expected.iter().map(|a| *a as u64).collect();
+        let mut too_low = maximum_token_amounts.clone();
+        too_low[1] -= 1;
         assert_eq!(
             Err(SwapError::ExceededSlippage.into()),
             accounts.deposit_all_token_types(
-                &withdrawer_key,
-                &token_a_key,
-                &mut token_a_account,
-                &token_b_key,
-                &mut token_b_account,
-                &pool_key,
-                &mut pool_account,
-                1, // doesn't matter
-                token_a_amount,
-                token_b_amount,
+                &user_key,
+                &user_token_keys,
+                &mut user_token_accounts,
+                &user_pool_key,
+                &mut user_pool_account,
+                pool_token_amount,
+                &too_low,
             )
         );
+        accounts
+            .deposit_all_token_types(
+                &user_key,
+                &user_token_keys,
+                &mut user_token_accounts,
+                &user_pool_key,
+                &mut user_pool_account,
+                pool_token_amount,
+                &maximum_token_amounts,
+            )
+            .unwrap();
+        assert_eq!(token_account_amount(&user_pool_account), pool_token_amount);
+        for (i, amount) in accounts.token_amounts().iter().enumerate() {
+            assert_eq!(*amount, token_amounts[i] + expected[i]);
+        }
 
-        // deposit enough tokens, success!
-        let token_b_amount = 125;
-        let token_a_amount = token_b_amount * token_b_price;
-        let (
-            token_a_key,
-            mut token_a_account,
-            token_b_key,
-            mut token_b_account,
-            pool_key,
-            mut pool_account,
-        ) = accounts.setup_token_accounts(
-            &user_key,
-            &withdrawer_key,
-            token_a_amount,
-            token_b_amount,
-            0,
+        // withdraw them all back, less the withdrawal fee
+        let token_amounts = accounts.token_amounts();
+        let withdraw_fee = fees.owner_withdraw_fee(pool_token_amount as u128).unwrap();
+        let expected = pool
+            .curve
+            .pool_tokens_to_trading_tokens(
+                pool_token_amount as u128 - withdraw_fee,
+                mint_supply(&accounts.pool_mint_account) as u128,
+                &token_amounts,
+                RoundDirection::Floor,
+            )
+            .unwrap();
+        let user_amounts: Vec<u64> = user_token_accounts
+            .iter()
+            .map(token_account_amount)
+            .collect();
+        let mut too_high: Vec<u64> = expected.iter().map(|a| *a as u64).collect();
+        too_high[0] += 1;
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.withdraw_all_token_types(
+                &user_key,
+                &user_pool_key,
+                &mut user_pool_account,
+                &user_token_keys,
+                &mut user_token_accounts,
+                pool_token_amount,
+                &too_high,
+            )
         );
-
         accounts
-            .deposit_all_token_types(
-                &withdrawer_key,
-                &token_a_key,
-                &mut token_a_account,
-                &token_b_key,
-                &mut token_b_account,
-                &pool_key,
-                &mut pool_account,
-                1, // doesn't matter
-                token_a_amount,
-                token_b_amount,
+            .withdraw_all_token_types(
+                &user_key,
+                &user_pool_key,
+                &mut user_pool_account,
+                &user_token_keys,
+                &mut user_token_accounts,
+                pool_token_amount,
+                &[0, 0, 0],
             )
             .unwrap();
-    }
+        assert_eq!(token_account_amount(&user_pool_account), 0);
+        assert_eq!(
+            token_account_amount(&accounts.pool_fee_account) as u128,
+            fee_pool_tokens as u128 + withdraw_fee
+        );
+        for (i, account) in user_token_accounts.iter().enumerate() {
+            assert_eq!(
+                token_account_amount(account) as u128,
+                user_amounts[i] as u128 + expected[i]
+            );
+        }
 
-    #[test]
-    fn test_deposits_allowed_single_token() {
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 10;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 30;
-        let owner_withdraw_fee_numerator = 0;
-        let owner_withdraw_fee_denominator = 30;
-        let host_fee_numerator = 10;
-        let host_fee_denominator = 100;
+        // deposit token 1 alone
+        let token_amounts = accounts.token_amounts();
+        let source_token_amount = 30_000;
+        let expected = pool
+            .curve
+            .deposit_single_token_type(
+                source_token_amount as u128,
+                1,
+                &token_amounts,
+                mint_supply(&accounts.pool_mint_account) as u128,
+                &fees,
+            )
+            .unwrap() as u64;
+        let mut user_source_account = user_token_accounts[1].clone();
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.deposit_single_token_type_exact_amount_in(
+                &user_key,
+                1,
+                &user_token_keys[1],
+                &mut user_source_account,
+                &user_pool_key,
+                &mut user_pool_account,
+                source_token_amount,
+                expected + 1,
+            )
+        );
+        accounts
+            .deposit_single_token_type_exact_amount_in(
+                &user_key,
+                1,
+                &user_token_keys[1],
+                &mut user_source_account,
+                &user_pool_key,
+                &mut user_pool_account,
+                source_token_amount,
+                expected,
+            )
+            .unwrap();
+        user_token_accounts[1] = user_source_account;
+        assert_eq!(token_account_amount(&user_pool_account), expected);
+        assert_eq!(
+            accounts.token_amounts()[1],
+            token_amounts[1] + source_token_amount as u128
+        );
 
-        let token_a_amount = 1_000_000;
-        let token_b_amount = 0;
-        let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
-        };
+        // withdraw token 2 alone
+        let token_amounts = accounts.token_amounts();
+        let destination_token_amount = 10_000;
+        let burn = pool
+            .curve
+            .withdraw_single_token_type_exact_out(
+                destination_token_amount as u128,
+                2,
+                &token_amounts,
+                mint_supply(&accounts.pool_mint_account) as u128,
+                &fees,
+            )
+            .unwrap();
+        let withdraw_fee = fees.owner_withdraw_fee(burn).unwrap();
+        let maximum_pool_token_amount = (burn + withdraw_fee) as u64;
+        let user_pool_amount = token_account_amount(&user_pool_account);
+        let user_destination_amount = token_account_amount(&user_token_accounts[2]);
+        let mut user_destination_account = user_token_accounts[2].clone();
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.withdraw_single_token_type_exact_amount_out(
+                &user_key,
+                2,
+                &user_pool_key,
+                &mut user_pool_account,
+                &user_token_keys[2],
+                &mut user_destination_account,
+                destination_token_amount,
+                maximum_pool_token_amount - 1,
+            )
+        );
+        accounts
+            .withdraw_single_token_type_exact_amount_out(
+                &user_key,
+                2,
+                &user_pool_key,
+                &mut user_pool_account,
+                &user_token_keys[2],
+                &mut user_destination_account,
+                destination_token_amount,
+                maximum_pool_token_amount,
+            )
+            .unwrap();
+        assert_eq!(
+            token_account_amount(&user_pool_account),
+            user_pool_amount - maximum_pool_token_amount
+        );
+        assert_eq!(
+            token_account_amount(&user_destination_account),
+            user_destination_amount + destination_token_amount
+        );
 
-        let token_b_offset = 2_000_000;
-        let swap_curve = SwapCurve {
-            curve_type: CurveType::Offset,
-            calculator: Arc::new(OffsetCurve { token_b_offset }),
+        // stable pool of three tokens at par
+        let stable_curve = MultiAssetCurve {
+            curve_type: MultiAssetCurveType::Stable,
+            amp: 100,
+            weights: vec![1, 1, 1],
         };
-        let creator_key = Pubkey::new_unique();
-        let depositor_key = Pubkey::new_unique();
-
-        let mut accounts = SwapAccountInfo::new(
-            &creator_key,
+        let mut accounts = MultiAssetPoolAccountInfo::new(
+            &user_key,
             fees,
-            swap_curve,
-            token_a_amount,
-            token_b_amount,
+            stable_curve,
+            &[5_000_000, 5_000_000, 5_000_000],
         );
-
-        accounts.initialize_swap().unwrap();
-
-        let initial_a = 1_000_000;
-        let initial_b = 2_000_000;
-        let (
-            _depositor_token_a_key,
-            _depositor_token_a_account,
-            depositor_token_b_key,
-            mut depositor_token_b_account,
-            depositor_pool_key,
-            mut depositor_pool_account,
-        ) = accounts.setup_token_accounts(&creator_key, &depositor_key, initial_a, initial_b, 0);
-
-        assert_eq!(
-            Err(SwapError::UnsupportedCurveOperation.into()),
-            accounts.deposit_single_token_type_exact_amount_in(
-                &depositor_key,
-                &depositor_token_b_key,
-                &mut depositor_token_b_account,
-                &depositor_pool_key,
-                &mut depositor_pool_account,
-                initial_b,
+        accounts.initialize_pool().unwrap();
+        let (user_token_keys, mut user_token_accounts) =
+            accounts.setup_token_accounts(&user_key, initial_amount);
+        let (mut user_source_account, mut user_destination_account) =
+            (user_token_accounts.remove(1), user_token_accounts.remove(0));
+        accounts
+            .swap(
+                &user_key,
+                1,
+                &user_token_keys[1],
+                &mut user_source_account,
                 0,
+                &user_token_keys[0],
+                &mut user_destination_account,
+                amount_in,
+                99_000,
             )
-        );
+            .unwrap();
+        assert!(token_account_amount(&user_destination_account) < initial_amount + amount_in);
     }
 }
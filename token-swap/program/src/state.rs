@@ -1,14 +1,29 @@
 //! State transition types
 
-use crate::curve::{base::SwapCurve, fees::Fees};
+use crate::{
+    curve::{
+        base::{SwapCurve, SwapResult},
+        calculator::{RoundDirection, TradeDirection, TradingTokenResult},
+        concentrated_liquidity::{
+            compute_swap_step, sqrt_price_at_tick, tick_at_sqrt_price, token_amounts_for_liquidity,
+            total_trade_fee, MAX_SQRT_PRICE, MAX_TICK, MIN_SQRT_PRICE, MIN_TICK,
+        },
+        fees::{DynamicFees, Fees},
+        multi_asset::{MultiAssetCurve, MultiAssetCurveType, MAX_MULTI_ASSET_TOKENS},
+    },
+    error::SwapError,
+    twap::PriceAccumulator,
+};
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use enum_dispatch::enum_dispatch;
 use solana_program::{
     program_error::ProgramError,
+    program_option::COption,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
 };
-use std::sync::Arc;
+use spl_math::uint::U256;
+use std::{convert::TryFrom, sync::Arc};
 
 /// Trait representing access to program state across all versions
 #[enum_dispatch]
@@ -17,7 +32,7 @@ pub trait SwapState {
     fn is_initialized(&self) -> bool;
     /// Bump seed used to generate the program address / authority
     fn bump_seed(&self) -> u8;
-    /// Token program ID associated with the swap
+    /// Token program ID of the pool tokens
     fn token_program_id(&self) -> &Pubkey;
     /// Address of token A liquidity account
     fn token_a_account(&self) -> &Pubkey;
@@ -38,13 +53,43 @@ pub trait SwapState {
     fn fees(&self) -> &Fees;
     /// Curve associated with swap
     fn swap_curve(&self) -> &SwapCurve;
+
+    /// Authority allowed to pause and resume the swap, if any
+    fn pause_authority(&self) -> Option<&Pubkey>;
+    /// Are swaps and deposits currently halted
+    fn is_paused(&self) -> bool;
+
+    /// Oracle price account used by the curve, if any
+    fn oracle(&self) -> Option<&Pubkey>;
+
+    /// Dynamic fee parameters, raising the trade fee with volatility
+    fn dynamic_fees(&self) -> &DynamicFees;
+    /// Volatility accumulated from the price impact of recent swaps
+    fn volatility(&self) -> u64;
+    /// Slot of the last update to the volatility accumulator
+    fn volatility_update_slot(&self) -> u64;
+
+    /// Cumulative prices of the swap, if its layout holds them
+    fn price_accumulator(&self) -> Option<&PriceAccumulator>;
 }
 
+/// Dynamic fees of swaps that cannot hold them, always disabled
+const DISABLED_DYNAMIC_FEES: DynamicFees = DynamicFees {
+    decay_half_life_slots: 0,
+    volatility_fee_numerator: 0,
+    volatility_fee_denominator: 0,
+    max_volatility_fee_numerator: 0,
+};
+
 /// All versions of SwapState
 #[enum_dispatch(SwapState)]
 pub enum SwapVersion {
-    /// Latest version, used for all new swaps
+    /// Original version, cannot be paused
     SwapV1,
+    /// Adds an emergency pause, an oracle and dynamic fees
+    SwapV2,
+    /// Latest version, used for all new swaps
+    SwapV3,
 }
 
 /// SwapVersion does not implement program_pack::Pack because there are size
@@ -52,15 +97,53 @@ pub enum SwapVersion {
 /// special implementations are provided here
 impl SwapVersion {
     /// Size of the latest version of the SwapState
-    pub const LATEST_LEN: usize = 1 + SwapV1::LEN; // add one for the version enum
+    pub const LATEST_LEN: usize = 1 + SwapV3::LEN; // add one for the version enum
 
     /// Pack a swap into a byte array, based on its version
     pub fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
         match src {
             Self::SwapV1(swap_info) => {
-                dst[0] = 1;
-                SwapV1::pack(swap_info, &mut dst[1..])
+                let (version, rest) = dst
+                    .split_first_mut()
+                    .ok_or(ProgramError::InvalidAccountData)?;
+                *version = 1;
+                SwapV1::pack(
+                    swap_info,
+                    rest.get_mut(..SwapV1::LEN)
+                        .ok_or(ProgramError::InvalidAccountData)?,
+                )
+            }
+            Self::SwapV2(swap_info) => {
+                let (version, rest) = dst
+                    .split_first_mut()
+                    .ok_or(ProgramError::InvalidAccountData)?;
+                *version = 2;
+                SwapV2::pack(
+                    swap_info,
+                    rest.get_mut(..SwapV2::LEN)
+                        .ok_or(ProgramError::InvalidAccountData)?,
+                )
             }
+            Self::SwapV3(swap_info) => {
+                let (version, rest) = dst
+                    .split_first_mut()
+                    .ok_or(ProgramError::InvalidAccountData)?;
+                *version = 3;
+                SwapV3::pack(
+                    swap_info,
+                    rest.get_mut(..SwapV3::LEN)
+                        .ok_or(ProgramError::InvalidAccountData)?,
+                )
+            }
+        }
+    }
+
+    /// Pack a swap unpacked with `unpack_latest` back into its account,
+    /// keeping the version the account was initialized with
+    pub fn pack_latest(src: SwapV3, dst: &mut [u8]) -> Result<(), ProgramError> {
+        match dst.first() {
+            Some(2) => Self::pack(Self::SwapV2(src.into()), dst),
+            _ => Self::pack(Self::SwapV3(src), dst),
         }
     }
 
@@ -71,7 +154,41 @@ impl SwapVersion {
             .split_first()
             .ok_or(ProgramError::InvalidAccountData)?;
         match version {
-            1 => Ok(Arc::new(SwapV1::unpack(rest)?)),
+            1 => Ok(Arc::new(SwapV1::unpack(
+                rest.get(..SwapV1::LEN)
+                    .ok_or(ProgramError::InvalidAccountData)?,
+            )?)),
+            2 => Ok(Arc::new(SwapV2::unpack(
+                rest.get(..SwapV2::LEN)
+                    .ok_or(ProgramError::InvalidAccountData)?,
+            )?)),
+            3 => Ok(Arc::new(SwapV3::unpack(
+                rest.get(..SwapV3::LEN)
+                    .ok_or(ProgramError::InvalidAccountData)?,
+            )?)),
+            _ => Err(ProgramError::UninitializedAccount),
+        }
+    }
+
+    /// Unpack the swap account as the latest version, for instructions that
+    /// modify state which only exists since the second version.  Swaps of the
+    /// second version are upgraded in memory, with an empty price accumulator
+    /// which is dropped by `pack_latest`.
+    pub fn unpack_latest(input: &[u8]) -> Result<SwapV3, ProgramError> {
+        let (&version, rest) = input
+            .split_first()
+            .ok_or(ProgramError::InvalidAccountData)?;
+        match version {
+            3 => SwapV3::unpack(
+                rest.get(..SwapV3::LEN)
+                    .ok_or(ProgramError::InvalidAccountData)?,
+            ),
+            2 => SwapV2::unpack(
+                rest.get(..SwapV2::LEN)
+                    .ok_or(ProgramError::InvalidAccountData)?,
+            )
+            .map(SwapV3::from),
+            1 => Err(SwapError::UnsupportedSwapVersion.into()),
             _ => Err(ProgramError::UninitializedAccount),
         }
     }
@@ -99,7 +216,8 @@ pub struct SwapV1 {
     /// token mint.
     pub bump_seed: u8,
 
-    /// Program ID of the tokens being exchanged.
+    /// Program ID of the pool tokens.  Tokens A and B may be owned by either
+    /// the SPL Token or the Token-2022 program.
     pub token_program_id: Pubkey,
 
     /// Token A
@@ -171,6 +289,34 @@ impl SwapState for SwapV1 {
     fn swap_curve(&self) -> &SwapCurve {
         &self.swap_curve
     }
+
+    fn pause_authority(&self) -> Option<&Pubkey> {
+        None
+    }
+
+    fn is_paused(&self) -> bool {
+        false
+    }
+
+    fn oracle(&self) -> Option<&Pubkey> {
+        None
+    }
+
+    fn dynamic_fees(&self) -> &DynamicFees {
+        &DISABLED_DYNAMIC_FEES
+    }
+
+    fn volatility(&self) -> u64 {
+        0
+    }
+
+    fn volatility_update_slot(&self) -> u64 {
+        0
+    }
+
+    fn price_accumulator(&self) -> Option<&PriceAccumulator> {
+        None
+    }
 }
 
 impl Sealed for SwapV1 {}
@@ -248,130 +394,2214 @@ impl Pack for SwapV1 {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::curve::stable::StableCurve;
+/// Program states, adding an emergency pause, an oracle and dynamic fees to
+/// the original layout.
+#[repr(C)]
+#[derive(Debug, Default, PartialEq)]
+pub struct SwapV2 {
+    /// Initialized state.
+    pub is_initialized: bool,
+    /// Bump seed used in program address.
+    /// The program address is created deterministically with the bump seed,
+    /// swap program id, and swap account pubkey.  This program address has
+    /// authority over the swap's token A account, token B account, and pool
+    /// token mint.
+    pub bump_seed: u8,
 
-    use std::convert::TryInto;
+    /// Program ID of the pool tokens.  Tokens A and B may be owned by either
+    /// the SPL Token or the Token-2022 program.
+    pub token_program_id: Pubkey,
 
-    const TEST_FEES: Fees = Fees {
-        trade_fee_numerator: 1,
-        trade_fee_denominator: 4,
-        owner_trade_fee_numerator: 3,
-        owner_trade_fee_denominator: 10,
-        owner_withdraw_fee_numerator: 2,
-        owner_withdraw_fee_denominator: 7,
-        host_fee_numerator: 5,
-        host_fee_denominator: 20,
-    };
+    /// Token A
+    pub token_a: Pubkey,
+    /// Token B
+    pub token_b: Pubkey,
 
-    const TEST_BUMP_SEED: u8 = 255;
-    const TEST_TOKEN_PROGRAM_ID: Pubkey = Pubkey::new_from_array([1u8; 32]);
-    const TEST_TOKEN_A: Pubkey = Pubkey::new_from_array([2u8; 32]);
-    const TEST_TOKEN_B: Pubkey = Pubkey::new_from_array([3u8; 32]);
-    const TEST_POOL_MINT: Pubkey = Pubkey::new_from_array([4u8; 32]);
-    const TEST_TOKEN_A_MINT: Pubkey = Pubkey::new_from_array([5u8; 32]);
-    const TEST_TOKEN_B_MINT: Pubkey = Pubkey::new_from_array([6u8; 32]);
-    const TEST_POOL_FEE_ACCOUNT: Pubkey = Pubkey::new_from_array([7u8; 32]);
+    /// Pool tokens are issued when A or B tokens are deposited.
+    /// Pool tokens can be withdrawn back to the original A or B token.
+    pub pool_mint: Pubkey,
 
-    const TEST_CURVE_TYPE: u8 = 2;
-    const TEST_AMP: u64 = 1;
-    const TEST_CURVE: StableCurve = StableCurve { amp: TEST_AMP };
+    /// Mint information for token A
+    pub token_a_mint: Pubkey,
+    /// Mint information for token B
+    pub token_b_mint: Pubkey,
 
-    #[test]
-    fn swap_version_pack() {
-        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
-        let calculator = Arc::new(TEST_CURVE);
-        let swap_curve = SwapCurve {
-            curve_type,
-            calculator,
-        };
-        let swap_info = SwapVersion::SwapV1(SwapV1 {
-            is_initialized: true,
-            bump_seed: TEST_BUMP_SEED,
-            token_program_id: TEST_TOKEN_PROGRAM_ID,
-            token_a: TEST_TOKEN_A,
-            token_b: TEST_TOKEN_B,
-            pool_mint: TEST_POOL_MINT,
-            token_a_mint: TEST_TOKEN_A_MINT,
-            token_b_mint: TEST_TOKEN_B_MINT,
-            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
-            fees: TEST_FEES,
-            swap_curve: swap_curve.clone(),
-        });
+    /// Pool token account to receive trading and / or withdrawal fees
+    pub pool_fee_account: Pubkey,
 
-        let mut packed = [0u8; SwapVersion::LATEST_LEN];
-        SwapVersion::pack(swap_info, &mut packed).unwrap();
-        let unpacked = SwapVersion::unpack(&packed).unwrap();
+    /// All fee information
+    pub fees: Fees,
 
-        assert!(unpacked.is_initialized());
-        assert_eq!(unpacked.bump_seed(), TEST_BUMP_SEED);
-        assert_eq!(*unpacked.token_program_id(), TEST_TOKEN_PROGRAM_ID);
-        assert_eq!(*unpacked.token_a_account(), TEST_TOKEN_A);
-        assert_eq!(*unpacked.token_b_account(), TEST_TOKEN_B);
-        assert_eq!(*unpacked.pool_mint(), TEST_POOL_MINT);
-        assert_eq!(*unpacked.token_a_mint(), TEST_TOKEN_A_MINT);
-        assert_eq!(*unpacked.token_b_mint(), TEST_TOKEN_B_MINT);
-        assert_eq!(*unpacked.pool_fee_account(), TEST_POOL_FEE_ACCOUNT);
-        assert_eq!(*unpacked.fees(), TEST_FEES);
-        assert_eq!(*unpacked.swap_curve(), swap_curve);
+    /// Swap curve parameters, to be unpacked and used by the SwapCurve, which
+    /// calculates swaps, deposits, and withdrawals
+    pub swap_curve: SwapCurve,
+
+    /// Authority allowed to halt and resume swaps and deposits.  If not
+    /// provided at initialization, the swap can never be paused.
+    pub pause_authority: COption<Pubkey>,
+    /// While paused, only `WithdrawAllTokenTypes` is allowed, so that liquidity
+    /// providers can always exit the pool.
+    pub is_paused: bool,
+
+    /// Oracle price account read by curves priced from an external market,
+    /// such as the proactive market making curve.
+    pub oracle: COption<Pubkey>,
+
+    /// Dynamic fee parameters, tuned by the owner of the pool fee account.
+    /// Disabled at initialization.
+    pub dynamic_fees: DynamicFees,
+    /// Volatility accumulated from the price impact of swaps, in parts per
+    /// million, as of `volatility_update_slot`
+    pub volatility: u64,
+    /// Slot of the last update to the volatility accumulator
+    pub volatility_update_slot: u64,
+}
+
+impl From<SwapV2> for SwapV1 {
+    fn from(swap_v2: SwapV2) -> Self {
+        Self {
+            is_initialized: swap_v2.is_initialized,
+            bump_seed: swap_v2.bump_seed,
+            token_program_id: swap_v2.token_program_id,
+            token_a: swap_v2.token_a,
+            token_b: swap_v2.token_b,
+            pool_mint: swap_v2.pool_mint,
+            token_a_mint: swap_v2.token_a_mint,
+            token_b_mint: swap_v2.token_b_mint,
+            pool_fee_account: swap_v2.pool_fee_account,
+            fees: swap_v2.fees,
+            swap_curve: swap_v2.swap_curve,
+        }
     }
+}
 
-    #[test]
-    fn swap_v1_pack() {
-        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
-        let calculator = Arc::new(TEST_CURVE);
-        let swap_curve = SwapCurve {
-            curve_type,
-            calculator,
-        };
-        let swap_info = SwapV1 {
-            is_initialized: true,
-            bump_seed: TEST_BUMP_SEED,
-            token_program_id: TEST_TOKEN_PROGRAM_ID,
-            token_a: TEST_TOKEN_A,
-            token_b: TEST_TOKEN_B,
-            pool_mint: TEST_POOL_MINT,
-            token_a_mint: TEST_TOKEN_A_MINT,
-            token_b_mint: TEST_TOKEN_B_MINT,
-            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
-            fees: TEST_FEES,
-            swap_curve,
-        };
+impl SwapState for SwapV2 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
 
-        let mut packed = [0u8; SwapV1::LEN];
-        SwapV1::pack_into_slice(&swap_info, &mut packed);
-        let unpacked = SwapV1::unpack(&packed).unwrap();
-        assert_eq!(swap_info, unpacked);
+    fn bump_seed(&self) -> u8 {
+        self.bump_seed
+    }
 
-        let mut packed = vec![1u8, TEST_BUMP_SEED];
-        packed.extend_from_slice(&TEST_TOKEN_PROGRAM_ID.to_bytes());
-        packed.extend_from_slice(&TEST_TOKEN_A.to_bytes());
-        packed.extend_from_slice(&TEST_TOKEN_B.to_bytes());
-        packed.extend_from_slice(&TEST_POOL_MINT.to_bytes());
-        packed.extend_from_slice(&TEST_TOKEN_A_MINT.to_bytes());
-        packed.extend_from_slice(&TEST_TOKEN_B_MINT.to_bytes());
-        packed.extend_from_slice(&TEST_POOL_FEE_ACCOUNT.to_bytes());
-        packed.extend_from_slice(&TEST_FEES.trade_fee_numerator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.trade_fee_denominator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_numerator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_denominator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_numerator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_denominator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.host_fee_numerator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.host_fee_denominator.to_le_bytes());
-        packed.push(TEST_CURVE_TYPE);
-        packed.extend_from_slice(&TEST_AMP.to_le_bytes());
-        packed.extend_from_slice(&[0u8; 24]);
-        let unpacked = SwapV1::unpack(&packed).unwrap();
-        assert_eq!(swap_info, unpacked);
+    fn token_program_id(&self) -> &Pubkey {
+        &self.token_program_id
+    }
 
-        let packed = [0u8; SwapV1::LEN];
-        let swap_info: SwapV1 = Default::default();
-        let unpack_unchecked = SwapV1::unpack_unchecked(&packed).unwrap();
-        assert_eq!(unpack_unchecked, swap_info);
-        let err = SwapV1::unpack(&packed).unwrap_err();
-        assert_eq!(err, ProgramError::UninitializedAccount);
+    fn token_a_account(&self) -> &Pubkey {
+        &self.token_a
+    }
+
+    fn token_b_account(&self) -> &Pubkey {
+        &self.token_b
+    }
+
+    fn pool_mint(&self) -> &Pubkey {
+        &self.pool_mint
+    }
+
+    fn token_a_mint(&self) -> &Pubkey {
+        &self.token_a_mint
+    }
+
+    fn token_b_mint(&self) -> &Pubkey {
+        &self.token_b_mint
+    }
+
+    fn pool_fee_account(&self) -> &Pubkey {
+        &self.pool_fee_account
+    }
+
+    fn fees(&self) -> &Fees {
+        &self.fees
+    }
+
+    fn swap_curve(&self) -> &SwapCurve {
+        &self.swap_curve
+    }
+
+    fn pause_authority(&self) -> Option<&Pubkey> {
+        self.pause_authority.as_ref().into()
+    }
+
+    fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    fn oracle(&self) -> Option<&Pubkey> {
+        self.oracle.as_ref().into()
+    }
+
+    fn dynamic_fees(&self) -> &DynamicFees {
+        &self.dynamic_fees
+    }
+
+    fn volatility(&self) -> u64 {
+        self.volatility
+    }
+
+    fn volatility_update_slot(&self) -> u64 {
+        self.volatility_update_slot
+    }
+
+    fn price_accumulator(&self) -> Option<&PriceAccumulator> {
+        None
+    }
+}
+
+impl Sealed for SwapV2 {}
+impl IsInitialized for SwapV2 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SwapV2 {
+    const LEN: usize = 444;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 444];
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            pause_authority,
+            is_paused,
+            oracle,
+            dynamic_fees,
+            volatility,
+            volatility_update_slot,
+        ) = mut_array_refs![output, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 36, 1, 36, 32, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        bump_seed[0] = self.bump_seed;
+        token_program_id.copy_from_slice(self.token_program_id.as_ref());
+        token_a.copy_from_slice(self.token_a.as_ref());
+        token_b.copy_from_slice(self.token_b.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        token_a_mint.copy_from_slice(self.token_a_mint.as_ref());
+        token_b_mint.copy_from_slice(self.token_b_mint.as_ref());
+        pool_fee_account.copy_from_slice(self.pool_fee_account.as_ref());
+        self.fees.pack_into_slice(&mut fees[..]);
+        self.swap_curve.pack_into_slice(&mut swap_curve[..]);
+        pack_coption_key(&self.pause_authority, pause_authority);
+        is_paused[0] = self.is_paused as u8;
+        pack_coption_key(&self.oracle, oracle);
+        self.dynamic_fees.pack_into_slice(&mut dynamic_fees[..]);
+        *volatility = self.volatility.to_le_bytes();
+        *volatility_update_slot = self.volatility_update_slot.to_le_bytes();
+    }
+
+    /// Unpacks a byte buffer into a [SwapV2](struct.SwapV2.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 444];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            pause_authority,
+            is_paused,
+            oracle,
+            dynamic_fees,
+            volatility,
+            volatility_update_slot,
+        ) = array_refs![input, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 36, 1, 36, 32, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            bump_seed: bump_seed[0],
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            token_a: Pubkey::new_from_array(*token_a),
+            token_b: Pubkey::new_from_array(*token_b),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            token_a_mint: Pubkey::new_from_array(*token_a_mint),
+            token_b_mint: Pubkey::new_from_array(*token_b_mint),
+            pool_fee_account: Pubkey::new_from_array(*pool_fee_account),
+            fees: Fees::unpack_from_slice(fees)?,
+            swap_curve: SwapCurve::unpack_from_slice(swap_curve)?,
+            pause_authority: unpack_coption_key(pause_authority)?,
+            is_paused: match is_paused {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            oracle: unpack_coption_key(oracle)?,
+            dynamic_fees: DynamicFees::unpack_from_slice(dynamic_fees)?,
+            volatility: u64::from_le_bytes(*volatility),
+            volatility_update_slot: u64::from_le_bytes(*volatility_update_slot),
+        })
+    }
+}
+
+/// Program states, adding cumulative prices to the second layout, for
+/// integrators to compute time-weighted average prices.
+#[repr(C)]
+#[derive(Debug, Default, PartialEq)]
+pub struct SwapV3 {
+    /// Initialized state.
+    pub is_initialized: bool,
+    /// Bump seed used in program address.
+    /// The program address is created deterministically with the bump seed,
+    /// swap program id, and swap account pubkey.  This program address has
+    /// authority over the swap's token A account, token B account, and pool
+    /// token mint.
+    pub bump_seed: u8,
+
+    /// Program ID of the pool tokens.  Tokens A and B may be owned by either
+    /// the SPL Token or the Token-2022 program.
+    pub token_program_id: Pubkey,
+
+    /// Token A
+    pub token_a: Pubkey,
+    /// Token B
+    pub token_b: Pubkey,
+
+    /// Pool tokens are issued when A or B tokens are deposited.
+    /// Pool tokens can be withdrawn back to the original A or B token.
+    pub pool_mint: Pubkey,
+
+    /// Mint information for token A
+    pub token_a_mint: Pubkey,
+    /// Mint information for token B
+    pub token_b_mint: Pubkey,
+
+    /// Pool token account to receive trading and / or withdrawal fees
+    pub pool_fee_account: Pubkey,
+
+    /// All fee information
+    pub fees: Fees,
+
+    /// Swap curve parameters, to be unpacked and used by the SwapCurve, which
+    /// calculates swaps, deposits, and withdrawals
+    pub swap_curve: SwapCurve,
+
+    /// Authority allowed to halt and resume swaps and deposits.  If not
+    /// provided at initialization, the swap can never be paused.
+    pub pause_authority: COption<Pubkey>,
+    /// While paused, only `WithdrawAllTokenTypes` is allowed, so that liquidity
+    /// providers can always exit the pool.
+    pub is_paused: bool,
+
+    /// Oracle price account read by curves priced from an external market,
+    /// such as the proactive market making curve.
+    pub oracle: COption<Pubkey>,
+
+    /// Dynamic fee parameters, tuned by the owner of the pool fee account.
+    /// Disabled at initialization.
+    pub dynamic_fees: DynamicFees,
+    /// Volatility accumulated from the price impact of swaps, in parts per
+    /// million, as of `volatility_update_slot`
+    pub volatility: u64,
+    /// Slot of the last update to the volatility accumulator
+    pub volatility_update_slot: u64,
+
+    /// Prices of the swap accumulated over time, updated by every swap,
+    /// deposit and withdrawal
+    pub price_accumulator: PriceAccumulator,
+}
+
+impl From<SwapV3> for SwapV2 {
+    fn from(swap_v3: SwapV3) -> Self {
+        Self {
+            is_initialized: swap_v3.is_initialized,
+            bump_seed: swap_v3.bump_seed,
+            token_program_id: swap_v3.token_program_id,
+            token_a: swap_v3.token_a,
+            token_b: swap_v3.token_b,
+            pool_mint: swap_v3.pool_mint,
+            token_a_mint: swap_v3.token_a_mint,
+            token_b_mint: swap_v3.token_b_mint,
+            pool_fee_account: swap_v3.pool_fee_account,
+            fees: swap_v3.fees,
+            swap_curve: swap_v3.swap_curve,
+            pause_authority: swap_v3.pause_authority,
+            is_paused: swap_v3.is_paused,
+            oracle: swap_v3.oracle,
+            dynamic_fees: swap_v3.dynamic_fees,
+            volatility: swap_v3.volatility,
+            volatility_update_slot: swap_v3.volatility_update_slot,
+        }
+    }
+}
+
+impl From<SwapV2> for SwapV3 {
+    fn from(swap_v2: SwapV2) -> Self {
+        Self {
+            is_initialized: swap_v2.is_initialized,
+            bump_seed: swap_v2.bump_seed,
+            token_program_id: swap_v2.token_program_id,
+            token_a: swap_v2.token_a,
+            token_b: swap_v2.token_b,
+            pool_mint: swap_v2.pool_mint,
+            token_a_mint: swap_v2.token_a_mint,
+            token_b_mint: swap_v2.token_b_mint,
+            pool_fee_account: swap_v2.pool_fee_account,
+            fees: swap_v2.fees,
+            swap_curve: swap_v2.swap_curve,
+            pause_authority: swap_v2.pause_authority,
+            is_paused: swap_v2.is_paused,
+            oracle: swap_v2.oracle,
+            dynamic_fees: swap_v2.dynamic_fees,
+            volatility: swap_v2.volatility,
+            volatility_update_slot: swap_v2.volatility_update_slot,
+            price_accumulator: PriceAccumulator::default(),
+        }
+    }
+}
+
+impl From<SwapV3> for SwapV1 {
+    fn from(swap_v3: SwapV3) -> Self {
+        SwapV2::from(swap_v3).into()
+    }
+}
+
+impl SwapState for SwapV3 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+
+    fn bump_seed(&self) -> u8 {
+        self.bump_seed
+    }
+
+    fn token_program_id(&self) -> &Pubkey {
+        &self.token_program_id
+    }
+
+    fn token_a_account(&self) -> &Pubkey {
+        &self.token_a
+    }
+
+    fn token_b_account(&self) -> &Pubkey {
+        &self.token_b
+    }
+
+    fn pool_mint(&self) -> &Pubkey {
+        &self.pool_mint
+    }
+
+    fn token_a_mint(&self) -> &Pubkey {
+        &self.token_a_mint
+    }
+
+    fn token_b_mint(&self) -> &Pubkey {
+        &self.token_b_mint
+    }
+
+    fn pool_fee_account(&self) -> &Pubkey {
+        &self.pool_fee_account
+    }
+
+    fn fees(&self) -> &Fees {
+        &self.fees
+    }
+
+    fn swap_curve(&self) -> &SwapCurve {
+        &self.swap_curve
+    }
+
+    fn pause_authority(&self) -> Option<&Pubkey> {
+        self.pause_authority.as_ref().into()
+    }
+
+    fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    fn oracle(&self) -> Option<&Pubkey> {
+        self.oracle.as_ref().into()
+    }
+
+    fn dynamic_fees(&self) -> &DynamicFees {
+        &self.dynamic_fees
+    }
+
+    fn volatility(&self) -> u64 {
+        self.volatility
+    }
+
+    fn volatility_update_slot(&self) -> u64 {
+        self.volatility_update_slot
+    }
+
+    fn price_accumulator(&self) -> Option<&PriceAccumulator> {
+        Some(&self.price_accumulator)
+    }
+}
+
+impl Sealed for SwapV3 {}
+impl IsInitialized for SwapV3 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SwapV3 {
+    const LEN: usize = 484;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 484];
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            pause_authority,
+            is_paused,
+            oracle,
+            dynamic_fees,
+            volatility,
+            volatility_update_slot,
+            price_accumulator,
+        ) = mut_array_refs![
+            output, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 36, 1, 36, 32, 8, 8, 40
+        ];
+        is_initialized[0] = self.is_initialized as u8;
+        bump_seed[0] = self.bump_seed;
+        token_program_id.copy_from_slice(self.token_program_id.as_ref());
+        token_a.copy_from_slice(self.token_a.as_ref());
+        token_b.copy_from_slice(self.token_b.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        token_a_mint.copy_from_slice(self.token_a_mint.as_ref());
+        token_b_mint.copy_from_slice(self.token_b_mint.as_ref());
+        pool_fee_account.copy_from_slice(self.pool_fee_account.as_ref());
+        self.fees.pack_into_slice(&mut fees[..]);
+        self.swap_curve.pack_into_slice(&mut swap_curve[..]);
+        pack_coption_key(&self.pause_authority, pause_authority);
+        is_paused[0] = self.is_paused as u8;
+        pack_coption_key(&self.oracle, oracle);
+        self.dynamic_fees.pack_into_slice(&mut dynamic_fees[..]);
+        *volatility = self.volatility.to_le_bytes();
+        *volatility_update_slot = self.volatility_update_slot.to_le_bytes();
+        self.price_accumulator
+            .pack_into_slice(&mut price_accumulator[..]);
+    }
+
+    /// Unpacks a byte buffer into a [SwapV3](struct.SwapV3.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 484];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            bump_seed,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            pause_authority,
+            is_paused,
+            oracle,
+            dynamic_fees,
+            volatility,
+            volatility_update_slot,
+            price_accumulator,
+        ) = array_refs![input, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 36, 1, 36, 32, 8, 8, 40];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            bump_seed: bump_seed[0],
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            token_a: Pubkey::new_from_array(*token_a),
+            token_b: Pubkey::new_from_array(*token_b),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            token_a_mint: Pubkey::new_from_array(*token_a_mint),
+            token_b_mint: Pubkey::new_from_array(*token_b_mint),
+            pool_fee_account: Pubkey::new_from_array(*pool_fee_account),
+            fees: Fees::unpack_from_slice(fees)?,
+            swap_curve: SwapCurve::unpack_from_slice(swap_curve)?,
+            pause_authority: unpack_coption_key(pause_authority)?,
+            is_paused: match is_paused {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            oracle: unpack_coption_key(oracle)?,
+            dynamic_fees: DynamicFees::unpack_from_slice(dynamic_fees)?,
+            volatility: u64::from_le_bytes(*volatility),
+            volatility_update_slot: u64::from_le_bytes(*volatility_update_slot),
+            price_accumulator: PriceAccumulator::unpack_from_slice(price_accumulator)?,
+        })
+    }
+}
+
+/// Tag of concentrated pool accounts, stored in place of the version of
+/// swap accounts, and not used by any swap version
+pub const CONCENTRATED_POOL_TAG: u8 = 0x80;
+/// Tag of concentrated pool position accounts
+pub const POSITION_TAG: u8 = 0x81;
+/// Tag of multi-asset pool accounts
+pub const MULTI_ASSET_POOL_TAG: u8 = 0x82;
+/// Maximum number of initialized ticks in a concentrated pool, bounding the
+/// size of its account
+pub const MAX_INITIALIZED_TICKS: usize = 64;
+/// Minimum liquidity of a position holding any, so that the initialized tick
+/// slots of a concentrated pool cannot be used up by dust positions
+pub const MIN_POSITION_LIQUIDITY: u128 = 1_000_000;
+
+/// Checks if the account holds a concentrated pool, a position or a
+/// multi-asset pool, which must not be initialized as a swap
+pub fn is_tagged_account(input: &[u8]) -> bool {
+    matches!(
+        input.first(),
+        Some(&CONCENTRATED_POOL_TAG) | Some(&POSITION_TAG) | Some(&MULTI_ASSET_POOL_TAG)
+    )
+}
+
+/// Boundary of a price range in a concentrated pool, initialized as long as
+/// a position uses it
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tick {
+    /// Index of the tick, at which the price of token A in token B is
+    /// `1.0001^index`
+    pub index: i32,
+    /// Liquidity added to the pool when the price rises across the tick, and
+    /// removed when it falls across it
+    pub liquidity_net: i128,
+    /// Total liquidity of the positions bounded by the tick
+    pub liquidity_gross: u128,
+    /// Token A fees earned per unit of liquidity on the other side of the
+    /// tick from the current price, in Q64.64
+    pub fee_growth_outside_a: u128,
+    /// Token B fees earned per unit of liquidity on the other side of the
+    /// tick from the current price, in Q64.64
+    pub fee_growth_outside_b: u128,
+}
+
+impl Sealed for Tick {}
+impl IsInitialized for Tick {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Pack for Tick {
+    const LEN: usize = 68;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 68];
+        let (index, liquidity_net, liquidity_gross, fee_growth_outside_a, fee_growth_outside_b) =
+            mut_array_refs![output, 4, 16, 16, 16, 16];
+        *index = self.index.to_le_bytes();
+        *liquidity_net = self.liquidity_net.to_le_bytes();
+        *liquidity_gross = self.liquidity_gross.to_le_bytes();
+        *fee_growth_outside_a = self.fee_growth_outside_a.to_le_bytes();
+        *fee_growth_outside_b = self.fee_growth_outside_b.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 68];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (index, liquidity_net, liquidity_gross, fee_growth_outside_a, fee_growth_outside_b) =
+            array_refs![input, 4, 16, 16, 16, 16];
+        Ok(Self {
+            index: i32::from_le_bytes(*index),
+            liquidity_net: i128::from_le_bytes(*liquidity_net),
+            liquidity_gross: u128::from_le_bytes(*liquidity_gross),
+            fee_growth_outside_a: u128::from_le_bytes(*fee_growth_outside_a),
+            fee_growth_outside_b: u128::from_le_bytes(*fee_growth_outside_b),
+        })
+    }
+}
+
+/// Pool where liquidity is provided in price ranges through positions,
+/// instead of across the whole curve through pool tokens
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConcentratedPool {
+    /// Bump seed used to generate the program address / authority
+    pub bump_seed: u8,
+    /// Address of token A liquidity account
+    pub token_a: Pubkey,
+    /// Address of token B liquidity account
+    pub token_b: Pubkey,
+    /// Address of token A mint
+    pub token_a_mint: Pubkey,
+    /// Address of token B mint
+    pub token_b_mint: Pubkey,
+    /// Owner allowed to collect the owner trade fees
+    pub protocol_fee_owner: Pubkey,
+    /// All fee information, of which only the trade and owner trade fees
+    /// apply, both taken from the source token of swaps
+    pub fees: Fees,
+    /// Positions can only be bounded by multiples of the tick spacing
+    pub tick_spacing: u16,
+    /// Greatest tick at or below the current price
+    pub tick_current: i32,
+    /// Square root of the current price of token A in token B, in Q64.64
+    pub sqrt_price: u128,
+    /// Liquidity of the positions in range of the current price
+    pub liquidity: u128,
+    /// Token A fees earned per unit of liquidity since the pool was created,
+    /// in Q64.64, wrapping around on overflow
+    pub fee_growth_global_a: u128,
+    /// Token B fees earned per unit of liquidity since the pool was created,
+    /// in Q64.64, wrapping around on overflow
+    pub fee_growth_global_b: u128,
+    /// Owner trade fees in token A held by the pool until collected
+    pub protocol_fees_a: u64,
+    /// Owner trade fees in token B held by the pool until collected
+    pub protocol_fees_b: u64,
+    /// Initialized ticks, sorted by index
+    pub ticks: Vec<Tick>,
+}
+
+impl ConcentratedPool {
+    /// Checks that the tick can bound a position in the pool
+    pub fn check_tick(&self, tick: i32) -> Result<(), SwapError> {
+        if !(MIN_TICK..=MAX_TICK).contains(&tick) || tick % i32::from(self.tick_spacing) != 0 {
+            Err(SwapError::InvalidTick)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn tick(&self, index: i32) -> Option<&Tick> {
+        self.ticks
+            .binary_search_by_key(&index, |tick| tick.index)
+            .ok()
+            .map(|position| &self.ticks[position])
+    }
+
+    /// Token A and B fees earned per unit of liquidity inside the tick range
+    /// since its ticks were initialized, in Q64.64, wrapping around on
+    /// overflow like the global fee growth
+    pub fn fee_growth_inside(&self, tick_lower: i32, tick_upper: i32) -> (u128, u128) {
+        let outside = |index: i32| {
+            self.tick(index)
+                .map(|tick| (tick.fee_growth_outside_a, tick.fee_growth_outside_b))
+                .unwrap_or_default()
+        };
+        let global = (self.fee_growth_global_a, self.fee_growth_global_b);
+        let (lower_a, lower_b) = outside(tick_lower);
+        let (below_a, below_b) = if self.tick_current >= tick_lower {
+            (lower_a, lower_b)
+        } else {
+            (
+                global.0.wrapping_sub(lower_a),
+                global.1.wrapping_sub(lower_b),
+            )
+        };
+        let (upper_a, upper_b) = outside(tick_upper);
+        let (above_a, above_b) = if self.tick_current < tick_upper {
+            (upper_a, upper_b)
+        } else {
+            (
+                global.0.wrapping_sub(upper_a),
+                global.1.wrapping_sub(upper_b),
+            )
+        };
+        (
+            global.0.wrapping_sub(below_a).wrapping_sub(above_a),
+            global.1.wrapping_sub(below_b).wrapping_sub(above_b),
+        )
+    }
+
+    fn update_tick(
+        &mut self,
+        index: i32,
+        liquidity_delta: i128,
+        upper: bool,
+    ) -> Result<(), SwapError> {
+        let position = match self.ticks.binary_search_by_key(&index, |tick| tick.index) {
+            Ok(position) => position,
+            Err(position) => {
+                if liquidity_delta < 0 {
+                    return Err(SwapError::InsufficientLiquidity);
+                }
+                if self.ticks.len() >= MAX_INITIALIZED_TICKS {
+                    return Err(SwapError::TooManyTicks);
+                }
+                // all fees so far are assumed to have been earned below the
+                // current price
+                let (fee_growth_outside_a, fee_growth_outside_b) = if index <= self.tick_current {
+                    (self.fee_growth_global_a, self.fee_growth_global_b)
+                } else {
+                    (0, 0)
+                };
+                self.ticks.insert(
+                    position,
+                    Tick {
+                        index,
+                        fee_growth_outside_a,
+                        fee_growth_outside_b,
+                        ..Tick::default()
+                    },
+                );
+                position
+            }
+        };
+        let tick = &mut self.ticks[position];
+        tick.liquidity_gross = if liquidity_delta < 0 {
+            tick.liquidity_gross
+                .checked_sub(liquidity_delta.unsigned_abs())
+                .ok_or(SwapError::InsufficientLiquidity)?
+        } else {
+            tick.liquidity_gross
+                .checked_add(liquidity_delta.unsigned_abs())
+                .ok_or(SwapError::CalculationFailure)?
+        };
+        tick.liquidity_net = if upper {
+            tick.liquidity_net.checked_sub(liquidity_delta)
+        } else {
+            tick.liquidity_net.checked_add(liquidity_delta)
+        }
+        .ok_or(SwapError::CalculationFailure)?;
+        Ok(())
+    }
+
+    /// Adds liquidity to the position, or removes it with a negative delta,
+    /// collecting the fees earned since its last update into the position.
+    /// Returns the token amounts to deposit, rounded up, or to withdraw,
+    /// rounded down.
+    pub fn modify_position(
+        &mut self,
+        position: &mut Position,
+        liquidity_delta: i128,
+    ) -> Result<TradingTokenResult, SwapError> {
+        let liquidity = add_liquidity_delta(position.liquidity, liquidity_delta)?;
+        if liquidity != 0 && liquidity < MIN_POSITION_LIQUIDITY {
+            return Err(SwapError::PositionLiquidityTooLow);
+        }
+        if liquidity_delta != 0 {
+            self.update_tick(position.tick_lower, liquidity_delta, false)?;
+            self.update_tick(position.tick_upper, liquidity_delta, true)?;
+        }
+        let (fee_growth_inside_a, fee_growth_inside_b) =
+            self.fee_growth_inside(position.tick_lower, position.tick_upper);
+        position.update(liquidity_delta, fee_growth_inside_a, fee_growth_inside_b)?;
+        if liquidity_delta < 0 {
+            self.ticks.retain(|tick| tick.liquidity_gross != 0);
+        }
+        if (position.tick_lower..position.tick_upper).contains(&self.tick_current) {
+            self.liquidity = add_liquidity_delta(self.liquidity, liquidity_delta)?;
+        }
+        let round_direction = if liquidity_delta < 0 {
+            RoundDirection::Floor
+        } else {
+            RoundDirection::Ceiling
+        };
+        token_amounts_for_liquidity(
+            self.tick_current,
+            self.sqrt_price,
+            position.tick_lower,
+            position.tick_upper,
+            liquidity_delta.unsigned_abs(),
+            round_direction,
+        )
+        .ok_or(SwapError::CalculationFailure)
+    }
+
+    /// Swaps the source amount, moving the price across initialized ticks
+    /// until the amount is used up or the price reaches its bound.  Fees are
+    /// credited to the liquidity in range at each step, except for the owner
+    /// trade fees, which are set aside for the protocol fee owner.
+    pub fn swap(
+        &mut self,
+        source_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapResult> {
+        let (fee_numerator, fee_denominator, owner_fee_numerator) = total_trade_fee(&self.fees)?;
+        let mut amount_remaining = source_amount;
+        let mut source_amount_swapped = 0u128;
+        let mut destination_amount_swapped = 0u128;
+        let mut trade_fee = 0u128;
+        let mut owner_fee = 0u128;
+        while amount_remaining > 0 {
+            let next_tick = match trade_direction {
+                TradeDirection::AtoB => self
+                    .ticks
+                    .iter()
+                    .rposition(|tick| tick.index <= self.tick_current),
+                TradeDirection::BtoA => self
+                    .ticks
+                    .iter()
+                    .position(|tick| tick.index > self.tick_current),
+            };
+            let sqrt_price_target = match (next_tick, trade_direction) {
+                (Some(position), _) => sqrt_price_at_tick(self.ticks[position].index)?,
+                (None, TradeDirection::AtoB) => MIN_SQRT_PRICE,
+                (None, TradeDirection::BtoA) => MAX_SQRT_PRICE,
+            };
+            if next_tick.is_none() && sqrt_price_target == self.sqrt_price {
+                break;
+            }
+            let step = compute_swap_step(
+                self.sqrt_price,
+                sqrt_price_target,
+                self.liquidity,
+                amount_remaining,
+                fee_numerator,
+                fee_denominator,
+                trade_direction,
+            )?;
+            amount_remaining = amount_remaining
+                .checked_sub(step.amount_in)?
+                .checked_sub(step.fee_amount)?;
+            source_amount_swapped = source_amount_swapped
+                .checked_add(step.amount_in)?
+                .checked_add(step.fee_amount)?;
+            destination_amount_swapped = destination_amount_swapped.checked_add(step.amount_out)?;
+
+            let step_owner_fee = if fee_numerator == 0 {
+                0
+            } else {
+                u128::try_from(
+                    U256::from(step.fee_amount)
+                        .checked_mul(U256::from(owner_fee_numerator))?
+                        .checked_div(U256::from(fee_numerator))?,
+                )
+                .ok()?
+            };
+            let step_trade_fee = step.fee_amount.checked_sub(step_owner_fee)?;
+            owner_fee = owner_fee.checked_add(step_owner_fee)?;
+            trade_fee = trade_fee.checked_add(step_trade_fee)?;
+            if self.liquidity > 0 {
+                let fee_growth = ((U256::from(step_trade_fee) << 64)
+                    .checked_div(U256::from(self.liquidity))?)
+                .low_u128();
+                match trade_direction {
+                    TradeDirection::AtoB => {
+                        self.fee_growth_global_a = self.fee_growth_global_a.wrapping_add(fee_growth)
+                    }
+                    TradeDirection::BtoA => {
+                        self.fee_growth_global_b = self.fee_growth_global_b.wrapping_add(fee_growth)
+                    }
+                }
+            }
+
+            self.sqrt_price = step.sqrt_price_next;
+            if step.sqrt_price_next != sqrt_price_target {
+                self.tick_current = tick_at_sqrt_price(self.sqrt_price)?;
+            } else if let Some(position) = next_tick {
+                let tick = &mut self.ticks[position];
+                tick.fee_growth_outside_a = self
+                    .fee_growth_global_a
+                    .wrapping_sub(tick.fee_growth_outside_a);
+                tick.fee_growth_outside_b = self
+                    .fee_growth_global_b
+                    .wrapping_sub(tick.fee_growth_outside_b);
+                match trade_direction {
+                    TradeDirection::AtoB => {
+                        self.liquidity =
+                            add_liquidity_delta(self.liquidity, tick.liquidity_net.checked_neg()?)
+                                .ok()?;
+                        self.tick_current = tick.index.checked_sub(1)?;
+                    }
+                    TradeDirection::BtoA => {
+                        self.liquidity =
+                            add_liquidity_delta(self.liquidity, tick.liquidity_net).ok()?;
+                        self.tick_current = tick.index;
+                    }
+                }
+            } else {
+                self.tick_current = tick_at_sqrt_price(self.sqrt_price)?;
+                break;
+            }
+        }
+        if source_amount_swapped == 0 || destination_amount_swapped == 0 {
+            return None;
+        }
+        let owner_fee_u64 = u64::try_from(owner_fee).ok()?;
+        match trade_direction {
+            TradeDirection::AtoB => {
+                self.protocol_fees_a = self.protocol_fees_a.checked_add(owner_fee_u64)?
+            }
+            TradeDirection::BtoA => {
+                self.protocol_fees_b = self.protocol_fees_b.checked_add(owner_fee_u64)?
+            }
+        }
+        Some(SwapResult {
+            new_swap_source_amount: swap_source_amount.checked_add(source_amount_swapped)?,
+            new_swap_destination_amount: swap_destination_amount
+                .checked_sub(destination_amount_swapped)?,
+            source_amount_swapped,
+            destination_amount_swapped,
+            trade_fee,
+            owner_fee,
+        })
+    }
+}
+
+fn add_liquidity_delta(liquidity: u128, liquidity_delta: i128) -> Result<u128, SwapError> {
+    if liquidity_delta < 0 {
+        liquidity
+            .checked_sub(liquidity_delta.unsigned_abs())
+            .ok_or(SwapError::InsufficientLiquidity)
+    } else {
+        liquidity
+            .checked_add(liquidity_delta.unsigned_abs())
+            .ok_or(SwapError::CalculationFailure)
+    }
+}
+
+impl Sealed for ConcentratedPool {}
+impl IsInitialized for ConcentratedPool {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Pack for ConcentratedPool {
+    const LEN: usize = 4666;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 4666];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            tag,
+            bump_seed,
+            token_a,
+            token_b,
+            token_a_mint,
+            token_b_mint,
+            protocol_fee_owner,
+            fees,
+            tick_spacing,
+            tick_current,
+            sqrt_price,
+            liquidity,
+            fee_growth_global_a,
+            fee_growth_global_b,
+            protocol_fees_a,
+            protocol_fees_b,
+            tick_count,
+            ticks,
+        ) = mut_array_refs![
+            output,
+            1,
+            1,
+            32,
+            32,
+            32,
+            32,
+            32,
+            64,
+            2,
+            4,
+            16,
+            16,
+            16,
+            16,
+            8,
+            8,
+            2,
+            Tick::LEN * MAX_INITIALIZED_TICKS
+        ];
+        tag[0] = CONCENTRATED_POOL_TAG;
+        bump_seed[0] = self.bump_seed;
+        token_a.copy_from_slice(self.token_a.as_ref());
+        token_b.copy_from_slice(self.token_b.as_ref());
+        token_a_mint.copy_from_slice(self.token_a_mint.as_ref());
+        token_b_mint.copy_from_slice(self.token_b_mint.as_ref());
+        protocol_fee_owner.copy_from_slice(self.protocol_fee_owner.as_ref());
+        self.fees.pack_into_slice(&mut fees[..]);
+        *tick_spacing = self.tick_spacing.to_le_bytes();
+        *tick_current = self.tick_current.to_le_bytes();
+        *sqrt_price = self.sqrt_price.to_le_bytes();
+        *liquidity = self.liquidity.to_le_bytes();
+        *fee_growth_global_a = self.fee_growth_global_a.to_le_bytes();
+        *fee_growth_global_b = self.fee_growth_global_b.to_le_bytes();
+        *protocol_fees_a = self.protocol_fees_a.to_le_bytes();
+        *protocol_fees_b = self.protocol_fees_b.to_le_bytes();
+        *tick_count = (self.ticks.len() as u16).to_le_bytes();
+        ticks.fill(0);
+        for (tick, packed_tick) in self.ticks.iter().zip(ticks.chunks_exact_mut(Tick::LEN)) {
+            tick.pack_into_slice(packed_tick);
+        }
+    }
+
+    /// Unpacks a byte buffer into a [ConcentratedPool](struct.ConcentratedPool.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 4666];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            tag,
+            bump_seed,
+            token_a,
+            token_b,
+            token_a_mint,
+            token_b_mint,
+            protocol_fee_owner,
+            fees,
+            tick_spacing,
+            tick_current,
+            sqrt_price,
+            liquidity,
+            fee_growth_global_a,
+            fee_growth_global_b,
+            protocol_fees_a,
+            protocol_fees_b,
+            tick_count,
+            ticks,
+        ) = array_refs![
+            input,
+            1,
+            1,
+            32,
+            32,
+            32,
+            32,
+            32,
+            64,
+            2,
+            4,
+            16,
+            16,
+            16,
+            16,
+            8,
+            8,
+            2,
+            Tick::LEN * MAX_INITIALIZED_TICKS
+        ];
+        match tag[0] {
+            CONCENTRATED_POOL_TAG => {}
+            0 => return Err(ProgramError::UninitializedAccount),
+            _ => return Err(ProgramError::InvalidAccountData),
+        }
+        let tick_count = usize::from(u16::from_le_bytes(*tick_count));
+        if tick_count > MAX_INITIALIZED_TICKS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            bump_seed: bump_seed[0],
+            token_a: Pubkey::new_from_array(*token_a),
+            token_b: Pubkey::new_from_array(*token_b),
+            token_a_mint: Pubkey::new_from_array(*token_a_mint),
+            token_b_mint: Pubkey::new_from_array(*token_b_mint),
+            protocol_fee_owner: Pubkey::new_from_array(*protocol_fee_owner),
+            fees: Fees::unpack_from_slice(fees)?,
+            tick_spacing: u16::from_le_bytes(*tick_spacing),
+            tick_current: i32::from_le_bytes(*tick_current),
+            sqrt_price: u128::from_le_bytes(*sqrt_price),
+            liquidity: u128::from_le_bytes(*liquidity),
+            fee_growth_global_a: u128::from_le_bytes(*fee_growth_global_a),
+            fee_growth_global_b: u128::from_le_bytes(*fee_growth_global_b),
+            protocol_fees_a: u64::from_le_bytes(*protocol_fees_a),
+            protocol_fees_b: u64::from_le_bytes(*protocol_fees_b),
+            ticks: ticks
+                .chunks_exact(Tick::LEN)
+                .take(tick_count)
+                .map(Tick::unpack_from_slice)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Liquidity provided to a concentrated pool in a tick range, with the fees
+/// it earned
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Position {
+    /// Concentrated pool of the position
+    pub pool: Pubkey,
+    /// Owner allowed to add liquidity to the position and to withdraw it
+    pub owner: Pubkey,
+    /// Lower tick of the price range, inclusive
+    pub tick_lower: i32,
+    /// Upper tick of the price range, exclusive
+    pub tick_upper: i32,
+    /// Liquidity provided in the range
+    pub liquidity: u128,
+    /// Token A fee growth inside the range at the last update, in Q64.64
+    pub fee_growth_inside_last_a: u128,
+    /// Token B fee growth inside the range at the last update, in Q64.64
+    pub fee_growth_inside_last_b: u128,
+    /// Token A fees earned by the position and not yet withdrawn
+    pub fees_owed_a: u64,
+    /// Token B fees earned by the position and not yet withdrawn
+    pub fees_owed_b: u64,
+}
+
+impl Position {
+    /// Credits the fees earned by the liquidity of the position given the fee
+    /// growth inside its range, then applies the liquidity delta
+    pub fn update(
+        &mut self,
+        liquidity_delta: i128,
+        fee_growth_inside_a: u128,
+        fee_growth_inside_b: u128,
+    ) -> Result<(), SwapError> {
+        let fees_earned = |fee_growth_inside: u128, fee_growth_inside_last: u128| {
+            let fees = U256::from(fee_growth_inside.wrapping_sub(fee_growth_inside_last))
+                .checked_mul(U256::from(self.liquidity))?
+                >> 64;
+            u64::try_from(fees).ok()
+        };
+        let fees_earned_a = fees_earned(fee_growth_inside_a, self.fee_growth_inside_last_a)
+            .ok_or(SwapError::FeeCalculationFailure)?;
+        let fees_earned_b = fees_earned(fee_growth_inside_b, self.fee_growth_inside_last_b)
+            .ok_or(SwapError::FeeCalculationFailure)?;
+        self.fees_owed_a = self
+            .fees_owed_a
+            .checked_add(fees_earned_a)
+            .ok_or(SwapError::FeeCalculationFailure)?;
+        self.fees_owed_b = self
+            .fees_owed_b
+            .checked_add(fees_earned_b)
+            .ok_or(SwapError::FeeCalculationFailure)?;
+        self.fee_growth_inside_last_a = fee_growth_inside_a;
+        self.fee_growth_inside_last_b = fee_growth_inside_b;
+        self.liquidity = add_liquidity_delta(self.liquidity, liquidity_delta)?;
+        Ok(())
+    }
+}
+
+impl Sealed for Position {}
+impl IsInitialized for Position {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Pack for Position {
+    const LEN: usize = 137;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 137];
+        let (
+            tag,
+            pool,
+            owner,
+            tick_lower,
+            tick_upper,
+            liquidity,
+            fee_growth_inside_last_a,
+            fee_growth_inside_last_b,
+            fees_owed_a,
+            fees_owed_b,
+        ) = mut_array_refs![output, 1, 32, 32, 4, 4, 16, 16, 16, 8, 8];
+        tag[0] = POSITION_TAG;
+        pool.copy_from_slice(self.pool.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        *tick_lower = self.tick_lower.to_le_bytes();
+        *tick_upper = self.tick_upper.to_le_bytes();
+        *liquidity = self.liquidity.to_le_bytes();
+        *fee_growth_inside_last_a = self.fee_growth_inside_last_a.to_le_bytes();
+        *fee_growth_inside_last_b = self.fee_growth_inside_last_b.to_le_bytes();
+        *fees_owed_a = self.fees_owed_a.to_le_bytes();
+        *fees_owed_b = self.fees_owed_b.to_le_bytes();
+    }
+
+    /// Unpacks a byte buffer into a [Position](struct.Position.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 137];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            tag,
+            pool,
+            owner,
+            tick_lower,
+            tick_upper,
+            liquidity,
+            fee_growth_inside_last_a,
+            fee_growth_inside_last_b,
+            fees_owed_a,
+            fees_owed_b,
+        ) = array_refs![input, 1, 32, 32, 4, 4, 16, 16, 16, 8, 8];
+        match tag[0] {
+            POSITION_TAG => {}
+            0 => return Err(ProgramError::UninitializedAccount),
+            _ => return Err(ProgramError::InvalidAccountData),
+        }
+        Ok(Self {
+            pool: Pubkey::new_from_array(*pool),
+            owner: Pubkey::new_from_array(*owner),
+            tick_lower: i32::from_le_bytes(*tick_lower),
+            tick_upper: i32::from_le_bytes(*tick_upper),
+            liquidity: u128::from_le_bytes(*liquidity),
+            fee_growth_inside_last_a: u128::from_le_bytes(*fee_growth_inside_last_a),
+            fee_growth_inside_last_b: u128::from_le_bytes(*fee_growth_inside_last_b),
+            fees_owed_a: u64::from_le_bytes(*fees_owed_a),
+            fees_owed_b: u64::from_le_bytes(*fees_owed_b),
+        })
+    }
+}
+
+/// Token held by a multi-asset pool
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MultiAssetToken {
+    /// Address of the token liquidity account
+    pub account: Pubkey,
+    /// Address of the token mint
+    pub mint: Pubkey,
+}
+
+/// Pool of up to `MAX_MULTI_ASSET_TOKENS` tokens, swapped between any pair
+/// and priced by a weighted or stable curve
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiAssetPool {
+    /// Bump seed used to generate the program address / authority
+    pub bump_seed: u8,
+    /// Program ID of the pool tokens
+    pub token_program_id: Pubkey,
+    /// Pool tokens are issued when the tokens are deposited.
+    /// Pool tokens can be withdrawn back to the original tokens.
+    pub pool_mint: Pubkey,
+    /// Pool token account to receive trading and / or withdrawal fees
+    pub pool_fee_account: Pubkey,
+    /// All fee information
+    pub fees: Fees,
+    /// Curve pricing the tokens, with their weights
+    pub curve: MultiAssetCurve,
+    /// Tokens of the pool, in the order of the curve weights
+    pub tokens: Vec<MultiAssetToken>,
+}
+
+impl MultiAssetPool {
+    /// Index of the token held in the given liquidity account
+    pub fn token_index(&self, account: &Pubkey) -> Option<usize> {
+        self.tokens
+            .iter()
+            .position(|token| token.account == *account)
+    }
+}
+
+impl Sealed for MultiAssetPool {}
+impl IsInitialized for MultiAssetPool {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+/// Size of each packed token of a multi-asset pool: account, mint and weight
+const MULTI_ASSET_TOKEN_LEN: usize = 72;
+
+impl Pack for MultiAssetPool {
+    const LEN: usize = 748;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 748];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            tag,
+            bump_seed,
+            token_program_id,
+            pool_mint,
+            pool_fee_account,
+            fees,
+            curve_type,
+            amp,
+            token_count,
+            tokens,
+        ) = mut_array_refs![
+            output,
+            1,
+            1,
+            32,
+            32,
+            32,
+            64,
+            1,
+            8,
+            1,
+            MULTI_ASSET_TOKEN_LEN * MAX_MULTI_ASSET_TOKENS
+        ];
+        tag[0] = MULTI_ASSET_POOL_TAG;
+        bump_seed[0] = self.bump_seed;
+        token_program_id.copy_from_slice(self.token_program_id.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        pool_fee_account.copy_from_slice(self.pool_fee_account.as_ref());
+        self.fees.pack_into_slice(&mut fees[..]);
+        curve_type[0] = self.curve.curve_type as u8;
+        *amp = self.curve.amp.to_le_bytes();
+        token_count[0] = self.tokens.len() as u8;
+        tokens.fill(0);
+        for ((token, weight), packed_token) in self
+            .tokens
+            .iter()
+            .zip(self.curve.weights.iter())
+            .zip(tokens.chunks_exact_mut(MULTI_ASSET_TOKEN_LEN))
+        {
+            let packed_token = array_mut_ref![packed_token, 0, MULTI_ASSET_TOKEN_LEN];
+            let (account, mint, packed_weight) = mut_array_refs![packed_token, 32, 32, 8];
+            account.copy_from_slice(token.account.as_ref());
+            mint.copy_from_slice(token.mint.as_ref());
+            *packed_weight = weight.to_le_bytes();
+        }
+    }
+
+    /// Unpacks a byte buffer into a [MultiAssetPool](struct.MultiAssetPool.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 748];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            tag,
+            bump_seed,
+            token_program_id,
+            pool_mint,
+            pool_fee_account,
+            fees,
+            curve_type,
+            amp,
+            token_count,
+            tokens,
+        ) = array_refs![
+            input,
+            1,
+            1,
+            32,
+            32,
+            32,
+            64,
+            1,
+            8,
+            1,
+            MULTI_ASSET_TOKEN_LEN * MAX_MULTI_ASSET_TOKENS
+        ];
+        match tag[0] {
+            MULTI_ASSET_POOL_TAG => {}
+            0 => return Err(ProgramError::UninitializedAccount),
+            _ => return Err(ProgramError::InvalidAccountData),
+        }
+        let token_count = usize::from(token_count[0]);
+        if token_count > MAX_MULTI_ASSET_TOKENS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (tokens, weights) = tokens
+            .chunks_exact(MULTI_ASSET_TOKEN_LEN)
+            .take(token_count)
+            .map(|packed_token| {
+                let packed_token = array_ref![packed_token, 0, MULTI_ASSET_TOKEN_LEN];
+                let (account, mint, weight) = array_refs![packed_token, 32, 32, 8];
+                (
+                    MultiAssetToken {
+                        account: Pubkey::new_from_array(*account),
+                        mint: Pubkey::new_from_array(*mint),
+                    },
+                    u64::from_le_bytes(*weight),
+                )
+            })
+            .unzip();
+        Ok(Self {
+            bump_seed: bump_seed[0],
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            pool_fee_account: Pubkey::new_from_array(*pool_fee_account),
+            fees: Fees::unpack_from_slice(fees)?,
+            curve: MultiAssetCurve {
+                curve_type: MultiAssetCurveType::try_from(curve_type[0])?,
+                amp: u64::from_le_bytes(*amp),
+                weights,
+            },
+            tokens,
+        })
+    }
+}
+
+// Helpers
+fn pack_coption_key(src: &COption<Pubkey>, dst: &mut [u8; 36]) {
+    let (tag, body) = mut_array_refs![dst, 4, 32];
+    match src {
+        COption::Some(key) => {
+            *tag = [1, 0, 0, 0];
+            body.copy_from_slice(key.as_ref());
+        }
+        COption::None => {
+            *tag = [0; 4];
+        }
+    }
+}
+
+fn unpack_coption_key(src: &[u8; 36]) -> Result<COption<Pubkey>, ProgramError> {
+    let (tag, body) = array_refs![src, 4, 32];
+    match *tag {
+        [0, 0, 0, 0] => Ok(COption::None),
+        [1, 0, 0, 0] => Ok(COption::Some(Pubkey::new_from_array(*body))),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::stable::StableCurve;
+
+    use std::convert::TryInto;
+
+    const TEST_FEES: Fees = Fees {
+        trade_fee_numerator: 1,
+        trade_fee_denominator: 4,
+        owner_trade_fee_numerator: 3,
+        owner_trade_fee_denominator: 10,
+        owner_withdraw_fee_numerator: 2,
+        owner_withdraw_fee_denominator: 7,
+        host_fee_numerator: 5,
+        host_fee_denominator: 20,
+    };
+
+    const TEST_BUMP_SEED: u8 = 255;
+    const TEST_TOKEN_PROGRAM_ID: Pubkey = Pubkey::new_from_array([1u8; 32]);
+    const TEST_TOKEN_A: Pubkey = Pubkey::new_from_array([2u8; 32]);
+    const TEST_TOKEN_B: Pubkey = Pubkey::new_from_array([3u8; 32]);
+    const TEST_POOL_MINT: Pubkey = Pubkey::new_from_array([4u8; 32]);
+    const TEST_TOKEN_A_MINT: Pubkey = Pubkey::new_from_array([5u8; 32]);
+    const TEST_TOKEN_B_MINT: Pubkey = Pubkey::new_from_array([6u8; 32]);
+    const TEST_POOL_FEE_ACCOUNT: Pubkey = Pubkey::new_from_array([7u8; 32]);
+    const TEST_PAUSE_AUTHORITY: Pubkey = Pubkey::new_from_array([8u8; 32]);
+    const TEST_ORACLE: Pubkey = Pubkey::new_from_array([9u8; 32]);
+
+    const TEST_DYNAMIC_FEES: DynamicFees = DynamicFees {
+        decay_half_life_slots: 150,
+        volatility_fee_numerator: 1,
+        volatility_fee_denominator: 10,
+        max_volatility_fee_numerator: 2,
+    };
+    const TEST_VOLATILITY: u64 = 12_345;
+    const TEST_VOLATILITY_UPDATE_SLOT: u64 = 678;
+
+    const TEST_PRICE_ACCUMULATOR: PriceAccumulator = PriceAccumulator {
+        price_a_cumulative: 1 << 100,
+        price_b_cumulative: 9_876_543,
+        last_update_timestamp: 1_650_000_000,
+    };
+
+    const TEST_CURVE_TYPE: u8 = 2;
+    const TEST_AMP: u64 = 1;
+    const TEST_CURVE: StableCurve = StableCurve { amp: TEST_AMP };
+
+    #[test]
+    fn swap_version_pack() {
+        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
+        let calculator = Arc::new(TEST_CURVE);
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+        let swap_info = SwapVersion::SwapV1(SwapV1 {
+            is_initialized: true,
+            bump_seed: TEST_BUMP_SEED,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve: swap_curve.clone(),
+        });
+
+        let mut packed = [0u8; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(swap_info, &mut packed).unwrap();
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+
+        assert!(unpacked.is_initialized());
+        assert_eq!(unpacked.bump_seed(), TEST_BUMP_SEED);
+        assert_eq!(*unpacked.token_program_id(), TEST_TOKEN_PROGRAM_ID);
+        assert_eq!(*unpacked.token_a_account(), TEST_TOKEN_A);
+        assert_eq!(*unpacked.token_b_account(), TEST_TOKEN_B);
+        assert_eq!(*unpacked.pool_mint(), TEST_POOL_MINT);
+        assert_eq!(*unpacked.token_a_mint(), TEST_TOKEN_A_MINT);
+        assert_eq!(*unpacked.token_b_mint(), TEST_TOKEN_B_MINT);
+        assert_eq!(*unpacked.pool_fee_account(), TEST_POOL_FEE_ACCOUNT);
+        assert_eq!(*unpacked.fees(), TEST_FEES);
+        assert_eq!(*unpacked.swap_curve(), swap_curve);
+        assert_eq!(unpacked.pause_authority(), None);
+        assert!(!unpacked.is_paused());
+        assert_eq!(unpacked.oracle(), None);
+        assert!(!unpacked.dynamic_fees().is_enabled());
+        assert_eq!(unpacked.volatility(), 0);
+        assert_eq!(unpacked.volatility_update_slot(), 0);
+        assert_eq!(unpacked.price_accumulator(), None);
+        assert_eq!(
+            SwapVersion::unpack_latest(&packed).unwrap_err(),
+            SwapError::UnsupportedSwapVersion.into()
+        );
+    }
+
+    #[test]
+    fn swap_version_pack_v2() {
+        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
+        let calculator = Arc::new(TEST_CURVE);
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+        let swap_info = SwapV2 {
+            is_initialized: true,
+            bump_seed: TEST_BUMP_SEED,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve: swap_curve.clone(),
+            pause_authority: COption::Some(TEST_PAUSE_AUTHORITY),
+            is_paused: true,
+            oracle: COption::Some(TEST_ORACLE),
+            dynamic_fees: TEST_DYNAMIC_FEES,
+            volatility: TEST_VOLATILITY,
+            volatility_update_slot: TEST_VOLATILITY_UPDATE_SLOT,
+        };
+
+        let mut packed = [0u8; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(SwapVersion::SwapV2(swap_info), &mut packed).unwrap();
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+
+        assert!(unpacked.is_initialized());
+        assert_eq!(unpacked.bump_seed(), TEST_BUMP_SEED);
+        assert_eq!(*unpacked.token_program_id(), TEST_TOKEN_PROGRAM_ID);
+        assert_eq!(*unpacked.token_a_account(), TEST_TOKEN_A);
+        assert_eq!(*unpacked.token_b_account(), TEST_TOKEN_B);
+        assert_eq!(*unpacked.pool_mint(), TEST_POOL_MINT);
+        assert_eq!(*unpacked.token_a_mint(), TEST_TOKEN_A_MINT);
+        assert_eq!(*unpacked.token_b_mint(), TEST_TOKEN_B_MINT);
+        assert_eq!(*unpacked.pool_fee_account(), TEST_POOL_FEE_ACCOUNT);
+        assert_eq!(*unpacked.fees(), TEST_FEES);
+        assert_eq!(*unpacked.swap_curve(), swap_curve);
+        assert_eq!(unpacked.pause_authority(), Some(&TEST_PAUSE_AUTHORITY));
+        assert!(unpacked.is_paused());
+        assert_eq!(unpacked.oracle(), Some(&TEST_ORACLE));
+        assert_eq!(*unpacked.dynamic_fees(), TEST_DYNAMIC_FEES);
+        assert_eq!(unpacked.volatility(), TEST_VOLATILITY);
+        assert_eq!(
+            unpacked.volatility_update_slot(),
+            TEST_VOLATILITY_UPDATE_SLOT
+        );
+        assert_eq!(unpacked.price_accumulator(), None);
+
+        let mut latest = SwapVersion::unpack_latest(&packed).unwrap();
+        assert_eq!(latest.pause_authority, COption::Some(TEST_PAUSE_AUTHORITY));
+        assert!(latest.is_paused);
+        assert_eq!(latest.oracle, COption::Some(TEST_ORACLE));
+        assert_eq!(latest.dynamic_fees, TEST_DYNAMIC_FEES);
+        assert_eq!(latest.price_accumulator, PriceAccumulator::default());
+
+        // packed back as the second version, dropping the price accumulator
+        latest.is_paused = false;
+        latest.price_accumulator = TEST_PRICE_ACCUMULATOR;
+        SwapVersion::pack_latest(latest, &mut packed).unwrap();
+        assert_eq!(packed[0], 2);
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert!(!unpacked.is_paused());
+        assert_eq!(unpacked.price_accumulator(), None);
+    }
+
+    #[test]
+    fn swap_version_pack_v3() {
+        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
+        let calculator = Arc::new(TEST_CURVE);
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+        let swap_info = SwapV3 {
+            is_initialized: true,
+            bump_seed: TEST_BUMP_SEED,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve: swap_curve.clone(),
+            pause_authority: COption::Some(TEST_PAUSE_AUTHORITY),
+            is_paused: true,
+            oracle: COption::Some(TEST_ORACLE),
+            dynamic_fees: TEST_DYNAMIC_FEES,
+            volatility: TEST_VOLATILITY,
+            volatility_update_slot: TEST_VOLATILITY_UPDATE_SLOT,
+            price_accumulator: TEST_PRICE_ACCUMULATOR,
+        };
+
+        let mut packed = [0u8; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(SwapVersion::SwapV3(swap_info), &mut packed).unwrap();
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+
+        assert!(unpacked.is_initialized());
+        assert_eq!(unpacked.bump_seed(), TEST_BUMP_SEED);
+        assert_eq!(*unpacked.token_program_id(), TEST_TOKEN_PROGRAM_ID);
+        assert_eq!(*unpacked.token_a_account(), TEST_TOKEN_A);
+        assert_eq!(*unpacked.token_b_account(), TEST_TOKEN_B);
+        assert_eq!(*unpacked.pool_mint(), TEST_POOL_MINT);
+        assert_eq!(*unpacked.token_a_mint(), TEST_TOKEN_A_MINT);
+        assert_eq!(*unpacked.token_b_mint(), TEST_TOKEN_B_MINT);
+        assert_eq!(*unpacked.pool_fee_account(), TEST_POOL_FEE_ACCOUNT);
+        assert_eq!(*unpacked.fees(), TEST_FEES);
+        assert_eq!(*unpacked.swap_curve(), swap_curve);
+        assert_eq!(unpacked.pause_authority(), Some(&TEST_PAUSE_AUTHORITY));
+        assert!(unpacked.is_paused());
+        assert_eq!(unpacked.oracle(), Some(&TEST_ORACLE));
+        assert_eq!(*unpacked.dynamic_fees(), TEST_DYNAMIC_FEES);
+        assert_eq!(unpacked.volatility(), TEST_VOLATILITY);
+        assert_eq!(
+            unpacked.volatility_update_slot(),
+            TEST_VOLATILITY_UPDATE_SLOT
+        );
+        assert_eq!(unpacked.price_accumulator(), Some(&TEST_PRICE_ACCUMULATOR));
+
+        let mut latest = SwapVersion::unpack_latest(&packed).unwrap();
+        assert_eq!(latest.price_accumulator, TEST_PRICE_ACCUMULATOR);
+        latest.price_accumulator = PriceAccumulator::new(1_700_000_000);
+        SwapVersion::pack_latest(latest, &mut packed).unwrap();
+        assert_eq!(packed[0], 3);
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert_eq!(
+            unpacked.price_accumulator(),
+            Some(&PriceAccumulator::new(1_700_000_000))
+        );
+    }
+
+    #[test]
+    fn swap_v1_pack() {
+        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
+        let calculator = Arc::new(TEST_CURVE);
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+        let swap_info = SwapV1 {
+            is_initialized: true,
+            bump_seed: TEST_BUMP_SEED,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve,
+        };
+
+        let mut packed = [0u8; SwapV1::LEN];
+        SwapV1::pack_into_slice(&swap_info, &mut packed);
+        let unpacked = SwapV1::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        let mut packed = vec![1u8, TEST_BUMP_SEED];
+        packed.extend_from_slice(&TEST_TOKEN_PROGRAM_ID.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_A.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_B.to_bytes());
+        packed.extend_from_slice(&TEST_POOL_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_A_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_B_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_POOL_FEE_ACCOUNT.to_bytes());
+        packed.extend_from_slice(&TEST_FEES.trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.host_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.host_fee_denominator.to_le_bytes());
+        packed.push(TEST_CURVE_TYPE);
+        packed.extend_from_slice(&TEST_AMP.to_le_bytes());
+        packed.extend_from_slice(&[0u8; 24]);
+        let unpacked = SwapV1::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        let packed = [0u8; SwapV1::LEN];
+        let swap_info: SwapV1 = Default::default();
+        let unpack_unchecked = SwapV1::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpack_unchecked, swap_info);
+        let err = SwapV1::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn swap_v2_pack() {
+        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
+        let calculator = Arc::new(TEST_CURVE);
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+        let swap_info = SwapV2 {
+            is_initialized: true,
+            bump_seed: TEST_BUMP_SEED,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve,
+            pause_authority: COption::Some(TEST_PAUSE_AUTHORITY),
+            is_paused: true,
+            oracle: COption::Some(TEST_ORACLE),
+            dynamic_fees: TEST_DYNAMIC_FEES,
+            volatility: TEST_VOLATILITY,
+            volatility_update_slot: TEST_VOLATILITY_UPDATE_SLOT,
+        };
+
+        let mut packed = [0u8; SwapV2::LEN];
+        SwapV2::pack_into_slice(&swap_info, &mut packed);
+        let unpacked = SwapV2::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        let mut packed = vec![1u8, TEST_BUMP_SEED];
+        packed.extend_from_slice(&TEST_TOKEN_PROGRAM_ID.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_A.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_B.to_bytes());
+        packed.extend_from_slice(&TEST_POOL_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_A_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_B_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_POOL_FEE_ACCOUNT.to_bytes());
+        packed.extend_from_slice(&TEST_FEES.trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.host_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.host_fee_denominator.to_le_bytes());
+        packed.push(TEST_CURVE_TYPE);
+        packed.extend_from_slice(&TEST_AMP.to_le_bytes());
+        packed.extend_from_slice(&[0u8; 24]);
+        packed.extend_from_slice(&[1u8, 0, 0, 0]);
+        packed.extend_from_slice(&TEST_PAUSE_AUTHORITY.to_bytes());
+        packed.push(1);
+        packed.extend_from_slice(&[1u8, 0, 0, 0]);
+        packed.extend_from_slice(&TEST_ORACLE.to_bytes());
+        packed.extend_from_slice(&TEST_DYNAMIC_FEES.decay_half_life_slots.to_le_bytes());
+        packed.extend_from_slice(&TEST_DYNAMIC_FEES.volatility_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_DYNAMIC_FEES.volatility_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_DYNAMIC_FEES.max_volatility_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_VOLATILITY.to_le_bytes());
+        packed.extend_from_slice(&TEST_VOLATILITY_UPDATE_SLOT.to_le_bytes());
+        let unpacked = SwapV2::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        let packed = [0u8; SwapV2::LEN];
+        let swap_info: SwapV2 = Default::default();
+        let unpack_unchecked = SwapV2::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpack_unchecked, swap_info);
+        let err = SwapV2::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn swap_v3_pack() {
+        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
+        let calculator = Arc::new(TEST_CURVE);
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+        let swap_info = SwapV3 {
+            is_initialized: true,
+            bump_seed: TEST_BUMP_SEED,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve,
+            pause_authority: COption::Some(TEST_PAUSE_AUTHORITY),
+            is_paused: true,
+            oracle: COption::Some(TEST_ORACLE),
+            dynamic_fees: TEST_DYNAMIC_FEES,
+            volatility: TEST_VOLATILITY,
+            volatility_update_slot: TEST_VOLATILITY_UPDATE_SLOT,
+            price_accumulator: TEST_PRICE_ACCUMULATOR,
+        };
+
+        let mut packed = [0u8; SwapV3::LEN];
+        SwapV3::pack_into_slice(&swap_info, &mut packed);
+        let unpacked = SwapV3::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        let mut packed = vec![1u8, TEST_BUMP_SEED];
+        packed.extend_from_slice(&TEST_TOKEN_PROGRAM_ID.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_A.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_B.to_bytes());
+        packed.extend_from_slice(&TEST_POOL_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_A_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_B_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_POOL_FEE_ACCOUNT.to_bytes());
+        packed.extend_from_slice(&TEST_FEES.trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.host_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.host_fee_denominator.to_le_bytes());
+        packed.push(TEST_CURVE_TYPE);
+        packed.extend_from_slice(&TEST_AMP.to_le_bytes());
+        packed.extend_from_slice(&[0u8; 24]);
+        packed.extend_from_slice(&[1u8, 0, 0, 0]);
+        packed.extend_from_slice(&TEST_PAUSE_AUTHORITY.to_bytes());
+        packed.push(1);
+        packed.extend_from_slice(&[1u8, 0, 0, 0]);
+        packed.extend_from_slice(&TEST_ORACLE.to_bytes());
+        packed.extend_from_slice(&TEST_DYNAMIC_FEES.decay_half_life_slots.to_le_bytes());
+        packed.extend_from_slice(&TEST_DYNAMIC_FEES.volatility_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_DYNAMIC_FEES.volatility_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_DYNAMIC_FEES.max_volatility_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_VOLATILITY.to_le_bytes());
+        packed.extend_from_slice(&TEST_VOLATILITY_UPDATE_SLOT.to_le_bytes());
+        packed.extend_from_slice(&TEST_PRICE_ACCUMULATOR.price_a_cumulative.to_le_bytes());
+        packed.extend_from_slice(&TEST_PRICE_ACCUMULATOR.price_b_cumulative.to_le_bytes());
+        packed.extend_from_slice(&TEST_PRICE_ACCUMULATOR.last_update_timestamp.to_le_bytes());
+        let unpacked = SwapV3::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        let packed = [0u8; SwapV3::LEN];
+        let swap_info: SwapV3 = Default::default();
+        let unpack_unchecked = SwapV3::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpack_unchecked, swap_info);
+        let err = SwapV3::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    fn test_concentrated_pool() -> ConcentratedPool {
+        ConcentratedPool {
+            bump_seed: TEST_BUMP_SEED,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            protocol_fee_owner: TEST_POOL_FEE_ACCOUNT,
+            fees: Fees {
+                trade_fee_numerator: 25,
+                trade_fee_denominator: 10_000,
+                owner_trade_fee_numerator: 5,
+                owner_trade_fee_denominator: 10_000,
+                ..Fees::default()
+            },
+            tick_spacing: 10,
+            tick_current: 0,
+            sqrt_price: 1 << 64,
+            ..ConcentratedPool::default()
+        }
+    }
+
+    fn test_position(tick_lower: i32, tick_upper: i32) -> Position {
+        Position {
+            pool: TEST_POOL_MINT,
+            owner: TEST_PAUSE_AUTHORITY,
+            tick_lower,
+            tick_upper,
+            ..Position::default()
+        }
+    }
+
+    #[test]
+    fn concentrated_pool_pack() {
+        let mut pool = test_concentrated_pool();
+        pool.liquidity = 1_000_000;
+        pool.fee_growth_global_a = u128::MAX - 5;
+        pool.fee_growth_global_b = 7;
+        pool.protocol_fees_a = 11;
+        pool.protocol_fees_b = 13;
+        pool.ticks = vec![
+            Tick {
+                index: -20,
+                liquidity_net: 1_000_000,
+                liquidity_gross: 1_000_000,
+                fee_growth_outside_a: 3,
+                fee_growth_outside_b: 4,
+            },
+            Tick {
+                index: 30,
+                liquidity_net: -1_000_000,
+                liquidity_gross: 1_000_000,
+                fee_growth_outside_a: 0,
+                fee_growth_outside_b: 0,
+            },
+        ];
+
+        let mut packed = [0u8; ConcentratedPool::LEN];
+        ConcentratedPool::pack_into_slice(&pool, &mut packed);
+        assert!(is_tagged_account(&packed));
+        assert!(!SwapVersion::is_initialized(&packed));
+        let unpacked = ConcentratedPool::unpack(&packed).unwrap();
+        assert_eq!(pool, unpacked);
+
+        let packed = [0u8; ConcentratedPool::LEN];
+        let err = ConcentratedPool::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn position_pack() {
+        let position = Position {
+            liquidity: 1_000_000,
+            fee_growth_inside_last_a: 1 << 70,
+            fee_growth_inside_last_b: 2,
+            fees_owed_a: 3,
+            fees_owed_b: 4,
+            ..test_position(-60, 120)
+        };
+        let mut packed = [0u8; Position::LEN];
+        Position::pack_into_slice(&position, &mut packed);
+        let unpacked = Position::unpack(&packed).unwrap();
+        assert_eq!(position, unpacked);
+
+        let mut packed = vec![POSITION_TAG];
+        packed.extend_from_slice(&TEST_POOL_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_PAUSE_AUTHORITY.to_bytes());
+        packed.extend_from_slice(&(-60i32).to_le_bytes());
+        packed.extend_from_slice(&120i32.to_le_bytes());
+        packed.extend_from_slice(&1_000_000u128.to_le_bytes());
+        packed.extend_from_slice(&(1u128 << 70).to_le_bytes());
+        packed.extend_from_slice(&2u128.to_le_bytes());
+        packed.extend_from_slice(&3u64.to_le_bytes());
+        packed.extend_from_slice(&4u64.to_le_bytes());
+        let unpacked = Position::unpack(&packed).unwrap();
+        assert_eq!(position, unpacked);
+
+        let err = Position::unpack(&[0u8; Position::LEN]).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn multi_asset_pool_pack() {
+        let token_c = Pubkey::new_from_array([10u8; 32]);
+        let token_c_mint = Pubkey::new_from_array([11u8; 32]);
+        let pool = MultiAssetPool {
+            bump_seed: TEST_BUMP_SEED,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            pool_mint: TEST_POOL_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            curve: MultiAssetCurve {
+                curve_type: MultiAssetCurveType::Weighted,
+                amp: 0,
+                weights: vec![50, 30, 20],
+            },
+            tokens: vec![
+                MultiAssetToken {
+                    account: TEST_TOKEN_A,
+                    mint: TEST_TOKEN_A_MINT,
+                },
+                MultiAssetToken {
+                    account: TEST_TOKEN_B,
+                    mint: TEST_TOKEN_B_MINT,
+                },
+                MultiAssetToken {
+                    account: token_c,
+                    mint: token_c_mint,
+                },
+            ],
+        };
+        assert_eq!(pool.token_index(&token_c), Some(2));
+        assert_eq!(pool.token_index(&TEST_POOL_MINT), None);
+
+        let mut packed = [0u8; MultiAssetPool::LEN];
+        MultiAssetPool::pack_into_slice(&pool, &mut packed);
+        assert!(is_tagged_account(&packed));
+        assert!(!SwapVersion::is_initialized(&packed));
+        let unpacked = MultiAssetPool::unpack(&packed).unwrap();
+        assert_eq!(pool, unpacked);
+
+        let mut packed = vec![MULTI_ASSET_POOL_TAG, TEST_BUMP_SEED];
+        packed.extend_from_slice(&TEST_TOKEN_PROGRAM_ID.to_bytes());
+        packed.extend_from_slice(&TEST_POOL_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_POOL_FEE_ACCOUNT.to_bytes());
+        packed.extend_from_slice(&TEST_FEES.trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.host_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.host_fee_denominator.to_le_bytes());
+        packed.push(0);
+        packed.extend_from_slice(&0u64.to_le_bytes());
+        packed.push(3);
+        for (account, mint, weight) in [
+            (TEST_TOKEN_A, TEST_TOKEN_A_MINT, 50u64),
+            (TEST_TOKEN_B, TEST_TOKEN_B_MINT, 30),
+            (token_c, token_c_mint, 20),
+        ] {
+            packed.extend_from_slice(&account.to_bytes());
+            packed.extend_from_slice(&mint.to_bytes());
+            packed.extend_from_slice(&weight.to_le_bytes());
+        }
+        packed.resize(MultiAssetPool::LEN, 0);
+        let unpacked = MultiAssetPool::unpack(&packed).unwrap();
+        assert_eq!(pool, unpacked);
+
+        packed[171] = MAX_MULTI_ASSET_TOKENS as u8 + 1;
+        let err = MultiAssetPool::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::InvalidAccountData);
+
+        let err = MultiAssetPool::unpack(&[0u8; MultiAssetPool::LEN]).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn concentrated_pool_ticks() {
+        let pool = test_concentrated_pool();
+        assert_eq!(pool.check_tick(-20), Ok(()));
+        assert_eq!(pool.check_tick(25), Err(SwapError::InvalidTick));
+        assert_eq!(pool.check_tick(MAX_TICK + 4), Err(SwapError::InvalidTick));
+
+        let mut pool = test_concentrated_pool();
+        for tick in 0..MAX_INITIALIZED_TICKS as i32 / 2 {
+            let mut position = test_position(-10 * (tick + 1), 10 * (tick + 1));
+            pool.modify_position(&mut position, MIN_POSITION_LIQUIDITY as i128)
+                .unwrap();
+        }
+        assert_eq!(pool.ticks.len(), MAX_INITIALIZED_TICKS);
+        assert_eq!(
+            pool.modify_position(
+                &mut test_position(-1_000, 1_000),
+                MIN_POSITION_LIQUIDITY as i128
+            ),
+            Err(SwapError::TooManyTicks)
+        );
+    }
+
+    #[test]
+    fn concentrated_pool_min_position_liquidity() {
+        let mut pool = test_concentrated_pool();
+        let mut position = test_position(-100, 100);
+        let min_liquidity = MIN_POSITION_LIQUIDITY as i128;
+
+        // dust positions cannot initialize ticks
+        assert_eq!(
+            pool.modify_position(&mut position, min_liquidity - 1),
+            Err(SwapError::PositionLiquidityTooLow)
+        );
+        assert!(pool.ticks.is_empty());
+
+        pool.modify_position(&mut position, min_liquidity).unwrap();
+        pool.modify_position(&mut position, 1).unwrap();
+
+        // nor can they be left behind by a partial withdrawal
+        assert_eq!(
+            pool.modify_position(&mut position.clone(), -min_liquidity),
+            Err(SwapError::PositionLiquidityTooLow)
+        );
+        pool.modify_position(&mut position, -min_liquidity - 1)
+            .unwrap();
+        assert_eq!(position.liquidity, 0);
+        assert!(pool.ticks.is_empty());
+    }
+
+    #[test]
+    fn concentrated_pool_swap_across_ticks() {
+        let mut pool = test_concentrated_pool();
+        let mut in_range = test_position(-100, 100);
+        let mut below = test_position(-200, -100);
+
+        let amounts = pool.modify_position(&mut in_range, 1_000_000_000).unwrap();
+        assert!(amounts.token_a_amount > 0);
+        assert!(amounts.token_b_amount > 0);
+        assert_eq!(pool.liquidity, 1_000_000_000);
+        let amounts = pool.modify_position(&mut below, 2_000_000_000).unwrap();
+        assert_eq!(amounts.token_a_amount, 0);
+        assert!(amounts.token_b_amount > 0);
+        assert_eq!(pool.liquidity, 1_000_000_000);
+        assert_eq!(
+            pool.ticks.iter().map(|tick| tick.index).collect::<Vec<_>>(),
+            vec![-200, -100, 100]
+        );
+
+        // about 5 million of token A moves the price down to tick -100, the
+        // rest is swapped with the liquidity below
+        let result = pool
+            .swap(8_000_000, 20_000_000, 20_000_000, TradeDirection::AtoB)
+            .unwrap();
+        assert_eq!(result.source_amount_swapped, 8_000_000);
+        assert_eq!(result.new_swap_source_amount, 28_000_000);
+        assert!(result.destination_amount_swapped < 8_000_000);
+        assert!(result.destination_amount_swapped > 7_900_000);
+        assert!(pool.tick_current < -100);
+        assert!(pool.tick_current >= -200);
+        assert_eq!(pool.liquidity, 2_000_000_000);
+        // fees are rounded up at each step, with owner fees rounded down to a
+        // sixth of them
+        let fees = result.trade_fee + result.owner_fee;
+        assert!((24_000..=24_002).contains(&fees));
+        assert!((fees / 6 - 2..=fees / 6).contains(&result.owner_fee));
+        assert_eq!(u128::from(pool.protocol_fees_a), result.owner_fee);
+        assert_eq!(pool.protocol_fees_b, 0);
+
+        // both positions earned fees for the liquidity they provided
+        pool.modify_position(&mut in_range, 0).unwrap();
+        pool.modify_position(&mut below, 0).unwrap();
+        assert!(in_range.fees_owed_a > 0);
+        assert!(below.fees_owed_a > 0);
+        assert_eq!(in_range.fees_owed_b, 0);
+        assert_eq!(below.fees_owed_b, 0);
+        let fees_owed = u128::from(in_range.fees_owed_a + below.fees_owed_a);
+        assert!(fees_owed <= result.trade_fee);
+        assert!(fees_owed + 2 >= result.trade_fee);
+
+        // swapping back across the tick restores the in range liquidity
+        let result = pool
+            .swap(
+                result.destination_amount_swapped,
+                20_000_000,
+                28_000_000,
+                TradeDirection::BtoA,
+            )
+            .unwrap();
+        assert!(result.destination_amount_swapped < 8_000_000);
+        assert!(pool.tick_current >= -100);
+        assert_eq!(pool.liquidity, 1_000_000_000);
+        assert!(pool.protocol_fees_b > 0);
+
+        // withdrawing all liquidity of a position releases its ticks
+        assert_eq!(
+            pool.clone()
+                .modify_position(&mut in_range.clone(), -1_000_000_001),
+            Err(SwapError::InsufficientLiquidity)
+        );
+        let amounts = pool.modify_position(&mut in_range, -1_000_000_000).unwrap();
+        assert!(amounts.token_a_amount > 0);
+        assert!(amounts.token_b_amount > 0);
+        assert_eq!(in_range.liquidity, 0);
+        assert!(in_range.fees_owed_b > 0);
+        assert_eq!(pool.liquidity, 0);
+        assert_eq!(
+            pool.ticks.iter().map(|tick| tick.index).collect::<Vec<_>>(),
+            vec![-200, -100]
+        );
     }
 }
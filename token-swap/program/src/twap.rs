@@ -0,0 +1,252 @@
+//! Time-weighted average prices, from the cumulative prices kept in the swap
+//! state
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    clock::UnixTimestamp,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+};
+
+/// Number of fractional bits of the fixed-point prices, so that a price of
+/// one is `1 << PRICE_FRACTIONAL_BITS`
+pub const PRICE_FRACTIONAL_BITS: u32 = 64;
+
+/// Spot price of one token in the other, given by the ratio of the swap
+/// token amounts, as a fixed-point number with `PRICE_FRACTIONAL_BITS`
+/// fractional bits.  Amounts are in base units, so the price is not adjusted
+/// for the decimals of either mint.
+pub fn spot_price(token_amount: u64, other_token_amount: u64) -> Option<u128> {
+    if token_amount == 0 {
+        return None;
+    }
+    (u128::from(other_token_amount) << PRICE_FRACTIONAL_BITS).checked_div(u128::from(token_amount))
+}
+
+/// Running sums of the spot prices of the pool, each weighted by the number
+/// of seconds it held.  Similar to Uniswap V2, the sums wrap around on
+/// overflow, so only the difference between two observations is meaningful.
+/// A snapshot of the accumulator is an observation, and the time-weighted
+/// average price between two observations is given by
+/// `time_weighted_average_price`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PriceAccumulator {
+    /// Sum of the price of token A in token B, times the seconds it held
+    pub price_a_cumulative: u128,
+    /// Sum of the price of token B in token A, times the seconds it held
+    pub price_b_cumulative: u128,
+    /// Unix timestamp of the last accumulation
+    pub last_update_timestamp: UnixTimestamp,
+}
+
+impl PriceAccumulator {
+    /// Create an accumulator with no history, starting at the given time
+    pub fn new(timestamp: UnixTimestamp) -> Self {
+        Self {
+            price_a_cumulative: 0,
+            price_b_cumulative: 0,
+            last_update_timestamp: timestamp,
+        }
+    }
+
+    /// Observe the accumulator at the given time, assuming that the swap
+    /// token amounts have held since the last accumulation.  Integrators
+    /// should pass the current amounts in the swap token accounts to get an
+    /// up-to-date observation.
+    pub fn observe(
+        &self,
+        token_a_amount: u64,
+        token_b_amount: u64,
+        timestamp: UnixTimestamp,
+    ) -> Self {
+        let seconds_elapsed = timestamp.saturating_sub(self.last_update_timestamp);
+        if seconds_elapsed <= 0 {
+            return *self;
+        }
+        let seconds_elapsed = seconds_elapsed as u128;
+        let accumulate = |cumulative: u128, price: Option<u128>| match price {
+            Some(price) => cumulative.wrapping_add(price.wrapping_mul(seconds_elapsed)),
+            None => cumulative,
+        };
+        Self {
+            price_a_cumulative: accumulate(
+                self.price_a_cumulative,
+                spot_price(token_a_amount, token_b_amount),
+            ),
+            price_b_cumulative: accumulate(
+                self.price_b_cumulative,
+                spot_price(token_b_amount, token_a_amount),
+            ),
+            last_update_timestamp: timestamp,
+        }
+    }
+
+    /// Accumulate the prices given by the swap token amounts, which held since
+    /// the last accumulation, up to the given time
+    pub fn accumulate(
+        &mut self,
+        token_a_amount: u64,
+        token_b_amount: u64,
+        timestamp: UnixTimestamp,
+    ) {
+        *self = self.observe(token_a_amount, token_b_amount, timestamp);
+    }
+}
+
+/// Time-weighted average prices between two observations of a pool, as
+/// fixed-point numbers with `PRICE_FRACTIONAL_BITS` fractional bits.  Returns
+/// the average price of token A in token B and of token B in token A, or
+/// `None` if the end is not strictly after the start.
+pub fn time_weighted_average_price(
+    start: &PriceAccumulator,
+    end: &PriceAccumulator,
+) -> Option<(u128, u128)> {
+    let seconds_elapsed = end
+        .last_update_timestamp
+        .checked_sub(start.last_update_timestamp)
+        .filter(|seconds| *seconds > 0)? as u128;
+    Some((
+        end.price_a_cumulative
+            .wrapping_sub(start.price_a_cumulative)
+            / seconds_elapsed,
+        end.price_b_cumulative
+            .wrapping_sub(start.price_b_cumulative)
+            / seconds_elapsed,
+    ))
+}
+
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for PriceAccumulator {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for PriceAccumulator {}
+impl Pack for PriceAccumulator {
+    const LEN: usize = 40;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 40];
+        let (price_a_cumulative, price_b_cumulative, last_update_timestamp) =
+            mut_array_refs![output, 16, 16, 8];
+        *price_a_cumulative = self.price_a_cumulative.to_le_bytes();
+        *price_b_cumulative = self.price_b_cumulative.to_le_bytes();
+        *last_update_timestamp = self.last_update_timestamp.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<PriceAccumulator, ProgramError> {
+        let input = array_ref![input, 0, 40];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (price_a_cumulative, price_b_cumulative, last_update_timestamp) =
+            array_refs![input, 16, 16, 8];
+        Ok(Self {
+            price_a_cumulative: u128::from_le_bytes(*price_a_cumulative),
+            price_b_cumulative: u128::from_le_bytes(*price_b_cumulative),
+            last_update_timestamp: i64::from_le_bytes(*last_update_timestamp),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE: u128 = 1 << PRICE_FRACTIONAL_BITS;
+
+    #[test]
+    fn pack_price_accumulator() {
+        let accumulator = PriceAccumulator {
+            price_a_cumulative: u128::MAX - 1,
+            price_b_cumulative: 12_345,
+            last_update_timestamp: -678,
+        };
+        let mut packed = [0u8; PriceAccumulator::LEN];
+        Pack::pack_into_slice(&accumulator, &mut packed[..]);
+        let unpacked = PriceAccumulator::unpack_from_slice(&packed).unwrap();
+        assert_eq!(accumulator, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&(u128::MAX - 1).to_le_bytes());
+        packed.extend_from_slice(&12_345u128.to_le_bytes());
+        packed.extend_from_slice(&(-678i64).to_le_bytes());
+        let unpacked = PriceAccumulator::unpack_from_slice(&packed).unwrap();
+        assert_eq!(accumulator, unpacked);
+    }
+
+    #[test]
+    fn spot_prices() {
+        assert_eq!(spot_price(1_000, 1_000), Some(ONE));
+        assert_eq!(spot_price(1_000, 4_000), Some(4 * ONE));
+        assert_eq!(spot_price(4_000, 1_000), Some(ONE / 4));
+        assert_eq!(spot_price(0, 1_000), None);
+        assert_eq!(spot_price(1_000, 0), Some(0));
+        assert_eq!(
+            spot_price(1, u64::MAX),
+            Some(u128::from(u64::MAX) << PRICE_FRACTIONAL_BITS)
+        );
+    }
+
+    #[test]
+    fn accumulate_prices() {
+        let mut accumulator = PriceAccumulator::new(100);
+
+        // no time elapsed, nothing accumulated
+        accumulator.accumulate(1_000, 2_000, 100);
+        assert_eq!(accumulator, PriceAccumulator::new(100));
+
+        // time going backwards is ignored
+        accumulator.accumulate(1_000, 2_000, 50);
+        assert_eq!(accumulator, PriceAccumulator::new(100));
+
+        accumulator.accumulate(1_000, 2_000, 110);
+        assert_eq!(accumulator.price_a_cumulative, 20 * ONE);
+        assert_eq!(accumulator.price_b_cumulative, 5 * ONE);
+        assert_eq!(accumulator.last_update_timestamp, 110);
+
+        // an empty side leaves its price unchanged
+        accumulator.accumulate(0, 2_000, 120);
+        assert_eq!(accumulator.price_a_cumulative, 20 * ONE);
+        assert_eq!(accumulator.price_b_cumulative, 5 * ONE);
+        assert_eq!(accumulator.last_update_timestamp, 120);
+    }
+
+    #[test]
+    fn average_prices() {
+        let start = PriceAccumulator::new(0);
+        // price of A is 2 for 10 seconds, then 8 for 30 seconds
+        let middle = start.observe(1_000, 2_000, 10);
+        let end = middle.observe(1_000, 8_000, 40);
+
+        assert_eq!(
+            time_weighted_average_price(&start, &middle),
+            Some((2 * ONE, ONE / 2))
+        );
+        assert_eq!(
+            time_weighted_average_price(&middle, &end),
+            Some((8 * ONE, ONE / 8))
+        );
+        assert_eq!(
+            time_weighted_average_price(&start, &end),
+            Some((
+                (2 * ONE * 10 + 8 * ONE * 30) / 40,
+                (ONE / 2 * 10 + ONE / 8 * 30) / 40
+            ))
+        );
+
+        assert_eq!(time_weighted_average_price(&end, &start), None);
+        assert_eq!(time_weighted_average_price(&end, &end), None);
+    }
+
+    #[test]
+    fn average_prices_wrap_around() {
+        let start = PriceAccumulator {
+            price_a_cumulative: u128::MAX - ONE,
+            price_b_cumulative: u128::MAX,
+            last_update_timestamp: 1_000,
+        };
+        let end = start.observe(3_000, 3_000, 1_010);
+        assert!(end.price_a_cumulative < start.price_a_cumulative);
+        assert!(end.price_b_cumulative < start.price_b_cumulative);
+        assert_eq!(time_weighted_average_price(&start, &end), Some((ONE, ONE)));
+    }
+}
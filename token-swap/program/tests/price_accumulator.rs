@@ -0,0 +1,357 @@
+#![cfg(feature = "test-bpf")]
+
+use {
+    solana_program::{program_pack::Pack, pubkey::Pubkey, system_instruction},
+    solana_program_test::*,
+    solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    },
+    spl_token_swap::{
+        curve::{
+            base::{CurveType, SwapCurve},
+            constant_product::ConstantProductCurve,
+            fees::Fees,
+        },
+        id, instruction,
+        processor::Processor,
+        state::SwapVersion,
+    },
+    std::sync::Arc,
+};
+
+const TOKEN_A_AMOUNT: u64 = 1_000_000;
+const TOKEN_B_AMOUNT: u64 = 2_000_000;
+const USER_AMOUNT: u64 = 100_000;
+
+struct SwapAccounts {
+    swap: Keypair,
+    authority: Pubkey,
+    token_a_mint: Pubkey,
+    token_b_mint: Pubkey,
+    token_a: Pubkey,
+    token_b: Pubkey,
+    pool_mint: Pubkey,
+    pool_fee_account: Pubkey,
+    user_token_a: Pubkey,
+    user_token_b: Pubkey,
+    user_pool: Pubkey,
+}
+
+fn program_test() -> ProgramTest {
+    ProgramTest::new("spl_token_swap", id(), processor!(Processor::process))
+}
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instructions: &[solana_program::instruction::Instruction],
+    signers: &[&Keypair],
+) {
+    let last_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+async fn create_mint(context: &mut ProgramTestContext, authority: &Pubkey) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &context.payer.pubkey(),
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint(
+            &spl_token::id(),
+            &mint.pubkey(),
+            authority,
+            None,
+            0,
+        )
+        .unwrap(),
+    ];
+    process(context, &instructions, &[&mint]).await;
+    mint.pubkey()
+}
+
+async fn create_token_account(
+    context: &mut ProgramTestContext,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let account = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let mut instructions = vec![
+        system_instruction::create_account(
+            &context.payer.pubkey(),
+            &account.pubkey(),
+            rent.minimum_balance(spl_token::state::Account::LEN),
+            spl_token::state::Account::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_account(
+            &spl_token::id(),
+            &account.pubkey(),
+            mint,
+            owner,
+        )
+        .unwrap(),
+    ];
+    if amount > 0 {
+        instructions.push(
+            spl_token::instruction::mint_to(
+                &spl_token::id(),
+                mint,
+                &account.pubkey(),
+                &context.payer.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap(),
+        );
+    }
+    process(context, &instructions, &[&account]).await;
+    account.pubkey()
+}
+
+async fn get_token_balance(context: &mut ProgramTestContext, address: &Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(*address)
+        .await
+        .unwrap()
+        .unwrap();
+    spl_token::state::Account::unpack(&account.data)
+        .unwrap()
+        .amount
+}
+
+async fn setup() -> (ProgramTestContext, SwapAccounts) {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    let swap = Keypair::new();
+    let (authority, _) = Pubkey::find_program_address(&[&swap.pubkey().to_bytes()], &id());
+    let token_a_mint = create_mint(&mut context, &payer).await;
+    let token_b_mint = create_mint(&mut context, &payer).await;
+    let pool_mint = create_mint(&mut context, &authority).await;
+    let token_a =
+        create_token_account(&mut context, &token_a_mint, &authority, TOKEN_A_AMOUNT).await;
+    let token_b =
+        create_token_account(&mut context, &token_b_mint, &authority, TOKEN_B_AMOUNT).await;
+    let pool_fee_account = create_token_account(&mut context, &pool_mint, &payer, 0).await;
+    let user_pool = create_token_account(&mut context, &pool_mint, &payer, 0).await;
+    let user_token_a = create_token_account(&mut context, &token_a_mint, &payer, USER_AMOUNT).await;
+    let user_token_b = create_token_account(&mut context, &token_b_mint, &payer, USER_AMOUNT).await;
+
+    // sized for the latest layout, so that the pool accumulates prices
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &swap.pubkey(),
+            rent.minimum_balance(SwapVersion::LATEST_LEN),
+            SwapVersion::LATEST_LEN as u64,
+            &id(),
+        ),
+        instruction::initialize(
+            &id(),
+            &spl_token::id(),
+            &swap.pubkey(),
+            &authority,
+            &token_a,
+            &token_b,
+            &pool_mint,
+            &pool_fee_account,
+            &user_pool,
+            &token_a_mint,
+            &token_b_mint,
+            None,
+            None,
+            Fees {
+                trade_fee_numerator: 25,
+                trade_fee_denominator: 10_000,
+                owner_trade_fee_numerator: 5,
+                owner_trade_fee_denominator: 10_000,
+                owner_withdraw_fee_numerator: 1,
+                owner_withdraw_fee_denominator: 100,
+                host_fee_numerator: 0,
+                host_fee_denominator: 0,
+            },
+            SwapCurve {
+                curve_type: CurveType::ConstantProduct,
+                calculator: Arc::new(ConstantProductCurve {}),
+            },
+        )
+        .unwrap(),
+    ];
+    process(&mut context, &instructions, &[&swap]).await;
+
+    let swap_account = context
+        .banks_client
+        .get_account(swap.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let token_swap = SwapVersion::unpack(&swap_account.data).unwrap();
+    assert!(token_swap.price_accumulator().is_some());
+
+    (
+        context,
+        SwapAccounts {
+            swap,
+            authority,
+            token_a_mint,
+            token_b_mint,
+            token_a,
+            token_b,
+            pool_mint,
+            pool_fee_account,
+            user_token_a,
+            user_token_b,
+            user_pool,
+        },
+    )
+}
+
+#[tokio::test]
+async fn success_deposit_and_withdraw_all_token_types() {
+    let (mut context, accounts) = setup().await;
+    let payer = context.payer.pubkey();
+    let initial_pool_tokens = get_token_balance(&mut context, &accounts.user_pool).await;
+
+    let pool_token_amount = initial_pool_tokens / 100;
+    let deposit = instruction::deposit_all_token_types(
+        &id(),
+        &spl_token::id(),
+        &spl_token::id(),
+        &spl_token::id(),
+        &accounts.swap.pubkey(),
+        &accounts.authority,
+        &payer,
+        &accounts.user_token_a,
+        &accounts.user_token_b,
+        &accounts.token_a,
+        &accounts.token_b,
+        &accounts.pool_mint,
+        &accounts.user_pool,
+        &accounts.token_a_mint,
+        &accounts.token_b_mint,
+        instruction::DepositAllTokenTypes {
+            pool_token_amount,
+            maximum_token_a_amount: USER_AMOUNT,
+            maximum_token_b_amount: USER_AMOUNT,
+        },
+    )
+    .unwrap();
+    process(&mut context, &[deposit], &[]).await;
+    assert_eq!(
+        get_token_balance(&mut context, &accounts.user_pool).await,
+        initial_pool_tokens + pool_token_amount
+    );
+
+    let withdraw = instruction::withdraw_all_token_types(
+        &id(),
+        &spl_token::id(),
+        &spl_token::id(),
+        &spl_token::id(),
+        &accounts.swap.pubkey(),
+        &accounts.authority,
+        &payer,
+        &accounts.pool_mint,
+        &accounts.pool_fee_account,
+        &accounts.user_pool,
+        &accounts.token_a,
+        &accounts.token_b,
+        &accounts.user_token_a,
+        &accounts.user_token_b,
+        &accounts.token_a_mint,
+        &accounts.token_b_mint,
+        instruction::WithdrawAllTokenTypes {
+            pool_token_amount,
+            minimum_token_a_amount: 0,
+            minimum_token_b_amount: 0,
+        },
+    )
+    .unwrap();
+    process(&mut context, &[withdraw], &[]).await;
+    assert_eq!(
+        get_token_balance(&mut context, &accounts.user_pool).await,
+        initial_pool_tokens
+    );
+}
+
+#[tokio::test]
+async fn success_deposit_and_withdraw_single_token_type() {
+    let (mut context, accounts) = setup().await;
+    let payer = context.payer.pubkey();
+    let initial_pool_tokens = get_token_balance(&mut context, &accounts.user_pool).await;
+
+    let deposit = instruction::deposit_single_token_type_exact_amount_in(
+        &id(),
+        &spl_token::id(),
+        &spl_token::id(),
+        &accounts.swap.pubkey(),
+        &accounts.authority,
+        &payer,
+        &accounts.user_token_a,
+        &accounts.token_a,
+        &accounts.token_b,
+        &accounts.pool_mint,
+        &accounts.user_pool,
+        &accounts.token_a_mint,
+        None,
+        instruction::DepositSingleTokenTypeExactAmountIn {
+            source_token_amount: USER_AMOUNT / 2,
+            minimum_pool_token_amount: 1,
+        },
+    )
+    .unwrap();
+    process(&mut context, &[deposit], &[]).await;
+    assert!(get_token_balance(&mut context, &accounts.user_pool).await > initial_pool_tokens);
+
+    let withdraw = instruction::withdraw_single_token_type_exact_amount_out(
+        &id(),
+        &spl_token::id(),
+        &spl_token::id(),
+        &accounts.swap.pubkey(),
+        &accounts.authority,
+        &payer,
+        &accounts.pool_mint,
+        &accounts.pool_fee_account,
+        &accounts.user_pool,
+        &accounts.token_a,
+        &accounts.token_b,
+        &accounts.user_token_b,
+        &accounts.token_b_mint,
+        None,
+        instruction::WithdrawSingleTokenTypeExactAmountOut {
+            destination_token_amount: USER_AMOUNT / 4,
+            maximum_pool_token_amount: initial_pool_tokens,
+        },
+    )
+    .unwrap();
+    process(&mut context, &[withdraw], &[]).await;
+    assert_eq!(
+        get_token_balance(&mut context, &accounts.user_token_b).await,
+        USER_AMOUNT + USER_AMOUNT / 4
+    );
+}